@@ -0,0 +1,87 @@
+//! Throughput of the T-table block functions, to compare the default build with the
+//! `unsafe-speed` feature, whose table lookups skip the bounds checks:
+//! ```text
+//! cargo bench --bench table_lookups
+//! cargo bench --bench table_lookups --features unsafe-speed
+//! ```
+//! `AES_FRAST_BENCH_MIB` sets the amount of data per run (64 MiB by default). Each function runs 5
+//! times, and the best run is printed.
+use aes_frast::aes_core::{
+    block_decrypt128_inplace, block_decrypt128_x8, block_decrypt256_inplace,
+    block_encrypt128_inplace, block_encrypt128_x8, block_encrypt256_inplace,
+    key_schedule_decrypt128, key_schedule_decrypt256, key_schedule_encrypt128,
+    key_schedule_encrypt256,
+};
+use aes_frast::{BLOCKSIZE_IN_BYTES, N_SUBKEYS_128BIT, N_SUBKEYS_256BIT};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+
+/// Run `process` over `data` in slices of `unit` bytes, and return the best MiB/s of the runs.
+fn measure(data: &mut [u8], unit: usize, process: impl Fn(&mut [u8])) -> f64 {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        for chunk in data.chunks_exact_mut(unit) {
+            process(black_box(chunk));
+        }
+        best = best.min(start.elapsed());
+        black_box(&data);
+    }
+    (data.len() >> 20) as f64 / best.as_secs_f64()
+}
+
+fn main() {
+    let mib: usize = std::env::var("AES_FRAST_BENCH_MIB")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(64);
+    let mut data: Vec<u8> = (0..(mib << 20)).map(|i| i as u8).collect();
+    let key: Vec<u8> = (0..32).collect();
+    let mut e128 = [0u32; N_SUBKEYS_128BIT];
+    let mut d128 = [0u32; N_SUBKEYS_128BIT];
+    let mut e256 = [0u32; N_SUBKEYS_256BIT];
+    let mut d256 = [0u32; N_SUBKEYS_256BIT];
+    key_schedule_encrypt128(&key[..16], &mut e128);
+    key_schedule_decrypt128(&key[..16], &mut d128);
+    key_schedule_encrypt256(&key, &mut e256);
+    key_schedule_decrypt256(&key, &mut d256);
+
+    let feature = if cfg!(feature = "unsafe-speed") {
+        "unsafe-speed"
+    } else {
+        "default"
+    };
+    println!("{feature} build, {mib} MiB, best of {RUNS} runs");
+    let block = BLOCKSIZE_IN_BYTES;
+    let results = [
+        (
+            "encrypt128",
+            measure(&mut data, block, |b| block_encrypt128_inplace(b, &e128)),
+        ),
+        (
+            "decrypt128",
+            measure(&mut data, block, |b| block_decrypt128_inplace(b, &d128)),
+        ),
+        (
+            "encrypt256",
+            measure(&mut data, block, |b| block_encrypt256_inplace(b, &e256)),
+        ),
+        (
+            "decrypt256",
+            measure(&mut data, block, |b| block_decrypt256_inplace(b, &d256)),
+        ),
+        (
+            "encrypt128_x8",
+            measure(&mut data, 8 * block, |b| block_encrypt128_x8(b, &e128)),
+        ),
+        (
+            "decrypt128_x8",
+            measure(&mut data, 8 * block, |b| block_decrypt128_x8(b, &d128)),
+        ),
+    ];
+    for (name, speed) in results {
+        println!("{name:>14}: {speed:8.1} MiB/s");
+    }
+}
@@ -3,6 +3,12 @@
 //! The algorithms here are meant to demonstrate how the computations are done,
 //! not high-performance Galois Field operations. In other words, the Galois
 //! field algorithm here is not optimized.
+/// The condition of the T-Boxes for encryption.
+const ENCRYPTION_T_BOX_CFG: &str =
+    "#[cfg(not(any(feature = \"fixslice\", feature = \"small-tables\")))]\n";
+/// The condition of the T-Boxes for decryption.
+const DECRYPTION_T_BOX_CFG: &str = "#[cfg(not(any(feature = \"fixslice\", \
+    feature = \"small-tables\", feature = \"compact-decrypt\")))]\n";
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     let dest_path = std::path::Path::new(&std::env::var_os("OUT_DIR").unwrap()).join("tables.rs");
@@ -130,14 +136,15 @@ fn calculate_tables() -> String {
     let mut final_out = String::with_capacity(32768);
     final_out.push_str(&sboxtable_code);
     final_out.push_str(&inversetable_code);
-    final_out.push_str(&e_t0_code);
-    final_out.push_str(&e_t1_code);
-    final_out.push_str(&e_t2_code);
-    final_out.push_str(&e_t3_code);
-    final_out.push_str(&d_t0_code);
-    final_out.push_str(&d_t1_code);
-    final_out.push_str(&d_t2_code);
-    final_out.push_str(&d_t3_code);
+    // The T-Boxes are only compiled in when the block functions look them up.
+    for code in [&e_t0_code, &e_t1_code, &e_t2_code, &e_t3_code] {
+        final_out.push_str(ENCRYPTION_T_BOX_CFG);
+        final_out.push_str(code);
+    }
+    for code in [&d_t0_code, &d_t1_code, &d_t2_code, &d_t3_code] {
+        final_out.push_str(DECRYPTION_T_BOX_CFG);
+        final_out.push_str(code);
+    }
     // Compute the addend (round coefficient) needed in the round function.
     let x = GF2_8::new(0b_0000_0010);
     let mut a = GF2_8::new(0b_0000_0001);
@@ -273,6 +280,7 @@ mod gf28 {
             write!(f, "{:08b}", self.inner)
         }
     }
+    #[allow(clippy::suspicious_arithmetic_impl)]
     impl Add for GF2_8 {
         type Output = Self;
         /// Add operator.
@@ -282,6 +290,7 @@ mod gf28 {
             }
         }
     }
+    #[allow(clippy::suspicious_arithmetic_impl)]
     impl Sub for GF2_8 {
         type Output = Self;
         /// Subtact operator. In fact this is the same as its add operator.
@@ -0,0 +1,518 @@
+//! # acvp
+//! `acvp` answers ACVP (Automated Cryptographic Validation Protocol) test vector sets with this
+//! library, for automated validation runs. This mod requires the `acvp` feature.
+//!
+//! The request is the JSON of a vector set (`{"vsId": ..., "algorithm": ..., "testGroups":
+//! [...]}`), optionally preceded by the version object in an array (`[{"acvVersion": ...},
+//! {...}]`). The response has the same shape, with the expected results in each test case.
+//!
+//! algorithm | test types
+//! - | -
+//! `ACVP-AES-ECB` | `AFT`, `MCT`
+//! `ACVP-AES-CBC` | `AFT`, `MCT`
+//! `ACVP-AES-CTR` | `AFT`, `CTR`
+//! `ACVP-AES-KW` | `AFT`, with the `cipher` forward function
+//! `ACVP-AES-KWP` | `AFT`, with the `cipher` forward function
+//! `CMAC-AES` | `AFT`, with the `gen` and `ver` directions
+//!
+//! Other algorithms (such as `ACVP-AES-GCM`) are answered with `Err(AesError::Unsupported)`.
+//! # Examples
+//! ```
+//! use aes_frast::acvp::respond;
+//!
+//! // This example came from NIST SP 800-38A F.2.1
+//! let request = r#"{
+//!     "vsId": 42,
+//!     "algorithm": "ACVP-AES-CBC",
+//!     "revision": "1.0",
+//!     "testGroups": [{
+//!         "tgId": 1,
+//!         "testType": "AFT",
+//!         "direction": "encrypt",
+//!         "keyLen": 128,
+//!         "tests": [{
+//!             "tcId": 1,
+//!             "key": "2B7E151628AED2A6ABF7158809CF4F3C",
+//!             "iv": "000102030405060708090A0B0C0D0E0F",
+//!             "pt": "6BC1BEE22E409F96E93D7E117393172A"
+//!         }]
+//!     }]
+//! }"#;
+//!
+//! let response = respond(request).unwrap();
+//! assert_eq!(
+//!     response,
+//!     r#"{"algorithm":"ACVP-AES-CBC","revision":"1.0","testGroups":[{"tests":[{"ct":"7649ABAC8119B246CEE98E9B12E9197D","tcId":1}],"tgId":1}],"vsId":42}"#
+//! );
+//! ```
+use super::aes_cipher::AesAny;
+use super::aes_cmac::Cmac;
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode as mode;
+use super::error::AesError;
+use super::key_wrap::{self, WrappedKey};
+use super::mct::{self, schedule};
+use super::tag::verify_tag;
+use serde_json::{json, Map, Value};
+
+/// Answer an ACVP vector set given as JSON text.
+///
+/// * *parameter* `request`: the JSON of the vector set.
+/// * *return* : the JSON of the response.
+///
+/// Malformed JSON or missing fields give `Err(AesError::InvalidParameter)`.
+pub fn respond(request: &str) -> Result<String, AesError> {
+    let request: Value = serde_json::from_str(request).map_err(|_| AesError::InvalidParameter)?;
+    Ok(respond_value(&request)?.to_string())
+}
+
+/// Answer an ACVP vector set given as a parsed JSON value, see [`respond`].
+///
+/// [`respond`]: ../acvp/fn.respond.html
+pub fn respond_value(request: &Value) -> Result<Value, AesError> {
+    match request {
+        Value::Array(items) => {
+            let mut response = Vec::with_capacity(items.len());
+            for item in items {
+                if item.get("acvVersion").is_some() {
+                    response.push(item.clone());
+                } else {
+                    response.push(respond_vector_set(item)?);
+                }
+            }
+            Ok(Value::Array(response))
+        }
+        _ => respond_vector_set(request),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Ecb,
+    Cbc,
+    Ctr,
+    Kw,
+    Kwp,
+    Cmac,
+}
+
+fn respond_vector_set(vector_set: &Value) -> Result<Value, AesError> {
+    let algorithm = match string_field(vector_set, "algorithm")? {
+        "ACVP-AES-ECB" => Algorithm::Ecb,
+        "ACVP-AES-CBC" => Algorithm::Cbc,
+        "ACVP-AES-CTR" => Algorithm::Ctr,
+        "ACVP-AES-KW" => Algorithm::Kw,
+        "ACVP-AES-KWP" => Algorithm::Kwp,
+        "CMAC-AES" => Algorithm::Cmac,
+        _ => return Err(AesError::Unsupported),
+    };
+    let mut groups = Vec::new();
+    for group in array_field(vector_set, "testGroups")? {
+        // The MAC generation and verification take the places of encryption and decryption.
+        let encrypt = match (algorithm, string_field(group, "direction")?) {
+            (Algorithm::Cmac, "gen") => true,
+            (Algorithm::Cmac, "ver") => false,
+            (Algorithm::Cmac, _) => return Err(AesError::InvalidParameter),
+            (_, "encrypt") => true,
+            (_, "decrypt") => false,
+            _ => return Err(AesError::InvalidParameter),
+        };
+        let monte_carlo = match (algorithm, string_field(group, "testType")?) {
+            (_, "AFT") | (Algorithm::Ctr, "CTR") => false,
+            (Algorithm::Ecb, "MCT") | (Algorithm::Cbc, "MCT") => true,
+            _ => return Err(AesError::Unsupported),
+        };
+        // The inverse forward function (wrapping with the block-decryption) is not provided.
+        let key_wrap = matches!(algorithm, Algorithm::Kw | Algorithm::Kwp);
+        if key_wrap && string_field(group, "kwCipher")? != "cipher" {
+            return Err(AesError::Unsupported);
+        }
+        let mut tests = Vec::new();
+        for test in array_field(group, "tests")? {
+            let mut result = Map::new();
+            result.insert("tcId".to_string(), field(test, "tcId")?.clone());
+            if monte_carlo {
+                let results = monte_carlo_test(algorithm, encrypt, test)?;
+                result.insert("resultsArray".to_string(), Value::Array(results));
+            } else if key_wrap {
+                let (name, value) = key_wrap_test(algorithm, encrypt, test)?;
+                result.insert(name.to_string(), value);
+            } else if algorithm == Algorithm::Cmac {
+                let (name, value) = cmac_test(encrypt, group, test)?;
+                result.insert(name.to_string(), value);
+            } else {
+                let (name, output) = functional_test(algorithm, encrypt, test)?;
+                result.insert(name.to_string(), Value::String(to_hex(&output)));
+            }
+            tests.push(Value::Object(result));
+        }
+        groups.push(json!({ "tgId": field(group, "tgId")?, "tests": tests }));
+    }
+    let mut response = Map::new();
+    response.insert("vsId".to_string(), field(vector_set, "vsId")?.clone());
+    response.insert(
+        "algorithm".to_string(),
+        field(vector_set, "algorithm")?.clone(),
+    );
+    if let Some(revision) = vector_set.get("revision") {
+        response.insert("revision".to_string(), revision.clone());
+    }
+    response.insert("testGroups".to_string(), Value::Array(groups));
+    Ok(Value::Object(response))
+}
+
+/// Run an algorithm functional test, returning the name and the value of the result.
+fn functional_test(
+    algorithm: Algorithm,
+    encrypt: bool,
+    test: &Value,
+) -> Result<(&'static str, Vec<u8>), AesError> {
+    let key = hex_field(test, "key")?;
+    let (name, input) = if encrypt {
+        ("ct", hex_field(test, "pt")?)
+    } else {
+        ("pt", hex_field(test, "ct")?)
+    };
+    let iv = match algorithm {
+        Algorithm::Ecb => vec![0u8; BLOCKSIZE_IN_BYTES],
+        _ => hex_field(test, "iv")?,
+    };
+    if iv.len() != BLOCKSIZE_IN_BYTES {
+        return Err(AesError::InvalidParameter);
+    }
+    if algorithm != Algorithm::Ctr && input.len() & 0b1111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    let mut output = vec![0u8; input.len()];
+    if input.is_empty() {
+        return Ok((name, output));
+    }
+    // CTR only uses block-encryption.
+    let keys = schedule(&key, encrypt || algorithm == Algorithm::Ctr)?;
+    match (algorithm, encrypt) {
+        (Algorithm::Ecb, true) => mode::ecb_enc(&input, &mut output, &keys),
+        (Algorithm::Ecb, false) => mode::ecb_dec(&input, &mut output, &keys),
+        (Algorithm::Cbc, true) => mode::cbc_enc(&input, &mut output, &keys, &iv),
+        (Algorithm::Cbc, false) => mode::cbc_dec(&input, &mut output, &keys, &iv),
+        (Algorithm::Ctr, _) => mode::ctr_enc_dec(&input, &mut output, &keys, &iv),
+        (Algorithm::Kw, _) | (Algorithm::Kwp, _) | (Algorithm::Cmac, _) => unreachable!(),
+    };
+    Ok((name, output))
+}
+
+/// Run a functional test of KW or KWP, returning the name and the value of the result.
+///
+/// An unwrapping which fails the integrity check gives `"testPassed": false`.
+fn key_wrap_test(
+    algorithm: Algorithm,
+    encrypt: bool,
+    test: &Value,
+) -> Result<(&'static str, Value), AesError> {
+    let kek = AesAny::new(&hex_field(test, "key")?)?;
+    let padding = algorithm == Algorithm::Kwp;
+    if encrypt {
+        let plain = hex_field(test, "pt")?;
+        let wrapped = if padding {
+            key_wrap::wrap_with_padding(&kek, &plain)?
+        } else {
+            key_wrap::wrap(&kek, &plain)?
+        };
+        return Ok(("ct", Value::String(to_hex(wrapped.as_bytes()))));
+    }
+    let wrapped = WrappedKey::new(&hex_field(test, "ct")?)?;
+    let unwrapped = if padding {
+        key_wrap::unwrap_with_padding(&kek, &wrapped)
+    } else {
+        key_wrap::unwrap(&kek, &wrapped)
+    };
+    match unwrapped {
+        Ok(plain) => Ok(("pt", Value::String(to_hex(plain.as_bytes())))),
+        Err(AesError::TagMismatch) => Ok(("testPassed", Value::Bool(false))),
+        Err(error) => Err(error),
+    }
+}
+
+/// Run a functional test of CMAC, returning the name and the value of the result: the MAC of
+/// `macLen` bits of the group, or whether the given MAC verifies.
+fn cmac_test(
+    generate: bool,
+    group: &Value,
+    test: &Value,
+) -> Result<(&'static str, Value), AesError> {
+    let mac_bits = field(group, "macLen")?
+        .as_u64()
+        .ok_or(AesError::InvalidParameter)?;
+    if mac_bits == 0 || mac_bits > 128 || mac_bits & 0b111 != 0 {
+        return Err(AesError::InvalidParameter);
+    }
+    let cmac = Cmac::new(AesAny::new(&hex_field(test, "key")?)?);
+    let mac = cmac.mac(&hex_field(test, "message")?);
+    let mac = &mac.as_bytes()[..(mac_bits as usize / 8)];
+    if generate {
+        Ok(("mac", Value::String(to_hex(mac))))
+    } else {
+        let passed = verify_tag(&hex_field(test, "mac")?, mac).is_ok();
+        Ok(("testPassed", Value::Bool(passed)))
+    }
+}
+
+/// Run a Monte Carlo test of ECB or CBC, as described in the AESAVS.
+fn monte_carlo_test(
+    algorithm: Algorithm,
+    encrypt: bool,
+    test: &Value,
+) -> Result<Vec<Value>, AesError> {
+    let (input_name, output_name) = if encrypt { ("pt", "ct") } else { ("ct", "pt") };
+    let key = hex_field(test, "key")?;
+    let input = block_field(test, input_name)?;
+    let cbc = algorithm == Algorithm::Cbc;
+    let iv = if cbc {
+        block_field(test, "iv")?
+    } else {
+        [0u8; BLOCKSIZE_IN_BYTES]
+    };
+    let rounds = mct::monte_carlo(cbc, encrypt, &key, iv, input)?;
+    let mut results = Vec::with_capacity(rounds.len());
+    for round in rounds {
+        let mut result = Map::new();
+        result.insert("key".to_string(), Value::String(to_hex(&round.key)));
+        if cbc {
+            result.insert("iv".to_string(), Value::String(to_hex(&round.iv)));
+        }
+        result.insert(input_name.to_string(), Value::String(to_hex(&round.input)));
+        result.insert(
+            output_name.to_string(),
+            Value::String(to_hex(&round.output)),
+        );
+        results.push(Value::Object(result));
+    }
+    Ok(results)
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, AesError> {
+    value.get(name).ok_or(AesError::InvalidParameter)
+}
+
+fn string_field<'a>(value: &'a Value, name: &str) -> Result<&'a str, AesError> {
+    field(value, name)?
+        .as_str()
+        .ok_or(AesError::InvalidParameter)
+}
+
+fn array_field<'a>(value: &'a Value, name: &str) -> Result<&'a Vec<Value>, AesError> {
+    field(value, name)?
+        .as_array()
+        .ok_or(AesError::InvalidParameter)
+}
+
+fn hex_field(value: &Value, name: &str) -> Result<Vec<u8>, AesError> {
+    let s = string_field(value, name)?;
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return Err(AesError::InvalidParameter);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16).map_err(|_| AesError::InvalidParameter))
+        .collect()
+}
+
+fn block_field(value: &Value, name: &str) -> Result<[u8; BLOCKSIZE_IN_BYTES], AesError> {
+    hex_field(value, name)?
+        .try_into()
+        .map_err(|_| AesError::InvalidParameter)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(algorithm: &str, group: Value) -> Value {
+        let request = json!({
+            "vsId": 7,
+            "algorithm": algorithm,
+            "revision": "1.0",
+            "testGroups": [group],
+        });
+        let response = respond_value(&json!([{ "acvVersion": "1.0" }, request])).unwrap();
+        assert_eq!(response[0], json!({ "acvVersion": "1.0" }));
+        assert_eq!(response[1]["vsId"], 7);
+        response[1]["testGroups"][0]["tests"][0].clone()
+    }
+
+    #[test]
+    fn functional_tests_work() {
+        // These examples came from NIST SP 800-38A F.1.4 and F.5.5
+        let test = run(
+            "ACVP-AES-ECB",
+            json!({ "tgId": 1, "testType": "AFT", "direction": "decrypt", "tests": [{
+                "tcId": 5,
+                "key": "8E73B0F7DA0E6452C810F32B809079E562F8EAD2522C6B7B",
+                "ct": "BD334F1D6E45F25FF712A214571FA5CC",
+            }]}),
+        );
+        assert_eq!(
+            test,
+            json!({ "tcId": 5, "pt": "6BC1BEE22E409F96E93D7E117393172A" })
+        );
+        let test = run(
+            "ACVP-AES-CTR",
+            json!({ "tgId": 1, "testType": "AFT", "direction": "encrypt", "tests": [{
+                "tcId": 6,
+                "key": "603DEB1015CA71BE2B73AEF0857D77811F352C073B6108D72D9810A30914DFF4",
+                "iv": "F0F1F2F3F4F5F6F7F8F9FAFBFCFDFEFF",
+                "pt": "6BC1BEE22E409F96E93D7E117393172AAE2D",
+            }]}),
+        );
+        assert_eq!(
+            test,
+            json!({ "tcId": 6, "ct": "601EC313775789A5B7A7F504BBF3D228F443" })
+        );
+    }
+
+    #[test]
+    fn key_wrap_tests_work() {
+        // These examples came from RFC 3394 4.1 and RFC 5649 section 6.
+        let group = |direction: &str, test: Value| {
+            json!({ "tgId": 1, "testType": "AFT", "direction": direction, "kwCipher": "cipher",
+                    "tests": [test] })
+        };
+        let kek = "000102030405060708090A0B0C0D0E0F";
+        let wrapped = "1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5";
+        let test = run(
+            "ACVP-AES-KW",
+            group(
+                "encrypt",
+                json!({ "tcId": 1, "key": kek, "pt": "00112233445566778899AABBCCDDEEFF" }),
+            ),
+        );
+        assert_eq!(test, json!({ "tcId": 1, "ct": wrapped }));
+        let test = run(
+            "ACVP-AES-KW",
+            group("decrypt", json!({ "tcId": 2, "key": kek, "ct": wrapped })),
+        );
+        assert_eq!(
+            test,
+            json!({ "tcId": 2, "pt": "00112233445566778899AABBCCDDEEFF" })
+        );
+        let test = run(
+            "ACVP-AES-KW",
+            group(
+                "decrypt",
+                json!({ "tcId": 3, "key": kek,
+                        "ct": "1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE6" }),
+            ),
+        );
+        assert_eq!(test, json!({ "tcId": 3, "testPassed": false }));
+        let test = run(
+            "ACVP-AES-KWP",
+            group(
+                "decrypt",
+                json!({ "tcId": 4, "key": "5840DF6E29B02AF1AB493B705BF16EA1AE8338F4DCC176A8",
+                        "ct": "AFBEB0F07DFBF5419200F2CCB50BB24F" }),
+            ),
+        );
+        assert_eq!(test, json!({ "tcId": 4, "pt": "466F7250617369" }));
+
+        let inverse = json!({ "vsId": 1, "algorithm": "ACVP-AES-KW", "testGroups": [{
+            "tgId": 1, "testType": "AFT", "direction": "encrypt", "kwCipher": "inverse",
+            "tests": []
+        }]});
+        assert_eq!(respond_value(&inverse), Err(AesError::Unsupported));
+    }
+
+    #[test]
+    fn cmac_tests_work() {
+        // These examples came from RFC 4493, section 4 (examples 2 and 1).
+        let key = "2B7E151628AED2A6ABF7158809CF4F3C";
+        let message = "6BC1BEE22E409F96E93D7E117393172A";
+        let test = run(
+            "CMAC-AES",
+            json!({ "tgId": 1, "testType": "AFT", "direction": "gen", "keyLen": 128,
+                    "msgLen": 128, "macLen": 128,
+                    "tests": [{ "tcId": 1, "key": key, "message": message }] }),
+        );
+        assert_eq!(
+            test,
+            json!({ "tcId": 1, "mac": "070A16B46B4D4144F79BDD9DD04A287C" })
+        );
+        for (mac, passed) in [("BB1D6929E9593728", true), ("BB1D6929E9593729", false)] {
+            let test = run(
+                "CMAC-AES",
+                json!({ "tgId": 2, "testType": "AFT", "direction": "ver", "keyLen": 128,
+                        "msgLen": 0, "macLen": 64,
+                        "tests": [{ "tcId": 2, "key": key, "message": "", "mac": mac }] }),
+            );
+            assert_eq!(test, json!({ "tcId": 2, "testPassed": passed }));
+        }
+    }
+
+    #[test]
+    fn monte_carlo_tests_work() {
+        // The expected values came from an independent implementation of the AESAVS pseudocode.
+        let cases = [
+            (
+                "ACVP-AES-ECB",
+                "encrypt",
+                json!({ "tcId": 1, "key": "000102030405060708090A0B0C0D0E0F1011121314151617",
+                        "pt": "101112131415161718191A1B1C1D1E1F" }),
+                json!({ "key": "89983ECD5F020FE27C47AC11ADAC2A0115F351CCC303DF22",
+                        "pt": "1948D8B41A2CF5B900C74AA01AF722E1",
+                        "ct": "4AE80C1DFCCE2E31989EA7317B7D9AE9" }),
+            ),
+            (
+                "ACVP-AES-ECB",
+                "decrypt",
+                json!({ "tcId": 1, "key": "000102030405060708090A0B0C0D0E0F",
+                        "ct": "101112131415161718191A1B1C1D1E1F" }),
+                json!({ "key": "B7A1A3D68473814D003869A236C8BFDB",
+                        "ct": "BAF03F00D4FE520317C9FFDF5292ED96",
+                        "pt": "8E518236BA2844FD9F89EB4BB6C246AB" }),
+            ),
+            (
+                "ACVP-AES-CBC",
+                "encrypt",
+                json!({ "tcId": 1, "key": "000102030405060708090A0B0C0D0E0F",
+                        "iv": "00000000000000000000000000000000",
+                        "pt": "101112131415161718191A1B1C1D1E1F" }),
+                json!({ "key": "1875625D20815ABFE06D4106C5C7CF1E",
+                        "iv": "4B5DBBEA9218C5F563B0F78BBD623D4E",
+                        "pt": "FFB6C0D657B35C5346166AB453E6C228",
+                        "ct": "11897AB1C09813331F96E8157C7B9AD5" }),
+            ),
+            (
+                "ACVP-AES-CBC",
+                "decrypt",
+                json!({ "tcId": 1,
+                        "key": "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F",
+                        "iv": "6465666768696A6B6C6D6E6F70717273",
+                        "ct": "101112131415161718191A1B1C1D1E1F" }),
+                json!({ "key": "BD13BF0BAB55029ADECF2874FB000FD2C0647016ABCE48FEFFCF50063C8792C5",
+                        "iv": "FF9F4EFCB67580935126781E99B7887C",
+                        "ct": "BAAB4B95DBCF746048E3477919B38084",
+                        "pt": "2068BB7FE283FD121A362186CA2CC1F0" }),
+            ),
+        ];
+        for (algorithm, direction, test, expected_last) in cases {
+            let group = json!({ "tgId": 1, "testType": "MCT", "direction": direction,
+                                "tests": [test] });
+            let results = run(algorithm, group)["resultsArray"].clone();
+            assert_eq!(results.as_array().unwrap().len(), 100);
+            assert_eq!(results[99], expected_last);
+        }
+    }
+
+    #[test]
+    fn errors_work() {
+        assert_eq!(respond("not json"), Err(AesError::InvalidParameter));
+        let unsupported = json!({ "vsId": 1, "algorithm": "ACVP-AES-GCM", "testGroups": [] });
+        assert_eq!(respond_value(&unsupported), Err(AesError::Unsupported));
+        let missing = json!({ "vsId": 1, "algorithm": "ACVP-AES-ECB", "testGroups": [{
+            "tgId": 1, "testType": "AFT", "direction": "encrypt", "tests": [{ "tcId": 1 }]
+        }]});
+        assert_eq!(respond_value(&missing), Err(AesError::InvalidParameter));
+    }
+}
@@ -0,0 +1,748 @@
+//! # aes_cipher
+//! `aes_cipher` provides the cipher objects [`Aes128`], [`Aes192`] and [`Aes256`], and
+//! [`AesAny`] for a key size chosen at run time, and [`Aes`] for generic code over the key
+//! sizes.
+//!
+//! A cipher object schedules the key once when it is created, and keeps the sub-keys for both
+//! encryption and decryption. So there is no need to manage the sub-keys buffers and to pick the
+//! right functions for the key size, like the low-level API in `aes_core` requires.
+//!
+//! The sub-keys can still be borrowed to work with the functions in `aes_with_operation_mode`.
+//! The cipher objects also implement `BlockCipher128`, so they can be passed to the functions
+//! ending with `_with` there directly.
+//!
+//! [`Aes128`]: ../aes_cipher/struct.Aes128.html
+//! [`Aes192`]: ../aes_cipher/struct.Aes192.html
+//! [`Aes256`]: ../aes_cipher/struct.Aes256.html
+//! [`AesAny`]: ../aes_cipher/enum.AesAny.html
+//! [`Aes`]: ../aes_cipher/struct.Aes.html
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+use super::aes_core::{KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::key::{Key, Key128, Key192, Key256, KeySize};
+use std::fmt;
+
+macro_rules! define_aes_cipher {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $key_bytes:expr, $n_subkeys:expr, $n_rounds:expr,
+        $key:ident, $schedule:path,
+        $block_encrypt:path, $block_decrypt:path
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            encryption_subkeys: [u32; $n_subkeys],
+            decryption_subkeys: [u32; $n_subkeys],
+        }
+
+        impl $name {
+            /// Create a cipher object from a key, scheduling the sub-keys for both encryption and
+            /// decryption.
+            pub fn new(key: &[u8; $key_bytes]) -> Self {
+                let mut encryption_subkeys = [0u32; $n_subkeys];
+                let mut decryption_subkeys = [0u32; $n_subkeys];
+                $schedule(key, &mut encryption_subkeys, &mut decryption_subkeys);
+                Self {
+                    encryption_subkeys,
+                    decryption_subkeys,
+                }
+            }
+
+            /// Create a cipher object from the sub-keys for **encryption** which have already
+            /// been scheduled, e.g. by [`key_schedule_encrypt128`]. The sub-keys for decryption
+            /// are derived from them.
+            ///
+            /// [`key_schedule_encrypt128`]: ../aes_core/fn.key_schedule_encrypt128.html
+            pub fn from_encryption_subkeys(subkeys: &[u32; $n_subkeys]) -> Self {
+                let encryption_subkeys = *subkeys;
+                let mut decryption_subkeys = *subkeys;
+                aes_core::subkeys_encrypt_to_decrypt(&mut decryption_subkeys);
+                Self {
+                    encryption_subkeys,
+                    decryption_subkeys,
+                }
+            }
+
+            /// Create a cipher object from a key given as 32-bit words.
+            ///
+            /// The words are in the notation of NIST.FIPS.197, i.e. the first byte of the key is
+            /// the **most** significant byte of the first word. For example, the key
+            /// `2B 7E 15 16 28 AE ...` is given as `[0x2B7E1516, 0x28AED2A6, ...]`.
+            pub fn from_key_words(words: &[u32; $key_bytes / 4]) -> Self {
+                let mut key = [0u8; $key_bytes];
+                for (bytes, word) in key.chunks_exact_mut(4).zip(words.iter()) {
+                    bytes.copy_from_slice(&word.to_be_bytes());
+                }
+                Self::new(&key)
+            }
+
+            /// **Encrypt** a block in place.
+            pub fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                $block_encrypt(block, &self.encryption_subkeys);
+            }
+
+            /// **Decrypt** a block in place.
+            pub fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                $block_decrypt(block, &self.decryption_subkeys);
+            }
+
+            /// **Encrypt** consecutive blocks in place, each one independently (like ECB mode).
+            ///
+            /// # Panics
+            /// This function panics if the length of `blocks` is not a multiple of 16.
+            pub fn encrypt_blocks(&self, blocks: &mut [u8]) {
+                aes_core::encrypt_blocks(blocks, &self.encryption_subkeys);
+            }
+
+            /// **Decrypt** consecutive blocks in place, each one independently (like ECB mode).
+            ///
+            /// # Panics
+            /// This function panics if the length of `blocks` is not a multiple of 16.
+            pub fn decrypt_blocks(&self, blocks: &mut [u8]) {
+                aes_core::decrypt_blocks(blocks, &self.decryption_subkeys);
+            }
+
+            /// Export the sub-keys for encryption as round keys in the byte order of
+            /// NIST.FIPS.197, e.g. to load them into a hardware AES engine.
+            ///
+            /// See [`subkeys_to_round_keys`] for details.
+            ///
+            /// [`subkeys_to_round_keys`]: ../aes_core/fn.subkeys_to_round_keys.html
+            pub fn round_keys(&self) -> [[u8; BLOCKSIZE_IN_BYTES]; $n_rounds + 1] {
+                let mut round_keys = [[0u8; BLOCKSIZE_IN_BYTES]; $n_rounds + 1];
+                aes_core::subkeys_to_round_keys(&self.encryption_subkeys, &mut round_keys);
+                round_keys
+            }
+
+            /// Borrow the sub-keys for encryption, e.g. to pass them to the functions in
+            /// `aes_with_operation_mode`.
+            pub fn encryption_subkeys(&self) -> &[u32] {
+                &self.encryption_subkeys
+            }
+
+            /// Borrow the sub-keys for decryption, e.g. to pass them to the functions in
+            /// `aes_with_operation_mode`.
+            pub fn decryption_subkeys(&self) -> &[u32] {
+                &self.decryption_subkeys
+            }
+        }
+
+        impl_redacted_debug!($name);
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                self.encryption_subkeys.fill(0);
+                self.decryption_subkeys.fill(0);
+                std::hint::black_box((&self.encryption_subkeys, &self.decryption_subkeys));
+            }
+        }
+
+        impl From<&$key> for $name {
+            fn from(key: &$key) -> Self {
+                Self::new(key.as_bytes())
+            }
+        }
+
+        impl BlockCipher128 for $name {
+            fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                $block_encrypt(block, &self.encryption_subkeys);
+            }
+
+            fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                $block_decrypt(block, &self.decryption_subkeys);
+            }
+
+            fn encrypt_blocks(&self, blocks: &mut [u8]) {
+                $name::encrypt_blocks(self, blocks);
+            }
+
+            fn decrypt_blocks(&self, blocks: &mut [u8]) {
+                $name::decrypt_blocks(self, blocks);
+            }
+        }
+    };
+}
+
+define_aes_cipher!(
+    /// AES cipher object with a **128bit key**.
+    /// # Examples
+    /// ```
+    /// use aes_frast::Aes128;
+    ///
+    /// // This example came from NIST.FIPS.197 Appendix C.1
+    /// let key: [u8; 16] = [
+    ///     0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+    ///     0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
+    /// ];
+    /// let aes = Aes128::new(&key);
+    ///
+    /// let mut block: [u8; 16] = [
+    ///     0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+    ///     0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF,
+    /// ];
+    /// aes.encrypt_block(&mut block);
+    /// assert_eq!(block, [0x69, 0xC4, 0xE0, 0xD8, 0x6A, 0x7B, 0x04, 0x30,
+    ///                    0xD8, 0xCD, 0xB7, 0x80, 0x70, 0xB4, 0xC5, 0x5A]);
+    ///
+    /// aes.decrypt_block(&mut block);
+    /// assert_eq!(block, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+    ///                    0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+    /// ```
+    Aes128,
+    KEY_BYTES_128BIT,
+    N_SUBKEYS_128BIT,
+    10,
+    Key128,
+    aes_core::key_schedule_encrypt_decrypt128,
+    aes_core::block_encrypt128_inplace,
+    aes_core::block_decrypt128_inplace
+);
+
+define_aes_cipher!(
+    /// AES cipher object with a **192bit key**.
+    /// # Examples
+    /// Please refer to [`Aes128`], they are very similar.
+    ///
+    /// [`Aes128`]: ../aes_cipher/struct.Aes128.html
+    Aes192,
+    KEY_BYTES_192BIT,
+    N_SUBKEYS_192BIT,
+    12,
+    Key192,
+    aes_core::key_schedule_encrypt_decrypt192,
+    aes_core::block_encrypt192_inplace,
+    aes_core::block_decrypt192_inplace
+);
+
+define_aes_cipher!(
+    /// AES cipher object with a **256bit key**.
+    /// # Examples
+    /// Please refer to [`Aes128`], they are very similar.
+    ///
+    /// [`Aes128`]: ../aes_cipher/struct.Aes128.html
+    Aes256,
+    KEY_BYTES_256BIT,
+    N_SUBKEYS_256BIT,
+    14,
+    Key256,
+    aes_core::key_schedule_encrypt_decrypt256,
+    aes_core::block_encrypt256_inplace,
+    aes_core::block_decrypt256_inplace
+);
+
+/// AES cipher object whose key size is chosen at run time, e.g. from a file header.
+///
+/// It has the same methods as the fixed-size cipher objects, so there is no need to match on the
+/// key size around every operation.
+/// # Examples
+/// ```
+/// use aes_frast::key::KeySize;
+/// use aes_frast::{Aes192, AesAny, AesError};
+///
+/// let key = [0x2Bu8; 24]; // e.g. read from a header, so its length is not known statically
+/// let aes = AesAny::new(&key).unwrap();
+/// assert_eq!(aes.key_size(), KeySize::Aes192);
+/// assert_eq!(aes.round_keys().len(), 13);
+///
+/// let mut block = [0x5Au8; 16];
+/// aes.encrypt_block(&mut block);
+/// let mut expected = [0x5Au8; 16];
+/// Aes192::new(&key).encrypt_block(&mut expected);
+/// assert_eq!(block, expected);
+///
+/// assert_eq!(AesAny::new(&[0u8; 20]).err(), Some(AesError::InvalidKeyLength));
+/// ```
+#[derive(Clone)]
+pub enum AesAny {
+    /// A **128bit key**
+    Aes128(Aes128),
+    /// A **192bit key**
+    Aes192(Aes192),
+    /// A **256bit key**
+    Aes256(Aes256),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $aes:ident => $body:expr) => {
+        match $self {
+            AesAny::Aes128($aes) => $body,
+            AesAny::Aes192($aes) => $body,
+            AesAny::Aes256($aes) => $body,
+        }
+    };
+}
+
+impl AesAny {
+    /// Create a cipher object from a key of any of the three sizes.
+    ///
+    /// * *parameter* `key`: the key, 16, 24 or 32 bytes long.
+    /// * *return* : the cipher object, or `Err(AesError::InvalidKeyLength)`.
+    pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        match key.len() {
+            KEY_BYTES_128BIT => Ok(AesAny::Aes128(Aes128::new(key.try_into().unwrap()))),
+            KEY_BYTES_192BIT => Ok(AesAny::Aes192(Aes192::new(key.try_into().unwrap()))),
+            KEY_BYTES_256BIT => Ok(AesAny::Aes256(Aes256::new(key.try_into().unwrap()))),
+            _ => Err(AesError::InvalidKeyLength),
+        }
+    }
+
+    /// The key size.
+    pub fn key_size(&self) -> KeySize {
+        match self {
+            AesAny::Aes128(_) => KeySize::Aes128,
+            AesAny::Aes192(_) => KeySize::Aes192,
+            AesAny::Aes256(_) => KeySize::Aes256,
+        }
+    }
+
+    /// **Encrypt** a block in place.
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        dispatch!(self, aes => aes.encrypt_block(block))
+    }
+
+    /// **Decrypt** a block in place.
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        dispatch!(self, aes => aes.decrypt_block(block))
+    }
+
+    /// **Encrypt** consecutive blocks in place, each one independently (like ECB mode).
+    ///
+    /// # Panics
+    /// This function panics if the length of `blocks` is not a multiple of 16.
+    pub fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        dispatch!(self, aes => aes.encrypt_blocks(blocks))
+    }
+
+    /// **Decrypt** consecutive blocks in place, each one independently (like ECB mode).
+    ///
+    /// # Panics
+    /// This function panics if the length of `blocks` is not a multiple of 16.
+    pub fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        dispatch!(self, aes => aes.decrypt_blocks(blocks))
+    }
+
+    /// Export the sub-keys for encryption as round keys in the byte order of NIST.FIPS.197.
+    /// There are 11, 13 or 15 of them, depending on the key size.
+    pub fn round_keys(&self) -> Vec<[u8; BLOCKSIZE_IN_BYTES]> {
+        dispatch!(self, aes => aes.round_keys().to_vec())
+    }
+
+    /// Borrow the sub-keys for encryption, e.g. to pass them to the functions in
+    /// `aes_with_operation_mode`.
+    pub fn encryption_subkeys(&self) -> &[u32] {
+        dispatch!(self, aes => aes.encryption_subkeys())
+    }
+
+    /// Borrow the sub-keys for decryption, e.g. to pass them to the functions in
+    /// `aes_with_operation_mode`.
+    pub fn decryption_subkeys(&self) -> &[u32] {
+        dispatch!(self, aes => aes.decryption_subkeys())
+    }
+}
+
+impl BlockCipher128 for AesAny {
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        AesAny::encrypt_block(self, block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        AesAny::decrypt_block(self, block);
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        AesAny::encrypt_blocks(self, blocks);
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        AesAny::decrypt_blocks(self, blocks);
+    }
+}
+
+impl fmt::Debug for AesAny {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AesAny")
+            .field("size", &self.key_size())
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<Aes128> for AesAny {
+    fn from(aes: Aes128) -> Self {
+        AesAny::Aes128(aes)
+    }
+}
+
+impl From<Aes192> for AesAny {
+    fn from(aes: Aes192) -> Self {
+        AesAny::Aes192(aes)
+    }
+}
+
+impl From<Aes256> for AesAny {
+    fn from(aes: Aes256) -> Self {
+        AesAny::Aes256(aes)
+    }
+}
+
+impl From<&Key> for AesAny {
+    fn from(key: &Key) -> Self {
+        // A `Key` always has a valid length.
+        AesAny::new(key.as_bytes()).unwrap()
+    }
+}
+
+/// AES cipher object whose key size is a const generic parameter, for generic code over the
+/// three key sizes.
+///
+/// `Aes<16>`, `Aes<24>` and `Aes<32>` work like [`Aes128`], [`Aes192`] and [`Aes256`]. The key
+/// size is known at compile time, so the choice of the block functions is resolved by the
+/// compiler, unlike [`AesAny`]. Any other `KEY_BYTES` is rejected at compile time.
+/// # Examples
+/// ```
+/// use aes_frast::{Aes, Aes256};
+///
+/// fn encrypt_twice<const N: usize>(key: &[u8; N], block: &mut [u8; 16]) {
+///     let aes = Aes::<N>::new(key);
+///     aes.encrypt_block(block);
+///     aes.encrypt_block(block);
+/// }
+///
+/// let key = [0x2Bu8; 32];
+/// let mut block = [0x5Au8; 16];
+/// encrypt_twice(&key, &mut block);
+///
+/// let mut expected = [0x5Au8; 16];
+/// let aes = Aes256::new(&key);
+/// aes.encrypt_block(&mut expected);
+/// aes.encrypt_block(&mut expected);
+/// assert_eq!(block, expected);
+/// assert_eq!(Aes::<32>::ROUNDS, 14);
+/// ```
+///
+/// A key of 20 bytes doesn't compile:
+/// ```compile_fail
+/// use aes_frast::Aes;
+///
+/// let aes = Aes::<20>::new(&[0u8; 20]);
+/// ```
+///
+/// [`Aes128`]: ../aes_cipher/struct.Aes128.html
+/// [`Aes192`]: ../aes_cipher/struct.Aes192.html
+/// [`Aes256`]: ../aes_cipher/struct.Aes256.html
+/// [`AesAny`]: ../aes_cipher/enum.AesAny.html
+#[derive(Clone)]
+pub struct Aes<const KEY_BYTES: usize> {
+    // Arrays can't be sized by an expression of `KEY_BYTES` yet, so the sub-keys are kept in
+    // buffers for the longest key, and only the leading `N_SUBKEYS` words are used.
+    encryption_subkeys: [u32; N_SUBKEYS_256BIT],
+    decryption_subkeys: [u32; N_SUBKEYS_256BIT],
+}
+
+impl<const KEY_BYTES: usize> Aes<KEY_BYTES> {
+    /// The key size.
+    pub const KEY_SIZE: KeySize = match KEY_BYTES {
+        KEY_BYTES_128BIT => KeySize::Aes128,
+        KEY_BYTES_192BIT => KeySize::Aes192,
+        KEY_BYTES_256BIT => KeySize::Aes256,
+        _ => panic!("AES keys are 16, 24 or 32 bytes long."),
+    };
+
+    /// The number of sub-keys in 32-bit words.
+    pub const N_SUBKEYS: usize = match Self::KEY_SIZE {
+        KeySize::Aes128 => N_SUBKEYS_128BIT,
+        KeySize::Aes192 => N_SUBKEYS_192BIT,
+        KeySize::Aes256 => N_SUBKEYS_256BIT,
+    };
+
+    /// The number of rounds.
+    pub const ROUNDS: usize = Self::N_SUBKEYS / 4 - 1;
+
+    /// Create a cipher object from a key, scheduling the sub-keys for both encryption and
+    /// decryption.
+    pub fn new(key: &[u8; KEY_BYTES]) -> Self {
+        let mut encryption_subkeys = [0u32; N_SUBKEYS_256BIT];
+        let mut decryption_subkeys = [0u32; N_SUBKEYS_256BIT];
+        aes_core::key_schedule_encrypt_decrypt_auto(
+            key,
+            &mut encryption_subkeys[..Self::N_SUBKEYS],
+            &mut decryption_subkeys[..Self::N_SUBKEYS],
+        );
+        Self {
+            encryption_subkeys,
+            decryption_subkeys,
+        }
+    }
+
+    /// **Encrypt** a block in place.
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        let subkeys = self.encryption_subkeys();
+        match Self::KEY_SIZE {
+            KeySize::Aes128 => aes_core::block_encrypt128_inplace(block, subkeys),
+            KeySize::Aes192 => aes_core::block_encrypt192_inplace(block, subkeys),
+            KeySize::Aes256 => aes_core::block_encrypt256_inplace(block, subkeys),
+        }
+    }
+
+    /// **Decrypt** a block in place.
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        let subkeys = self.decryption_subkeys();
+        match Self::KEY_SIZE {
+            KeySize::Aes128 => aes_core::block_decrypt128_inplace(block, subkeys),
+            KeySize::Aes192 => aes_core::block_decrypt192_inplace(block, subkeys),
+            KeySize::Aes256 => aes_core::block_decrypt256_inplace(block, subkeys),
+        }
+    }
+
+    /// **Encrypt** consecutive blocks in place, each one independently (like ECB mode).
+    ///
+    /// # Panics
+    /// This function panics if the length of `blocks` is not a multiple of 16.
+    pub fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        aes_core::encrypt_blocks(blocks, self.encryption_subkeys());
+    }
+
+    /// **Decrypt** consecutive blocks in place, each one independently (like ECB mode).
+    ///
+    /// # Panics
+    /// This function panics if the length of `blocks` is not a multiple of 16.
+    pub fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        aes_core::decrypt_blocks(blocks, self.decryption_subkeys());
+    }
+
+    /// Export the sub-keys for encryption as round keys in the byte order of NIST.FIPS.197.
+    /// There are `ROUNDS + 1` of them.
+    pub fn round_keys(&self) -> Vec<[u8; BLOCKSIZE_IN_BYTES]> {
+        let mut round_keys = vec![[0u8; BLOCKSIZE_IN_BYTES]; Self::ROUNDS + 1];
+        aes_core::subkeys_to_round_keys(self.encryption_subkeys(), &mut round_keys);
+        round_keys
+    }
+
+    /// Borrow the sub-keys for encryption, e.g. to pass them to the functions in
+    /// `aes_with_operation_mode`.
+    pub fn encryption_subkeys(&self) -> &[u32] {
+        &self.encryption_subkeys[..Self::N_SUBKEYS]
+    }
+
+    /// Borrow the sub-keys for decryption, e.g. to pass them to the functions in
+    /// `aes_with_operation_mode`.
+    pub fn decryption_subkeys(&self) -> &[u32] {
+        &self.decryption_subkeys[..Self::N_SUBKEYS]
+    }
+}
+
+impl<const KEY_BYTES: usize> Drop for Aes<KEY_BYTES> {
+    fn drop(&mut self) {
+        self.encryption_subkeys.fill(0);
+        self.decryption_subkeys.fill(0);
+        std::hint::black_box((&self.encryption_subkeys, &self.decryption_subkeys));
+    }
+}
+
+impl<const KEY_BYTES: usize> fmt::Debug for Aes<KEY_BYTES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Aes")
+            .field("size", &Self::KEY_SIZE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const KEY_BYTES: usize> BlockCipher128 for Aes<KEY_BYTES> {
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        Aes::encrypt_block(self, block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        Aes::decrypt_block(self, block);
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        Aes::encrypt_blocks(self, blocks);
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        Aes::decrypt_blocks(self, blocks);
+    }
+}
+
+impl<const KEY_BYTES: usize> From<&Aes<KEY_BYTES>> for AesAny {
+    fn from(aes: &Aes<KEY_BYTES>) -> Self {
+        let subkeys = aes.encryption_subkeys();
+        match Aes::<KEY_BYTES>::KEY_SIZE {
+            KeySize::Aes128 => Aes128::from_encryption_subkeys(subkeys.try_into().unwrap()).into(),
+            KeySize::Aes192 => Aes192::from_encryption_subkeys(subkeys.try_into().unwrap()).into(),
+            KeySize::Aes256 => Aes256::from_encryption_subkeys(subkeys.try_into().unwrap()).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_with_operation_mode;
+
+    const PLAIN: [u8; BLOCKSIZE_IN_BYTES] = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE,
+        0xFF,
+    ];
+
+    #[test]
+    fn aes192_works() {
+        // This example came from NIST.FIPS.197 Appendix C.2
+        let key: [u8; KEY_BYTES_192BIT] = core::array::from_fn(|i| i as u8);
+        let aes = Aes192::new(&key);
+        let mut block = PLAIN;
+        aes.encrypt_block(&mut block);
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0xDD, 0xA9, 0x7C, 0xA4, 0x86, 0x4C, 0xDF, 0xE0, 0x6E, 0xAF, 0x70, 0xA0, 0xEC, 0x0D,
+            0x71, 0x91,
+        ];
+        assert_eq!(block, expected);
+        aes.decrypt_block(&mut block);
+        assert_eq!(block, PLAIN);
+    }
+
+    #[test]
+    fn aes256_works() {
+        // This example came from NIST.FIPS.197 Appendix C.3
+        let key: [u8; KEY_BYTES_256BIT] = core::array::from_fn(|i| i as u8);
+        let aes = Aes256::new(&key);
+        let mut block = PLAIN;
+        aes.encrypt_block(&mut block);
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0x8E, 0xA2, 0xB7, 0xCA, 0x51, 0x67, 0x45, 0xBF, 0xEA, 0xFC, 0x49, 0x90, 0x4B, 0x49,
+            0x60, 0x89,
+        ];
+        assert_eq!(block, expected);
+        aes.decrypt_block(&mut block);
+        assert_eq!(block, PLAIN);
+    }
+
+    #[test]
+    fn encrypt_blocks_works() {
+        let key: [u8; KEY_BYTES_128BIT] = core::array::from_fn(|i| (i * 3) as u8);
+        let aes = Aes128::new(&key);
+        let plain: Vec<u8> = (0..64).collect();
+        let mut expected = vec![0u8; 64];
+        aes_with_operation_mode::ecb_enc(&plain, &mut expected, aes.encryption_subkeys());
+        let mut blocks = plain.clone();
+        aes.encrypt_blocks(&mut blocks);
+        assert_eq!(blocks, expected);
+        aes.decrypt_blocks(&mut blocks);
+        assert_eq!(blocks, plain);
+    }
+
+    #[test]
+    fn other_constructors_work() {
+        // This example key came from NIST.FIPS.197 Appendix A.1
+        let key: [u8; KEY_BYTES_128BIT] = [
+            0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF,
+            0x4F, 0x3C,
+        ];
+        let aes = Aes128::new(&key);
+        let from_words = Aes128::from_key_words(&[0x2B7E1516, 0x28AED2A6, 0xABF71588, 0x09CF4F3C]);
+        assert_eq!(from_words.encryption_subkeys(), aes.encryption_subkeys());
+        assert_eq!(from_words.decryption_subkeys(), aes.decryption_subkeys());
+        let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+        subkeys.copy_from_slice(aes.encryption_subkeys());
+        let from_subkeys = Aes128::from_encryption_subkeys(&subkeys);
+        assert_eq!(from_subkeys.encryption_subkeys(), aes.encryption_subkeys());
+        assert_eq!(from_subkeys.decryption_subkeys(), aes.decryption_subkeys());
+    }
+
+    #[test]
+    fn round_keys_works() {
+        let key: [u8; KEY_BYTES_192BIT] = core::array::from_fn(|i| i as u8);
+        let round_keys = Aes192::new(&key).round_keys();
+        assert_eq!(round_keys.len(), 13);
+        assert_eq!(round_keys[0], key[..16]);
+        // This example came from NIST.FIPS.197 Appendix C.2
+        let expected_last: [u8; BLOCKSIZE_IN_BYTES] = [
+            0xA4, 0x97, 0x0A, 0x33, 0x1A, 0x78, 0xDC, 0x09, 0xC4, 0x18, 0xC2, 0x71, 0xE3, 0xA4,
+            0x1D, 0x5D,
+        ];
+        assert_eq!(round_keys[12], expected_last);
+    }
+
+    #[test]
+    #[should_panic]
+    fn encrypt_blocks_incomplete_panic() {
+        let aes = Aes128::new(&[0u8; KEY_BYTES_128BIT]);
+        aes.encrypt_blocks(&mut [0u8; 20]);
+    }
+
+    #[test]
+    fn aes_any_works() {
+        let plain: Vec<u8> = (0..48).collect();
+        for key_bytes in [KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT] {
+            let key: Vec<u8> = (0..key_bytes as u8).collect();
+            let aes = AesAny::from(&Key::new(&key).unwrap());
+            assert_eq!(aes.key_size().bytes(), key_bytes);
+            assert_eq!(aes.round_keys().len(), key_bytes / 4 + 7);
+            let mut expected = vec![0u8; 48];
+            aes_with_operation_mode::ecb_enc(&plain, &mut expected, aes.encryption_subkeys());
+            let mut blocks = plain.clone();
+            aes.encrypt_blocks(&mut blocks);
+            assert_eq!(blocks, expected);
+            aes.decrypt_blocks(&mut blocks);
+            assert_eq!(blocks, plain);
+        }
+        let aes: AesAny = Aes256::new(&[0u8; KEY_BYTES_256BIT]).into();
+        assert_eq!(aes.decryption_subkeys().len(), N_SUBKEYS_256BIT);
+    }
+
+    fn check_generic<const N: usize>() {
+        let key: [u8; N] = core::array::from_fn(|i| i as u8);
+        let aes = Aes::<N>::new(&key);
+        let any = AesAny::new(&key).unwrap();
+        assert_eq!(Aes::<N>::KEY_SIZE, any.key_size());
+        assert_eq!(aes.encryption_subkeys(), any.encryption_subkeys());
+        assert_eq!(aes.decryption_subkeys(), any.decryption_subkeys());
+        assert_eq!(aes.round_keys(), any.round_keys());
+        assert_eq!(
+            AesAny::from(&aes).decryption_subkeys(),
+            any.decryption_subkeys()
+        );
+        let mut block = PLAIN;
+        aes.encrypt_block(&mut block);
+        let mut expected = PLAIN;
+        any.encrypt_block(&mut expected);
+        assert_eq!(block, expected);
+        aes.decrypt_block(&mut block);
+        assert_eq!(block, PLAIN);
+        let plain: Vec<u8> = (0..48).collect();
+        let mut blocks = plain.clone();
+        aes.encrypt_blocks(&mut blocks);
+        aes.decrypt_blocks(&mut blocks);
+        assert_eq!(blocks, plain);
+    }
+
+    #[test]
+    fn aes_generic_works() {
+        check_generic::<KEY_BYTES_128BIT>();
+        check_generic::<KEY_BYTES_192BIT>();
+        check_generic::<KEY_BYTES_256BIT>();
+        assert_eq!(Aes::<KEY_BYTES_192BIT>::ROUNDS, 12);
+        assert_eq!(
+            format!("{:?}", Aes::<KEY_BYTES_128BIT>::new(&[0u8; 16])),
+            "Aes { size: Aes128, .. }"
+        );
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        use crate::aes_cmac::Cmac;
+        use crate::aes_with_operation_mode::Cbc;
+        use crate::iv_generator::FixedIv;
+
+        let aes = Aes128::new(&[0xABu8; KEY_BYTES_128BIT]);
+        assert_eq!(format!("{:?}", aes), "Aes128 { .. }");
+        let any = AesAny::new(&[0xABu8; KEY_BYTES_192BIT]).unwrap();
+        assert_eq!(format!("{:?}", any), "AesAny { size: Aes192, .. }");
+        assert_eq!(format!("{:?}", Cbc::new(aes.clone())), "Cbc { .. }");
+        assert_eq!(format!("{:?}", Cmac::new(aes)), "Cmac { .. }");
+        assert_eq!(format!("{:?}", FixedIv([0xABu8; 16])), "FixedIv { .. }");
+    }
+}
@@ -0,0 +1,353 @@
+//! # aes_cmac
+//! `aes_cmac` provides CMAC, the block-cipher-based MAC of NIST SP 800-38B (AES-CMAC in RFC 4493).
+//!
+//! CMAC is a CBC-MAC whose last block is masked with one of two subkeys, `K1` for a complete
+//! block and `K2` for a padded one, both derived from the encryption of the zero block. Unlike
+//! the raw CBC-MAC, it is secure for messages of different lengths.
+//!
+//! [`Cmac`] keeps the cipher and the subkeys, for many messages under one key; [`aes_cmac`] is
+//! the one-shot function. A MAC may be truncated, down to 8 bytes; SP 800-38B advises against
+//! shorter tags.
+//!
+//! [`Cmac::s2v`] (and the one-shot [`s2v`]) is S2V of RFC 5297, the CMAC of a vector of strings
+//! which SIV takes as its synthetic IV.
+//!
+//! [`Cmac`]: ../aes_cmac/struct.Cmac.html
+//! [`Cmac::s2v`]: ../aes_cmac/struct.Cmac.html#method.s2v
+//! [`s2v`]: ../aes_cmac/fn.s2v.html
+//! [`aes_cmac`]: ../aes_cmac/fn.aes_cmac.html
+use super::aes_cipher::AesAny;
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::tag::{verify_tag, Tag};
+
+/// The shortest truncated MAC accepted, in bytes.
+pub const MIN_TAG_LEN: usize = 8;
+
+/// CMAC over a [`BlockCipher128`], with its subkeys.
+/// # Examples
+/// ```
+/// use aes_frast::aes_cmac::Cmac;
+/// use aes_frast::Aes128;
+///
+/// // This example came from RFC 4493, section 4 (example 2).
+/// let cmac = Cmac::new(Aes128::new(&[0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///                                    0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C]));
+/// let message = [0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96,
+///                0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17, 0x2A];
+/// let mac = cmac.mac(&message);
+/// assert_eq!(mac.as_bytes(), &[0x07, 0x0A, 0x16, 0xB4, 0x6B, 0x4D, 0x41, 0x44,
+///                              0xF7, 0x9B, 0xDD, 0x9D, 0xD0, 0x4A, 0x28, 0x7C]);
+/// assert_eq!(cmac.verify(&message, mac.as_bytes()), Ok(()));
+///
+/// // A truncated MAC is checked against the same leading bytes.
+/// let short = cmac.mac_truncated(&message, 8).unwrap();
+/// assert_eq!(cmac.verify(&message, short.as_bytes()), Ok(()));
+/// assert!(cmac.verify(&message[..15], short.as_bytes()).is_err());
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Cmac<C> {
+    cipher: C,
+    k1: Block,
+    k2: Block,
+}
+
+impl<C: BlockCipher128> Cmac<C> {
+    /// Create the MAC, deriving the subkeys `K1` and `K2` from `cipher`.
+    pub fn new(cipher: C) -> Self {
+        let mut l = Block::default();
+        cipher.encrypt_block(&mut l.0);
+        let k1 = l.dbl();
+        let k2 = k1.dbl();
+        Self { cipher, k1, k2 }
+    }
+
+    /// The block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The subkeys `K1` (for a complete last block) and `K2` (for a padded one).
+    pub fn subkeys(&self) -> (Block, Block) {
+        (self.k1, self.k2)
+    }
+
+    /// Compute the 16-byte MAC of `message`.
+    pub fn mac(&self, message: &[u8]) -> Tag {
+        Tag::from(self.mac_block(message).0)
+    }
+
+    fn mac_block(&self, message: &[u8]) -> Block {
+        let blocks = message.len().div_ceil(BLOCKSIZE_IN_BYTES).max(1);
+        let (head, last) = message.split_at(BLOCKSIZE_IN_BYTES * (blocks - 1));
+        let mut mac = Block::default();
+        for chunk in head.chunks_exact(BLOCKSIZE_IN_BYTES) {
+            mac ^= Block::from_slice(chunk);
+            self.cipher.encrypt_block(&mut mac.0);
+        }
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[..last.len()].copy_from_slice(last);
+        if last.len() == BLOCKSIZE_IN_BYTES {
+            mac ^= self.k1;
+        } else {
+            block[last.len()] = 0x80;
+            mac ^= self.k2;
+        }
+        mac ^= Block::from(block);
+        self.cipher.encrypt_block(&mut mac.0);
+        mac
+    }
+
+    /// Compute the MAC of `message`, truncated to its leading `tag_len` bytes.
+    ///
+    /// * *parameter* `tag_len`: from 8 to 16.
+    /// * *return* : the MAC, or `Err(AesError::InvalidParameter)` for other lengths.
+    pub fn mac_truncated(&self, message: &[u8], tag_len: usize) -> Result<Tag, AesError> {
+        if !(MIN_TAG_LEN..=BLOCKSIZE_IN_BYTES).contains(&tag_len) {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(self.mac(message).truncate(tag_len))
+    }
+
+    /// Verify the received MAC of `message` in constant time.
+    ///
+    /// * *parameter* `tag`: the received MAC, which may be truncated to 8 bytes or more.
+    /// * *return* : `Ok(())` if it is right, otherwise `Err(AesError::TagMismatch)`, which is
+    ///   returned for a too short or too long `tag` too.
+    pub fn verify(&self, message: &[u8], tag: &[u8]) -> Result<(), AesError> {
+        let tag_len = tag.len().clamp(MIN_TAG_LEN, BLOCKSIZE_IN_BYTES);
+        // A `tag` of a wrong length fails the length check of `verify_tag`.
+        verify_tag(tag, &self.mac(message).as_bytes()[..tag_len])
+    }
+
+    /// S2V (RFC 5297, section 2.4), the "string to vector" function of SIV, over the vector of
+    /// strings `strings`.
+    ///
+    /// Each string but the last is MACed on its own and chained with doublings; the last one is
+    /// mixed in before the final MAC. So the order and the boundaries of the strings matter, and
+    /// the result is a 16-byte pseudorandom value of all of them, e.g. a synthetic IV over
+    /// several header fields.
+    /// # Examples
+    /// ```
+    /// use aes_frast::aes_cmac::Cmac;
+    /// use aes_frast::Aes128;
+    ///
+    /// let cmac = Cmac::new(Aes128::new(&[0x7Fu8; 16]));
+    /// let v = cmac.s2v(&[b"header", b"sequence 7", b"payload"]);
+    /// assert_ne!(v, cmac.s2v(&[b"header", b"sequence 7payload"]));
+    /// assert_ne!(v, cmac.s2v(&[b"sequence 7", b"header", b"payload"]));
+    /// ```
+    pub fn s2v(&self, strings: &[&[u8]]) -> Tag {
+        let (last, init) = match strings.split_last() {
+            Some(split) => split,
+            None => {
+                let mut one = [0u8; BLOCKSIZE_IN_BYTES];
+                one[BLOCKSIZE_IN_BYTES - 1] = 1;
+                return self.mac(&one);
+            }
+        };
+        let mut d = self.mac_block(&[0u8; BLOCKSIZE_IN_BYTES]);
+        for string in init {
+            d = d.dbl() ^ self.mac_block(string);
+        }
+        if last.len() >= BLOCKSIZE_IN_BYTES {
+            // `last xorend D`: D is XORed into the last 16 bytes.
+            let mut t = last.to_vec();
+            let tail = t.len() - BLOCKSIZE_IN_BYTES;
+            for (x, y) in t[tail..].iter_mut().zip(d.0.iter()) {
+                *x ^= y;
+            }
+            self.mac(&t)
+        } else {
+            let mut padded = [0u8; BLOCKSIZE_IN_BYTES];
+            padded[..last.len()].copy_from_slice(last);
+            padded[last.len()] = 0x80;
+            self.mac(&(d.dbl() ^ Block::from(padded)).0)
+        }
+    }
+}
+
+/// Compute the 16-byte AES-CMAC of `message` in one shot.
+///
+/// * *parameter* `key`: an AES key of 16, 24 or 32 bytes.
+/// * *parameter* `message`: the message.
+/// * *return* : the MAC, or `Err(AesError::InvalidKeyLength)`.
+/// # Examples
+/// ```
+/// use aes_frast::aes_cmac::aes_cmac;
+///
+/// let key = [0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///            0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C];
+/// // This example came from RFC 4493, section 4 (example 1), the empty message.
+/// let mac = aes_cmac(&key, b"").unwrap();
+/// assert_eq!(mac.as_bytes(), &[0xBB, 0x1D, 0x69, 0x29, 0xE9, 0x59, 0x37, 0x28,
+///                              0x7F, 0xA3, 0x7D, 0x12, 0x9B, 0x75, 0x67, 0x46]);
+/// ```
+pub fn aes_cmac(key: &[u8], message: &[u8]) -> Result<Tag, AesError> {
+    Ok(Cmac::new(AesAny::new(key)?).mac(message))
+}
+
+/// Compute S2V (RFC 5297) of `strings` with an AES key in one shot, see [`Cmac::s2v`].
+///
+/// * *parameter* `key`: an AES key of 16, 24 or 32 bytes, the first half of a SIV key.
+/// * *parameter* `strings`: the vector of strings.
+/// * *return* : the 16-byte value, or `Err(AesError::InvalidKeyLength)`.
+///
+/// [`Cmac::s2v`]: ../aes_cmac/struct.Cmac.html#method.s2v
+pub fn s2v(key: &[u8], strings: &[&[u8]]) -> Result<Tag, AesError> {
+    Ok(Cmac::new(AesAny::new(key)?).s2v(strings))
+}
+
+impl_redacted_debug!(Cmac<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::unhex;
+
+    const MESSAGE: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                           30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    #[test]
+    fn sp800_38b_examples() {
+        // NIST SP 800-38B, Appendix D, with the message lengths 0, 16, 40 and 64 bytes.
+        let cases = [
+            (
+                "2b7e151628aed2a6abf7158809cf4f3c",
+                [
+                    "bb1d6929e95937287fa37d129b756746",
+                    "070a16b46b4d4144f79bdd9dd04a287c",
+                    "dfa66747de9ae63030ca32611497c827",
+                    "51f0bebf7e3b9d92fc49741779363cfe",
+                ],
+            ),
+            (
+                "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+                [
+                    "d17ddf46adaacde531cac483de7a9367",
+                    "9e99a7bf31e710900662f65e617c5184",
+                    "8a1de5be2eb31aad089a82e6ee908b0e",
+                    "a1d5df0eed790f794d77589659f39a11",
+                ],
+            ),
+            (
+                "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+                [
+                    "028962f61b7bf89efc6b551f4667d983",
+                    "28a7023f452e8f82bd4bf28d8c37c35c",
+                    "aaf3d8f1de5640c232f5b169b9c911e6",
+                    "e1992190549f6ed5696a2c056c315410",
+                ],
+            ),
+        ];
+        let message = unhex(MESSAGE);
+        for (key, macs) in cases {
+            for (len, mac) in [0, 16, 40, 64].into_iter().zip(macs) {
+                let computed = aes_cmac(&unhex(key), &message[..len]).unwrap();
+                assert_eq!(
+                    computed.as_bytes(),
+                    unhex(mac).as_slice(),
+                    "{} {}",
+                    key,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn subkeys() {
+        // RFC 4493, section 4.
+        let cmac = Cmac::new(AesAny::new(&unhex("2b7e151628aed2a6abf7158809cf4f3c")).unwrap());
+        let (k1, k2) = cmac.subkeys();
+        assert_eq!(k1.0.to_vec(), unhex("fbeed618357133667c85e08f7236a8de"));
+        assert_eq!(k2.0.to_vec(), unhex("f7ddac306ae266ccf90bc11ee46d513b"));
+    }
+
+    #[test]
+    fn truncation_and_verification() {
+        let cmac = Cmac::new(AesAny::new(&[0x11u8; 16]).unwrap());
+        let message = b"truncate me";
+        let full = cmac.mac(message);
+        for tag_len in MIN_TAG_LEN..=BLOCKSIZE_IN_BYTES {
+            let short = cmac.mac_truncated(message, tag_len).unwrap();
+            assert_eq!(short.as_bytes(), &full.as_bytes()[..tag_len]);
+            assert_eq!(cmac.verify(message, short.as_bytes()), Ok(()));
+        }
+        for tag_len in [0, 7, 17] {
+            assert_eq!(
+                cmac.mac_truncated(message, tag_len).err(),
+                Some(AesError::InvalidParameter)
+            );
+        }
+        assert_eq!(
+            cmac.verify(message, &full.as_bytes()[..7]),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(cmac.verify(message, &[]), Err(AesError::TagMismatch));
+        let mut forged = full.as_bytes().to_vec();
+        forged[3] ^= 0x20;
+        assert_eq!(cmac.verify(message, &forged), Err(AesError::TagMismatch));
+        assert_eq!(
+            aes_cmac(&[0u8; 15], message).err(),
+            Some(AesError::InvalidKeyLength)
+        );
+    }
+
+    #[test]
+    fn rfc5297_s2v() {
+        // RFC 5297, Appendix A.1 and A.2, whose V is the S2V of the associated data, (the nonce)
+        // and the plain.
+        let v = s2v(
+            &unhex("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0"),
+            &[
+                &unhex("101112131415161718191a1b1c1d1e1f2021222324252627"),
+                &unhex("112233445566778899aabbccddee"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            v.as_bytes(),
+            unhex("85632d07c6e8f37f950acd320a2ecc93").as_slice()
+        );
+        let key = unhex("7f7e7d7c7b7a79787776757473727170");
+        let v = s2v(
+            &key,
+            &[
+                &unhex(
+                    "00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100",
+                ),
+                &unhex("102030405060708090a0"),
+                &unhex("09f911029d74e35bd84156c5635688c0"),
+                &unhex(
+                    "7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553",
+                ),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            v.as_bytes(),
+            unhex("7bdb6e3b432667eb06f4d14bff2fbd0f").as_slice()
+        );
+        // An empty last string and a single one; the values came from the AES-SIV of OpenSSL
+        // (through Python's `cryptography`).
+        let v = s2v(&key, &[b"short", b""]).unwrap();
+        assert_eq!(
+            v.as_bytes(),
+            unhex("a2bacf814d96fa66a2badaed23ad7d2c").as_slice()
+        );
+        let v = s2v(&key, &[&[b'x'; 16]]).unwrap();
+        assert_eq!(
+            v.as_bytes(),
+            unhex("5a752db61be4a10682e439d29d8b402d").as_slice()
+        );
+        // No string at all: the MAC of the block `0^127 || 1`.
+        let mut one = [0u8; 16];
+        one[15] = 1;
+        assert_eq!(s2v(&key, &[]).unwrap(), aes_cmac(&key, &one).unwrap());
+    }
+}
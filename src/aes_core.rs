@@ -4,12 +4,26 @@
 //!
 //! This module provides **low-level API**.
 //!
-//! In this library, AES is implemented by looking-up-tables.
+//! In this library, AES is implemented by looking-up-tables. With the `aesni` feature, the block
+//! functions run on the AES-NI instructions instead, on the x86 and x86_64 CPUs which have them.
+//! With the `fixslice` feature, they run on a fixsliced AES without tables, which takes constant
+//! time (AES-NI still comes first when both features are enabled). With the `small-tables`
+//! feature, they look up the 256-byte S-boxes only and compute MixColumns, leaving out the 8 KiB
+//! of T-Boxes. The `compact-decrypt` feature does so for decryption only, which runs the steps of
+//! the inverse cipher and leaves out the 4 KiB of T-Boxes for decryption.
 //! ## Attention!
 //! This low-level API does NOT provide error handling.
 //!
 //! Please be careful with the lengths of the slices when passing it as the parameters of a
-//! function. Otherwise, it will panic at `index out of bounds` or `assertion failed`.
+//! function. Otherwise, it will panic at `index out of bounds` or `assertion failed`. The key
+//! schedule functions with `auto` also have a `try_` version, which returns an `AesError`
+//! instead.
+//!
+//! The `expand_key_*` functions are `const fn`s, which schedule a fixed key at compile time.
+//!
+//! The `_x4` and `_x8` block functions process 4 or 8 independent blocks per call, interleaving
+//! their rounds. The parallelizable operation modes (ECB, CTR, CBC decryption and XTS) are built
+//! on them.
 //! ## Block cipher
 //! The AES algorithm only supports 128-bit (16 bytes) block.
 //!
@@ -23,6 +37,33 @@
 //! 128 | 16 | 44
 //! 192 | 24 | 52
 //! 256 | 32 | 60
+//! ## Sub-keys word packing
+//! The sub-keys are packed into `u32` words in **little-endian** order: the first byte of each
+//! word is its least significant byte. For example, the first word of the key
+//! `2B 7E 15 16 ...` is `0x16157E2B`. This is the packing used by many hardware AES blocks and
+//! reference implementations, so the sub-keys can be handed over directly.
+//!
+//! NIST.FIPS.197 writes the words in **big-endian** order (`0x2B7E1516`). Use
+//! [`subkeys_to_be_words`] and [`subkeys_from_be_words`] to convert between the two packings, or
+//! [`subkeys_to_round_keys`] to get the bytes.
+//!
+//! [`subkeys_to_be_words`]: ../aes_core/fn.subkeys_to_be_words.html
+//! [`subkeys_from_be_words`]: ../aes_core/fn.subkeys_from_be_words.html
+//! [`subkeys_to_round_keys`]: ../aes_core/fn.subkeys_to_round_keys.html
+// With the `fixslice` feature, the block functions use neither the T-Boxes nor the inverse S-box,
+// and with the `small-tables` feature, they use the S-boxes only. With the `compact-decrypt`
+// feature, only decryption uses the inverse S-box alone.
+#![cfg_attr(
+    any(
+        feature = "fixslice",
+        feature = "small-tables",
+        feature = "compact-decrypt"
+    ),
+    allow(dead_code, unused_macros)
+)]
+
+use super::error::AesError;
+use super::key::{Key128, Key192, Key256};
 
 include!(concat!(env!("OUT_DIR"), "/tables.rs"));
 
@@ -41,6 +82,35 @@ pub const N_SUBKEYS_192BIT: usize = 52;
 /// Sub-keys size in words (u32) from 256bit key, which is 60.
 pub const N_SUBKEYS_256BIT: usize = 60;
 
+// Look up a table of 256 entries at an `u8` index, through `table_lookup::at`.
+// The block functions look up their tables this way, so the `unsafe-speed` feature can skip the
+// bounds checks: an `u8` index is always less than 256. The key schedule keeps plain indexing,
+// which the `const fn`s need.
+macro_rules! lookup {
+    ($table:expr, $index:expr) => {
+        table_lookup::at($table, $index)
+    };
+}
+
+#[cfg(not(feature = "unsafe-speed"))]
+mod table_lookup {
+    #[inline(always)]
+    pub(super) fn at<T: Copy>(table: &[T; 256], index: u8) -> T {
+        table[index as usize]
+    }
+}
+
+// The only `unsafe` code of the T-table implementation.
+#[cfg(feature = "unsafe-speed")]
+#[allow(unsafe_code)]
+mod table_lookup {
+    #[inline(always)]
+    pub(super) fn at<T: Copy>(table: &[T; 256], index: u8) -> T {
+        // SAFETY: `index` is an `u8`, so it is less than the 256 entries of `table`.
+        unsafe { *table.get_unchecked(index as usize) }
+    }
+}
+
 // Operator precedence in Rust:
 // https://doc.rust-lang.org/reference/expressions.html#expression-precedence
 
@@ -125,23 +195,30 @@ macro_rules! round_h_function {
     };
 }
 
+// The key schedule macros use `while` loops and `assert!`, so that they also expand in the
+// `const fn`s such as `expand_key_128`.
+
 /// 128bit key schedule
 macro_rules! key_schedule_128_function {
     ($origin:ident, $subkeys:ident) => {{
-        ::std::assert_eq!($subkeys.len(), N_SUBKEYS_128BIT);
-        for i in 0..4 {
+        ::std::assert!($subkeys.len() == N_SUBKEYS_128BIT);
+        let mut i = 0;
+        while i < 4 {
             $subkeys[i] = four_u8_to_u32!(
                 $origin[4 * i],
                 $origin[4 * i + 1],
                 $origin[4 * i + 2],
                 $origin[4 * i + 3]
             );
+            i += 1;
         }
-        for i in 0..10 {
+        let mut i = 0;
+        while i < 10 {
             $subkeys[4 * i + 4] = $subkeys[4 * i] ^ round_g_function!($subkeys[4 * i + 3], i);
             $subkeys[4 * i + 5] = $subkeys[4 * i + 1] ^ $subkeys[4 * i + 4];
             $subkeys[4 * i + 6] = $subkeys[4 * i + 2] ^ $subkeys[4 * i + 5];
             $subkeys[4 * i + 7] = $subkeys[4 * i + 3] ^ $subkeys[4 * i + 6];
+            i += 1;
         }
     }};
 }
@@ -149,22 +226,26 @@ macro_rules! key_schedule_128_function {
 /// 192bit key schedule
 macro_rules! key_schedule_192_function {
     ($origin:ident, $subkeys:ident) => {{
-        ::std::assert_eq!($subkeys.len(), N_SUBKEYS_192BIT);
-        for i in 0..6 {
+        ::std::assert!($subkeys.len() == N_SUBKEYS_192BIT);
+        let mut i = 0;
+        while i < 6 {
             $subkeys[i] = four_u8_to_u32!(
                 $origin[4 * i],
                 $origin[4 * i + 1],
                 $origin[4 * i + 2],
                 $origin[4 * i + 3]
             );
+            i += 1;
         }
-        for i in 0..7 {
+        let mut i = 0;
+        while i < 7 {
             $subkeys[6 * i + 6] = $subkeys[6 * i] ^ round_g_function!($subkeys[6 * i + 5], i);
             $subkeys[6 * i + 7] = $subkeys[6 * i + 1] ^ $subkeys[6 * i + 6];
             $subkeys[6 * i + 8] = $subkeys[6 * i + 2] ^ $subkeys[6 * i + 7];
             $subkeys[6 * i + 9] = $subkeys[6 * i + 3] ^ $subkeys[6 * i + 8];
             $subkeys[6 * i + 10] = $subkeys[6 * i + 4] ^ $subkeys[6 * i + 9];
             $subkeys[6 * i + 11] = $subkeys[6 * i + 5] ^ $subkeys[6 * i + 10];
+            i += 1;
         }
         $subkeys[48] = $subkeys[42] ^ round_g_function!($subkeys[47], 7);
         $subkeys[49] = $subkeys[43] ^ $subkeys[48];
@@ -176,16 +257,19 @@ macro_rules! key_schedule_192_function {
 /// 256bit key schedule
 macro_rules! key_schedule_256_function {
     ($origin:ident, $subkeys:ident) => {{
-        ::std::assert_eq!($subkeys.len(), N_SUBKEYS_256BIT);
-        for i in 0..8 {
+        ::std::assert!($subkeys.len() == N_SUBKEYS_256BIT);
+        let mut i = 0;
+        while i < 8 {
             $subkeys[i] = four_u8_to_u32!(
                 $origin[4 * i],
                 $origin[4 * i + 1],
                 $origin[4 * i + 2],
                 $origin[4 * i + 3]
             );
+            i += 1;
         }
-        for i in 0..6 {
+        let mut i = 0;
+        while i < 6 {
             $subkeys[8 * i + 8] = $subkeys[8 * i] ^ round_g_function!($subkeys[8 * i + 7], i);
             $subkeys[8 * i + 9] = $subkeys[8 * i + 1] ^ $subkeys[8 * i + 8];
             $subkeys[8 * i + 10] = $subkeys[8 * i + 2] ^ $subkeys[8 * i + 9];
@@ -194,6 +278,7 @@ macro_rules! key_schedule_256_function {
             $subkeys[8 * i + 13] = $subkeys[8 * i + 5] ^ $subkeys[8 * i + 12];
             $subkeys[8 * i + 14] = $subkeys[8 * i + 6] ^ $subkeys[8 * i + 13];
             $subkeys[8 * i + 15] = $subkeys[8 * i + 7] ^ $subkeys[8 * i + 14];
+            i += 1;
         }
         $subkeys[56] = $subkeys[48] ^ round_g_function!($subkeys[55], 6);
         $subkeys[57] = $subkeys[49] ^ $subkeys[56];
@@ -206,11 +291,28 @@ macro_rules! key_schedule_256_function {
 macro_rules! dkey_mixcolumn {
     ($subkeys:ident, $length:expr) => {{
         // The first and the last round don't need the inverse MixColumn transform
-        for i in 4..($length - 4) {
-            $subkeys[i] = TD0[SBOX[usize_b0_of_u32!($subkeys[i])] as usize]
-                ^ TD1[SBOX[usize_b1_of_u32!($subkeys[i])] as usize]
-                ^ TD2[SBOX[usize_b2_of_u32!($subkeys[i])] as usize]
-                ^ TD3[SBOX[usize_b3_of_u32!($subkeys[i])] as usize];
+        let mut i = 4;
+        while i < ($length - 4) {
+            #[cfg(not(any(
+                feature = "fixslice",
+                feature = "small-tables",
+                feature = "compact-decrypt"
+            )))]
+            {
+                $subkeys[i] = TD0[SBOX[usize_b0_of_u32!($subkeys[i])] as usize]
+                    ^ TD1[SBOX[usize_b1_of_u32!($subkeys[i])] as usize]
+                    ^ TD2[SBOX[usize_b2_of_u32!($subkeys[i])] as usize]
+                    ^ TD3[SBOX[usize_b3_of_u32!($subkeys[i])] as usize];
+            }
+            #[cfg(any(
+                feature = "fixslice",
+                feature = "small-tables",
+                feature = "compact-decrypt"
+            ))]
+            {
+                $subkeys[i] = small_tables::inv_mix_column($subkeys[i]);
+            }
+            i += 1;
         }
     }};
 }
@@ -222,94 +324,134 @@ macro_rules! encryption_function {
         ::std::assert_eq!($output.len(), 128 / 8);
         ::std::assert_eq!($input.len(), 128 / 8);
         ::std::assert_eq!($subkeys.len(), $subkeys_length);
-        let mut wa0 = four_u8_to_u32!($input[0], $input[1], $input[2], $input[3]) ^ $subkeys[0];
-        let mut wa1 = four_u8_to_u32!($input[4], $input[5], $input[6], $input[7]) ^ $subkeys[1];
-        let mut wa2 = four_u8_to_u32!($input[8], $input[9], $input[10], $input[11]) ^ $subkeys[2];
-        let mut wa3 = four_u8_to_u32!($input[12], $input[13], $input[14], $input[15]) ^ $subkeys[3];
-        // round 1
-        let mut wb0 = TE0[usize_b0_of_u32!(wa0)]
-            ^ TE1[usize_b1_of_u32!(wa1)]
-            ^ TE2[usize_b2_of_u32!(wa2)]
-            ^ TE3[usize_b3_of_u32!(wa3)]
-            ^ $subkeys[4];
-        let mut wb1 = TE0[usize_b0_of_u32!(wa1)]
-            ^ TE1[usize_b1_of_u32!(wa2)]
-            ^ TE2[usize_b2_of_u32!(wa3)]
-            ^ TE3[usize_b3_of_u32!(wa0)]
-            ^ $subkeys[5];
-        let mut wb2 = TE0[usize_b0_of_u32!(wa2)]
-            ^ TE1[usize_b1_of_u32!(wa3)]
-            ^ TE2[usize_b2_of_u32!(wa0)]
-            ^ TE3[usize_b3_of_u32!(wa1)]
-            ^ $subkeys[6];
-        let mut wb3 = TE0[usize_b0_of_u32!(wa3)]
-            ^ TE1[usize_b1_of_u32!(wa0)]
-            ^ TE2[usize_b2_of_u32!(wa1)]
-            ^ TE3[usize_b3_of_u32!(wa2)]
-            ^ $subkeys[7];
-        // round 2 to round 9 (or 11, 13)
-        for i in 1..$inner_rounds {
-            // even-number rounds
-            wa0 = TE0[usize_b0_of_u32!(wb0)]
-                ^ TE1[usize_b1_of_u32!(wb1)]
-                ^ TE2[usize_b2_of_u32!(wb2)]
-                ^ TE3[usize_b3_of_u32!(wb3)]
-                ^ $subkeys[8 * i];
-            wa1 = TE0[usize_b0_of_u32!(wb1)]
-                ^ TE1[usize_b1_of_u32!(wb2)]
-                ^ TE2[usize_b2_of_u32!(wb3)]
-                ^ TE3[usize_b3_of_u32!(wb0)]
-                ^ $subkeys[8 * i + 1];
-            wa2 = TE0[usize_b0_of_u32!(wb2)]
-                ^ TE1[usize_b1_of_u32!(wb3)]
-                ^ TE2[usize_b2_of_u32!(wb0)]
-                ^ TE3[usize_b3_of_u32!(wb1)]
-                ^ $subkeys[8 * i + 2];
-            wa3 = TE0[usize_b0_of_u32!(wb3)]
-                ^ TE1[usize_b1_of_u32!(wb0)]
-                ^ TE2[usize_b2_of_u32!(wb1)]
-                ^ TE3[usize_b3_of_u32!(wb2)]
-                ^ $subkeys[8 * i + 3];
-            // odd-number rounds
-            wb0 = TE0[usize_b0_of_u32!(wa0)]
-                ^ TE1[usize_b1_of_u32!(wa1)]
-                ^ TE2[usize_b2_of_u32!(wa2)]
-                ^ TE3[usize_b3_of_u32!(wa3)]
-                ^ $subkeys[8 * i + 4];
-            wb1 = TE0[usize_b0_of_u32!(wa1)]
-                ^ TE1[usize_b1_of_u32!(wa2)]
-                ^ TE2[usize_b2_of_u32!(wa3)]
-                ^ TE3[usize_b3_of_u32!(wa0)]
-                ^ $subkeys[8 * i + 5];
-            wb2 = TE0[usize_b0_of_u32!(wa2)]
-                ^ TE1[usize_b1_of_u32!(wa3)]
-                ^ TE2[usize_b2_of_u32!(wa0)]
-                ^ TE3[usize_b3_of_u32!(wa1)]
-                ^ $subkeys[8 * i + 6];
-            wb3 = TE0[usize_b0_of_u32!(wa3)]
-                ^ TE1[usize_b1_of_u32!(wa0)]
-                ^ TE2[usize_b2_of_u32!(wa1)]
-                ^ TE3[usize_b3_of_u32!(wa2)]
-                ^ $subkeys[8 * i + 7];
+        #[cfg(all(feature = "aesni", any(target_arch = "x86", target_arch = "x86_64")))]
+        if super::aesni::available() {
+            let mut block: [u8; BLOCKSIZE_IN_BYTES] = $input[..].try_into().unwrap();
+            super::aesni::encrypt_block(&mut block, $subkeys);
+            $output.copy_from_slice(&block);
+            return;
+        }
+        #[cfg(feature = "fixslice")]
+        {
+            let mut block: [u8; BLOCKSIZE_IN_BYTES] = $input[..].try_into().unwrap();
+            super::fixslice::encrypt_block(&mut block, $subkeys);
+            $output.copy_from_slice(&block);
+        }
+        #[cfg(all(feature = "small-tables", not(feature = "fixslice")))]
+        {
+            let mut block: [u8; BLOCKSIZE_IN_BYTES] = $input[..].try_into().unwrap();
+            small_tables::encrypt_block(&mut block, $subkeys);
+            $output.copy_from_slice(&block);
+        }
+        #[cfg(not(any(feature = "fixslice", feature = "small-tables")))]
+        {
+            let mut wa0 = four_u8_to_u32!($input[0], $input[1], $input[2], $input[3]) ^ $subkeys[0];
+            let mut wa1 = four_u8_to_u32!($input[4], $input[5], $input[6], $input[7]) ^ $subkeys[1];
+            let mut wa2 =
+                four_u8_to_u32!($input[8], $input[9], $input[10], $input[11]) ^ $subkeys[2];
+            let mut wa3 =
+                four_u8_to_u32!($input[12], $input[13], $input[14], $input[15]) ^ $subkeys[3];
+            // round 1
+            let mut wb0 = lookup!(TE0, u8_b0_of_u32!(wa0))
+                ^ lookup!(TE1, u8_b1_of_u32!(wa1))
+                ^ lookup!(TE2, u8_b2_of_u32!(wa2))
+                ^ lookup!(TE3, u8_b3_of_u32!(wa3))
+                ^ $subkeys[4];
+            let mut wb1 = lookup!(TE0, u8_b0_of_u32!(wa1))
+                ^ lookup!(TE1, u8_b1_of_u32!(wa2))
+                ^ lookup!(TE2, u8_b2_of_u32!(wa3))
+                ^ lookup!(TE3, u8_b3_of_u32!(wa0))
+                ^ $subkeys[5];
+            let mut wb2 = lookup!(TE0, u8_b0_of_u32!(wa2))
+                ^ lookup!(TE1, u8_b1_of_u32!(wa3))
+                ^ lookup!(TE2, u8_b2_of_u32!(wa0))
+                ^ lookup!(TE3, u8_b3_of_u32!(wa1))
+                ^ $subkeys[6];
+            let mut wb3 = lookup!(TE0, u8_b0_of_u32!(wa3))
+                ^ lookup!(TE1, u8_b1_of_u32!(wa0))
+                ^ lookup!(TE2, u8_b2_of_u32!(wa1))
+                ^ lookup!(TE3, u8_b3_of_u32!(wa2))
+                ^ $subkeys[7];
+            // round 2 to round 9 (or 11, 13)
+            for i in 1..$inner_rounds {
+                // even-number rounds
+                wa0 = lookup!(TE0, u8_b0_of_u32!(wb0))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wb1))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wb2))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wb3))
+                    ^ $subkeys[8 * i];
+                wa1 = lookup!(TE0, u8_b0_of_u32!(wb1))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wb2))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wb3))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wb0))
+                    ^ $subkeys[8 * i + 1];
+                wa2 = lookup!(TE0, u8_b0_of_u32!(wb2))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wb3))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wb0))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wb1))
+                    ^ $subkeys[8 * i + 2];
+                wa3 = lookup!(TE0, u8_b0_of_u32!(wb3))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wb0))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wb1))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wb2))
+                    ^ $subkeys[8 * i + 3];
+                // odd-number rounds
+                wb0 = lookup!(TE0, u8_b0_of_u32!(wa0))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wa1))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wa2))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wa3))
+                    ^ $subkeys[8 * i + 4];
+                wb1 = lookup!(TE0, u8_b0_of_u32!(wa1))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wa2))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wa3))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wa0))
+                    ^ $subkeys[8 * i + 5];
+                wb2 = lookup!(TE0, u8_b0_of_u32!(wa2))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wa3))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wa0))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wa1))
+                    ^ $subkeys[8 * i + 6];
+                wb3 = lookup!(TE0, u8_b0_of_u32!(wa3))
+                    ^ lookup!(TE1, u8_b1_of_u32!(wa0))
+                    ^ lookup!(TE2, u8_b2_of_u32!(wa1))
+                    ^ lookup!(TE3, u8_b3_of_u32!(wa2))
+                    ^ $subkeys[8 * i + 7];
+            }
+            // final round - no MixColumn
+            // accessing array elements by index in reverse order is faster than in normal order
+            $output[15] =
+                lookup!(SBOX, u8_b3_of_u32!(wb2)) ^ u8_b3_of_u32!($subkeys[$subkeys_length - 1]);
+            $output[14] =
+                lookup!(SBOX, u8_b2_of_u32!(wb1)) ^ u8_b2_of_u32!($subkeys[$subkeys_length - 1]);
+            $output[13] =
+                lookup!(SBOX, u8_b1_of_u32!(wb0)) ^ u8_b1_of_u32!($subkeys[$subkeys_length - 1]);
+            $output[12] =
+                lookup!(SBOX, u8_b0_of_u32!(wb3)) ^ u8_b0_of_u32!($subkeys[$subkeys_length - 1]);
+            $output[11] =
+                lookup!(SBOX, u8_b3_of_u32!(wb1)) ^ u8_b3_of_u32!($subkeys[$subkeys_length - 2]);
+            $output[10] =
+                lookup!(SBOX, u8_b2_of_u32!(wb0)) ^ u8_b2_of_u32!($subkeys[$subkeys_length - 2]);
+            $output[9] =
+                lookup!(SBOX, u8_b1_of_u32!(wb3)) ^ u8_b1_of_u32!($subkeys[$subkeys_length - 2]);
+            $output[8] =
+                lookup!(SBOX, u8_b0_of_u32!(wb2)) ^ u8_b0_of_u32!($subkeys[$subkeys_length - 2]);
+            $output[7] =
+                lookup!(SBOX, u8_b3_of_u32!(wb0)) ^ u8_b3_of_u32!($subkeys[$subkeys_length - 3]);
+            $output[6] =
+                lookup!(SBOX, u8_b2_of_u32!(wb3)) ^ u8_b2_of_u32!($subkeys[$subkeys_length - 3]);
+            $output[5] =
+                lookup!(SBOX, u8_b1_of_u32!(wb2)) ^ u8_b1_of_u32!($subkeys[$subkeys_length - 3]);
+            $output[4] =
+                lookup!(SBOX, u8_b0_of_u32!(wb1)) ^ u8_b0_of_u32!($subkeys[$subkeys_length - 3]);
+            $output[3] =
+                lookup!(SBOX, u8_b3_of_u32!(wb3)) ^ u8_b3_of_u32!($subkeys[$subkeys_length - 4]);
+            $output[2] =
+                lookup!(SBOX, u8_b2_of_u32!(wb2)) ^ u8_b2_of_u32!($subkeys[$subkeys_length - 4]);
+            $output[1] =
+                lookup!(SBOX, u8_b1_of_u32!(wb1)) ^ u8_b1_of_u32!($subkeys[$subkeys_length - 4]);
+            $output[0] =
+                lookup!(SBOX, u8_b0_of_u32!(wb0)) ^ u8_b0_of_u32!($subkeys[$subkeys_length - 4]);
         }
-        // final round - no MixColumn
-        // accessing array elements by index in reverse order is faster than in normal order
-        $output[15] = SBOX[usize_b3_of_u32!(wb2)] ^ u8_b3_of_u32!($subkeys[$subkeys_length - 1]);
-        $output[14] = SBOX[usize_b2_of_u32!(wb1)] ^ u8_b2_of_u32!($subkeys[$subkeys_length - 1]);
-        $output[13] = SBOX[usize_b1_of_u32!(wb0)] ^ u8_b1_of_u32!($subkeys[$subkeys_length - 1]);
-        $output[12] = SBOX[usize_b0_of_u32!(wb3)] ^ u8_b0_of_u32!($subkeys[$subkeys_length - 1]);
-        $output[11] = SBOX[usize_b3_of_u32!(wb1)] ^ u8_b3_of_u32!($subkeys[$subkeys_length - 2]);
-        $output[10] = SBOX[usize_b2_of_u32!(wb0)] ^ u8_b2_of_u32!($subkeys[$subkeys_length - 2]);
-        $output[9] = SBOX[usize_b1_of_u32!(wb3)] ^ u8_b1_of_u32!($subkeys[$subkeys_length - 2]);
-        $output[8] = SBOX[usize_b0_of_u32!(wb2)] ^ u8_b0_of_u32!($subkeys[$subkeys_length - 2]);
-        $output[7] = SBOX[usize_b3_of_u32!(wb0)] ^ u8_b3_of_u32!($subkeys[$subkeys_length - 3]);
-        $output[6] = SBOX[usize_b2_of_u32!(wb3)] ^ u8_b2_of_u32!($subkeys[$subkeys_length - 3]);
-        $output[5] = SBOX[usize_b1_of_u32!(wb2)] ^ u8_b1_of_u32!($subkeys[$subkeys_length - 3]);
-        $output[4] = SBOX[usize_b0_of_u32!(wb1)] ^ u8_b0_of_u32!($subkeys[$subkeys_length - 3]);
-        $output[3] = SBOX[usize_b3_of_u32!(wb3)] ^ u8_b3_of_u32!($subkeys[$subkeys_length - 4]);
-        $output[2] = SBOX[usize_b2_of_u32!(wb2)] ^ u8_b2_of_u32!($subkeys[$subkeys_length - 4]);
-        $output[1] = SBOX[usize_b1_of_u32!(wb1)] ^ u8_b1_of_u32!($subkeys[$subkeys_length - 4]);
-        $output[0] = SBOX[usize_b0_of_u32!(wb0)] ^ u8_b0_of_u32!($subkeys[$subkeys_length - 4]);
     };
 }
 
@@ -320,98 +462,127 @@ macro_rules! decryption_function {
         ::std::assert_eq!($output.len(), 128 / 8);
         ::std::assert_eq!($input.len(), 128 / 8);
         ::std::assert_eq!($subkeys.len(), $subkeys_length);
-        let mut wa0 = four_u8_to_u32!($input[0], $input[1], $input[2], $input[3])
-            ^ $subkeys[$subkeys_length - 4];
-        let mut wa1 = four_u8_to_u32!($input[4], $input[5], $input[6], $input[7])
-            ^ $subkeys[$subkeys_length - 3];
-        let mut wa2 = four_u8_to_u32!($input[8], $input[9], $input[10], $input[11])
-            ^ $subkeys[$subkeys_length - 2];
-        let mut wa3 = four_u8_to_u32!($input[12], $input[13], $input[14], $input[15])
-            ^ $subkeys[$subkeys_length - 1];
-        // round 1
-        let mut wb0 = TD0[usize_b0_of_u32!(wa0)]
-            ^ TD1[usize_b1_of_u32!(wa3)]
-            ^ TD2[usize_b2_of_u32!(wa2)]
-            ^ TD3[usize_b3_of_u32!(wa1)]
-            ^ $subkeys[$subkeys_length - 8];
-        let mut wb1 = TD0[usize_b0_of_u32!(wa1)]
-            ^ TD1[usize_b1_of_u32!(wa0)]
-            ^ TD2[usize_b2_of_u32!(wa3)]
-            ^ TD3[usize_b3_of_u32!(wa2)]
-            ^ $subkeys[$subkeys_length - 7];
-        let mut wb2 = TD0[usize_b0_of_u32!(wa2)]
-            ^ TD1[usize_b1_of_u32!(wa1)]
-            ^ TD2[usize_b2_of_u32!(wa0)]
-            ^ TD3[usize_b3_of_u32!(wa3)]
-            ^ $subkeys[$subkeys_length - 6];
-        let mut wb3 = TD0[usize_b0_of_u32!(wa3)]
-            ^ TD1[usize_b1_of_u32!(wa2)]
-            ^ TD2[usize_b2_of_u32!(wa1)]
-            ^ TD3[usize_b3_of_u32!(wa0)]
-            ^ $subkeys[$subkeys_length - 5];
-        // round 2 to round 9 (or 11, 13)
-        for i in 1..$inner_rounds {
-            // even-number rounds
-            wa0 = TD0[usize_b0_of_u32!(wb0)]
-                ^ TD1[usize_b1_of_u32!(wb3)]
-                ^ TD2[usize_b2_of_u32!(wb2)]
-                ^ TD3[usize_b3_of_u32!(wb1)]
-                ^ $subkeys[$subkeys_length - 4 - (8 * i)];
-            wa1 = TD0[usize_b0_of_u32!(wb1)]
-                ^ TD1[usize_b1_of_u32!(wb0)]
-                ^ TD2[usize_b2_of_u32!(wb3)]
-                ^ TD3[usize_b3_of_u32!(wb2)]
-                ^ $subkeys[$subkeys_length - 3 - (8 * i)];
-            wa2 = TD0[usize_b0_of_u32!(wb2)]
-                ^ TD1[usize_b1_of_u32!(wb1)]
-                ^ TD2[usize_b2_of_u32!(wb0)]
-                ^ TD3[usize_b3_of_u32!(wb3)]
-                ^ $subkeys[$subkeys_length - 2 - (8 * i)];
-            wa3 = TD0[usize_b0_of_u32!(wb3)]
-                ^ TD1[usize_b1_of_u32!(wb2)]
-                ^ TD2[usize_b2_of_u32!(wb1)]
-                ^ TD3[usize_b3_of_u32!(wb0)]
-                ^ $subkeys[$subkeys_length - 1 - (8 * i)];
-            // odd-number rounds
-            wb0 = TD0[usize_b0_of_u32!(wa0)]
-                ^ TD1[usize_b1_of_u32!(wa3)]
-                ^ TD2[usize_b2_of_u32!(wa2)]
-                ^ TD3[usize_b3_of_u32!(wa1)]
-                ^ $subkeys[$subkeys_length - 8 - (8 * i)];
-            wb1 = TD0[usize_b0_of_u32!(wa1)]
-                ^ TD1[usize_b1_of_u32!(wa0)]
-                ^ TD2[usize_b2_of_u32!(wa3)]
-                ^ TD3[usize_b3_of_u32!(wa2)]
-                ^ $subkeys[$subkeys_length - 7 - (8 * i)];
-            wb2 = TD0[usize_b0_of_u32!(wa2)]
-                ^ TD1[usize_b1_of_u32!(wa1)]
-                ^ TD2[usize_b2_of_u32!(wa0)]
-                ^ TD3[usize_b3_of_u32!(wa3)]
-                ^ $subkeys[$subkeys_length - 6 - (8 * i)];
-            wb3 = TD0[usize_b0_of_u32!(wa3)]
-                ^ TD1[usize_b1_of_u32!(wa2)]
-                ^ TD2[usize_b2_of_u32!(wa1)]
-                ^ TD3[usize_b3_of_u32!(wa0)]
-                ^ $subkeys[$subkeys_length - 5 - (8 * i)];
+        #[cfg(all(feature = "aesni", any(target_arch = "x86", target_arch = "x86_64")))]
+        if super::aesni::available() {
+            let mut block: [u8; BLOCKSIZE_IN_BYTES] = $input[..].try_into().unwrap();
+            super::aesni::decrypt_block(&mut block, $subkeys);
+            $output.copy_from_slice(&block);
+            return;
+        }
+        #[cfg(feature = "fixslice")]
+        {
+            let mut block: [u8; BLOCKSIZE_IN_BYTES] = $input[..].try_into().unwrap();
+            super::fixslice::decrypt_block(&mut block, $subkeys);
+            $output.copy_from_slice(&block);
+        }
+        #[cfg(all(
+            any(feature = "small-tables", feature = "compact-decrypt"),
+            not(feature = "fixslice")
+        ))]
+        {
+            let mut block: [u8; BLOCKSIZE_IN_BYTES] = $input[..].try_into().unwrap();
+            small_tables::decrypt_block(&mut block, $subkeys);
+            $output.copy_from_slice(&block);
+        }
+        #[cfg(not(any(
+            feature = "fixslice",
+            feature = "small-tables",
+            feature = "compact-decrypt"
+        )))]
+        {
+            let mut wa0 = four_u8_to_u32!($input[0], $input[1], $input[2], $input[3])
+                ^ $subkeys[$subkeys_length - 4];
+            let mut wa1 = four_u8_to_u32!($input[4], $input[5], $input[6], $input[7])
+                ^ $subkeys[$subkeys_length - 3];
+            let mut wa2 = four_u8_to_u32!($input[8], $input[9], $input[10], $input[11])
+                ^ $subkeys[$subkeys_length - 2];
+            let mut wa3 = four_u8_to_u32!($input[12], $input[13], $input[14], $input[15])
+                ^ $subkeys[$subkeys_length - 1];
+            // round 1
+            let mut wb0 = lookup!(TD0, u8_b0_of_u32!(wa0))
+                ^ lookup!(TD1, u8_b1_of_u32!(wa3))
+                ^ lookup!(TD2, u8_b2_of_u32!(wa2))
+                ^ lookup!(TD3, u8_b3_of_u32!(wa1))
+                ^ $subkeys[$subkeys_length - 8];
+            let mut wb1 = lookup!(TD0, u8_b0_of_u32!(wa1))
+                ^ lookup!(TD1, u8_b1_of_u32!(wa0))
+                ^ lookup!(TD2, u8_b2_of_u32!(wa3))
+                ^ lookup!(TD3, u8_b3_of_u32!(wa2))
+                ^ $subkeys[$subkeys_length - 7];
+            let mut wb2 = lookup!(TD0, u8_b0_of_u32!(wa2))
+                ^ lookup!(TD1, u8_b1_of_u32!(wa1))
+                ^ lookup!(TD2, u8_b2_of_u32!(wa0))
+                ^ lookup!(TD3, u8_b3_of_u32!(wa3))
+                ^ $subkeys[$subkeys_length - 6];
+            let mut wb3 = lookup!(TD0, u8_b0_of_u32!(wa3))
+                ^ lookup!(TD1, u8_b1_of_u32!(wa2))
+                ^ lookup!(TD2, u8_b2_of_u32!(wa1))
+                ^ lookup!(TD3, u8_b3_of_u32!(wa0))
+                ^ $subkeys[$subkeys_length - 5];
+            // round 2 to round 9 (or 11, 13)
+            for i in 1..$inner_rounds {
+                // even-number rounds
+                wa0 = lookup!(TD0, u8_b0_of_u32!(wb0))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wb3))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wb2))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wb1))
+                    ^ $subkeys[$subkeys_length - 4 - (8 * i)];
+                wa1 = lookup!(TD0, u8_b0_of_u32!(wb1))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wb0))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wb3))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wb2))
+                    ^ $subkeys[$subkeys_length - 3 - (8 * i)];
+                wa2 = lookup!(TD0, u8_b0_of_u32!(wb2))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wb1))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wb0))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wb3))
+                    ^ $subkeys[$subkeys_length - 2 - (8 * i)];
+                wa3 = lookup!(TD0, u8_b0_of_u32!(wb3))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wb2))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wb1))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wb0))
+                    ^ $subkeys[$subkeys_length - 1 - (8 * i)];
+                // odd-number rounds
+                wb0 = lookup!(TD0, u8_b0_of_u32!(wa0))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wa3))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wa2))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wa1))
+                    ^ $subkeys[$subkeys_length - 8 - (8 * i)];
+                wb1 = lookup!(TD0, u8_b0_of_u32!(wa1))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wa0))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wa3))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wa2))
+                    ^ $subkeys[$subkeys_length - 7 - (8 * i)];
+                wb2 = lookup!(TD0, u8_b0_of_u32!(wa2))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wa1))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wa0))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wa3))
+                    ^ $subkeys[$subkeys_length - 6 - (8 * i)];
+                wb3 = lookup!(TD0, u8_b0_of_u32!(wa3))
+                    ^ lookup!(TD1, u8_b1_of_u32!(wa2))
+                    ^ lookup!(TD2, u8_b2_of_u32!(wa1))
+                    ^ lookup!(TD3, u8_b3_of_u32!(wa0))
+                    ^ $subkeys[$subkeys_length - 5 - (8 * i)];
+            }
+            // final round
+            // accessing array elements by index in reverse order is faster than in normal order
+            $output[15] = lookup!(SINV, u8_b3_of_u32!(wb0)) ^ u8_b3_of_u32!($subkeys[3]);
+            $output[14] = lookup!(SINV, u8_b2_of_u32!(wb1)) ^ u8_b2_of_u32!($subkeys[3]);
+            $output[13] = lookup!(SINV, u8_b1_of_u32!(wb2)) ^ u8_b1_of_u32!($subkeys[3]);
+            $output[12] = lookup!(SINV, u8_b0_of_u32!(wb3)) ^ u8_b0_of_u32!($subkeys[3]);
+            $output[11] = lookup!(SINV, u8_b3_of_u32!(wb3)) ^ u8_b3_of_u32!($subkeys[2]);
+            $output[10] = lookup!(SINV, u8_b2_of_u32!(wb0)) ^ u8_b2_of_u32!($subkeys[2]);
+            $output[9] = lookup!(SINV, u8_b1_of_u32!(wb1)) ^ u8_b1_of_u32!($subkeys[2]);
+            $output[8] = lookup!(SINV, u8_b0_of_u32!(wb2)) ^ u8_b0_of_u32!($subkeys[2]);
+            $output[7] = lookup!(SINV, u8_b3_of_u32!(wb2)) ^ u8_b3_of_u32!($subkeys[1]);
+            $output[6] = lookup!(SINV, u8_b2_of_u32!(wb3)) ^ u8_b2_of_u32!($subkeys[1]);
+            $output[5] = lookup!(SINV, u8_b1_of_u32!(wb0)) ^ u8_b1_of_u32!($subkeys[1]);
+            $output[4] = lookup!(SINV, u8_b0_of_u32!(wb1)) ^ u8_b0_of_u32!($subkeys[1]);
+            $output[3] = lookup!(SINV, u8_b3_of_u32!(wb1)) ^ u8_b3_of_u32!($subkeys[0]);
+            $output[2] = lookup!(SINV, u8_b2_of_u32!(wb2)) ^ u8_b2_of_u32!($subkeys[0]);
+            $output[1] = lookup!(SINV, u8_b1_of_u32!(wb3)) ^ u8_b1_of_u32!($subkeys[0]);
+            $output[0] = lookup!(SINV, u8_b0_of_u32!(wb0)) ^ u8_b0_of_u32!($subkeys[0]);
         }
-        // final round
-        // accessing array elements by index in reverse order is faster than in normal order
-        $output[15] = SINV[usize_b3_of_u32!(wb0)] ^ u8_b3_of_u32!($subkeys[3]);
-        $output[14] = SINV[usize_b2_of_u32!(wb1)] ^ u8_b2_of_u32!($subkeys[3]);
-        $output[13] = SINV[usize_b1_of_u32!(wb2)] ^ u8_b1_of_u32!($subkeys[3]);
-        $output[12] = SINV[usize_b0_of_u32!(wb3)] ^ u8_b0_of_u32!($subkeys[3]);
-        $output[11] = SINV[usize_b3_of_u32!(wb3)] ^ u8_b3_of_u32!($subkeys[2]);
-        $output[10] = SINV[usize_b2_of_u32!(wb0)] ^ u8_b2_of_u32!($subkeys[2]);
-        $output[9] = SINV[usize_b1_of_u32!(wb1)] ^ u8_b1_of_u32!($subkeys[2]);
-        $output[8] = SINV[usize_b0_of_u32!(wb2)] ^ u8_b0_of_u32!($subkeys[2]);
-        $output[7] = SINV[usize_b3_of_u32!(wb2)] ^ u8_b3_of_u32!($subkeys[1]);
-        $output[6] = SINV[usize_b2_of_u32!(wb3)] ^ u8_b2_of_u32!($subkeys[1]);
-        $output[5] = SINV[usize_b1_of_u32!(wb0)] ^ u8_b1_of_u32!($subkeys[1]);
-        $output[4] = SINV[usize_b0_of_u32!(wb1)] ^ u8_b0_of_u32!($subkeys[1]);
-        $output[3] = SINV[usize_b3_of_u32!(wb1)] ^ u8_b3_of_u32!($subkeys[0]);
-        $output[2] = SINV[usize_b2_of_u32!(wb2)] ^ u8_b2_of_u32!($subkeys[0]);
-        $output[1] = SINV[usize_b1_of_u32!(wb3)] ^ u8_b1_of_u32!($subkeys[0]);
-        $output[0] = SINV[usize_b0_of_u32!(wb0)] ^ u8_b0_of_u32!($subkeys[0]);
     }};
 }
 
@@ -658,6 +829,808 @@ pub fn key_schedule_decrypt256(origin: &[u8], buffer: &mut [u32]) {
     dkey_mixcolumn!(buffer, N_SUBKEYS_256BIT);
 }
 
+/// Schedule a key to sub-keys for both **encryption** and **decryption** in one pass, with
+/// **auto-selected** key-size.
+/// * *parameter* `origin`: the slice that contains original key.
+/// * *parameter* `encryption`: the buffer to store the sub-keys for encryption.
+/// * *parameter* `decryption`: the buffer to store the sub-keys for decryption.
+///
+/// The parameters must possess elements of the following amounts:
+///
+/// key-size | `origin` | `encryption` | `decryption`
+/// - | - | - | -
+/// 128bit | 16 | 44 | 44
+/// 192bit | 24 | 52 | 52
+/// 256bit | 32 | 60 | 60
+///
+/// The key is expanded only once, and the sub-keys for decryption are derived from the ones for
+/// encryption. This is faster than calling [`key_schedule_encrypt_auto`] and
+/// [`key_schedule_decrypt_auto`], which expand the key twice.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{key_schedule_decrypt_auto, key_schedule_encrypt_decrypt_auto};
+/// use aes_frast::{KEY_BYTES_256BIT, N_SUBKEYS_256BIT};
+///
+/// let origin_key = [0x5Au8; KEY_BYTES_256BIT];
+/// let mut e_subkeys: [u32; N_SUBKEYS_256BIT] = [0; N_SUBKEYS_256BIT];
+/// let mut d_subkeys: [u32; N_SUBKEYS_256BIT] = [0; N_SUBKEYS_256BIT];
+/// key_schedule_encrypt_decrypt_auto(&origin_key, &mut e_subkeys, &mut d_subkeys);
+///
+/// let mut expected: [u32; N_SUBKEYS_256BIT] = [0; N_SUBKEYS_256BIT];
+/// key_schedule_decrypt_auto(&origin_key, &mut expected);
+/// assert_eq!(d_subkeys, expected);
+/// ```
+///
+/// [`key_schedule_encrypt_auto`]: ../aes_core/fn.key_schedule_encrypt_auto.html
+/// [`key_schedule_decrypt_auto`]: ../aes_core/fn.key_schedule_decrypt_auto.html
+pub fn key_schedule_encrypt_decrypt_auto(
+    origin: &[u8],
+    encryption: &mut [u32],
+    decryption: &mut [u32],
+) {
+    match origin.len() {
+        KEY_BYTES_128BIT => key_schedule_encrypt_decrypt128(origin, encryption, decryption),
+        KEY_BYTES_192BIT => key_schedule_encrypt_decrypt192(origin, encryption, decryption),
+        KEY_BYTES_256BIT => key_schedule_encrypt_decrypt256(origin, encryption, decryption),
+        _ => panic!("Invalid key length."),
+    }
+}
+
+/// The number of sub-keys of a key of `key_len` bytes.
+fn subkeys_len(key_len: usize) -> Result<usize, AesError> {
+    match key_len {
+        KEY_BYTES_128BIT => Ok(N_SUBKEYS_128BIT),
+        KEY_BYTES_192BIT => Ok(N_SUBKEYS_192BIT),
+        KEY_BYTES_256BIT => Ok(N_SUBKEYS_256BIT),
+        _ => Err(AesError::InvalidKeyLength),
+    }
+}
+
+/// The fallible version of [`key_schedule_encrypt_auto`].
+///
+/// * *return* : `Err(AesError::InvalidKeyLength)` if `origin` is not 16, 24 or 32 bytes long,
+///   `Err(AesError::InvalidParameter)` if `buffer` doesn't have the matching number of words.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::try_key_schedule_encrypt_auto;
+/// use aes_frast::{AesError, N_SUBKEYS_128BIT};
+///
+/// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+/// assert_eq!(try_key_schedule_encrypt_auto(&[0x2Bu8; 16], &mut subkeys), Ok(()));
+/// assert_eq!(try_key_schedule_encrypt_auto(&[0x2Bu8; 15], &mut subkeys),
+///            Err(AesError::InvalidKeyLength));
+/// assert_eq!(try_key_schedule_encrypt_auto(&[0x2Bu8; 32], &mut subkeys),
+///            Err(AesError::InvalidParameter));
+/// ```
+///
+/// [`key_schedule_encrypt_auto`]: ../aes_core/fn.key_schedule_encrypt_auto.html
+pub fn try_key_schedule_encrypt_auto(origin: &[u8], buffer: &mut [u32]) -> Result<(), AesError> {
+    if buffer.len() != subkeys_len(origin.len())? {
+        return Err(AesError::InvalidParameter);
+    }
+    key_schedule_encrypt_auto(origin, buffer);
+    Ok(())
+}
+
+/// The fallible version of [`key_schedule_decrypt_auto`].
+///
+/// * *return* : the same errors as [`try_key_schedule_encrypt_auto`].
+///
+/// [`key_schedule_decrypt_auto`]: ../aes_core/fn.key_schedule_decrypt_auto.html
+/// [`try_key_schedule_encrypt_auto`]: ../aes_core/fn.try_key_schedule_encrypt_auto.html
+pub fn try_key_schedule_decrypt_auto(origin: &[u8], buffer: &mut [u32]) -> Result<(), AesError> {
+    if buffer.len() != subkeys_len(origin.len())? {
+        return Err(AesError::InvalidParameter);
+    }
+    key_schedule_decrypt_auto(origin, buffer);
+    Ok(())
+}
+
+/// The fallible version of [`key_schedule_encrypt_decrypt_auto`].
+///
+/// * *return* : the same errors as [`try_key_schedule_encrypt_auto`], where both buffers must
+///   have the matching number of words.
+///
+/// [`key_schedule_encrypt_decrypt_auto`]: ../aes_core/fn.key_schedule_encrypt_decrypt_auto.html
+/// [`try_key_schedule_encrypt_auto`]: ../aes_core/fn.try_key_schedule_encrypt_auto.html
+pub fn try_key_schedule_encrypt_decrypt_auto(
+    origin: &[u8],
+    encryption: &mut [u32],
+    decryption: &mut [u32],
+) -> Result<(), AesError> {
+    let n = subkeys_len(origin.len())?;
+    if encryption.len() != n || decryption.len() != n {
+        return Err(AesError::InvalidParameter);
+    }
+    key_schedule_encrypt_decrypt_auto(origin, encryption, decryption);
+    Ok(())
+}
+
+/// Schedule a **128bit key** to sub-keys for both **encryption** and **decryption** in one pass.
+///
+/// * *parameter* `origin`: the slice (length = 16) that contains original key.
+/// * *parameter* `encryption`: the buffer (length = 44) to store the sub-keys for encryption.
+/// * *parameter* `decryption`: the buffer (length = 44) to store the sub-keys for decryption.
+/// # Examples
+/// Please refer to [`key_schedule_encrypt_decrypt_auto`] function, they are very similar.
+///
+/// [`key_schedule_encrypt_decrypt_auto`]: ../aes_core/fn.key_schedule_encrypt_decrypt_auto.html
+pub fn key_schedule_encrypt_decrypt128(
+    origin: &[u8],
+    encryption: &mut [u32],
+    decryption: &mut [u32],
+) {
+    assert_eq!(origin.len(), KEY_BYTES_128BIT);
+    key_schedule_128_function!(origin, encryption);
+    decryption.copy_from_slice(encryption);
+    dkey_mixcolumn!(decryption, N_SUBKEYS_128BIT);
+}
+
+/// Schedule a **192bit key** to sub-keys for both **encryption** and **decryption** in one pass.
+///
+/// * *parameter* `origin`: the slice (length = 24) that contains original key.
+/// * *parameter* `encryption`: the buffer (length = 52) to store the sub-keys for encryption.
+/// * *parameter* `decryption`: the buffer (length = 52) to store the sub-keys for decryption.
+/// # Examples
+/// Please refer to [`key_schedule_encrypt_decrypt_auto`] function, they are very similar.
+///
+/// [`key_schedule_encrypt_decrypt_auto`]: ../aes_core/fn.key_schedule_encrypt_decrypt_auto.html
+pub fn key_schedule_encrypt_decrypt192(
+    origin: &[u8],
+    encryption: &mut [u32],
+    decryption: &mut [u32],
+) {
+    assert_eq!(origin.len(), KEY_BYTES_192BIT);
+    key_schedule_192_function!(origin, encryption);
+    decryption.copy_from_slice(encryption);
+    dkey_mixcolumn!(decryption, N_SUBKEYS_192BIT);
+}
+
+/// Schedule a **256bit key** to sub-keys for both **encryption** and **decryption** in one pass.
+///
+/// * *parameter* `origin`: the slice (length = 32) that contains original key.
+/// * *parameter* `encryption`: the buffer (length = 60) to store the sub-keys for encryption.
+/// * *parameter* `decryption`: the buffer (length = 60) to store the sub-keys for decryption.
+/// # Examples
+/// Please refer to [`key_schedule_encrypt_decrypt_auto`] function, they are very similar.
+///
+/// [`key_schedule_encrypt_decrypt_auto`]: ../aes_core/fn.key_schedule_encrypt_decrypt_auto.html
+pub fn key_schedule_encrypt_decrypt256(
+    origin: &[u8],
+    encryption: &mut [u32],
+    decryption: &mut [u32],
+) {
+    assert_eq!(origin.len(), KEY_BYTES_256BIT);
+    key_schedule_256_function!(origin, encryption);
+    decryption.copy_from_slice(encryption);
+    dkey_mixcolumn!(decryption, N_SUBKEYS_256BIT);
+}
+
+macro_rules! define_typed_key_schedules {
+    ($(
+        $(#[$attr:meta])*
+        $bits:literal, $key:ident, $n_subkeys:ident, $schedule:ident:
+        $encrypt:ident, $decrypt:ident, $encrypt_decrypt:ident, $untyped:ident;
+    )*) => {
+        $(
+            #[doc = concat!("Schedule a **", $bits, "bit key** to sub-keys for **encryption**, with")]
+            /// the lengths checked at compile time.
+            ///
+            #[doc = concat!("The same as [`", stringify!($untyped), "`], but a key or a buffer of")]
+            /// the wrong length is a compile error instead of a panic.
+            /// * *parameter* `origin`: the original key.
+            /// * *parameter* `buffer`: the buffer to store the sub-keys.
+            $(#[$attr])*
+            ///
+            #[doc = concat!("[`", stringify!($untyped), "`]: ../aes_core/fn.", stringify!($untyped), ".html")]
+            pub fn $encrypt(origin: &$key, buffer: &mut [u32; $n_subkeys]) {
+                let origin = origin.as_bytes();
+                $schedule!(origin, buffer);
+            }
+
+            #[doc = concat!("Schedule a **", $bits, "bit key** to sub-keys for **decryption**, with")]
+            /// the lengths checked at compile time.
+            /// * *parameter* `origin`: the original key.
+            /// * *parameter* `buffer`: the buffer to store the sub-keys.
+            pub fn $decrypt(origin: &$key, buffer: &mut [u32; $n_subkeys]) {
+                let origin = origin.as_bytes();
+                $schedule!(origin, buffer);
+                dkey_mixcolumn!(buffer, $n_subkeys);
+            }
+
+            #[doc = concat!("Schedule a **", $bits, "bit key** to sub-keys for both **encryption** and")]
+            /// **decryption** in one pass, with the lengths checked at compile time.
+            /// * *parameter* `origin`: the original key.
+            /// * *parameter* `encryption`: the buffer to store the sub-keys for encryption.
+            /// * *parameter* `decryption`: the buffer to store the sub-keys for decryption.
+            pub fn $encrypt_decrypt(
+                origin: &$key,
+                encryption: &mut [u32; $n_subkeys],
+                decryption: &mut [u32; $n_subkeys],
+            ) {
+                let origin = origin.as_bytes();
+                $schedule!(origin, encryption);
+                decryption.copy_from_slice(encryption);
+                dkey_mixcolumn!(decryption, $n_subkeys);
+            }
+        )*
+    };
+}
+
+define_typed_key_schedules! {
+    /// # Examples
+    /// ```
+    /// use aes_frast::aes_core::{key_schedule_encrypt128, key_schedule_encrypt128_typed};
+    /// use aes_frast::key::Key128;
+    /// use aes_frast::N_SUBKEYS_128BIT;
+    ///
+    /// let key = Key128::from([0x2Bu8; 16]);
+    /// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+    /// key_schedule_encrypt128_typed(&key, &mut subkeys);
+    ///
+    /// let mut expected = [0u32; N_SUBKEYS_128BIT];
+    /// key_schedule_encrypt128(key.as_bytes(), &mut expected);
+    /// assert_eq!(subkeys, expected);
+    /// ```
+    ///
+    /// A buffer of the wrong length doesn't compile:
+    /// ```compile_fail
+    /// use aes_frast::aes_core::key_schedule_encrypt128_typed;
+    /// use aes_frast::key::Key128;
+    /// use aes_frast::N_SUBKEYS_192BIT;
+    ///
+    /// let mut subkeys = [0u32; N_SUBKEYS_192BIT];
+    /// key_schedule_encrypt128_typed(&Key128::from([0u8; 16]), &mut subkeys);
+    /// ```
+    "128", Key128, N_SUBKEYS_128BIT, key_schedule_128_function:
+        key_schedule_encrypt128_typed, key_schedule_decrypt128_typed,
+        key_schedule_encrypt_decrypt128_typed, key_schedule_encrypt128;
+    /// # Examples
+    /// Please refer to [`key_schedule_encrypt128_typed`] function, they are very similar.
+    ///
+    /// [`key_schedule_encrypt128_typed`]: ../aes_core/fn.key_schedule_encrypt128_typed.html
+    "192", Key192, N_SUBKEYS_192BIT, key_schedule_192_function:
+        key_schedule_encrypt192_typed, key_schedule_decrypt192_typed,
+        key_schedule_encrypt_decrypt192_typed, key_schedule_encrypt192;
+    /// # Examples
+    /// Please refer to [`key_schedule_encrypt128_typed`] function, they are very similar.
+    ///
+    /// [`key_schedule_encrypt128_typed`]: ../aes_core/fn.key_schedule_encrypt128_typed.html
+    "256", Key256, N_SUBKEYS_256BIT, key_schedule_256_function:
+        key_schedule_encrypt256_typed, key_schedule_decrypt256_typed,
+        key_schedule_encrypt_decrypt256_typed, key_schedule_encrypt256;
+}
+
+/// Schedule a **128bit key** to sub-keys for **encryption** at compile time.
+///
+/// * *parameter* `origin`: the original key.
+/// * *return* : the sub-keys, the same as [`key_schedule_encrypt128`] stores.
+///
+/// This is a `const fn`, so the sub-keys of a fixed key can be computed by the compiler and
+/// placed in flash with the rest of the read-only data.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{block_encrypt128, expand_key_128, key_schedule_encrypt128};
+/// use aes_frast::{KEY_BYTES_128BIT, N_SUBKEYS_128BIT};
+///
+/// const KEY: [u8; KEY_BYTES_128BIT] = [0x2Bu8; KEY_BYTES_128BIT];
+/// const KEYS: [u32; N_SUBKEYS_128BIT] = expand_key_128(&KEY);
+///
+/// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+/// key_schedule_encrypt128(&KEY, &mut subkeys);
+/// assert_eq!(KEYS, subkeys);
+///
+/// let mut output = [0u8; 16];
+/// block_encrypt128(&[0u8; 16], &mut output, &KEYS);
+/// ```
+///
+/// [`key_schedule_encrypt128`]: ../aes_core/fn.key_schedule_encrypt128.html
+pub const fn expand_key_128(origin: &[u8; KEY_BYTES_128BIT]) -> [u32; N_SUBKEYS_128BIT] {
+    let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+    key_schedule_128_function!(origin, subkeys);
+    subkeys
+}
+
+/// Schedule a **192bit key** to sub-keys for **encryption** at compile time.
+///
+/// * *parameter* `origin`: the original key.
+/// * *return* : the sub-keys, the same as [`key_schedule_encrypt192`] stores.
+/// # Examples
+/// Please refer to [`expand_key_128`] function, they are very similar.
+///
+/// [`key_schedule_encrypt192`]: ../aes_core/fn.key_schedule_encrypt192.html
+/// [`expand_key_128`]: ../aes_core/fn.expand_key_128.html
+pub const fn expand_key_192(origin: &[u8; KEY_BYTES_192BIT]) -> [u32; N_SUBKEYS_192BIT] {
+    let mut subkeys = [0u32; N_SUBKEYS_192BIT];
+    key_schedule_192_function!(origin, subkeys);
+    subkeys
+}
+
+/// Schedule a **256bit key** to sub-keys for **encryption** at compile time.
+///
+/// * *parameter* `origin`: the original key.
+/// * *return* : the sub-keys, the same as [`key_schedule_encrypt256`] stores.
+/// # Examples
+/// Please refer to [`expand_key_128`] function, they are very similar.
+///
+/// [`key_schedule_encrypt256`]: ../aes_core/fn.key_schedule_encrypt256.html
+/// [`expand_key_128`]: ../aes_core/fn.expand_key_128.html
+pub const fn expand_key_256(origin: &[u8; KEY_BYTES_256BIT]) -> [u32; N_SUBKEYS_256BIT] {
+    let mut subkeys = [0u32; N_SUBKEYS_256BIT];
+    key_schedule_256_function!(origin, subkeys);
+    subkeys
+}
+
+/// Schedule a **128bit key** to sub-keys for **decryption** at compile time.
+///
+/// * *parameter* `origin`: the original key.
+/// * *return* : the sub-keys, the same as [`key_schedule_decrypt128`] stores.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{block_decrypt128, expand_key_128, expand_key_decrypt_128};
+/// use aes_frast::aes_core::block_encrypt128;
+/// use aes_frast::{KEY_BYTES_128BIT, N_SUBKEYS_128BIT};
+///
+/// const KEY: [u8; KEY_BYTES_128BIT] = [0x2Bu8; KEY_BYTES_128BIT];
+/// const E_KEYS: [u32; N_SUBKEYS_128BIT] = expand_key_128(&KEY);
+/// const D_KEYS: [u32; N_SUBKEYS_128BIT] = expand_key_decrypt_128(&KEY);
+///
+/// let mut ciphertext = [0u8; 16];
+/// let mut plaintext = [0u8; 16];
+/// block_encrypt128(&[0x5Au8; 16], &mut ciphertext, &E_KEYS);
+/// block_decrypt128(&ciphertext, &mut plaintext, &D_KEYS);
+/// assert_eq!(plaintext, [0x5Au8; 16]);
+/// ```
+///
+/// [`key_schedule_decrypt128`]: ../aes_core/fn.key_schedule_decrypt128.html
+pub const fn expand_key_decrypt_128(origin: &[u8; KEY_BYTES_128BIT]) -> [u32; N_SUBKEYS_128BIT] {
+    let mut subkeys = expand_key_128(origin);
+    dkey_mixcolumn!(subkeys, N_SUBKEYS_128BIT);
+    subkeys
+}
+
+/// Schedule a **192bit key** to sub-keys for **decryption** at compile time.
+///
+/// * *parameter* `origin`: the original key.
+/// * *return* : the sub-keys, the same as [`key_schedule_decrypt192`] stores.
+///
+/// [`key_schedule_decrypt192`]: ../aes_core/fn.key_schedule_decrypt192.html
+pub const fn expand_key_decrypt_192(origin: &[u8; KEY_BYTES_192BIT]) -> [u32; N_SUBKEYS_192BIT] {
+    let mut subkeys = expand_key_192(origin);
+    dkey_mixcolumn!(subkeys, N_SUBKEYS_192BIT);
+    subkeys
+}
+
+/// Schedule a **256bit key** to sub-keys for **decryption** at compile time.
+///
+/// * *parameter* `origin`: the original key.
+/// * *return* : the sub-keys, the same as [`key_schedule_decrypt256`] stores.
+///
+/// [`key_schedule_decrypt256`]: ../aes_core/fn.key_schedule_decrypt256.html
+pub const fn expand_key_decrypt_256(origin: &[u8; KEY_BYTES_256BIT]) -> [u32; N_SUBKEYS_256BIT] {
+    let mut subkeys = expand_key_256(origin);
+    dkey_mixcolumn!(subkeys, N_SUBKEYS_256BIT);
+    subkeys
+}
+
+/// Transform the sub-keys for encryption into the sub-keys for decryption in place.
+pub(crate) fn subkeys_encrypt_to_decrypt(buffer: &mut [u32]) {
+    match buffer.len() {
+        N_SUBKEYS_128BIT => dkey_mixcolumn!(buffer, N_SUBKEYS_128BIT),
+        N_SUBKEYS_192BIT => dkey_mixcolumn!(buffer, N_SUBKEYS_192BIT),
+        N_SUBKEYS_256BIT => dkey_mixcolumn!(buffer, N_SUBKEYS_256BIT),
+        _ => panic!("Invalid sub-keys length."),
+    }
+}
+
+/// Convert the sub-keys for **encryption** into the sub-keys for **decryption** with
+/// **auto-selected** key-size.
+///
+/// * *parameter* `encryption`: the slice (length = 44, 52 or 60) that contains the sub-keys for
+///   encryption.
+/// * *parameter* `buffer`: the buffer (the same length as `encryption`) to store the sub-keys for
+///   decryption.
+///
+/// The inverse MixColumn is applied to the sub-keys of the middle rounds, so the original key is
+/// not needed: it gives the same sub-keys as [`key_schedule_decrypt_auto`] on that key.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{
+///     key_schedule_convert_enc_to_dec_auto, key_schedule_decrypt_auto, key_schedule_encrypt_auto,
+/// };
+/// use aes_frast::N_SUBKEYS_192BIT;
+///
+/// let origin_key = [0x8Eu8; 24];
+/// let mut encryption = [0u32; N_SUBKEYS_192BIT];
+/// let mut decryption = [0u32; N_SUBKEYS_192BIT];
+/// let mut expected = [0u32; N_SUBKEYS_192BIT];
+///
+/// key_schedule_encrypt_auto(&origin_key, &mut encryption);
+/// key_schedule_convert_enc_to_dec_auto(&encryption, &mut decryption);
+/// key_schedule_decrypt_auto(&origin_key, &mut expected);
+/// assert_eq!(decryption, expected);
+/// ```
+/// # Panics
+/// This function panics if the length of `encryption` is not 44, 52 or 60, or if the length of
+/// `buffer` is different.
+///
+/// [`key_schedule_decrypt_auto`]: ../aes_core/fn.key_schedule_decrypt_auto.html
+pub fn key_schedule_convert_enc_to_dec_auto(encryption: &[u32], buffer: &mut [u32]) {
+    assert_eq!(buffer.len(), encryption.len());
+    buffer.copy_from_slice(encryption);
+    subkeys_encrypt_to_decrypt(buffer);
+}
+
+/// Convert the sub-keys for **encryption** from a **128bit key** into the sub-keys for
+/// **decryption**.
+///
+/// * *parameter* `encryption`: the slice (length = 44) that contains the sub-keys for encryption.
+/// * *parameter* `buffer`: the buffer (length = 44) to store the sub-keys for decryption.
+/// # Examples
+/// Please refer to [`key_schedule_convert_enc_to_dec_auto`] function, they are very similar.
+///
+/// [`key_schedule_convert_enc_to_dec_auto`]: ../aes_core/fn.key_schedule_convert_enc_to_dec_auto.html
+pub fn key_schedule_convert_enc_to_dec128(encryption: &[u32], buffer: &mut [u32]) {
+    assert_eq!(encryption.len(), N_SUBKEYS_128BIT);
+    buffer.copy_from_slice(encryption);
+    dkey_mixcolumn!(buffer, N_SUBKEYS_128BIT);
+}
+
+/// Convert the sub-keys for **encryption** from a **192bit key** into the sub-keys for
+/// **decryption**.
+///
+/// * *parameter* `encryption`: the slice (length = 52) that contains the sub-keys for encryption.
+/// * *parameter* `buffer`: the buffer (length = 52) to store the sub-keys for decryption.
+/// # Examples
+/// Please refer to [`key_schedule_convert_enc_to_dec_auto`] function, they are very similar.
+///
+/// [`key_schedule_convert_enc_to_dec_auto`]: ../aes_core/fn.key_schedule_convert_enc_to_dec_auto.html
+pub fn key_schedule_convert_enc_to_dec192(encryption: &[u32], buffer: &mut [u32]) {
+    assert_eq!(encryption.len(), N_SUBKEYS_192BIT);
+    buffer.copy_from_slice(encryption);
+    dkey_mixcolumn!(buffer, N_SUBKEYS_192BIT);
+}
+
+/// Convert the sub-keys for **encryption** from a **256bit key** into the sub-keys for
+/// **decryption**.
+///
+/// * *parameter* `encryption`: the slice (length = 60) that contains the sub-keys for encryption.
+/// * *parameter* `buffer`: the buffer (length = 60) to store the sub-keys for decryption.
+/// # Examples
+/// Please refer to [`key_schedule_convert_enc_to_dec_auto`] function, they are very similar.
+///
+/// [`key_schedule_convert_enc_to_dec_auto`]: ../aes_core/fn.key_schedule_convert_enc_to_dec_auto.html
+pub fn key_schedule_convert_enc_to_dec256(encryption: &[u32], buffer: &mut [u32]) {
+    assert_eq!(encryption.len(), N_SUBKEYS_256BIT);
+    buffer.copy_from_slice(encryption);
+    dkey_mixcolumn!(buffer, N_SUBKEYS_256BIT);
+}
+
+/// The fallible version of [`key_schedule_convert_enc_to_dec_auto`].
+///
+/// * *return* : `Err(AesError::InvalidKeyLength)` if the length of `encryption` is not 44, 52 or
+///   60, or `Err(AesError::InvalidParameter)` if the length of `buffer` is different.
+///
+/// [`key_schedule_convert_enc_to_dec_auto`]: ../aes_core/fn.key_schedule_convert_enc_to_dec_auto.html
+pub fn try_key_schedule_convert_enc_to_dec_auto(
+    encryption: &[u32],
+    buffer: &mut [u32],
+) -> Result<(), AesError> {
+    if !matches!(
+        encryption.len(),
+        N_SUBKEYS_128BIT | N_SUBKEYS_192BIT | N_SUBKEYS_256BIT
+    ) {
+        return Err(AesError::InvalidKeyLength);
+    }
+    if buffer.len() != encryption.len() {
+        return Err(AesError::InvalidParameter);
+    }
+    key_schedule_convert_enc_to_dec_auto(encryption, buffer);
+    Ok(())
+}
+
+/// Convert scheduled sub-keys into words packed in **big-endian** order.
+///
+/// * *parameter* `subkeys`: the slice (length = 44, 52 or 60) that contains the sub-keys.
+/// * *parameter* `buffer`: the buffer (the same length as `subkeys`) to store the words.
+///
+/// The words are in the notation of NIST.FIPS.197, see the [module documentation].
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{key_schedule_encrypt128, subkeys_to_be_words};
+/// use aes_frast::{KEY_BYTES_128BIT, N_SUBKEYS_128BIT};
+///
+/// // This example key came from NIST.FIPS.197 Appendix A.1
+/// let origin_key: [u8; KEY_BYTES_128BIT] = [
+///     0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///     0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C,
+/// ];
+/// let mut subkeys: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+/// key_schedule_encrypt128(&origin_key, &mut subkeys);
+/// assert_eq!(subkeys[0], 0x16157E2B);
+///
+/// let mut words: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+/// subkeys_to_be_words(&subkeys, &mut words);
+/// assert_eq!(words[0], 0x2B7E1516);
+/// assert_eq!(words[43], 0xB6630CA6);
+/// ```
+///
+/// [module documentation]: ../aes_core/index.html#sub-keys-word-packing
+pub fn subkeys_to_be_words(subkeys: &[u32], buffer: &mut [u32]) {
+    assert_eq!(subkeys.len(), buffer.len());
+    for (word, subkey) in buffer.iter_mut().zip(subkeys.iter()) {
+        *word = subkey.swap_bytes();
+    }
+}
+
+/// Convert words packed in **big-endian** order into sub-keys for this library.
+///
+/// * *parameter* `words`: the slice (length = 44, 52 or 60) that contains the words.
+/// * *parameter* `buffer`: the buffer (the same length as `words`) to store the sub-keys.
+///
+/// This is the inverse of [`subkeys_to_be_words`].
+///
+/// [`subkeys_to_be_words`]: ../aes_core/fn.subkeys_to_be_words.html
+pub fn subkeys_from_be_words(words: &[u32], buffer: &mut [u32]) {
+    assert_eq!(words.len(), buffer.len());
+    for (subkey, word) in buffer.iter_mut().zip(words.iter()) {
+        *subkey = word.swap_bytes();
+    }
+}
+
+/// Export scheduled sub-keys as 16-byte round keys.
+///
+/// The sub-keys are packed into `u32` words in little-endian order, i.e. the first byte of a
+/// word is its least significant byte. This function writes them out as bytes, so the round
+/// keys are in the byte order of NIST.FIPS.197, which is also what hardware AES engines expect.
+///
+/// * *parameter* `subkeys`: the slice (length = 44, 52 or 60) that contains the sub-keys.
+/// * *parameter* `round_keys`: the buffer (length = 11, 13 or 15) to store the round keys.
+///
+/// Note that the sub-keys for **decryption** have been transformed by the inverse MixColumn
+/// (except the first and the last round), so the round keys exported from them are the ones of
+/// the *equivalent inverse cipher*. Export the sub-keys for **encryption** if the hardware runs
+/// the inverse cipher itself.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{key_schedule_encrypt128, subkeys_to_round_keys};
+/// use aes_frast::{BLOCKSIZE_IN_BYTES, KEY_BYTES_128BIT, N_SUBKEYS_128BIT};
+///
+/// // This example key came from NIST.FIPS.197 Appendix A.1
+/// let origin_key: [u8; KEY_BYTES_128BIT] = [
+///     0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///     0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C,
+/// ];
+/// let mut subkeys: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+/// key_schedule_encrypt128(&origin_key, &mut subkeys);
+///
+/// let mut round_keys = [[0u8; BLOCKSIZE_IN_BYTES]; N_SUBKEYS_128BIT / 4];
+/// subkeys_to_round_keys(&subkeys, &mut round_keys);
+///
+/// assert_eq!(round_keys[0], origin_key);
+/// assert_eq!(round_keys[10], [
+///     0xD0, 0x14, 0xF9, 0xA8, 0xC9, 0xEE, 0x25, 0x89,
+///     0xE1, 0x3F, 0x0C, 0xC8, 0xB6, 0x63, 0x0C, 0xA6,
+/// ]);
+/// ```
+pub fn subkeys_to_round_keys(subkeys: &[u32], round_keys: &mut [[u8; BLOCKSIZE_IN_BYTES]]) {
+    assert!(
+        subkeys.len() == N_SUBKEYS_128BIT
+            || subkeys.len() == N_SUBKEYS_192BIT
+            || subkeys.len() == N_SUBKEYS_256BIT
+    );
+    assert_eq!(round_keys.len() * 4, subkeys.len());
+    for (round_key, words) in round_keys.iter_mut().zip(subkeys.chunks_exact(4)) {
+        for (bytes, word) in round_key.chunks_exact_mut(4).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+/// Recover the original key from any contiguous round keys, by running the key schedule
+/// backwards.
+///
+/// * *parameter* `round_keys`: the contiguous round keys, as exported by
+///   [`subkeys_to_round_keys`] from the sub-keys for **encryption**. At least 1 round key is
+///   needed for a 128bit key, and 2 for a 192bit or 256bit key.
+/// * *parameter* `first_round`: the round (0 for the original key) of the first round key.
+/// * *parameter* `key`: the buffer (length = 16, 24 or 32) to store the original key, whose length
+///   selects the key size.
+/// * *return* : `Err(AesError::InvalidKeyLength)` if the length of `key` is not 16, 24 or 32, or
+///   `Err(AesError::InvalidParameter)` if there are not enough round keys, they run past the last
+///   round, or they are not the round keys of one key. `key` is filled only on success.
+///
+/// Every round key given is checked against the schedule of the recovered key, so a mistyped
+/// byte in a memory dump is reported rather than silently giving a wrong key.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::key_from_round_keys;
+/// use aes_frast::KEY_BYTES_128BIT;
+///
+/// // This example came from NIST.FIPS.197 Appendix A.1, the round key of round 10.
+/// let last_round_key = [
+///     0xD0, 0x14, 0xF9, 0xA8, 0xC9, 0xEE, 0x25, 0x89,
+///     0xE1, 0x3F, 0x0C, 0xC8, 0xB6, 0x63, 0x0C, 0xA6,
+/// ];
+/// let mut key = [0u8; KEY_BYTES_128BIT];
+/// key_from_round_keys(&[last_round_key], 10, &mut key).unwrap();
+/// assert_eq!(key, [
+///     0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///     0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C,
+/// ]);
+/// ```
+pub fn key_from_round_keys(
+    round_keys: &[[u8; BLOCKSIZE_IN_BYTES]],
+    first_round: usize,
+    key: &mut [u8],
+) -> Result<(), AesError> {
+    let n_subkeys = match key.len() {
+        KEY_BYTES_128BIT => N_SUBKEYS_128BIT,
+        KEY_BYTES_192BIT => N_SUBKEYS_192BIT,
+        KEY_BYTES_256BIT => N_SUBKEYS_256BIT,
+        _ => return Err(AesError::InvalidKeyLength),
+    };
+    let nk = key.len() / 4;
+    let start = 4 * first_round;
+    let end = round_keys
+        .len()
+        .checked_mul(4)
+        .and_then(|n| n.checked_add(start))
+        .filter(|&end| end - start >= nk && end <= n_subkeys)
+        .ok_or(AesError::InvalidParameter)?;
+    // The words are in the big-endian notation of NIST.FIPS.197 here.
+    let mut words = [0u32; N_SUBKEYS_256BIT];
+    for (chunk, round_key) in words[start..end].chunks_exact_mut(4).zip(round_keys) {
+        for (word, bytes) in chunk.iter_mut().zip(round_key.chunks_exact(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+    }
+    // w[i - Nk] = w[i] ^ temp(w[i - 1]), from the last known word down to the original key.
+    for i in (nk..(start + nk)).rev() {
+        words[i - nk] = words[i] ^ key_expansion_temp(words[i - 1], i, nk);
+    }
+    let mut recovered = [0u8; KEY_BYTES_256BIT];
+    for (bytes, word) in recovered.chunks_exact_mut(4).zip(&words[..nk]) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    let mut subkeys = [0u32; N_SUBKEYS_256BIT];
+    key_schedule_encrypt_auto(&recovered[..key.len()], &mut subkeys[..n_subkeys]);
+    let consistent =
+        subkeys[start..end]
+            .chunks_exact(4)
+            .zip(round_keys)
+            .all(|(chunk, round_key)| {
+                chunk
+                    .iter()
+                    .zip(round_key.chunks_exact(4))
+                    .all(|(word, bytes)| word.to_le_bytes() == bytes)
+            });
+    if consistent {
+        key.copy_from_slice(&recovered[..key.len()]);
+    }
+    words.fill(0);
+    subkeys.fill(0);
+    recovered.fill(0);
+    if consistent {
+        Ok(())
+    } else {
+        Err(AesError::InvalidParameter)
+    }
+}
+
+/// The word XORed with `w[i - Nk]` to give `w[i]` in the key expansion of NIST.FIPS.197, from
+/// `prev = w[i - 1]` in the big-endian notation.
+fn key_expansion_temp(prev: u32, i: usize, nk: usize) -> u32 {
+    let sub_word = |w: u32| u32::from_be_bytes(w.to_be_bytes().map(|b| SBOX[b as usize]));
+    if i.is_multiple_of(nk) {
+        sub_word(prev.rotate_left(8)) ^ ((RC[i / nk - 1] as u32) << 24)
+    } else if nk == 8 && i % nk == 4 {
+        sub_word(prev)
+    } else {
+        prev
+    }
+}
+
+/// The S-box-only block functions of the `small-tables` feature. The state is kept as the four
+/// little-endian column words, like the T-Box functions, but MixColumns is computed on the words.
+///
+/// The decryption of the `compact-decrypt` feature is the one here too, with the steps of the
+/// inverse cipher (InvShiftRows, InvSubBytes and InvMixColumns) instead of the T-Boxes for
+/// decryption. Without them, the key schedule also computes InvMixColumn here.
+#[cfg(any(
+    feature = "fixslice",
+    feature = "small-tables",
+    feature = "compact-decrypt"
+))]
+mod small_tables {
+    use super::*;
+
+    // Multiply the four bytes of a word by `x` in GF(2^8).
+    const fn xtime_word(w: u32) -> u32 {
+        ((w & 0x7F7F7F7F) << 1) ^ (((w >> 7) & 0x01010101) * 0x1B)
+    }
+
+    // MixColumn of a column word: `2a_r ^ 3a_(r+1) ^ a_(r+2) ^ a_(r+3)` for each row `r`, where the
+    // byte of row `r` is the byte `r` of the word.
+    pub(super) const fn mix_column(w: u32) -> u32 {
+        let r1 = w.rotate_right(8);
+        xtime_word(w ^ r1) ^ r1 ^ w.rotate_right(16) ^ w.rotate_right(24)
+    }
+
+    // InvMixColumn of a column word. The inverse matrix is the product of MixColumn and
+    // `1 + 4 * (1 + R^2)`, where `R` rotates the rows.
+    pub(super) const fn inv_mix_column(w: u32) -> u32 {
+        let u = xtime_word(xtime_word(w ^ w.rotate_right(16)));
+        mix_column(w ^ u)
+    }
+
+    // Load a block into column words and add the round key `k`.
+    fn load(block: &[u8; BLOCKSIZE_IN_BYTES], k: &[u32]) -> [u32; 4] {
+        core::array::from_fn(|j| {
+            let b = &block[(4 * j)..(4 * j + 4)];
+            four_u8_to_u32!(b[0], b[1], b[2], b[3]) ^ k[j]
+        })
+    }
+
+    // SubBytes and ShiftRows of the column words.
+    fn sub_shift(w: &[u32; 4]) -> [u32; 4] {
+        core::array::from_fn(|j| {
+            four_u8_to_u32!(
+                lookup!(SBOX, u8_b0_of_u32!(w[j])),
+                lookup!(SBOX, u8_b1_of_u32!(w[(j + 1) & 3])),
+                lookup!(SBOX, u8_b2_of_u32!(w[(j + 2) & 3])),
+                lookup!(SBOX, u8_b3_of_u32!(w[(j + 3) & 3]))
+            )
+        })
+    }
+
+    // InvSubBytes and InvShiftRows of the column words.
+    fn inv_sub_shift(w: &[u32; 4]) -> [u32; 4] {
+        core::array::from_fn(|j| {
+            four_u8_to_u32!(
+                lookup!(SINV, u8_b0_of_u32!(w[j])),
+                lookup!(SINV, u8_b1_of_u32!(w[(j + 3) & 3])),
+                lookup!(SINV, u8_b2_of_u32!(w[(j + 2) & 3])),
+                lookup!(SINV, u8_b3_of_u32!(w[(j + 1) & 3]))
+            )
+        })
+    }
+
+    // Add the round key `k` to the column words and store them into a block.
+    fn store(w: &[u32; 4], k: &[u32], block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        for j in 0..4 {
+            block[(4 * j)..(4 * j + 4)].copy_from_slice(&(w[j] ^ k[j]).to_le_bytes());
+        }
+    }
+
+    // Encrypt a block with the S-box only.
+    pub(super) fn encrypt_block(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+        let rounds = subkeys.len() / 4 - 1;
+        let mut w = load(block, &subkeys[..4]);
+        for round in 1..rounds {
+            let t = sub_shift(&w);
+            let k = &subkeys[(4 * round)..(4 * round + 4)];
+            w = core::array::from_fn(|j| mix_column(t[j]) ^ k[j]);
+        }
+        // final round - no MixColumn
+        store(&sub_shift(&w), &subkeys[(4 * rounds)..], block);
+    }
+
+    // Decrypt a block with the inverse S-box only. It is the equivalent inverse cipher of
+    // NIST.FIPS.197, whose round keys already have InvMixColumn applied, like the T-Box one.
+    pub(super) fn decrypt_block(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+        let rounds = subkeys.len() / 4 - 1;
+        let mut w = load(block, &subkeys[(4 * rounds)..]);
+        for round in (1..rounds).rev() {
+            let t = inv_sub_shift(&w);
+            let k = &subkeys[(4 * round)..(4 * round + 4)];
+            w = core::array::from_fn(|j| inv_mix_column(t[j]) ^ k[j]);
+        }
+        // final round - no InvMixColumn
+        store(&inv_sub_shift(&w), &subkeys[..4], block);
+    }
+}
+
 /// **Encrypt** a block with scheduled keys (from **128bit key**) in place.
 ///
 /// Encrypt the data in `block` and write it back there, using the `subkeys`.
@@ -1132,6 +2105,503 @@ pub fn block_decrypt256(input: &[u8], output: &mut [u8], subkeys: &[u32]) {
     decryption_function!(input, output, subkeys, 7, N_SUBKEYS_256BIT);
 }
 
+/// **Encrypt** 4 blocks with scheduled keys (from **128bit key**) in place.
+///
+/// Encrypt the 4 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 4 calls of [`block_encrypt128_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 64) that stores 4 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 44) that contains the sub-keys.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{
+///     block_encrypt128_inplace, block_encrypt128_x4, key_schedule_encrypt128,
+/// };
+/// use aes_frast::{BLOCKSIZE_IN_BYTES, N_SUBKEYS_128BIT};
+///
+/// let origin_key = [0x2Bu8; 16];
+/// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+/// key_schedule_encrypt128(&origin_key, &mut subkeys);
+///
+/// let mut blocks: Vec<u8> = (0..64).collect();
+/// let mut expected = blocks.clone();
+/// for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+///     block_encrypt128_inplace(block, &subkeys);
+/// }
+/// block_encrypt128_x4(&mut blocks, &subkeys);
+/// assert_eq!(blocks, expected);
+/// ```
+///
+/// [`block_encrypt128_inplace`]: ../aes_core/fn.block_encrypt128_inplace.html
+pub fn block_encrypt128_x4(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 4 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_128BIT);
+    encrypt_wide::<4>(blocks, subkeys);
+}
+
+/// **Encrypt** 8 blocks with scheduled keys (from **128bit key**) in place.
+///
+/// Encrypt the 8 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 8 calls of [`block_encrypt128_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 128) that stores 8 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 44) that contains the sub-keys.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{
+///     block_encrypt128_inplace, block_encrypt128_x8, key_schedule_encrypt128,
+/// };
+/// use aes_frast::{BLOCKSIZE_IN_BYTES, N_SUBKEYS_128BIT};
+///
+/// let origin_key = [0x2Bu8; 16];
+/// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+/// key_schedule_encrypt128(&origin_key, &mut subkeys);
+///
+/// let mut blocks: Vec<u8> = (0..128).collect();
+/// let mut expected = blocks.clone();
+/// for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+///     block_encrypt128_inplace(block, &subkeys);
+/// }
+/// block_encrypt128_x8(&mut blocks, &subkeys);
+/// assert_eq!(blocks, expected);
+/// ```
+///
+/// [`block_encrypt128_inplace`]: ../aes_core/fn.block_encrypt128_inplace.html
+pub fn block_encrypt128_x8(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 8 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_128BIT);
+    encrypt_wide::<8>(blocks, subkeys);
+}
+
+/// **Encrypt** 4 blocks with scheduled keys (from **192bit key**) in place.
+///
+/// Encrypt the 4 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 4 calls of [`block_encrypt192_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 64) that stores 4 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 52) that contains the sub-keys.
+///
+/// [`block_encrypt192_inplace`]: ../aes_core/fn.block_encrypt192_inplace.html
+pub fn block_encrypt192_x4(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 4 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_192BIT);
+    encrypt_wide::<4>(blocks, subkeys);
+}
+
+/// **Encrypt** 8 blocks with scheduled keys (from **192bit key**) in place.
+///
+/// Encrypt the 8 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 8 calls of [`block_encrypt192_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 128) that stores 8 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 52) that contains the sub-keys.
+///
+/// [`block_encrypt192_inplace`]: ../aes_core/fn.block_encrypt192_inplace.html
+pub fn block_encrypt192_x8(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 8 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_192BIT);
+    encrypt_wide::<8>(blocks, subkeys);
+}
+
+/// **Encrypt** 4 blocks with scheduled keys (from **256bit key**) in place.
+///
+/// Encrypt the 4 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 4 calls of [`block_encrypt256_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 64) that stores 4 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 60) that contains the sub-keys.
+///
+/// [`block_encrypt256_inplace`]: ../aes_core/fn.block_encrypt256_inplace.html
+pub fn block_encrypt256_x4(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 4 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_256BIT);
+    encrypt_wide::<4>(blocks, subkeys);
+}
+
+/// **Encrypt** 8 blocks with scheduled keys (from **256bit key**) in place.
+///
+/// Encrypt the 8 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 8 calls of [`block_encrypt256_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 128) that stores 8 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 60) that contains the sub-keys.
+///
+/// [`block_encrypt256_inplace`]: ../aes_core/fn.block_encrypt256_inplace.html
+pub fn block_encrypt256_x8(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 8 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_256BIT);
+    encrypt_wide::<8>(blocks, subkeys);
+}
+
+/// **Decrypt** 4 blocks with scheduled keys (from **128bit key**) in place.
+///
+/// Decrypt the 4 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 4 calls of [`block_decrypt128_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 64) that stores 4 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 44) that contains the sub-keys.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{
+///     block_decrypt128_inplace, block_decrypt128_x4, key_schedule_decrypt128,
+/// };
+/// use aes_frast::{BLOCKSIZE_IN_BYTES, N_SUBKEYS_128BIT};
+///
+/// let origin_key = [0x2Bu8; 16];
+/// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+/// key_schedule_decrypt128(&origin_key, &mut subkeys);
+///
+/// let mut blocks: Vec<u8> = (0..64).collect();
+/// let mut expected = blocks.clone();
+/// for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+///     block_decrypt128_inplace(block, &subkeys);
+/// }
+/// block_decrypt128_x4(&mut blocks, &subkeys);
+/// assert_eq!(blocks, expected);
+/// ```
+///
+/// [`block_decrypt128_inplace`]: ../aes_core/fn.block_decrypt128_inplace.html
+pub fn block_decrypt128_x4(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 4 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_128BIT);
+    decrypt_wide::<4>(blocks, subkeys);
+}
+
+/// **Decrypt** 8 blocks with scheduled keys (from **128bit key**) in place.
+///
+/// Decrypt the 8 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 8 calls of [`block_decrypt128_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 128) that stores 8 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 44) that contains the sub-keys.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::{
+///     block_decrypt128_inplace, block_decrypt128_x8, key_schedule_decrypt128,
+/// };
+/// use aes_frast::{BLOCKSIZE_IN_BYTES, N_SUBKEYS_128BIT};
+///
+/// let origin_key = [0x2Bu8; 16];
+/// let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+/// key_schedule_decrypt128(&origin_key, &mut subkeys);
+///
+/// let mut blocks: Vec<u8> = (0..128).collect();
+/// let mut expected = blocks.clone();
+/// for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+///     block_decrypt128_inplace(block, &subkeys);
+/// }
+/// block_decrypt128_x8(&mut blocks, &subkeys);
+/// assert_eq!(blocks, expected);
+/// ```
+///
+/// [`block_decrypt128_inplace`]: ../aes_core/fn.block_decrypt128_inplace.html
+pub fn block_decrypt128_x8(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 8 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_128BIT);
+    decrypt_wide::<8>(blocks, subkeys);
+}
+
+/// **Decrypt** 4 blocks with scheduled keys (from **192bit key**) in place.
+///
+/// Decrypt the 4 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 4 calls of [`block_decrypt192_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 64) that stores 4 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 52) that contains the sub-keys.
+///
+/// [`block_decrypt192_inplace`]: ../aes_core/fn.block_decrypt192_inplace.html
+pub fn block_decrypt192_x4(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 4 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_192BIT);
+    decrypt_wide::<4>(blocks, subkeys);
+}
+
+/// **Decrypt** 8 blocks with scheduled keys (from **192bit key**) in place.
+///
+/// Decrypt the 8 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 8 calls of [`block_decrypt192_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 128) that stores 8 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 52) that contains the sub-keys.
+///
+/// [`block_decrypt192_inplace`]: ../aes_core/fn.block_decrypt192_inplace.html
+pub fn block_decrypt192_x8(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 8 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_192BIT);
+    decrypt_wide::<8>(blocks, subkeys);
+}
+
+/// **Decrypt** 4 blocks with scheduled keys (from **256bit key**) in place.
+///
+/// Decrypt the 4 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 4 calls of [`block_decrypt256_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 64) that stores 4 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 60) that contains the sub-keys.
+///
+/// [`block_decrypt256_inplace`]: ../aes_core/fn.block_decrypt256_inplace.html
+pub fn block_decrypt256_x4(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 4 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_256BIT);
+    decrypt_wide::<4>(blocks, subkeys);
+}
+
+/// **Decrypt** 8 blocks with scheduled keys (from **256bit key**) in place.
+///
+/// Decrypt the 8 consecutive blocks in `blocks` independently (like ECB mode) and write them back
+/// there, using the `subkeys`. The rounds of the blocks are interleaved, so this is faster than
+/// 8 calls of [`block_decrypt256_inplace`].
+///
+/// * *parameter* `blocks`: the slice (length = 128) that stores 8 blocks of data.
+/// * *parameter* `subkeys`: the slice (length = 60) that contains the sub-keys.
+///
+/// [`block_decrypt256_inplace`]: ../aes_core/fn.block_decrypt256_inplace.html
+pub fn block_decrypt256_x8(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len(), 8 * BLOCKSIZE_IN_BYTES);
+    assert_eq!(subkeys.len(), N_SUBKEYS_256BIT);
+    decrypt_wide::<8>(blocks, subkeys);
+}
+/// **Encrypt** consecutive blocks in place, 8 and then 4 at a time while there are enough of
+/// them. The sub-keys may come from any key size.
+///
+/// This is the batch used by the cipher objects and the parallelizable operation modes.
+pub(crate) fn encrypt_blocks(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len() & 0b1111, 0, "Incomplete block.");
+    check_subkeys_length(subkeys);
+    let mut chunks = blocks.chunks_exact_mut(8 * BLOCKSIZE_IN_BYTES);
+    for chunk in &mut chunks {
+        encrypt_wide::<8>(chunk, subkeys);
+    }
+    let mut chunks = chunks
+        .into_remainder()
+        .chunks_exact_mut(4 * BLOCKSIZE_IN_BYTES);
+    for chunk in &mut chunks {
+        encrypt_wide::<4>(chunk, subkeys);
+    }
+    for block in chunks.into_remainder().chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+        encrypt_wide::<1>(block, subkeys);
+    }
+}
+
+/// **Decrypt** consecutive blocks in place, 8 and then 4 at a time while there are enough of
+/// them. The sub-keys may come from any key size.
+///
+/// This is the batch used by the cipher objects and the parallelizable operation modes.
+pub(crate) fn decrypt_blocks(blocks: &mut [u8], subkeys: &[u32]) {
+    assert_eq!(blocks.len() & 0b1111, 0, "Incomplete block.");
+    check_subkeys_length(subkeys);
+    let mut chunks = blocks.chunks_exact_mut(8 * BLOCKSIZE_IN_BYTES);
+    for chunk in &mut chunks {
+        decrypt_wide::<8>(chunk, subkeys);
+    }
+    let mut chunks = chunks
+        .into_remainder()
+        .chunks_exact_mut(4 * BLOCKSIZE_IN_BYTES);
+    for chunk in &mut chunks {
+        decrypt_wide::<4>(chunk, subkeys);
+    }
+    for block in chunks.into_remainder().chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+        decrypt_wide::<1>(block, subkeys);
+    }
+}
+
+fn check_subkeys_length(subkeys: &[u32]) {
+    assert!(
+        matches!(
+            subkeys.len(),
+            N_SUBKEYS_128BIT | N_SUBKEYS_192BIT | N_SUBKEYS_256BIT
+        ),
+        "Invalid key length."
+    );
+}
+
+// Encrypt `N` blocks (`blocks.len() == 16 * N`). The tables are looked up round by round for all
+// the blocks, so that the lookups of a block don't wait for the ones of the previous block.
+fn encrypt_wide<const N: usize>(blocks: &mut [u8], subkeys: &[u32]) {
+    #[cfg(all(feature = "aesni", any(target_arch = "x86", target_arch = "x86_64")))]
+    if super::aesni::available() {
+        super::aesni::encrypt_blocks::<N>(blocks, subkeys);
+        return;
+    }
+    #[cfg(feature = "fixslice")]
+    {
+        let mut pairs = blocks.chunks_exact_mut(2 * BLOCKSIZE_IN_BYTES);
+        for pair in &mut pairs {
+            let (first, second) = pair.split_at_mut(BLOCKSIZE_IN_BYTES);
+            super::fixslice::encrypt_blocks(
+                [first.try_into().unwrap(), second.try_into().unwrap()],
+                subkeys,
+            );
+        }
+        if let Ok(block) = pairs.into_remainder().try_into() {
+            super::fixslice::encrypt_block(block, subkeys);
+        }
+    }
+    #[cfg(all(feature = "small-tables", not(feature = "fixslice")))]
+    for block in blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+        small_tables::encrypt_block(block.try_into().unwrap(), subkeys);
+    }
+    #[cfg(not(any(feature = "fixslice", feature = "small-tables")))]
+    {
+        let rounds = subkeys.len() / 4 - 1;
+        let mut state = [[0u32; 4]; N];
+        for (words, block) in state
+            .iter_mut()
+            .zip(blocks.chunks_exact(BLOCKSIZE_IN_BYTES))
+        {
+            for (j, word) in words.iter_mut().enumerate() {
+                let b = &block[(4 * j)..(4 * j + 4)];
+                *word = four_u8_to_u32!(b[0], b[1], b[2], b[3]) ^ subkeys[j];
+            }
+        }
+        for round in 1..rounds {
+            let k = &subkeys[(4 * round)..(4 * round + 4)];
+            for w in state.iter_mut() {
+                *w = [
+                    lookup!(TE0, u8_b0_of_u32!(w[0]))
+                        ^ lookup!(TE1, u8_b1_of_u32!(w[1]))
+                        ^ lookup!(TE2, u8_b2_of_u32!(w[2]))
+                        ^ lookup!(TE3, u8_b3_of_u32!(w[3]))
+                        ^ k[0],
+                    lookup!(TE0, u8_b0_of_u32!(w[1]))
+                        ^ lookup!(TE1, u8_b1_of_u32!(w[2]))
+                        ^ lookup!(TE2, u8_b2_of_u32!(w[3]))
+                        ^ lookup!(TE3, u8_b3_of_u32!(w[0]))
+                        ^ k[1],
+                    lookup!(TE0, u8_b0_of_u32!(w[2]))
+                        ^ lookup!(TE1, u8_b1_of_u32!(w[3]))
+                        ^ lookup!(TE2, u8_b2_of_u32!(w[0]))
+                        ^ lookup!(TE3, u8_b3_of_u32!(w[1]))
+                        ^ k[2],
+                    lookup!(TE0, u8_b0_of_u32!(w[3]))
+                        ^ lookup!(TE1, u8_b1_of_u32!(w[0]))
+                        ^ lookup!(TE2, u8_b2_of_u32!(w[1]))
+                        ^ lookup!(TE3, u8_b3_of_u32!(w[2]))
+                        ^ k[3],
+                ];
+            }
+        }
+        // final round - no MixColumn
+        let k = &subkeys[(4 * rounds)..];
+        for (w, block) in state
+            .iter()
+            .zip(blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES))
+        {
+            for j in 0..4 {
+                let word = four_u8_to_u32!(
+                    lookup!(SBOX, u8_b0_of_u32!(w[j])),
+                    lookup!(SBOX, u8_b1_of_u32!(w[(j + 1) & 3])),
+                    lookup!(SBOX, u8_b2_of_u32!(w[(j + 2) & 3])),
+                    lookup!(SBOX, u8_b3_of_u32!(w[(j + 3) & 3]))
+                ) ^ k[j];
+                block[(4 * j)..(4 * j + 4)].copy_from_slice(&word.to_le_bytes());
+            }
+        }
+    }
+}
+
+// Decrypt `N` blocks (`blocks.len() == 16 * N`), like `encrypt_wide`.
+fn decrypt_wide<const N: usize>(blocks: &mut [u8], subkeys: &[u32]) {
+    #[cfg(all(feature = "aesni", any(target_arch = "x86", target_arch = "x86_64")))]
+    if super::aesni::available() {
+        super::aesni::decrypt_blocks::<N>(blocks, subkeys);
+        return;
+    }
+    #[cfg(feature = "fixslice")]
+    {
+        let mut pairs = blocks.chunks_exact_mut(2 * BLOCKSIZE_IN_BYTES);
+        for pair in &mut pairs {
+            let (first, second) = pair.split_at_mut(BLOCKSIZE_IN_BYTES);
+            super::fixslice::decrypt_blocks(
+                [first.try_into().unwrap(), second.try_into().unwrap()],
+                subkeys,
+            );
+        }
+        if let Ok(block) = pairs.into_remainder().try_into() {
+            super::fixslice::decrypt_block(block, subkeys);
+        }
+    }
+    #[cfg(all(
+        any(feature = "small-tables", feature = "compact-decrypt"),
+        not(feature = "fixslice")
+    ))]
+    for block in blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+        small_tables::decrypt_block(block.try_into().unwrap(), subkeys);
+    }
+    #[cfg(not(any(
+        feature = "fixslice",
+        feature = "small-tables",
+        feature = "compact-decrypt"
+    )))]
+    {
+        let rounds = subkeys.len() / 4 - 1;
+        let k = &subkeys[(4 * rounds)..];
+        let mut state = [[0u32; 4]; N];
+        for (words, block) in state
+            .iter_mut()
+            .zip(blocks.chunks_exact(BLOCKSIZE_IN_BYTES))
+        {
+            for (j, word) in words.iter_mut().enumerate() {
+                let b = &block[(4 * j)..(4 * j + 4)];
+                *word = four_u8_to_u32!(b[0], b[1], b[2], b[3]) ^ k[j];
+            }
+        }
+        for round in (1..rounds).rev() {
+            let k = &subkeys[(4 * round)..(4 * round + 4)];
+            for w in state.iter_mut() {
+                *w = [
+                    lookup!(TD0, u8_b0_of_u32!(w[0]))
+                        ^ lookup!(TD1, u8_b1_of_u32!(w[3]))
+                        ^ lookup!(TD2, u8_b2_of_u32!(w[2]))
+                        ^ lookup!(TD3, u8_b3_of_u32!(w[1]))
+                        ^ k[0],
+                    lookup!(TD0, u8_b0_of_u32!(w[1]))
+                        ^ lookup!(TD1, u8_b1_of_u32!(w[0]))
+                        ^ lookup!(TD2, u8_b2_of_u32!(w[3]))
+                        ^ lookup!(TD3, u8_b3_of_u32!(w[2]))
+                        ^ k[1],
+                    lookup!(TD0, u8_b0_of_u32!(w[2]))
+                        ^ lookup!(TD1, u8_b1_of_u32!(w[1]))
+                        ^ lookup!(TD2, u8_b2_of_u32!(w[0]))
+                        ^ lookup!(TD3, u8_b3_of_u32!(w[3]))
+                        ^ k[2],
+                    lookup!(TD0, u8_b0_of_u32!(w[3]))
+                        ^ lookup!(TD1, u8_b1_of_u32!(w[2]))
+                        ^ lookup!(TD2, u8_b2_of_u32!(w[1]))
+                        ^ lookup!(TD3, u8_b3_of_u32!(w[0]))
+                        ^ k[3],
+                ];
+            }
+        }
+        // final round - no InvMixColumn
+        for (w, block) in state
+            .iter()
+            .zip(blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES))
+        {
+            for j in 0..4 {
+                let word = four_u8_to_u32!(
+                    lookup!(SINV, u8_b0_of_u32!(w[j])),
+                    lookup!(SINV, u8_b1_of_u32!(w[(j + 3) & 3])),
+                    lookup!(SINV, u8_b2_of_u32!(w[(j + 2) & 3])),
+                    lookup!(SINV, u8_b3_of_u32!(w[(j + 1) & 3]))
+                ) ^ subkeys[j];
+                block[(4 * j)..(4 * j + 4)].copy_from_slice(&word.to_le_bytes());
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1414,4 +2884,324 @@ mod tests {
             assert_eq!(scheduled256a[i], scheduled256b[i]);
         }
     }
+
+    #[test]
+    fn subkeys_to_round_keys_works() {
+        // This example came from NIST.FIPS.197 Appendix A.3
+        let origin256: [u8; KEY_BYTES_256BIT] = [
+            0x60, 0x3D, 0xEB, 0x10, 0x15, 0xCA, 0x71, 0xBE, 0x2B, 0x73, 0xAE, 0xF0, 0x85, 0x7D,
+            0x77, 0x81, 0x1F, 0x35, 0x2C, 0x07, 0x3B, 0x61, 0x08, 0xD7, 0x2D, 0x98, 0x10, 0xA3,
+            0x09, 0x14, 0xDF, 0xF4,
+        ];
+        let mut subkeys: [u32; N_SUBKEYS_256BIT] = [0; N_SUBKEYS_256BIT];
+        key_schedule_encrypt256(&origin256, &mut subkeys);
+        let mut round_keys = [[0u8; BLOCKSIZE_IN_BYTES]; N_SUBKEYS_256BIT / 4];
+        subkeys_to_round_keys(&subkeys, &mut round_keys);
+        assert_eq!(round_keys[0], origin256[..16]);
+        assert_eq!(round_keys[1], origin256[16..]);
+        let expected_last: [u8; BLOCKSIZE_IN_BYTES] = [
+            0xFE, 0x48, 0x90, 0xD1, 0xE6, 0x18, 0x8D, 0x0B, 0x04, 0x6D, 0xF3, 0x44, 0x70, 0x6C,
+            0x63, 0x1E,
+        ];
+        assert_eq!(round_keys[14], expected_last);
+    }
+
+    #[test]
+    #[should_panic]
+    fn subkeys_to_round_keys_wrong_length_panic() {
+        let subkeys: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+        let mut round_keys = [[0u8; BLOCKSIZE_IN_BYTES]; 10];
+        subkeys_to_round_keys(&subkeys, &mut round_keys);
+    }
+
+    #[test]
+    fn subkeys_encrypt_to_decrypt_works() {
+        let origin192: [u8; KEY_BYTES_192BIT] = [
+            0x8E, 0x73, 0xB0, 0xF7, 0xDA, 0x0E, 0x64, 0x52, 0xC8, 0x10, 0xF3, 0x2B, 0x80, 0x90,
+            0x79, 0xE5, 0x62, 0xF8, 0xEA, 0xD2, 0x52, 0x2C, 0x6B, 0x7B,
+        ];
+        let mut converted: [u32; N_SUBKEYS_192BIT] = [0; N_SUBKEYS_192BIT];
+        let mut expected: [u32; N_SUBKEYS_192BIT] = [0; N_SUBKEYS_192BIT];
+        key_schedule_encrypt192(&origin192, &mut converted);
+        subkeys_encrypt_to_decrypt(&mut converted);
+        key_schedule_decrypt192(&origin192, &mut expected);
+        assert_eq!(converted, expected);
+    }
+
+    #[test]
+    fn key_schedule_convert_enc_to_dec_works() {
+        type Convert = fn(&[u32], &mut [u32]);
+        let converts: [(usize, Convert); 3] = [
+            (KEY_BYTES_128BIT, key_schedule_convert_enc_to_dec128),
+            (KEY_BYTES_192BIT, key_schedule_convert_enc_to_dec192),
+            (KEY_BYTES_256BIT, key_schedule_convert_enc_to_dec256),
+        ];
+        for (key_bytes, convert) in converts {
+            let origin: Vec<u8> = (0..key_bytes).map(|i| (i * 17 + 4) as u8).collect();
+            let mut encryption = vec![0u32; key_bytes + 28];
+            let mut expected = vec![0u32; key_bytes + 28];
+            key_schedule_encrypt_auto(&origin, &mut encryption);
+            key_schedule_decrypt_auto(&origin, &mut expected);
+            let mut converted = vec![0u32; key_bytes + 28];
+            convert(&encryption, &mut converted);
+            assert_eq!(converted, expected);
+            converted.fill(0);
+            key_schedule_convert_enc_to_dec_auto(&encryption, &mut converted);
+            assert_eq!(converted, expected);
+            converted.fill(0);
+            assert_eq!(
+                try_key_schedule_convert_enc_to_dec_auto(&encryption, &mut converted),
+                Ok(())
+            );
+            assert_eq!(converted, expected);
+        }
+        assert_eq!(
+            try_key_schedule_convert_enc_to_dec_auto(&[0u32; 40], &mut [0u32; 40]),
+            Err(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            try_key_schedule_convert_enc_to_dec_auto(&[0u32; 44], &mut [0u32; 52]),
+            Err(AesError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn key_from_round_keys_works() {
+        for key_bytes in [KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT] {
+            let origin: Vec<u8> = (0..key_bytes).map(|i| (i * 29 + 7) as u8).collect();
+            let mut subkeys = vec![0u32; key_bytes + 28];
+            key_schedule_encrypt_auto(&origin, &mut subkeys);
+            let mut round_keys = vec![[0u8; BLOCKSIZE_IN_BYTES]; subkeys.len() / 4];
+            subkeys_to_round_keys(&subkeys, &mut round_keys);
+            let needed = if key_bytes == KEY_BYTES_128BIT { 1 } else { 2 };
+            for first in 0..=(round_keys.len() - needed) {
+                let mut key = vec![0u8; key_bytes];
+                let given = &round_keys[first..(first + needed)];
+                assert_eq!(key_from_round_keys(given, first, &mut key), Ok(()));
+                assert_eq!(key, origin);
+                key.fill(0);
+                assert_eq!(
+                    key_from_round_keys(&round_keys[first..], first, &mut key),
+                    Ok(())
+                );
+                assert_eq!(key, origin);
+            }
+            let mut key = vec![0u8; key_bytes];
+            let last = round_keys.len() - 1;
+            assert_eq!(
+                key_from_round_keys(&round_keys[last..], last + 1, &mut key),
+                Err(AesError::InvalidParameter)
+            );
+            let mut corrupted = round_keys.clone();
+            corrupted[last][3] ^= 1;
+            assert_eq!(
+                key_from_round_keys(&corrupted[(last - 2)..], last - 2, &mut key),
+                Err(AesError::InvalidParameter)
+            );
+            assert_eq!(key, vec![0u8; key_bytes]);
+        }
+        let round_key = [[0u8; BLOCKSIZE_IN_BYTES]; 2];
+        assert_eq!(
+            key_from_round_keys(&round_key[..1], 3, &mut [0u8; KEY_BYTES_256BIT]),
+            Err(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            key_from_round_keys(&round_key, 0, &mut [0u8; 20]),
+            Err(AesError::InvalidKeyLength)
+        );
+    }
+
+    #[test]
+    fn subkeys_be_words_works() {
+        let origin128: [u8; KEY_BYTES_128BIT] = [
+            0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF,
+            0x4F, 0x3C,
+        ];
+        let mut subkeys: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+        let mut words: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+        let mut back: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+        key_schedule_encrypt128(&origin128, &mut subkeys);
+        subkeys_to_be_words(&subkeys, &mut words);
+        // This example came from NIST.FIPS.197 Appendix A.1
+        assert_eq!(words[4], 0xA0FAFE17);
+        assert_eq!(words[4], u32::from_be_bytes([0xA0, 0xFA, 0xFE, 0x17]));
+        subkeys_from_be_words(&words, &mut back);
+        assert_eq!(back, subkeys);
+    }
+
+    #[test]
+    fn key_schedule_encrypt_decrypt_works() {
+        let origin192: [u8; KEY_BYTES_192BIT] = [
+            0x8E, 0x73, 0xB0, 0xF7, 0xDA, 0x0E, 0x64, 0x52, 0xC8, 0x10, 0xF3, 0x2B, 0x80, 0x90,
+            0x79, 0xE5, 0x62, 0xF8, 0xEA, 0xD2, 0x52, 0x2C, 0x6B, 0x7B,
+        ];
+        let mut encryption: [u32; N_SUBKEYS_192BIT] = [0; N_SUBKEYS_192BIT];
+        let mut decryption: [u32; N_SUBKEYS_192BIT] = [0; N_SUBKEYS_192BIT];
+        let mut expected: [u32; N_SUBKEYS_192BIT] = [0; N_SUBKEYS_192BIT];
+        key_schedule_encrypt_decrypt192(&origin192, &mut encryption, &mut decryption);
+        key_schedule_encrypt192(&origin192, &mut expected);
+        assert_eq!(encryption, expected);
+        key_schedule_decrypt192(&origin192, &mut expected);
+        assert_eq!(decryption, expected);
+    }
+
+    #[test]
+    fn typed_key_schedules_work() {
+        let origin: Vec<u8> = (0..32).collect();
+        let mut expected = [0u32; N_SUBKEYS_256BIT];
+        let mut encryption = [0u32; N_SUBKEYS_256BIT];
+        let mut decryption = [0u32; N_SUBKEYS_256BIT];
+
+        let key = Key128::try_from(&origin[..16]).unwrap();
+        key_schedule_encrypt_decrypt128_typed(
+            &key,
+            (&mut encryption[..N_SUBKEYS_128BIT]).try_into().unwrap(),
+            (&mut decryption[..N_SUBKEYS_128BIT]).try_into().unwrap(),
+        );
+        key_schedule_encrypt128(&origin[..16], &mut expected[..N_SUBKEYS_128BIT]);
+        assert_eq!(encryption[..N_SUBKEYS_128BIT], expected[..N_SUBKEYS_128BIT]);
+        key_schedule_decrypt128(&origin[..16], &mut expected[..N_SUBKEYS_128BIT]);
+        assert_eq!(decryption[..N_SUBKEYS_128BIT], expected[..N_SUBKEYS_128BIT]);
+
+        let key = Key192::try_from(&origin[..24]).unwrap();
+        let mut typed = [0u32; N_SUBKEYS_192BIT];
+        key_schedule_decrypt192_typed(&key, &mut typed);
+        key_schedule_decrypt192(&origin[..24], &mut expected[..N_SUBKEYS_192BIT]);
+        assert_eq!(typed[..], expected[..N_SUBKEYS_192BIT]);
+
+        let key = Key256::try_from(&origin[..]).unwrap();
+        key_schedule_encrypt256_typed(&key, &mut encryption);
+        key_schedule_decrypt256_typed(&key, &mut decryption);
+        key_schedule_encrypt256(&origin, &mut expected);
+        assert_eq!(encryption, expected);
+        key_schedule_decrypt256(&origin, &mut expected);
+        assert_eq!(decryption, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_schedule_encrypt_decrypt_wrong_length_panic() {
+        let origin128 = [0u8; KEY_BYTES_128BIT];
+        let mut encryption: [u32; N_SUBKEYS_128BIT] = [0; N_SUBKEYS_128BIT];
+        let mut decryption: [u32; N_SUBKEYS_192BIT] = [0; N_SUBKEYS_192BIT];
+        key_schedule_encrypt_decrypt128(&origin128, &mut encryption, &mut decryption);
+    }
+
+    #[test]
+    fn expand_key_works() {
+        const KEY128: [u8; KEY_BYTES_128BIT] = [0x2Bu8; KEY_BYTES_128BIT];
+        const KEY192: [u8; KEY_BYTES_192BIT] = [0x8Eu8; KEY_BYTES_192BIT];
+        const KEY256: [u8; KEY_BYTES_256BIT] = [0x60u8; KEY_BYTES_256BIT];
+        const E128: [u32; N_SUBKEYS_128BIT] = expand_key_128(&KEY128);
+        const D128: [u32; N_SUBKEYS_128BIT] = expand_key_decrypt_128(&KEY128);
+        const E192: [u32; N_SUBKEYS_192BIT] = expand_key_192(&KEY192);
+        const D192: [u32; N_SUBKEYS_192BIT] = expand_key_decrypt_192(&KEY192);
+        const E256: [u32; N_SUBKEYS_256BIT] = expand_key_256(&KEY256);
+        const D256: [u32; N_SUBKEYS_256BIT] = expand_key_decrypt_256(&KEY256);
+
+        let mut encryption = [0u32; N_SUBKEYS_128BIT];
+        let mut decryption = [0u32; N_SUBKEYS_128BIT];
+        key_schedule_encrypt_decrypt128(&KEY128, &mut encryption, &mut decryption);
+        assert_eq!((E128, D128), (encryption, decryption));
+        let mut encryption = [0u32; N_SUBKEYS_192BIT];
+        let mut decryption = [0u32; N_SUBKEYS_192BIT];
+        key_schedule_encrypt_decrypt192(&KEY192, &mut encryption, &mut decryption);
+        assert_eq!((E192, D192), (encryption, decryption));
+        let mut encryption = [0u32; N_SUBKEYS_256BIT];
+        let mut decryption = [0u32; N_SUBKEYS_256BIT];
+        key_schedule_encrypt_decrypt256(&KEY256, &mut encryption, &mut decryption);
+        assert_eq!((E256, D256), (encryption, decryption));
+    }
+
+    #[test]
+    fn block_wide_works() {
+        type Single = fn(&mut [u8], &[u32]);
+        type Wide = fn(&mut [u8], &[u32]);
+        let functions: [(usize, Single, Wide, Wide, Single, Wide, Wide); 3] = [
+            (
+                KEY_BYTES_128BIT,
+                block_encrypt128_inplace,
+                block_encrypt128_x4,
+                block_encrypt128_x8,
+                block_decrypt128_inplace,
+                block_decrypt128_x4,
+                block_decrypt128_x8,
+            ),
+            (
+                KEY_BYTES_192BIT,
+                block_encrypt192_inplace,
+                block_encrypt192_x4,
+                block_encrypt192_x8,
+                block_decrypt192_inplace,
+                block_decrypt192_x4,
+                block_decrypt192_x8,
+            ),
+            (
+                KEY_BYTES_256BIT,
+                block_encrypt256_inplace,
+                block_encrypt256_x4,
+                block_encrypt256_x8,
+                block_decrypt256_inplace,
+                block_decrypt256_x4,
+                block_decrypt256_x8,
+            ),
+        ];
+        for (key_bytes, encrypt, encrypt_x4, encrypt_x8, decrypt, decrypt_x4, decrypt_x8) in
+            functions
+        {
+            let key: Vec<u8> = (0..key_bytes).map(|i| (i * 29 + 3) as u8).collect();
+            let mut encryption = vec![0u32; key_bytes + 28];
+            let mut decryption = vec![0u32; key_bytes + 28];
+            key_schedule_encrypt_decrypt_auto(&key, &mut encryption, &mut decryption);
+            let plain: Vec<u8> = (0..128).map(|i| (i * 7 + 1) as u8).collect();
+            let mut expected = plain.clone();
+            for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                encrypt(block, &encryption);
+            }
+            let mut blocks = plain.clone();
+            encrypt_x8(&mut blocks, &encryption);
+            assert_eq!(blocks, expected);
+            decrypt_x8(&mut blocks, &decryption);
+            assert_eq!(blocks, plain);
+            encrypt_x4(&mut blocks[..64], &encryption);
+            assert_eq!(blocks[..64], expected[..64]);
+            decrypt_x4(&mut blocks[..64], &decryption);
+            assert_eq!(blocks, plain);
+            // 13 blocks: one batch of 8, one of 4 and a single block.
+            let plain: Vec<u8> = (0..208).map(|i| (i * 5 + 2) as u8).collect();
+            let mut expected = plain.clone();
+            for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                encrypt(block, &encryption);
+            }
+            let mut blocks = plain.clone();
+            encrypt_blocks(&mut blocks, &encryption);
+            assert_eq!(blocks, expected);
+            for block in expected.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                decrypt(block, &decryption);
+            }
+            decrypt_blocks(&mut blocks, &decryption);
+            assert_eq!(blocks, expected);
+            assert_eq!(blocks, plain);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn block_wide_wrong_length_panic() {
+        block_encrypt128_x4(&mut [0u8; 48], &[0u32; N_SUBKEYS_128BIT]);
+    }
+
+    #[cfg(any(
+        feature = "fixslice",
+        feature = "small-tables",
+        feature = "compact-decrypt"
+    ))]
+    #[test]
+    fn small_tables_mix_column_works() {
+        // The column `DB 13 53 45` becomes `8E 4D A1 BC`.
+        let column = u32::from_le_bytes([0xDB, 0x13, 0x53, 0x45]);
+        let mixed = u32::from_le_bytes([0x8E, 0x4D, 0xA1, 0xBC]);
+        assert_eq!(small_tables::mix_column(column), mixed);
+        assert_eq!(small_tables::inv_mix_column(mixed), column);
+    }
 }
@@ -0,0 +1,265 @@
+//! # aes_hash
+//! `aes_hash` turns AES into a hash function, using it as the compression function of a
+//! Merkle–Damgård construction.
+//!
+//! Two classic single-block-length compression functions are provided:
+//!
+//! name | compression function
+//! - | -
+//! Matyas–Meyer–Oseas (MMO) | `H[i] = E(H[i-1], m[i]) xor m[i]`
+//! Davies–Meyer (DM) | `H[i] = E(m[i], H[i-1]) xor H[i-1]`
+//!
+//! where `E(k, p)` is the AES-128 encryption of `p` under the key `k`.
+//!
+//! **Attention!** The output of these hash functions is only 128 bits long, so they offer at most
+//! 64-bit collision resistance. Use them only where a protocol demands them (e.g. the Zigbee
+//! MMO hash), not as a general-purpose hash function.
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES, N_SUBKEYS_128BIT};
+
+/// Matyas–Meyer–Oseas compression function.
+///
+/// The chaining value is used as an AES-128 key to encrypt the message block, and the result is
+/// XORed with the message block: `H[i] = E(H[i-1], m[i]) xor m[i]`.
+///
+/// * *parameter* `chaining`: the slice (length = 16) that contains the chaining value. It is
+///   updated in place.
+/// * *parameter* `block`: the slice (length = 16) that contains a block of message.
+/// # Examples
+/// ```
+/// use aes_frast::aes_hash::mmo_compress;
+/// use aes_frast::BLOCKSIZE_IN_BYTES;
+///
+/// let mut chaining = [0u8; BLOCKSIZE_IN_BYTES];
+/// let block = [0u8; BLOCKSIZE_IN_BYTES];
+/// mmo_compress(&mut chaining, &block);
+/// // AES-128 of the zero block under the zero key
+/// assert_eq!(chaining, [0x66, 0xE9, 0x4B, 0xD4, 0xEF, 0x8A, 0x2C, 0x3B,
+///                       0x88, 0x4C, 0xFA, 0x59, 0xCA, 0x34, 0x2B, 0x2E]);
+/// ```
+pub fn mmo_compress(chaining: &mut [u8], block: &[u8]) {
+    assert_eq!(chaining.len(), BLOCKSIZE_IN_BYTES);
+    assert_eq!(block.len(), BLOCKSIZE_IN_BYTES);
+    let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+    aes_core::key_schedule_encrypt128(chaining, &mut subkeys);
+    aes_core::block_encrypt128(block, chaining, &subkeys);
+    for j in 0..BLOCKSIZE_IN_BYTES {
+        chaining[j] ^= block[j];
+    }
+}
+
+/// Davies–Meyer compression function.
+///
+/// The message block is used as an AES-128 key to encrypt the chaining value, and the result is
+/// XORed with the previous chaining value: `H[i] = E(m[i], H[i-1]) xor H[i-1]`.
+///
+/// * *parameter* `chaining`: the slice (length = 16) that contains the chaining value. It is
+///   updated in place.
+/// * *parameter* `block`: the slice (length = 16) that contains a block of message.
+/// # Examples
+/// ```
+/// use aes_frast::aes_hash::davies_meyer_compress;
+/// use aes_frast::BLOCKSIZE_IN_BYTES;
+///
+/// let mut chaining = [0u8; BLOCKSIZE_IN_BYTES];
+/// let block = [0u8; BLOCKSIZE_IN_BYTES];
+/// davies_meyer_compress(&mut chaining, &block);
+/// // AES-128 of the zero block under the zero key
+/// assert_eq!(chaining, [0x66, 0xE9, 0x4B, 0xD4, 0xEF, 0x8A, 0x2C, 0x3B,
+///                       0x88, 0x4C, 0xFA, 0x59, 0xCA, 0x34, 0x2B, 0x2E]);
+/// ```
+pub fn davies_meyer_compress(chaining: &mut [u8], block: &[u8]) {
+    assert_eq!(chaining.len(), BLOCKSIZE_IN_BYTES);
+    assert_eq!(block.len(), BLOCKSIZE_IN_BYTES);
+    let mut subkeys = [0u32; N_SUBKEYS_128BIT];
+    let mut buffer = [0u8; BLOCKSIZE_IN_BYTES];
+    aes_core::key_schedule_encrypt128(block, &mut subkeys);
+    aes_core::block_encrypt128(chaining, &mut buffer, &subkeys);
+    for j in 0..BLOCKSIZE_IN_BYTES {
+        chaining[j] ^= buffer[j];
+    }
+}
+
+/// Merkle–Damgård construction over a 128-bit compression function.
+///
+/// The message is padded with a single `0x80` byte, then zeros, then its length in bits as a
+/// 64-bit big-endian integer (the usual Merkle–Damgård strengthening), so that the total length
+/// is a multiple of 16 bytes. Then the blocks are fed to `compress` one by one, starting from the
+/// chaining value `iv`.
+///
+/// * *parameter* `message`: the slice that contains the message.
+/// * *parameter* `iv`: the slice (length = 16) that contains the initial chaining value.
+/// * *parameter* `compress`: the compression function, such as [`mmo_compress`] or
+///   [`davies_meyer_compress`].
+/// * *return* : the final chaining value.
+/// # Examples
+/// ```
+/// use aes_frast::aes_hash::{davies_meyer_compress, davies_meyer_hash, merkle_damgard};
+///
+/// let digest = merkle_damgard(b"abc", &[0u8; 16], davies_meyer_compress);
+/// assert_eq!(digest, davies_meyer_hash(b"abc"));
+/// ```
+///
+/// [`mmo_compress`]: ../aes_hash/fn.mmo_compress.html
+/// [`davies_meyer_compress`]: ../aes_hash/fn.davies_meyer_compress.html
+pub fn merkle_damgard(
+    message: &[u8],
+    iv: &[u8],
+    compress: fn(&mut [u8], &[u8]),
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let bit_length = (message.len() as u64).wrapping_mul(8);
+    digest_with_tail(message, iv, compress, &bit_length.to_be_bytes())
+}
+
+/// Hash a message with the Matyas–Meyer–Oseas construction, as specified by Zigbee.
+///
+/// This is the "block-cipher-based cryptographic hash function" from the Zigbee specification,
+/// which is the MMO construction with an all-zero initial value and a special padding: the
+/// message is followed by a single `0x80` byte, then zeros, then its length in bits as a 16-bit
+/// big-endian integer. For messages of 2<sup>16</sup> bits (8192 bytes) or more, the
+/// length is a 32-bit big-endian integer followed by two zero bytes instead.
+///
+/// * *parameter* `message`: the slice that contains the message.
+/// * *return* : the 16 bytes digest.
+/// # Examples
+/// ```
+/// use aes_frast::aes_hash::mmo_hash;
+///
+/// // This example came from the Zigbee specification
+/// let digest = mmo_hash(&[0xC0]);
+/// assert_eq!(digest, [0xAE, 0x3A, 0x10, 0x2A, 0x28, 0xD4, 0x3E, 0xE0,
+///                     0xD4, 0xA0, 0x9E, 0x22, 0x78, 0x8B, 0x20, 0x6C]);
+/// ```
+/// # Panics
+/// This function panics if the message is 2<sup>32</sup> bits long or longer.
+pub fn mmo_hash(message: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let bit_length = message.len() as u64 * 8;
+    let iv = [0u8; BLOCKSIZE_IN_BYTES];
+    if bit_length < 1 << 16 {
+        digest_with_tail(
+            message,
+            &iv,
+            mmo_compress,
+            &(bit_length as u16).to_be_bytes(),
+        )
+    } else {
+        assert!(bit_length < 1 << 32, "Message too long.");
+        let length_bytes = (bit_length as u32).to_be_bytes();
+        let tail = [
+            length_bytes[0],
+            length_bytes[1],
+            length_bytes[2],
+            length_bytes[3],
+            0,
+            0,
+        ];
+        digest_with_tail(message, &iv, mmo_compress, &tail)
+    }
+}
+
+/// Hash a message with the Davies–Meyer construction.
+///
+/// This is [`merkle_damgard`] with [`davies_meyer_compress`] and an all-zero initial value.
+///
+/// * *parameter* `message`: the slice that contains the message.
+/// * *return* : the 16 bytes digest.
+/// # Examples
+/// ```
+/// use aes_frast::aes_hash::davies_meyer_hash;
+///
+/// let digest = davies_meyer_hash(b"abc");
+/// assert_eq!(digest, [0x10, 0xD5, 0x40, 0xF6, 0xE1, 0xD7, 0xD2, 0xB0,
+///                     0x9B, 0x47, 0xA6, 0x5E, 0x6D, 0xE2, 0x93, 0x00]);
+/// ```
+///
+/// [`merkle_damgard`]: ../aes_hash/fn.merkle_damgard.html
+/// [`davies_meyer_compress`]: ../aes_hash/fn.davies_meyer_compress.html
+pub fn davies_meyer_hash(message: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    merkle_damgard(message, &[0u8; BLOCKSIZE_IN_BYTES], davies_meyer_compress)
+}
+
+/// Pad `message` with `0x80`, zeros and `tail` (the encoded length) to a multiple of 16 bytes, and
+/// compress all the blocks.
+fn digest_with_tail(
+    message: &[u8],
+    iv: &[u8],
+    compress: fn(&mut [u8], &[u8]),
+    tail: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    assert_eq!(iv.len(), BLOCKSIZE_IN_BYTES);
+    let mut chaining = [0u8; BLOCKSIZE_IN_BYTES];
+    chaining.copy_from_slice(iv);
+    // The complete blocks
+    let block_number = message.len() >> 4;
+    for i in 0..block_number {
+        let start = i << 4;
+        compress(&mut chaining, &message[start..(start + BLOCKSIZE_IN_BYTES)]);
+    }
+    // The last (tail) blocks, one or two of them
+    let rest = &message[(block_number << 4)..];
+    let mut last = [0u8; 2 * BLOCKSIZE_IN_BYTES];
+    last[..rest.len()].copy_from_slice(rest);
+    last[rest.len()] = 0x80;
+    let last_length = if rest.len() + 1 + tail.len() > BLOCKSIZE_IN_BYTES {
+        2 * BLOCKSIZE_IN_BYTES
+    } else {
+        BLOCKSIZE_IN_BYTES
+    };
+    last[(last_length - tail.len())..last_length].copy_from_slice(tail);
+    for start in (0..last_length).step_by(BLOCKSIZE_IN_BYTES) {
+        compress(&mut chaining, &last[start..(start + BLOCKSIZE_IN_BYTES)]);
+    }
+    chaining
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mmo_hash_works() {
+        // This example came from the Zigbee specification
+        let message: Vec<u8> = (0xC0..0xD0).collect();
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0xA7, 0x97, 0x7E, 0x88, 0xBC, 0x0B, 0x61, 0xE8, 0x21, 0x08, 0x27, 0x10, 0x9A, 0x22,
+            0x8F, 0x2D,
+        ];
+        assert_eq!(mmo_hash(&message), expected);
+        let message: Vec<u8> = (0xC0..0xE0).collect();
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0xB9, 0xB3, 0xD7, 0x76, 0x30, 0x24, 0x13, 0x17, 0xB8, 0x1C, 0x0D, 0x82, 0x70, 0x7C,
+            0xC3, 0x07,
+        ];
+        assert_eq!(mmo_hash(&message), expected);
+    }
+
+    #[test]
+    fn mmo_hash_long_message_works() {
+        // 8191 bytes still use the 16-bit length, 8192 bytes switch to the 32-bit length.
+        let message: Vec<u8> = (0..8192).map(|i| i as u8).collect();
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0x24, 0xEC, 0x2F, 0xE7, 0x5B, 0xBF, 0xFC, 0xB3, 0x47, 0x89, 0xBC, 0x06, 0x10, 0xE7,
+            0xF1, 0x65,
+        ];
+        assert_eq!(mmo_hash(&message[..8191]), expected);
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0xDC, 0x6B, 0x06, 0x87, 0xF0, 0x9F, 0x86, 0x07, 0x13, 0x1C, 0x17, 0x0B, 0x3B, 0xD3,
+            0x15, 0x91,
+        ];
+        assert_eq!(mmo_hash(&message), expected);
+    }
+
+    #[test]
+    fn davies_meyer_hash_works() {
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0x0E, 0xDD, 0x33, 0xD3, 0xC6, 0x21, 0xE5, 0x46, 0x45, 0x5B, 0xD8, 0xBA, 0x14, 0x18,
+            0xBE, 0xC8,
+        ];
+        assert_eq!(davies_meyer_hash(&[]), expected);
+        let message: Vec<u8> = (0..64).collect();
+        let expected: [u8; BLOCKSIZE_IN_BYTES] = [
+            0x4E, 0xA2, 0xC2, 0xDE, 0x25, 0x4F, 0xD3, 0x46, 0xD3, 0x01, 0x5C, 0x5B, 0x33, 0x6C,
+            0x31, 0x54,
+        ];
+        assert_eq!(davies_meyer_hash(&message), expected);
+    }
+}
@@ -1,27 +1,90 @@
 //! # aes_with_operation_mode
 //! `aes_with_operation_mode` allows you to use AES with operation modes like CBC, CFB and so on.  
+//! Each mode also has a function ending with `_with`, which takes any `BlockCipher128` instead
+//! of the scheduled keys, and a struct generic over the cipher, like `Cbc<C>`, with type aliases
+//! for AES, like `Aes128Cbc`.
+use super::aes_cipher::{Aes128, Aes192, Aes256};
 use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+#[cfg(feature = "parallel")]
+use super::aes_with_operation_mode_io::ParallelHooks;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+#[cfg(feature = "parallel")]
+use super::parallel;
 use std::mem;
-macro_rules! select_encrypt_function {
-    ($key:ident) => {
-        match $key.len() {
-            aes_core::N_SUBKEYS_128BIT => aes_core::block_encrypt128,
-            aes_core::N_SUBKEYS_192BIT => aes_core::block_encrypt192,
-            aes_core::N_SUBKEYS_256BIT => aes_core::block_encrypt256,
-            _ => panic!("Invalid key length."),
-        }
-    };
+/// The in-place block functions of `aes_core`.
+type InplaceFunction = fn(&mut [u8], &[u32]);
+/// The signature shared by the functions in this mod that take an IV.
+pub(crate) type ChainedFunction = fn(&[u8], &mut [u8], &[u32], &[u8]) -> Vec<u8>;
+
+/// The scheduled keys of the functions without `_with`, seen as a [`BlockCipher128`].
+pub(crate) struct Subkeys<'a> {
+    keys: &'a [u32],
+    encrypt: InplaceFunction,
+    decrypt: InplaceFunction,
 }
-macro_rules! select_decrypt_function {
-    ($key:ident) => {
-        match $key.len() {
-            aes_core::N_SUBKEYS_128BIT => aes_core::block_decrypt128,
-            aes_core::N_SUBKEYS_192BIT => aes_core::block_decrypt192,
-            aes_core::N_SUBKEYS_256BIT => aes_core::block_decrypt256,
+
+impl<'a> Subkeys<'a> {
+    pub(crate) fn new(keys: &'a [u32]) -> Self {
+        let (encrypt, decrypt): (InplaceFunction, InplaceFunction) = match keys.len() {
+            aes_core::N_SUBKEYS_128BIT => (
+                aes_core::block_encrypt128_inplace,
+                aes_core::block_decrypt128_inplace,
+            ),
+            aes_core::N_SUBKEYS_192BIT => (
+                aes_core::block_encrypt192_inplace,
+                aes_core::block_decrypt192_inplace,
+            ),
+            aes_core::N_SUBKEYS_256BIT => (
+                aes_core::block_encrypt256_inplace,
+                aes_core::block_decrypt256_inplace,
+            ),
             _ => panic!("Invalid key length."),
+        };
+        Self {
+            keys,
+            encrypt,
+            decrypt,
         }
-    };
+    }
+}
+
+impl BlockCipher128 for Subkeys<'_> {
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        (self.encrypt)(block, self.keys);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        (self.decrypt)(block, self.keys);
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        aes_core::encrypt_blocks(blocks, self.keys);
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        aes_core::decrypt_blocks(blocks, self.keys);
+    }
 }
+
+/// Encrypt the block in `input` and write it to `output`. Both must be 16 bytes long.
+fn encrypt_into<C: BlockCipher128 + ?Sized>(block_cipher: &C, input: &[u8], output: &mut [u8]) {
+    assert_eq!(output.len(), BLOCKSIZE_IN_BYTES);
+    let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+    block.copy_from_slice(input);
+    block_cipher.encrypt_block(&mut block);
+    output.copy_from_slice(&block);
+}
+
+/// Decrypt the block in `input` and write it to `output`. Both must be 16 bytes long.
+fn decrypt_into<C: BlockCipher128 + ?Sized>(block_cipher: &C, input: &[u8], output: &mut [u8]) {
+    assert_eq!(output.len(), BLOCKSIZE_IN_BYTES);
+    let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+    block.copy_from_slice(input);
+    block_cipher.decrypt_block(&mut block);
+    output.copy_from_slice(&block);
+}
+
 /// ECB (Electronic Codebook) Encryption
 ///
 /// This function encrypts a long plain from the first parameter and put the long cipher
@@ -79,17 +142,21 @@ macro_rules! select_decrypt_function {
 /// }
 /// ```
 pub fn ecb_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    // `>> 4` is the same as `/ 16` and `<< 4` is the same as `* 4`.
-    let block_number = plain.len() >> 4;
-    let mut start = 0;
-    let mut end = BLOCKSIZE_IN_BYTES;
-    for i in 0..block_number {
-        start = i << 4;
-        end = start + BLOCKSIZE_IN_BYTES;
-        encryptor(&plain[start..end], &mut cipher[start..end], keys);
-    }
-    cipher[start..end].to_owned()
+    ecb_enc_with(&Subkeys::new(keys), plain, cipher)
+}
+/// ECB (Electronic Codebook) Encryption with any [`BlockCipher128`].
+///
+/// The same as [`ecb_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ecb_enc`]: ../aes_with_operation_mode/fn.ecb_enc.html
+pub fn ecb_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+) -> Vec<u8> {
+    Ecb::new(block_cipher).encrypt(plain, cipher)
 }
 /// ECB (Electronic Codebook) Decryption
 ///
@@ -103,16 +170,21 @@ pub fn ecb_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32]) -> Vec<u8> {
 ///
 /// [`ecb_enc`]: ../aes_with_operation_mode/fn.ecb_enc.html
 pub fn ecb_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32]) -> Vec<u8> {
-    let decryptor = select_decrypt_function!(keys);
-    let block_number = cipher.len() >> 4;
-    let mut start = 0;
-    let mut end = BLOCKSIZE_IN_BYTES;
-    for i in 0..block_number {
-        start = i << 4;
-        end = start + BLOCKSIZE_IN_BYTES;
-        decryptor(&cipher[start..end], &mut plain[start..end], keys);
-    }
-    cipher[start..end].to_owned()
+    ecb_dec_with(&Subkeys::new(keys), cipher, plain)
+}
+/// ECB (Electronic Codebook) Decryption with any [`BlockCipher128`].
+///
+/// The same as [`ecb_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ecb_dec`]: ../aes_with_operation_mode/fn.ecb_dec.html
+pub fn ecb_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+) -> Vec<u8> {
+    Ecb::new(block_cipher).decrypt(cipher, plain)
 }
 /// CBC (Cipher Block Chaining) Encryption
 ///
@@ -173,28 +245,22 @@ pub fn ecb_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32]) -> Vec<u8> {
 /// }
 /// ```
 pub fn cbc_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
-    // The 1st (head) block
-    for j in 0..BLOCKSIZE_IN_BYTES {
-        buffer[j] = iv[j] ^ plain[j];
-    }
-    encryptor(&buffer, &mut cipher[..BLOCKSIZE_IN_BYTES], keys);
-    // The other blocks
-    let block_number = plain.len() >> 4;
-    let mut start = 0;
-    for i in 1..block_number {
-        start = i << 4;
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            buffer[j] = cipher[start + j - BLOCKSIZE_IN_BYTES] ^ plain[start + j];
-        }
-        encryptor(
-            &buffer,
-            &mut cipher[start..(start + BLOCKSIZE_IN_BYTES)],
-            keys,
-        );
-    }
-    cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    cbc_enc_with(&Subkeys::new(keys), plain, cipher, iv)
+}
+/// CBC (Cipher Block Chaining) Encryption with any [`BlockCipher128`].
+///
+/// The same as [`cbc_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cbc_enc`]: ../aes_with_operation_mode/fn.cbc_enc.html
+pub fn cbc_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Cbc::new(block_cipher).encrypt(plain, cipher, iv)
 }
 /// CBC (Cipher Block Chaining) Decryption
 ///
@@ -209,28 +275,22 @@ pub fn cbc_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<
 ///
 /// [`cbc_enc`]: ../aes_with_operation_mode/fn.cbc_enc.html
 pub fn cbc_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let decryptor = select_decrypt_function!(keys);
-    let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
-    // The 1st (head) block
-    decryptor(&cipher[..BLOCKSIZE_IN_BYTES], &mut buffer, keys);
-    for j in 0..BLOCKSIZE_IN_BYTES {
-        plain[j] = iv[j] ^ buffer[j];
-    }
-    // The other blocks
-    let block_number = cipher.len() >> 4;
-    let mut start = 0;
-    for i in 1..block_number {
-        start = i << 4;
-        decryptor(
-            &cipher[start..(start + BLOCKSIZE_IN_BYTES)],
-            &mut buffer,
-            keys,
-        );
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            plain[start + j] = cipher[start + j - BLOCKSIZE_IN_BYTES] ^ buffer[j];
-        }
-    }
-    cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    cbc_dec_with(&Subkeys::new(keys), cipher, plain, iv)
+}
+/// CBC (Cipher Block Chaining) Decryption with any [`BlockCipher128`].
+///
+/// The same as [`cbc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cbc_dec`]: ../aes_with_operation_mode/fn.cbc_dec.html
+pub fn cbc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Cbc::new(block_cipher).decrypt(cipher, plain, iv)
 }
 /// CFB (Cipher Feedback) Encryption
 ///
@@ -292,49 +352,22 @@ pub fn cbc_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<
 /// }
 /// ```
 pub fn cfb_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
-    // If input has only one block, consider it as the last block, not the 1st.
-    // If input has only two blocks, consider it has no middle blocks.
-    // The 1st (head) block
-    encryptor(iv, &mut buffer, keys);
-    let block_number = plain.len() >> 4;
-    let mut start = 0;
-    if plain.len() >= BLOCKSIZE_IN_BYTES {
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            cipher[j] = buffer[j] ^ plain[j];
-        }
-        // The middle blocks
-        for i in 1..block_number {
-            start = i << 4;
-            encryptor(
-                &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
-                &mut buffer,
-                keys,
-            );
-            for j in 0..BLOCKSIZE_IN_BYTES {
-                cipher[start + j] = buffer[j] ^ plain[start + j];
-            }
-        }
-    }
-    // The last (tail) block
-    match plain.len() & 0b1111 {
-        r if r != 0 => {
-            if block_number != 0 {
-                start = block_number << 4;
-                encryptor(
-                    &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
-                    &mut buffer,
-                    keys,
-                );
-            }
-            for j in 0..r {
-                cipher[start + j] = buffer[j] ^ plain[start + j];
-            }
-            cipher[start..(start + r)].to_owned()
-        }
-        _ => cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned(),
-    }
+    cfb_enc_with(&Subkeys::new(keys), plain, cipher, iv)
+}
+/// CFB (Cipher Feedback) Encryption with any [`BlockCipher128`].
+///
+/// The same as [`cfb_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_enc`]: ../aes_with_operation_mode/fn.cfb_enc.html
+pub fn cfb_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Cfb::new(block_cipher).encrypt(plain, cipher, iv)
 }
 /// CFB (Cipher Feedback) Decryption
 ///
@@ -352,50 +385,22 @@ pub fn cfb_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<
 ///
 /// [`cfb_enc`]: ../aes_with_operation_mode/fn.cfb_enc.html
 pub fn cfb_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    // You may think this function is the same as the `cfb_enc` function, but in fact they differ
-    // in the last line. Both functions return `&cipher[start..(start + BLOCKSIZE_IN_BYTES)]`, which is the first
-    // parameter in this function, while it's the second parameter in the `cfb_enc` function.
-    let encryptor = select_encrypt_function!(keys);
-    let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
-    // The 1st (head) block
-    encryptor(iv, &mut buffer, keys);
-    let block_number = plain.len() >> 4;
-    let mut start = 0;
-    if cipher.len() >= BLOCKSIZE_IN_BYTES {
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            plain[j] = buffer[j] ^ cipher[j]
-        }
-        // The middle blocks
-        for i in 1usize..block_number {
-            start = i << 4;
-            encryptor(
-                &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
-                &mut buffer,
-                keys,
-            );
-            for j in 0..BLOCKSIZE_IN_BYTES {
-                plain[start + j] = buffer[j] ^ cipher[start + j];
-            }
-        }
-    }
-    match cipher.len() & 0b1111 {
-        // The last (tail) block
-        r if r != 0 => {
-            if block_number != 0 {
-                start = block_number << 4;
-                encryptor(
-                    &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
-                    &mut buffer,
-                    keys,
-                );
-            }
-            for j in 0..r {
-                plain[start + j] = buffer[j] ^ cipher[start + j];
-            }
-            cipher[start..(start + r)].to_owned()
-        }
-        _ => cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned(),
-    }
+    cfb_dec_with(&Subkeys::new(keys), cipher, plain, iv)
+}
+/// CFB (Cipher Feedback) Decryption with any [`BlockCipher128`].
+///
+/// The same as [`cfb_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_dec`]: ../aes_with_operation_mode/fn.cfb_dec.html
+pub fn cfb_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Cfb::new(block_cipher).decrypt(cipher, plain, iv)
 }
 /// OFB (Output Feedback) Encryption and Decryption
 ///
@@ -466,42 +471,100 @@ pub fn cfb_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<
 /// [`key_schedule_decrypt192`]: ../aes_core/fn.key_schedule_decrypt192.html
 /// [`key_schedule_decrypt256`]: ../aes_core/fn.key_schedule_decrypt256.html
 pub fn ofb_enc_dec(input: &[u8], output: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    let mut buffer_new = vec![0; BLOCKSIZE_IN_BYTES];
-    let mut buffer_last = vec![0; BLOCKSIZE_IN_BYTES];
-    // The 1st (head) block
-    encryptor(iv, &mut buffer_new, keys);
-    let block_number = input.len() >> 4;
-    let mut start;
-    if input.len() >= BLOCKSIZE_IN_BYTES {
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            output[j] = buffer_new[j] ^ input[j]
-        }
-        // The middle blocks
-        for i in 1..block_number {
-            start = i << 4;
-            mem::swap(&mut buffer_new, &mut buffer_last);
-            encryptor(&buffer_last, &mut buffer_new, keys);
-            for j in 0..BLOCKSIZE_IN_BYTES {
-                output[start + j] = buffer_new[j] ^ input[start + j];
-            }
-        }
-    } else {
-        buffer_new = Vec::from(iv);
-    }
-    match input.len() & 0b1111 {
-        // The last (tail) block
-        r if r != 0 => {
-            start = block_number << 4;
-            mem::swap(&mut buffer_new, &mut buffer_last);
-            encryptor(&buffer_last, &mut buffer_new, keys);
-            for j in 0..r {
-                output[start + j] = buffer_new[j] ^ input[start + j];
-            }
-        }
-        _ => {}
-    }
-    buffer_new
+    ofb_enc_dec_with(&Subkeys::new(keys), input, output, iv)
+}
+/// OFB (Output Feedback) Encryption and Decryption with any [`BlockCipher128`].
+///
+/// The same as [`ofb_enc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ofb_enc_dec`]: ../aes_with_operation_mode/fn.ofb_enc_dec.html
+pub fn ofb_enc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Ofb::new(block_cipher).encrypt_decrypt(input, output, iv)
+}
+/// CTR (Counter) Encryption and Decryption
+///
+/// This mode doesn't require padding.
+///
+/// This function encrypts (or decrypts) a long input from the first parameter and put the long
+/// output into the second parameter, using the scheduled keys and the initial counter block
+/// in the third and fourth parameters.  
+/// The counter block is increased by 1 for each block, as a 128-bit big-endian integer (modulo
+/// 2<sup>128</sup>).  
+/// Finally, it returns the next counter block, i.e. the one after the last used counter block.
+/// So the data can be processed in pieces whose lengths are multiples of 16 bytes, passing the
+/// returned counter block to the next call.  
+/// **\[Attention!\]** Never use the same counter block twice with the same key.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// // This example came from NIST SP 800-38A F.5.1
+/// let length: usize = 64;
+/// let plain: Vec<u8> = vec![0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96,
+///                           0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17, 0x2A,
+///                           0xAE, 0x2D, 0x8A, 0x57, 0x1E, 0x03, 0xAC, 0x9C,
+///                           0x9E, 0xB7, 0x6F, 0xAC, 0x45, 0xAF, 0x8E, 0x51,
+///                           0x30, 0xC8, 0x1C, 0x46, 0xA3, 0x5C, 0xE4, 0x11,
+///                           0xE5, 0xFB, 0xC1, 0x19, 0x1A, 0x0A, 0x52, 0xEF,
+///                           0xF6, 0x9F, 0x24, 0x45, 0xDF, 0x4F, 0x9B, 0x17,
+///                           0xAD, 0x2B, 0x41, 0x7B, 0xE6, 0x6C, 0x37, 0x10];
+/// let mut cipher = vec![0u8; length];
+/// let mut dec_cipher = vec![0u8; length];
+/// let o_key: Vec<u8> = vec![0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///                           0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C];
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// let counter: Vec<u8> = vec![0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7,
+///                             0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF];
+///
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+/// let next_counter = aes_with_operation_mode::ctr_enc_dec(&plain, &mut cipher, &w_keys, &counter);
+///
+/// let expected_encrypted = vec![0x87, 0x4D, 0x61, 0x91, 0xB6, 0x20, 0xE3, 0x26,
+///                               0x1B, 0xEF, 0x68, 0x64, 0x99, 0x0D, 0xB6, 0xCE,
+///                               0x98, 0x06, 0xF6, 0x6B, 0x79, 0x70, 0xFD, 0xFF,
+///                               0x86, 0x17, 0x18, 0x7B, 0xB9, 0xFF, 0xFD, 0xFF,
+///                               0x5A, 0xE4, 0xDF, 0x3E, 0xDB, 0xD5, 0xD3, 0x5E,
+///                               0x5B, 0x4F, 0x09, 0x02, 0x0D, 0xB0, 0x3E, 0xAB,
+///                               0x1E, 0x03, 0x1D, 0xDA, 0x2F, 0xBE, 0x03, 0xD1,
+///                               0x79, 0x21, 0x70, 0xA0, 0xF3, 0x00, 0x9C, 0xEE];
+///
+/// for i in 0..length {
+///     assert_eq!(expected_encrypted[i], cipher[i], "ERROR in encrypt {}", i);
+/// }
+/// assert_eq!(next_counter, vec![0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7,
+///                               0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFF, 0x03]);
+///
+/// // Notice: CTR only uses block-encryption, no matter we use it as encryption or decryption.
+/// aes_with_operation_mode::ctr_enc_dec(&cipher, &mut dec_cipher, &w_keys, &counter);
+///
+/// for i in 0..length {
+///     assert_eq!(plain[i], dec_cipher[i], "ERROR in decrypt {}", i);
+/// }
+/// ```
+pub fn ctr_enc_dec(input: &[u8], output: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    ctr_enc_dec_with(&Subkeys::new(keys), input, output, iv)
+}
+/// CTR (Counter) Encryption and Decryption with any [`BlockCipher128`].
+///
+/// The same as [`ctr_enc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ctr_enc_dec`]: ../aes_with_operation_mode/fn.ctr_enc_dec.html
+pub fn ctr_enc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Ctr::new(block_cipher).encrypt_decrypt(input, output, iv)
 }
 /// PCBC (Propagating Cipher Block Chaining) Encryption (**Experimental**)
 ///
@@ -549,33 +612,23 @@ pub fn ofb_enc_dec(input: &[u8], output: &mut [u8], keys: &[u32], iv: &[u8]) ->
 /// }
 /// ```
 pub fn pcbc_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    let mut buffer = vec![0; BLOCKSIZE_IN_BYTES];
-    // The 1st (head) block
-    for j in 0..BLOCKSIZE_IN_BYTES {
-        buffer[j] = iv[j] ^ plain[j];
-    }
-    encryptor(&buffer, &mut cipher[..BLOCKSIZE_IN_BYTES], keys);
-    // The other blocks
-    let block_number = plain.len() >> 4;
-    let mut start = 0;
-    for i in 1..block_number {
-        start = i << 4;
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            buffer[j] = cipher[start + j - BLOCKSIZE_IN_BYTES]
-                ^ plain[start + j - BLOCKSIZE_IN_BYTES]
-                ^ plain[start + j];
-        }
-        encryptor(
-            &buffer,
-            &mut cipher[start..(start + BLOCKSIZE_IN_BYTES)],
-            keys,
-        );
-    }
-    for j in 0..BLOCKSIZE_IN_BYTES {
-        buffer[j] = cipher[start + j] ^ plain[start + j];
-    }
-    buffer
+    pcbc_enc_with(&Subkeys::new(keys), plain, cipher, iv)
+}
+/// PCBC (Propagating Cipher Block Chaining) Encryption with any [`BlockCipher128`]
+/// (**Experimental**).
+///
+/// The same as [`pcbc_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`pcbc_enc`]: ../aes_with_operation_mode/fn.pcbc_enc.html
+pub fn pcbc_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Pcbc::new(block_cipher).encrypt(plain, cipher, iv)
 }
 /// PCBC (Propagating Cipher Block Chaining) Decryption (**Experimental**)
 ///
@@ -592,33 +645,23 @@ pub fn pcbc_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec
 ///
 /// [`pcbc_enc`]: ../aes_with_operation_mode/fn.pcbc_enc.html
 pub fn pcbc_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let decryptor = select_decrypt_function!(keys);
-    let mut buffer = vec![0; BLOCKSIZE_IN_BYTES];
-    // The 1st (head) block
-    decryptor(&cipher[..BLOCKSIZE_IN_BYTES], &mut buffer, keys);
-    for j in 0..BLOCKSIZE_IN_BYTES {
-        plain[j] = iv[j] ^ buffer[j];
-    }
-    // The other block
-    let block_number = cipher.len() >> 4;
-    let mut start = 0;
-    for i in 1usize..block_number {
-        start = i << 4;
-        decryptor(
-            &cipher[start..(start + BLOCKSIZE_IN_BYTES)],
-            &mut buffer,
-            keys,
-        );
-        for j in 0..BLOCKSIZE_IN_BYTES {
-            plain[start + j] = cipher[start + j - BLOCKSIZE_IN_BYTES]
-                ^ plain[start + j - BLOCKSIZE_IN_BYTES]
-                ^ buffer[j];
-        }
-    }
-    for j in 0..BLOCKSIZE_IN_BYTES {
-        buffer[j] = cipher[start + j] ^ plain[start + j];
-    }
-    buffer
+    pcbc_dec_with(&Subkeys::new(keys), cipher, plain, iv)
+}
+/// PCBC (Propagating Cipher Block Chaining) Decryption with any [`BlockCipher128`]
+/// (**Experimental**).
+///
+/// The same as [`pcbc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`pcbc_dec`]: ../aes_with_operation_mode/fn.pcbc_dec.html
+pub fn pcbc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Pcbc::new(block_cipher).decrypt(cipher, plain, iv)
 }
 /// CFB (Cipher Feedback) Encryption with 8-bit feedback size (**Experimental**)
 /// # Examples
@@ -670,16 +713,23 @@ pub fn pcbc_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec
 /// }
 /// ```
 pub fn cfb_8_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    let mut out_buffer = vec![0; BLOCKSIZE_IN_BYTES];
-    let mut in_buffer = iv.to_owned();
-    for i in 0..plain.len() {
-        encryptor(&in_buffer, &mut out_buffer, keys);
-        cipher[i] = out_buffer[0] ^ plain[i];
-        in_buffer.rotate_left(1);
-        in_buffer[15] = cipher[i];
-    }
-    out_buffer
+    cfb_8_enc_with(&Subkeys::new(keys), plain, cipher, iv)
+}
+/// CFB (Cipher Feedback) Encryption with 8-bit feedback size with any [`BlockCipher128`]
+/// (**Experimental**).
+///
+/// The same as [`cfb_8_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_8_enc`]: ../aes_with_operation_mode/fn.cfb_8_enc.html
+pub fn cfb_8_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Cfb8::new(block_cipher).encrypt(plain, cipher, iv)
 }
 /// CFB (Cipher Feedback) Decryption with 8-bit feedback size (**Experimental**)
 /// # Examples
@@ -687,14 +737,1472 @@ pub fn cfb_8_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Ve
 ///
 /// [`cfb_8_enc`]: ../aes_with_operation_mode/fn.cfb_8_enc.html
 pub fn cfb_8_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
-    let encryptor = select_encrypt_function!(keys);
-    let mut out_buffer = vec![0; BLOCKSIZE_IN_BYTES];
-    let mut in_buffer = iv.to_owned();
-    for i in 0..cipher.len() {
-        encryptor(&in_buffer, &mut out_buffer, keys);
-        plain[i] = out_buffer[0] ^ cipher[i];
-        in_buffer.rotate_left(1);
-        in_buffer[15] = cipher[i];
-    }
-    out_buffer
+    cfb_8_dec_with(&Subkeys::new(keys), cipher, plain, iv)
+}
+/// CFB (Cipher Feedback) Decryption with 8-bit feedback size with any [`BlockCipher128`]
+/// (**Experimental**).
+///
+/// The same as [`cfb_8_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_8_dec`]: ../aes_with_operation_mode/fn.cfb_8_dec.html
+pub fn cfb_8_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Cfb8::new(block_cipher).decrypt(cipher, plain, iv)
+}
+/// CFB (Cipher Feedback) Encryption with 1-bit feedback size (**Experimental**)
+///
+/// The bits are numbered from the most significant bit of the first byte, as in
+/// NIST SP 800-38A. Only the first `bits` bits of `cipher` are written; the rest of its last
+/// byte is left as it is.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// // This example came from NIST SP 800-38A F.3.1, extended to 36 bits.
+/// let o_key: Vec<u8> = vec![0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///                           0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C];
+/// let iv: Vec<u8> = (0..16).collect();
+/// let plain: Vec<u8> = vec![0x6B, 0xC1, 0xBE, 0xE2, 0x2E];
+/// let mut cipher = vec![0u8; 5];
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+///
+/// aes_with_operation_mode::cfb_1_enc(&plain, &mut cipher, 36, &w_keys, &iv);
+/// assert_eq!(cipher[..4], [0x68, 0xB3, 0xA2, 0x64]);
+/// assert_eq!(cipher[4] & 0xF0, 0xF0);
+///
+/// // Like the other CFB modes, decryption uses the encryption keys.
+/// let mut decrypted = vec![0u8; 5];
+/// aes_with_operation_mode::cfb_1_dec(&cipher, &mut decrypted, 36, &w_keys, &iv);
+/// assert_eq!(decrypted[..4], plain[..4]);
+/// assert_eq!(decrypted[4] & 0xF0, plain[4] & 0xF0);
+/// ```
+/// # Panics
+/// This function panics if `plain` or `cipher` is shorter than `bits` bits.
+pub fn cfb_1_enc(plain: &[u8], cipher: &mut [u8], bits: usize, keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    cfb_1_enc_with(&Subkeys::new(keys), plain, cipher, bits, iv)
+}
+/// CFB (Cipher Feedback) Encryption with 1-bit feedback size with any [`BlockCipher128`]
+/// (**Experimental**).
+///
+/// The same as [`cfb_1_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_1_enc`]: ../aes_with_operation_mode/fn.cfb_1_enc.html
+pub fn cfb_1_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    bits: usize,
+    iv: &[u8],
+) -> Vec<u8> {
+    Cfb1::new(block_cipher).encrypt(plain, cipher, bits, iv)
+}
+/// CFB (Cipher Feedback) Decryption with 1-bit feedback size (**Experimental**)
+/// # Examples
+/// Please refer to the [`cfb_1_enc`] function, codes are included there.
+/// # Panics
+/// This function panics if `cipher` or `plain` is shorter than `bits` bits.
+///
+/// [`cfb_1_enc`]: ../aes_with_operation_mode/fn.cfb_1_enc.html
+pub fn cfb_1_dec(cipher: &[u8], plain: &mut [u8], bits: usize, keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    cfb_1_dec_with(&Subkeys::new(keys), cipher, plain, bits, iv)
+}
+/// CFB (Cipher Feedback) Decryption with 1-bit feedback size with any [`BlockCipher128`]
+/// (**Experimental**).
+///
+/// The same as [`cfb_1_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_1_dec`]: ../aes_with_operation_mode/fn.cfb_1_dec.html
+pub fn cfb_1_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    bits: usize,
+    iv: &[u8],
+) -> Vec<u8> {
+    Cfb1::new(block_cipher).decrypt(cipher, plain, bits, iv)
+}
+/// IGE (Infinite Garble Extension) Encryption (**Experimental**)
+///
+/// This function encrypts a long plain from the first parameter and put the long cipher
+/// into the second parameter, using the scheduled keys and the 32-byte initialization vector
+/// in the third and fourth parameters. The IV is the block before the first cipher block
+/// followed by the block before the first plain block, as in OpenSSL and MTProto (Telegram).  
+/// Each cipher block is `E(plain block ^ previous cipher block) ^ previous plain block`.  
+/// Finally, it returns the IV for the next call: the final block of the cipher followed by the
+/// final block of the plain.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// // This example came from the IGE tests of OpenSSL.
+/// let o_key: Vec<u8> = (0..16).collect();
+/// let iv: Vec<u8> = (0..32).collect();
+/// let plain = vec![0u8; 32];
+/// let mut cipher = vec![0u8; 32];
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+///
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+/// aes_with_operation_mode::ige_enc(&plain, &mut cipher, &w_keys, &iv);
+/// assert_eq!(cipher, vec![0x1A, 0x85, 0x19, 0xA6, 0x55, 0x7B, 0xE6, 0x52,
+///                         0xE9, 0xDA, 0x8E, 0x43, 0xDA, 0x4E, 0xF4, 0x45,
+///                         0x3C, 0xF4, 0x56, 0xB4, 0xCA, 0x48, 0x8A, 0xA3,
+///                         0x83, 0xC7, 0x9C, 0x98, 0xB3, 0x47, 0x97, 0xCB]);
+///
+/// let mut decrypted = vec![0u8; 32];
+/// aes_core::key_schedule_decrypt_auto(&o_key, &mut w_keys);
+/// aes_with_operation_mode::ige_dec(&cipher, &mut decrypted, &w_keys, &iv);
+/// assert_eq!(decrypted, plain);
+/// ```
+pub fn ige_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    ige_enc_with(&Subkeys::new(keys), plain, cipher, iv)
+}
+/// IGE (Infinite Garble Extension) Encryption with any [`BlockCipher128`] (**Experimental**).
+///
+/// The same as [`ige_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ige_enc`]: ../aes_with_operation_mode/fn.ige_enc.html
+pub fn ige_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Ige::new(block_cipher).encrypt(plain, cipher, iv)
+}
+/// IGE (Infinite Garble Extension) Decryption (**Experimental**)
+///
+/// This function decrypts a long cipher from the first parameter and put the long plain
+/// into the second parameter, using the scheduled keys and the 32-byte initialization vector
+/// of the encryption in the third and fourth parameters.  
+/// Finally, it returns the IV for the next call: the final block of the cipher followed by the
+/// final block of the plain.
+/// # Examples
+/// Please refer to the [`ige_enc`] function, codes are included there.
+///
+/// [`ige_enc`]: ../aes_with_operation_mode/fn.ige_enc.html
+pub fn ige_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    ige_dec_with(&Subkeys::new(keys), cipher, plain, iv)
+}
+/// IGE (Infinite Garble Extension) Decryption with any [`BlockCipher128`] (**Experimental**).
+///
+/// The same as [`ige_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ige_dec`]: ../aes_with_operation_mode/fn.ige_dec.html
+pub fn ige_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    Ige::new(block_cipher).decrypt(cipher, plain, iv)
+}
+/// CBC with ciphertext stealing (CBC-CTS) Encryption, as in Kerberos
+///
+/// This is the CBC-CTS of the `aes*-cts-hmac-sha1-96` encryption types of Kerberos
+/// (RFC 3962), which is CBC-CS3 of the addendum to NIST SP 800-38A. The plain is at least 16
+/// bytes long, but doesn't need to be a multiple of 16: the last block is padded with zeros,
+/// the padded data is encrypted in CBC mode, the last two cipher blocks are swapped and the
+/// cipher is truncated to the length of the plain. A plain of exactly one block is plain CBC.
+/// Apart from that, the last two blocks are always swapped, even when the plain is a multiple
+/// of 16 bytes.  
+/// Finally, it returns the IV for the next call, which is the final block of the CBC cipher
+/// before swapping (the "Next IV" of RFC 3962).
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// // This example came from RFC 3962 Appendix B.
+/// let o_key = b"chicken teriyaki";
+/// let iv = [0u8; 16];
+/// let plain = b"I would like the ";
+/// let mut cipher = vec![0u8; plain.len()];
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+///
+/// aes_core::key_schedule_encrypt_auto(o_key, &mut w_keys);
+/// let next_iv = aes_with_operation_mode::cbc_cts_enc(plain, &mut cipher, &w_keys, &iv);
+/// assert_eq!(cipher, vec![0xC6, 0x35, 0x35, 0x68, 0xF2, 0xBF, 0x8C, 0xB4,
+///                         0xD8, 0xA5, 0x80, 0x36, 0x2D, 0xA7, 0xFF, 0x7F,
+///                         0x97]);
+/// assert_eq!(next_iv, cipher[..16]);
+///
+/// let mut decrypted = vec![0u8; plain.len()];
+/// aes_core::key_schedule_decrypt_auto(o_key, &mut w_keys);
+/// aes_with_operation_mode::cbc_cts_dec(&cipher, &mut decrypted, &w_keys, &iv);
+/// assert_eq!(decrypted, plain);
+/// ```
+/// # Panics
+/// This function panics if the plain is shorter than 16 bytes.
+pub fn cbc_cts_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    cbc_cts_enc_with(&Subkeys::new(keys), plain, cipher, iv)
+}
+/// CBC with ciphertext stealing (CBC-CTS) Encryption with any [`BlockCipher128`].
+///
+/// The same as [`cbc_cts_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cbc_cts_enc`]: ../aes_with_operation_mode/fn.cbc_cts_enc.html
+pub fn cbc_cts_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    plain: &[u8],
+    cipher: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    CbcCts::new(block_cipher).encrypt(plain, cipher, iv)
+}
+/// CBC with ciphertext stealing (CBC-CTS) Decryption, as in Kerberos
+///
+/// The reverse of [`cbc_cts_enc`], with the keys for decryption. It returns the same IV for the
+/// next call as the encryption.
+/// # Examples
+/// Please refer to the [`cbc_cts_enc`] function, codes are included there.
+/// # Panics
+/// This function panics if the cipher is shorter than 16 bytes.
+///
+/// [`cbc_cts_enc`]: ../aes_with_operation_mode/fn.cbc_cts_enc.html
+pub fn cbc_cts_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32], iv: &[u8]) -> Vec<u8> {
+    cbc_cts_dec_with(&Subkeys::new(keys), cipher, plain, iv)
+}
+/// CBC with ciphertext stealing (CBC-CTS) Decryption with any [`BlockCipher128`].
+///
+/// The same as [`cbc_cts_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cbc_cts_dec`]: ../aes_with_operation_mode/fn.cbc_cts_dec.html
+pub fn cbc_cts_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    cipher: &[u8],
+    plain: &mut [u8],
+    iv: &[u8],
+) -> Vec<u8> {
+    CbcCts::new(block_cipher).decrypt(cipher, plain, iv)
+}
+/// ECB (Electronic Codebook) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Ecb<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Ecb<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`ecb_enc`].
+    ///
+    /// [`ecb_enc`]: ../aes_with_operation_mode/fn.ecb_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8]) -> Vec<u8> {
+        // The blocks are independent, so they go to the cipher all at once.
+        let length = plain.len() & !0b1111;
+        cipher[..length].copy_from_slice(&plain[..length]);
+        self.cipher.encrypt_blocks(&mut cipher[..length]);
+        let start = length.saturating_sub(BLOCKSIZE_IN_BYTES);
+        cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    }
+
+    /// The same as [`ecb_dec`].
+    ///
+    /// [`ecb_dec`]: ../aes_with_operation_mode/fn.ecb_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8]) -> Vec<u8> {
+        let length = cipher.len() & !0b1111;
+        plain[..length].copy_from_slice(&cipher[..length]);
+        self.cipher.decrypt_blocks(&mut plain[..length]);
+        let start = length.saturating_sub(BLOCKSIZE_IN_BYTES);
+        cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    }
+
+    /// The same as [`encrypt`], on up to `threads` threads.
+    ///
+    /// Data of less than 256 blocks per thread is not worth the threads, and is processed on the
+    /// calling thread only.
+    ///
+    /// [`encrypt`]: ../aes_with_operation_mode/struct.Ecb.html#method.encrypt
+    #[cfg(feature = "parallel")]
+    pub fn encrypt_parallel(&self, plain: &[u8], cipher: &mut [u8], threads: usize) -> Vec<u8>
+    where
+        C: Sync,
+    {
+        let length = plain.len() & !0b1111;
+        parallel::for_each_chunk(&plain[..length], cipher, threads, |_, input, output| {
+            output.copy_from_slice(input);
+            self.cipher.encrypt_blocks(output);
+        });
+        let start = length.saturating_sub(BLOCKSIZE_IN_BYTES);
+        cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    }
+
+    /// The same as [`decrypt`], on up to `threads` threads.
+    ///
+    /// Data of less than 256 blocks per thread is not worth the threads, and is processed on the
+    /// calling thread only.
+    ///
+    /// [`decrypt`]: ../aes_with_operation_mode/struct.Ecb.html#method.decrypt
+    #[cfg(feature = "parallel")]
+    pub fn decrypt_parallel(&self, cipher: &[u8], plain: &mut [u8], threads: usize) -> Vec<u8>
+    where
+        C: Sync,
+    {
+        let length = cipher.len() & !0b1111;
+        parallel::for_each_chunk(&cipher[..length], plain, threads, |_, input, output| {
+            output.copy_from_slice(input);
+            self.cipher.decrypt_blocks(output);
+        });
+        let start = length.saturating_sub(BLOCKSIZE_IN_BYTES);
+        cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    }
+}
+
+/// CBC (Cipher Block Chaining) mode over a [`BlockCipher128`].
+///
+/// The modes over AES have type aliases, such as [`Aes128Cbc`].
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode::{cbc_enc, Aes128Cbc};
+/// use aes_frast::Aes128;
+///
+/// let aes = Aes128::new(&[0x2Bu8; 16]);
+/// let iv = [0xA5u8; 16];
+/// let plain: Vec<u8> = (0..32).collect();
+/// let mut expected = vec![0u8; 32];
+/// cbc_enc(&plain, &mut expected, aes.encryption_subkeys(), &iv);
+///
+/// let cbc = Aes128Cbc::new(aes);
+/// let mut cipher = vec![0u8; 32];
+/// let last_block = cbc.encrypt(&plain, &mut cipher, &iv);
+/// assert_eq!(cipher, expected);
+/// assert_eq!(last_block, &cipher[16..]);
+///
+/// let mut decrypted = vec![0u8; 32];
+/// cbc.decrypt(&cipher, &mut decrypted, &iv);
+/// assert_eq!(decrypted, plain);
+/// ```
+///
+/// [`Aes128Cbc`]: ../aes_with_operation_mode/type.Aes128Cbc.html
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Cbc<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Cbc<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`cbc_enc`].
+    ///
+    /// [`cbc_enc`]: ../aes_with_operation_mode/fn.cbc_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
+        // The 1st (head) block
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            buffer[j] = iv[j] ^ plain[j];
+        }
+        encrypt_into(&self.cipher, &buffer, &mut cipher[..BLOCKSIZE_IN_BYTES]);
+        // The other blocks
+        let block_number = plain.len() >> 4;
+        let mut start = 0;
+        for i in 1..block_number {
+            start = i << 4;
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                buffer[j] = cipher[start + j - BLOCKSIZE_IN_BYTES] ^ plain[start + j];
+            }
+            encrypt_into(
+                &self.cipher,
+                &buffer,
+                &mut cipher[start..(start + BLOCKSIZE_IN_BYTES)],
+            );
+        }
+        cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned()
+    }
+
+    /// The same as [`cbc_dec`].
+    ///
+    /// [`cbc_dec`]: ../aes_with_operation_mode/fn.cbc_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        // Unlike encryption, decryption doesn't chain: all the blocks are decrypted at once,
+        // then each one is XORed with the cipher block before it (the IV for the 1st one).
+        let length = (cipher.len() & !0b1111).max(BLOCKSIZE_IN_BYTES);
+        plain[..length].copy_from_slice(&cipher[..length]);
+        self.cipher.decrypt_blocks(&mut plain[..length]);
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            plain[j] ^= iv[j];
+        }
+        for j in BLOCKSIZE_IN_BYTES..length {
+            plain[j] ^= cipher[j - BLOCKSIZE_IN_BYTES];
+        }
+        cipher[(length - BLOCKSIZE_IN_BYTES)..length].to_owned()
+    }
+}
+
+/// CFB (Cipher Feedback) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Cfb<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Cfb<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`cfb_enc`].
+    ///
+    /// [`cfb_enc`]: ../aes_with_operation_mode/fn.cfb_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
+        // If input has only one block, consider it as the last block, not the 1st.
+        // If input has only two blocks, consider it has no middle blocks.
+        // The 1st (head) block
+        encrypt_into(&self.cipher, iv, &mut buffer);
+        let block_number = plain.len() >> 4;
+        let mut start = 0;
+        if plain.len() >= BLOCKSIZE_IN_BYTES {
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                cipher[j] = buffer[j] ^ plain[j];
+            }
+            // The middle blocks
+            for i in 1..block_number {
+                start = i << 4;
+                encrypt_into(
+                    &self.cipher,
+                    &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
+                    &mut buffer,
+                );
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    cipher[start + j] = buffer[j] ^ plain[start + j];
+                }
+            }
+        }
+        // The last (tail) block
+        match plain.len() & 0b1111 {
+            r if r != 0 => {
+                if block_number != 0 {
+                    start = block_number << 4;
+                    encrypt_into(
+                        &self.cipher,
+                        &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
+                        &mut buffer,
+                    );
+                }
+                for j in 0..r {
+                    cipher[start + j] = buffer[j] ^ plain[start + j];
+                }
+                cipher[start..(start + r)].to_owned()
+            }
+            _ => cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned(),
+        }
+    }
+
+    /// The same as [`cfb_dec`].
+    ///
+    /// [`cfb_dec`]: ../aes_with_operation_mode/fn.cfb_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        // You may think this function is the same as the `cfb_enc` function, but in fact they differ
+        // in the last line. Both functions return `&cipher[start..(start + BLOCKSIZE_IN_BYTES)]`, which is the first
+        // parameter in this function, while it's the second parameter in the `cfb_enc` function.
+        let mut buffer: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
+        // The 1st (head) block
+        encrypt_into(&self.cipher, iv, &mut buffer);
+        let block_number = plain.len() >> 4;
+        let mut start = 0;
+        if cipher.len() >= BLOCKSIZE_IN_BYTES {
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                plain[j] = buffer[j] ^ cipher[j]
+            }
+            // The middle blocks
+            for i in 1usize..block_number {
+                start = i << 4;
+                encrypt_into(
+                    &self.cipher,
+                    &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
+                    &mut buffer,
+                );
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    plain[start + j] = buffer[j] ^ cipher[start + j];
+                }
+            }
+        }
+        match cipher.len() & 0b1111 {
+            // The last (tail) block
+            r if r != 0 => {
+                if block_number != 0 {
+                    start = block_number << 4;
+                    encrypt_into(
+                        &self.cipher,
+                        &cipher[(start - BLOCKSIZE_IN_BYTES)..start],
+                        &mut buffer,
+                    );
+                }
+                for j in 0..r {
+                    plain[start + j] = buffer[j] ^ cipher[start + j];
+                }
+                cipher[start..(start + r)].to_owned()
+            }
+            _ => cipher[start..(start + BLOCKSIZE_IN_BYTES)].to_owned(),
+        }
+    }
+}
+
+/// OFB (Output Feedback) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Ofb<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Ofb<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`ofb_enc_dec`].
+    ///
+    /// [`ofb_enc_dec`]: ../aes_with_operation_mode/fn.ofb_enc_dec.html
+    pub fn encrypt_decrypt(&self, input: &[u8], output: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut buffer_new = vec![0; BLOCKSIZE_IN_BYTES];
+        let mut buffer_last = vec![0; BLOCKSIZE_IN_BYTES];
+        // The 1st (head) block
+        encrypt_into(&self.cipher, iv, &mut buffer_new);
+        let block_number = input.len() >> 4;
+        let mut start;
+        if input.len() >= BLOCKSIZE_IN_BYTES {
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                output[j] = buffer_new[j] ^ input[j]
+            }
+            // The middle blocks
+            for i in 1..block_number {
+                start = i << 4;
+                mem::swap(&mut buffer_new, &mut buffer_last);
+                encrypt_into(&self.cipher, &buffer_last, &mut buffer_new);
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    output[start + j] = buffer_new[j] ^ input[start + j];
+                }
+            }
+        } else {
+            buffer_new = Vec::from(iv);
+        }
+        match input.len() & 0b1111 {
+            // The last (tail) block
+            r if r != 0 => {
+                start = block_number << 4;
+                mem::swap(&mut buffer_new, &mut buffer_last);
+                encrypt_into(&self.cipher, &buffer_last, &mut buffer_new);
+                for j in 0..r {
+                    output[start + j] = buffer_new[j] ^ input[start + j];
+                }
+            }
+            _ => {}
+        }
+        buffer_new
+    }
+}
+
+/// CTR (Counter) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Ctr<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Ctr<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`ctr_enc_dec`].
+    ///
+    /// [`ctr_enc_dec`]: ../aes_with_operation_mode/fn.ctr_enc_dec.html
+    pub fn encrypt_decrypt(&self, input: &[u8], output: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        assert_eq!(iv.len(), BLOCKSIZE_IN_BYTES);
+        let mut counter: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
+        counter.copy_from_slice(iv);
+        // The keystream is computed 8 counter blocks at a time.
+        let mut buffer = [0u8; 8 * BLOCKSIZE_IN_BYTES];
+        for (input_chunk, output_chunk) in input
+            .chunks(buffer.len())
+            .zip(output.chunks_mut(buffer.len()))
+        {
+            let keystream = &mut buffer[..((input_chunk.len() + 0b1111) & !0b1111)];
+            for block in keystream.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                block.copy_from_slice(&counter);
+                counter = u128::from_be_bytes(counter).wrapping_add(1).to_be_bytes();
+            }
+            self.cipher.encrypt_blocks(keystream);
+            for j in 0..input_chunk.len() {
+                output_chunk[j] = keystream[j] ^ input_chunk[j];
+            }
+        }
+        counter.to_vec()
+    }
+
+    /// The same as [`encrypt_decrypt`], on up to `threads` threads. Each thread starts from the
+    /// counter of the first block of its chunk.
+    ///
+    /// Data of less than 256 blocks per thread is not worth the threads, and is processed on the
+    /// calling thread only.
+    ///
+    /// [`encrypt_decrypt`]: ../aes_with_operation_mode/struct.Ctr.html#method.encrypt_decrypt
+    #[cfg(feature = "parallel")]
+    pub fn encrypt_decrypt_parallel(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+        iv: &[u8],
+        threads: usize,
+    ) -> Vec<u8>
+    where
+        C: Sync,
+    {
+        assert_eq!(iv.len(), BLOCKSIZE_IN_BYTES);
+        let mut counter: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
+        counter.copy_from_slice(iv);
+        let counter = u128::from_be_bytes(counter);
+        parallel::for_each_chunk(input, output, threads, |first_block, input, output| {
+            let start = counter.wrapping_add(first_block as u128).to_be_bytes();
+            self.encrypt_decrypt(input, output, &start);
+        });
+        let blocks = input.len().div_ceil(BLOCKSIZE_IN_BYTES);
+        counter.wrapping_add(blocks as u128).to_be_bytes().to_vec()
+    }
+
+    /// The same as [`encrypt_decrypt_parallel`], reporting the progress and checking for
+    /// cancellation through `hooks`.
+    ///
+    /// On cancellation, `output` is zeroed, so no partial output is left.
+    /// * *return* : the next counter, or `AesError::Cancelled`.
+    /// # Examples
+    /// ```
+    /// use aes_frast::aes_cipher::Aes128;
+    /// use aes_frast::aes_with_operation_mode::Aes128Ctr;
+    /// use aes_frast::aes_with_operation_mode_io::{CancelToken, ParallelHooks};
+    /// use aes_frast::AesError;
+    ///
+    /// let ctr = Aes128Ctr::new(Aes128::new(&[0x42u8; 16]));
+    /// let iv = [0x24u8; 16];
+    /// let plain = vec![0x5Au8; 1 << 20];
+    /// let mut cipher = vec![0u8; plain.len()];
+    ///
+    /// let token = CancelToken::new();
+    /// let progress = |processed: u64| {
+    ///     // e.g. the "Cancel" button of a GUI, pressed half way.
+    ///     if processed >= 1 << 19 {
+    ///         token.cancel();
+    ///     }
+    /// };
+    /// let hooks = ParallelHooks {
+    ///     progress: Some(&progress),
+    ///     cancel: Some(&token),
+    /// };
+    /// let result = ctr.encrypt_decrypt_parallel_with_hooks(&plain, &mut cipher, &iv, 4, &hooks);
+    /// assert_eq!(result, Err(AesError::Cancelled));
+    /// assert!(cipher.iter().all(|&b| b == 0));
+    /// ```
+    ///
+    /// [`encrypt_decrypt_parallel`]: ../aes_with_operation_mode/struct.Ctr.html#method.encrypt_decrypt_parallel
+    #[cfg(feature = "parallel")]
+    pub fn encrypt_decrypt_parallel_with_hooks(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+        iv: &[u8],
+        threads: usize,
+        hooks: &ParallelHooks,
+    ) -> Result<Vec<u8>, AesError>
+    where
+        C: Sync,
+    {
+        assert_eq!(iv.len(), BLOCKSIZE_IN_BYTES);
+        let mut counter: [u8; BLOCKSIZE_IN_BYTES] = [0; BLOCKSIZE_IN_BYTES];
+        counter.copy_from_slice(iv);
+        let counter = u128::from_be_bytes(counter);
+        parallel::for_each_chunk_with_hooks(
+            input,
+            output,
+            threads,
+            hooks,
+            |first_block, input, output| {
+                let start = counter.wrapping_add(first_block as u128).to_be_bytes();
+                self.encrypt_decrypt(input, output, &start);
+            },
+        )?;
+        let blocks = input.len().div_ceil(BLOCKSIZE_IN_BYTES);
+        Ok(counter.wrapping_add(blocks as u128).to_be_bytes().to_vec())
+    }
+}
+
+/// PCBC (Propagating Cipher Block Chaining) (**Experimental**) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Pcbc<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Pcbc<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`pcbc_enc`].
+    ///
+    /// [`pcbc_enc`]: ../aes_with_operation_mode/fn.pcbc_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut buffer = vec![0; BLOCKSIZE_IN_BYTES];
+        // The 1st (head) block
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            buffer[j] = iv[j] ^ plain[j];
+        }
+        encrypt_into(&self.cipher, &buffer, &mut cipher[..BLOCKSIZE_IN_BYTES]);
+        // The other blocks
+        let block_number = plain.len() >> 4;
+        let mut start = 0;
+        for i in 1..block_number {
+            start = i << 4;
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                buffer[j] = cipher[start + j - BLOCKSIZE_IN_BYTES]
+                    ^ plain[start + j - BLOCKSIZE_IN_BYTES]
+                    ^ plain[start + j];
+            }
+            encrypt_into(
+                &self.cipher,
+                &buffer,
+                &mut cipher[start..(start + BLOCKSIZE_IN_BYTES)],
+            );
+        }
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            buffer[j] = cipher[start + j] ^ plain[start + j];
+        }
+        buffer
+    }
+
+    /// The same as [`pcbc_dec`].
+    ///
+    /// [`pcbc_dec`]: ../aes_with_operation_mode/fn.pcbc_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut buffer = vec![0; BLOCKSIZE_IN_BYTES];
+        // The 1st (head) block
+        decrypt_into(&self.cipher, &cipher[..BLOCKSIZE_IN_BYTES], &mut buffer);
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            plain[j] = iv[j] ^ buffer[j];
+        }
+        // The other block
+        let block_number = cipher.len() >> 4;
+        let mut start = 0;
+        for i in 1usize..block_number {
+            start = i << 4;
+            decrypt_into(
+                &self.cipher,
+                &cipher[start..(start + BLOCKSIZE_IN_BYTES)],
+                &mut buffer,
+            );
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                plain[start + j] = cipher[start + j - BLOCKSIZE_IN_BYTES]
+                    ^ plain[start + j - BLOCKSIZE_IN_BYTES]
+                    ^ buffer[j];
+            }
+        }
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            buffer[j] = cipher[start + j] ^ plain[start + j];
+        }
+        buffer
+    }
+}
+
+/// CFB (Cipher Feedback) with 8-bit feedback size (**Experimental**) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Cfb8<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Cfb8<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`cfb_8_enc`].
+    ///
+    /// [`cfb_8_enc`]: ../aes_with_operation_mode/fn.cfb_8_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut out_buffer = vec![0; BLOCKSIZE_IN_BYTES];
+        let mut in_buffer = iv.to_owned();
+        for i in 0..plain.len() {
+            encrypt_into(&self.cipher, &in_buffer, &mut out_buffer);
+            cipher[i] = out_buffer[0] ^ plain[i];
+            in_buffer.rotate_left(1);
+            in_buffer[15] = cipher[i];
+        }
+        out_buffer
+    }
+
+    /// The same as [`cfb_8_dec`].
+    ///
+    /// [`cfb_8_dec`]: ../aes_with_operation_mode/fn.cfb_8_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let mut out_buffer = vec![0; BLOCKSIZE_IN_BYTES];
+        let mut in_buffer = iv.to_owned();
+        for i in 0..cipher.len() {
+            encrypt_into(&self.cipher, &in_buffer, &mut out_buffer);
+            plain[i] = out_buffer[0] ^ cipher[i];
+            in_buffer.rotate_left(1);
+            in_buffer[15] = cipher[i];
+        }
+        out_buffer
+    }
+}
+
+/// CFB (Cipher Feedback) with 1-bit feedback size (**Experimental**) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Cfb1<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Cfb1<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`cfb_1_enc`].
+    ///
+    /// [`cfb_1_enc`]: ../aes_with_operation_mode/fn.cfb_1_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], bits: usize, iv: &[u8]) -> Vec<u8> {
+        self.apply(plain, cipher, bits, iv, true)
+    }
+
+    /// The same as [`cfb_1_dec`].
+    ///
+    /// [`cfb_1_dec`]: ../aes_with_operation_mode/fn.cfb_1_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], bits: usize, iv: &[u8]) -> Vec<u8> {
+        self.apply(cipher, plain, bits, iv, false)
+    }
+
+    fn apply(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+        bits: usize,
+        iv: &[u8],
+        encrypt: bool,
+    ) -> Vec<u8> {
+        assert!(
+            bits <= input.len() * 8 && bits <= output.len() * 8,
+            "The data is shorter than the number of bits."
+        );
+        let mut out_buffer = vec![0; BLOCKSIZE_IN_BYTES];
+        let mut in_buffer = iv.to_owned();
+        for i in 0..bits {
+            encrypt_into(&self.cipher, &in_buffer, &mut out_buffer);
+            let shift = 7 - (i & 0b111);
+            let input_bit = (input[i / 8] >> shift) & 1;
+            let output_bit = input_bit ^ (out_buffer[0] >> 7);
+            output[i / 8] = (output[i / 8] & !(1 << shift)) | (output_bit << shift);
+            // The cipher bit is fed back into the shift register.
+            let feedback = if encrypt { output_bit } else { input_bit };
+            for j in 0..(BLOCKSIZE_IN_BYTES - 1) {
+                in_buffer[j] = (in_buffer[j] << 1) | (in_buffer[j + 1] >> 7);
+            }
+            in_buffer[BLOCKSIZE_IN_BYTES - 1] = (in_buffer[BLOCKSIZE_IN_BYTES - 1] << 1) | feedback;
+        }
+        out_buffer
+    }
+}
+
+/// IGE (Infinite Garble Extension) (**Experimental**) mode over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Ige<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> Ige<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`ige_enc`].
+    ///
+    /// [`ige_enc`]: ../aes_with_operation_mode/fn.ige_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        assert_eq!(
+            iv.len(),
+            2 * BLOCKSIZE_IN_BYTES,
+            "The IV of IGE is 32 bytes."
+        );
+        let (mut previous_cipher, mut previous_plain) = iv.split_at(BLOCKSIZE_IN_BYTES);
+        let mut buffer = [0u8; BLOCKSIZE_IN_BYTES];
+        for (plain_block, cipher_block) in plain
+            .chunks_exact(BLOCKSIZE_IN_BYTES)
+            .zip(cipher.chunks_exact_mut(BLOCKSIZE_IN_BYTES))
+        {
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                buffer[j] = plain_block[j] ^ previous_cipher[j];
+            }
+            self.cipher.encrypt_block(&mut buffer);
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                cipher_block[j] = buffer[j] ^ previous_plain[j];
+            }
+            previous_cipher = cipher_block;
+            previous_plain = plain_block;
+        }
+        [previous_cipher, previous_plain].concat()
+    }
+
+    /// The same as [`ige_dec`].
+    ///
+    /// [`ige_dec`]: ../aes_with_operation_mode/fn.ige_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        assert_eq!(
+            iv.len(),
+            2 * BLOCKSIZE_IN_BYTES,
+            "The IV of IGE is 32 bytes."
+        );
+        let (mut previous_cipher, previous_plain) = iv.split_at(BLOCKSIZE_IN_BYTES);
+        let mut previous_plain = previous_plain.to_owned();
+        let mut buffer = [0u8; BLOCKSIZE_IN_BYTES];
+        for (cipher_block, plain_block) in cipher
+            .chunks_exact(BLOCKSIZE_IN_BYTES)
+            .zip(plain.chunks_exact_mut(BLOCKSIZE_IN_BYTES))
+        {
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                buffer[j] = cipher_block[j] ^ previous_plain[j];
+            }
+            self.cipher.decrypt_block(&mut buffer);
+            for j in 0..BLOCKSIZE_IN_BYTES {
+                plain_block[j] = buffer[j] ^ previous_cipher[j];
+            }
+            previous_cipher = cipher_block;
+            previous_plain.copy_from_slice(plain_block);
+        }
+        [previous_cipher, &previous_plain].concat()
+    }
+}
+
+/// CBC with ciphertext stealing (CBC-CTS) mode, as in Kerberos, over a [`BlockCipher128`].
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct CbcCts<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> CbcCts<C> {
+    /// Create the mode over a block cipher.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// Borrow the block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The same as [`cbc_cts_enc`].
+    ///
+    /// [`cbc_cts_enc`]: ../aes_with_operation_mode/fn.cbc_cts_enc.html
+    pub fn encrypt(&self, plain: &[u8], cipher: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let length = plain.len();
+        assert!(
+            length >= BLOCKSIZE_IN_BYTES,
+            "CBC-CTS needs at least one block."
+        );
+        let cbc = Cbc::new(&self.cipher);
+        if length == BLOCKSIZE_IN_BYTES {
+            return cbc.encrypt(plain, cipher, iv);
+        }
+        // The blocks before the last one, which may be partial.
+        let head = ((length - 1) >> 4) << 4;
+        let last_length = length - head;
+        let second_last = cbc.encrypt(&plain[..head], &mut cipher[..head], iv);
+        let mut buffer = [0u8; BLOCKSIZE_IN_BYTES];
+        buffer[..last_length].copy_from_slice(&plain[head..]);
+        for j in 0..BLOCKSIZE_IN_BYTES {
+            buffer[j] ^= second_last[j];
+        }
+        self.cipher.encrypt_block(&mut buffer);
+        // Swap the last two blocks, and truncate.
+        cipher[(head - BLOCKSIZE_IN_BYTES)..head].copy_from_slice(&buffer);
+        cipher[head..length].copy_from_slice(&second_last[..last_length]);
+        buffer.to_vec()
+    }
+
+    /// The same as [`cbc_cts_dec`].
+    ///
+    /// [`cbc_cts_dec`]: ../aes_with_operation_mode/fn.cbc_cts_dec.html
+    pub fn decrypt(&self, cipher: &[u8], plain: &mut [u8], iv: &[u8]) -> Vec<u8> {
+        let length = cipher.len();
+        assert!(
+            length >= BLOCKSIZE_IN_BYTES,
+            "CBC-CTS needs at least one block."
+        );
+        let cbc = Cbc::new(&self.cipher);
+        if length == BLOCKSIZE_IN_BYTES {
+            return cbc.decrypt(cipher, plain, iv);
+        }
+        let head = ((length - 1) >> 4) << 4;
+        let last_length = length - head;
+        let last = &cipher[(head - BLOCKSIZE_IN_BYTES)..head];
+        // The decrypted last block is the padded last plain XOR the second last cipher block,
+        // whose tail is stolen by the zero padding.
+        let mut buffer = [0u8; BLOCKSIZE_IN_BYTES];
+        decrypt_into(&self.cipher, last, &mut buffer);
+        let mut unswapped = cipher[..head].to_owned();
+        let second_last = &mut unswapped[(head - BLOCKSIZE_IN_BYTES)..];
+        second_last[..last_length].copy_from_slice(&cipher[head..]);
+        second_last[last_length..].copy_from_slice(&buffer[last_length..]);
+        for j in 0..last_length {
+            plain[head + j] = buffer[j] ^ cipher[head + j];
+        }
+        cbc.decrypt(&unswapped, &mut plain[..head], iv);
+        last.to_owned()
+    }
+}
+
+/// Check the arguments of a `try_` function against what its panicking version accepts.
+///
+/// `input` must be at least `min_len` bytes long, and a multiple of 16 bytes if `whole_blocks`.
+fn check_arguments(
+    input: &[u8],
+    output: &[u8],
+    keys: &[u32],
+    iv: &[u8],
+    iv_len: usize,
+    min_len: usize,
+    whole_blocks: bool,
+) -> Result<(), AesError> {
+    if !matches!(
+        keys.len(),
+        aes_core::N_SUBKEYS_128BIT | aes_core::N_SUBKEYS_192BIT | aes_core::N_SUBKEYS_256BIT
+    ) {
+        return Err(AesError::InvalidKeyLength);
+    }
+    if iv.len() != iv_len {
+        return Err(AesError::InvalidParameter);
+    }
+    if input.len() < min_len
+        || (whole_blocks && input.len() & 0b1111 != 0)
+        || output.len() != input.len()
+    {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(())
+}
+
+macro_rules! define_try_functions {
+    ($($name:ident => $function:ident, $iv_len:expr, $min_len:expr, $whole_blocks:expr;)*) => {
+        $(
+            #[doc = concat!("The fallible version of [`", stringify!($function), "`].")]
+            ///
+            /// Instead of panicking (or silently ignoring an incomplete last block), it returns
+            /// `Err(AesError::InvalidKeyLength)` if `keys` has a wrong length,
+            /// `Err(AesError::InvalidParameter)` if `iv` has a wrong length and
+            /// `Err(AesError::InvalidDataLength)` if the input has a wrong length or the output
+            /// is not as long as the input. Otherwise it returns what the panicking version
+            /// returns.
+            ///
+            #[doc = concat!(
+                "[`", stringify!($function), "`]: ../aes_with_operation_mode/fn.",
+                stringify!($function), ".html"
+            )]
+            pub fn $name(
+                input: &[u8],
+                output: &mut [u8],
+                keys: &[u32],
+                iv: &[u8],
+            ) -> Result<Vec<u8>, AesError> {
+                check_arguments(input, output, keys, iv, $iv_len, $min_len, $whole_blocks)?;
+                Ok($function(input, output, keys, iv))
+            }
+        )*
+    };
+}
+
+define_try_functions! {
+    try_cbc_enc => cbc_enc, BLOCKSIZE_IN_BYTES, BLOCKSIZE_IN_BYTES, true;
+    try_cbc_dec => cbc_dec, BLOCKSIZE_IN_BYTES, BLOCKSIZE_IN_BYTES, true;
+    try_cfb_enc => cfb_enc, BLOCKSIZE_IN_BYTES, 1, false;
+    try_cfb_dec => cfb_dec, BLOCKSIZE_IN_BYTES, 1, false;
+    try_ofb_enc_dec => ofb_enc_dec, BLOCKSIZE_IN_BYTES, 0, false;
+    try_ctr_enc_dec => ctr_enc_dec, BLOCKSIZE_IN_BYTES, 0, false;
+    try_pcbc_enc => pcbc_enc, BLOCKSIZE_IN_BYTES, BLOCKSIZE_IN_BYTES, true;
+    try_pcbc_dec => pcbc_dec, BLOCKSIZE_IN_BYTES, BLOCKSIZE_IN_BYTES, true;
+    try_cfb_8_enc => cfb_8_enc, BLOCKSIZE_IN_BYTES, 0, false;
+    try_cfb_8_dec => cfb_8_dec, BLOCKSIZE_IN_BYTES, 0, false;
+    try_ige_enc => ige_enc, 2 * BLOCKSIZE_IN_BYTES, 0, true;
+    try_ige_dec => ige_dec, 2 * BLOCKSIZE_IN_BYTES, 0, true;
+    try_cbc_cts_enc => cbc_cts_enc, BLOCKSIZE_IN_BYTES, BLOCKSIZE_IN_BYTES, false;
+    try_cbc_cts_dec => cbc_cts_dec, BLOCKSIZE_IN_BYTES, BLOCKSIZE_IN_BYTES, false;
+}
+
+/// The fallible version of [`ecb_enc`].
+///
+/// It returns `Err(AesError::InvalidKeyLength)` if `keys` has a wrong length and
+/// `Err(AesError::InvalidDataLength)` if the plain is not a non-zero multiple of 16 bytes or the
+/// cipher is not as long as the plain.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode::{try_cbc_enc, try_ecb_enc};
+/// use aes_frast::AesError;
+///
+/// let keys = vec![0u32; 44];
+/// let mut cipher = vec![0u8; 32];
+/// assert!(try_ecb_enc(&[0u8; 32], &mut cipher, &keys).is_ok());
+/// assert_eq!(try_ecb_enc(&[0u8; 20], &mut cipher, &keys), Err(AesError::InvalidDataLength));
+/// assert_eq!(try_ecb_enc(&[0u8; 32], &mut cipher, &keys[..40]), Err(AesError::InvalidKeyLength));
+/// assert_eq!(try_cbc_enc(&[0u8; 32], &mut cipher, &keys, &[0u8; 12]),
+///            Err(AesError::InvalidParameter));
+/// ```
+///
+/// [`ecb_enc`]: ../aes_with_operation_mode/fn.ecb_enc.html
+pub fn try_ecb_enc(plain: &[u8], cipher: &mut [u8], keys: &[u32]) -> Result<Vec<u8>, AesError> {
+    check_arguments(plain, cipher, keys, &[], 0, BLOCKSIZE_IN_BYTES, true)?;
+    Ok(ecb_enc(plain, cipher, keys))
+}
+
+/// The fallible version of [`ecb_dec`].
+///
+/// It returns the same errors as [`try_ecb_enc`].
+///
+/// [`ecb_dec`]: ../aes_with_operation_mode/fn.ecb_dec.html
+/// [`try_ecb_enc`]: ../aes_with_operation_mode/fn.try_ecb_enc.html
+pub fn try_ecb_dec(cipher: &[u8], plain: &mut [u8], keys: &[u32]) -> Result<Vec<u8>, AesError> {
+    check_arguments(cipher, plain, keys, &[], 0, BLOCKSIZE_IN_BYTES, true)?;
+    Ok(ecb_dec(cipher, plain, keys))
+}
+
+/// The fallible version of [`cfb_1_enc`].
+///
+/// It returns the same errors as the other `try_` functions, and
+/// `Err(AesError::InvalidDataLength)` if `plain` is shorter than `bits` bits or `cipher` is not
+/// as long as `plain`.
+///
+/// [`cfb_1_enc`]: ../aes_with_operation_mode/fn.cfb_1_enc.html
+pub fn try_cfb_1_enc(
+    plain: &[u8],
+    cipher: &mut [u8],
+    bits: usize,
+    keys: &[u32],
+    iv: &[u8],
+) -> Result<Vec<u8>, AesError> {
+    check_bits(plain, cipher, bits, keys, iv)?;
+    Ok(cfb_1_enc(plain, cipher, bits, keys, iv))
+}
+
+/// The fallible version of [`cfb_1_dec`].
+///
+/// It returns the same errors as [`try_cfb_1_enc`].
+///
+/// [`cfb_1_dec`]: ../aes_with_operation_mode/fn.cfb_1_dec.html
+/// [`try_cfb_1_enc`]: ../aes_with_operation_mode/fn.try_cfb_1_enc.html
+pub fn try_cfb_1_dec(
+    cipher: &[u8],
+    plain: &mut [u8],
+    bits: usize,
+    keys: &[u32],
+    iv: &[u8],
+) -> Result<Vec<u8>, AesError> {
+    check_bits(cipher, plain, bits, keys, iv)?;
+    Ok(cfb_1_dec(cipher, plain, bits, keys, iv))
+}
+
+/// Check the arguments of the `try_` functions of CFB-1, which only use the first `bits` bits.
+fn check_bits(
+    input: &[u8],
+    output: &[u8],
+    bits: usize,
+    keys: &[u32],
+    iv: &[u8],
+) -> Result<(), AesError> {
+    check_arguments(&[], &[], keys, iv, BLOCKSIZE_IN_BYTES, 0, false)?;
+    let bytes = bits.div_ceil(8);
+    if input.len() < bytes || output.len() != input.len() {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(())
+}
+
+macro_rules! define_aes_aliases {
+    ($($mode:ident: $aes128:ident, $aes192:ident, $aes256:ident;)*) => {
+        $(
+            #[doc = concat!("[`", stringify!($mode), "`] over AES-128.")]
+            pub type $aes128 = $mode<Aes128>;
+            #[doc = concat!("[`", stringify!($mode), "`] over AES-192.")]
+            pub type $aes192 = $mode<Aes192>;
+            #[doc = concat!("[`", stringify!($mode), "`] over AES-256.")]
+            pub type $aes256 = $mode<Aes256>;
+        )*
+    };
+}
+
+define_aes_aliases! {
+    Ecb: Aes128Ecb, Aes192Ecb, Aes256Ecb;
+    Cbc: Aes128Cbc, Aes192Cbc, Aes256Cbc;
+    Cfb: Aes128Cfb, Aes192Cfb, Aes256Cfb;
+    Ofb: Aes128Ofb, Aes192Ofb, Aes256Ofb;
+    Ctr: Aes128Ctr, Aes192Ctr, Aes256Ctr;
+    Pcbc: Aes128Pcbc, Aes192Pcbc, Aes256Pcbc;
+    Cfb8: Aes128Cfb8, Aes192Cfb8, Aes256Cfb8;
+    Cfb1: Aes128Cfb1, Aes192Cfb1, Aes256Cfb1;
+    Ige: Aes128Ige, Aes192Ige, Aes256Ige;
+    CbcCts: Aes128CbcCts, Aes192CbcCts, Aes256CbcCts;
+}
+
+impl_redacted_debug!(
+    Ecb<C>,
+    Cbc<C>,
+    Cfb<C>,
+    Ofb<C>,
+    Ctr<C>,
+    Pcbc<C>,
+    Cfb8<C>,
+    Cfb1<C>,
+    Ige<C>,
+    CbcCts<C>,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    #[test]
+    fn try_functions_never_panic() {
+        type TryFunction = fn(&[u8], &mut [u8], &[u32], &[u8]) -> Result<Vec<u8>, AesError>;
+        let functions: [(TryFunction, ChainedFunction); 14] = [
+            (try_cbc_enc, cbc_enc),
+            (try_cbc_dec, cbc_dec),
+            (try_cfb_enc, cfb_enc),
+            (try_cfb_dec, cfb_dec),
+            (try_ofb_enc_dec, ofb_enc_dec),
+            (try_ctr_enc_dec, ctr_enc_dec),
+            (try_pcbc_enc, pcbc_enc),
+            (try_pcbc_dec, pcbc_dec),
+            (try_cfb_8_enc, cfb_8_enc),
+            (try_cfb_8_dec, cfb_8_dec),
+            (try_ige_enc, ige_enc),
+            (try_ige_dec, ige_dec),
+            (try_cbc_cts_enc, cbc_cts_enc),
+            (try_cbc_cts_dec, cbc_cts_dec),
+        ];
+        let input: Vec<u8> = (0..48).collect();
+        for keys_len in [0, 43, 44, 52, 60, 61] {
+            let keys = vec![0x0F1E2D3Cu32; keys_len];
+            for iv_len in [0, 12, 16, 32] {
+                let iv = vec![0x5Au8; iv_len];
+                for len in 0..=input.len() {
+                    for output_len in [len.saturating_sub(1), len, len + 16] {
+                        for (try_function, function) in functions {
+                            let mut output = vec![0u8; output_len];
+                            if let Ok(next_iv) =
+                                try_function(&input[..len], &mut output, &keys, &iv)
+                            {
+                                let mut expected = vec![0u8; output_len];
+                                assert_eq!(
+                                    next_iv,
+                                    function(&input[..len], &mut expected, &keys, &iv)
+                                );
+                                assert_eq!(output, expected);
+                            }
+                        }
+                        let mut output = vec![0u8; output_len];
+                        if try_ecb_enc(&input[..len], &mut output, &keys).is_ok() {
+                            assert!((44..=60).contains(&keys_len) && len > 0 && len & 0b1111 == 0);
+                        }
+                        let _ = try_cfb_1_enc(&input[..len], &mut output, len * 8 + 1, &keys, &iv);
+                        let _ = try_cfb_1_dec(&input[..len], &mut output, len * 8, &keys, &iv);
+                    }
+                }
+            }
+        }
+        let keys = vec![0u32; 52];
+        let mut output = vec![0u8; 32];
+        assert_eq!(
+            try_cbc_enc(&input[..20], &mut output, &keys, &[0u8; 16]),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            try_ctr_enc_dec(&input[..20], &mut output, &keys, &[0u8; 12]),
+            Err(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            try_cfb_enc(&input[..20], &mut output, &keys[..44 + 1], &[0u8; 16]),
+            Err(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            try_cfb_1_enc(&input[..2], &mut output, 17, &keys, &[0u8; 16]),
+            Err(AesError::InvalidDataLength)
+        );
+        assert!(try_ige_enc(&input[..32], &mut output, &keys, &[0u8; 32]).is_ok());
+    }
+
+    #[test]
+    fn cbc_cts_rfc3962() {
+        // RFC 3962 Appendix B: the output and the next IV.
+        let cases = [
+            (
+                17,
+                "c6353568f2bf8cb4d8a580362da7ff7f97",
+                "c6353568f2bf8cb4d8a580362da7ff7f",
+            ),
+            (
+                31,
+                "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+                "fc00783e0efdb2c1d445d4c8eff7ed22",
+            ),
+            (
+                32,
+                "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+                "39312523a78662d5be7fcbcc98ebf5a8",
+            ),
+            (
+                47,
+                "97687268d6ecccc0c07b25e25ecfe584b3fffd940c16a18c1b5549d2f838029e\
+                 39312523a78662d5be7fcbcc98ebf5",
+                "b3fffd940c16a18c1b5549d2f838029e",
+            ),
+            (
+                48,
+                "97687268d6ecccc0c07b25e25ecfe5849dad8bbb96c4cdc03bc103e1a194bbd8\
+                 39312523a78662d5be7fcbcc98ebf5a8",
+                "9dad8bbb96c4cdc03bc103e1a194bbd8",
+            ),
+            (
+                64,
+                "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a8\
+                 4807efe836ee89a526730dbc2f7bc8409dad8bbb96c4cdc03bc103e1a194bbd8",
+                "4807efe836ee89a526730dbc2f7bc840",
+            ),
+        ];
+        let message = b"I would like the General Gau's Chicken, please, and wonton soup.";
+        let mode = Aes128CbcCts::new(Aes128::new(b"chicken teriyaki"));
+        for (length, expected, expected_next_iv) in cases {
+            let plain = &message[..length];
+            let mut cipher = vec![0u8; length];
+            let next_iv = mode.encrypt(plain, &mut cipher, &[0u8; 16]);
+            assert_eq!(hex(&cipher), expected);
+            assert_eq!(hex(&next_iv), expected_next_iv);
+
+            let mut decrypted = vec![0u8; length];
+            let next_iv = mode.decrypt(&cipher, &mut decrypted, &[0u8; 16]);
+            assert_eq!(decrypted, plain);
+            assert_eq!(hex(&next_iv), expected_next_iv);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_matches_serial() {
+        let aes = Aes256::new(&[0x5Au8; 32]);
+        let ecb = Ecb::new(&aes);
+        let ctr = Ctr::new(&aes);
+        let iv = [0xFFu8; BLOCKSIZE_IN_BYTES];
+        let input: Vec<u8> = (0..(1500 * BLOCKSIZE_IN_BYTES + 9))
+            .map(|i| (i * 31) as u8)
+            .collect();
+        for len in [16, 4096, input.len()] {
+            let whole = len & !0b1111;
+            for threads in [1, 2, 5] {
+                let mut expected = vec![0u8; len];
+                let mut output = vec![0u8; len];
+                let last = ecb.encrypt(&input[..whole], &mut expected);
+                assert_eq!(
+                    ecb.encrypt_parallel(&input[..whole], &mut output, threads),
+                    last
+                );
+                assert_eq!(output, expected);
+                let last = ecb.decrypt(&input[..whole], &mut expected);
+                assert_eq!(
+                    ecb.decrypt_parallel(&input[..whole], &mut output, threads),
+                    last
+                );
+                assert_eq!(output, expected);
+                let counter = ctr.encrypt_decrypt(&input[..len], &mut expected, &iv);
+                assert_eq!(
+                    ctr.encrypt_decrypt_parallel(&input[..len], &mut output, &iv, threads),
+                    counter
+                );
+                assert_eq!(output, expected);
+            }
+        }
+    }
 }
@@ -0,0 +1,457 @@
+//! # aes_with_operation_mode_inplace
+//! `aes_with_operation_mode_inplace` provides the CBC, CFB, OFB and CTR modes of
+//! `aes_with_operation_mode`, but they read the input from and write the output to the same
+//! buffer. Nothing is allocated: the few blocks of state are kept on the stack.
+//!
+//! The functions have the same names and parameters as the ones in `aes_with_operation_mode`,
+//! except that the input and output slices are replaced by one `data` slice. They return the
+//! block to pass as the IV (or counter block) of the next call as an array, so a long message can
+//! be processed in several calls whose lengths are multiples of 16 bytes.
+//!
+//! Like in `aes_with_operation_mode`, each mode also has a function ending with `_with`, which
+//! takes any [`BlockCipher128`] instead of the scheduled keys.
+//!
+//! These are the only in-place mode loops of this library: the mode methods of the `context`
+//! contexts, the `dma` functions (after copying the input into the output buffer) and the
+//! software fallback of [`Offloaded`] are all built on the functions ending with `_with`.
+//!
+//! [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+//! [`Offloaded`]: ../offload/struct.Offloaded.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode::Subkeys;
+use super::block_cipher::BlockCipher128;
+
+/// The number of blocks which are processed at a time by the modes that can be batched.
+const BATCH_BLOCKS: usize = 8;
+
+/// Copy the IV (or counter block) into an array.
+fn iv_block(iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    assert_eq!(iv.len(), BLOCKSIZE_IN_BYTES);
+    let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+    block.copy_from_slice(iv);
+    block
+}
+
+/// XOR `mask` into the leading bytes of `data`.
+fn xor_into(data: &mut [u8], mask: &[u8]) {
+    for (d, m) in data.iter_mut().zip(mask) {
+        *d ^= m;
+    }
+}
+
+/// CBC (Cipher Block Chaining) Encryption in place
+///
+/// This function encrypts the plain in the first parameter into the cipher in the same buffer,
+/// using the scheduled keys and the initialization vector (IV) in the second and third
+/// parameters.
+/// Finally, it returns the final block of the cipher (the IV if `data` is empty), which is the
+/// IV of the next call.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode, aes_with_operation_mode_inplace};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// // This example came from NIST SP 800-38A F.2.1
+/// let plain: Vec<u8> = vec![0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96,
+///                           0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17, 0x2A,
+///                           0xAE, 0x2D, 0x8A, 0x57, 0x1E, 0x03, 0xAC, 0x9C,
+///                           0x9E, 0xB7, 0x6F, 0xAC, 0x45, 0xAF, 0x8E, 0x51];
+/// let o_key: Vec<u8> = vec![0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///                           0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C];
+/// let iv: Vec<u8> = vec![0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+///                        0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F];
+/// let mut e_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// let mut d_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut e_keys);
+/// aes_core::key_schedule_decrypt_auto(&o_key, &mut d_keys);
+///
+/// let mut data = plain.clone();
+/// let last = aes_with_operation_mode_inplace::cbc_enc(&mut data, &e_keys, &iv);
+/// assert_eq!(data, vec![0x76, 0x49, 0xAB, 0xAC, 0x81, 0x19, 0xB2, 0x46,
+///                       0xCE, 0xE9, 0x8E, 0x9B, 0x12, 0xE9, 0x19, 0x7D,
+///                       0x50, 0x86, 0xCB, 0x9B, 0x50, 0x72, 0x19, 0xEE,
+///                       0x95, 0xDB, 0x11, 0x3A, 0x91, 0x76, 0x78, 0xB2]);
+///
+/// // The same as the out-of-place function.
+/// let mut cipher = vec![0u8; plain.len()];
+/// assert_eq!(aes_with_operation_mode::cbc_enc(&plain, &mut cipher, &e_keys, &iv), last);
+/// assert_eq!(cipher, data);
+///
+/// aes_with_operation_mode_inplace::cbc_dec(&mut data, &d_keys, &iv);
+/// assert_eq!(data, plain);
+/// ```
+/// # Panics
+/// This function panics if the length of `data` is not a multiple of 16, or the length of `iv`
+/// is not 16.
+pub fn cbc_enc(data: &mut [u8], keys: &[u32], iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    cbc_enc_with(&Subkeys::new(keys), data, iv)
+}
+/// CBC (Cipher Block Chaining) Encryption in place with any [`BlockCipher128`].
+///
+/// The same as [`cbc_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cbc_enc`]: ../aes_with_operation_mode_inplace/fn.cbc_enc.html
+pub fn cbc_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    assert_eq!(data.len() & 0b1111, 0);
+    let mut chain = iv_block(iv);
+    for block in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+        xor_into(&mut chain, block);
+        block_cipher.encrypt_block(&mut chain);
+        block.copy_from_slice(&chain);
+    }
+    chain
+}
+/// CBC (Cipher Block Chaining) Decryption in place
+///
+/// This function decrypts the cipher in the first parameter into the plain in the same buffer,
+/// using the scheduled keys and the initialization vector (IV) in the second and third
+/// parameters.
+/// Finally, it returns the final block of the cipher (the IV if `data` is empty), which is the
+/// IV of the next call.
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, codes are included there.
+/// # Panics
+/// This function panics if the length of `data` is not a multiple of 16, or the length of `iv`
+/// is not 16.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_inplace/fn.cbc_enc.html
+pub fn cbc_dec(data: &mut [u8], keys: &[u32], iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    cbc_dec_with(&Subkeys::new(keys), data, iv)
+}
+/// CBC (Cipher Block Chaining) Decryption in place with any [`BlockCipher128`].
+///
+/// The same as [`cbc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cbc_dec`]: ../aes_with_operation_mode_inplace/fn.cbc_dec.html
+pub fn cbc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    assert_eq!(data.len() & 0b1111, 0);
+    let mut chain = iv_block(iv);
+    // The cipher blocks of a batch are saved before they are overwritten, since each plain block
+    // needs the cipher block before it.
+    let mut saved = [0u8; BATCH_BLOCKS * BLOCKSIZE_IN_BYTES];
+    for chunk in data.chunks_mut(saved.len()) {
+        let saved = &mut saved[..chunk.len()];
+        saved.copy_from_slice(chunk);
+        block_cipher.decrypt_blocks(chunk);
+        xor_into(&mut chunk[..BLOCKSIZE_IN_BYTES], &chain);
+        xor_into(&mut chunk[BLOCKSIZE_IN_BYTES..], saved);
+        chain.copy_from_slice(&saved[(saved.len() - BLOCKSIZE_IN_BYTES)..]);
+    }
+    chain
+}
+/// CFB (Cipher Feedback) Encryption in place
+///
+/// The feedback size is fixed to 128 bits, which is the same as block size.
+/// This mode doesn't require padding.
+///
+/// This function encrypts the plain in the first parameter into the cipher in the same buffer,
+/// using the scheduled keys and the initialization vector (IV) in the second and third
+/// parameters.
+/// Finally, it returns the final complete block of the cipher (the IV if there is none), which is
+/// the IV of the next call. Unlike [`cfb_enc`] of `aes_with_operation_mode`, an incomplete last
+/// block is not returned, since a message can't be continued after it.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode_inplace};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// let plain: Vec<u8> = (0..40).collect();
+/// let o_key: Vec<u8> = (0x80..0x90).collect();
+/// let iv: Vec<u8> = (0x10..0x20).collect();
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+///
+/// // A message can be processed in several calls.
+/// let mut data = plain.clone();
+/// let (head, tail) = data.split_at_mut(32);
+/// let next_iv = aes_with_operation_mode_inplace::cfb_enc(head, &w_keys, &iv);
+/// aes_with_operation_mode_inplace::cfb_enc(tail, &w_keys, &next_iv);
+///
+/// // Notice: CFB only uses block-encryption, no matter we use it as encryption or decryption.
+/// aes_with_operation_mode_inplace::cfb_dec(&mut data, &w_keys, &iv);
+/// assert_eq!(data, plain);
+/// ```
+/// # Panics
+/// This function panics if the length of `iv` is not 16.
+///
+/// [`cfb_enc`]: ../aes_with_operation_mode/fn.cfb_enc.html
+pub fn cfb_enc(data: &mut [u8], keys: &[u32], iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    cfb_enc_with(&Subkeys::new(keys), data, iv)
+}
+/// CFB (Cipher Feedback) Encryption in place with any [`BlockCipher128`].
+///
+/// The same as [`cfb_enc`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_enc`]: ../aes_with_operation_mode_inplace/fn.cfb_enc.html
+pub fn cfb_enc_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let mut chain = iv_block(iv);
+    let mut buffer = chain;
+    for block in data.chunks_mut(BLOCKSIZE_IN_BYTES) {
+        buffer.copy_from_slice(&chain);
+        block_cipher.encrypt_block(&mut buffer);
+        xor_into(block, &buffer);
+        if block.len() == BLOCKSIZE_IN_BYTES {
+            chain.copy_from_slice(block);
+        }
+    }
+    chain
+}
+/// CFB (Cipher Feedback) Decryption in place
+///
+/// The feedback size is fixed to 128 bits, which is the same as block size.
+/// This mode doesn't require padding.
+///
+/// This function decrypts the cipher in the first parameter into the plain in the same buffer,
+/// using the scheduled keys and the initialization vector (IV) in the second and third
+/// parameters.
+/// Finally, it returns the final complete block of the cipher (the IV if there is none), which is
+/// the IV of the next call.
+/// # Examples
+/// Please refer to the [`cfb_enc`] function, codes are included there.
+/// # Panics
+/// This function panics if the length of `iv` is not 16.
+///
+/// [`cfb_enc`]: ../aes_with_operation_mode_inplace/fn.cfb_enc.html
+pub fn cfb_dec(data: &mut [u8], keys: &[u32], iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    cfb_dec_with(&Subkeys::new(keys), data, iv)
+}
+/// CFB (Cipher Feedback) Decryption in place with any [`BlockCipher128`].
+///
+/// The same as [`cfb_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`cfb_dec`]: ../aes_with_operation_mode_inplace/fn.cfb_dec.html
+pub fn cfb_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let mut chain = iv_block(iv);
+    // The keystream of a batch is the encrypted IV and cipher blocks before each block, so it is
+    // known in advance, unlike in encryption.
+    let mut keystream = [0u8; BATCH_BLOCKS * BLOCKSIZE_IN_BYTES];
+    for chunk in data.chunks_mut(keystream.len()) {
+        let keystream = &mut keystream[..((chunk.len() + 0b1111) & !0b1111)];
+        keystream[..BLOCKSIZE_IN_BYTES].copy_from_slice(&chain);
+        let feedback = keystream.len() - BLOCKSIZE_IN_BYTES;
+        keystream[BLOCKSIZE_IN_BYTES..].copy_from_slice(&chunk[..feedback]);
+        if chunk.len() == keystream.len() {
+            chain.copy_from_slice(&chunk[feedback..]);
+        } else if feedback != 0 {
+            chain.copy_from_slice(&chunk[(feedback - BLOCKSIZE_IN_BYTES)..feedback]);
+        }
+        block_cipher.encrypt_blocks(keystream);
+        xor_into(chunk, keystream);
+    }
+    chain
+}
+/// OFB (Output Feedback) Encryption and Decryption in place
+///
+/// The feedback size is fixed to 128 bits, which is the same as block size.
+/// This mode doesn't require depadding if you didn't add padding when encrypting.
+///
+/// This function encrypts (or decrypts) the data in the first parameter in the same buffer,
+/// using the scheduled keys and the initialization vector (IV) in the second and third
+/// parameters.
+/// Finally, it returns the final block of the encryptor output (neither the plain nor cipher),
+/// which is the IV of the next call.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode, aes_with_operation_mode_inplace};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// let plain: Vec<u8> = (0..50).collect();
+/// let o_key: Vec<u8> = (0x80..0x90).collect();
+/// let iv: Vec<u8> = (0x10..0x20).collect();
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+///
+/// let mut data = plain.clone();
+/// let last = aes_with_operation_mode_inplace::ofb_enc_dec(&mut data, &w_keys, &iv);
+///
+/// let mut cipher = vec![0u8; plain.len()];
+/// assert_eq!(aes_with_operation_mode::ofb_enc_dec(&plain, &mut cipher, &w_keys, &iv), last);
+/// assert_eq!(cipher, data);
+/// ```
+/// # Panics
+/// This function panics if the length of `iv` is not 16.
+pub fn ofb_enc_dec(data: &mut [u8], keys: &[u32], iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    ofb_enc_dec_with(&Subkeys::new(keys), data, iv)
+}
+/// OFB (Output Feedback) Encryption and Decryption in place with any [`BlockCipher128`].
+///
+/// The same as [`ofb_enc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ofb_enc_dec`]: ../aes_with_operation_mode_inplace/fn.ofb_enc_dec.html
+pub fn ofb_enc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let mut buffer = iv_block(iv);
+    for block in data.chunks_mut(BLOCKSIZE_IN_BYTES) {
+        block_cipher.encrypt_block(&mut buffer);
+        xor_into(block, &buffer);
+    }
+    buffer
+}
+/// CTR (Counter) Encryption and Decryption in place
+///
+/// This mode doesn't require padding.
+///
+/// This function encrypts (or decrypts) the data in the first parameter in the same buffer,
+/// using the scheduled keys and the initial counter block in the second and third parameters.
+/// The counter block is increased by 1 for each block, as a 128-bit big-endian integer (modulo
+/// 2<sup>128</sup>).
+/// Finally, it returns the next counter block, i.e. the one after the last used counter block.
+/// **\[Attention!\]** Never use the same counter block twice with the same key.
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode_inplace};
+/// use aes_frast::N_SUBKEYS_128BIT;
+/// // This example came from NIST SP 800-38A F.5.1
+/// let mut data: Vec<u8> = vec![0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96,
+///                              0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17, 0x2A];
+/// let o_key: Vec<u8> = vec![0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+///                           0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C];
+/// let counter: Vec<u8> = vec![0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7,
+///                             0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF];
+/// let mut w_keys: Vec<u32> = vec![0u32; N_SUBKEYS_128BIT];
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+///
+/// let next_counter = aes_with_operation_mode_inplace::ctr_enc_dec(&mut data, &w_keys, &counter);
+/// assert_eq!(data, vec![0x87, 0x4D, 0x61, 0x91, 0xB6, 0x20, 0xE3, 0x26,
+///                       0x1B, 0xEF, 0x68, 0x64, 0x99, 0x0D, 0xB6, 0xCE]);
+/// assert_eq!(next_counter, [0xF0, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7,
+///                           0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFF, 0x00]);
+/// ```
+/// # Panics
+/// This function panics if the length of `iv` is not 16.
+pub fn ctr_enc_dec(data: &mut [u8], keys: &[u32], iv: &[u8]) -> [u8; BLOCKSIZE_IN_BYTES] {
+    ctr_enc_dec_with(&Subkeys::new(keys), data, iv)
+}
+/// CTR (Counter) Encryption and Decryption in place with any [`BlockCipher128`].
+///
+/// The same as [`ctr_enc_dec`], but the blocks are processed by `block_cipher` instead of the
+/// scheduled keys.
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+/// [`ctr_enc_dec`]: ../aes_with_operation_mode_inplace/fn.ctr_enc_dec.html
+pub fn ctr_enc_dec_with<C: BlockCipher128 + ?Sized>(
+    block_cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let mut counter = iv_block(iv);
+    let mut keystream = [0u8; BATCH_BLOCKS * BLOCKSIZE_IN_BYTES];
+    for chunk in data.chunks_mut(keystream.len()) {
+        let keystream = &mut keystream[..((chunk.len() + 0b1111) & !0b1111)];
+        for block in keystream.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+            block.copy_from_slice(&counter);
+            counter = u128::from_be_bytes(counter).wrapping_add(1).to_be_bytes();
+        }
+        block_cipher.encrypt_blocks(keystream);
+        xor_into(chunk, keystream);
+    }
+    counter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core;
+    use crate::aes_with_operation_mode as mode;
+    use crate::N_SUBKEYS_192BIT;
+
+    type Inplace = fn(&mut [u8], &[u32], &[u8]) -> [u8; BLOCKSIZE_IN_BYTES];
+
+    #[test]
+    fn inplace_matches_out_of_place() {
+        let o_key: Vec<u8> = (0..24).collect();
+        let mut e_keys = [0u32; N_SUBKEYS_192BIT];
+        let mut d_keys = [0u32; N_SUBKEYS_192BIT];
+        aes_core::key_schedule_encrypt_auto(&o_key, &mut e_keys);
+        aes_core::key_schedule_decrypt_auto(&o_key, &mut d_keys);
+        let iv: Vec<u8> = (0x40..0x50).collect();
+        // Lengths around the batch size, with and without incomplete last blocks.
+        for length in [0usize, 5, 16, 37, 128, 144, 150, 400] {
+            let plain: Vec<u8> = (0..length).map(|i| (i * 7 + 3) as u8).collect();
+            let modes: [(Inplace, mode::ChainedFunction, &[u32], bool); 6] = [
+                (cbc_enc, mode::cbc_enc, &e_keys, false),
+                (cbc_dec, mode::cbc_dec, &d_keys, false),
+                (cfb_enc, mode::cfb_enc, &e_keys, true),
+                (cfb_dec, mode::cfb_dec, &e_keys, true),
+                (ofb_enc_dec, mode::ofb_enc_dec, &e_keys, true),
+                (ctr_enc_dec, mode::ctr_enc_dec, &e_keys, true),
+            ];
+            for (inplace, out_of_place, keys, any_length) in modes {
+                if (!any_length && length & 0b1111 != 0) || length == 0 {
+                    continue;
+                }
+                let mut expected = vec![0u8; length];
+                let expected_last = out_of_place(&plain, &mut expected, keys, &iv);
+                let mut data = plain.clone();
+                let last = inplace(&mut data, keys, &iv);
+                assert_eq!(data, expected);
+                if length & 0b1111 == 0 {
+                    assert_eq!(last.to_vec(), expected_last);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inplace_can_be_chained() {
+        let o_key: Vec<u8> = (0..16).collect();
+        let mut e_keys = [0u32; aes_core::N_SUBKEYS_128BIT];
+        let mut d_keys = [0u32; aes_core::N_SUBKEYS_128BIT];
+        aes_core::key_schedule_encrypt_auto(&o_key, &mut e_keys);
+        aes_core::key_schedule_decrypt_auto(&o_key, &mut d_keys);
+        let iv: Vec<u8> = (0x40..0x50).collect();
+        let plain: Vec<u8> = (0..320).map(|i| (i * 13 + 1) as u8).collect();
+        let modes: [(Inplace, &[u32]); 6] = [
+            (cbc_enc, &e_keys),
+            (cbc_dec, &d_keys),
+            (cfb_enc, &e_keys),
+            (cfb_dec, &e_keys),
+            (ofb_enc_dec, &e_keys),
+            (ctr_enc_dec, &e_keys),
+        ];
+        for (inplace, keys) in modes {
+            let mut whole = plain.clone();
+            let whole_last = inplace(&mut whole, keys, &iv);
+            let mut pieces = plain.clone();
+            let (head, tail) = pieces.split_at_mut(48);
+            let next = inplace(head, keys, &iv);
+            assert_eq!(inplace(tail, keys, &next), whole_last);
+            assert_eq!(pieces, whole);
+        }
+        // An empty buffer gives back the IV.
+        assert_eq!(cbc_enc(&mut [], &e_keys, &iv).to_vec(), iv);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cbc_incomplete_block_panic() {
+        let e_keys = [0u32; aes_core::N_SUBKEYS_128BIT];
+        cbc_enc(&mut [0u8; 20], &e_keys, &[0u8; BLOCKSIZE_IN_BYTES]);
+    }
+}
@@ -0,0 +1,1410 @@
+//! # aes_with_operation_mode_io
+//! `aes_with_operation_mode_io` provides the same operation modes as `aes_with_operation_mode`,
+//! but writes the output into any [`Write`] sink (a file, a socket, a `Vec<u8>`...) instead of
+//! an output slice. So the output never needs to be held in memory as a whole.
+//!
+//! The functions have the same names, parameters and return values as the ones in
+//! `aes_with_operation_mode`, except that the output slice is replaced by a writer, and the
+//! return value is wrapped in an [`io::Result`]. The input is processed in chunks of
+//! [`CHUNK_SIZE_IN_BYTES`] bytes, and every chunk is written out as soon as it is ready.
+//!
+//! [`encrypt_reader_to_writer`] and [`decrypt_reader_to_writer`] go one step further: they read
+//! the input from any `Read` source, and add (or check and remove) the padding of a `Recipe`.
+//!
+//! [`encrypt_stream_with_hooks`] and [`decrypt_stream_with_hooks`] report their progress and can
+//! be cancelled from another thread by a [`CancelToken`], and [`encrypt_file`] and
+//! [`decrypt_file`] remove their output file when they are cancelled or fail.
+//!
+//! [`EncryptWriter`] wraps a writer and encrypts whatever is written to it, as described by a
+//! `Recipe`, for the code which produces the plain piece by piece. [`DecryptReader`] wraps a
+//! reader and decrypts whatever is read from it, for the code which consumes the plain piece by
+//! piece.
+//!
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`io::Result`]: https://doc.rust-lang.org/std/io/type.Result.html
+//! [`CHUNK_SIZE_IN_BYTES`]: ../aes_with_operation_mode_io/constant.CHUNK_SIZE_IN_BYTES.html
+//! [`encrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.encrypt_reader_to_writer.html
+//! [`decrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.decrypt_reader_to_writer.html
+//! [`encrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.encrypt_stream_with_hooks.html
+//! [`decrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.decrypt_stream_with_hooks.html
+//! [`CancelToken`]: ../aes_with_operation_mode_io/struct.CancelToken.html
+//! [`encrypt_file`]: ../aes_with_operation_mode_io/fn.encrypt_file.html
+//! [`decrypt_file`]: ../aes_with_operation_mode_io/fn.decrypt_file.html
+//! [`EncryptWriter`]: ../aes_with_operation_mode_io/struct.EncryptWriter.html
+//! [`DecryptReader`]: ../aes_with_operation_mode_io/struct.DecryptReader.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode::{self as mode, ChainedFunction};
+use super::error::AesError;
+use super::incremental::{Decryptor, Encryptor};
+use super::metrics;
+use super::recipe::{Mode, Recipe};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The size in bytes of the chunks which are processed and written at a time, which is 4096.
+///
+/// It is a multiple of the block size, so the chunks can be chained exactly like blocks.
+pub const CHUNK_SIZE_IN_BYTES: usize = 4096;
+
+/// Process `input` chunk by chunk with a mode function that has an IV, feeding the value returned
+/// by each call as the IV of the next call.
+fn chained_to_writer<W: Write + ?Sized>(
+    input: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+    function: ChainedFunction,
+) -> io::Result<Vec<u8>> {
+    let mut buffer = [0u8; CHUNK_SIZE_IN_BYTES];
+    let mut chaining = iv.to_owned();
+    for chunk in input.chunks(CHUNK_SIZE_IN_BYTES) {
+        let output = &mut buffer[..chunk.len()];
+        chaining = function(chunk, output, keys, &chaining);
+        writer.write_all(output)?;
+    }
+    Ok(chaining)
+}
+
+/// ECB (Electronic Codebook) Encryption into a writer
+///
+/// This function encrypts a long plain from the first parameter and write the long cipher
+/// into the second parameter, using the scheduled keys in the third parameter.
+/// Finally, it returns the final block of the cipher (NOT the plain).
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn ecb_enc<W: Write + ?Sized>(
+    plain: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+) -> io::Result<Vec<u8>> {
+    let mut buffer = [0u8; CHUNK_SIZE_IN_BYTES];
+    let mut last = Vec::new();
+    for chunk in plain.chunks(CHUNK_SIZE_IN_BYTES) {
+        let output = &mut buffer[..chunk.len()];
+        last = mode::ecb_enc(chunk, output, keys);
+        writer.write_all(output)?;
+    }
+    Ok(last)
+}
+
+/// ECB (Electronic Codebook) Decryption into a writer
+///
+/// This function decrypts a long cipher from the first parameter and write the long plain
+/// into the second parameter, using the scheduled keys in the third parameter.
+/// Finally, it returns the final block of the cipher (NOT the plain).
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn ecb_dec<W: Write + ?Sized>(
+    cipher: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+) -> io::Result<Vec<u8>> {
+    let mut buffer = [0u8; CHUNK_SIZE_IN_BYTES];
+    let mut last = Vec::new();
+    for chunk in cipher.chunks(CHUNK_SIZE_IN_BYTES) {
+        let output = &mut buffer[..chunk.len()];
+        last = mode::ecb_dec(chunk, output, keys);
+        writer.write_all(output)?;
+    }
+    Ok(last)
+}
+
+/// CBC (Cipher Block Chaining) Encryption into a writer
+///
+/// This function encrypts a long plain from the first parameter and write the long cipher
+/// into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the final block of the cipher (NOT the plain).
+/// # Examples
+/// ```
+/// use aes_frast::{aes_core, aes_with_operation_mode, aes_with_operation_mode_io};
+/// use aes_frast::N_SUBKEYS_128BIT;
+///
+/// let plain = vec![0x5Au8; 10000];
+/// let o_key = [0x0Fu8; 16];
+/// let iv = [0x04u8; 16];
+/// let mut w_keys = [0u32; N_SUBKEYS_128BIT];
+/// aes_core::key_schedule_encrypt_auto(&o_key, &mut w_keys);
+///
+/// // Any `std::io::Write` can be the sink, like a `File` or a `TcpStream`.
+/// let mut sink: Vec<u8> = Vec::new();
+/// aes_with_operation_mode_io::cbc_enc(&plain, &mut sink, &w_keys, &iv).unwrap();
+///
+/// let mut cipher = vec![0u8; 10000];
+/// aes_with_operation_mode::cbc_enc(&plain, &mut cipher, &w_keys, &iv);
+/// assert_eq!(sink, cipher);
+/// ```
+pub fn cbc_enc<W: Write + ?Sized>(
+    plain: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(plain, writer, keys, iv, mode::cbc_enc)
+}
+
+/// CBC (Cipher Block Chaining) Decryption into a writer
+///
+/// This function decrypts a long cipher from the first parameter and write the long plain
+/// into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the final block of the cipher (NOT the plain).
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn cbc_dec<W: Write + ?Sized>(
+    cipher: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(cipher, writer, keys, iv, mode::cbc_dec)
+}
+
+/// CFB (Cipher Feedback) Encryption into a writer
+///
+/// The feedback size is fixed to 128 bits, which is the same as block size.
+/// This mode doesn't require padding.
+///
+/// This function encrypts a long plain from the first parameter and write the long cipher
+/// into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the final block of the cipher (NOT the plain).
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn cfb_enc<W: Write + ?Sized>(
+    plain: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(plain, writer, keys, iv, mode::cfb_enc)
+}
+
+/// CFB (Cipher Feedback) Decryption into a writer
+///
+/// The feedback size is fixed to 128 bits, which is the same as block size.
+/// This mode doesn't require padding.
+///
+/// This function decrypts a long cipher from the first parameter and write the long plain
+/// into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the final block of the cipher (NOT the plain).
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn cfb_dec<W: Write + ?Sized>(
+    cipher: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(cipher, writer, keys, iv, mode::cfb_dec)
+}
+
+/// OFB (Output Feedback) Encryption and Decryption into a writer
+///
+/// The feedback size is fixed to 128 bits, which is the same as block size.
+/// This mode doesn't require depadding if you didn't add padding when encrypting.
+///
+/// This function encrypts (or decrypts) a long input from the first parameter and write the long
+/// output into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the final block of the encryptor output (neither the plain nor cipher).
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn ofb_enc_dec<W: Write + ?Sized>(
+    input: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(input, writer, keys, iv, mode::ofb_enc_dec)
+}
+
+/// CTR (Counter) Encryption and Decryption into a writer
+///
+/// This function encrypts (or decrypts) a long input from the first parameter and write the long
+/// output into the second parameter, using the scheduled keys and the initial counter block
+/// in the third and fourth parameters.
+/// Finally, it returns the next counter block.
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn ctr_enc_dec<W: Write + ?Sized>(
+    input: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(input, writer, keys, iv, mode::ctr_enc_dec)
+}
+
+/// PCBC (Propagating Cipher Block Chaining) Encryption into a writer (**Experimental**)
+///
+/// This function encrypts a long plain from the first parameter and write the long cipher
+/// into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the XOR result of the final block of the cipher and the final block of the plain.
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn pcbc_enc<W: Write + ?Sized>(
+    plain: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(plain, writer, keys, iv, mode::pcbc_enc)
+}
+
+/// PCBC (Propagating Cipher Block Chaining) Decryption into a writer (**Experimental**)
+///
+/// This function decrypts a long cipher from the first parameter and write the long plain
+/// into the second parameter, using the scheduled keys and the initialization vector (IV)
+/// in the third and fourth parameters.
+/// Finally, it returns the XOR result of the final block of the cipher and the final block of the plain.
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn pcbc_dec<W: Write + ?Sized>(
+    cipher: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    chained_to_writer(cipher, writer, keys, iv, mode::pcbc_dec)
+}
+
+/// The shift register of CFB8 after the bytes in `cipher` have been fed back into `register`.
+fn cfb_8_next_register(register: &[u8], cipher: &[u8]) -> Vec<u8> {
+    let joined = [register, cipher].concat();
+    joined[(joined.len() - BLOCKSIZE_IN_BYTES)..].to_owned()
+}
+
+/// CFB (Cipher Feedback) Encryption with 8-bit feedback size into a writer (**Experimental**)
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn cfb_8_enc<W: Write + ?Sized>(
+    plain: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    let mut buffer = [0u8; CHUNK_SIZE_IN_BYTES];
+    let mut register = iv.to_owned();
+    let mut last = vec![0; BLOCKSIZE_IN_BYTES];
+    for chunk in plain.chunks(CHUNK_SIZE_IN_BYTES) {
+        let output = &mut buffer[..chunk.len()];
+        last = mode::cfb_8_enc(chunk, output, keys, &register);
+        register = cfb_8_next_register(&register, output);
+        writer.write_all(output)?;
+    }
+    Ok(last)
+}
+
+/// CFB (Cipher Feedback) Decryption with 8-bit feedback size into a writer (**Experimental**)
+/// # Examples
+/// Please refer to the [`cbc_enc`] function, they are very similar.
+///
+/// [`cbc_enc`]: ../aes_with_operation_mode_io/fn.cbc_enc.html
+pub fn cfb_8_dec<W: Write + ?Sized>(
+    cipher: &[u8],
+    writer: &mut W,
+    keys: &[u32],
+    iv: &[u8],
+) -> io::Result<Vec<u8>> {
+    let mut buffer = [0u8; CHUNK_SIZE_IN_BYTES];
+    let mut register = iv.to_owned();
+    let mut last = vec![0; BLOCKSIZE_IN_BYTES];
+    for chunk in cipher.chunks(CHUNK_SIZE_IN_BYTES) {
+        let output = &mut buffer[..chunk.len()];
+        last = mode::cfb_8_dec(chunk, output, keys, &register);
+        register = cfb_8_next_register(&register, chunk);
+        writer.write_all(output)?;
+    }
+    Ok(last)
+}
+
+/// Read a reader up to the end or until `buffer` is full, retrying interrupted reads.
+pub(crate) fn read_full<R: Read + ?Sized>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Turn an [`AesError`] into an [`io::Error`], which keeps it as the inner error.
+///
+/// [`AesError`]: ../error/enum.AesError.html
+/// [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+pub(crate) fn io_error(error: AesError) -> io::Error {
+    let kind = match error {
+        AesError::InvalidDataLength | AesError::InvalidPadding => io::ErrorKind::InvalidData,
+        AesError::Cancelled => io::ErrorKind::Other,
+        _ => io::ErrorKind::InvalidInput,
+    };
+    metrics::record_error();
+    io::Error::new(kind, error)
+}
+
+/// Run the mode function of `recipe` on a chunk, updating the chaining value for the next chunk.
+pub(crate) fn process_chunk(
+    recipe: &Recipe,
+    encrypt: bool,
+    input: &[u8],
+    output: &mut [u8],
+    keys: &[u32],
+    chaining: &mut Vec<u8>,
+) {
+    if input.is_empty() {
+        return;
+    }
+    let next = recipe.mode_function(encrypt)(input, output, keys, chaining);
+    *chaining = match (recipe.mode(), encrypt) {
+        (Mode::Cfb8, true) => cfb_8_next_register(chaining, output),
+        (Mode::Cfb8, false) => cfb_8_next_register(chaining, input),
+        _ => next,
+    };
+}
+
+/// **Encrypt** everything from a reader into a writer, as described by a [`Recipe`], padding
+/// included.
+///
+/// The input is read and written in chunks of [`CHUNK_SIZE_IN_BYTES`] bytes, so neither side
+/// needs to fit in memory. Wrap unbuffered writers (like a `File`) in a `BufWriter` only if the
+/// chunk size is too small for them.
+///
+/// * *parameter* `reader`: the plain.
+/// * *parameter* `writer`: the sink of the cipher.
+/// * *parameter* `recipe`: the mode and padding.
+/// * *parameter* `key`: the key, whose length must match the recipe.
+/// * *parameter* `iv`: the initialization vector (or initial counter block), which is ignored by
+///   ECB mode.
+/// * *return* : the number of bytes written, or the error. The errors of the recipe (such as
+///   a wrong key length) are `io::Error`s which wrap the [`AesError`]. Without padding, a plain
+///   of a block mode whose length is not a multiple of 16 is found at its end only, after the
+///   complete chunks have been written.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_io::{decrypt_reader_to_writer, encrypt_reader_to_writer};
+/// use aes_frast::recipe::Recipe;
+///
+/// let recipe: Recipe = "AES-256/CBC/PKCS7".parse().unwrap();
+/// let key = [0x42u8; 32];
+/// let iv = [0x24u8; 16];
+/// let plain = vec![0x5Au8; 10000];
+///
+/// // Any `std::io::Read` and `std::io::Write` will do, like a `File` or a `TcpStream`.
+/// let mut cipher = Vec::new();
+/// let written = encrypt_reader_to_writer(&mut &plain[..], &mut cipher, &recipe, &key, &iv).unwrap();
+/// assert_eq!(written, 10016);
+/// assert_eq!(cipher, recipe.encrypt(&key, &iv, &plain).unwrap());
+///
+/// let mut decrypted = Vec::new();
+/// decrypt_reader_to_writer(&mut &cipher[..], &mut decrypted, &recipe, &key, &iv).unwrap();
+/// assert_eq!(decrypted, plain);
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`CHUNK_SIZE_IN_BYTES`]: ../aes_with_operation_mode_io/constant.CHUNK_SIZE_IN_BYTES.html
+/// [`AesError`]: ../error/enum.AesError.html
+pub fn encrypt_reader_to_writer<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+) -> io::Result<u64> {
+    encrypt_stream(reader, writer, recipe, key, iv, CHUNK_SIZE_IN_BYTES)
+}
+
+/// **Decrypt** everything from a reader into a writer, as described by a [`Recipe`], checking
+/// and removing the padding.
+///
+/// The input is read and written in chunks of [`CHUNK_SIZE_IN_BYTES`] bytes, and one chunk is
+/// held back until the next one is read, because only the last chunk has the padding.
+///
+/// * *parameter* `reader`: the cipher.
+/// * *parameter* `writer`: the sink of the plain.
+/// * *parameter* `recipe`: the mode and padding.
+/// * *parameter* `key`: the key, whose length must match the recipe.
+/// * *parameter* `iv`: the initialization vector (or initial counter block), which is ignored by
+///   ECB mode.
+/// * *return* : the number of bytes written, or the error. A malformed padding or length is
+///   only found at the end of the cipher, after all but the last chunk of the plain has been
+///   written, so the plain must not be used unless this function succeeds.
+/// # Examples
+/// Please refer to the [`encrypt_reader_to_writer`] function.
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`CHUNK_SIZE_IN_BYTES`]: ../aes_with_operation_mode_io/constant.CHUNK_SIZE_IN_BYTES.html
+/// [`encrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.encrypt_reader_to_writer.html
+pub fn decrypt_reader_to_writer<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+) -> io::Result<u64> {
+    decrypt_stream(reader, writer, recipe, key, iv, CHUNK_SIZE_IN_BYTES)
+}
+
+/// **Encrypt** everything from a reader into a writer, as described by a [`Recipe`], padding
+/// included, with buffers of `buffer_size` bytes.
+///
+/// This is [`encrypt_reader_to_writer`] with a chosen buffer size instead of
+/// [`CHUNK_SIZE_IN_BYTES`]: larger buffers mean fewer (and larger) reads and writes, smaller ones
+/// less memory. The buffer size is rounded up to a multiple of 16 bytes, and is at least 16.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_io::{decrypt_stream, encrypt_stream};
+/// use aes_frast::recipe::Recipe;
+///
+/// let recipe: Recipe = "AES-128/CTR".parse().unwrap();
+/// let key = [0x42u8; 16];
+/// let iv = [0x24u8; 16];
+/// let plain = vec![0x5Au8; 100_000];
+///
+/// let mut cipher = Vec::new();
+/// encrypt_stream(&mut &plain[..], &mut cipher, &recipe, &key, &iv, 1 << 16).unwrap();
+/// assert_eq!(cipher, recipe.encrypt(&key, &iv, &plain).unwrap());
+///
+/// let mut decrypted = Vec::new();
+/// decrypt_stream(&mut &cipher[..], &mut decrypted, &recipe, &key, &iv, 1000).unwrap();
+/// assert_eq!(decrypted, plain);
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`encrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.encrypt_reader_to_writer.html
+/// [`CHUNK_SIZE_IN_BYTES`]: ../aes_with_operation_mode_io/constant.CHUNK_SIZE_IN_BYTES.html
+pub fn encrypt_stream<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+    buffer_size: usize,
+) -> io::Result<u64> {
+    encrypt_stream_with_hooks(
+        reader,
+        writer,
+        recipe,
+        key,
+        iv,
+        buffer_size,
+        StreamHooks::default(),
+    )
+}
+
+/// **Decrypt** everything from a reader into a writer, as described by a [`Recipe`], checking
+/// and removing the padding, with buffers of `buffer_size` bytes.
+///
+/// This is [`decrypt_reader_to_writer`] with a chosen buffer size, rounded up like in
+/// [`encrypt_stream`]. The same as there, a malformed padding or length is only found at the end,
+/// so the plain must not be used unless this function succeeds.
+/// # Examples
+/// Please refer to the [`encrypt_stream`] function.
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`decrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.decrypt_reader_to_writer.html
+/// [`encrypt_stream`]: ../aes_with_operation_mode_io/fn.encrypt_stream.html
+pub fn decrypt_stream<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+    buffer_size: usize,
+) -> io::Result<u64> {
+    decrypt_stream_with_hooks(
+        reader,
+        writer,
+        recipe,
+        key,
+        iv,
+        buffer_size,
+        StreamHooks::default(),
+    )
+}
+
+/// A flag which cancels a running [`encrypt_stream_with_hooks`] or similar function from any
+/// thread. Clones share the flag.
+///
+/// [`encrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.encrypt_stream_with_hooks.html
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the operations which check this token. It can't be undone.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The optional hooks of the long-running functions, such as [`encrypt_stream_with_hooks`].
+///
+/// [`encrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.encrypt_stream_with_hooks.html
+#[derive(Default)]
+pub struct StreamHooks<'a> {
+    /// Called after each chunk with the number of input bytes processed so far.
+    pub progress: Option<&'a mut dyn FnMut(u64)>,
+    /// Checked before each chunk: once it is cancelled, the function stops with an `io::Error`
+    /// of kind `Other` which wraps `AesError::Cancelled`.
+    pub cancel: Option<&'a CancelToken>,
+}
+
+impl StreamHooks<'_> {
+    fn check_cancel(&self) -> io::Result<()> {
+        match self.cancel {
+            Some(token) if token.is_cancelled() => Err(io_error(AesError::Cancelled)),
+            _ => Ok(()),
+        }
+    }
+
+    fn report(&mut self, processed: u64) {
+        if let Some(progress) = &mut self.progress {
+            progress(processed);
+        }
+    }
+}
+
+impl std::fmt::Debug for StreamHooks<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamHooks")
+            .field("progress", &self.progress.is_some())
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+/// The optional hooks of the parallel functions, such as
+/// [`Ctr::encrypt_decrypt_parallel_with_hooks`].
+///
+/// [`Ctr::encrypt_decrypt_parallel_with_hooks`]: ../aes_with_operation_mode/struct.Ctr.html#method.encrypt_decrypt_parallel_with_hooks
+#[cfg(feature = "parallel")]
+#[derive(Default)]
+pub struct ParallelHooks<'a> {
+    /// Called after each slice of 4096 blocks with the number of bytes processed so far by all
+    /// threads. It may be called from several threads at once, so the values may arrive out of
+    /// order.
+    pub progress: Option<&'a (dyn Fn(u64) + Sync)>,
+    /// Checked by every thread before each slice of 4096 blocks: once it is cancelled, the
+    /// function stops with `AesError::Cancelled`.
+    pub cancel: Option<&'a CancelToken>,
+}
+
+#[cfg(feature = "parallel")]
+impl std::fmt::Debug for ParallelHooks<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParallelHooks")
+            .field("progress", &self.progress.is_some())
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+/// **Encrypt** everything from a reader into a writer, like [`encrypt_stream`], reporting the
+/// progress and checking for cancellation through `hooks`.
+///
+/// **Attention!** After a cancellation (or any other error), the writer holds an incomplete
+/// cipher, which must be discarded. [`encrypt_file`] removes its output file then.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_io::{
+///     encrypt_stream, encrypt_stream_with_hooks, CancelToken, StreamHooks,
+/// };
+/// use aes_frast::recipe::Recipe;
+/// use aes_frast::AesError;
+///
+/// let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+/// let key = [0x42u8; 16];
+/// let iv = [0x24u8; 16];
+/// let plain = vec![0x5Au8; 10_000];
+///
+/// let mut reports = Vec::new();
+/// let mut cipher = Vec::new();
+/// let hooks = StreamHooks {
+///     progress: Some(&mut |processed| reports.push(processed)),
+///     cancel: None,
+/// };
+/// encrypt_stream_with_hooks(&mut &plain[..], &mut cipher, &recipe, &key, &iv, 4096, hooks)
+///     .unwrap();
+/// assert_eq!(reports, [4096, 8192, 10_000]);
+///
+/// // A token cancelled by another thread, e.g. the "Cancel" button of a GUI.
+/// let token = CancelToken::new();
+/// token.clone().cancel();
+/// let hooks = StreamHooks {
+///     progress: None,
+///     cancel: Some(&token),
+/// };
+/// let error = encrypt_stream_with_hooks(
+///     &mut &plain[..], &mut Vec::new(), &recipe, &key, &iv, 4096, hooks,
+/// )
+/// .unwrap_err();
+/// assert_eq!(error.into_inner().unwrap().downcast_ref(), Some(&AesError::Cancelled));
+/// ```
+///
+/// [`encrypt_stream`]: ../aes_with_operation_mode_io/fn.encrypt_stream.html
+/// [`encrypt_file`]: ../aes_with_operation_mode_io/fn.encrypt_file.html
+pub fn encrypt_stream_with_hooks<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+    buffer_size: usize,
+    mut hooks: StreamHooks,
+) -> io::Result<u64> {
+    let mut encryptor = Encryptor::new(recipe, key, iv).map_err(io_error)?;
+    let mut input = vec![0u8; block_aligned(buffer_size)];
+    let mut output = Vec::with_capacity(input.len() + BLOCKSIZE_IN_BYTES);
+    let (mut processed, mut written) = (0u64, 0u64);
+    loop {
+        hooks.check_cancel()?;
+        let length = read_full(reader, &mut input)?;
+        output.clear();
+        if length == 0 {
+            encryptor.finalize(&mut output).map_err(io_error)?;
+            writer.write_all(&output)?;
+            return Ok(written + output.len() as u64);
+        }
+        encryptor.update(&input[..length], &mut output);
+        writer.write_all(&output)?;
+        written += output.len() as u64;
+        processed += length as u64;
+        hooks.report(processed);
+    }
+}
+
+/// **Decrypt** everything from a reader into a writer, like [`decrypt_stream`], reporting the
+/// progress and checking for cancellation through `hooks`.
+///
+/// **Attention!** After a cancellation (or any other error), the writer holds an incomplete
+/// plain, which must be discarded. [`decrypt_file`] removes its output file then.
+/// # Examples
+/// Please refer to the [`encrypt_stream_with_hooks`] function.
+///
+/// [`decrypt_stream`]: ../aes_with_operation_mode_io/fn.decrypt_stream.html
+/// [`decrypt_file`]: ../aes_with_operation_mode_io/fn.decrypt_file.html
+/// [`encrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.encrypt_stream_with_hooks.html
+pub fn decrypt_stream_with_hooks<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+    buffer_size: usize,
+    mut hooks: StreamHooks,
+) -> io::Result<u64> {
+    let mut decryptor = Decryptor::new(recipe, key, iv).map_err(io_error)?;
+    let mut input = vec![0u8; block_aligned(buffer_size)];
+    let mut output = Vec::with_capacity(input.len() + BLOCKSIZE_IN_BYTES);
+    let (mut processed, mut written) = (0u64, 0u64);
+    loop {
+        hooks.check_cancel()?;
+        let length = read_full(reader, &mut input)?;
+        output.clear();
+        if length == 0 {
+            decryptor.finalize(&mut output).map_err(io_error)?;
+            writer.write_all(&output)?;
+            return Ok(written + output.len() as u64);
+        }
+        decryptor.update(&input[..length], &mut output);
+        writer.write_all(&output)?;
+        written += output.len() as u64;
+        processed += length as u64;
+        hooks.report(processed);
+    }
+}
+
+/// **Encrypt** the file `input` into the new file `output`, as described by a [`Recipe`], with
+/// [`encrypt_stream_with_hooks`].
+///
+/// If the encryption is cancelled or fails, `output` is removed, so no incomplete cipher is
+/// left behind.
+///
+/// * *parameter* `input`: the path of the plain.
+/// * *parameter* `output`: the path of the cipher, which is created or truncated.
+/// * *parameter* `recipe`: the mode and padding.
+/// * *parameter* `key`: the key, whose length must match the recipe.
+/// * *parameter* `iv`: the initialization vector (or initial counter block), which is ignored by
+///   ECB mode.
+/// * *parameter* `hooks`: the progress callback and the cancellation token, if any.
+/// * *return* : the number of bytes written, or the error.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_io::{decrypt_file, encrypt_file, StreamHooks};
+/// use aes_frast::recipe::Recipe;
+///
+/// let recipe: Recipe = "AES-256/CTR".parse().unwrap();
+/// let key = [0x42u8; 32];
+/// let iv = [0x24u8; 16];
+/// let dir = std::env::temp_dir();
+/// let (plain, cipher, decrypted) = (
+///     dir.join(format!("aes_frast_doc_{}.txt", std::process::id())),
+///     dir.join(format!("aes_frast_doc_{}.enc", std::process::id())),
+///     dir.join(format!("aes_frast_doc_{}.dec", std::process::id())),
+/// );
+/// std::fs::write(&plain, b"Hello, file!").unwrap();
+///
+/// encrypt_file(&plain, &cipher, &recipe, &key, &iv, StreamHooks::default()).unwrap();
+/// decrypt_file(&cipher, &decrypted, &recipe, &key, &iv, StreamHooks::default()).unwrap();
+/// assert_eq!(std::fs::read(&decrypted).unwrap(), b"Hello, file!");
+/// # for path in [plain, cipher, decrypted] {
+/// #     std::fs::remove_file(path).unwrap();
+/// # }
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`encrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.encrypt_stream_with_hooks.html
+pub fn encrypt_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+    hooks: StreamHooks,
+) -> io::Result<u64> {
+    file_to_file(input.as_ref(), output.as_ref(), |reader, writer| {
+        encrypt_stream_with_hooks(reader, writer, recipe, key, iv, CHUNK_SIZE_IN_BYTES, hooks)
+    })
+}
+
+/// **Decrypt** the file `input` into the new file `output`, as described by a [`Recipe`], with
+/// [`decrypt_stream_with_hooks`].
+///
+/// If the decryption is cancelled or fails (a malformed padding included), `output` is removed,
+/// so no incomplete or unchecked plain is left behind. The parameters are those of
+/// [`encrypt_file`].
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`decrypt_stream_with_hooks`]: ../aes_with_operation_mode_io/fn.decrypt_stream_with_hooks.html
+/// [`encrypt_file`]: ../aes_with_operation_mode_io/fn.encrypt_file.html
+pub fn decrypt_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    recipe: &Recipe,
+    key: &[u8],
+    iv: &[u8],
+    hooks: StreamHooks,
+) -> io::Result<u64> {
+    file_to_file(input.as_ref(), output.as_ref(), |reader, writer| {
+        decrypt_stream_with_hooks(reader, writer, recipe, key, iv, CHUNK_SIZE_IN_BYTES, hooks)
+    })
+}
+
+/// Run `process` from the file `input` into the file `output`, removing `output` on errors.
+fn file_to_file<F>(input: &Path, output: &Path, process: F) -> io::Result<u64>
+where
+    F: FnOnce(&mut BufReader<File>, &mut BufWriter<File>) -> io::Result<u64>,
+{
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer = BufWriter::new(File::create(output)?);
+    let result = process(&mut reader, &mut writer).and_then(|written| {
+        writer.flush()?;
+        Ok(written)
+    });
+    if result.is_err() {
+        drop(writer);
+        let _ = fs::remove_file(output);
+    }
+    result
+}
+
+/// Round `size` up to a non-zero multiple of the block size.
+fn block_aligned(size: usize) -> usize {
+    size.max(1).div_ceil(BLOCKSIZE_IN_BYTES) * BLOCKSIZE_IN_BYTES
+}
+
+/// A writer which **encrypts** everything written to it into another writer, as described by a
+/// [`Recipe`], padding included.
+///
+/// The cipher of the complete blocks is written to the inner writer as soon as they are written
+/// to this one. The rest, with the padding, is written by [`finish`], which must be called at the
+/// end: dropping the writer without it loses the last block. `flush` flushes the inner writer,
+/// but can't write an incomplete block.
+///
+/// After an error of the inner writer, the cipher written so far is incomplete, and the writer
+/// should be dropped.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_io::EncryptWriter;
+/// use aes_frast::recipe::Recipe;
+/// use std::io::Write;
+///
+/// let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+/// let key = [0x42u8; 16];
+/// let iv = [0x24u8; 16];
+///
+/// // Any `std::io::Write` will do, like a `File` or a `TcpStream`.
+/// let mut writer = EncryptWriter::new(Vec::new(), &recipe, &key, &iv).unwrap();
+/// write!(writer, "Hello, {}!", "world").unwrap();
+/// writer.write_all(&[0x5Au8; 100]).unwrap();
+/// let cipher = writer.finish().unwrap();
+///
+/// let mut plain = b"Hello, world!".to_vec();
+/// plain.extend_from_slice(&[0x5Au8; 100]);
+/// assert_eq!(cipher, recipe.encrypt(&key, &iv, &plain).unwrap());
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`finish`]: ../aes_with_operation_mode_io/struct.EncryptWriter.html#method.finish
+pub struct EncryptWriter<W> {
+    inner: W,
+    encryptor: Encryptor,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /// Wrap `inner`.
+    ///
+    /// * *parameter* `inner`: the sink of the cipher.
+    /// * *parameter* `recipe`: the mode and padding.
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *return* : the writer, or `Err(AesError::InvalidKeyLength)` or
+    ///   `Err(AesError::InvalidParameter)` (for the IV).
+    pub fn new(inner: W, recipe: &Recipe, key: &[u8], iv: &[u8]) -> Result<Self, AesError> {
+        Ok(Self {
+            inner,
+            encryptor: Encryptor::new(recipe, key, iv)?,
+            buffer: Vec::with_capacity(CHUNK_SIZE_IN_BYTES),
+        })
+    }
+
+    /// Borrow the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Borrow the inner writer mutably. Writing to it directly corrupts the cipher.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Encrypt the rest of the plain with the padding, write it and flush the inner writer.
+    ///
+    /// * *return* : the inner writer, or the error. Without padding, a plain of a block mode
+    ///   whose length is not a multiple of 16 is an `io::Error` which wraps
+    ///   `AesError::InvalidDataLength`.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.buffer.clear();
+        self.encryptor
+            .finalize(&mut self.buffer)
+            .map_err(io_error)?;
+        self.inner.write_all(&self.buffer)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.clear();
+        self.encryptor.update(buf, &mut self.buffer);
+        self.inner.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl_redacted_debug!(EncryptWriter<W>);
+
+/// A reader which **decrypts** everything read from another reader, as described by a
+/// [`Recipe`], checking and removing the padding at the end.
+///
+/// The inner reader is read in chunks of up to [`CHUNK_SIZE_IN_BYTES`] bytes, so the memory used
+/// doesn't depend on the length of the cipher. A malformed padding or length is found at the end
+/// of the inner reader only, and is returned by `read` as an `io::Error` of kind `InvalidData`
+/// which wraps the `AesError`, after all but the last block of the plain has been read. So the
+/// plain must not be used unless the reader reaches its end without error.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_io::DecryptReader;
+/// use aes_frast::recipe::Recipe;
+/// use std::io::Read;
+///
+/// let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+/// let key = [0x42u8; 16];
+/// let iv = [0x24u8; 16];
+/// let cipher = recipe.encrypt(&key, &iv, b"Hello, world!").unwrap();
+///
+/// // Any `std::io::Read` will do, like a `File` or a `TcpStream`.
+/// let mut reader = DecryptReader::new(&cipher[..], &recipe, &key, &iv).unwrap();
+/// let mut plain = String::new();
+/// reader.read_to_string(&mut plain).unwrap();
+/// assert_eq!(plain, "Hello, world!");
+///
+/// // A corrupted padding is an error at the end.
+/// let mut corrupted = cipher.clone();
+/// corrupted[15] ^= 1;
+/// let mut reader = DecryptReader::new(&corrupted[..], &recipe, &key, &iv).unwrap();
+/// assert!(reader.read_to_end(&mut Vec::new()).is_err());
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`CHUNK_SIZE_IN_BYTES`]: ../aes_with_operation_mode_io/constant.CHUNK_SIZE_IN_BYTES.html
+pub struct DecryptReader<R> {
+    inner: R,
+    decryptor: Option<Decryptor>,
+    error: Option<AesError>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Wrap `inner`.
+    ///
+    /// * *parameter* `inner`: the cipher.
+    /// * *parameter* `recipe`: the mode and padding.
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *return* : the reader, or `Err(AesError::InvalidKeyLength)` or
+    ///   `Err(AesError::InvalidParameter)` (for the IV).
+    pub fn new(inner: R, recipe: &Recipe, key: &[u8], iv: &[u8]) -> Result<Self, AesError> {
+        Ok(Self {
+            inner,
+            decryptor: Some(Decryptor::new(recipe, key, iv)?),
+            error: None,
+            input: vec![0u8; CHUNK_SIZE_IN_BYTES],
+            output: Vec::with_capacity(CHUNK_SIZE_IN_BYTES + BLOCKSIZE_IN_BYTES),
+            position: 0,
+        })
+    }
+
+    /// Borrow the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwrap the inner reader. The plain which was decrypted but not read yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decrypt the next chunk of the inner reader into `output`, or finalize at its end.
+    fn fill_output(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.position = 0;
+        let length = loop {
+            match self.inner.read(&mut self.input) {
+                Ok(length) => break length,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        };
+        if length == 0 {
+            if let Some(decryptor) = self.decryptor.take() {
+                if let Err(error) = decryptor.finalize(&mut self.output) {
+                    self.error = Some(error);
+                    return Err(io_error(error));
+                }
+            }
+        } else if let Some(decryptor) = &mut self.decryptor {
+            decryptor.update(&self.input[..length], &mut self.output);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.output.len() {
+            if let Some(error) = self.error {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+            }
+            if self.decryptor.is_none() {
+                return Ok(0);
+            }
+            self.fill_output()?;
+        }
+        let length = buf.len().min(self.output.len() - self.position);
+        buf[..length].copy_from_slice(&self.output[self.position..(self.position + length)]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+impl_redacted_debug!(DecryptReader<R>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core;
+    use crate::N_SUBKEYS_192BIT;
+
+    // Longer than two chunks and not a multiple of the chunk size, so the chaining between chunks
+    // and the last short chunk are both covered.
+    const LENGTH: usize = 2 * CHUNK_SIZE_IN_BYTES + 3 * BLOCKSIZE_IN_BYTES;
+
+    fn setup() -> (
+        Vec<u8>,
+        [u32; N_SUBKEYS_192BIT],
+        [u32; N_SUBKEYS_192BIT],
+        Vec<u8>,
+    ) {
+        let plain: Vec<u8> = (0..LENGTH).map(|i| (i * 7 + 3) as u8).collect();
+        let o_key: Vec<u8> = (0..24).collect();
+        let mut e_keys = [0u32; N_SUBKEYS_192BIT];
+        let mut d_keys = [0u32; N_SUBKEYS_192BIT];
+        aes_core::key_schedule_encrypt_auto(&o_key, &mut e_keys);
+        aes_core::key_schedule_decrypt_auto(&o_key, &mut d_keys);
+        let iv: Vec<u8> = (0x40..0x50).collect();
+        (plain, e_keys, d_keys, iv)
+    }
+
+    #[test]
+    fn ecb_works() {
+        let (plain, e_keys, d_keys, _) = setup();
+        let mut expected = vec![0u8; LENGTH];
+        let expected_last = mode::ecb_enc(&plain, &mut expected, &e_keys);
+        let mut sink = Vec::new();
+        let last = ecb_enc(&plain, &mut sink, &e_keys).unwrap();
+        assert_eq!(sink, expected);
+        assert_eq!(last, expected_last);
+        let mut decrypted = Vec::new();
+        ecb_dec(&sink, &mut decrypted, &d_keys).unwrap();
+        assert_eq!(decrypted, plain);
+    }
+
+    #[test]
+    fn chained_modes_work() {
+        let (plain, e_keys, d_keys, iv) = setup();
+        type WriterFunction = fn(&[u8], &mut Vec<u8>, &[u32], &[u8]) -> io::Result<Vec<u8>>;
+        let cases: [(ChainedFunction, WriterFunction, WriterFunction, bool); 6] = [
+            (mode::cbc_enc, cbc_enc, cbc_dec, true),
+            (mode::cfb_enc, cfb_enc, cfb_dec, false),
+            (mode::ofb_enc_dec, ofb_enc_dec, ofb_enc_dec, false),
+            (mode::ctr_enc_dec, ctr_enc_dec, ctr_enc_dec, false),
+            (mode::pcbc_enc, pcbc_enc, pcbc_dec, true),
+            (mode::cfb_8_enc, cfb_8_enc, cfb_8_dec, false),
+        ];
+        for (slice_enc, writer_enc, writer_dec, use_dkeys) in cases {
+            let mut expected = vec![0u8; LENGTH];
+            let expected_last = slice_enc(&plain, &mut expected, &e_keys, &iv);
+            let mut sink = Vec::new();
+            let last = writer_enc(&plain, &mut sink, &e_keys, &iv).unwrap();
+            assert_eq!(sink, expected);
+            assert_eq!(last, expected_last);
+            let mut decrypted = Vec::new();
+            let keys = if use_dkeys { &d_keys } else { &e_keys };
+            writer_dec(&sink, &mut decrypted, keys, &iv).unwrap();
+            assert_eq!(decrypted, plain);
+        }
+    }
+
+    #[test]
+    fn unaligned_stream_modes_work() {
+        let (plain, e_keys, _, iv) = setup();
+        let plain = &plain[..(LENGTH - 5)];
+        let mut expected = vec![0u8; plain.len()];
+        mode::cfb_enc(plain, &mut expected, &e_keys, &iv);
+        let mut sink = Vec::new();
+        cfb_enc(plain, &mut sink, &e_keys, &iv).unwrap();
+        assert_eq!(sink, expected);
+        mode::ofb_enc_dec(plain, &mut expected, &e_keys, &iv);
+        let mut sink = Vec::new();
+        ofb_enc_dec(plain, &mut sink, &e_keys, &iv).unwrap();
+        assert_eq!(sink, expected);
+        mode::ctr_enc_dec(plain, &mut expected, &e_keys, &iv);
+        let mut sink = Vec::new();
+        ctr_enc_dec(plain, &mut sink, &e_keys, &iv).unwrap();
+        assert_eq!(sink, expected);
+    }
+
+    #[test]
+    fn dyn_writer_works() {
+        let (plain, e_keys, _, iv) = setup();
+        let mut sink: Vec<u8> = Vec::new();
+        let writer: &mut dyn Write = &mut sink;
+        cbc_enc(&plain, writer, &e_keys, &iv).unwrap();
+        assert_eq!(sink.len(), LENGTH);
+    }
+
+    #[test]
+    fn reader_to_writer_matches_recipe() {
+        let key: Vec<u8> = (0..16).collect();
+        let iv: Vec<u8> = (0x40..0x50).collect();
+        let recipes = [
+            "AES-128/ECB/PKCS7",
+            "AES-128/CBC/ANSIX923",
+            "AES-128/PCBC/PKCS7",
+            "AES-128/CFB",
+            "AES-128/CFB8",
+            "AES-128/OFB",
+            "AES-128/CTR",
+        ];
+        // Empty, shorter than a block, exactly one chunk, and across chunks.
+        let lengths = [0, 5, CHUNK_SIZE_IN_BYTES, LENGTH + 7];
+        for recipe in recipes {
+            let recipe: Recipe = recipe.parse().unwrap();
+            for length in lengths {
+                let plain: Vec<u8> = (0..length).map(|i| (i * 7 + 3) as u8).collect();
+                let expected = recipe.encrypt(&key, &iv, &plain).unwrap();
+                let mut cipher = Vec::new();
+                let written =
+                    encrypt_reader_to_writer(&mut &plain[..], &mut cipher, &recipe, &key, &iv)
+                        .unwrap();
+                assert_eq!(cipher, expected, "{} {}", recipe, length);
+                assert_eq!(written, cipher.len() as u64);
+                let mut decrypted = Vec::new();
+                let written =
+                    decrypt_reader_to_writer(&mut &cipher[..], &mut decrypted, &recipe, &key, &iv)
+                        .unwrap();
+                assert_eq!(decrypted, plain, "{} {}", recipe, length);
+                assert_eq!(written, plain.len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn reader_to_writer_errors() {
+        let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+        let error = encrypt_reader_to_writer(
+            &mut &[0u8; 4][..],
+            &mut Vec::new(),
+            &recipe,
+            &[0u8; 15],
+            &[0u8; 16],
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let inner = error.into_inner().unwrap();
+        assert_eq!(
+            inner.downcast_ref::<AesError>(),
+            Some(&AesError::InvalidKeyLength)
+        );
+
+        let cipher = [0u8; 32];
+        let error = decrypt_reader_to_writer(
+            &mut &cipher[..],
+            &mut Vec::new(),
+            &recipe,
+            &[0u8; 16],
+            &[0u8; 16],
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn encrypt_writer_matches_recipe() {
+        let (plain, _, _, iv) = setup();
+        let key: Vec<u8> = (0..24).collect();
+        for recipe in ["AES-192/CBC/PKCS7", "AES-192/CFB8/NoPadding", "AES-192/CTR"] {
+            let recipe: Recipe = recipe.parse().unwrap();
+            let mut writer = EncryptWriter::new(Vec::new(), &recipe, &key, &iv).unwrap();
+            for chunk in plain.chunks(1000) {
+                writer.write_all(chunk).unwrap();
+            }
+            writer.flush().unwrap();
+            assert_eq!(
+                writer.finish().unwrap(),
+                recipe.encrypt(&key, &iv, &plain).unwrap()
+            );
+        }
+
+        let recipe: Recipe = "AES-192/CBC/NoPadding".parse().unwrap();
+        let mut writer = EncryptWriter::new(Vec::new(), &recipe, &key, &iv).unwrap();
+        writer.write_all(&plain[..40]).unwrap();
+        assert_eq!(writer.get_ref().len(), 32);
+        let error = writer.finish().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(EncryptWriter::new(Vec::new(), &recipe, &key[..16], &iv).is_err());
+    }
+
+    #[test]
+    fn decrypt_reader_matches_recipe() {
+        let (plain, _, _, iv) = setup();
+        let key: Vec<u8> = (0..24).collect();
+        for recipe in ["AES-192/CBC/PKCS7", "AES-192/CFB8/NoPadding", "AES-192/CTR"] {
+            let recipe: Recipe = recipe.parse().unwrap();
+            let cipher = recipe.encrypt(&key, &iv, &plain).unwrap();
+            let mut reader = DecryptReader::new(&cipher[..], &recipe, &key, &iv).unwrap();
+            let mut decrypted = Vec::new();
+            let mut buffer = [0u8; 1000];
+            loop {
+                let length = reader.read(&mut buffer).unwrap();
+                if length == 0 {
+                    break;
+                }
+                decrypted.extend_from_slice(&buffer[..length]);
+            }
+            assert_eq!(decrypted, plain);
+            assert_eq!(reader.read(&mut buffer).unwrap(), 0);
+        }
+
+        let recipe: Recipe = "AES-192/CBC/PKCS7".parse().unwrap();
+        let cipher = recipe.encrypt(&key, &iv, &plain).unwrap();
+        let mut reader = DecryptReader::new(&cipher[..40], &recipe, &key, &iv).unwrap();
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        // The error stays.
+        assert!(reader.read(&mut [0u8; 16]).is_err());
+        let mut reader = DecryptReader::new(&[][..], &recipe, &key, &iv).unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn stream_buffer_sizes() {
+        let (plain, _, _, iv) = setup();
+        let key: Vec<u8> = (0..24).collect();
+        let recipe: Recipe = "AES-192/PCBC/ANSIX923".parse().unwrap();
+        let expected = recipe.encrypt(&key, &iv, &plain).unwrap();
+        for buffer_size in [0, 1, 17, 4096, 100_000] {
+            let mut cipher = Vec::new();
+            let written = encrypt_stream(
+                &mut &plain[..],
+                &mut cipher,
+                &recipe,
+                &key,
+                &iv,
+                buffer_size,
+            )
+            .unwrap();
+            assert_eq!(written, expected.len() as u64);
+            assert_eq!(cipher, expected);
+            let mut decrypted = Vec::new();
+            let written = decrypt_stream(
+                &mut &cipher[..],
+                &mut decrypted,
+                &recipe,
+                &key,
+                &iv,
+                buffer_size,
+            )
+            .unwrap();
+            assert_eq!(written, plain.len() as u64);
+            assert_eq!(decrypted, plain);
+        }
+    }
+
+    #[test]
+    fn stream_hooks() {
+        let (plain, _, _, iv) = setup();
+        let key: Vec<u8> = (0..24).collect();
+        let recipe: Recipe = "AES-192/CBC/PKCS7".parse().unwrap();
+        let cipher = recipe.encrypt(&key, &iv, &plain).unwrap();
+
+        let mut reports = Vec::new();
+        let hooks = StreamHooks {
+            progress: Some(&mut |processed| reports.push(processed)),
+            cancel: None,
+        };
+        let mut decrypted = Vec::new();
+        decrypt_stream_with_hooks(
+            &mut &cipher[..],
+            &mut decrypted,
+            &recipe,
+            &key,
+            &iv,
+            4096,
+            hooks,
+        )
+        .unwrap();
+        assert_eq!(decrypted, plain);
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(reports.last(), Some(&(cipher.len() as u64)));
+
+        // Cancelled from the progress callback after the first chunk.
+        let token = CancelToken::new();
+        let remote = token.clone();
+        let mut calls = 0;
+        let hooks = StreamHooks {
+            progress: Some(&mut |_| {
+                calls += 1;
+                remote.cancel();
+            }),
+            cancel: Some(&token),
+        };
+        let error = encrypt_stream_with_hooks(
+            &mut &plain[..],
+            &mut Vec::new(),
+            &recipe,
+            &key,
+            &iv,
+            4096,
+            hooks,
+        )
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        assert_eq!(
+            error.into_inner().unwrap().downcast_ref(),
+            Some(&AesError::Cancelled)
+        );
+    }
+
+    #[test]
+    fn files_are_removed_on_cancel() {
+        let (plain, _, _, iv) = setup();
+        let key: Vec<u8> = (0..24).collect();
+        let recipe: Recipe = "AES-192/OFB".parse().unwrap();
+        let dir = std::env::temp_dir();
+        let name = format!("aes_frast_test_{}_cancel", std::process::id());
+        let (input, output) = (
+            dir.join(format!("{name}.in")),
+            dir.join(format!("{name}.out")),
+        );
+        fs::write(&input, &plain).unwrap();
+
+        let token = CancelToken::new();
+        let hooks = StreamHooks {
+            progress: Some(&mut |_| token.cancel()),
+            cancel: Some(&token),
+        };
+        assert!(encrypt_file(&input, &output, &recipe, &key, &iv, hooks).is_err());
+        assert!(!output.exists());
+
+        let written = encrypt_file(&input, &output, &recipe, &key, &iv, StreamHooks::default());
+        assert_eq!(written.unwrap(), plain.len() as u64);
+        assert_eq!(
+            fs::read(&output).unwrap(),
+            recipe.encrypt(&key, &iv, &plain).unwrap()
+        );
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+}
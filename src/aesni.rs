@@ -0,0 +1,204 @@
+//! # aesni
+//! `aesni` runs the block functions of `aes_core` on the AES-NI instructions of x86 and x86_64
+//! CPUs, when the CPU has them.
+//!
+//! The sub-keys are the ones of `aes_core`, so nothing else changes: the block functions check
+//! [`available`] and fall back to the tables on CPUs without AES-NI. The instructions take
+//! constant time, unlike the table lookups.
+//!
+//! This is the only module of the library with `unsafe` code, so it is behind the `aesni`
+//! feature.
+#![allow(unsafe_code)]
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+
+/// Whether the CPU has AES-NI. The result is detected once and cached by `std`.
+pub(crate) fn available() -> bool {
+    std::is_x86_feature_detected!("aes") && std::is_x86_feature_detected!("sse2")
+}
+
+/// **Encrypt** a block in place with the sub-keys (length = 44, 52 or 60) for encryption.
+///
+/// Must only be called when [`available`] returns `true`.
+pub(crate) fn encrypt_block(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+    debug_assert!(available());
+    // SAFETY: the callers check `available()` first, so the CPU supports the instructions.
+    unsafe { encrypt_block_aesni(block, subkeys) }
+}
+
+/// **Decrypt** a block in place with the sub-keys (length = 44, 52 or 60) for decryption.
+///
+/// Must only be called when [`available`] returns `true`.
+pub(crate) fn decrypt_block(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+    debug_assert!(available());
+    // SAFETY: the callers check `available()` first, so the CPU supports the instructions.
+    unsafe { decrypt_block_aesni(block, subkeys) }
+}
+
+/// **Encrypt** `N` consecutive blocks (length = 16 * `N`) in place with the sub-keys for
+/// encryption. The rounds of the blocks are interleaved to keep the AES unit busy.
+///
+/// Must only be called when [`available`] returns `true`.
+pub(crate) fn encrypt_blocks<const N: usize>(blocks: &mut [u8], subkeys: &[u32]) {
+    debug_assert!(available());
+    assert_eq!(blocks.len(), N * BLOCKSIZE_IN_BYTES);
+    // SAFETY: the callers check `available()` first, so the CPU supports the instructions.
+    unsafe { encrypt_blocks_aesni::<N>(blocks, subkeys) }
+}
+
+/// **Decrypt** `N` consecutive blocks (length = 16 * `N`) in place with the sub-keys for
+/// decryption. The rounds of the blocks are interleaved to keep the AES unit busy.
+///
+/// Must only be called when [`available`] returns `true`.
+pub(crate) fn decrypt_blocks<const N: usize>(blocks: &mut [u8], subkeys: &[u32]) {
+    debug_assert!(available());
+    assert_eq!(blocks.len(), N * BLOCKSIZE_IN_BYTES);
+    // SAFETY: the callers check `available()` first, so the CPU supports the instructions.
+    unsafe { decrypt_blocks_aesni::<N>(blocks, subkeys) }
+}
+
+/// Load the round key `round` from the sub-keys. The sub-keys are packed in little-endian order,
+/// so the first word goes to the lowest 32 bits.
+#[target_feature(enable = "sse2")]
+unsafe fn round_key(subkeys: &[u32], round: usize) -> __m128i {
+    let words = &subkeys[(4 * round)..(4 * round + 4)];
+    _mm_set_epi32(
+        words[3] as i32,
+        words[2] as i32,
+        words[1] as i32,
+        words[0] as i32,
+    )
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn encrypt_block_aesni(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+    let rounds = subkeys.len() / 4 - 1;
+    let mut state = _mm_loadu_si128(block.as_ptr().cast());
+    state = _mm_xor_si128(state, round_key(subkeys, 0));
+    for round in 1..rounds {
+        state = _mm_aesenc_si128(state, round_key(subkeys, round));
+    }
+    state = _mm_aesenclast_si128(state, round_key(subkeys, rounds));
+    _mm_storeu_si128(block.as_mut_ptr().cast(), state);
+}
+
+// The sub-keys for decryption of `aes_core` already have the inverse MixColumn applied to the
+// middle rounds, which is what `aesdec` expects. They are used from the last round to the first.
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decrypt_block_aesni(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+    let rounds = subkeys.len() / 4 - 1;
+    let mut state = _mm_loadu_si128(block.as_ptr().cast());
+    state = _mm_xor_si128(state, round_key(subkeys, rounds));
+    for round in (1..rounds).rev() {
+        state = _mm_aesdec_si128(state, round_key(subkeys, round));
+    }
+    state = _mm_aesdeclast_si128(state, round_key(subkeys, 0));
+    _mm_storeu_si128(block.as_mut_ptr().cast(), state);
+}
+
+// The loads and stores are unaligned and stay inside `blocks`, whose length is checked by the
+// callers.
+#[target_feature(enable = "aes,sse2")]
+unsafe fn encrypt_blocks_aesni<const N: usize>(blocks: &mut [u8], subkeys: &[u32]) {
+    let rounds = subkeys.len() / 4 - 1;
+    let key = round_key(subkeys, 0);
+    let mut state = [_mm_setzero_si128(); N];
+    for (s, block) in state
+        .iter_mut()
+        .zip(blocks.chunks_exact(BLOCKSIZE_IN_BYTES))
+    {
+        *s = _mm_xor_si128(_mm_loadu_si128(block.as_ptr().cast()), key);
+    }
+    for round in 1..rounds {
+        let key = round_key(subkeys, round);
+        for s in state.iter_mut() {
+            *s = _mm_aesenc_si128(*s, key);
+        }
+    }
+    let key = round_key(subkeys, rounds);
+    for (s, block) in state
+        .iter()
+        .zip(blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES))
+    {
+        _mm_storeu_si128(block.as_mut_ptr().cast(), _mm_aesenclast_si128(*s, key));
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decrypt_blocks_aesni<const N: usize>(blocks: &mut [u8], subkeys: &[u32]) {
+    let rounds = subkeys.len() / 4 - 1;
+    let key = round_key(subkeys, rounds);
+    let mut state = [_mm_setzero_si128(); N];
+    for (s, block) in state
+        .iter_mut()
+        .zip(blocks.chunks_exact(BLOCKSIZE_IN_BYTES))
+    {
+        *s = _mm_xor_si128(_mm_loadu_si128(block.as_ptr().cast()), key);
+    }
+    for round in (1..rounds).rev() {
+        let key = round_key(subkeys, round);
+        for s in state.iter_mut() {
+            *s = _mm_aesdec_si128(*s, key);
+        }
+    }
+    let key = round_key(subkeys, 0);
+    for (s, block) in state
+        .iter()
+        .zip(blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES))
+    {
+        _mm_storeu_si128(block.as_mut_ptr().cast(), _mm_aesdeclast_si128(*s, key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::key_schedule_encrypt_decrypt_auto;
+
+    #[test]
+    fn aesni_matches_fips197() {
+        if !available() {
+            return;
+        }
+        // These examples came from NIST.FIPS.197 Appendix C
+        let plain: [u8; BLOCKSIZE_IN_BYTES] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+            0xEE, 0xFF,
+        ];
+        let expected: [[u8; BLOCKSIZE_IN_BYTES]; 3] = [
+            [
+                0x69, 0xC4, 0xE0, 0xD8, 0x6A, 0x7B, 0x04, 0x30, 0xD8, 0xCD, 0xB7, 0x80, 0x70, 0xB4,
+                0xC5, 0x5A,
+            ],
+            [
+                0xDD, 0xA9, 0x7C, 0xA4, 0x86, 0x4C, 0xDF, 0xE0, 0x6E, 0xAF, 0x70, 0xA0, 0xEC, 0x0D,
+                0x71, 0x91,
+            ],
+            [
+                0x8E, 0xA2, 0xB7, 0xCA, 0x51, 0x67, 0x45, 0xBF, 0xEA, 0xFC, 0x49, 0x90, 0x4B, 0x49,
+                0x60, 0x89,
+            ],
+        ];
+        for (key_bytes, expected) in [16usize, 24, 32].into_iter().zip(expected) {
+            let key: Vec<u8> = (0..key_bytes as u8).collect();
+            let mut encryption = vec![0u32; key_bytes + 28];
+            let mut decryption = vec![0u32; key_bytes + 28];
+            key_schedule_encrypt_decrypt_auto(&key, &mut encryption, &mut decryption);
+            let mut block = plain;
+            encrypt_block(&mut block, &encryption);
+            assert_eq!(block, expected);
+            decrypt_block(&mut block, &decryption);
+            assert_eq!(block, plain);
+            let mut blocks = plain.repeat(4);
+            encrypt_blocks::<4>(&mut blocks, &encryption);
+            assert_eq!(blocks, expected.repeat(4));
+            decrypt_blocks::<4>(&mut blocks, &decryption);
+            assert_eq!(blocks, plain.repeat(4));
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! # alphabet
+//! `alphabet` maps strings to the numeral strings of format-preserving encryption and back.
+//!
+//! Format-preserving encryption (such as FF1 and FF3-1 of NIST SP 800-38G) works on numerals in
+//! a radix: a string of digits is a numeral string in radix 10, a hex string one in radix 16.
+//! An [`Alphabet`] is the ordered set of characters of a format, whose position is the value of
+//! the numeral. Converting a string and back always gives the same string, and characters which
+//! are not in the alphabet are errors rather than being dropped.
+//!
+//! [`Alphabet`]: ../alphabet/struct.Alphabet.html
+use super::error::AesError;
+use std::collections::HashMap;
+
+/// The largest radix of SP 800-38G, which is 2^16.
+pub const MAX_RADIX: usize = 1 << 16;
+
+/// An ordered set of characters, the numeral `i` being the `i`-th character.
+/// # Examples
+/// ```
+/// use aes_frast::alphabet::Alphabet;
+///
+/// let digits = Alphabet::digits();
+/// assert_eq!(digits.radix(), 10);
+/// let numerals = digits.to_numerals("0123").unwrap();
+/// assert_eq!(numerals, [0, 1, 2, 3]);
+/// assert_eq!(digits.to_string(&numerals).unwrap(), "0123");
+/// assert!(digits.to_numerals("12a").is_err());
+///
+/// let custom = Alphabet::new("ACGT").unwrap();
+/// assert_eq!(custom.to_numerals("GATTACA").unwrap(), [2, 0, 3, 3, 0, 1, 0]);
+/// assert!(Alphabet::new("ABA").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Alphabet {
+    characters: Vec<char>,
+    numerals: HashMap<char, u16>,
+}
+
+impl Alphabet {
+    /// Create an alphabet from its characters, in the order of their numeral values.
+    ///
+    /// * *parameter* `characters`: the characters, at least 2 and at most [`MAX_RADIX`] of them,
+    ///   without repeats.
+    /// * *return* : the alphabet, or `Err(AesError::InvalidParameter)`.
+    ///
+    /// [`MAX_RADIX`]: ../alphabet/constant.MAX_RADIX.html
+    pub fn new(characters: &str) -> Result<Self, AesError> {
+        let characters: Vec<char> = characters.chars().collect();
+        if characters.len() < 2 || characters.len() > MAX_RADIX {
+            return Err(AesError::InvalidParameter);
+        }
+        let mut numerals = HashMap::with_capacity(characters.len());
+        for (numeral, &character) in characters.iter().enumerate() {
+            if numerals.insert(character, numeral as u16).is_some() {
+                return Err(AesError::InvalidParameter);
+            }
+        }
+        Ok(Self {
+            characters,
+            numerals,
+        })
+    }
+
+    /// The decimal digits `0-9`.
+    pub fn digits() -> Self {
+        Self::new("0123456789").unwrap()
+    }
+
+    /// The lowercase hexadecimal digits `0-9a-f`.
+    pub fn hex_lower() -> Self {
+        Self::new("0123456789abcdef").unwrap()
+    }
+
+    /// The uppercase hexadecimal digits `0-9A-F`.
+    pub fn hex_upper() -> Self {
+        Self::new("0123456789ABCDEF").unwrap()
+    }
+
+    /// The digits and lowercase letters `0-9a-z` of base 36.
+    pub fn base36() -> Self {
+        Self::new("0123456789abcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+
+    /// The digits and letters `0-9A-Za-z` of base 62.
+    pub fn base62() -> Self {
+        Self::new("0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+
+    /// The radix, which is the number of characters.
+    pub fn radix(&self) -> usize {
+        self.characters.len()
+    }
+
+    /// Whether every character of `s` is in the alphabet.
+    pub fn contains_all(&self, s: &str) -> bool {
+        s.chars().all(|c| self.numerals.contains_key(&c))
+    }
+
+    /// Convert a string to its numeral string.
+    ///
+    /// * *parameter* `s`: the string.
+    /// * *return* : the numerals, one per character, or `Err(AesError::InvalidParameter)` if a
+    ///   character is not in the alphabet.
+    pub fn to_numerals(&self, s: &str) -> Result<Vec<u16>, AesError> {
+        s.chars()
+            .map(|c| self.numerals.get(&c).copied())
+            .collect::<Option<Vec<u16>>>()
+            .ok_or(AesError::InvalidParameter)
+    }
+
+    /// Convert a numeral string to its string.
+    ///
+    /// * *parameter* `numerals`: the numerals.
+    /// * *return* : the string, or `Err(AesError::InvalidParameter)` if a numeral is not less
+    ///   than the radix.
+    pub fn to_string(&self, numerals: &[u16]) -> Result<String, AesError> {
+        numerals
+            .iter()
+            .map(|&n| self.characters.get(n as usize).copied())
+            .collect::<Option<String>>()
+            .ok_or(AesError::InvalidParameter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let alphabets = [
+            Alphabet::digits(),
+            Alphabet::hex_lower(),
+            Alphabet::hex_upper(),
+            Alphabet::base36(),
+            Alphabet::base62(),
+            Alphabet::new("αβγδ€").unwrap(),
+        ];
+        let radixes = [10, 16, 16, 36, 62, 5];
+        for (alphabet, radix) in alphabets.iter().zip(radixes) {
+            assert_eq!(alphabet.radix(), radix);
+            let numerals: Vec<u16> = (0..(radix as u16)).rev().collect();
+            let s = alphabet.to_string(&numerals).unwrap();
+            assert_eq!(s.chars().count(), radix);
+            assert!(alphabet.contains_all(&s));
+            assert_eq!(alphabet.to_numerals(&s).unwrap(), numerals);
+            assert_eq!(
+                alphabet.to_string(&[radix as u16]),
+                Err(AesError::InvalidParameter)
+            );
+        }
+        assert_eq!(Alphabet::hex_lower().to_numerals("ff").unwrap(), [15, 15]);
+        assert!(Alphabet::hex_lower().to_numerals("FF").is_err());
+    }
+
+    #[test]
+    fn invalid_alphabets() {
+        assert!(Alphabet::new("").is_err());
+        assert!(Alphabet::new("0").is_err());
+        assert!(Alphabet::new("0120").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! # block
+//! `block` provides the [`Block`] type, a 128-bit block with the little operations the
+//! operation modes and MACs need, such as XOR and doubling in GF(2^128).
+//!
+//! [`Block`]: ../block/struct.Block.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use std::ops::{BitXor, BitXorAssign};
+
+/// A 128-bit (16 bytes) block.
+/// # Examples
+/// ```
+/// use aes_frast::block::Block;
+///
+/// let a = Block::from([0x0Fu8; 16]);
+/// let b = Block::from([0xF0u8; 16]);
+/// assert_eq!(a ^ b, Block::from([0xFFu8; 16]));
+///
+/// let mut c = a;
+/// c ^= a;
+/// assert_eq!(c, Block::default());
+/// assert_eq!(c.as_ref(), &[0u8; 16]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Block(pub [u8; BLOCKSIZE_IN_BYTES]);
+
+impl Block {
+    /// Create a block from a slice.
+    /// # Panics
+    /// This function panics if the length of `bytes` is not 16.
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), BLOCKSIZE_IN_BYTES);
+        let mut block = Self::default();
+        block.0.copy_from_slice(bytes);
+        block
+    }
+
+    /// Double the block in GF(2^128), with the **big-endian** convention.
+    ///
+    /// The block is read as a big-endian integer, shifted left by one bit, and reduced by the
+    /// polynomial `x^128 + x^7 + x^2 + x + 1`. This is the doubling of CMAC (NIST SP 800-38B)
+    /// and OCB.
+    /// # Examples
+    /// ```
+    /// use aes_frast::block::Block;
+    ///
+    /// let mut block = [0u8; 16];
+    /// block[0] = 0x80;
+    /// let mut expected = [0u8; 16];
+    /// expected[15] = 0x87;
+    /// assert_eq!(Block::from(block).dbl(), Block::from(expected));
+    /// ```
+    pub fn dbl(&self) -> Self {
+        let value = u128::from_be_bytes(self.0);
+        let reduction = (value >> 127) as u8 * 0x87;
+        Self(((value << 1) ^ reduction as u128).to_be_bytes())
+    }
+
+    /// Double the block in GF(2^128), with the **little-endian** convention.
+    ///
+    /// The block is read as a little-endian integer, shifted left by one bit, and reduced by the
+    /// polynomial `x^128 + x^7 + x^2 + x + 1`. This is the multiplication by `α` of the
+    /// XEX/XTS tweaks (IEEE 1619).
+    /// # Examples
+    /// ```
+    /// use aes_frast::block::Block;
+    ///
+    /// let mut block = [0u8; 16];
+    /// block[15] = 0x80;
+    /// let mut expected = [0u8; 16];
+    /// expected[0] = 0x87;
+    /// assert_eq!(Block::from(block).dbl_le(), Block::from(expected));
+    /// ```
+    pub fn dbl_le(&self) -> Self {
+        let value = u128::from_le_bytes(self.0);
+        let reduction = (value >> 127) as u8 * 0x87;
+        Self(((value << 1) ^ reduction as u128).to_le_bytes())
+    }
+}
+
+impl From<[u8; BLOCKSIZE_IN_BYTES]> for Block {
+    fn from(bytes: [u8; BLOCKSIZE_IN_BYTES]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Block> for [u8; BLOCKSIZE_IN_BYTES] {
+    fn from(block: Block) -> Self {
+        block.0
+    }
+}
+
+impl AsRef<[u8]> for Block {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsMut<[u8]> for Block {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl BitXor for Block {
+    type Output = Self;
+    fn bitxor(mut self, rhs: Self) -> Self {
+        self ^= rhs;
+        self
+    }
+}
+
+impl BitXorAssign for Block {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        for (x, y) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *x ^= y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Aes128;
+
+    #[test]
+    fn dbl_works() {
+        // The sub-keys of CMAC, which came from RFC 4493 section 4
+        let key: [u8; 16] = [
+            0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF,
+            0x4F, 0x3C,
+        ];
+        let mut l = [0u8; 16];
+        Aes128::new(&key).encrypt_block(&mut l);
+        let k1 = Block(l).dbl();
+        let k2 = k1.dbl();
+        let expected_k1: [u8; 16] = [
+            0xFB, 0xEE, 0xD6, 0x18, 0x35, 0x71, 0x33, 0x66, 0x7C, 0x85, 0xE0, 0x8F, 0x72, 0x36,
+            0xA8, 0xDE,
+        ];
+        let expected_k2: [u8; 16] = [
+            0xF7, 0xDD, 0xAC, 0x30, 0x6A, 0xE2, 0x66, 0xCC, 0xF9, 0x0B, 0xC1, 0x1E, 0xE4, 0x6D,
+            0x51, 0x3B,
+        ];
+        assert_eq!(k1, Block(expected_k1));
+        assert_eq!(k2, Block(expected_k2));
+    }
+
+    #[test]
+    fn dbl_le_works() {
+        let mut expected = [0xFFu8; 16];
+        expected[0] = 0xFE ^ 0x87;
+        assert_eq!(Block([0xFFu8; 16]).dbl_le(), Block(expected));
+        let mut block = [0u8; 16];
+        block[7] = 0x80;
+        let mut expected = [0u8; 16];
+        expected[8] = 0x01;
+        assert_eq!(Block(block).dbl_le(), Block(expected));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_slice_wrong_length_panic() {
+        Block::from_slice(&[0u8; 15]);
+    }
+}
@@ -0,0 +1,205 @@
+//! # block_cipher
+//! `block_cipher` provides the [`BlockCipher128`] trait, the interface between a 128-bit block
+//! cipher and the operation modes.
+//!
+//! The functions whose names end with `_with` in `aes_with_operation_mode` are generic over this
+//! trait, so the modes of this library also work with other 128-bit block ciphers, like Camellia
+//! or SM4, once they implement it. The padding functions in `padding_128bit` work on bytes only
+//! and need no cipher at all.
+//!
+//! [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+
+/// A block cipher with 128-bit blocks.
+///
+/// The cipher objects [`Aes128`], [`Aes192`] and [`Aes256`] implement this trait.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode::{cbc_dec_with, cbc_enc_with};
+/// use aes_frast::block_cipher::BlockCipher128;
+///
+/// // A toy cipher which only shows how to plug a cipher in. It is NOT secure at all.
+/// struct Rotate;
+///
+/// impl BlockCipher128 for Rotate {
+///     fn encrypt_block(&self, block: &mut [u8; 16]) {
+///         block.rotate_left(3);
+///     }
+///     fn decrypt_block(&self, block: &mut [u8; 16]) {
+///         block.rotate_right(3);
+///     }
+/// }
+///
+/// let plain: Vec<u8> = (0..32).collect();
+/// let iv = [0xA5u8; 16];
+/// let mut cipher = vec![0u8; 32];
+/// let mut decrypted = vec![0u8; 32];
+/// cbc_enc_with(&Rotate, &plain, &mut cipher, &iv);
+/// cbc_dec_with(&Rotate, &cipher, &mut decrypted, &iv);
+/// assert_ne!(cipher, plain);
+/// assert_eq!(decrypted, plain);
+/// ```
+///
+/// [`Aes128`]: ../aes_cipher/struct.Aes128.html
+/// [`Aes192`]: ../aes_cipher/struct.Aes192.html
+/// [`Aes256`]: ../aes_cipher/struct.Aes256.html
+pub trait BlockCipher128 {
+    /// **Encrypt** a block in place.
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]);
+
+    /// **Decrypt** a block in place.
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]);
+
+    /// **Encrypt** consecutive blocks in place, each one independently (like ECB mode).
+    ///
+    /// The parallelizable operation modes hand their blocks over in batches through this
+    /// method. The default one calls [`encrypt_block`] for each block; ciphers which process
+    /// several blocks at once faster override it.
+    /// # Panics
+    /// This function panics if the length of `blocks` is not a multiple of 16.
+    ///
+    /// [`encrypt_block`]: ../block_cipher/trait.BlockCipher128.html#tymethod.encrypt_block
+    fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        assert_eq!(blocks.len() & 0b1111, 0, "Incomplete block.");
+        for block in blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+            self.encrypt_block(block.try_into().unwrap());
+        }
+    }
+
+    /// **Decrypt** consecutive blocks in place, each one independently (like ECB mode).
+    ///
+    /// See [`encrypt_blocks`].
+    /// # Panics
+    /// This function panics if the length of `blocks` is not a multiple of 16.
+    ///
+    /// [`encrypt_blocks`]: ../block_cipher/trait.BlockCipher128.html#method.encrypt_blocks
+    fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        assert_eq!(blocks.len() & 0b1111, 0, "Incomplete block.");
+        for block in blocks.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+            self.decrypt_block(block.try_into().unwrap());
+        }
+    }
+}
+
+impl<C: BlockCipher128 + ?Sized> BlockCipher128 for &C {
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        (**self).encrypt_block(block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        (**self).decrypt_block(block);
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [u8]) {
+        (**self).encrypt_blocks(blocks);
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [u8]) {
+        (**self).decrypt_blocks(blocks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_cipher::{Aes128, Aes192, Aes256};
+    use crate::aes_with_operation_mode::*;
+
+    type KeyedMode = fn(&[u8], &mut [u8], &[u32], &[u8]) -> Vec<u8>;
+    type GenericMode = fn(&(dyn BlockCipher128 + 'static), &[u8], &mut [u8], &[u8]) -> Vec<u8>;
+
+    fn check_modes(
+        cipher: &(dyn BlockCipher128 + 'static),
+        encryption_keys: &[u32],
+        decryption_keys: &[u32],
+    ) {
+        let modes: [(KeyedMode, GenericMode, bool); 12] = [
+            (cbc_enc, cbc_enc_with, true),
+            (cbc_dec, cbc_dec_with, false),
+            (cfb_enc, cfb_enc_with, true),
+            (cfb_dec, cfb_dec_with, true),
+            (ofb_enc_dec, ofb_enc_dec_with, true),
+            (ctr_enc_dec, ctr_enc_dec_with, true),
+            (pcbc_enc, pcbc_enc_with, true),
+            (pcbc_dec, pcbc_dec_with, false),
+            (cfb_8_enc, cfb_8_enc_with, true),
+            (cfb_8_dec, cfb_8_dec_with, true),
+            (
+                |i, o, k, _| ecb_enc(i, o, k),
+                |c, i, o, _| ecb_enc_with(c, i, o),
+                true,
+            ),
+            (
+                |i, o, k, _| ecb_dec(i, o, k),
+                |c, i, o, _| ecb_dec_with(c, i, o),
+                false,
+            ),
+        ];
+        let input: Vec<u8> = (0..48).map(|i| (i * 7) as u8).collect();
+        let iv: [u8; BLOCKSIZE_IN_BYTES] = core::array::from_fn(|i| (i * 13) as u8);
+        for (keyed, generic, encrypting) in modes {
+            let keys = if encrypting {
+                encryption_keys
+            } else {
+                decryption_keys
+            };
+            let mut expected = vec![0u8; 48];
+            let mut output = vec![0u8; 48];
+            let expected_last = keyed(&input, &mut expected, keys, &iv);
+            let last = generic(cipher, &input, &mut output, &iv);
+            assert_eq!(output, expected);
+            assert_eq!(last, expected_last);
+        }
+    }
+
+    #[test]
+    fn generic_modes_match_keyed_modes() {
+        let aes = Aes128::new(&[0x2Bu8; 16]);
+        check_modes(&aes, aes.encryption_subkeys(), aes.decryption_subkeys());
+        let aes = Aes192::new(&[0x7Eu8; 24]);
+        check_modes(&aes, aes.encryption_subkeys(), aes.decryption_subkeys());
+        let aes = Aes256::new(&[0x15u8; 32]);
+        check_modes(&aes, aes.encryption_subkeys(), aes.decryption_subkeys());
+    }
+
+    // Only the required methods, so the modes see the default, block-by-block batches.
+    struct BlockByBlock(Aes128);
+
+    impl BlockCipher128 for BlockByBlock {
+        fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+            self.0.encrypt_block(block);
+        }
+        fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+            self.0.decrypt_block(block);
+        }
+    }
+
+    #[test]
+    fn batched_modes_match_block_by_block() {
+        let aes = Aes128::new(&[0x3Cu8; 16]);
+        let block_by_block = BlockByBlock(Aes128::new(&[0x3Cu8; 16]));
+        let iv = [0xF0u8; BLOCKSIZE_IN_BYTES];
+        // 13 blocks and a partial one, more than a batch of 8.
+        let input: Vec<u8> = (0..215).map(|i| (i * 11) as u8).collect();
+        let whole = &input[..208];
+        let modes: [GenericMode; 4] = [
+            |c, i, o, _| ecb_enc_with(c, i, o),
+            |c, i, o, _| ecb_dec_with(c, i, o),
+            cbc_dec_with,
+            ctr_enc_dec_with,
+        ];
+        for mode in modes {
+            let mut expected = vec![0u8; 208];
+            let mut output = vec![0u8; 208];
+            let expected_last = mode(&block_by_block, whole, &mut expected, &iv);
+            let last = mode(&aes, whole, &mut output, &iv);
+            assert_eq!(output, expected);
+            assert_eq!(last, expected_last);
+        }
+        let mut expected = vec![0u8; 215];
+        let mut output = vec![0u8; 215];
+        ctr_enc_dec_with(&block_by_block, &input, &mut expected, &iv);
+        ctr_enc_dec_with(&aes, &input, &mut output, &iv);
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,170 @@
+//! # cbc_mac
+//! `cbc_mac` provides CBC-MAC, the last block of the CBC encryption of a message with a zero IV.
+//!
+//! **Attention!** The raw CBC-MAC ([`raw_cbc_mac`]) is only secure for messages of one fixed
+//! length. Over messages of different lengths it is forgeable: from the MAC `T` of a one-block
+//! message `M`, anyone gets the MAC of `M || (M ^ T)` for free, which is `T` again. Use it only
+//! where a protocol mandates it, and never with a non-zero or random IV.
+//!
+//! [`CbcMac`] fixes this by prepending the length of the message, so that no message is a prefix
+//! of another of a different length. For new designs, prefer CMAC (see the [`aes_cmac`] mod),
+//! which is standard and needs no length in advance.
+//!
+//! [`raw_cbc_mac`]: ../cbc_mac/fn.raw_cbc_mac.html
+//! [`CbcMac`]: ../cbc_mac/struct.CbcMac.html
+//! [`aes_cmac`]: ../aes_cmac/index.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::tag::{verify_tag, Tag};
+
+/// Compute the raw CBC-MAC of `message`, **for messages of one fixed length only**.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `message`: the message, a non-zero multiple of 16 bytes; pad it as the protocol
+///   says.
+/// * *return* : the 16-byte MAC, or `Err(AesError::InvalidDataLength)`.
+/// # Examples
+/// ```
+/// use aes_frast::cbc_mac::raw_cbc_mac;
+/// use aes_frast::Aes128;
+///
+/// let cipher = Aes128::new(&[0x0Fu8; 16]);
+/// let message = [0x31u8; 16];
+/// let mac = raw_cbc_mac(&cipher, &message).unwrap();
+///
+/// // The forgery of variable lengths: `M || (M ^ T)` has the same MAC as `M`.
+/// let mut forged = message.to_vec();
+/// forged.extend(message.iter().zip(mac.as_bytes()).map(|(m, t)| m ^ t));
+/// assert_eq!(raw_cbc_mac(&cipher, &forged).unwrap(), mac);
+/// ```
+pub fn raw_cbc_mac<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    message: &[u8],
+) -> Result<Tag, AesError> {
+    if message.is_empty() || message.len() & 0b1111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(Tag::from(chain(cipher, Block::default(), message).0))
+}
+
+/// CBC-MAC over a [`BlockCipher128`], with the length of the message prepended, so that it is
+/// secure for messages of any length.
+///
+/// The MAC is the raw CBC-MAC of a first block holding the length of the message in bytes (a
+/// 128-bit big-endian integer), followed by the message padded with zeros to a multiple of 16
+/// bytes. The length must be known before the message is processed.
+/// # Examples
+/// ```
+/// use aes_frast::cbc_mac::CbcMac;
+/// use aes_frast::Aes128;
+///
+/// let cbc_mac = CbcMac::new(Aes128::new(&[0x0Fu8; 16]));
+/// let message = [0x31u8; 16];
+/// let mac = cbc_mac.mac(&message);
+/// assert_eq!(cbc_mac.verify(&message, mac.as_bytes()), Ok(()));
+///
+/// // The forgery of the raw CBC-MAC doesn't work any more.
+/// let mut forged = message.to_vec();
+/// forged.extend(message.iter().zip(mac.as_bytes()).map(|(m, t)| m ^ t));
+/// assert!(cbc_mac.verify(&forged, mac.as_bytes()).is_err());
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct CbcMac<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher128> CbcMac<C> {
+    /// Create the MAC.
+    pub fn new(cipher: C) -> Self {
+        Self { cipher }
+    }
+
+    /// The block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Compute the 16-byte MAC of `message`.
+    pub fn mac(&self, message: &[u8]) -> Tag {
+        let mut mac = Block::from((message.len() as u128).to_be_bytes());
+        self.cipher.encrypt_block(&mut mac.0);
+        let full = message.len() & !0b1111;
+        mac = chain(&self.cipher, mac, &message[..full]);
+        if full < message.len() {
+            let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+            block[..(message.len() - full)].copy_from_slice(&message[full..]);
+            mac = chain(&self.cipher, mac, &block);
+        }
+        Tag::from(mac.0)
+    }
+
+    /// Verify the received 16-byte MAC of `message` in constant time.
+    ///
+    /// * *return* : `Ok(())` if it is right, otherwise `Err(AesError::TagMismatch)`.
+    pub fn verify(&self, message: &[u8], tag: &[u8]) -> Result<(), AesError> {
+        verify_tag(tag, self.mac(message).as_bytes())
+    }
+}
+
+/// Continue the CBC chain from `mac` over the complete blocks of `blocks`.
+fn chain<C: BlockCipher128 + ?Sized>(cipher: &C, mut mac: Block, blocks: &[u8]) -> Block {
+    for chunk in blocks.chunks_exact(BLOCKSIZE_IN_BYTES) {
+        mac ^= Block::from_slice(chunk);
+        cipher.encrypt_block(&mut mac.0);
+    }
+    mac
+}
+
+impl_redacted_debug!(CbcMac<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::key_schedule_encrypt_auto;
+    use crate::aes_with_operation_mode::cbc_enc;
+    use crate::AesAny;
+
+    #[test]
+    fn raw_is_the_last_cbc_block() {
+        let key = [0x24u8; 24];
+        let mut keys = vec![0u32; 52];
+        key_schedule_encrypt_auto(&key, &mut keys);
+        let cipher = AesAny::new(&key).unwrap();
+        let message: Vec<u8> = (0..80).collect();
+        let mut encrypted = vec![0u8; 80];
+        cbc_enc(&message, &mut encrypted, &keys, &[0u8; 16]);
+        assert_eq!(
+            raw_cbc_mac(&cipher, &message).unwrap().as_bytes(),
+            &encrypted[64..]
+        );
+        for len in [0, 15, 17] {
+            assert_eq!(
+                raw_cbc_mac(&cipher, &message[..len]).err(),
+                Some(AesError::InvalidDataLength)
+            );
+        }
+    }
+
+    #[test]
+    fn length_is_prepended() {
+        let cipher = AesAny::new(&[0x42u8; 16]).unwrap();
+        let cbc_mac = CbcMac::new(cipher.clone());
+        let message: Vec<u8> = (0..37).collect();
+        let mut prepended = (37u128).to_be_bytes().to_vec();
+        prepended.extend_from_slice(&message);
+        prepended.resize(64, 0);
+        assert_eq!(
+            cbc_mac.mac(&message),
+            raw_cbc_mac(&cipher, &prepended).unwrap()
+        );
+        // The zero padding is not ambiguous, thanks to the length.
+        let mut padded = message.clone();
+        padded.push(0);
+        assert_ne!(cbc_mac.mac(&message), cbc_mac.mac(&padded));
+        assert_ne!(cbc_mac.mac(&[]), cbc_mac.mac(&[0u8; 16]));
+    }
+}
@@ -0,0 +1,879 @@
+//! # ccm
+//! `ccm` provides CCM (Counter with CBC-MAC) authenticated encryption, as defined in
+//! NIST SP 800-38C and RFC 3610.
+//!
+//! CCM authenticates the associated data and the plain with CBC-MAC, then encrypts the plain and
+//! the MAC with CTR mode. Both passes need the lengths up front, so the whole message is
+//! processed at once. The nonce is 7 to 13 bytes long: a shorter nonce leaves more bytes for the
+//! counter, and so allows longer messages (up to `2^(8 * (15 - nonce length))` bytes).
+//!
+//! For a stream whose length is unknown when encryption starts, [`Ccm::seal_buffered`] collects
+//! it in a `Vec` first, and [`Ccm::seal_spilled`] in a caller-provided `Write + Seek` spill, such
+//! as a temporary file. Both write nothing until the input has ended: the buffer costs memory
+//! as large as the message, the spill costs a second read of the whole message.
+//!
+//! **Attention!** A nonce must never be used twice with the same key. See the [`nonce`] mod for
+//! deriving per-record nonces.
+//!
+//! [`nonce`]: ../nonce/index.html
+//! Associated data assembled field by field can be given to [`Ccm::begin`] piece by piece. The
+//! length of the associated data is encoded before it, so the pieces are still collected before
+//! the MAC starts.
+//!
+//! [`Ccm::begin`]: ../ccm/struct.Ccm.html#method.begin
+//! [`Ccm::seal_buffered`]: ../ccm/struct.Ccm.html#method.seal_buffered
+//! [`Ccm::seal_spilled`]: ../ccm/struct.Ccm.html#method.seal_spilled
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode_io::{io_error, read_full, CHUNK_SIZE_IN_BYTES};
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::tag::{verify_tag, Tag, TagPlacement};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// CCM mode over a [`BlockCipher128`], with a fixed nonce length and tag length.
+/// # Examples
+/// ```
+/// use aes_frast::ccm::Ccm;
+/// use aes_frast::Aes128;
+///
+/// // An IoT-style configuration: a 13-byte nonce and an 8-byte tag.
+/// let ccm = Ccm::new(Aes128::new(&[0x2Bu8; 16]), 13, 8).unwrap();
+/// let nonce = [0x01u8; 13];
+/// let header = b"frame header";
+///
+/// let sealed = ccm.seal(&nonce, header, b"temperature=21.5").unwrap();
+/// assert_eq!(sealed.len(), 16 + 8);
+/// assert_eq!(ccm.open(&nonce, header, &sealed).unwrap(), b"temperature=21.5");
+///
+/// // Any change of the header, the cipher or the tag is detected.
+/// assert!(ccm.open(&nonce, b"frame HEADER", &sealed).is_err());
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Ccm<C> {
+    cipher: C,
+    nonce_len: usize,
+    tag_len: usize,
+}
+
+impl<C: BlockCipher128> Ccm<C> {
+    /// Create the mode.
+    ///
+    /// * *parameter* `cipher`: the block cipher.
+    /// * *parameter* `nonce_len`: the nonce length in bytes, from 7 to 13.
+    /// * *parameter* `tag_len`: the tag length in bytes, 4, 6, 8, 10, 12, 14 or 16.
+    /// * *return* : the mode, or `Err(AesError::InvalidParameter)` for other lengths.
+    pub fn new(cipher: C, nonce_len: usize, tag_len: usize) -> Result<Self, AesError> {
+        if !(7..=13).contains(&nonce_len) || !(4..=16).contains(&tag_len) || tag_len & 1 != 0 {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(Self {
+            cipher,
+            nonce_len,
+            tag_len,
+        })
+    }
+
+    /// The block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The nonce length in bytes.
+    pub fn nonce_len(&self) -> usize {
+        self.nonce_len
+    }
+
+    /// The tag length in bytes.
+    pub fn tag_len(&self) -> usize {
+        self.tag_len
+    }
+
+    /// **Encrypt** `data` in place, returning the tag separately.
+    ///
+    /// * *parameter* `nonce`: the nonce, whose length must be the nonce length of the mode.
+    /// * *parameter* `aad`: the associated data, which is authenticated but not encrypted.
+    /// * *parameter* `data`: the plain, which becomes the cipher.
+    /// * *return* : the tag, or `Err(AesError::InvalidParameter)` for a wrong nonce length, or
+    ///   `Err(AesError::InvalidDataLength)` if `data` is too long for the nonce length. `data` is
+    ///   left untouched on errors.
+    pub fn encrypt(&self, nonce: &[u8], aad: &[u8], data: &mut [u8]) -> Result<Tag, AesError> {
+        self.check(nonce, data.len() as u64)?;
+        let mac = self.cbc_mac(nonce, aad, data);
+        let s0 = self.ctr(nonce, data);
+        Ok(Tag::new(&(mac ^ s0).0[..self.tag_len]))
+    }
+
+    /// **Decrypt** `data` in place and verify the tag.
+    ///
+    /// * *parameter* `nonce`: the nonce of the encryption.
+    /// * *parameter* `aad`: the associated data of the encryption.
+    /// * *parameter* `data`: the cipher, which becomes the plain.
+    /// * *parameter* `tag`: the received tag.
+    /// * *return* : `Ok(())` if the tag is right, otherwise `Err(AesError::TagMismatch)`, and
+    ///   `data` is filled with zeros, so an unauthenticated plain is never exposed. Wrong lengths
+    ///   give the errors of [`encrypt`].
+    ///
+    /// [`encrypt`]: ../ccm/struct.Ccm.html#method.encrypt
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AesError> {
+        self.check(nonce, data.len() as u64)?;
+        if tag.len() != self.tag_len {
+            return Err(AesError::TagMismatch);
+        }
+        let result = self.decrypt_and_verify(nonce, aad, data, tag);
+        if result.is_err() {
+            data.fill(0);
+        }
+        result
+    }
+
+    /// **Decrypt** `data` in place and verify the tag, but keep the plain even if the tag is
+    /// wrong.
+    ///
+    /// **Attention!** The plain of a wrong tag is unauthenticated: it may be forged or
+    /// corrupted, and must never be used as data. This function is for forensic tooling which
+    /// inspects damaged messages, and is only compiled with the `forensic-open` feature. Use
+    /// [`decrypt`] for anything else.
+    ///
+    /// * *parameter* `nonce`: the nonce of the encryption.
+    /// * *parameter* `aad`: the associated data of the encryption.
+    /// * *parameter* `data`: the cipher, which becomes the (possibly unauthenticated) plain.
+    /// * *parameter* `tag`: the received tag, of any length.
+    /// * *return* : `Ok(())` if the tag is right, otherwise `Err(AesError::TagMismatch)`, with
+    ///   `data` decrypted all the same. Wrong lengths of the nonce or `data` give the errors of
+    ///   [`encrypt`], and leave `data` untouched.
+    /// # Examples
+    /// ```
+    /// use aes_frast::ccm::Ccm;
+    /// use aes_frast::{Aes128, AesError};
+    ///
+    /// let ccm = Ccm::new(Aes128::new(&[0x2Bu8; 16]), 13, 8).unwrap();
+    /// let nonce = [0x01u8; 13];
+    /// let mut data = *b"temperature=21.5";
+    /// let tag = ccm.encrypt(&nonce, b"", &mut data).unwrap();
+    ///
+    /// // A damaged tag: `decrypt` wipes the data, this function keeps it for inspection.
+    /// let mut damaged = tag.as_bytes().to_vec();
+    /// damaged[0] ^= 1;
+    /// let mut wiped = data;
+    /// assert_eq!(ccm.decrypt(&nonce, b"", &mut wiped, &damaged), Err(AesError::TagMismatch));
+    /// assert_eq!(wiped, [0u8; 16]);
+    /// assert_eq!(
+    ///     ccm.decrypt_unverified_for_forensics(&nonce, b"", &mut data, &damaged),
+    ///     Err(AesError::TagMismatch)
+    /// );
+    /// assert_eq!(&data, b"temperature=21.5");
+    /// ```
+    ///
+    /// [`decrypt`]: ../ccm/struct.Ccm.html#method.decrypt
+    /// [`encrypt`]: ../ccm/struct.Ccm.html#method.encrypt
+    #[cfg(feature = "forensic-open")]
+    pub fn decrypt_unverified_for_forensics(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AesError> {
+        self.check(nonce, data.len() as u64)?;
+        self.decrypt_and_verify(nonce, aad, data, tag)
+    }
+
+    /// **Encrypt** `plain`, returning the cipher followed by the tag.
+    pub fn seal(&self, nonce: &[u8], aad: &[u8], plain: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.seal_with_layout(nonce, aad, plain, TagPlacement::Appended)
+    }
+
+    /// **Decrypt** `sealed`, the cipher followed by the tag, returning the plain only if the tag
+    /// is right.
+    pub fn open(&self, nonce: &[u8], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.open_with_layout(nonce, aad, sealed, TagPlacement::Appended)
+    }
+
+    /// **Encrypt** `plain`, returning the cipher and the tag in the layout of `placement`.
+    ///
+    /// The errors are those of [`encrypt`].
+    /// # Examples
+    /// ```
+    /// use aes_frast::ccm::Ccm;
+    /// use aes_frast::tag::TagPlacement;
+    /// use aes_frast::Aes128;
+    ///
+    /// let ccm = Ccm::new(Aes128::new(&[0x2Bu8; 16]), 12, 16).unwrap();
+    /// let nonce = [0x01u8; 12];
+    ///
+    /// // A legacy format which stores the tag before the cipher.
+    /// let sealed = ccm.seal_with_layout(&nonce, b"", b"record", TagPlacement::Prepended).unwrap();
+    /// let appended = ccm.seal(&nonce, b"", b"record").unwrap();
+    /// assert_eq!(sealed[..16], appended[6..]);
+    /// assert_eq!(sealed[16..], appended[..6]);
+    /// assert_eq!(
+    ///     ccm.open_with_layout(&nonce, b"", &sealed, TagPlacement::Prepended).unwrap(),
+    ///     b"record"
+    /// );
+    /// ```
+    ///
+    /// [`encrypt`]: ../ccm/struct.Ccm.html#method.encrypt
+    pub fn seal_with_layout(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        plain: &[u8],
+        placement: TagPlacement,
+    ) -> Result<Vec<u8>, AesError> {
+        let cipher_start = match placement {
+            TagPlacement::Appended => 0,
+            TagPlacement::Prepended => self.tag_len,
+        };
+        let mut sealed = vec![0u8; cipher_start];
+        sealed.extend_from_slice(plain);
+        let tag = self.encrypt(nonce, aad, &mut sealed[cipher_start..])?;
+        match placement {
+            TagPlacement::Appended => sealed.extend_from_slice(tag.as_bytes()),
+            TagPlacement::Prepended => sealed[..cipher_start].copy_from_slice(tag.as_bytes()),
+        }
+        Ok(sealed)
+    }
+
+    /// **Decrypt** `sealed`, the cipher and the tag in the layout of `placement`, returning the
+    /// plain only if the tag is right.
+    ///
+    /// * *return* : the plain, or `Err(AesError::InvalidDataLength)` if `sealed` is shorter than
+    ///   the tag, or the errors of [`decrypt`].
+    ///
+    /// [`decrypt`]: ../ccm/struct.Ccm.html#method.decrypt
+    pub fn open_with_layout(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        sealed: &[u8],
+        placement: TagPlacement,
+    ) -> Result<Vec<u8>, AesError> {
+        let (cipher, tag) = placement.split(sealed, self.tag_len)?;
+        let mut plain = cipher.to_vec();
+        self.decrypt(nonce, aad, &mut plain, tag)?;
+        Ok(plain)
+    }
+
+    /// Start a message whose associated data is given in pieces, by [`CcmBuilder::update_aad`].
+    ///
+    /// * *parameter* `nonce`: the nonce of the message.
+    /// # Examples
+    /// ```
+    /// use aes_frast::ccm::Ccm;
+    /// use aes_frast::Aes128;
+    ///
+    /// let ccm = Ccm::new(Aes128::new(&[0x2Bu8; 16]), 13, 8).unwrap();
+    /// let nonce = [0x01u8; 13];
+    ///
+    /// // A protocol header, field by field.
+    /// let mut message = ccm.begin(&nonce);
+    /// message.update_aad(&[0x41, 0x88]);
+    /// message.update_aad(&0x1234u16.to_le_bytes());
+    /// message.update_aad(b"dst");
+    /// let sealed = message.seal(b"payload").unwrap();
+    ///
+    /// let header = [0x41, 0x88, 0x34, 0x12, b'd', b's', b't'];
+    /// assert_eq!(sealed, ccm.seal(&nonce, &header, b"payload").unwrap());
+    /// assert_eq!(ccm.open(&nonce, &header, &sealed).unwrap(), b"payload");
+    /// ```
+    ///
+    /// [`CcmBuilder::update_aad`]: ../ccm/struct.CcmBuilder.html#method.update_aad
+    pub fn begin<'a>(&'a self, nonce: &'a [u8]) -> CcmBuilder<'a, C> {
+        CcmBuilder {
+            ccm: self,
+            nonce,
+            aad: Vec::new(),
+        }
+    }
+
+    /// **Encrypt** everything from `reader`, whose length is not known in advance, into
+    /// `buffer`: the cipher followed by the tag.
+    ///
+    /// The first pass reads the whole plain into `buffer`, and the second one encrypts it there.
+    /// So the memory use is the length of the message, and nothing is ready before the reader
+    /// has ended. [`seal_spilled`] uses a spill such as a temporary file instead of memory.
+    ///
+    /// * *parameter* `nonce`: the nonce, whose length must be the nonce length of the mode.
+    /// * *parameter* `aad`: the associated data, which is authenticated but not encrypted.
+    /// * *parameter* `reader`: the plain.
+    /// * *parameter* `buffer`: the buffer which is cleared, then holds the sealed message. Its
+    ///   capacity can be reused for the next message.
+    /// * *return* : the error of the reader, or the errors of [`encrypt`] wrapped in an
+    ///   `io::Error`, in which case `buffer` is cleared.
+    /// # Examples
+    /// ```
+    /// use aes_frast::ccm::Ccm;
+    /// use aes_frast::Aes128;
+    ///
+    /// let ccm = Ccm::new(Aes128::new(&[0x2Bu8; 16]), 12, 16).unwrap();
+    /// let nonce = [0x01u8; 12];
+    /// let plain = vec![0x5Au8; 10_000];
+    ///
+    /// let mut sealed = Vec::new();
+    /// ccm.seal_buffered(&nonce, b"", &mut &plain[..], &mut sealed).unwrap();
+    /// assert_eq!(sealed, ccm.seal(&nonce, b"", &plain).unwrap());
+    /// ```
+    ///
+    /// [`seal_spilled`]: ../ccm/struct.Ccm.html#method.seal_spilled
+    /// [`encrypt`]: ../ccm/struct.Ccm.html#method.encrypt
+    pub fn seal_buffered<R: Read + ?Sized>(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        buffer.clear();
+        reader.read_to_end(buffer)?;
+        match self.encrypt(nonce, aad, buffer) {
+            Ok(tag) => {
+                buffer.extend_from_slice(tag.as_bytes());
+                Ok(())
+            }
+            Err(error) => {
+                buffer.fill(0);
+                buffer.clear();
+                Err(io_error(error))
+            }
+        }
+    }
+
+    /// **Encrypt** everything from `reader`, whose length is not known in advance, into
+    /// `writer`: the cipher followed by the tag. The plain is spilled to `spill`, such as a
+    /// temporary file, to learn its length.
+    ///
+    /// The first pass copies the plain into `spill`, and the second one reads it back, and
+    /// writes the cipher in chunks of [`CHUNK_SIZE_IN_BYTES`] bytes. So the memory use is one
+    /// chunk whatever the length of the message, at the cost of writing and reading the whole
+    /// plain once more, and nothing is written before the reader has ended.
+    ///
+    /// **Attention!** The spill holds the plain. Keep it where the plain may be stored, and wipe
+    /// or delete it afterwards.
+    ///
+    /// * *parameter* `nonce`: the nonce, whose length must be the nonce length of the mode.
+    /// * *parameter* `aad`: the associated data, which is authenticated but not encrypted.
+    /// * *parameter* `reader`: the plain.
+    /// * *parameter* `spill`: the spill, which is overwritten from its start.
+    /// * *parameter* `writer`: the sink of the sealed message.
+    /// * *return* : the number of bytes written, or the error of the reader, the spill or the
+    ///   writer, or the errors of [`encrypt`] wrapped in an `io::Error`. A plain which is too
+    ///   long for the nonce length is found before anything is written.
+    /// # Examples
+    /// ```
+    /// use aes_frast::ccm::Ccm;
+    /// use aes_frast::Aes128;
+    /// use std::io::Cursor;
+    ///
+    /// let ccm = Ccm::new(Aes128::new(&[0x2Bu8; 16]), 12, 16).unwrap();
+    /// let nonce = [0x01u8; 12];
+    /// let plain = vec![0x5Au8; 10_000];
+    ///
+    /// // A `File` from `tempfile()` or the like will do as well.
+    /// let mut spill = Cursor::new(Vec::new());
+    /// let mut sealed = Vec::new();
+    /// let written = ccm
+    ///     .seal_spilled(&nonce, b"", &mut &plain[..], &mut spill, &mut sealed)
+    ///     .unwrap();
+    /// assert_eq!(written, 10_016);
+    /// assert_eq!(sealed, ccm.seal(&nonce, b"", &plain).unwrap());
+    /// ```
+    ///
+    /// [`CHUNK_SIZE_IN_BYTES`]: ../aes_with_operation_mode_io/constant.CHUNK_SIZE_IN_BYTES.html
+    /// [`encrypt`]: ../ccm/struct.Ccm.html#method.encrypt
+    pub fn seal_spilled<R, S, W>(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        reader: &mut R,
+        spill: &mut S,
+        writer: &mut W,
+    ) -> io::Result<u64>
+    where
+        R: Read + ?Sized,
+        S: Read + Write + Seek + ?Sized,
+        W: Write + ?Sized,
+    {
+        spill.seek(SeekFrom::Start(0))?;
+        let len = io::copy(reader, spill)?;
+        self.check(nonce, len).map_err(io_error)?;
+
+        spill.seek(SeekFrom::Start(0))?;
+        let mut spill = spill.take(len);
+        let mut mac = self.cbc_mac_header(nonce, aad, len);
+        let mut chunk = vec![0u8; CHUNK_SIZE_IN_BYTES];
+        let mut done = 0u64;
+        while done < len {
+            let n = read_full(&mut spill, &mut chunk)?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            // Every chunk but the last one is full, so a multiple of 16 bytes long.
+            self.absorb(&mut mac, &chunk[..n]);
+            self.ctr_from(nonce, done / BLOCKSIZE_IN_BYTES as u64, &mut chunk[..n]);
+            writer.write_all(&chunk[..n])?;
+            done += n as u64;
+        }
+        chunk.fill(0);
+        std::hint::black_box(&chunk);
+        let tag = mac ^ self.s0(nonce);
+        writer.write_all(&tag.0[..self.tag_len])?;
+        Ok(len + self.tag_len as u64)
+    }
+
+    /// Decrypt `data` in place, then compare its MAC with `tag`.
+    fn decrypt_and_verify(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AesError> {
+        let s0 = self.ctr(nonce, data);
+        let mac = self.cbc_mac(nonce, aad, data);
+        verify_tag(tag, &(mac ^ s0).0[..self.tag_len])
+    }
+
+    /// The length in bytes of the counter (and of the message length), `q` in SP 800-38C.
+    fn counter_len(&self) -> usize {
+        BLOCKSIZE_IN_BYTES - 1 - self.nonce_len
+    }
+
+    /// Check the nonce length, and that a message of `len` bytes fits the counter.
+    fn check(&self, nonce: &[u8], len: u64) -> Result<(), AesError> {
+        if nonce.len() != self.nonce_len {
+            return Err(AesError::InvalidParameter);
+        }
+        let length_bits = 8 * self.counter_len() as u32;
+        if length_bits < u64::BITS && len >> length_bits != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        Ok(())
+    }
+
+    /// The block of the first byte `flags`, then the nonce, then `value` in the last `q` bytes.
+    fn format_block(&self, flags: u8, nonce: &[u8], value: u64) -> Block {
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[0] = flags;
+        block[1..=self.nonce_len].copy_from_slice(nonce);
+        let value = value.to_be_bytes();
+        let q = self.counter_len().min(value.len());
+        block[(BLOCKSIZE_IN_BYTES - q)..].copy_from_slice(&value[(value.len() - q)..]);
+        Block::from(block)
+    }
+
+    /// CBC-MAC of `B0`, the encoded associated data and the plain, each padded with zeros.
+    fn cbc_mac(&self, nonce: &[u8], aad: &[u8], plain: &[u8]) -> Block {
+        let mut mac = self.cbc_mac_header(nonce, aad, plain.len() as u64);
+        self.absorb(&mut mac, plain);
+        mac
+    }
+
+    /// CBC-MAC `bytes` into `mac`, padding them with zeros. Pieces of a message can be absorbed
+    /// one after another, if all but the last one are multiples of 16 bytes long.
+    fn absorb(&self, mac: &mut Block, bytes: &[u8]) {
+        for chunk in bytes.chunks(BLOCKSIZE_IN_BYTES) {
+            let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+            block[..chunk.len()].copy_from_slice(chunk);
+            *mac ^= Block::from(block);
+            self.cipher.encrypt_block(&mut mac.0);
+        }
+    }
+
+    /// CBC-MAC of `B0` (with the plain length `plain_len`) and the encoded associated data.
+    fn cbc_mac_header(&self, nonce: &[u8], aad: &[u8], plain_len: u64) -> Block {
+        let flags = (u8::from(!aad.is_empty()) << 6)
+            | (((self.tag_len - 2) / 2) as u8) << 3
+            | (self.counter_len() - 1) as u8;
+        let mut mac = self.format_block(flags, nonce, plain_len);
+        self.cipher.encrypt_block(&mut mac.0);
+        if !aad.is_empty() {
+            let mut encoded = Vec::with_capacity(10 + aad.len());
+            let length = aad.len() as u64;
+            if length < 0xFF00 {
+                encoded.extend_from_slice(&(length as u16).to_be_bytes());
+            } else if length <= u64::from(u32::MAX) {
+                encoded.extend_from_slice(&[0xFF, 0xFE]);
+                encoded.extend_from_slice(&(length as u32).to_be_bytes());
+            } else {
+                encoded.extend_from_slice(&[0xFF, 0xFF]);
+                encoded.extend_from_slice(&length.to_be_bytes());
+            }
+            encoded.extend_from_slice(aad);
+            self.absorb(&mut mac, &encoded);
+        }
+        mac
+    }
+
+    /// CTR with the counter blocks `Ctr_1`, `Ctr_2`, ... over `data`. It returns `E(Ctr_0)`,
+    /// which encrypts the MAC.
+    fn ctr(&self, nonce: &[u8], data: &mut [u8]) -> Block {
+        self.ctr_from(nonce, 0, data);
+        self.s0(nonce)
+    }
+
+    /// CTR over `data`, which starts `skipped` blocks into the message.
+    fn ctr_from(&self, nonce: &[u8], skipped: u64, data: &mut [u8]) {
+        let flags = (self.counter_len() - 1) as u8;
+        for (i, chunk) in data.chunks_mut(BLOCKSIZE_IN_BYTES).enumerate() {
+            let mut keystream = self.format_block(flags, nonce, skipped + i as u64 + 1);
+            self.cipher.encrypt_block(&mut keystream.0);
+            for (byte, key_byte) in chunk.iter_mut().zip(keystream.0.iter()) {
+                *byte ^= key_byte;
+            }
+        }
+    }
+
+    /// `E(Ctr_0)`, which encrypts the MAC.
+    fn s0(&self, nonce: &[u8]) -> Block {
+        let mut s0 = self.format_block((self.counter_len() - 1) as u8, nonce, 0);
+        self.cipher.encrypt_block(&mut s0.0);
+        s0
+    }
+}
+
+impl_redacted_debug!(Ccm<C>);
+
+/// A CCM message whose associated data is given in pieces, made by [`Ccm::begin`].
+///
+/// CCM encodes the length of the associated data before it, so the MAC can't start before the
+/// last piece: the pieces are collected in a `Vec`, and the message is processed at once by
+/// [`encrypt`], [`decrypt`], [`seal`] or [`open`].
+///
+/// [`Ccm::begin`]: ../ccm/struct.Ccm.html#method.begin
+/// [`encrypt`]: ../ccm/struct.CcmBuilder.html#method.encrypt
+/// [`decrypt`]: ../ccm/struct.CcmBuilder.html#method.decrypt
+/// [`seal`]: ../ccm/struct.CcmBuilder.html#method.seal
+/// [`open`]: ../ccm/struct.CcmBuilder.html#method.open
+pub struct CcmBuilder<'a, C> {
+    ccm: &'a Ccm<C>,
+    nonce: &'a [u8],
+    aad: Vec<u8>,
+}
+
+impl<C: BlockCipher128> CcmBuilder<'_, C> {
+    /// Append a piece of associated data.
+    pub fn update_aad(&mut self, aad: &[u8]) -> &mut Self {
+        self.aad.extend_from_slice(aad);
+        self
+    }
+
+    /// **Encrypt** `data` in place, see [`Ccm::encrypt`].
+    ///
+    /// [`Ccm::encrypt`]: ../ccm/struct.Ccm.html#method.encrypt
+    pub fn encrypt(self, data: &mut [u8]) -> Result<Tag, AesError> {
+        self.ccm.encrypt(self.nonce, &self.aad, data)
+    }
+
+    /// **Decrypt** `data` in place and verify the tag, see [`Ccm::decrypt`].
+    ///
+    /// [`Ccm::decrypt`]: ../ccm/struct.Ccm.html#method.decrypt
+    pub fn decrypt(self, data: &mut [u8], tag: &[u8]) -> Result<(), AesError> {
+        self.ccm.decrypt(self.nonce, &self.aad, data, tag)
+    }
+
+    /// **Encrypt** `plain`, returning the cipher followed by the tag, see [`Ccm::seal`].
+    ///
+    /// [`Ccm::seal`]: ../ccm/struct.Ccm.html#method.seal
+    pub fn seal(self, plain: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.ccm.seal(self.nonce, &self.aad, plain)
+    }
+
+    /// **Decrypt** `sealed`, the cipher followed by the tag, see [`Ccm::open`].
+    ///
+    /// [`Ccm::open`]: ../ccm/struct.Ccm.html#method.open
+    pub fn open(self, sealed: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.ccm.open(self.nonce, &self.aad, sealed)
+    }
+}
+
+impl<C> std::fmt::Debug for CcmBuilder<'_, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CcmBuilder").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+    use crate::{Aes128, Aes256};
+
+    fn range(start: u8, end: u8) -> Vec<u8> {
+        (start..end).collect()
+    }
+
+    #[test]
+    fn sp800_38c_examples() {
+        // NIST SP 800-38C, Appendix C, Examples 1 to 3.
+        let key: [u8; 16] = core::array::from_fn(|i| 0x40 + i as u8);
+        let cases = [
+            (7, 4, 8, 4, "7162015b4dac255d"),
+            (8, 6, 16, 16, "d2a1f0e051ea5f62081a7792073d593d1fc64fbfaccd"),
+            (
+                12,
+                8,
+                20,
+                24,
+                "e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5484392fbc1b09951",
+            ),
+        ];
+        for (nonce_len, tag_len, aad_len, plain_len, expected) in cases {
+            let ccm = Ccm::new(Aes128::new(&key), nonce_len, tag_len).unwrap();
+            let nonce = range(0x10, 0x10 + nonce_len as u8);
+            let aad = range(0, aad_len);
+            let plain = range(0x20, 0x20 + plain_len);
+            let sealed = ccm.seal(&nonce, &aad, &plain).unwrap();
+            assert_eq!(hex(&sealed), expected);
+            assert_eq!(ccm.open(&nonce, &aad, &sealed).unwrap(), plain);
+        }
+    }
+
+    #[test]
+    fn long_aad_and_empty_message() {
+        // The expected values came from AESCCM of Python's `cryptography`.
+        let key = range(0, 32);
+        let ccm = Ccm::new(Aes256::new(key[..].try_into().unwrap()), 13, 16).unwrap();
+        let nonce = range(0, 13);
+        let aad: Vec<u8> = (0..70000).map(|i| i as u8).collect();
+        let plain: Vec<u8> = (0..50).map(|i| (i % 251) as u8).collect();
+        let sealed = ccm.seal(&nonce, &aad, &plain).unwrap();
+        assert_eq!(
+            hex(&sealed[(sealed.len() - 32)..]),
+            "4257c93092fc309cae8da5d5f8734f3605b1553c2a058ebd5e87016abfbbc3f2"
+        );
+        assert_eq!(ccm.open(&nonce, &aad, &sealed).unwrap(), plain);
+
+        let empty = ccm.seal(&nonce, &[], &[]).unwrap();
+        assert_eq!(hex(&empty), "5ca387835c0d63b16110f45241370da8");
+    }
+
+    #[test]
+    fn failures() {
+        let ccm = Ccm::new(Aes128::new(&[0u8; 16]), 12, 16).unwrap();
+        let nonce = [0u8; 12];
+        let mut data = [0x5Au8; 40];
+        let tag = ccm.encrypt(&nonce, b"aad", &mut data).unwrap();
+
+        let mut forged = data;
+        forged[0] ^= 1;
+        assert_eq!(
+            ccm.decrypt(&nonce, b"aad", &mut forged, tag.as_bytes()),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(forged, [0u8; 40]);
+        assert_eq!(
+            ccm.decrypt(&nonce, b"aad", &mut data.clone(), &tag.as_bytes()[..8]),
+            Err(AesError::TagMismatch)
+        );
+        ccm.decrypt(&nonce, b"aad", &mut data, tag.as_bytes())
+            .unwrap();
+        assert_eq!(data, [0x5Au8; 40]);
+
+        assert_eq!(
+            ccm.seal(&[0u8; 13], &[], &[]),
+            Err(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            ccm.open(&nonce, &[], &[0u8; 15]),
+            Err(AesError::InvalidDataLength)
+        );
+        for (nonce_len, tag_len) in [(6, 16), (14, 16), (12, 5), (12, 2), (12, 18)] {
+            assert!(Ccm::new(Aes128::new(&[0u8; 16]), nonce_len, tag_len).is_err());
+        }
+
+        // With a 13-byte nonce, the length of the message has 2 bytes.
+        let short = Ccm::new(Aes128::new(&[0u8; 16]), 13, 4).unwrap();
+        assert_eq!(
+            short.seal(&[0u8; 13], &[], &vec![0u8; 1 << 16]),
+            Err(AesError::InvalidDataLength)
+        );
+        assert!(short
+            .seal(&[0u8; 13], &[], &vec![0u8; (1 << 16) - 1])
+            .is_ok());
+    }
+
+    #[test]
+    fn tag_mismatch_wipes() {
+        let ccm = Ccm::new(Aes128::new(&[0x11u8; 16]), 13, 8).unwrap();
+        let nonce = [0x22u8; 13];
+        let mut sealed = ccm.seal(&nonce, b"aad", &[0x5Au8; 35]).unwrap();
+        let mut data = sealed[..35].to_vec();
+        let mut tag = sealed[35..].to_vec();
+        tag[7] ^= 0x80;
+        assert_eq!(
+            ccm.decrypt(&nonce, b"aad", &mut data, &tag),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(data, [0u8; 35]);
+        sealed[0] ^= 1;
+        assert_eq!(
+            ccm.open(&nonce, b"aad", &sealed),
+            Err(AesError::TagMismatch)
+        );
+    }
+
+    #[test]
+    fn prepended_tag() {
+        let ccm = Ccm::new(Aes128::new(&[0x33u8; 16]), 12, 16).unwrap();
+        let nonce = [0x44u8; 12];
+        let plain = range(0, 45);
+        let appended = ccm.seal(&nonce, b"hdr", &plain).unwrap();
+        let prepended = ccm
+            .seal_with_layout(&nonce, b"hdr", &plain, TagPlacement::Prepended)
+            .unwrap();
+        assert_eq!(prepended[..16], appended[45..]);
+        assert_eq!(prepended[16..], appended[..45]);
+        assert_eq!(
+            ccm.open_with_layout(&nonce, b"hdr", &prepended, TagPlacement::Prepended)
+                .unwrap(),
+            plain
+        );
+        assert_eq!(
+            ccm.seal_with_layout(&nonce, b"hdr", &plain, TagPlacement::default())
+                .unwrap(),
+            appended
+        );
+
+        // The wrong layout, a changed tag byte or a too short input are rejected.
+        assert_eq!(
+            ccm.open(&nonce, b"hdr", &prepended),
+            Err(AesError::TagMismatch)
+        );
+        let mut forged = prepended.clone();
+        forged[0] ^= 1;
+        assert_eq!(
+            ccm.open_with_layout(&nonce, b"hdr", &forged, TagPlacement::Prepended),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(
+            ccm.open_with_layout(&nonce, b"hdr", &[0u8; 15], TagPlacement::Prepended),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+
+    #[test]
+    fn two_pass_sealing() {
+        let ccm = Ccm::new(Aes128::new(&[0x66u8; 16]), 13, 8).unwrap();
+        let nonce = [0x77u8; 13];
+        let plain: Vec<u8> = (0..10_000).map(|i| (i % 253) as u8).collect();
+        let mut buffer = vec![0xEEu8; 5];
+        let mut spill = std::io::Cursor::new(vec![0xEEu8; 20_000]);
+        for len in [0, 15, 16, 4096, 4100, 10_000] {
+            let expected = ccm.seal(&nonce, b"header", &plain[..len]).unwrap();
+            ccm.seal_buffered(&nonce, b"header", &mut &plain[..len], &mut buffer)
+                .unwrap();
+            assert_eq!(buffer, expected, "{}", len);
+            let mut sealed = Vec::new();
+            let written = ccm
+                .seal_spilled(
+                    &nonce,
+                    b"header",
+                    &mut &plain[..len],
+                    &mut spill,
+                    &mut sealed,
+                )
+                .unwrap();
+            assert_eq!(written, expected.len() as u64);
+            assert_eq!(sealed, expected, "{}", len);
+        }
+
+        // With a 13-byte nonce, the plain is at most 65535 bytes long.
+        let long = vec![0u8; 1 << 16];
+        let error = ccm
+            .seal_buffered(&nonce, b"", &mut &long[..], &mut buffer)
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(buffer.is_empty());
+        let mut sealed = Vec::new();
+        let error = ccm
+            .seal_spilled(&nonce, b"", &mut &long[..], &mut spill, &mut sealed)
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(sealed.is_empty());
+    }
+
+    #[test]
+    fn aad_in_pieces() {
+        let ccm = Ccm::new(Aes128::new(&[0x12u8; 16]), 12, 16).unwrap();
+        let nonce = [0x34u8; 12];
+        let aad = range(0, 40);
+        let plain = range(0x80, 0xA3);
+        let expected = ccm.seal(&nonce, &aad, &plain).unwrap();
+
+        let mut message = ccm.begin(&nonce);
+        for piece in aad.chunks(7) {
+            message.update_aad(piece);
+        }
+        assert_eq!(message.seal(&plain).unwrap(), expected);
+        let mut message = ccm.begin(&nonce);
+        message
+            .update_aad(&aad[..20])
+            .update_aad(&[])
+            .update_aad(&aad[20..]);
+        assert_eq!(message.open(&expected).unwrap(), plain);
+
+        let mut data = plain.clone();
+        let mut message = ccm.begin(&nonce);
+        message.update_aad(&aad);
+        let tag = message.encrypt(&mut data).unwrap();
+        assert_eq!(data, expected[..plain.len()]);
+        let mut message = ccm.begin(&nonce);
+        message.update_aad(&aad[..39]);
+        assert_eq!(
+            message.decrypt(&mut data, tag.as_bytes()),
+            Err(AesError::TagMismatch)
+        );
+
+        // No pieces at all is no associated data.
+        assert_eq!(
+            ccm.begin(&nonce).seal(&plain).unwrap(),
+            ccm.seal(&nonce, &[], &plain).unwrap()
+        );
+    }
+
+    #[cfg(feature = "forensic-open")]
+    #[test]
+    fn forensic_decrypt_keeps_plain() {
+        let ccm = Ccm::new(Aes128::new(&[0x11u8; 16]), 13, 8).unwrap();
+        let nonce = [0x22u8; 13];
+        let mut data = [0x5Au8; 35];
+        let tag = ccm.encrypt(&nonce, b"aad", &mut data).unwrap();
+
+        let mut forged = data;
+        forged[3] ^= 0x0F;
+        let mut wiped = forged;
+        assert_eq!(
+            ccm.decrypt(&nonce, b"aad", &mut wiped, tag.as_bytes()),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(wiped, [0u8; 35]);
+        assert_eq!(
+            ccm.decrypt_unverified_for_forensics(&nonce, b"aad", &mut forged, tag.as_bytes()),
+            Err(AesError::TagMismatch)
+        );
+        let mut expected = [0x5Au8; 35];
+        expected[3] ^= 0x0F;
+        assert_eq!(forged, expected);
+
+        // A right tag, or a truncated one.
+        let mut plain = data;
+        ccm.decrypt_unverified_for_forensics(&nonce, b"aad", &mut plain, tag.as_bytes())
+            .unwrap();
+        assert_eq!(plain, [0x5Au8; 35]);
+        let mut plain = data;
+        assert_eq!(
+            ccm.decrypt_unverified_for_forensics(&nonce, b"aad", &mut plain, &tag.as_bytes()[..4]),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(plain, [0x5Au8; 35]);
+    }
+}
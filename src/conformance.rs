@@ -0,0 +1,723 @@
+//! # conformance
+//! `conformance` runs the official example vectors of the NIST SP 800-38 series against this
+//! library, so a build can show that it "runs the NIST examples" with one call.
+//!
+//! Suite | Vectors
+//! - | -
+//! SP 800-38A | Appendix F: ECB, CBC, CFB-1, CFB-8, CFB-128, OFB and CTR, with 128, 192 and 256-bit keys
+//! SP 800-38B | The CMAC examples: CMAC-AES with 128, 192 and 256-bit keys, on messages of 0, 16, 40 and 64 bytes
+//! SP 800-38C | Appendix C: Examples 1 to 4 of CCM-AES128, with 7 to 13-byte nonces and 4 to 14-byte tags
+//! SP 800-38F | KW (RFC 3394 section 4) and KWP (RFC 5649 section 6)
+//!
+//! The same vectors are checked by the tests of this library.
+//! # Examples
+//! ```
+//! use aes_frast::conformance;
+//!
+//! let report = conformance::run_all();
+//! assert!(report.is_success(), "failed: {:?}", report.failed);
+//! assert!(report.passed.contains(&"SP 800-38A F.2.1 CBC-AES128.Encrypt".to_string()));
+//! ```
+use super::aes_cipher::AesAny;
+use super::aes_cmac::Cmac;
+use super::aes_core;
+use super::aes_with_operation_mode as mode;
+use super::ccm::Ccm;
+use super::key_wrap::{self, WrappedKey};
+
+/// The result of running conformance vectors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The names of the passed vectors.
+    pub passed: Vec<String>,
+    /// The names of the failed vectors.
+    pub failed: Vec<String>,
+}
+
+impl Report {
+    /// Whether any vector ran and all of them passed.
+    pub fn is_success(&self) -> bool {
+        !self.passed.is_empty() && self.failed.is_empty()
+    }
+
+    fn check(&mut self, name: String, passed: bool) {
+        if passed {
+            self.passed.push(name);
+        } else {
+            self.failed.push(name);
+        }
+    }
+
+    fn merge(&mut self, other: Report) {
+        self.passed.extend(other.passed);
+        self.failed.extend(other.failed);
+    }
+}
+
+/// Run all the conformance suites.
+pub fn run_all() -> Report {
+    let mut report = Report::default();
+    report.merge(sp800_38a());
+    report.merge(sp800_38b());
+    report.merge(sp800_38c());
+    report.merge(sp800_38f());
+    report
+}
+
+/// Run the examples of NIST SP 800-38A Appendix F.
+///
+/// Each example is run both ways, and named after its section, e.g.
+/// `SP 800-38A F.2.1 CBC-AES128.Encrypt` and `SP 800-38A F.2.2 CBC-AES128.Decrypt`.
+pub fn sp800_38a() -> Report {
+    let mut report = Report::default();
+    for case in SP800_38A {
+        let key = unhex(case.key);
+        let iv = unhex(case.iv);
+        let plain = unhex(case.plain);
+        let cipher = unhex(case.cipher);
+        // Nb * (Nr + 1) = 4 * (Nk + 7) words of sub-keys
+        let mut e_keys = vec![0u32; (key.len() / 4 + 7) * 4];
+        let mut d_keys = vec![0u32; (key.len() / 4 + 7) * 4];
+        aes_core::key_schedule_encrypt_auto(&key, &mut e_keys);
+        aes_core::key_schedule_decrypt_auto(&key, &mut d_keys);
+        let mut encrypted = vec![0u8; plain.len()];
+        let mut decrypted = vec![0u8; cipher.len()];
+        match case.mode {
+            Mode::Ecb => {
+                mode::ecb_enc(&plain, &mut encrypted, &e_keys);
+                mode::ecb_dec(&cipher, &mut decrypted, &d_keys);
+            }
+            Mode::Cbc => {
+                mode::cbc_enc(&plain, &mut encrypted, &e_keys, &iv);
+                mode::cbc_dec(&cipher, &mut decrypted, &d_keys, &iv);
+            }
+            Mode::Cfb1 => {
+                mode::cfb_1_enc(&plain, &mut encrypted, plain.len() * 8, &e_keys, &iv);
+                mode::cfb_1_dec(&cipher, &mut decrypted, cipher.len() * 8, &e_keys, &iv);
+            }
+            Mode::Cfb8 => {
+                mode::cfb_8_enc(&plain, &mut encrypted, &e_keys, &iv);
+                mode::cfb_8_dec(&cipher, &mut decrypted, &e_keys, &iv);
+            }
+            Mode::Cfb128 => {
+                mode::cfb_enc(&plain, &mut encrypted, &e_keys, &iv);
+                mode::cfb_dec(&cipher, &mut decrypted, &e_keys, &iv);
+            }
+            Mode::Ofb => {
+                mode::ofb_enc_dec(&plain, &mut encrypted, &e_keys, &iv);
+                mode::ofb_enc_dec(&cipher, &mut decrypted, &e_keys, &iv);
+            }
+            Mode::Ctr => {
+                mode::ctr_enc_dec(&plain, &mut encrypted, &e_keys, &iv);
+                mode::ctr_enc_dec(&cipher, &mut decrypted, &e_keys, &iv);
+            }
+        }
+        // The decryption example follows the encryption example in the next section.
+        let (chapter, number) = case.section.rsplit_once('.').unwrap();
+        let number: u32 = number.parse().unwrap();
+        report.check(
+            format!("SP 800-38A {} {}.Encrypt", case.section, case.name),
+            encrypted == cipher,
+        );
+        report.check(
+            format!(
+                "SP 800-38A {}.{} {}.Decrypt",
+                chapter,
+                number + 1,
+                case.name
+            ),
+            decrypted == plain,
+        );
+    }
+    report
+}
+
+/// Run the CMAC examples of NIST SP 800-38B, which are on prefixes of the SP 800-38A plain.
+///
+/// Each example is generated and verified, and named after its section, e.g.
+/// `SP 800-38B D.1 Example 2 CMAC-AES128.Generate` and
+/// `SP 800-38B D.1 Example 2 CMAC-AES128.Verify`.
+pub fn sp800_38b() -> Report {
+    let mut report = Report::default();
+    let plain = unhex(PLAIN);
+    for case in SP800_38B {
+        let cmac = Cmac::new(AesAny::new(&unhex(case.key)).unwrap());
+        let message = &plain[..case.message_len];
+        let mac = unhex(case.mac);
+        report.check(
+            format!("SP 800-38B {} {}.Generate", case.section, case.name),
+            cmac.mac(message).as_bytes()[..] == mac[..],
+        );
+        report.check(
+            format!("SP 800-38B {} {}.Verify", case.section, case.name),
+            cmac.verify(message, &mac).is_ok(),
+        );
+    }
+    report
+}
+
+/// Run the CCM examples of NIST SP 800-38C Appendix C.
+///
+/// Each example is run both ways, and named after its number, e.g.
+/// `SP 800-38C Example 1 CCM-AES128.Encrypt` and `SP 800-38C Example 1 CCM-AES128.Decrypt`.
+pub fn sp800_38c() -> Report {
+    let mut report = Report::default();
+    let key = AesAny::new(&unhex(KEY_SP800_38C)).unwrap();
+    for case in SP800_38C {
+        let ccm = Ccm::new(key.clone(), case.nonce_len, case.tag_len).unwrap();
+        // The nonce, the associated data and the plain are counting bytes.
+        let nonce: Vec<u8> = (0..case.nonce_len).map(|i| 0x10 + i as u8).collect();
+        let aad: Vec<u8> = (0..case.aad_len).map(|i| i as u8).collect();
+        let plain: Vec<u8> = (0..case.plain_len).map(|i| 0x20 + i as u8).collect();
+        let sealed = unhex(case.sealed);
+        report.check(
+            format!("SP 800-38C {} CCM-AES128.Encrypt", case.name),
+            ccm.seal(&nonce, &aad, &plain) == Ok(sealed.clone()),
+        );
+        report.check(
+            format!("SP 800-38C {} CCM-AES128.Decrypt", case.name),
+            ccm.open(&nonce, &aad, &sealed) == Ok(plain),
+        );
+    }
+    report
+}
+
+/// Run the key wrap examples for NIST SP 800-38F: those of RFC 3394 for KW and of RFC 5649
+/// for KWP, since SP 800-38F gives none.
+///
+/// Each example is run both ways, and named after the function of SP 800-38F and the section of
+/// the RFC, e.g. `SP 800-38F KW-AE RFC 3394 4.1` and `SP 800-38F KW-AD RFC 3394 4.1`.
+pub fn sp800_38f() -> Report {
+    let mut report = Report::default();
+    for case in SP800_38F {
+        let kek = AesAny::new(&unhex(case.kek)).unwrap();
+        let key = unhex(case.key);
+        let wrapped = WrappedKey::new(&unhex(case.wrapped)).unwrap();
+        let (algorithm, encrypted, decrypted) = if case.padding {
+            (
+                "KWP",
+                key_wrap::wrap_with_padding(&kek, &key),
+                key_wrap::unwrap_with_padding(&kek, &wrapped),
+            )
+        } else {
+            (
+                "KW",
+                key_wrap::wrap(&kek, &key),
+                key_wrap::unwrap(&kek, &wrapped),
+            )
+        };
+        report.check(
+            format!("SP 800-38F {}-AE {}", algorithm, case.name),
+            encrypted == Ok(wrapped),
+        );
+        report.check(
+            format!("SP 800-38F {}-AD {}", algorithm, case.name),
+            decrypted.is_ok_and(|decrypted| decrypted.as_bytes() == key),
+        );
+    }
+    report
+}
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16).unwrap())
+        .collect()
+}
+
+enum Mode {
+    Ecb,
+    Cbc,
+    Cfb1,
+    Cfb8,
+    Cfb128,
+    Ofb,
+    Ctr,
+}
+
+struct Sp80038aCase {
+    section: &'static str,
+    name: &'static str,
+    mode: Mode,
+    key: &'static str,
+    iv: &'static str,
+    plain: &'static str,
+    cipher: &'static str,
+}
+
+const KEY_128: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+const KEY_192: &str = "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b";
+const KEY_256: &str = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+const IV: &str = "000102030405060708090a0b0c0d0e0f";
+const COUNTER: &str = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+const PLAIN: &str = concat!(
+    "6bc1bee22e409f96e93d7e117393172a",
+    "ae2d8a571e03ac9c9eb76fac45af8e51",
+    "30c81c46a35ce411e5fbc1191a0a52ef",
+    "f69f2445df4f9b17ad2b417be66c3710"
+);
+// The 16 bits of the CFB-1 examples.
+const PLAIN_CFB1: &str = "6bc1";
+const PLAIN_CFB8: &str = "6bc1bee22e409f96e93d7e117393172aae2d";
+
+const SP800_38A: &[Sp80038aCase] = &[
+    Sp80038aCase {
+        section: "F.1.1",
+        name: "ECB-AES128",
+        mode: Mode::Ecb,
+        key: KEY_128,
+        iv: "",
+        plain: PLAIN,
+        cipher: "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf43b1cd7f598ece23881b00e3ed0306887b0c785e27e8ad3f8223207104725dd4",
+    },
+    Sp80038aCase {
+        section: "F.1.3",
+        name: "ECB-AES192",
+        mode: Mode::Ecb,
+        key: KEY_192,
+        iv: "",
+        plain: PLAIN,
+        cipher: "bd334f1d6e45f25ff712a214571fa5cc974104846d0ad3ad7734ecb3ecee4eefef7afd2270e2e60adce0ba2face6444e9a4b41ba738d6c72fb16691603c18e0e",
+    },
+    Sp80038aCase {
+        section: "F.1.5",
+        name: "ECB-AES256",
+        mode: Mode::Ecb,
+        key: KEY_256,
+        iv: "",
+        plain: PLAIN,
+        cipher: "f3eed1bdb5d2a03c064b5a7e3db181f8591ccb10d410ed26dc5ba74a31362870b6ed21b99ca6f4f9f153e7b1beafed1d23304b7a39f9f3ff067d8d8f9e24ecc7",
+    },
+    Sp80038aCase {
+        section: "F.2.1",
+        name: "CBC-AES128",
+        mode: Mode::Cbc,
+        key: KEY_128,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b273bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7",
+    },
+    Sp80038aCase {
+        section: "F.2.3",
+        name: "CBC-AES192",
+        mode: Mode::Cbc,
+        key: KEY_192,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "4f021db243bc633d7178183a9fa071e8b4d9ada9ad7dedf4e5e738763f69145a571b242012fb7ae07fa9baac3df102e008b0e27988598881d920a9e64f5615cd",
+    },
+    Sp80038aCase {
+        section: "F.2.5",
+        name: "CBC-AES256",
+        mode: Mode::Cbc,
+        key: KEY_256,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b",
+    },
+    Sp80038aCase {
+        section: "F.3.1",
+        name: "CFB1-AES128",
+        mode: Mode::Cfb1,
+        key: KEY_128,
+        iv: IV,
+        plain: PLAIN_CFB1,
+        cipher: "68b3",
+    },
+    Sp80038aCase {
+        section: "F.3.3",
+        name: "CFB1-AES192",
+        mode: Mode::Cfb1,
+        key: KEY_192,
+        iv: IV,
+        plain: PLAIN_CFB1,
+        cipher: "9359",
+    },
+    Sp80038aCase {
+        section: "F.3.5",
+        name: "CFB1-AES256",
+        mode: Mode::Cfb1,
+        key: KEY_256,
+        iv: IV,
+        plain: PLAIN_CFB1,
+        cipher: "9029",
+    },
+    Sp80038aCase {
+        section: "F.3.7",
+        name: "CFB8-AES128",
+        mode: Mode::Cfb8,
+        key: KEY_128,
+        iv: IV,
+        plain: PLAIN_CFB8,
+        cipher: "3b79424c9c0dd436bace9e0ed4586a4f32b9",
+    },
+    Sp80038aCase {
+        section: "F.3.9",
+        name: "CFB8-AES192",
+        mode: Mode::Cfb8,
+        key: KEY_192,
+        iv: IV,
+        plain: PLAIN_CFB8,
+        cipher: "cda2521ef0a905ca44cd057cbf0d47a0678a",
+    },
+    Sp80038aCase {
+        section: "F.3.11",
+        name: "CFB8-AES256",
+        mode: Mode::Cfb8,
+        key: KEY_256,
+        iv: IV,
+        plain: PLAIN_CFB8,
+        cipher: "dc1f1a8520a64db55fcc8ac554844e889700",
+    },
+    Sp80038aCase {
+        section: "F.3.13",
+        name: "CFB128-AES128",
+        mode: Mode::Cfb128,
+        key: KEY_128,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6",
+    },
+    Sp80038aCase {
+        section: "F.3.15",
+        name: "CFB128-AES192",
+        mode: Mode::Cfb128,
+        key: KEY_192,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "cdc80d6fddf18cab34c25909c99a417467ce7f7f81173621961a2b70171d3d7a2e1e8a1dd59b88b1c8e60fed1efac4c9c05f9f9ca9834fa042ae8fba584b09ff",
+    },
+    Sp80038aCase {
+        section: "F.3.17",
+        name: "CFB128-AES256",
+        mode: Mode::Cfb128,
+        key: KEY_256,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "dc7e84bfda79164b7ecd8486985d386039ffed143b28b1c832113c6331e5407bdf10132415e54b92a13ed0a8267ae2f975a385741ab9cef82031623d55b1e471",
+    },
+    Sp80038aCase {
+        section: "F.4.1",
+        name: "OFB-AES128",
+        mode: Mode::Ofb,
+        key: KEY_128,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed8259740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e",
+    },
+    Sp80038aCase {
+        section: "F.4.3",
+        name: "OFB-AES192",
+        mode: Mode::Ofb,
+        key: KEY_192,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "cdc80d6fddf18cab34c25909c99a4174fcc28b8d4c63837c09e81700c11004018d9a9aeac0f6596f559c6d4daf59a5f26d9f200857ca6c3e9cac524bd9acc92a",
+    },
+    Sp80038aCase {
+        section: "F.4.5",
+        name: "OFB-AES256",
+        mode: Mode::Ofb,
+        key: KEY_256,
+        iv: IV,
+        plain: PLAIN,
+        cipher: "dc7e84bfda79164b7ecd8486985d38604febdc6740d20b3ac88f6ad82a4fb08d71ab47a086e86eedf39d1c5bba97c4080126141d67f37be8538f5a8be740e484",
+    },
+    Sp80038aCase {
+        section: "F.5.1",
+        name: "CTR-AES128",
+        mode: Mode::Ctr,
+        key: KEY_128,
+        iv: COUNTER,
+        plain: PLAIN,
+        cipher: "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee",
+    },
+    Sp80038aCase {
+        section: "F.5.3",
+        name: "CTR-AES192",
+        mode: Mode::Ctr,
+        key: KEY_192,
+        iv: COUNTER,
+        plain: PLAIN,
+        cipher: "1abc932417521ca24f2b0459fe7e6e0b090339ec0aa6faefd5ccc2c6f4ce8e941e36b26bd1ebc670d1bd1d665620abf74f78a7f6d29809585a97daec58c6b050",
+    },
+    Sp80038aCase {
+        section: "F.5.5",
+        name: "CTR-AES256",
+        mode: Mode::Ctr,
+        key: KEY_256,
+        iv: COUNTER,
+        plain: PLAIN,
+        cipher: "601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c52b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6",
+    },
+];
+
+struct Sp80038bCase {
+    section: &'static str,
+    name: &'static str,
+    key: &'static str,
+    message_len: usize,
+    mac: &'static str,
+}
+
+const SP800_38B: &[Sp80038bCase] = &[
+    Sp80038bCase {
+        section: "D.1 Example 1",
+        name: "CMAC-AES128",
+        key: KEY_128,
+        message_len: 0,
+        mac: "bb1d6929e95937287fa37d129b756746",
+    },
+    Sp80038bCase {
+        section: "D.1 Example 2",
+        name: "CMAC-AES128",
+        key: KEY_128,
+        message_len: 16,
+        mac: "070a16b46b4d4144f79bdd9dd04a287c",
+    },
+    Sp80038bCase {
+        section: "D.1 Example 3",
+        name: "CMAC-AES128",
+        key: KEY_128,
+        message_len: 40,
+        mac: "dfa66747de9ae63030ca32611497c827",
+    },
+    Sp80038bCase {
+        section: "D.1 Example 4",
+        name: "CMAC-AES128",
+        key: KEY_128,
+        message_len: 64,
+        mac: "51f0bebf7e3b9d92fc49741779363cfe",
+    },
+    Sp80038bCase {
+        section: "D.2 Example 1",
+        name: "CMAC-AES192",
+        key: KEY_192,
+        message_len: 0,
+        mac: "d17ddf46adaacde531cac483de7a9367",
+    },
+    Sp80038bCase {
+        section: "D.2 Example 2",
+        name: "CMAC-AES192",
+        key: KEY_192,
+        message_len: 16,
+        mac: "9e99a7bf31e710900662f65e617c5184",
+    },
+    Sp80038bCase {
+        section: "D.2 Example 3",
+        name: "CMAC-AES192",
+        key: KEY_192,
+        message_len: 40,
+        mac: "8a1de5be2eb31aad089a82e6ee908b0e",
+    },
+    Sp80038bCase {
+        section: "D.2 Example 4",
+        name: "CMAC-AES192",
+        key: KEY_192,
+        message_len: 64,
+        mac: "a1d5df0eed790f794d77589659f39a11",
+    },
+    Sp80038bCase {
+        section: "D.3 Example 1",
+        name: "CMAC-AES256",
+        key: KEY_256,
+        message_len: 0,
+        mac: "028962f61b7bf89efc6b551f4667d983",
+    },
+    Sp80038bCase {
+        section: "D.3 Example 2",
+        name: "CMAC-AES256",
+        key: KEY_256,
+        message_len: 16,
+        mac: "28a7023f452e8f82bd4bf28d8c37c35c",
+    },
+    Sp80038bCase {
+        section: "D.3 Example 3",
+        name: "CMAC-AES256",
+        key: KEY_256,
+        message_len: 40,
+        mac: "aaf3d8f1de5640c232f5b169b9c911e6",
+    },
+    Sp80038bCase {
+        section: "D.3 Example 4",
+        name: "CMAC-AES256",
+        key: KEY_256,
+        message_len: 64,
+        mac: "e1992190549f6ed5696a2c056c315410",
+    },
+];
+
+struct Sp80038cCase {
+    name: &'static str,
+    nonce_len: usize,
+    tag_len: usize,
+    aad_len: usize,
+    plain_len: usize,
+    sealed: &'static str,
+}
+
+const KEY_SP800_38C: &str = "404142434445464748494a4b4c4d4e4f";
+
+const SP800_38C: &[Sp80038cCase] = &[
+    Sp80038cCase {
+        name: "Example 1",
+        nonce_len: 7,
+        tag_len: 4,
+        aad_len: 8,
+        plain_len: 4,
+        sealed: "7162015b4dac255d",
+    },
+    Sp80038cCase {
+        name: "Example 2",
+        nonce_len: 8,
+        tag_len: 6,
+        aad_len: 16,
+        plain_len: 16,
+        sealed: "d2a1f0e051ea5f62081a7792073d593d1fc64fbfaccd",
+    },
+    Sp80038cCase {
+        name: "Example 3",
+        nonce_len: 12,
+        tag_len: 8,
+        aad_len: 20,
+        plain_len: 24,
+        sealed: "e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5484392fbc1b09951",
+    },
+    Sp80038cCase {
+        name: "Example 4",
+        nonce_len: 13,
+        tag_len: 14,
+        // 256 repetitions of the bytes from 0x00 to 0xFF.
+        aad_len: 65536,
+        plain_len: 32,
+        sealed: "69915dad1e84c6376a68c2967e4dab615ae0fd1faec44cc484828529463ccf72b4ac6bec93e8598e7f0dadbcea5b",
+    },
+];
+
+struct Sp80038fCase {
+    name: &'static str,
+    padding: bool,
+    kek: &'static str,
+    key: &'static str,
+    wrapped: &'static str,
+}
+
+const KEK_128: &str = "000102030405060708090a0b0c0d0e0f";
+const KEK_192: &str = "000102030405060708090a0b0c0d0e0f1011121314151617";
+const KEK_256: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+const KEY_DATA_128: &str = "00112233445566778899aabbccddeeff";
+const KEY_DATA_192: &str = "00112233445566778899aabbccddeeff0001020304050607";
+const KEY_DATA_256: &str = "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f";
+const KEK_RFC5649: &str = "5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8";
+
+const SP800_38F: &[Sp80038fCase] = &[
+    Sp80038fCase {
+        name: "RFC 3394 4.1",
+        padding: false,
+        kek: KEK_128,
+        key: KEY_DATA_128,
+        wrapped: "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5",
+    },
+    Sp80038fCase {
+        name: "RFC 3394 4.2",
+        padding: false,
+        kek: KEK_192,
+        key: KEY_DATA_128,
+        wrapped: "96778b25ae6ca435f92b5b97c050aed2468ab8a17ad84e5d",
+    },
+    Sp80038fCase {
+        name: "RFC 3394 4.3",
+        padding: false,
+        kek: KEK_256,
+        key: KEY_DATA_128,
+        wrapped: "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7",
+    },
+    Sp80038fCase {
+        name: "RFC 3394 4.4",
+        padding: false,
+        kek: KEK_192,
+        key: KEY_DATA_192,
+        wrapped: "031d33264e15d33268f24ec260743edce1c6c7ddee725a936ba814915c6762d2",
+    },
+    Sp80038fCase {
+        name: "RFC 3394 4.5",
+        padding: false,
+        kek: KEK_256,
+        key: KEY_DATA_192,
+        wrapped: "a8f9bc1612c68b3ff6e6f4fbe30e71e4769c8b80a32cb8958cd5d17d6b254da1",
+    },
+    Sp80038fCase {
+        name: "RFC 3394 4.6",
+        padding: false,
+        kek: KEK_256,
+        key: KEY_DATA_256,
+        wrapped: "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21",
+    },
+    Sp80038fCase {
+        name: "RFC 5649 6 20-octet key",
+        padding: true,
+        kek: KEK_RFC5649,
+        key: "c37b7e6492584340bed12207808941155068f738",
+        wrapped: "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+    },
+    Sp80038fCase {
+        name: "RFC 5649 6 7-octet key",
+        padding: true,
+        kek: KEK_RFC5649,
+        key: "466f7250617369",
+        wrapped: "afbeb0f07dfbf5419200f2ccb50bb24f",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sp800_38a_works() {
+        let report = sp800_38a();
+        assert!(report.is_success(), "failed: {:?}", report.failed);
+        assert_eq!(report.passed.len(), 42);
+        assert!(report
+            .passed
+            .contains(&"SP 800-38A F.5.6 CTR-AES256.Decrypt".to_string()));
+        assert!(report
+            .passed
+            .contains(&"SP 800-38A F.3.2 CFB1-AES128.Decrypt".to_string()));
+    }
+
+    #[test]
+    fn sp800_38b_works() {
+        let report = sp800_38b();
+        assert!(report.is_success(), "failed: {:?}", report.failed);
+        assert_eq!(report.passed.len(), 24);
+        assert!(report
+            .passed
+            .contains(&"SP 800-38B D.3 Example 3 CMAC-AES256.Verify".to_string()));
+    }
+
+    #[test]
+    fn sp800_38c_works() {
+        let report = sp800_38c();
+        assert!(report.is_success(), "failed: {:?}", report.failed);
+        assert_eq!(report.passed.len(), 8);
+        assert!(report
+            .passed
+            .contains(&"SP 800-38C Example 4 CCM-AES128.Decrypt".to_string()));
+    }
+
+    #[test]
+    fn sp800_38f_works() {
+        let report = sp800_38f();
+        assert!(report.is_success(), "failed: {:?}", report.failed);
+        assert_eq!(report.passed.len(), 16);
+        assert!(report
+            .passed
+            .contains(&"SP 800-38F KWP-AD RFC 5649 6 7-octet key".to_string()));
+    }
+
+    #[test]
+    fn run_all_works() {
+        assert!(run_all().is_success());
+    }
+}
@@ -0,0 +1,285 @@
+//! # context
+//! `context` provides the cipher contexts [`Aes128Context`], [`Aes192Context`] and
+//! [`Aes256Context`], which can be placed in a `static`.
+//!
+//! A context is created without a key by the `const fn` [`new`], so a `static` context is all
+//! zeros and ends up in `.bss` (or any linker section given by `#[link_section]`). The key is set
+//! later at run time with `set_key`. Neither the contexts nor their mode methods allocate: the
+//! data is processed in place, and the IV or counter is updated in place for the next call.
+//! A context overwrites its sub-keys with zeros when it is dropped, like the cipher objects. A
+//! `static` one is never dropped, so call `clear` when its key is no longer needed.
+//!
+//! ## Sharing with interrupt handlers
+//! The mode methods take `&self` but the key setters take `&mut self`, so a context which is
+//! shared between the main code and interrupt handlers (or threads) needs a lock, like any other
+//! mutable `static`. On targets with `std`, a `std::sync::Mutex` can be created in a `static`.
+//! On single-core microcontrollers, a critical-section mutex (such as
+//! `cortex_m::interrupt::Mutex<RefCell<_>>`) does the same job without blocking. Never hold the
+//! lock across a long message in an interrupt handler: process it in slices instead, the IV or
+//! counter carries the state between the slices.
+//! # Examples
+//! ```
+//! use aes_frast::context::Aes128Context;
+//! use std::sync::Mutex;
+//!
+//! static CONTEXT: Mutex<Aes128Context> = Mutex::new(Aes128Context::new());
+//!
+//! CONTEXT.lock().unwrap().set_key(&[0x2Bu8; 16]);
+//!
+//! let mut data = [0x5Au8; 48];
+//! let mut counter = [0u8; 16];
+//! CONTEXT.lock().unwrap().ctr_apply(&mut data, &mut counter);
+//! assert_eq!(counter[15], 3);
+//!
+//! let mut counter = [0u8; 16];
+//! CONTEXT.lock().unwrap().ctr_apply(&mut data, &mut counter);
+//! assert_eq!(data, [0x5Au8; 48]);
+//! ```
+//!
+//! [`Aes128Context`]: ../context/struct.Aes128Context.html
+//! [`Aes192Context`]: ../context/struct.Aes192Context.html
+//! [`Aes256Context`]: ../context/struct.Aes256Context.html
+//! [`new`]: ../context/struct.Aes128Context.html#method.new
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+use super::aes_core::{KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::aes_with_operation_mode_inplace as inplace;
+use super::block_cipher::BlockCipher128;
+
+macro_rules! define_aes_context {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $key_bytes:expr, $n_subkeys:expr,
+        $schedule:path,
+        $block_encrypt:path, $block_decrypt:path
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            encryption_subkeys: [u32; $n_subkeys],
+            decryption_subkeys: [u32; $n_subkeys],
+            keyed: bool,
+        }
+
+        impl_redacted_debug!($name);
+
+        impl $name {
+            /// Create a context without a key. All the sub-keys are zeros.
+            pub const fn new() -> Self {
+                Self {
+                    encryption_subkeys: [0u32; $n_subkeys],
+                    decryption_subkeys: [0u32; $n_subkeys],
+                    keyed: false,
+                }
+            }
+
+            /// Schedule the sub-keys of `key`, replacing the current key if there is one.
+            pub fn set_key(&mut self, key: &[u8; $key_bytes]) {
+                $schedule(key, &mut self.encryption_subkeys, &mut self.decryption_subkeys);
+                self.keyed = true;
+            }
+
+            /// Overwrite the sub-keys with zeros. The context has no key afterwards.
+            pub fn clear(&mut self) {
+                self.encryption_subkeys.fill(0);
+                self.decryption_subkeys.fill(0);
+                self.keyed = false;
+            }
+
+            /// Whether a key has been set.
+            pub const fn is_keyed(&self) -> bool {
+                self.keyed
+            }
+
+            fn check_keyed(&self) {
+                assert!(self.keyed, "The context has no key.");
+            }
+
+            /// **Encrypt** consecutive blocks in place, each one independently (ECB mode).
+            ///
+            /// # Panics
+            /// This function panics if the context has no key, or if the length of `blocks` is
+            /// not a multiple of 16.
+            pub fn ecb_encrypt(&self, blocks: &mut [u8]) {
+                self.check_keyed();
+                aes_core::encrypt_blocks(blocks, &self.encryption_subkeys);
+            }
+
+            /// **Decrypt** consecutive blocks in place, each one independently (ECB mode).
+            ///
+            /// # Panics
+            /// This function panics if the context has no key, or if the length of `blocks` is
+            /// not a multiple of 16.
+            pub fn ecb_decrypt(&self, blocks: &mut [u8]) {
+                self.check_keyed();
+                aes_core::decrypt_blocks(blocks, &self.decryption_subkeys);
+            }
+
+            /// **Encrypt** consecutive blocks in place with CBC mode.
+            ///
+            /// `iv` is updated to the last cipher block, so the next call continues the message.
+            ///
+            /// # Panics
+            /// This function panics if the context has no key, or if the length of `blocks` is
+            /// not a multiple of 16.
+            pub fn cbc_encrypt(&self, blocks: &mut [u8], iv: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                self.check_keyed();
+                *iv = inplace::cbc_enc_with(self, blocks, iv);
+            }
+
+            /// **Decrypt** consecutive blocks in place with CBC mode.
+            ///
+            /// `iv` is updated to the last cipher block, so the next call continues the message.
+            ///
+            /// # Panics
+            /// This function panics if the context has no key, or if the length of `blocks` is
+            /// not a multiple of 16.
+            pub fn cbc_decrypt(&self, blocks: &mut [u8], iv: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                self.check_keyed();
+                *iv = inplace::cbc_dec_with(self, blocks, iv);
+            }
+
+            /// **Encrypt** or **decrypt** data in place with CTR mode.
+            ///
+            /// `counter` is a 128-bit big-endian counter, and it is updated to the counter block
+            /// after the last one used. The last block may be incomplete, but then its unused
+            /// keystream is lost, so only the last call of a message may have a length which is
+            /// not a multiple of 16.
+            ///
+            /// # Panics
+            /// This function panics if the context has no key.
+            pub fn ctr_apply(&self, data: &mut [u8], counter: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                self.check_keyed();
+                *counter = inplace::ctr_enc_dec_with(self, data, counter);
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                self.clear();
+                std::hint::black_box(&self.encryption_subkeys);
+                std::hint::black_box(&self.decryption_subkeys);
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl BlockCipher128 for $name {
+            fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                self.check_keyed();
+                $block_encrypt(block, &self.encryption_subkeys);
+            }
+
+            fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+                self.check_keyed();
+                $block_decrypt(block, &self.decryption_subkeys);
+            }
+
+            fn encrypt_blocks(&self, blocks: &mut [u8]) {
+                self.check_keyed();
+                aes_core::encrypt_blocks(blocks, &self.encryption_subkeys);
+            }
+
+            fn decrypt_blocks(&self, blocks: &mut [u8]) {
+                self.check_keyed();
+                aes_core::decrypt_blocks(blocks, &self.decryption_subkeys);
+            }
+        }
+    };
+}
+
+define_aes_context!(
+    /// A heap-free AES context with a **128bit key**, which can be placed in a `static`.
+    Aes128Context,
+    KEY_BYTES_128BIT,
+    N_SUBKEYS_128BIT,
+    aes_core::key_schedule_encrypt_decrypt128,
+    aes_core::block_encrypt128_inplace,
+    aes_core::block_decrypt128_inplace
+);
+define_aes_context!(
+    /// A heap-free AES context with a **192bit key**, which can be placed in a `static`.
+    Aes192Context,
+    KEY_BYTES_192BIT,
+    N_SUBKEYS_192BIT,
+    aes_core::key_schedule_encrypt_decrypt192,
+    aes_core::block_encrypt192_inplace,
+    aes_core::block_decrypt192_inplace
+);
+define_aes_context!(
+    /// A heap-free AES context with a **256bit key**, which can be placed in a `static`.
+    Aes256Context,
+    KEY_BYTES_256BIT,
+    N_SUBKEYS_256BIT,
+    aes_core::key_schedule_encrypt_decrypt256,
+    aes_core::block_encrypt256_inplace,
+    aes_core::block_decrypt256_inplace
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_cipher::Aes256;
+    use crate::aes_with_operation_mode;
+
+    static CONTEXT: std::sync::Mutex<Aes256Context> = std::sync::Mutex::new(Aes256Context::new());
+
+    #[test]
+    fn modes_match_operation_modes() {
+        let key: [u8; KEY_BYTES_256BIT] = core::array::from_fn(|i| (i * 5) as u8);
+        let aes = Aes256::new(&key);
+        let mut context = CONTEXT.lock().unwrap();
+        assert!(!context.is_keyed());
+        context.set_key(&key);
+        assert!(context.is_keyed());
+        let plain: Vec<u8> = (0..64).collect();
+        let iv: [u8; BLOCKSIZE_IN_BYTES] = core::array::from_fn(|i| (i * 9) as u8);
+
+        let mut expected = vec![0u8; 64];
+        aes_with_operation_mode::ecb_enc(&plain, &mut expected, aes.encryption_subkeys());
+        let mut data = plain.clone();
+        context.ecb_encrypt(&mut data);
+        assert_eq!(data, expected);
+        context.ecb_decrypt(&mut data);
+        assert_eq!(data, plain);
+
+        aes_with_operation_mode::cbc_enc(&plain, &mut expected, aes.encryption_subkeys(), &iv);
+        // Two calls continue the same message.
+        let mut chained_iv = iv;
+        data.copy_from_slice(&plain);
+        context.cbc_encrypt(&mut data[..32], &mut chained_iv);
+        context.cbc_encrypt(&mut data[32..], &mut chained_iv);
+        assert_eq!(data, expected);
+        assert_eq!(chained_iv, expected[48..]);
+        chained_iv = iv;
+        context.cbc_decrypt(&mut data[..16], &mut chained_iv);
+        context.cbc_decrypt(&mut data[16..], &mut chained_iv);
+        assert_eq!(data, plain);
+
+        let mut expected = vec![0u8; 60];
+        aes_with_operation_mode::ctr_enc_dec(
+            &plain[..60],
+            &mut expected,
+            aes.encryption_subkeys(),
+            &iv,
+        );
+        let mut counter = iv;
+        let mut data = plain[..60].to_vec();
+        context.ctr_apply(&mut data[..16], &mut counter);
+        context.ctr_apply(&mut data[16..], &mut counter);
+        assert_eq!(data, expected);
+
+        context.clear();
+        assert!(!context.is_keyed());
+    }
+
+    #[test]
+    #[should_panic]
+    fn unkeyed_panic() {
+        Aes128Context::new().ecb_encrypt(&mut [0u8; 16]);
+    }
+}
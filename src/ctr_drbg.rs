@@ -0,0 +1,397 @@
+//! # ctr_drbg
+//! `ctr_drbg` provides CTR_DRBG, the deterministic random bit generator of NIST SP 800-90A built
+//! on AES in counter mode.
+//!
+//! [`CtrDrbg`] is seeded from an entropy source, e.g. the TRNG of a microcontroller, and then
+//! generates any amount of random bytes. It can be used with or without the derivation function:
+//! * Without it ([`CtrDrbg::instantiate`]), the entropy input must be full entropy and exactly
+//!   `seedlen` (the key length plus 16) bytes long.
+//! * With it ([`CtrDrbg::instantiate_with_df`]), the entropy input may be of any length from the
+//!   key length, and a nonce is needed too. Use it when the entropy source is not full entropy.
+//!
+//! The generator implements [`Rng`], so it can generate the IVs and keys of this library.
+//!
+//! **Attention!** The output is only as unpredictable as the entropy input. The generator does
+//! not collect entropy on its own: reseed it from the entropy source with [`CtrDrbg::reseed`],
+//! at the latest when [`CtrDrbg::generate`] returns `Err(AesError::SequenceOverflow)`.
+//!
+//! [`CtrDrbg`]: ../ctr_drbg/struct.CtrDrbg.html
+//! [`CtrDrbg::instantiate`]: ../ctr_drbg/struct.CtrDrbg.html#method.instantiate
+//! [`CtrDrbg::instantiate_with_df`]: ../ctr_drbg/struct.CtrDrbg.html#method.instantiate_with_df
+//! [`CtrDrbg::reseed`]: ../ctr_drbg/struct.CtrDrbg.html#method.reseed
+//! [`CtrDrbg::generate`]: ../ctr_drbg/struct.CtrDrbg.html#method.generate
+//! [`Rng`]: ../rng/trait.Rng.html
+use super::aes_cipher::AesAny;
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::cbc_mac::raw_cbc_mac;
+use super::error::AesError;
+use super::key::KeySize;
+use super::rng::Rng;
+
+/// The number of requests between two reseeds, `reseed_interval` of SP 800-90A.
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+/// The maximum number of bytes per request, `max_number_of_bits_per_request` of SP 800-90A.
+pub const MAX_BYTES_PER_REQUEST: usize = 1 << 16;
+/// The maximum length in bytes of the entropy input, the nonce, the personalization string and
+/// the additional input, when the derivation function is used.
+pub const MAX_INPUT_BYTES: u64 = 1 << 32;
+
+/// An AES CTR_DRBG (SP 800-90A, section 10.2.1).
+/// # Examples
+/// ```
+/// use aes_frast::ctr_drbg::CtrDrbg;
+/// use aes_frast::key::{Key256, KeySize};
+///
+/// // In real use, the entropy input and the nonce come from an entropy source.
+/// let entropy = [0x3Cu8; 32];
+/// let nonce = [0x5Au8; 16];
+/// let mut drbg =
+///     CtrDrbg::instantiate_with_df(KeySize::Aes256, &entropy, &nonce, b"device 42").unwrap();
+///
+/// let mut random = [0u8; 64];
+/// drbg.generate(&mut random, b"").unwrap();
+///
+/// // It is an `Rng` too.
+/// let key = Key256::generate_with(&mut drbg);
+///
+/// drbg.reseed(&[0xC3u8; 32], b"").unwrap();
+/// assert_eq!(drbg.reseed_counter(), 1);
+/// ```
+#[derive(Clone)]
+pub struct CtrDrbg {
+    cipher: AesAny,
+    key_size: KeySize,
+    v: [u8; BLOCKSIZE_IN_BYTES],
+    reseed_counter: u64,
+    derivation_function: bool,
+}
+
+impl CtrDrbg {
+    /// Instantiate the generator **without** the derivation function.
+    ///
+    /// * *parameter* `key_size`: the key size of AES.
+    /// * *parameter* `entropy`: the full-entropy input, exactly `seedlen` bytes (32, 40 or 48).
+    /// * *parameter* `personalization`: the personalization string, at most `seedlen` bytes.
+    /// * *return* : the generator, or `Err(AesError::InvalidParameter)` for wrong lengths.
+    pub fn instantiate(
+        key_size: KeySize,
+        entropy: &[u8],
+        personalization: &[u8],
+    ) -> Result<Self, AesError> {
+        let mut drbg = Self::zero(key_size, false);
+        let seed_material = drbg.seed_material(entropy, &[], personalization)?;
+        drbg.update(&seed_material);
+        Ok(drbg)
+    }
+
+    /// Instantiate the generator **with** the derivation function.
+    ///
+    /// * *parameter* `key_size`: the key size of AES.
+    /// * *parameter* `entropy`: the entropy input, at least the key length.
+    /// * *parameter* `nonce`: the nonce, which should be at least a half of the key length.
+    /// * *parameter* `personalization`: the personalization string, which may be empty.
+    /// * *return* : the generator, or `Err(AesError::InvalidParameter)` for wrong lengths.
+    pub fn instantiate_with_df(
+        key_size: KeySize,
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> Result<Self, AesError> {
+        let mut drbg = Self::zero(key_size, true);
+        let seed_material = drbg.seed_material(entropy, nonce, personalization)?;
+        drbg.update(&seed_material);
+        Ok(drbg)
+    }
+
+    /// Reseed the generator with new entropy.
+    ///
+    /// * *parameter* `entropy`: the entropy input, of the same length as for the instantiation.
+    /// * *parameter* `additional`: the additional input, which may be empty.
+    /// * *return* : `Err(AesError::InvalidParameter)` for wrong lengths, when the state is left
+    ///   untouched.
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(), AesError> {
+        let seed_material = self.seed_material(entropy, &[], additional)?;
+        self.update(&seed_material);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fill `output` with random bytes.
+    ///
+    /// * *parameter* `output`: the buffer, at most 65536 bytes.
+    /// * *parameter* `additional`: the additional input, which may be empty.
+    /// * *return* : `Err(AesError::InvalidDataLength)` for a too long `output`,
+    ///   `Err(AesError::InvalidParameter)` for a too long `additional`, or
+    ///   `Err(AesError::SequenceOverflow)` if the generator must be reseeded first. `output` is
+    ///   left untouched on errors.
+    pub fn generate(&mut self, output: &mut [u8], additional: &[u8]) -> Result<(), AesError> {
+        if output.len() > MAX_BYTES_PER_REQUEST {
+            return Err(AesError::InvalidDataLength);
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(AesError::SequenceOverflow);
+        }
+        let additional = if additional.is_empty() {
+            vec![0u8; self.seed_len()]
+        } else {
+            let additional = self.condition(&[additional])?;
+            self.update(&additional);
+            additional
+        };
+        for chunk in output.chunks_mut(BLOCKSIZE_IN_BYTES) {
+            let block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.update(&additional);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// The number of requests since the last (re)seeding, plus one.
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    /// Whether the derivation function is used.
+    pub fn uses_derivation_function(&self) -> bool {
+        self.derivation_function
+    }
+
+    /// The generator of the zero key and the zero `V`, before the instantiation.
+    fn zero(key_size: KeySize, derivation_function: bool) -> Self {
+        Self {
+            cipher: AesAny::new(&vec![0u8; key_size.bytes()]).unwrap(),
+            key_size,
+            v: [0u8; BLOCKSIZE_IN_BYTES],
+            reseed_counter: 1,
+            derivation_function,
+        }
+    }
+
+    /// `seedlen`, the length in bytes of the key and `V` together.
+    fn seed_len(&self) -> usize {
+        self.key_size.bytes() + BLOCKSIZE_IN_BYTES
+    }
+
+    /// The seed material of an instantiation or a reseed, after checking the entropy input.
+    fn seed_material(
+        &self,
+        entropy: &[u8],
+        nonce: &[u8],
+        string: &[u8],
+    ) -> Result<Vec<u8>, AesError> {
+        let entropy_fits = if self.derivation_function {
+            entropy.len() >= self.key_size.bytes()
+        } else {
+            entropy.len() == self.seed_len()
+        };
+        if !entropy_fits {
+            return Err(AesError::InvalidParameter);
+        }
+        self.condition(&[entropy, nonce, string])
+    }
+
+    /// `seedlen` bytes from `parts`: the derivation of their concatenation, or without the
+    /// derivation function, their XOR, each padded with zeros.
+    fn condition(&self, parts: &[&[u8]]) -> Result<Vec<u8>, AesError> {
+        let seed_len = self.seed_len();
+        if self.derivation_function {
+            if parts
+                .iter()
+                .any(|part| part.len() as u64 >= MAX_INPUT_BYTES)
+            {
+                return Err(AesError::InvalidParameter);
+            }
+            return Ok(self.derive(&parts.concat(), seed_len));
+        }
+        if parts.iter().any(|part| part.len() > seed_len) {
+            return Err(AesError::InvalidParameter);
+        }
+        let mut seed_material = vec![0u8; seed_len];
+        for part in parts {
+            for (s, p) in seed_material.iter_mut().zip(part.iter()) {
+                *s ^= p;
+            }
+        }
+        Ok(seed_material)
+    }
+
+    fn next_block(&mut self) -> [u8; BLOCKSIZE_IN_BYTES] {
+        self.v = u128::from_be_bytes(self.v).wrapping_add(1).to_be_bytes();
+        let mut block = self.v;
+        self.cipher.encrypt_block(&mut block);
+        block
+    }
+
+    /// `CTR_DRBG_Update`: a new key and `V` from the keystream XOR `provided_data`.
+    fn update(&mut self, provided_data: &[u8]) {
+        let mut temp = Vec::new();
+        while temp.len() < self.seed_len() {
+            temp.extend_from_slice(&self.next_block());
+        }
+        for (t, p) in temp.iter_mut().zip(provided_data.iter()) {
+            *t ^= p;
+        }
+        let (key_len, seed_len) = (self.key_size.bytes(), self.seed_len());
+        self.cipher = AesAny::new(&temp[..key_len]).unwrap();
+        self.v.copy_from_slice(&temp[key_len..seed_len]);
+        temp.fill(0);
+    }
+
+    /// `Block_Cipher_df`: `length` bytes derived from `input`, by BCC (the raw CBC-MAC) under a
+    /// fixed key, and then by encryption in chain.
+    fn derive(&self, input: &[u8], length: usize) -> Vec<u8> {
+        let key_len = self.key_size.bytes();
+        // Room for the IV block of BCC, then `L || N || input || 0x80`, padded with zeros.
+        let mut s = vec![0u8; BLOCKSIZE_IN_BYTES];
+        s.extend_from_slice(&(input.len() as u32).to_be_bytes());
+        s.extend_from_slice(&(length as u32).to_be_bytes());
+        s.extend_from_slice(input);
+        s.push(0x80);
+        s.resize(s.len().div_ceil(BLOCKSIZE_IN_BYTES) * BLOCKSIZE_IN_BYTES, 0);
+
+        let bcc_key: Vec<u8> = (0..key_len as u8).collect();
+        let bcc_cipher = AesAny::new(&bcc_key).unwrap();
+        let mut temp = Vec::new();
+        let mut i = 0u32;
+        while temp.len() < key_len + BLOCKSIZE_IN_BYTES {
+            s[..4].copy_from_slice(&i.to_be_bytes());
+            temp.extend_from_slice(raw_cbc_mac(&bcc_cipher, &s).unwrap().as_bytes());
+            i += 1;
+        }
+        s.fill(0);
+
+        let cipher = AesAny::new(&temp[..key_len]).unwrap();
+        let mut x = [0u8; BLOCKSIZE_IN_BYTES];
+        x.copy_from_slice(&temp[key_len..(key_len + BLOCKSIZE_IN_BYTES)]);
+        temp.fill(0);
+        let mut output = Vec::new();
+        while output.len() < length {
+            cipher.encrypt_block(&mut x);
+            output.extend_from_slice(&x);
+        }
+        output.truncate(length);
+        output
+    }
+}
+
+impl Rng for CtrDrbg {
+    /// Fill `dest` with random bytes, in requests of at most 65536 bytes.
+    /// # Panics
+    /// This function panics if the generator must be reseeded.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(MAX_BYTES_PER_REQUEST) {
+            self.generate(chunk, &[])
+                .expect("The CTR_DRBG must be reseeded.");
+        }
+    }
+}
+
+impl_redacted_debug!(CtrDrbg);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    fn generate(drbg: &mut CtrDrbg, len: usize, additional: &[u8]) -> String {
+        let mut output = vec![0u8; len];
+        drbg.generate(&mut output, additional).unwrap();
+        hex(&output)
+    }
+
+    // The expected values came from the CTR-DRBG of OpenSSL, fed by its deterministic
+    // `TEST-RAND` generator.
+    #[test]
+    fn aes_128_without_df() {
+        let entropy: Vec<u8> = (0..32).collect();
+        let mut drbg = CtrDrbg::instantiate(KeySize::Aes128, &entropy, b"personal").unwrap();
+        assert!(!drbg.uses_derivation_function());
+        assert_eq!(
+            generate(&mut drbg, 40, b""),
+            "688926e9efa4528cb935b04aec4a5133a77f1085f20ba84d35315aa4a280176c041ca1cc9e2897cf"
+        );
+        assert_eq!(
+            generate(&mut drbg, 20, b"extra!!"),
+            "b241d370ffa654baeb9ff76e63d30b6842440614"
+        );
+        assert_eq!(drbg.reseed_counter(), 3);
+        let entropy: Vec<u8> = (100..132).collect();
+        drbg.reseed(&entropy, b"again").unwrap();
+        assert_eq!(
+            generate(&mut drbg, 64, b""),
+            "ac373d90745e7083fa9c6e411462c3c3b1a1cf6f0c51ba50da1c8f0ae6b3a05f\
+             7b60bb7581f694a9ad33d942c197d25663e09d6f59a2d06a794aef7109407ed3"
+        );
+    }
+
+    #[test]
+    fn aes_256_with_df() {
+        let entropy: Vec<u8> = (0x20..0x40).collect();
+        let nonce: Vec<u8> = (0x80..0x90).collect();
+        let mut drbg =
+            CtrDrbg::instantiate_with_df(KeySize::Aes256, &entropy, &nonce, b"device 42").unwrap();
+        assert!(drbg.uses_derivation_function());
+        assert_eq!(
+            generate(&mut drbg, 64, b""),
+            "30de5196ea410b9f2e4f25036ceff3459c0f273dc95966d481bab11ae7ffd8db\
+             f273ab45d03f481ec7907039252bf78c52560a01f57e7b5aaf159d8ad1314800"
+        );
+        assert_eq!(
+            generate(&mut drbg, 33, b"more input"),
+            "c4dad90a49f811d6db47d2127184636930bcc05a8c0d1c65fa6b975109864c2f66"
+        );
+        let entropy: Vec<u8> = (0x40..0x60).collect();
+        drbg.reseed(&entropy, b"re").unwrap();
+        assert_eq!(
+            generate(&mut drbg, 16, b""),
+            "7f98e580cbd874a350830f2c61ca1bf7"
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert_eq!(
+            CtrDrbg::instantiate(KeySize::Aes192, &[0u8; 39], b"").err(),
+            Some(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            CtrDrbg::instantiate(KeySize::Aes128, &[0u8; 32], &[0u8; 33]).err(),
+            Some(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            CtrDrbg::instantiate_with_df(KeySize::Aes256, &[0u8; 31], &[0u8; 16], b"").err(),
+            Some(AesError::InvalidParameter)
+        );
+        let mut drbg = CtrDrbg::instantiate(KeySize::Aes128, &[0u8; 32], b"").unwrap();
+        let mut output = vec![0u8; MAX_BYTES_PER_REQUEST + 1];
+        assert_eq!(
+            drbg.generate(&mut output, b""),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            drbg.generate(&mut output[..16], &[0u8; 33]),
+            Err(AesError::InvalidParameter)
+        );
+        drbg.reseed_counter = RESEED_INTERVAL + 1;
+        assert_eq!(
+            drbg.generate(&mut output[..16], b""),
+            Err(AesError::SequenceOverflow)
+        );
+        drbg.reseed(&[1u8; 32], b"").unwrap();
+        assert!(drbg.generate(&mut output[..16], b"").is_ok());
+    }
+
+    #[test]
+    fn rng_matches_generate() {
+        let mut a = CtrDrbg::instantiate(KeySize::Aes128, &[7u8; 32], b"").unwrap();
+        let mut b = a.clone();
+        let mut from_rng = vec![0u8; MAX_BYTES_PER_REQUEST + 100];
+        a.fill_bytes(&mut from_rng);
+        let mut from_generate = vec![0u8; MAX_BYTES_PER_REQUEST + 100];
+        let (first, second) = from_generate.split_at_mut(MAX_BYTES_PER_REQUEST);
+        b.generate(first, b"").unwrap();
+        b.generate(second, b"").unwrap();
+        assert_eq!(from_rng, from_generate);
+    }
+}
@@ -0,0 +1,264 @@
+//! # digest
+//! `digest` provides MD5 and SHA-256 for the key derivation of the password-based formats, such
+//! as `EVP_BytesToKey` of OpenSSL. They are not part of the public API: this is an AES library.
+//!
+//! MD5 is broken for collision resistance, and is only here because OpenSSL 1.0 and earlier
+//! derived keys with it.
+
+/// The length in bytes of an MD5 digest.
+pub(crate) const MD5_BYTES: usize = 16;
+/// The length in bytes of a SHA-256 digest.
+pub(crate) const SHA256_BYTES: usize = 32;
+/// The length in bytes of the blocks of MD5 and SHA-256.
+pub(crate) const DIGEST_BLOCK_BYTES: usize = 64;
+
+/// Split a message into 64-byte blocks, with the Merkle–Damgård padding of MD5 and SHA-256 (the
+/// bit length in little-endian for MD5, in big-endian for SHA-256), and feed them to `compress`.
+fn padded_blocks(
+    tail: &[u8],
+    total_len: u64,
+    big_endian: bool,
+    mut compress: impl FnMut(&[u8; DIGEST_BLOCK_BYTES]),
+) {
+    let mut block = [0u8; 2 * DIGEST_BLOCK_BYTES];
+    block[..tail.len()].copy_from_slice(tail);
+    block[tail.len()] = 0x80;
+    let blocks = if tail.len() < DIGEST_BLOCK_BYTES - 8 {
+        1
+    } else {
+        2
+    };
+    let bits = total_len.wrapping_mul(8);
+    let end = blocks * DIGEST_BLOCK_BYTES;
+    block[(end - 8)..end].copy_from_slice(&if big_endian {
+        bits.to_be_bytes()
+    } else {
+        bits.to_le_bytes()
+    });
+    for chunk in block[..end].chunks_exact(DIGEST_BLOCK_BYTES) {
+        compress(chunk.try_into().unwrap());
+    }
+}
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_K: [u32; 64] = [
+    0xD76AA478, 0xE8C7B756, 0x242070DB, 0xC1BDCEEE, 0xF57C0FAF, 0x4787C62A, 0xA8304613, 0xFD469501,
+    0x698098D8, 0x8B44F7AF, 0xFFFF5BB1, 0x895CD7BE, 0x6B901122, 0xFD987193, 0xA679438E, 0x49B40821,
+    0xF61E2562, 0xC040B340, 0x265E5A51, 0xE9B6C7AA, 0xD62F105D, 0x02441453, 0xD8A1E681, 0xE7D3FBC8,
+    0x21E1CDE6, 0xC33707D6, 0xF4D50D87, 0x455A14ED, 0xA9E3E905, 0xFCEFA3F8, 0x676F02D9, 0x8D2A4C8A,
+    0xFFFA3942, 0x8771F681, 0x6D9D6122, 0xFDE5380C, 0xA4BEEA44, 0x4BDECFA9, 0xF6BB4B60, 0xBEBFBC70,
+    0x289B7EC6, 0xEAA127FA, 0xD4EF3085, 0x04881D05, 0xD9D4D039, 0xE6DB99E5, 0x1FA27CF8, 0xC4AC5665,
+    0xF4292244, 0x432AFF97, 0xAB9423A7, 0xFC93A039, 0x655B59C3, 0x8F0CCC92, 0xFFEFF47D, 0x85845DD1,
+    0x6FA87E4F, 0xFE2CE6E0, 0xA3014314, 0x4E0811A1, 0xF7537E82, 0xBD3AF235, 0x2AD7D2BB, 0xEB86D391,
+];
+
+fn md5_compress(state: &mut [u32; 4], block: &[u8; DIGEST_BLOCK_BYTES]) {
+    let mut m = [0u32; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a
+            .wrapping_add(f)
+            .wrapping_add(MD5_K[i])
+            .wrapping_add(m[g])
+            .rotate_left(MD5_SHIFTS[(i / 16) * 4 + i % 4]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(rotated);
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+/// The MD5 digest (RFC 1321) of the concatenation of `parts`.
+pub(crate) fn md5(parts: &[&[u8]]) -> [u8; MD5_BYTES] {
+    let mut state: [u32; 4] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476];
+    let message = parts.concat();
+    let mut blocks = message.chunks_exact(DIGEST_BLOCK_BYTES);
+    for block in &mut blocks {
+        md5_compress(&mut state, block.try_into().unwrap());
+    }
+    padded_blocks(blocks.remainder(), message.len() as u64, false, |block| {
+        md5_compress(&mut state, block)
+    });
+    let mut digest = [0u8; MD5_BYTES];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428A2F98, 0x71374491, 0xB5C0FBCF, 0xE9B5DBA5, 0x3956C25B, 0x59F111F1, 0x923F82A4, 0xAB1C5ED5,
+    0xD807AA98, 0x12835B01, 0x243185BE, 0x550C7DC3, 0x72BE5D74, 0x80DEB1FE, 0x9BDC06A7, 0xC19BF174,
+    0xE49B69C1, 0xEFBE4786, 0x0FC19DC6, 0x240CA1CC, 0x2DE92C6F, 0x4A7484AA, 0x5CB0A9DC, 0x76F988DA,
+    0x983E5152, 0xA831C66D, 0xB00327C8, 0xBF597FC7, 0xC6E00BF3, 0xD5A79147, 0x06CA6351, 0x14292967,
+    0x27B70A85, 0x2E1B2138, 0x4D2C6DFC, 0x53380D13, 0x650A7354, 0x766A0ABB, 0x81C2C92E, 0x92722C85,
+    0xA2BFE8A1, 0xA81A664B, 0xC24B8B70, 0xC76C51A3, 0xD192E819, 0xD6990624, 0xF40E3585, 0x106AA070,
+    0x19A4C116, 0x1E376C08, 0x2748774C, 0x34B0BCB5, 0x391C0CB3, 0x4ED8AA4A, 0x5B9CCA4F, 0x682E6FF3,
+    0x748F82EE, 0x78A5636F, 0x84C87814, 0x8CC70208, 0x90BEFFFA, 0xA4506CEB, 0xBEF9A3F7, 0xC67178F2,
+];
+
+/// SHA-256 (FIPS 180-4), fed piece by piece. It is `Clone`, so the state after a common prefix
+/// (such as the padded key of HMAC) can be reused.
+#[derive(Clone)]
+pub(crate) struct Sha256 {
+    state: [u32; 8],
+    buffer: [u8; DIGEST_BLOCK_BYTES],
+    buffered: usize,
+    total_len: u64,
+}
+
+impl Sha256 {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: [
+                0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
+                0x5BE0CD19,
+            ],
+            buffer: [0u8; DIGEST_BLOCK_BYTES],
+            buffered: 0,
+            total_len: 0,
+        }
+    }
+
+    fn compress(state: &mut [u32; 8], block: &[u8; DIGEST_BLOCK_BYTES]) {
+        let mut w = [0u32; 64];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(data.len() as u64);
+        if self.buffered != 0 {
+            let take = data.len().min(DIGEST_BLOCK_BYTES - self.buffered);
+            self.buffer[self.buffered..(self.buffered + take)].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+            if self.buffered < DIGEST_BLOCK_BYTES {
+                return;
+            }
+            Self::compress(&mut self.state, &self.buffer);
+            self.buffered = 0;
+        }
+        let mut blocks = data.chunks_exact(DIGEST_BLOCK_BYTES);
+        for block in &mut blocks {
+            Self::compress(&mut self.state, block.try_into().unwrap());
+        }
+        let tail = blocks.remainder();
+        self.buffer[..tail.len()].copy_from_slice(tail);
+        self.buffered = tail.len();
+    }
+
+    pub(crate) fn finalize(mut self) -> [u8; SHA256_BYTES] {
+        let state = &mut self.state;
+        padded_blocks(
+            &self.buffer[..self.buffered],
+            self.total_len,
+            true,
+            |block| Self::compress(state, block),
+        );
+        let mut digest = [0u8; SHA256_BYTES];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state.iter()) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
+
+/// The SHA-256 digest of the concatenation of `parts`.
+pub(crate) fn sha256(parts: &[&[u8]]) -> [u8; SHA256_BYTES] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    #[test]
+    fn md5_works() {
+        // These examples came from RFC 1321 appendix A.5
+        assert_eq!(hex(&md5(&[b""])), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            hex(&md5(&[b"a", b"bc"])),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hex(&md5(&[
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ])),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn sha256_works() {
+        // These examples came from NIST's examples of FIPS 180
+        assert_eq!(
+            hex(&sha256(&[b"abc"])),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(&sha256(&[
+                b"abcdbcdecdefdefgefghfghighijhij",
+                b"kijkljklmklmnlmnomnopnopq"
+            ])),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        // Fed byte by byte, across the block boundaries.
+        let mut hasher = Sha256::new();
+        for _ in 0..1000 {
+            hasher.update(b"a");
+        }
+        assert_eq!(hasher.finalize(), sha256(&[&[b'a'; 1000]]));
+    }
+}
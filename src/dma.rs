@@ -0,0 +1,359 @@
+//! # dma
+//! `dma` provides operation modes on caller-provided buffers, for output which is handed
+//! directly to a DMA engine.
+//!
+//! The functions here never allocate and never write outside the output slice they are given:
+//! * They process **complete blocks only**, and return the exact number of bytes processed. The
+//!   bytes after that in both buffers are untouched, and an incomplete tail is left to the
+//!   caller, e.g. to be padded into a block of its own. The only exception is
+//!   [`ctr_apply_final`], which processes the incomplete last block of a CTR message.
+//! * An output slice which is too short is an error, not a panic, and nothing is written then.
+//! * The IV or counter is updated in place, so a message can be processed in several calls.
+//!
+//! DMA engines and cache maintenance often need buffers aligned to a cache line. [`align_offset`]
+//! and [`aligned_slice_mut`] find an aligned region in a buffer, and [`CacheAligned`] is a buffer
+//! which is aligned to 64 bytes.
+//!
+//! [`ctr_apply_final`]: ../dma/fn.ctr_apply_final.html
+//! [`align_offset`]: ../dma/fn.align_offset.html
+//! [`aligned_slice_mut`]: ../dma/fn.aligned_slice_mut.html
+//! [`CacheAligned`]: ../dma/struct.CacheAligned.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode_inplace as inplace;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use std::ops::{Deref, DerefMut};
+
+/// A buffer of `N` bytes aligned to 64 bytes, the cache line size of most CPUs.
+/// # Examples
+/// ```
+/// use aes_frast::dma::CacheAligned;
+///
+/// let buffer = CacheAligned::<64>::new();
+/// assert_eq!(buffer.as_ptr() as usize % 64, 0);
+/// assert_eq!(buffer.len(), 64);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C, align(64))]
+pub struct CacheAligned<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> CacheAligned<N> {
+    /// Create a buffer of zeros.
+    pub const fn new() -> Self {
+        Self([0u8; N])
+    }
+}
+
+impl<const N: usize> Default for CacheAligned<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for CacheAligned<N> {
+    type Target = [u8; N];
+    fn deref(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for CacheAligned<N> {
+    fn deref_mut(&mut self) -> &mut [u8; N] {
+        &mut self.0
+    }
+}
+
+/// Count the bytes to skip from the start of `buffer` to reach an address aligned to `align`.
+///
+/// * *parameter* `buffer`: the buffer.
+/// * *parameter* `align`: the alignment in bytes, which must be a power of two.
+/// * *return* : the number of bytes to skip, less than `align`. It may be greater than the length
+///   of `buffer`.
+/// # Panics
+/// This function panics if `align` is not a power of two.
+pub fn align_offset(buffer: &[u8], align: usize) -> usize {
+    assert!(align.is_power_of_two(), "Alignment must be a power of two.");
+    (align - (buffer.as_ptr() as usize & (align - 1))) & (align - 1)
+}
+
+/// Find the region of `len` bytes in `buffer` which starts at the first address aligned to
+/// `align`.
+///
+/// * *parameter* `buffer`: the buffer, which should have `align - 1` spare bytes.
+/// * *parameter* `align`: the alignment in bytes, which must be a power of two.
+/// * *parameter* `len`: the length of the region.
+/// * *return* : the region, or `None` if `buffer` is too short to hold it.
+/// # Panics
+/// This function panics if `align` is not a power of two.
+/// # Examples
+/// ```
+/// use aes_frast::dma::aligned_slice_mut;
+///
+/// let mut storage = vec![0u8; 64 + 31];
+/// let region = aligned_slice_mut(&mut storage, 32, 64).unwrap();
+/// assert_eq!(region.as_ptr() as usize % 32, 0);
+/// assert_eq!(region.len(), 64);
+/// ```
+pub fn aligned_slice_mut(buffer: &mut [u8], align: usize, len: usize) -> Option<&mut [u8]> {
+    let offset = align_offset(buffer, align);
+    buffer.get_mut(offset..offset.checked_add(len)?)
+}
+
+/// Copy the complete blocks of `input` into `output`, to be processed there in place by the
+/// modes of `aes_with_operation_mode_inplace`, and return them.
+fn copy_complete_blocks<'a>(input: &[u8], output: &'a mut [u8]) -> Result<&'a mut [u8], AesError> {
+    let length = input.len() & !0b1111;
+    copy_input(&input[..length], output)
+}
+
+/// Copy `input` into `output` and return the copy.
+fn copy_input<'a>(input: &[u8], output: &'a mut [u8]) -> Result<&'a mut [u8], AesError> {
+    let output = output
+        .get_mut(..input.len())
+        .ok_or(AesError::InvalidDataLength)?;
+    output.copy_from_slice(input);
+    Ok(output)
+}
+
+/// ECB (Electronic Codebook) Encryption of the complete blocks of `input` into `output`.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `input`: the plain.
+/// * *parameter* `output`: the buffer for the cipher.
+/// * *return* : the number of bytes processed, a multiple of 16. If `output` is shorter than
+///   that, `Err(AesError::InvalidDataLength)`.
+pub fn ecb_encrypt<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+) -> Result<usize, AesError> {
+    let blocks = copy_complete_blocks(input, output)?;
+    cipher.encrypt_blocks(blocks);
+    Ok(blocks.len())
+}
+
+/// ECB (Electronic Codebook) Decryption of the complete blocks of `input` into `output`.
+///
+/// The parameters and return value are the same as [`ecb_encrypt`].
+///
+/// [`ecb_encrypt`]: ../dma/fn.ecb_encrypt.html
+pub fn ecb_decrypt<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+) -> Result<usize, AesError> {
+    let blocks = copy_complete_blocks(input, output)?;
+    cipher.decrypt_blocks(blocks);
+    Ok(blocks.len())
+}
+
+/// CBC (Cipher Block Chaining) Encryption of the complete blocks of `input` into `output`.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `input`: the plain.
+/// * *parameter* `output`: the buffer for the cipher.
+/// * *parameter* `iv`: the IV, which is updated to the last cipher block for the next call.
+/// * *return* : the number of bytes processed, a multiple of 16. If `output` is shorter than
+///   that, `Err(AesError::InvalidDataLength)`.
+/// # Examples
+/// ```
+/// use aes_frast::dma::{cbc_encrypt, CacheAligned};
+/// use aes_frast::padding_128bit::pa_pkcs7;
+/// use aes_frast::Aes128;
+///
+/// let aes = Aes128::new(&[0x2Bu8; 16]);
+/// let mut iv = [0u8; 16];
+/// let mut output = CacheAligned::<64>::new();
+///
+/// // 40 bytes: 2 complete blocks, and a tail of 8 bytes which is left to the caller.
+/// let message = [0x5Au8; 40];
+/// let processed = cbc_encrypt(&aes, &message, &mut output[..], &mut iv).unwrap();
+/// assert_eq!(processed, 32);
+///
+/// // Pad the tail into a block of its own explicitly.
+/// let mut tail = message[processed..].to_vec();
+/// pa_pkcs7(&mut tail);
+/// let processed = cbc_encrypt(&aes, &tail, &mut output[32..], &mut iv).unwrap();
+/// assert_eq!(processed, 16);
+/// assert_eq!(iv, output[32..48]);
+/// ```
+pub fn cbc_encrypt<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+    iv: &mut [u8; BLOCKSIZE_IN_BYTES],
+) -> Result<usize, AesError> {
+    let blocks = copy_complete_blocks(input, output)?;
+    *iv = inplace::cbc_enc_with(cipher, blocks, iv);
+    Ok(blocks.len())
+}
+
+/// CBC (Cipher Block Chaining) Decryption of the complete blocks of `input` into `output`.
+///
+/// The parameters and return value are the same as [`cbc_encrypt`].
+///
+/// [`cbc_encrypt`]: ../dma/fn.cbc_encrypt.html
+pub fn cbc_decrypt<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+    iv: &mut [u8; BLOCKSIZE_IN_BYTES],
+) -> Result<usize, AesError> {
+    let blocks = copy_complete_blocks(input, output)?;
+    *iv = inplace::cbc_dec_with(cipher, blocks, iv);
+    Ok(blocks.len())
+}
+
+/// CTR (Counter) Encryption and Decryption of the complete blocks of `input` into `output`.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `input`: the plain or the cipher.
+/// * *parameter* `output`: the buffer for the output.
+/// * *parameter* `counter`: the 128-bit big-endian counter block, which is updated to the next
+///   unused counter block for the next call.
+/// * *return* : the number of bytes processed, a multiple of 16. If `output` is shorter than
+///   that, `Err(AesError::InvalidDataLength)`.
+///
+/// Process the incomplete last block of a message with [`ctr_apply_final`].
+///
+/// [`ctr_apply_final`]: ../dma/fn.ctr_apply_final.html
+pub fn ctr_apply<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+    counter: &mut [u8; BLOCKSIZE_IN_BYTES],
+) -> Result<usize, AesError> {
+    let blocks = copy_complete_blocks(input, output)?;
+    *counter = inplace::ctr_enc_dec_with(cipher, blocks, counter);
+    Ok(blocks.len())
+}
+
+/// CTR (Counter) Encryption and Decryption of the **whole** `input` into `output`, including an
+/// incomplete last block.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `input`: the plain or the cipher.
+/// * *parameter* `output`: the buffer for the output.
+/// * *parameter* `counter`: the 128-bit big-endian counter block. It is updated after every
+///   block, and the incomplete last block uses up a counter block too.
+/// * *return* : the number of bytes processed, which is the length of `input`. If `output` is
+///   shorter than that, `Err(AesError::InvalidDataLength)`.
+/// # Examples
+/// ```
+/// use aes_frast::dma::{ctr_apply, ctr_apply_final};
+/// use aes_frast::Aes128;
+///
+/// let aes = Aes128::new(&[0x2Bu8; 16]);
+/// let message = [0x5Au8; 40];
+/// let mut output = [0u8; 40];
+/// let mut counter = [0u8; 16];
+///
+/// let processed = ctr_apply(&aes, &message, &mut output, &mut counter).unwrap();
+/// assert_eq!(processed, 32);
+/// let tail = ctr_apply_final(&aes, &message[32..], &mut output[32..], &mut counter).unwrap();
+/// assert_eq!(tail, 8);
+/// assert_eq!(counter[15], 3);
+/// ```
+pub fn ctr_apply_final<C: BlockCipher128 + ?Sized>(
+    cipher: &C,
+    input: &[u8],
+    output: &mut [u8],
+    counter: &mut [u8; BLOCKSIZE_IN_BYTES],
+) -> Result<usize, AesError> {
+    let data = copy_input(input, output)?;
+    *counter = inplace::ctr_enc_dec_with(cipher, data, counter);
+    Ok(data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_cipher::Aes192;
+    use crate::aes_with_operation_mode::{cbc_enc, ctr_enc_dec, ecb_enc};
+
+    #[test]
+    fn modes_match_operation_modes() {
+        let aes = Aes192::new(&core::array::from_fn(|i| (i * 11) as u8));
+        let keys = aes.encryption_subkeys();
+        let plain: Vec<u8> = (0..70).collect();
+        let iv: [u8; BLOCKSIZE_IN_BYTES] = core::array::from_fn(|i| (0xF0 + i) as u8);
+        let mut expected = vec![0u8; 64];
+        // The tail (and the spare byte of output) must be left untouched.
+        let mut output = vec![0xEEu8; 71];
+
+        ecb_enc(&plain[..64], &mut expected, keys);
+        assert_eq!(ecb_encrypt(&aes, &plain, &mut output), Ok(64));
+        assert_eq!(output[..64], expected);
+        assert_eq!(output[64..], [0xEEu8; 7]);
+        let mut decrypted = vec![0u8; 64];
+        assert_eq!(ecb_decrypt(&aes, &output[..64], &mut decrypted), Ok(64));
+        assert_eq!(decrypted, plain[..64]);
+
+        cbc_enc(&plain[..64], &mut expected, keys, &iv);
+        let mut chained_iv = iv;
+        assert_eq!(
+            cbc_encrypt(&aes, &plain, &mut output, &mut chained_iv),
+            Ok(64)
+        );
+        assert_eq!(output[..64], expected);
+        assert_eq!(chained_iv, expected[48..]);
+        chained_iv = iv;
+        assert_eq!(
+            cbc_decrypt(&aes, &output[..20], &mut decrypted, &mut chained_iv),
+            Ok(16)
+        );
+        assert_eq!(
+            cbc_decrypt(&aes, &output[16..64], &mut decrypted[16..], &mut chained_iv),
+            Ok(48)
+        );
+        assert_eq!(decrypted, plain[..64]);
+
+        let mut expected = vec![0u8; 70];
+        ctr_enc_dec(&plain, &mut expected, keys, &iv);
+        let mut counter = iv;
+        assert_eq!(ctr_apply(&aes, &plain, &mut output, &mut counter), Ok(64));
+        assert_eq!(output[64..], [0xEEu8; 7]);
+        assert_eq!(
+            ctr_apply_final(&aes, &plain[64..], &mut output[64..], &mut counter),
+            Ok(6)
+        );
+        assert_eq!(output[..70], expected);
+        assert_eq!(output[70], 0xEE);
+    }
+
+    #[test]
+    fn short_output_fails() {
+        let aes = Aes192::new(&[0u8; 24]);
+        let mut output = [0xEEu8; 31];
+        let mut iv = [0u8; BLOCKSIZE_IN_BYTES];
+        assert_eq!(
+            ecb_encrypt(&aes, &[0u8; 32], &mut output),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            cbc_encrypt(&aes, &[0u8; 32], &mut output, &mut iv),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            ctr_apply_final(&aes, &[0u8; 32], &mut output, &mut iv),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(output, [0xEEu8; 31]);
+        assert_eq!(iv, [0u8; BLOCKSIZE_IN_BYTES]);
+    }
+
+    #[test]
+    fn alignment_works() {
+        let mut storage = [0u8; 200];
+        for align in [1, 2, 16, 32, 64] {
+            let offset = align_offset(&storage[3..], align);
+            assert!(offset < align);
+            assert_eq!((storage[3..].as_ptr() as usize + offset) % align, 0);
+            let region = aligned_slice_mut(&mut storage[3..], align, 100).unwrap();
+            assert_eq!(region.as_ptr() as usize % align, 0);
+            assert_eq!(region.len(), 100);
+        }
+        assert!(aligned_slice_mut(&mut storage[..10], 1, 11).is_none());
+        assert_eq!(std::mem::align_of::<CacheAligned<16>>(), 64);
+    }
+}
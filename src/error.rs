@@ -0,0 +1,42 @@
+//! # error
+//! `error` contains the error type of the fallible functions in this library.
+use std::fmt;
+
+/// The error returned by the fallible functions in this library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AesError {
+    /// The authentication tag (or MAC) does not match the expected one.
+    TagMismatch,
+    /// The key is not 16, 24 or 32 bytes long.
+    InvalidKeyLength,
+    /// The length of the data is not acceptable, e.g. not a multiple of the block size.
+    InvalidDataLength,
+    /// The padding of the decrypted data is malformed.
+    InvalidPadding,
+    /// A parameter (such as an IV or a counter size) is malformed.
+    InvalidParameter,
+    /// The requested algorithm or mechanism is not supported by this library.
+    Unsupported,
+    /// A sequence number (or counter) has reached its limit and would repeat.
+    SequenceOverflow,
+    /// The operation was cancelled through a `CancelToken`.
+    Cancelled,
+}
+
+impl fmt::Display for AesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AesError::TagMismatch => write!(f, "authentication tag mismatch"),
+            AesError::InvalidKeyLength => write!(f, "invalid key length"),
+            AesError::InvalidDataLength => write!(f, "invalid data length"),
+            AesError::InvalidPadding => write!(f, "invalid padding"),
+            AesError::InvalidParameter => write!(f, "invalid parameter"),
+            AesError::Unsupported => write!(f, "unsupported algorithm or mechanism"),
+            AesError::SequenceOverflow => write!(f, "sequence number overflow"),
+            AesError::Cancelled => write!(f, "operation cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for AesError {}
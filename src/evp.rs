@@ -0,0 +1,346 @@
+//! # evp
+//! `evp` provides one-shot functions named after the ciphers of OpenSSL (`EVP_aes_128_cbc` and
+//! so on), to make porting C code easier.
+//!
+//! Each function takes `(key, iv, data)`, and does the same as the OpenSSL cipher with the
+//! default settings:
+//! * ECB and CBC use PKCS #7 padding. So their decryption checks the padding, and returns
+//!   `Err(AesError::InvalidPadding)` on failure.
+//! * CFB (`cfb` is CFB-128, `cfb8` is CFB-8), OFB and CTR don't need padding.
+//! * CTR increases the whole 16-byte IV as a big-endian counter.
+//!
+//! They are thin wrappers over the functions in `aes_with_operation_mode`, which also support
+//! processing the data in pieces.
+//! # Examples
+//! ```
+//! use aes_frast::evp::{aes_128_cbc_decrypt, aes_128_cbc_encrypt};
+//!
+//! // $ printf 'The quick brown fox jumps over the lazy dog' | openssl enc -aes-128-cbc \
+//! //     -K 2b7e151628aed2a6abf7158809cf4f3c -iv 000102030405060708090a0b0c0d0e0f
+//! let key: [u8; 16] = [0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6,
+//!                      0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F, 0x3C];
+//! let iv: [u8; 16] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+//!                     0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F];
+//! let plain = b"The quick brown fox jumps over the lazy dog";
+//!
+//! let cipher = aes_128_cbc_encrypt(&key, &iv, plain);
+//! assert_eq!(cipher, vec![0xBD, 0x13, 0x20, 0x4F, 0x67, 0xD8, 0x16, 0x7F,
+//!                         0x20, 0x21, 0x1C, 0x99, 0xB0, 0xA7, 0xCC, 0x05,
+//!                         0x06, 0xD5, 0xC7, 0x03, 0xEA, 0xFB, 0x01, 0xA7,
+//!                         0xD0, 0x47, 0x3B, 0x5C, 0xC9, 0x99, 0xAA, 0xA2,
+//!                         0x4D, 0xC3, 0x16, 0xCA, 0x58, 0x05, 0x92, 0xEE,
+//!                         0x00, 0x01, 0xDF, 0x0B, 0xDB, 0xF4, 0xD3, 0x3A]);
+//!
+//! let decrypted = aes_128_cbc_decrypt(&key, &iv, &cipher).unwrap();
+//! assert_eq!(decrypted, plain);
+//! ```
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+use super::aes_core::{KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::aes_with_operation_mode as mode;
+use super::error::AesError;
+use super::padding_128bit;
+
+type Iv = [u8; BLOCKSIZE_IN_BYTES];
+type ModeFunction = fn(&[u8], &mut [u8], &[u32], &[u8]) -> Vec<u8>;
+
+/// Run a mode function which does not need padding, on a fresh output buffer.
+fn unpadded(data: &[u8], keys: &[u32], iv: &Iv, function: ModeFunction) -> Vec<u8> {
+    let mut output = vec![0u8; data.len()];
+    if !data.is_empty() {
+        function(data, &mut output, keys, iv);
+    }
+    output
+}
+
+/// Remove the PKCS #7 padding after checking it.
+fn depad(mut data: Vec<u8>) -> Result<Vec<u8>, AesError> {
+    padding_128bit::check_pkcs7(&data)?;
+    padding_128bit::de_ansix923_pkcs7(&mut data);
+    Ok(data)
+}
+
+fn check_complete_blocks(data: &[u8]) -> Result<(), AesError> {
+    if data.is_empty() || data.len() & 0b1111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(())
+}
+
+macro_rules! define_evp_functions {
+    (
+        $name:literal, $key_bytes:expr, $n_subkeys:expr,
+        $schedule_encrypt:path, $schedule_decrypt:path,
+        $ecb_encrypt:ident, $ecb_decrypt:ident,
+        $cbc_encrypt:ident, $cbc_decrypt:ident, $cbc_encrypt_with_random_iv:ident,
+        $cfb_encrypt:ident, $cfb_decrypt:ident,
+        $cfb8_encrypt:ident, $cfb8_decrypt:ident,
+        $ofb:ident, $ctr:ident
+    ) => {
+        #[doc = concat!("`", $name, "-ecb` encryption with PKCS #7 padding.")]
+        ///
+        /// The `iv` parameter is ignored, as in OpenSSL.
+        pub fn $ecb_encrypt(key: &[u8; $key_bytes], _iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            let mut padded = data.to_vec();
+            padding_128bit::pa_pkcs7(&mut padded);
+            let mut output = vec![0u8; padded.len()];
+            mode::ecb_enc(&padded, &mut output, &keys);
+            output
+        }
+
+        #[doc = concat!("`", $name, "-ecb` decryption with PKCS #7 padding.")]
+        ///
+        /// The `iv` parameter is ignored, as in OpenSSL.
+        pub fn $ecb_decrypt(
+            key: &[u8; $key_bytes],
+            _iv: &Iv,
+            data: &[u8],
+        ) -> Result<Vec<u8>, AesError> {
+            check_complete_blocks(data)?;
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_decrypt(key, &mut keys);
+            let mut output = vec![0u8; data.len()];
+            mode::ecb_dec(data, &mut output, &keys);
+            depad(output)
+        }
+
+        #[doc = concat!("`", $name, "-cbc` encryption with PKCS #7 padding.")]
+        pub fn $cbc_encrypt(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            let mut padded = data.to_vec();
+            padding_128bit::pa_pkcs7(&mut padded);
+            let mut output = vec![0u8; padded.len()];
+            mode::cbc_enc(&padded, &mut output, &keys, iv);
+            output
+        }
+
+        #[doc = concat!("`", $name, "-cbc` decryption with PKCS #7 padding.")]
+        pub fn $cbc_decrypt(
+            key: &[u8; $key_bytes],
+            iv: &Iv,
+            data: &[u8],
+        ) -> Result<Vec<u8>, AesError> {
+            check_complete_blocks(data)?;
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_decrypt(key, &mut keys);
+            let mut output = vec![0u8; data.len()];
+            mode::cbc_dec(data, &mut output, &keys, iv);
+            depad(output)
+        }
+
+        #[doc = concat!("`", $name, "-cbc` encryption with PKCS #7 padding and a random IV.")]
+        ///
+        /// The IV comes from the random number generator of the OS, and is returned with the
+        /// cipher, since it is needed for decryption. Only available with the `getrandom` feature.
+        /// # Panics
+        /// This function panics if the OS fails to provide random bytes.
+        #[cfg(feature = "getrandom")]
+        pub fn $cbc_encrypt_with_random_iv(key: &[u8; $key_bytes], data: &[u8]) -> (Iv, Vec<u8>) {
+            let iv = super::rng::os_iv();
+            (iv, $cbc_encrypt(key, &iv, data))
+        }
+
+        #[doc = concat!("`", $name, "-cfb` (CFB-128) encryption.")]
+        pub fn $cfb_encrypt(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            unpadded(data, &keys, iv, mode::cfb_enc)
+        }
+
+        #[doc = concat!("`", $name, "-cfb` (CFB-128) decryption.")]
+        pub fn $cfb_decrypt(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            unpadded(data, &keys, iv, mode::cfb_dec)
+        }
+
+        #[doc = concat!("`", $name, "-cfb8` (CFB-8) encryption.")]
+        pub fn $cfb8_encrypt(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            unpadded(data, &keys, iv, mode::cfb_8_enc)
+        }
+
+        #[doc = concat!("`", $name, "-cfb8` (CFB-8) decryption.")]
+        pub fn $cfb8_decrypt(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            unpadded(data, &keys, iv, mode::cfb_8_dec)
+        }
+
+        #[doc = concat!("`", $name, "-ofb` encryption and decryption.")]
+        pub fn $ofb(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            unpadded(data, &keys, iv, mode::ofb_enc_dec)
+        }
+
+        #[doc = concat!("`", $name, "-ctr` encryption and decryption.")]
+        pub fn $ctr(key: &[u8; $key_bytes], iv: &Iv, data: &[u8]) -> Vec<u8> {
+            let mut keys = [0u32; $n_subkeys];
+            $schedule_encrypt(key, &mut keys);
+            unpadded(data, &keys, iv, mode::ctr_enc_dec)
+        }
+    };
+}
+
+define_evp_functions!(
+    "aes-128",
+    KEY_BYTES_128BIT,
+    N_SUBKEYS_128BIT,
+    aes_core::key_schedule_encrypt128,
+    aes_core::key_schedule_decrypt128,
+    aes_128_ecb_encrypt,
+    aes_128_ecb_decrypt,
+    aes_128_cbc_encrypt,
+    aes_128_cbc_decrypt,
+    aes_128_cbc_encrypt_with_random_iv,
+    aes_128_cfb_encrypt,
+    aes_128_cfb_decrypt,
+    aes_128_cfb8_encrypt,
+    aes_128_cfb8_decrypt,
+    aes_128_ofb,
+    aes_128_ctr
+);
+
+define_evp_functions!(
+    "aes-192",
+    KEY_BYTES_192BIT,
+    N_SUBKEYS_192BIT,
+    aes_core::key_schedule_encrypt192,
+    aes_core::key_schedule_decrypt192,
+    aes_192_ecb_encrypt,
+    aes_192_ecb_decrypt,
+    aes_192_cbc_encrypt,
+    aes_192_cbc_decrypt,
+    aes_192_cbc_encrypt_with_random_iv,
+    aes_192_cfb_encrypt,
+    aes_192_cfb_decrypt,
+    aes_192_cfb8_encrypt,
+    aes_192_cfb8_decrypt,
+    aes_192_ofb,
+    aes_192_ctr
+);
+
+define_evp_functions!(
+    "aes-256",
+    KEY_BYTES_256BIT,
+    N_SUBKEYS_256BIT,
+    aes_core::key_schedule_encrypt256,
+    aes_core::key_schedule_decrypt256,
+    aes_256_ecb_encrypt,
+    aes_256_ecb_decrypt,
+    aes_256_cbc_encrypt,
+    aes_256_cbc_decrypt,
+    aes_256_cbc_encrypt_with_random_iv,
+    aes_256_cfb_encrypt,
+    aes_256_cfb_decrypt,
+    aes_256_cfb8_encrypt,
+    aes_256_cfb8_decrypt,
+    aes_256_ofb,
+    aes_256_ctr
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::unhex;
+
+    const KEY: [u8; KEY_BYTES_192BIT] = [
+        0x8E, 0x73, 0xB0, 0xF7, 0xDA, 0x0E, 0x64, 0x52, 0xC8, 0x10, 0xF3, 0x2B, 0x80, 0x90, 0x79,
+        0xE5, 0x62, 0xF8, 0xEA, 0xD2, 0x52, 0x2C, 0x6B, 0x7B,
+    ];
+    const IV: Iv = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+        0x0F,
+    ];
+    const PLAIN: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn padded_functions_work() {
+        // These examples came from `openssl enc -aes-192-ecb` and `-aes-192-cbc`
+        let ecb = aes_192_ecb_encrypt(&KEY, &IV, PLAIN);
+        assert_eq!(
+            ecb,
+            unhex(concat!(
+                "7edc21b3dd38f5791890578ed776256ab4dae8511297ba20",
+                "53728b7fd5b75508a84c08bdae7435f588bd5840386f458d"
+            ))
+        );
+        assert_eq!(aes_192_ecb_decrypt(&KEY, &IV, &ecb).unwrap(), PLAIN);
+        let cbc = aes_192_cbc_encrypt(&KEY, &IV, PLAIN);
+        assert_eq!(
+            cbc,
+            unhex(concat!(
+                "8d41c88a9a8fa67d3262136b71f01e9ef9dfd92f93d700bc",
+                "e76a320bbe0cbede364fb66169990326e22dc8c32d2cde0f"
+            ))
+        );
+        assert_eq!(aes_192_cbc_decrypt(&KEY, &IV, &cbc).unwrap(), PLAIN);
+        assert_eq!(aes_192_cbc_encrypt(&KEY, &IV, &[]).len(), 16);
+    }
+
+    #[test]
+    fn unpadded_functions_work() {
+        // These examples came from `openssl enc -aes-192-cfb` and so on
+        type Function = fn(&[u8; KEY_BYTES_192BIT], &Iv, &[u8]) -> Vec<u8>;
+        let cases: [(Function, Function, &str); 4] = [
+            (
+                aes_192_cfb_encrypt,
+                aes_192_cfb_decrypt,
+                "f261d6ad82c47a5eb6df456ad57e387e34f3f17ed56f61a77d18df0e97567693257e9f376a39a6e7560bec",
+            ),
+            (
+                aes_192_cfb8_encrypt,
+                aes_192_cfb8_decrypt,
+                "f2291c31a4471c379f2adc9d002b3dca74f4f612c99209ba5154315e09598ff8f2bc00922df3a5eb72667c",
+            ),
+            (
+                aes_192_ofb,
+                aes_192_ofb,
+                "f261d6ad82c47a5eb6df456ad57e387e348079fa38154290e47f17dae1cdaa24d537a6c002d0c45ed408cb",
+            ),
+            (
+                aes_192_ctr,
+                aes_192_ctr,
+                "f261d6ad82c47a5eb6df456ad57e387eff9a544e63691d268e7cd1a6c20a9c3f210df54191e173dd5879d7",
+            ),
+        ];
+        for (encrypt, decrypt, expected) in cases {
+            let cipher = encrypt(&KEY, &IV, PLAIN);
+            assert_eq!(cipher, unhex(expected));
+            assert_eq!(decrypt(&KEY, &IV, &cipher), PLAIN);
+            assert!(encrypt(&KEY, &IV, &[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn decrypt_errors_work() {
+        let key = [0u8; KEY_BYTES_256BIT];
+        let cbc = aes_256_cbc_encrypt(&key, &IV, PLAIN);
+        assert_eq!(
+            aes_256_cbc_decrypt(&key, &IV, &cbc[..40]),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            aes_256_cbc_decrypt(&key, &IV, &[]),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            aes_256_ecb_decrypt(&key, &IV, &cbc),
+            Err(AesError::InvalidPadding)
+        );
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn random_iv_works() {
+        let (iv, cipher) = aes_192_cbc_encrypt_with_random_iv(&KEY, PLAIN);
+        assert_eq!(cipher, aes_192_cbc_encrypt(&KEY, &iv, PLAIN));
+        assert_eq!(aes_192_cbc_decrypt(&KEY, &iv, &cipher).unwrap(), PLAIN);
+        assert_ne!(aes_192_cbc_encrypt_with_random_iv(&KEY, PLAIN).0, iv);
+    }
+}
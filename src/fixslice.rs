@@ -0,0 +1,413 @@
+//! # fixslice
+//! `fixslice` runs the block functions of `aes_core` on a 32-bit fixsliced AES, which uses no
+//! lookup tables and takes constant time.
+//!
+//! The state of two blocks is kept in eight 32-bit words, the bit planes: the word `k` holds the
+//! bit `k` of all the 32 bytes. The S-box is computed by a circuit of logic operations on the
+//! planes (the one of Boyar and Peralta, 113 gates), so no memory is addressed by secret data,
+//! which matters on microcontrollers without cache too. Only 32-bit operations and rotations are
+//! used in the rounds, which suit Cortex-M cores.
+//!
+//! In a plane, the byte in row `r` and column `c` of block `b` is at the bit `8 * c + 2 * r + b`.
+//! So MixColumns only rotates the planes. ShiftRows is left out of the rounds ("fixslicing"):
+//! the columns drift by one position per round, which MixColumns and the round keys take into
+//! account, and the drift is undone when the blocks are unpacked.
+//!
+//! This module is behind the `fixslice` feature. With it, the encryption tables are no longer
+//! used, while the key schedule still uses the S-box table and (for decryption) the decryption
+//! tables.
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+
+/// The bit planes of two blocks.
+type State = [u32; 8];
+
+/// Transpose the 8x8 bit matrix whose rows are the bytes of `x`.
+fn transpose8(x: u64) -> u64 {
+    let t = (x ^ (x >> 7)) & 0x00AA_00AA_00AA_00AA;
+    let x = x ^ t ^ (t << 7);
+    let t = (x ^ (x >> 14)) & 0x0000_CCCC_0000_CCCC;
+    let x = x ^ t ^ (t << 14);
+    let t = (x ^ (x >> 28)) & 0x0000_0000_F0F0_F0F0;
+    x ^ t ^ (t << 28)
+}
+
+/// The index in a block of the byte in row `r` and column `c` of the state, when the columns of
+/// row `r` have drifted by `drift * r` positions.
+fn byte_index(r: usize, c: usize, drift: usize) -> usize {
+    4 * ((c + drift * r) & 0b11) + r
+}
+
+/// Pack two blocks into bit planes.
+fn pack(blocks: [&[u8; BLOCKSIZE_IN_BYTES]; 2], drift: usize) -> State {
+    let mut state = [0u32; 8];
+    for c in 0..4 {
+        let mut x = 0u64;
+        for r in 0..4 {
+            for (b, block) in blocks.iter().enumerate() {
+                x |= (block[byte_index(r, c, drift)] as u64) << (8 * (2 * r + b));
+            }
+        }
+        let x = transpose8(x);
+        for (k, plane) in state.iter_mut().enumerate() {
+            *plane |= (((x >> (8 * k)) & 0xFF) as u32) << (8 * c);
+        }
+    }
+    state
+}
+
+/// Unpack bit planes into two blocks.
+fn unpack(state: &State, blocks: [&mut [u8; BLOCKSIZE_IN_BYTES]; 2], drift: usize) {
+    let [block0, block1] = blocks;
+    for c in 0..4 {
+        let mut x = 0u64;
+        for (k, plane) in state.iter().enumerate() {
+            x |= (((plane >> (8 * c)) & 0xFF) as u64) << (8 * k);
+        }
+        let x = transpose8(x);
+        for r in 0..4 {
+            block0[byte_index(r, c, drift)] = (x >> (16 * r)) as u8;
+            block1[byte_index(r, c, drift)] = (x >> (16 * r + 8)) as u8;
+        }
+    }
+}
+
+/// XOR a round key (4 sub-keys) into the state.
+fn add_round_key(state: &mut State, round_key: &[u32], drift: usize) {
+    let mut bytes = [0u8; BLOCKSIZE_IN_BYTES];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(round_key) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    let key = pack([&bytes, &bytes], drift);
+    for (plane, key) in state.iter_mut().zip(key.iter()) {
+        *plane ^= key;
+    }
+}
+
+/// SubBytes on the bit planes.
+fn sub_bytes(state: &mut State) {
+    let u0 = state[7];
+    let u1 = state[6];
+    let u2 = state[5];
+    let u3 = state[4];
+    let u4 = state[3];
+    let u5 = state[2];
+    let u6 = state[1];
+    let u7 = state[0];
+    let t1 = u0 ^ u3;
+    let t2 = u0 ^ u5;
+    let t3 = u0 ^ u6;
+    let t4 = u3 ^ u5;
+    let t5 = u4 ^ u6;
+    let t6 = t1 ^ t5;
+    let t7 = u1 ^ u2;
+    let t8 = u7 ^ t6;
+    let t9 = u7 ^ t7;
+    let t10 = t6 ^ t7;
+    let t11 = u1 ^ u5;
+    let t12 = u2 ^ u5;
+    let t13 = t3 ^ t4;
+    let t14 = t6 ^ t11;
+    let t15 = t5 ^ t11;
+    let t16 = t5 ^ t12;
+    let t17 = t9 ^ t16;
+    let t18 = u3 ^ u7;
+    let t19 = t7 ^ t18;
+    let t20 = t1 ^ t19;
+    let t21 = u6 ^ u7;
+    let t22 = t7 ^ t21;
+    let t23 = t2 ^ t22;
+    let t24 = t2 ^ t10;
+    let t25 = t20 ^ t17;
+    let t26 = t3 ^ t16;
+    let t27 = t1 ^ t12;
+    let m1 = t13 & t6;
+    let m2 = t23 & t8;
+    let m3 = t14 ^ m1;
+    let m4 = t19 & u7;
+    let m5 = m4 ^ m1;
+    let m6 = t3 & t16;
+    let m7 = t22 & t9;
+    let m8 = t26 ^ m6;
+    let m9 = t20 & t17;
+    let m10 = m9 ^ m6;
+    let m11 = t1 & t15;
+    let m12 = t4 & t27;
+    let m13 = m12 ^ m11;
+    let m14 = t2 & t10;
+    let m15 = m14 ^ m11;
+    let m16 = m3 ^ m2;
+    let m17 = m5 ^ t24;
+    let m18 = m8 ^ m7;
+    let m19 = m10 ^ m15;
+    let m20 = m16 ^ m13;
+    let m21 = m17 ^ m15;
+    let m22 = m18 ^ m13;
+    let m23 = m19 ^ t25;
+    let m24 = m22 ^ m23;
+    let m25 = m22 & m20;
+    let m26 = m21 ^ m25;
+    let m27 = m20 ^ m21;
+    let m28 = m23 ^ m25;
+    let m29 = m28 & m27;
+    let m30 = m26 & m24;
+    let m31 = m20 & m23;
+    let m32 = m27 & m31;
+    let m33 = m27 ^ m25;
+    let m34 = m21 & m22;
+    let m35 = m24 & m34;
+    let m36 = m24 ^ m25;
+    let m37 = m21 ^ m29;
+    let m38 = m32 ^ m33;
+    let m39 = m23 ^ m30;
+    let m40 = m35 ^ m36;
+    let m41 = m38 ^ m40;
+    let m42 = m37 ^ m39;
+    let m43 = m37 ^ m38;
+    let m44 = m39 ^ m40;
+    let m45 = m42 ^ m41;
+    let m46 = m44 & t6;
+    let m47 = m40 & t8;
+    let m48 = m39 & u7;
+    let m49 = m43 & t16;
+    let m50 = m38 & t9;
+    let m51 = m37 & t17;
+    let m52 = m42 & t15;
+    let m53 = m45 & t27;
+    let m54 = m41 & t10;
+    let m55 = m44 & t13;
+    let m56 = m40 & t23;
+    let m57 = m39 & t19;
+    let m58 = m43 & t3;
+    let m59 = m38 & t22;
+    let m60 = m37 & t20;
+    let m61 = m42 & t1;
+    let m62 = m45 & t4;
+    let m63 = m41 & t2;
+    let l0 = m61 ^ m62;
+    let l1 = m50 ^ m56;
+    let l2 = m46 ^ m48;
+    let l3 = m47 ^ m55;
+    let l4 = m54 ^ m58;
+    let l5 = m49 ^ m61;
+    let l6 = m62 ^ l5;
+    let l7 = m46 ^ l3;
+    let l8 = m51 ^ m59;
+    let l9 = m52 ^ m53;
+    let l10 = m53 ^ l4;
+    let l11 = m60 ^ l2;
+    let l12 = m48 ^ m51;
+    let l13 = m50 ^ l0;
+    let l14 = m52 ^ m61;
+    let l15 = m55 ^ l1;
+    let l16 = m56 ^ l0;
+    let l17 = m57 ^ l1;
+    let l18 = m58 ^ l8;
+    let l19 = m63 ^ l4;
+    let l20 = l0 ^ l1;
+    let l21 = l1 ^ l7;
+    let l22 = l3 ^ l12;
+    let l23 = l18 ^ l2;
+    let l24 = l15 ^ l9;
+    let l25 = l6 ^ l10;
+    let l26 = l7 ^ l9;
+    let l27 = l8 ^ l10;
+    let l28 = l11 ^ l14;
+    let l29 = l11 ^ l17;
+    state[7] = l6 ^ l24;
+    state[6] = !(l16 ^ l26);
+    state[5] = !(l19 ^ l28);
+    state[4] = l6 ^ l21;
+    state[3] = l20 ^ l22;
+    state[2] = l25 ^ l29;
+    state[1] = !(l13 ^ l27);
+    state[0] = !(l6 ^ l23);
+}
+
+/// The linear part of the affine transformation of the inverse S-box, plus its constant.
+fn inv_affine(state: &mut State) {
+    let x = *state;
+    for (i, plane) in state.iter_mut().enumerate() {
+        *plane = x[(i + 2) & 0b111] ^ x[(i + 5) & 0b111] ^ x[(i + 7) & 0b111];
+    }
+    state[0] = !state[0];
+    state[2] = !state[2];
+}
+
+/// InvSubBytes on the bit planes. The inverse in GF(2^8) is taken from the S-box circuit by
+/// undoing its affine transformation: `InvSBox(x) = A'(SBox(A'(x)))`, where `A'` is
+/// [`inv_affine`].
+fn inv_sub_bytes(state: &mut State) {
+    inv_affine(state);
+    sub_bytes(state);
+    inv_affine(state);
+}
+
+/// Multiply each byte by 2 in GF(2^8).
+fn xtime(x: &State) -> State {
+    [
+        x[7],
+        x[0] ^ x[7],
+        x[1],
+        x[2] ^ x[7],
+        x[3] ^ x[7],
+        x[4],
+        x[5],
+        x[6],
+    ]
+}
+
+/// Move the bytes of row `r + 1` to row `r`, in the column where row `r` of the same column has
+/// drifted to.
+fn rotate_rows(x: u32, drift: usize) -> u32 {
+    let x = x.rotate_left(8 * drift as u32);
+    ((x >> 2) & 0x3F3F_3F3F) | ((x << 6) & 0xC0C0_C0C0)
+}
+
+/// Move the bytes of row `r + 2` to row `r`, see [`rotate_rows`].
+fn rotate_rows_twice(x: u32, drift: usize) -> u32 {
+    let x = x.rotate_left(16 * drift as u32);
+    ((x >> 4) & 0x0F0F_0F0F) | ((x << 4) & 0xF0F0_F0F0)
+}
+
+/// MixColumns on the bit planes, whose columns have drifted by `drift`.
+///
+/// `2 * s[r] ^ 3 * s[r + 1] ^ s[r + 2] ^ s[r + 3]` is computed as
+/// `2 * t[r] ^ s[r + 1] ^ t[r + 2]`, where `t[r] = s[r] ^ s[r + 1]`.
+fn mix_columns(state: &mut State, drift: usize) {
+    let mut rotated = [0u32; 8];
+    let mut t = [0u32; 8];
+    for k in 0..8 {
+        rotated[k] = rotate_rows(state[k], drift);
+        t[k] = state[k] ^ rotated[k];
+    }
+    let doubled = xtime(&t);
+    for k in 0..8 {
+        state[k] = doubled[k] ^ rotated[k] ^ rotate_rows_twice(t[k], drift);
+    }
+}
+
+/// InvMixColumns on the bit planes, whose columns have drifted by `drift`.
+///
+/// The matrix of InvMixColumns is the one of MixColumns times the circulant `(5, 0, 4, 0)`, so
+/// `s[r] ^= 4 * (s[r] ^ s[r + 2])` is done before [`mix_columns`].
+fn inv_mix_columns(state: &mut State, drift: usize) {
+    let mut t = [0u32; 8];
+    for k in 0..8 {
+        t[k] = state[k] ^ rotate_rows_twice(state[k], drift);
+    }
+    let quadrupled = xtime(&xtime(&t));
+    for k in 0..8 {
+        state[k] ^= quadrupled[k];
+    }
+    mix_columns(state, drift);
+}
+
+/// **Encrypt** two blocks in place with the sub-keys (length = 44, 52 or 60) for encryption.
+pub(crate) fn encrypt_blocks(blocks: [&mut [u8; BLOCKSIZE_IN_BYTES]; 2], subkeys: &[u32]) {
+    let rounds = subkeys.len() / 4 - 1;
+    let mut state = pack([&*blocks[0], &*blocks[1]], 0);
+    add_round_key(&mut state, &subkeys[..4], 0);
+    for round in 1..=rounds {
+        // Leaving ShiftRows out drifts the columns of row `r` by `-r` positions per round.
+        let drift = round.wrapping_neg() & 0b11;
+        sub_bytes(&mut state);
+        if round != rounds {
+            mix_columns(&mut state, drift);
+        }
+        add_round_key(&mut state, &subkeys[(4 * round)..(4 * round + 4)], drift);
+    }
+    unpack(&state, blocks, rounds.wrapping_neg() & 0b11);
+}
+
+/// **Decrypt** two blocks in place with the sub-keys (length = 44, 52 or 60) for decryption.
+pub(crate) fn decrypt_blocks(blocks: [&mut [u8; BLOCKSIZE_IN_BYTES]; 2], subkeys: &[u32]) {
+    let rounds = subkeys.len() / 4 - 1;
+    let mut state = pack([&*blocks[0], &*blocks[1]], 0);
+    add_round_key(&mut state, &subkeys[(4 * rounds)..], 0);
+    for round in 1..=rounds {
+        // Leaving InvShiftRows out drifts the columns of row `r` by `r` positions per round.
+        let drift = round & 0b11;
+        let index = 4 * (rounds - round);
+        inv_sub_bytes(&mut state);
+        if round != rounds {
+            inv_mix_columns(&mut state, drift);
+        }
+        add_round_key(&mut state, &subkeys[index..(index + 4)], drift);
+    }
+    unpack(&state, blocks, rounds & 0b11);
+}
+
+/// **Encrypt** a block in place with the sub-keys (length = 44, 52 or 60) for encryption.
+pub(crate) fn encrypt_block(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+    let mut spare = *block;
+    encrypt_blocks([block, &mut spare], subkeys);
+}
+
+/// **Decrypt** a block in place with the sub-keys (length = 44, 52 or 60) for decryption.
+pub(crate) fn decrypt_block(block: &mut [u8; BLOCKSIZE_IN_BYTES], subkeys: &[u32]) {
+    let mut spare = *block;
+    decrypt_blocks([block, &mut spare], subkeys);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::key_schedule_encrypt_decrypt_auto;
+
+    #[test]
+    fn pack_unpack_works() {
+        let block0: [u8; BLOCKSIZE_IN_BYTES] = core::array::from_fn(|i| i as u8);
+        let block1: [u8; BLOCKSIZE_IN_BYTES] = core::array::from_fn(|i| 0xF0 ^ (i * 7) as u8);
+        for drift in 0..4 {
+            let state = pack([&block0, &block1], drift);
+            let (mut out0, mut out1) = ([0u8; BLOCKSIZE_IN_BYTES], [0u8; BLOCKSIZE_IN_BYTES]);
+            unpack(&state, [&mut out0, &mut out1], drift);
+            assert_eq!((out0, out1), (block0, block1));
+        }
+        // The bit 0 of the byte in row 1 and column 2 of block 1 is at bit 8 * 2 + 2 * 1 + 1.
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[4 * 2 + 1] = 0x01;
+        assert_eq!(pack([&[0u8; BLOCKSIZE_IN_BYTES], &block], 0)[0], 1 << 19);
+    }
+
+    #[test]
+    fn fixslice_matches_fips197() {
+        // These examples came from NIST.FIPS.197 Appendix C
+        let plain: [u8; BLOCKSIZE_IN_BYTES] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+            0xEE, 0xFF,
+        ];
+        let expected: [[u8; BLOCKSIZE_IN_BYTES]; 3] = [
+            [
+                0x69, 0xC4, 0xE0, 0xD8, 0x6A, 0x7B, 0x04, 0x30, 0xD8, 0xCD, 0xB7, 0x80, 0x70, 0xB4,
+                0xC5, 0x5A,
+            ],
+            [
+                0xDD, 0xA9, 0x7C, 0xA4, 0x86, 0x4C, 0xDF, 0xE0, 0x6E, 0xAF, 0x70, 0xA0, 0xEC, 0x0D,
+                0x71, 0x91,
+            ],
+            [
+                0x8E, 0xA2, 0xB7, 0xCA, 0x51, 0x67, 0x45, 0xBF, 0xEA, 0xFC, 0x49, 0x90, 0x4B, 0x49,
+                0x60, 0x89,
+            ],
+        ];
+        for (key_bytes, expected) in [16usize, 24, 32].into_iter().zip(expected) {
+            let key: Vec<u8> = (0..key_bytes as u8).collect();
+            let mut encryption = vec![0u32; key_bytes + 28];
+            let mut decryption = vec![0u32; key_bytes + 28];
+            key_schedule_encrypt_decrypt_auto(&key, &mut encryption, &mut decryption);
+            let mut block = plain;
+            encrypt_block(&mut block, &encryption);
+            assert_eq!(block, expected);
+            decrypt_block(&mut block, &decryption);
+            assert_eq!(block, plain);
+
+            // Two different blocks at once.
+            let (mut block0, mut block1) = (expected, plain);
+            encrypt_blocks([&mut block0, &mut block1], &encryption);
+            assert_eq!(block1, expected);
+            decrypt_blocks([&mut block0, &mut block1], &decryption);
+            assert_eq!((block0, block1), (expected, plain));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! # ghash
+//! `ghash` provides GHASH, the universal hash of GCM (NIST SP 800-38D), and the multiplication
+//! in GF(2^128) it is built on, for custom constructions such as GCM variants or tweak mixing.
+//!
+//! GHASH is keyed by the hash subkey `H`, which GCM takes as the encryption of the zero block
+//! (see [`Ghash::from_cipher`]). It is **not** a MAC on its own: its output must be masked, as
+//! GCM does with the encryption of the first counter block.
+//!
+//! The multiplication is computed bit by bit with masks rather than branches or tables, so its
+//! running time does not depend on the data. It is simple and not fast.
+//!
+//! [`Ghash::with_tables`] trades memory for speed instead: it precomputes the multiples of `H`
+//! for 4 or 8 bits at a time (see [`GhashTables`]). The lookups of the tables depend on the data,
+//! so they may leak it through the cache timings; embedded builds and untrusted neighbours call
+//! for no table, servers for the 8-bit one.
+//!
+//! [`Ghash::from_cipher`]: ../ghash/struct.Ghash.html#method.from_cipher
+//! [`Ghash::with_tables`]: ../ghash/struct.Ghash.html#method.with_tables
+//! [`GhashTables`]: ../ghash/enum.GhashTables.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+
+/// Multiply two elements of GF(2^128) with the bit order of GCM, where the first bit of the first
+/// byte is the coefficient of `x^0`, modulo `x^128 + x^7 + x^2 + x + 1`.
+/// # Examples
+/// ```
+/// use aes_frast::block::Block;
+/// use aes_frast::ghash::gf128_mul;
+///
+/// // `1` is the block whose first bit is set.
+/// let mut one = [0u8; 16];
+/// one[0] = 0x80;
+/// let x = Block::from([0x3Cu8; 16]);
+/// assert_eq!(gf128_mul(&x, &Block::from(one)), x);
+/// assert_eq!(gf128_mul(&x, &Block::default()), Block::default());
+/// ```
+pub fn gf128_mul(x: &Block, y: &Block) -> Block {
+    // SP 800-38D, Algorithm 1.
+    let x = u128::from_be_bytes(x.0);
+    let mut v = u128::from_be_bytes(y.0);
+    let mut z = 0u128;
+    for i in (0..128).rev() {
+        z ^= v & ((x >> i) & 1).wrapping_neg();
+        v = mul_x(v);
+    }
+    Block::from(z.to_be_bytes())
+}
+
+/// `R = 11100001 || 0^120`, the reduction polynomial of SP 800-38D.
+const R: u128 = 0xE1 << 120;
+
+/// Multiply by `x`: shift right by one bit, reducing the bit which falls off.
+const fn mul_x(v: u128) -> u128 {
+    (v >> 1) ^ (R & (v & 1).wrapping_neg())
+}
+
+/// The reductions of the `bits` lowest bits when shifting right by `bits` bits, i.e.
+/// `REDUCTION[v] = v * x^bits` for the values `v` of `N = 2^bits` entries.
+const fn reduction_table<const N: usize>(bits: u32) -> [u128; N] {
+    let mut table = [0u128; N];
+    let mut v = 0;
+    while v < N {
+        let mut r = v as u128;
+        let mut i = 0;
+        while i < bits {
+            r = mul_x(r);
+            i += 1;
+        }
+        table[v] = r;
+        v += 1;
+    }
+    table
+}
+
+static REDUCTION_4: [u128; 16] = reduction_table(4);
+static REDUCTION_8: [u128; 256] = reduction_table(8);
+
+/// The precomputed tables of a [`Ghash`], which trade memory for speed.
+///
+/// [`Ghash`]: ../ghash/struct.Ghash.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GhashTables {
+    /// No table: the multiplication is computed bit by bit, in constant time. It is the choice
+    /// of [`Ghash::new`].
+    ///
+    /// [`Ghash::new`]: ../ghash/struct.Ghash.html#method.new
+    None,
+    /// The 16 multiples of `H` by 4-bit values (256 bytes), for 4 bits per lookup.
+    Bits4,
+    /// The 256 multiples of `H` by 8-bit values (4 KiB), for 8 bits per lookup.
+    Bits8,
+}
+
+/// The multiplier by `H`.
+#[derive(Clone)]
+enum Multiplier {
+    Bitwise,
+    Bits4(Box<[u128; 16]>),
+    Bits8(Box<[u128; 256]>),
+}
+
+/// The multiples `table[v] = v * H`, where `v` is placed at the highest bits, i.e. at the lowest
+/// powers of `x`.
+fn multiples<const N: usize>(h: &Block, bits: u32) -> Box<[u128; N]> {
+    let mut table = Box::new([0u128; N]);
+    for (v, entry) in table.iter_mut().enumerate() {
+        let v = Block::from(((v as u128) << (128 - bits)).to_be_bytes());
+        *entry = u128::from_be_bytes(gf128_mul(&v, h).0);
+    }
+    table
+}
+
+/// Multiply `x` by `H` with the multiples of `H`, `bits` bits of `x` at a time, from the highest
+/// powers of `x` to the lowest (Horner's rule).
+fn mul_with_table(x: u128, table: &[u128], reduction: &[u128], bits: u32) -> u128 {
+    let mask = (1u128 << bits) - 1;
+    let mut z = 0u128;
+    let mut shift = 0;
+    while shift < 128 {
+        z = (z >> bits) ^ reduction[(z & mask) as usize];
+        z ^= table[((x >> shift) & mask) as usize];
+        shift += bits;
+    }
+    z
+}
+
+/// The GHASH state, keyed by the hash subkey `H`.
+/// # Examples
+/// ```
+/// use aes_frast::ghash::Ghash;
+/// use aes_frast::Aes128;
+///
+/// // This example came from test case 2 of the GCM specification: the key and the IV are zero,
+/// // there is no associated data, and the cipher is one block.
+/// let mut ghash = Ghash::from_cipher(&Aes128::new(&[0u8; 16]));
+/// ghash.update(&[0x03, 0x88, 0xDA, 0xCE, 0x60, 0xB6, 0xA3, 0x92,
+///                0xF3, 0x28, 0xC2, 0xB9, 0x71, 0xB2, 0xFE, 0x78]);
+/// ghash.update_lengths(0, 16);
+/// assert_eq!(ghash.finalize().0, [0xF3, 0x8C, 0xBB, 0x1A, 0xD6, 0x92, 0x23, 0xDC,
+///                                 0xC3, 0x45, 0x7A, 0xE5, 0xB6, 0xB0, 0xF8, 0x85]);
+/// ```
+#[derive(Clone)]
+pub struct Ghash {
+    h: Block,
+    y: Block,
+    multiplier: Multiplier,
+}
+
+impl Ghash {
+    /// Create the state with the hash subkey `h`, without tables.
+    pub fn new(h: Block) -> Self {
+        Self::with_tables(h, GhashTables::None)
+    }
+
+    /// Create the state with the hash subkey `h` and the chosen precomputed tables.
+    /// # Examples
+    /// ```
+    /// use aes_frast::block::Block;
+    /// use aes_frast::ghash::{Ghash, GhashTables};
+    ///
+    /// let h = Block::from([0x66u8; 16]);
+    /// let mut fast = Ghash::with_tables(h, GhashTables::Bits8);
+    /// let mut slow = Ghash::new(h);
+    /// fast.update(b"the same hash with less work");
+    /// slow.update(b"the same hash with less work");
+    /// assert_eq!(fast.tables(), GhashTables::Bits8);
+    /// assert_eq!(fast.finalize(), slow.finalize());
+    /// ```
+    pub fn with_tables(h: Block, tables: GhashTables) -> Self {
+        let multiplier = match tables {
+            GhashTables::None => Multiplier::Bitwise,
+            GhashTables::Bits4 => Multiplier::Bits4(multiples(&h, 4)),
+            GhashTables::Bits8 => Multiplier::Bits8(multiples(&h, 8)),
+        };
+        Self {
+            h,
+            y: Block::default(),
+            multiplier,
+        }
+    }
+
+    /// Create the state with the hash subkey of GCM, the encryption of the zero block, without
+    /// tables.
+    pub fn from_cipher<C: BlockCipher128 + ?Sized>(cipher: &C) -> Self {
+        Self::from_cipher_with_tables(cipher, GhashTables::None)
+    }
+
+    /// Create the state with the hash subkey of GCM and the chosen precomputed tables.
+    pub fn from_cipher_with_tables<C: BlockCipher128 + ?Sized>(
+        cipher: &C,
+        tables: GhashTables,
+    ) -> Self {
+        let mut h = Block::default();
+        cipher.encrypt_block(&mut h.0);
+        Self::with_tables(h, tables)
+    }
+
+    /// The hash subkey `H`.
+    pub fn subkey(&self) -> Block {
+        self.h
+    }
+
+    /// The precomputed tables of the state.
+    pub fn tables(&self) -> GhashTables {
+        match self.multiplier {
+            Multiplier::Bitwise => GhashTables::None,
+            Multiplier::Bits4(_) => GhashTables::Bits4,
+            Multiplier::Bits8(_) => GhashTables::Bits8,
+        }
+    }
+
+    /// Absorb one block: `Y = (Y ^ block) * H`.
+    pub fn update_block(&mut self, block: &Block) {
+        let x = self.y ^ *block;
+        self.y = match &self.multiplier {
+            Multiplier::Bitwise => gf128_mul(&x, &self.h),
+            Multiplier::Bits4(table) => {
+                let x = u128::from_be_bytes(x.0);
+                Block::from(mul_with_table(x, &table[..], &REDUCTION_4, 4).to_be_bytes())
+            }
+            Multiplier::Bits8(table) => {
+                let x = u128::from_be_bytes(x.0);
+                Block::from(mul_with_table(x, &table[..], &REDUCTION_8, 8).to_be_bytes())
+            }
+        };
+    }
+
+    /// Absorb `data`, padding its last partial block with zeros, as GCM does with the associated
+    /// data and with the cipher.
+    ///
+    /// So `update(a); update(b)` pads `a` on its own, and is not `update(a || b)` unless `a` is a
+    /// multiple of 16 bytes long.
+    pub fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(BLOCKSIZE_IN_BYTES) {
+            let mut block = Block::default();
+            block.0[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    /// Absorb the length block of GCM: the lengths in **bytes** of the associated data and of the
+    /// cipher, which are encoded in bits.
+    pub fn update_lengths(&mut self, aad_len: u64, cipher_len: u64) {
+        let mut block = Block::default();
+        block.0[..8].copy_from_slice(&(aad_len.wrapping_mul(8)).to_be_bytes());
+        block.0[8..].copy_from_slice(&(cipher_len.wrapping_mul(8)).to_be_bytes());
+        self.update_block(&block);
+    }
+
+    /// Start over with the same subkey.
+    pub fn reset(&mut self) {
+        self.y = Block::default();
+    }
+
+    /// The hash of the absorbed blocks.
+    pub fn finalize(self) -> Block {
+        self.y
+    }
+}
+
+impl_redacted_debug!(Ghash);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::unhex;
+    use crate::Aes128;
+
+    #[test]
+    fn gcm_tag() {
+        // An AES-128-GCM encryption, whose expected values came from OpenSSL (through Python's
+        // `cryptography`): the tag is GHASH masked with the encryption of `IV || 00000001`.
+        let key: [u8; 16] = std::array::from_fn(|i| i as u8 + 1);
+        let iv: Vec<u8> = (0xA0..0xAC).collect();
+        let aad: Vec<u8> = (0..20).collect();
+        let cipher_text = unhex(
+            "4242d5e522e847d17f328422bf6899cbee2bb5122be275b9f0898c1aeae487bb019f773a13fa1dd6",
+        );
+        let aes = Aes128::new(&key);
+        let mut ghash = Ghash::from_cipher(&aes);
+        ghash.update(&aad);
+        ghash.update(&cipher_text);
+        ghash.update_lengths(aad.len() as u64, cipher_text.len() as u64);
+        let mut j0 = Block::default();
+        j0.0[..12].copy_from_slice(&iv);
+        j0.0[15] = 1;
+        aes.encrypt_block(&mut j0.0);
+        assert_eq!(
+            (ghash.finalize() ^ j0).0.to_vec(),
+            unhex("4c5de684d332077e5ad4935be876814f")
+        );
+    }
+
+    #[test]
+    fn multiplication_laws() {
+        let a = Block::from([0x9Au8; 16]);
+        let b = Block::from(std::array::from_fn(|i| (i * 37) as u8));
+        let c = Block::from(std::array::from_fn(|i| (i * 11 + 5) as u8));
+        assert_eq!(gf128_mul(&a, &b), gf128_mul(&b, &a));
+        assert_eq!(
+            gf128_mul(&a, &(b ^ c)),
+            gf128_mul(&a, &b) ^ gf128_mul(&a, &c)
+        );
+        assert_eq!(
+            gf128_mul(&gf128_mul(&a, &b), &c),
+            gf128_mul(&a, &gf128_mul(&b, &c))
+        );
+        // `x` is the block of the second bit; multiplying by it shifts right by one bit.
+        let mut x = Block::default();
+        x.0[0] = 0x40;
+        let mut shifted = Block::default();
+        shifted.0[1] = 0x80;
+        let mut y = Block::default();
+        y.0[0] = 0x01;
+        assert_eq!(gf128_mul(&y, &x), shifted);
+    }
+
+    #[test]
+    fn reset_and_blocks() {
+        let mut ghash = Ghash::new(Block::from([0x77u8; 16]));
+        ghash.update(&[0x55u8; 40]);
+        ghash.reset();
+        ghash.update_block(&Block::from([0x55u8; 16]));
+        let mut other = Ghash::new(ghash.subkey());
+        other.update(&[0x55u8; 16]);
+        assert_eq!(ghash.finalize(), other.finalize());
+    }
+
+    #[test]
+    fn tables_match_bitwise() {
+        let h = Block::from(std::array::from_fn(|i| (i * 73 + 19) as u8));
+        let data: Vec<u8> = (0..200).map(|i| (i * 13) as u8).collect();
+        let mut expected = Ghash::new(h);
+        expected.update(&data);
+        expected.update_lengths(0, 200);
+        let expected = expected.finalize();
+        for tables in [GhashTables::None, GhashTables::Bits4, GhashTables::Bits8] {
+            let mut ghash = Ghash::with_tables(h, tables);
+            assert_eq!(ghash.tables(), tables);
+            ghash.update(&data);
+            ghash.update_lengths(0, 200);
+            assert_eq!(ghash.finalize(), expected);
+        }
+        let aes = Aes128::new(&[0u8; 16]);
+        let ghash = Ghash::from_cipher_with_tables(&aes, GhashTables::Bits4);
+        assert_eq!(ghash.subkey(), Ghash::from_cipher(&aes).subkey());
+    }
+}
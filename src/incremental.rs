@@ -0,0 +1,330 @@
+//! # incremental
+//! `incremental` encrypts and decrypts a message as described by a `Recipe`, with the message
+//! given in chunks of any size, for example as they arrive from the network.
+//!
+//! [`Encryptor`] and [`Decryptor`] keep the chaining value of the mode and the bytes of an
+//! incomplete block between the calls of `update`, which appends all the output it can to a
+//! `Vec<u8>`. `finalize` processes what is left, with the padding.
+//!
+//! Only complete blocks are processed by `update` (in every mode but CFB-8), so up to 15 bytes
+//! wait for the next call. With a padding, [`Decryptor`] holds back the last complete block too,
+//! since it may turn out to be the padding.
+//!
+//! [`Encryptor`]: ../incremental/struct.Encryptor.html
+//! [`Decryptor`]: ../incremental/struct.Decryptor.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode_io::process_chunk;
+use super::error::AesError;
+use super::metrics::{self, Operation};
+use super::padding_128bit::PaddingScheme;
+use super::recipe::{Mode, Recipe};
+
+/// The state shared by [`Encryptor`] and [`Decryptor`].
+///
+/// [`Encryptor`]: ../incremental/struct.Encryptor.html
+/// [`Decryptor`]: ../incremental/struct.Decryptor.html
+#[derive(Clone)]
+struct State {
+    recipe: Recipe,
+    keys: Vec<u32>,
+    chaining: Vec<u8>,
+    pending: Vec<u8>,
+    processed: u64,
+}
+
+impl State {
+    fn new(recipe: &Recipe, key: &[u8], iv: &[u8], encrypt: bool) -> Result<Self, AesError> {
+        Ok(Self {
+            recipe: *recipe,
+            keys: recipe.schedule(key, iv, encrypt)?,
+            chaining: iv.to_owned(),
+            pending: Vec::with_capacity(2 * BLOCKSIZE_IN_BYTES),
+            processed: 0,
+        })
+    }
+
+    /// Process the first `length` pending bytes, appending the result to `output`.
+    fn process(&mut self, encrypt: bool, length: usize, output: &mut Vec<u8>) {
+        let start = output.len();
+        output.resize(start + length, 0);
+        process_chunk(
+            &self.recipe,
+            encrypt,
+            &self.pending[..length],
+            &mut output[start..],
+            &self.keys,
+            &mut self.chaining,
+        );
+        self.pending.drain(..length);
+        self.processed += length as u64;
+    }
+
+    /// The number of pending bytes which can be processed now, keeping `held_back` bytes of
+    /// complete blocks.
+    fn ready(&self, held_back: usize) -> usize {
+        let ready = if self.recipe.mode() == Mode::Cfb8 {
+            self.pending.len()
+        } else {
+            self.pending.len() & !0b1111
+        };
+        ready.saturating_sub(held_back)
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        self.keys.fill(0);
+        self.pending.fill(0);
+        std::hint::black_box((&self.keys, &self.pending));
+    }
+}
+
+/// Incremental **encryption** as described by a [`Recipe`].
+/// # Examples
+/// ```
+/// use aes_frast::incremental::{Decryptor, Encryptor};
+/// use aes_frast::recipe::Recipe;
+///
+/// let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+/// let key = [0x42u8; 16];
+/// let iv = [0x24u8; 16];
+/// let plain: Vec<u8> = (0..100).collect();
+///
+/// let mut encryptor = Encryptor::new(&recipe, &key, &iv).unwrap();
+/// let mut cipher = Vec::new();
+/// for chunk in plain.chunks(7) {
+///     encryptor.update(chunk, &mut cipher);
+/// }
+/// encryptor.finalize(&mut cipher).unwrap();
+/// assert_eq!(cipher, recipe.encrypt(&key, &iv, &plain).unwrap());
+///
+/// let mut decryptor = Decryptor::new(&recipe, &key, &iv).unwrap();
+/// let mut decrypted = Vec::new();
+/// for chunk in cipher.chunks(33) {
+///     decryptor.update(chunk, &mut decrypted);
+/// }
+/// decryptor.finalize(&mut decrypted).unwrap();
+/// assert_eq!(decrypted, plain);
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+#[derive(Clone)]
+pub struct Encryptor {
+    state: State,
+}
+
+impl Encryptor {
+    /// Start encrypting a message.
+    ///
+    /// * *parameter* `recipe`: the mode and padding.
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *return* : the encryptor, or `Err(AesError::InvalidKeyLength)` or
+    ///   `Err(AesError::InvalidParameter)` (for the IV).
+    pub fn new(recipe: &Recipe, key: &[u8], iv: &[u8]) -> Result<Self, AesError> {
+        Ok(Self {
+            state: State::new(recipe, key, iv, true)?,
+        })
+    }
+
+    /// The recipe.
+    pub fn recipe(&self) -> Recipe {
+        self.state.recipe
+    }
+
+    /// Encrypt the next chunk of the plain, appending the cipher of the complete blocks to
+    /// `output`.
+    pub fn update(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        self.state.pending.extend_from_slice(input);
+        let ready = self.state.ready(0);
+        self.state.process(true, ready, output);
+    }
+
+    /// Encrypt the rest of the plain with the padding, appending the cipher to `output`.
+    ///
+    /// * *return* : `Ok(())`, or `Err(AesError::InvalidDataLength)` if the plain of a block mode
+    ///   without padding is not a multiple of 16 bytes long.
+    pub fn finalize(mut self, output: &mut Vec<u8>) -> Result<(), AesError> {
+        let state = &mut self.state;
+        let plain_bytes = state.processed + state.pending.len() as u64;
+        if let Err(error) = state.recipe.pad(&mut state.pending) {
+            metrics::record_error();
+            return Err(error);
+        }
+        state.process(true, state.pending.len(), output);
+        metrics::record(Operation::Encrypt, plain_bytes);
+        Ok(())
+    }
+}
+
+/// Incremental **decryption** as described by a [`Recipe`], which checks and removes the
+/// padding.
+/// # Examples
+/// Please refer to [`Encryptor`].
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`Encryptor`]: ../incremental/struct.Encryptor.html
+#[derive(Clone)]
+pub struct Decryptor {
+    state: State,
+}
+
+impl Decryptor {
+    /// Start decrypting a message.
+    ///
+    /// * *parameter* `recipe`: the mode and padding.
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *return* : the decryptor, or `Err(AesError::InvalidKeyLength)` or
+    ///   `Err(AesError::InvalidParameter)` (for the IV).
+    pub fn new(recipe: &Recipe, key: &[u8], iv: &[u8]) -> Result<Self, AesError> {
+        Ok(Self {
+            state: State::new(recipe, key, iv, false)?,
+        })
+    }
+
+    /// The recipe.
+    pub fn recipe(&self) -> Recipe {
+        self.state.recipe
+    }
+
+    /// Decrypt the next chunk of the cipher, appending the plain of the complete blocks to
+    /// `output`, but the last block when there is a padding.
+    ///
+    /// **Attention!** The plain is not authenticated, and the padding is only checked by
+    /// [`finalize`]. The plain must not be used unless it succeeds.
+    ///
+    /// [`finalize`]: ../incremental/struct.Decryptor.html#method.finalize
+    pub fn update(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        self.state.pending.extend_from_slice(input);
+        let held_back = match self.state.recipe.padding() {
+            PaddingScheme::NoPadding => 0,
+            _ => BLOCKSIZE_IN_BYTES,
+        };
+        let ready = self.state.ready(held_back);
+        self.state.process(false, ready, output);
+    }
+
+    /// Decrypt the rest of the cipher, and check and remove the padding, appending the plain to
+    /// `output`.
+    ///
+    /// * *return* : `Ok(())`, or `Err(AesError::InvalidDataLength)` if the cipher of a block mode
+    ///   is not a multiple of 16 bytes long (or is empty, with a padding), or
+    ///   `Err(AesError::InvalidPadding)`. Nothing is appended in case of error.
+    pub fn finalize(mut self, output: &mut Vec<u8>) -> Result<(), AesError> {
+        let state = &mut self.state;
+        let cipher_bytes = state.processed + state.pending.len() as u64;
+        let mut last = Vec::with_capacity(state.pending.len());
+        let result = state.recipe.check_length(&state.pending).and_then(|_| {
+            if state.recipe.padding() != PaddingScheme::NoPadding
+                && state.pending.len() != BLOCKSIZE_IN_BYTES
+            {
+                return Err(AesError::InvalidDataLength);
+            }
+            state.process(false, state.pending.len(), &mut last);
+            state.recipe.unpad(&mut last)
+        });
+        if let Err(error) = result {
+            metrics::record_error();
+            return Err(error);
+        }
+        output.extend_from_slice(&last);
+        metrics::record(Operation::Decrypt, cipher_bytes);
+        Ok(())
+    }
+}
+
+impl_redacted_debug!(Encryptor, Decryptor);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_of_any_size() {
+        let key = [0x6Bu8; 24];
+        let iv: Vec<u8> = (0x10..0x20).collect();
+        let plain: Vec<u8> = (0..200).map(|i| (i * 13) as u8).collect();
+        let recipes = [
+            "AES-192/ECB/PKCS7",
+            "AES-192/CBC/PKCS7",
+            "AES-192/PCBC/ANSIX923",
+            "AES-192/CBC/NoPadding",
+            "AES-192/CFB/NoPadding",
+            "AES-192/CFB8/NoPadding",
+            "AES-192/OFB/NoPadding",
+            "AES-192/CTR/NoPadding",
+        ];
+        for recipe in recipes {
+            let recipe: Recipe = recipe.parse().unwrap();
+            // Block modes without padding need a multiple of 16 bytes.
+            let plain = match recipe.padding() {
+                PaddingScheme::NoPadding if recipe.mode() == Mode::Cbc => &plain[..192],
+                _ => &plain[..],
+            };
+            let expected = recipe.encrypt(&key, &iv, plain).unwrap();
+            for chunk_len in [1, 15, 16, 17, 64, 500] {
+                let mut encryptor = Encryptor::new(&recipe, &key, &iv).unwrap();
+                let mut cipher = Vec::new();
+                for chunk in plain.chunks(chunk_len) {
+                    encryptor.update(chunk, &mut cipher);
+                }
+                encryptor.finalize(&mut cipher).unwrap();
+                assert_eq!(cipher, expected, "{:?} {}", recipe, chunk_len);
+
+                let mut decryptor = Decryptor::new(&recipe, &key, &iv).unwrap();
+                let mut decrypted = Vec::new();
+                for chunk in cipher.chunks(chunk_len) {
+                    decryptor.update(chunk, &mut decrypted);
+                }
+                decryptor.finalize(&mut decrypted).unwrap();
+                assert_eq!(decrypted, plain, "{:?} {}", recipe, chunk_len);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let key = [0x6Bu8; 16];
+        let iv = [0u8; 16];
+        let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+        assert_eq!(
+            Encryptor::new(&recipe, &key[..15], &iv).err(),
+            Some(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            Decryptor::new(&recipe, &key, &iv[..8]).err(),
+            Some(AesError::InvalidParameter)
+        );
+
+        let mut output = Vec::new();
+        let decryptor = Decryptor::new(&recipe, &key, &iv).unwrap();
+        assert_eq!(
+            decryptor.finalize(&mut output),
+            Err(AesError::InvalidDataLength)
+        );
+        let mut decryptor = Decryptor::new(&recipe, &key, &iv).unwrap();
+        decryptor.update(&[0u8; 40], &mut output);
+        assert_eq!(output.len(), 16);
+        assert_eq!(
+            decryptor.finalize(&mut output),
+            Err(AesError::InvalidDataLength)
+        );
+        // A flipped bit in the last block breaks the padding.
+        let mut padded = recipe.encrypt(&key, &iv, b"").unwrap();
+        padded[15] ^= 1;
+        let mut other = Decryptor::new(&recipe, &key, &iv).unwrap();
+        other.update(&padded, &mut output);
+        assert_eq!(other.finalize(&mut output), Err(AesError::InvalidPadding));
+
+        let recipe: Recipe = "AES-128/ECB/NoPadding".parse().unwrap();
+        let mut encryptor = Encryptor::new(&recipe, &key, &iv).unwrap();
+        encryptor.update(&[0u8; 20], &mut output);
+        assert_eq!(
+            encryptor.finalize(&mut output),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+}
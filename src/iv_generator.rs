@@ -0,0 +1,203 @@
+//! # iv_generator
+//! `iv_generator` provides the [`IvGenerator`] trait, which makes the IV policy of a cipher
+//! explicit, and its common implementations:
+//! * [`RandomIv`]: a random IV from an [`Rng`], for CBC and the other chaining modes.
+//! * [`CounterIv`]: a message counter in the upper half of the initial counter block, for CTR.
+//! * [`Plain64`]: the little-endian sector number, as `plain64` of dm-crypt.
+//! * [`Essiv`]: the encrypted sector number, as `essiv` of dm-crypt.
+//! * [`FixedIv`]: an IV given by the caller.
+//!
+//! [`Recipe::encrypt_with_iv_generator`] asks the generator for the IV of each message.
+//!
+//! [`IvGenerator`]: ../iv_generator/trait.IvGenerator.html
+//! [`RandomIv`]: ../iv_generator/struct.RandomIv.html
+//! [`CounterIv`]: ../iv_generator/struct.CounterIv.html
+//! [`Plain64`]: ../iv_generator/struct.Plain64.html
+//! [`Essiv`]: ../iv_generator/struct.Essiv.html
+//! [`FixedIv`]: ../iv_generator/struct.FixedIv.html
+//! [`Rng`]: ../rng/trait.Rng.html
+//! [`Recipe::encrypt_with_iv_generator`]: ../recipe/struct.Recipe.html#method.encrypt_with_iv_generator
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block_cipher::BlockCipher128;
+use super::rng::Rng;
+
+/// A policy which gives the IV (or initial counter block) of each message.
+/// # Examples
+/// ```
+/// use aes_frast::iv_generator::{IvGenerator, Plain64};
+///
+/// let iv = Plain64.generate_iv(0x0102);
+/// assert_eq!(iv[..2], [0x02, 0x01]);
+/// assert_eq!(iv[2..], [0u8; 14]);
+/// ```
+pub trait IvGenerator {
+    /// Give the IV of the next message.
+    ///
+    /// * *parameter* `index`: the position of the message, such as a sector or record number.
+    ///   The generators which don't depend on the position ignore it.
+    /// * *return* : the IV.
+    fn generate_iv(&mut self, index: u64) -> [u8; BLOCKSIZE_IN_BYTES];
+}
+
+impl<G: IvGenerator + ?Sized> IvGenerator for &mut G {
+    fn generate_iv(&mut self, index: u64) -> [u8; BLOCKSIZE_IN_BYTES] {
+        (**self).generate_iv(index)
+    }
+}
+
+/// Random IVs from an [`Rng`], which must be cryptographically secure.
+///
+/// [`Rng`]: ../rng/trait.Rng.html
+#[derive(Debug, Clone)]
+pub struct RandomIv<R>(pub R);
+
+impl<R: Rng> IvGenerator for RandomIv<R> {
+    fn generate_iv(&mut self, _index: u64) -> [u8; BLOCKSIZE_IN_BYTES] {
+        super::rng::generate_iv(&mut self.0)
+    }
+}
+
+/// Initial counter blocks for CTR: the message counter in the upper 64 bits (big-endian) and
+/// zeros in the lower 64 bits, which the block counter of the message runs through. So messages
+/// never share a counter block, as long as each one is shorter than 2^64 blocks.
+///
+/// These IVs are predictable, so they must not be used with CBC.
+/// # Panics
+/// [`generate_iv`] panics when the counter is exhausted, instead of repeating an IV.
+/// # Examples
+/// ```
+/// use aes_frast::iv_generator::{CounterIv, IvGenerator};
+///
+/// let mut counter = CounterIv::new(7);
+/// assert_eq!(counter.generate_iv(0), (7u128 << 64).to_be_bytes());
+/// assert_eq!(counter.generate_iv(0), (8u128 << 64).to_be_bytes());
+/// ```
+///
+/// [`generate_iv`]: ../iv_generator/trait.IvGenerator.html#tymethod.generate_iv
+#[derive(Debug, Clone)]
+pub struct CounterIv {
+    next: Option<u64>,
+}
+
+impl CounterIv {
+    /// Create the generator, whose first message counter is `start`.
+    pub fn new(start: u64) -> Self {
+        Self { next: Some(start) }
+    }
+}
+
+impl IvGenerator for CounterIv {
+    fn generate_iv(&mut self, _index: u64) -> [u8; BLOCKSIZE_IN_BYTES] {
+        let counter = self.next.expect("The IV counter is exhausted.");
+        self.next = counter.checked_add(1);
+        ((counter as u128) << 64).to_be_bytes()
+    }
+}
+
+/// The `plain64` IVs of dm-crypt: the index (sector number) in little-endian, padded with
+/// zeros.
+///
+/// These IVs are predictable, so CBC with them leaks which sectors start with equal data.
+/// Prefer [`Essiv`] for CBC.
+///
+/// [`Essiv`]: ../iv_generator/struct.Essiv.html
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plain64;
+
+impl IvGenerator for Plain64 {
+    fn generate_iv(&mut self, index: u64) -> [u8; BLOCKSIZE_IN_BYTES] {
+        let mut iv = [0u8; BLOCKSIZE_IN_BYTES];
+        iv[..8].copy_from_slice(&index.to_le_bytes());
+        iv
+    }
+}
+
+/// The `essiv` IVs of dm-crypt: the [`Plain64`] IV encrypted with a salt cipher.
+///
+/// The key of the salt cipher is the hash of the data key (SHA-256 in `aes-cbc-essiv:sha256`),
+/// which is up to the caller since this library has no hash function for it.
+/// # Examples
+/// ```
+/// use aes_frast::iv_generator::{Essiv, IvGenerator, Plain64};
+/// use aes_frast::Aes256;
+///
+/// let salt_key = [0x3Cu8; 32]; // SHA-256 of the data key
+/// let salt_cipher = Aes256::new(&salt_key);
+/// let mut expected = Plain64.generate_iv(9);
+/// salt_cipher.encrypt_block(&mut expected);
+///
+/// let mut essiv = Essiv::new(salt_cipher);
+/// assert_eq!(essiv.generate_iv(9), expected);
+/// ```
+///
+/// [`Plain64`]: ../iv_generator/struct.Plain64.html
+#[derive(Clone)]
+pub struct Essiv<C> {
+    salt_cipher: C,
+}
+
+impl<C: BlockCipher128> Essiv<C> {
+    /// Create the generator from the salt cipher.
+    pub fn new(salt_cipher: C) -> Self {
+        Self { salt_cipher }
+    }
+}
+
+impl<C: BlockCipher128> IvGenerator for Essiv<C> {
+    fn generate_iv(&mut self, index: u64) -> [u8; BLOCKSIZE_IN_BYTES] {
+        let mut iv = Plain64.generate_iv(index);
+        self.salt_cipher.encrypt_block(&mut iv);
+        iv
+    }
+}
+
+/// The same IV for every message, given by the caller, e.g. one received with the cipher.
+///
+/// Never encrypt two messages with the same key and the same fixed IV.
+#[derive(Clone, Copy)]
+pub struct FixedIv(pub [u8; BLOCKSIZE_IN_BYTES]);
+
+impl IvGenerator for FixedIv {
+    fn generate_iv(&mut self, _index: u64) -> [u8; BLOCKSIZE_IN_BYTES] {
+        self.0
+    }
+}
+
+impl_redacted_debug!(Essiv<C>, FixedIv);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(u8);
+
+    impl Rng for Counter {
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest.iter_mut() {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+    }
+
+    #[test]
+    fn generators() {
+        let mut random = RandomIv(Counter(0));
+        assert_eq!(random.generate_iv(0)[15], 0x0F);
+        assert_eq!(random.generate_iv(0)[0], 0x10);
+
+        let mut fixed = FixedIv([0x77u8; 16]);
+        assert_eq!(fixed.generate_iv(3), [0x77u8; 16]);
+
+        let by_reference: &mut dyn IvGenerator = &mut fixed;
+        assert_eq!(by_reference.generate_iv(0), [0x77u8; 16]);
+    }
+
+    #[test]
+    #[should_panic(expected = "exhausted")]
+    fn counter_exhausted() {
+        let mut counter = CounterIv::new(u64::MAX);
+        assert_eq!(counter.generate_iv(0)[..8], [0xFFu8; 8]);
+        counter.generate_iv(0);
+    }
+}
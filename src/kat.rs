@@ -0,0 +1,326 @@
+//! # kat
+//! `kat` generates CAVP-style `.rsp` files with this library: known answer tests (KATs) of ECB,
+//! CBC and CTR, and Monte Carlo tests (MCTs) of ECB and CBC, as described in the AESAVS. The
+//! files can be handed to hardware teams and third-party validators, who compare them with their
+//! own implementation. This mod requires the `kat` feature.
+//!
+//! A file has a `[ENCRYPT]` and a `[DECRYPT]` section, each with numbered cases of lowercase hex
+//! values, like the files of the CAVP:
+//! ```text
+//! # CAVS-style ECBVarTxt128 generated by aes_frast
+//! # State : Encrypt and Decrypt
+//! # Key Length : 128
+//!
+//! [ENCRYPT]
+//!
+//! COUNT = 0
+//! KEY = 00000000000000000000000000000000
+//! PLAINTEXT = 80000000000000000000000000000000
+//! CIPHERTEXT = 3ad78e726c1ec02b7ebfe92b23d9ec34
+//! ...
+//! ```
+//! # Examples
+//! ```
+//! use aes_frast::kat::{kat_rsp, var_txt_cases, KatMode};
+//!
+//! let cases = var_txt_cases(16).unwrap();
+//! assert_eq!(cases.len(), 128);
+//! let rsp = kat_rsp(KatMode::Ecb, "ECBVarTxt128", &cases).unwrap();
+//! assert!(rsp.contains("CIPHERTEXT = 3ad78e726c1ec02b7ebfe92b23d9ec34\n"));
+//! ```
+use super::aes_core::{BLOCKSIZE_IN_BYTES, KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_with_operation_mode as mode;
+use super::error::AesError;
+use super::mct;
+use std::fmt::Write;
+
+/// The operation mode of a `.rsp` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KatMode {
+    /// ECB, without IV.
+    Ecb,
+    /// CBC.
+    Cbc,
+    /// CTR, where the IV is the initial counter block. There is no MCT for CTR.
+    Ctr,
+}
+
+/// A case of a known answer test, with the plaintext. The ciphertext is computed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KatCase {
+    /// The key of 16, 24 or 32 bytes.
+    pub key: Vec<u8>,
+    /// The IV (or initial counter block), not used by ECB.
+    pub iv: [u8; BLOCKSIZE_IN_BYTES],
+    /// The plaintext, a multiple of 16 bytes except for CTR.
+    pub plaintext: Vec<u8>,
+}
+
+/// The cases of the VarKey test of the AESAVS: the `i`-th key has the leading `i + 1` bits set,
+/// and the IV and the plaintext are zeros.
+///
+/// * *parameter* `key_bytes`: the key size in bytes.
+/// * *return* : the cases, or `Err(AesError::InvalidKeyLength)`.
+pub fn var_key_cases(key_bytes: usize) -> Result<Vec<KatCase>, AesError> {
+    check_key_bytes(key_bytes)?;
+    Ok((0..(key_bytes * 8))
+        .map(|i| KatCase {
+            key: leading_ones(key_bytes, i + 1),
+            iv: [0u8; BLOCKSIZE_IN_BYTES],
+            plaintext: vec![0u8; BLOCKSIZE_IN_BYTES],
+        })
+        .collect())
+}
+
+/// The cases of the VarTxt test of the AESAVS: the `i`-th plaintext has the leading `i + 1`
+/// bits set, and the key and the IV are zeros.
+///
+/// * *parameter* `key_bytes`: the key size in bytes.
+/// * *return* : the cases, or `Err(AesError::InvalidKeyLength)`.
+pub fn var_txt_cases(key_bytes: usize) -> Result<Vec<KatCase>, AesError> {
+    check_key_bytes(key_bytes)?;
+    Ok((0..(BLOCKSIZE_IN_BYTES * 8))
+        .map(|i| KatCase {
+            key: vec![0u8; key_bytes],
+            iv: [0u8; BLOCKSIZE_IN_BYTES],
+            plaintext: leading_ones(BLOCKSIZE_IN_BYTES, i + 1),
+        })
+        .collect())
+}
+
+/// Generate the `.rsp` file of a known answer test.
+///
+/// * *parameter* `mode`: the operation mode.
+/// * *parameter* `name`: the name of the test in the header, like `"CBCGFSbox256"`.
+/// * *parameter* `cases`: the cases, which are encrypted in the `[ENCRYPT]` section and decrypted
+///   from the computed ciphertexts in the `[DECRYPT]` section.
+/// * *return* : the file, or `Err(AesError::InvalidKeyLength)` /
+///   `Err(AesError::InvalidDataLength)` for an invalid case.
+pub fn kat_rsp(mode: KatMode, name: &str, cases: &[KatCase]) -> Result<String, AesError> {
+    let mut ciphertexts = Vec::with_capacity(cases.len());
+    for case in cases {
+        if mode != KatMode::Ctr && case.plaintext.len() & 0b1111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        let keys = mct::schedule(&case.key, true)?;
+        let mut ciphertext = vec![0u8; case.plaintext.len()];
+        if !case.plaintext.is_empty() {
+            match mode {
+                KatMode::Ecb => mode::ecb_enc(&case.plaintext, &mut ciphertext, &keys),
+                KatMode::Cbc => mode::cbc_enc(&case.plaintext, &mut ciphertext, &keys, &case.iv),
+                KatMode::Ctr => {
+                    mode::ctr_enc_dec(&case.plaintext, &mut ciphertext, &keys, &case.iv)
+                }
+            };
+        }
+        ciphertexts.push(ciphertext);
+    }
+    let key_bytes = cases.first().map(|case| case.key.len());
+    let same_key_bytes = cases.iter().all(|case| Some(case.key.len()) == key_bytes);
+    let mut rsp = header(name, if same_key_bytes { key_bytes } else { None });
+    for (section, encrypt) in [("ENCRYPT", true), ("DECRYPT", false)] {
+        write!(rsp, "[{}]\n\n", section).unwrap();
+        for (count, (case, ciphertext)) in cases.iter().zip(ciphertexts.iter()).enumerate() {
+            let iv = (mode != KatMode::Ecb).then_some(&case.iv[..]);
+            write_case(
+                &mut rsp,
+                count,
+                &case.key,
+                iv,
+                &case.plaintext,
+                ciphertext,
+                encrypt,
+            );
+        }
+    }
+    Ok(rsp)
+}
+
+/// Generate the `.rsp` file of a Monte Carlo test of ECB or CBC, with 100 cases in each section.
+///
+/// * *parameter* `mode`: the operation mode, `KatMode::Ecb` or `KatMode::Cbc`.
+/// * *parameter* `name`: the name of the test in the header, like `"CBCMCT128"`.
+/// * *parameter* `key`: the initial key of 16, 24 or 32 bytes.
+/// * *parameter* `iv`: the initial IV, not used by ECB.
+/// * *parameter* `plaintext`: the initial plaintext of the `[ENCRYPT]` section.
+/// * *parameter* `ciphertext`: the initial ciphertext of the `[DECRYPT]` section.
+/// * *return* : the file, or `Err(AesError::Unsupported)` for CTR, or
+///   `Err(AesError::InvalidKeyLength)`.
+pub fn mct_rsp(
+    mode: KatMode,
+    name: &str,
+    key: &[u8],
+    iv: &[u8; BLOCKSIZE_IN_BYTES],
+    plaintext: &[u8; BLOCKSIZE_IN_BYTES],
+    ciphertext: &[u8; BLOCKSIZE_IN_BYTES],
+) -> Result<String, AesError> {
+    let cbc = match mode {
+        KatMode::Ecb => false,
+        KatMode::Cbc => true,
+        KatMode::Ctr => return Err(AesError::Unsupported),
+    };
+    let mut rsp = header(name, Some(key.len()));
+    for (section, encrypt, input) in [("ENCRYPT", true, plaintext), ("DECRYPT", false, ciphertext)]
+    {
+        let rounds = mct::monte_carlo(cbc, encrypt, key, *iv, *input)?;
+        write!(rsp, "[{}]\n\n", section).unwrap();
+        for (count, round) in rounds.iter().enumerate() {
+            let iv = cbc.then_some(&round.iv[..]);
+            let (plaintext, ciphertext) = if encrypt {
+                (&round.input, &round.output)
+            } else {
+                (&round.output, &round.input)
+            };
+            write_case(
+                &mut rsp, count, &round.key, iv, plaintext, ciphertext, encrypt,
+            );
+        }
+    }
+    Ok(rsp)
+}
+
+fn check_key_bytes(key_bytes: usize) -> Result<(), AesError> {
+    match key_bytes {
+        KEY_BYTES_128BIT | KEY_BYTES_192BIT | KEY_BYTES_256BIT => Ok(()),
+        _ => Err(AesError::InvalidKeyLength),
+    }
+}
+
+/// `len` bytes with the leading `ones` bits set.
+fn leading_ones(len: usize, ones: usize) -> Vec<u8> {
+    (0..len)
+        .map(|i| match ones.saturating_sub(i * 8) {
+            0 => 0x00,
+            n if n >= 8 => 0xFF,
+            n => 0xFF << (8 - n),
+        })
+        .collect()
+}
+
+fn header(name: &str, key_bytes: Option<usize>) -> String {
+    let mut header = format!(
+        "# CAVS-style {} generated by aes_frast\n# State : Encrypt and Decrypt\n",
+        name
+    );
+    if let Some(key_bytes) = key_bytes {
+        writeln!(header, "# Key Length : {}", key_bytes * 8).unwrap();
+    }
+    header.push('\n');
+    header
+}
+
+fn write_case(
+    rsp: &mut String,
+    count: usize,
+    key: &[u8],
+    iv: Option<&[u8]>,
+    plaintext: &[u8],
+    ciphertext: &[u8],
+    encrypt: bool,
+) {
+    writeln!(rsp, "COUNT = {}", count).unwrap();
+    writeln!(rsp, "KEY = {}", to_hex(key)).unwrap();
+    if let Some(iv) = iv {
+        writeln!(rsp, "IV = {}", to_hex(iv)).unwrap();
+    }
+    if encrypt {
+        writeln!(rsp, "PLAINTEXT = {}", to_hex(plaintext)).unwrap();
+        writeln!(rsp, "CIPHERTEXT = {}\n", to_hex(ciphertext)).unwrap();
+    } else {
+        writeln!(rsp, "CIPHERTEXT = {}", to_hex(ciphertext)).unwrap();
+        writeln!(rsp, "PLAINTEXT = {}\n", to_hex(plaintext)).unwrap();
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kat_rsp_works() {
+        let cases = var_key_cases(16).unwrap();
+        assert_eq!(cases.len(), 128);
+        assert_eq!(cases[0].key[0], 0x80);
+        assert_eq!(cases[127].key, vec![0xFFu8; 16]);
+        let rsp = kat_rsp(KatMode::Cbc, "CBCVarKey128", &cases[..1]).unwrap();
+        assert_eq!(
+            rsp,
+            "# CAVS-style CBCVarKey128 generated by aes_frast\n\
+             # State : Encrypt and Decrypt\n\
+             # Key Length : 128\n\
+             \n\
+             [ENCRYPT]\n\
+             \n\
+             COUNT = 0\n\
+             KEY = 80000000000000000000000000000000\n\
+             IV = 00000000000000000000000000000000\n\
+             PLAINTEXT = 00000000000000000000000000000000\n\
+             CIPHERTEXT = 0edd33d3c621e546455bd8ba1418bec8\n\
+             \n\
+             [DECRYPT]\n\
+             \n\
+             COUNT = 0\n\
+             KEY = 80000000000000000000000000000000\n\
+             IV = 00000000000000000000000000000000\n\
+             CIPHERTEXT = 0edd33d3c621e546455bd8ba1418bec8\n\
+             PLAINTEXT = 00000000000000000000000000000000\n\
+             \n"
+        );
+        let cases = var_txt_cases(32).unwrap();
+        assert_eq!(cases[9].plaintext[..2], [0xFF, 0xC0]);
+        let rsp = kat_rsp(KatMode::Ecb, "ECBVarTxt256", &cases).unwrap();
+        // ECBVarTxt256.rsp, COUNT = 127
+        assert!(rsp.contains("CIPHERTEXT = 4b00c27e8b26da7eab9d3a88dec8b031\n"));
+        assert!(!rsp.contains("IV = "));
+        assert_eq!(var_key_cases(20), Err(AesError::InvalidKeyLength));
+        let odd = KatCase {
+            key: vec![0u8; 16],
+            iv: [0u8; 16],
+            plaintext: vec![0u8; 5],
+        };
+        assert_eq!(
+            kat_rsp(KatMode::Cbc, "Odd", std::slice::from_ref(&odd)),
+            Err(AesError::InvalidDataLength)
+        );
+        assert!(kat_rsp(KatMode::Ctr, "Odd", &[odd]).is_ok());
+    }
+
+    #[test]
+    fn mct_rsp_works() {
+        // The initial values and the first results of ECBMCT128.rsp.
+        let key = [
+            0x13, 0x9A, 0x35, 0x42, 0x2F, 0x1D, 0x61, 0xDE, 0x3C, 0x91, 0x78, 0x7F, 0xE0, 0x50,
+            0x7A, 0xFD,
+        ];
+        let plaintext = [
+            0xB9, 0x14, 0x5A, 0x76, 0x8B, 0x7D, 0xC4, 0x89, 0xA0, 0x96, 0xB5, 0x46, 0xF4, 0x3B,
+            0x23, 0x1F,
+        ];
+        let rsp = mct_rsp(
+            KatMode::Ecb,
+            "ECBMCT128",
+            &key,
+            &[0u8; 16],
+            &plaintext,
+            &plaintext,
+        );
+        let rsp = rsp.unwrap();
+        assert!(rsp.contains("CIPHERTEXT = d7c3ffac9031238650901e157364c386\n"));
+        assert_eq!(rsp.matches("COUNT = ").count(), 200);
+        assert_eq!(
+            mct_rsp(
+                KatMode::Ctr,
+                "CTR",
+                &key,
+                &[0u8; 16],
+                &plaintext,
+                &plaintext
+            ),
+            Err(AesError::Unsupported)
+        );
+    }
+}
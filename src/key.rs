@@ -0,0 +1,533 @@
+//! # key
+//! `key` provides [`Key`], an AES key whose size is chosen at run time and whose bytes are
+//! overwritten with zeros when it is dropped, and the helpers which generate one.
+//!
+//! [`Key128`], [`Key192`] and [`Key256`] are the same for a size known at compile time. A cipher
+//! object is created from them with `From`, so the key length is checked once, when the key is
+//! converted from a slice, and never again.
+//!
+//! [`KeyMaterial`] is key material of any length, such as an unwrapped HMAC key, which is wiped
+//! when it is dropped too.
+//!
+//! [`RoundKeys`] holds the sub-keys scheduled from a key for the functions in
+//! `aes_with_operation_mode`, and wipes them when it is dropped too.
+//!
+//! The wiping is done in safe Rust, so it is best effort: the compiler is told the zeros are
+//! observed, but copies made earlier (by moves, or by the caller) are not wiped.
+//!
+//! [`Key`]: ../key/struct.Key.html
+//! [`Key128`]: ../key/struct.Key128.html
+//! [`Key192`]: ../key/struct.Key192.html
+//! [`Key256`]: ../key/struct.Key256.html
+//! [`KeyMaterial`]: ../key/struct.KeyMaterial.html
+//! [`RoundKeys`]: ../key/struct.RoundKeys.html
+use super::aes_core::{self, KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::error::AesError;
+use super::rng::Rng;
+use super::tag::ct_eq;
+use std::fmt;
+
+/// The three key sizes of AES.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeySize {
+    /// 128bit (16 bytes)
+    Aes128,
+    /// 192bit (24 bytes)
+    Aes192,
+    /// 256bit (32 bytes)
+    Aes256,
+}
+
+impl KeySize {
+    /// The key size in bytes.
+    pub fn bytes(&self) -> usize {
+        match self {
+            KeySize::Aes128 => KEY_BYTES_128BIT,
+            KeySize::Aes192 => KEY_BYTES_192BIT,
+            KeySize::Aes256 => KEY_BYTES_256BIT,
+        }
+    }
+
+    /// The key size in bits.
+    pub fn bits(&self) -> usize {
+        self.bytes() * 8
+    }
+
+    /// The key size of a key of `bytes` bytes, or `Err(AesError::InvalidKeyLength)`.
+    pub fn from_bytes(bytes: usize) -> Result<Self, AesError> {
+        match bytes {
+            KEY_BYTES_128BIT => Ok(KeySize::Aes128),
+            KEY_BYTES_192BIT => Ok(KeySize::Aes192),
+            KEY_BYTES_256BIT => Ok(KeySize::Aes256),
+            _ => Err(AesError::InvalidKeyLength),
+        }
+    }
+}
+
+/// An AES key, which is wiped when it is dropped.
+///
+/// Its `Debug` output shows the size only, never the bytes.
+/// # Examples
+/// ```
+/// use aes_frast::key::{Key, KeySize};
+/// use aes_frast::rng::Rng;
+///
+/// // A deterministic generator for testing. It is NOT random at all.
+/// struct Fixed;
+///
+/// impl Rng for Fixed {
+///     fn fill_bytes(&mut self, dest: &mut [u8]) {
+///         dest.fill(0x5A);
+///     }
+/// }
+///
+/// let key = Key::generate_with(KeySize::Aes192, &mut Fixed);
+/// assert_eq!(key.size(), KeySize::Aes192);
+/// assert_eq!(key.as_bytes(), &[0x5Au8; 24]);
+/// assert_eq!(format!("{:?}", key), "Key { size: Aes192, .. }");
+///
+/// let parsed = Key::new(&[0x2Bu8; 16]).unwrap();
+/// assert_eq!(parsed.size().bits(), 128);
+/// assert!(Key::new(&[0u8; 20]).is_err());
+/// ```
+#[derive(Clone)]
+pub struct Key {
+    bytes: [u8; KEY_BYTES_256BIT],
+    size: KeySize,
+}
+
+impl Key {
+    /// Copy a key from bytes.
+    ///
+    /// * *parameter* `bytes`: the key, which must be 16, 24 or 32 bytes long.
+    /// * *return* : the key, or `Err(AesError::InvalidKeyLength)`.
+    pub fn new(bytes: &[u8]) -> Result<Self, AesError> {
+        let size = KeySize::from_bytes(bytes.len())?;
+        let mut key = Self {
+            bytes: [0u8; KEY_BYTES_256BIT],
+            size,
+        };
+        key.bytes[..bytes.len()].copy_from_slice(bytes);
+        Ok(key)
+    }
+
+    /// Generate a random key of `size` from `rng`, which must be cryptographically secure.
+    pub fn generate_with<R: Rng + ?Sized>(size: KeySize, rng: &mut R) -> Self {
+        let mut key = Self {
+            bytes: [0u8; KEY_BYTES_256BIT],
+            size,
+        };
+        rng.fill_bytes(&mut key.bytes[..size.bytes()]);
+        key
+    }
+
+    /// The key size.
+    pub fn size(&self) -> KeySize {
+        self.size
+    }
+
+    /// The bytes of the key, 16, 24 or 32 of them.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.size.bytes()]
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.bytes.fill(0);
+        std::hint::black_box(&self.bytes);
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Key")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+macro_rules! define_fixed_key {
+    ($(#[$attr:meta])* $name:ident, $key_bytes:expr, $size:expr) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name([u8; $key_bytes]);
+
+        impl $name {
+            /// The key size.
+            pub const SIZE: KeySize = $size;
+
+            /// Generate a random key from `rng`, which must be cryptographically secure.
+            pub fn generate_with<R: Rng + ?Sized>(rng: &mut R) -> Self {
+                let mut key = Self([0u8; $key_bytes]);
+                rng.fill_bytes(&mut key.0);
+                key
+            }
+
+            /// The bytes of the key.
+            pub fn as_bytes(&self) -> &[u8; $key_bytes] {
+                &self.0
+            }
+        }
+
+        impl From<[u8; $key_bytes]> for $name {
+            fn from(bytes: [u8; $key_bytes]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = AesError;
+
+            /// Copy a key from bytes, or return `Err(AesError::InvalidKeyLength)`.
+            fn try_from(bytes: &[u8]) -> Result<Self, AesError> {
+                match bytes.try_into() {
+                    Ok(bytes) => Ok(Self(bytes)),
+                    Err(_) => Err(AesError::InvalidKeyLength),
+                }
+            }
+        }
+
+        impl TryFrom<&Key> for $name {
+            type Error = AesError;
+
+            /// Copy a [`Key`] of the same size, or return `Err(AesError::InvalidKeyLength)`.
+            fn try_from(key: &Key) -> Result<Self, AesError> {
+                Self::try_from(key.as_bytes())
+            }
+        }
+
+        impl From<&$name> for Key {
+            fn from(key: &$name) -> Self {
+                // The length is always valid.
+                Key::new(&key.0).unwrap()
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        /// The comparison is in constant time.
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                ct_eq(&self.0, &other.0)
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                self.0.fill(0);
+                std::hint::black_box(&self.0);
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "(..)"))
+            }
+        }
+    };
+}
+
+define_fixed_key!(
+    /// A **128bit** AES key, which is wiped when it is dropped.
+    ///
+    /// Its `Debug` output never shows the bytes.
+    /// # Examples
+    /// ```
+    /// use aes_frast::key::Key128;
+    /// use aes_frast::{Aes128, AesError};
+    ///
+    /// let bytes = vec![0x2Bu8; 16];
+    /// let key = Key128::try_from(bytes.as_slice()).unwrap();
+    /// assert_eq!(format!("{:?}", key), "Key128(..)");
+    /// assert_eq!(Key128::try_from(&bytes[..15]), Err(AesError::InvalidKeyLength));
+    ///
+    /// // The length was checked by the conversion; creating the cipher object can't fail.
+    /// let aes = Aes128::from(&key);
+    /// assert_eq!(aes.encryption_subkeys(), Aes128::new(&[0x2Bu8; 16]).encryption_subkeys());
+    /// ```
+    Key128,
+    KEY_BYTES_128BIT,
+    KeySize::Aes128
+);
+
+define_fixed_key!(
+    /// A **192bit** AES key, which is wiped when it is dropped.
+    /// # Examples
+    /// Please refer to [`Key128`], they are very similar.
+    ///
+    /// [`Key128`]: ../key/struct.Key128.html
+    Key192,
+    KEY_BYTES_192BIT,
+    KeySize::Aes192
+);
+
+define_fixed_key!(
+    /// A **256bit** AES key, which is wiped when it is dropped.
+    /// # Examples
+    /// Please refer to [`Key128`], they are very similar.
+    ///
+    /// [`Key128`]: ../key/struct.Key128.html
+    Key256,
+    KEY_BYTES_256BIT,
+    KeySize::Aes256
+);
+
+/// Key material of any length, such as a key unwrapped by the [`key_wrap`] mod, which is wiped
+/// when it is dropped.
+///
+/// Its `Debug` output shows the length only, never the bytes.
+/// # Examples
+/// ```
+/// use aes_frast::key::{Key, KeyMaterial, KeySize};
+///
+/// let material = KeyMaterial::new(&[0x5Au8; 24]);
+/// assert_eq!(format!("{:?}", material), "KeyMaterial { len: 24, .. }");
+/// let key = Key::try_from(&material).unwrap();
+/// assert_eq!(key.size(), KeySize::Aes192);
+///
+/// // A 20-byte HMAC key is not an AES key.
+/// assert!(Key::try_from(&KeyMaterial::new(&[0x5Au8; 20])).is_err());
+/// ```
+///
+/// [`key_wrap`]: ../key_wrap/index.html
+#[derive(Clone)]
+pub struct KeyMaterial(Vec<u8>);
+
+impl KeyMaterial {
+    /// Copy key material from bytes.
+    pub fn new(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+
+    pub(crate) fn from_vec(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub(crate) fn as_mut_vec(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+
+    /// The bytes of the key material.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&KeyMaterial> for Key {
+    type Error = AesError;
+
+    /// Copy key material of 16, 24 or 32 bytes, or return `Err(AesError::InvalidKeyLength)`.
+    fn try_from(material: &KeyMaterial) -> Result<Self, AesError> {
+        Key::new(&material.0)
+    }
+}
+
+impl AsRef<[u8]> for KeyMaterial {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// The comparison is in constant time, apart from the lengths.
+impl PartialEq for KeyMaterial {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for KeyMaterial {}
+
+impl Drop for KeyMaterial {
+    fn drop(&mut self) {
+        self.0.fill(0);
+        std::hint::black_box(&self.0);
+    }
+}
+
+impl fmt::Debug for KeyMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyMaterial")
+            .field("len", &self.0.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The sub-keys scheduled from a key, for encryption or for decryption, which are wiped when they
+/// are dropped.
+///
+/// This is an owned replacement of the `u32` buffers of the low-level API. Its `Debug` output
+/// shows the size only, never the words.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode::{cbc_dec, cbc_enc};
+/// use aes_frast::key::{KeySize, RoundKeys};
+///
+/// let key = [0x0Fu8; 32];
+/// let iv = [0x5Au8; 16];
+/// let plain = [0x31u8; 32];
+///
+/// let subkeys = RoundKeys::for_encryption(&key).unwrap();
+/// assert_eq!(subkeys.size(), KeySize::Aes256);
+/// assert_eq!(subkeys.as_slice().len(), 60);
+/// assert_eq!(format!("{:?}", subkeys), "RoundKeys { size: Aes256, .. }");
+/// let mut cipher = [0u8; 32];
+/// cbc_enc(&plain, &mut cipher, subkeys.as_slice(), &iv);
+///
+/// let subkeys = RoundKeys::for_decryption(&key).unwrap();
+/// let mut decrypted = [0u8; 32];
+/// cbc_dec(&cipher, &mut decrypted, subkeys.as_slice(), &iv);
+/// assert_eq!(decrypted, plain);
+///
+/// assert!(RoundKeys::for_encryption(&key[..20]).is_err());
+/// ```
+#[derive(Clone)]
+pub struct RoundKeys {
+    words: [u32; N_SUBKEYS_256BIT],
+    size: KeySize,
+}
+
+impl RoundKeys {
+    /// Schedule `key` to the sub-keys for **encryption**.
+    ///
+    /// * *parameter* `key`: the key, which must be 16, 24 or 32 bytes long.
+    /// * *return* : the sub-keys, or `Err(AesError::InvalidKeyLength)`.
+    pub fn for_encryption(key: &[u8]) -> Result<Self, AesError> {
+        let mut round_keys = Self::empty(key)?;
+        let n = round_keys.as_slice().len();
+        aes_core::key_schedule_encrypt_auto(key, &mut round_keys.words[..n]);
+        Ok(round_keys)
+    }
+
+    /// Schedule `key` to the sub-keys for **decryption**.
+    ///
+    /// * *parameter* `key`: the key, which must be 16, 24 or 32 bytes long.
+    /// * *return* : the sub-keys, or `Err(AesError::InvalidKeyLength)`.
+    pub fn for_decryption(key: &[u8]) -> Result<Self, AesError> {
+        let mut round_keys = Self::empty(key)?;
+        let n = round_keys.as_slice().len();
+        aes_core::key_schedule_decrypt_auto(key, &mut round_keys.words[..n]);
+        Ok(round_keys)
+    }
+
+    fn empty(key: &[u8]) -> Result<Self, AesError> {
+        Ok(Self {
+            words: [0u32; N_SUBKEYS_256BIT],
+            size: KeySize::from_bytes(key.len())?,
+        })
+    }
+
+    /// The size of the key they were scheduled from.
+    pub fn size(&self) -> KeySize {
+        self.size
+    }
+
+    /// The sub-keys, 44, 52 or 60 words of them, for the `keys` parameter of the functions in
+    /// `aes_with_operation_mode`.
+    pub fn as_slice(&self) -> &[u32] {
+        let n = match self.size {
+            KeySize::Aes128 => N_SUBKEYS_128BIT,
+            KeySize::Aes192 => N_SUBKEYS_192BIT,
+            KeySize::Aes256 => N_SUBKEYS_256BIT,
+        };
+        &self.words[..n]
+    }
+}
+
+impl Drop for RoundKeys {
+    fn drop(&mut self) {
+        self.words.fill(0);
+        std::hint::black_box(&self.words);
+    }
+}
+
+impl fmt::Debug for RoundKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RoundKeys")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Generate a random key of `size` with the random number generator of the OS.
+///
+/// Only available with the `getrandom` feature.
+/// # Examples
+/// ```
+/// use aes_frast::key::{generate_key, KeySize};
+/// use aes_frast::Aes256;
+///
+/// let key = generate_key(KeySize::Aes256);
+/// let aes = Aes256::new(key.as_bytes().try_into().unwrap());
+/// ```
+/// # Panics
+/// This function panics if the OS fails to provide random bytes.
+#[cfg(feature = "getrandom")]
+pub fn generate_key(size: KeySize) -> Key {
+    Key::generate_with(size, &mut super::rng::OsRng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_sizes() {
+        for size in [KeySize::Aes128, KeySize::Aes192, KeySize::Aes256] {
+            assert_eq!(KeySize::from_bytes(size.bytes()), Ok(size));
+            let key = Key::new(&vec![0xA5u8; size.bytes()]).unwrap();
+            assert_eq!(key.as_bytes().len(), size.bits() / 8);
+        }
+        assert_eq!(KeySize::from_bytes(0), Err(AesError::InvalidKeyLength));
+    }
+
+    #[test]
+    fn fixed_keys() {
+        let bytes: Vec<u8> = (0..32).collect();
+        let key = Key192::try_from(&bytes[..24]).unwrap();
+        assert_eq!(key.as_bytes()[..], bytes[..24]);
+        assert_eq!(Key192::SIZE.bytes(), 24);
+        let any = Key::from(&key);
+        assert_eq!(any.size(), KeySize::Aes192);
+        assert_eq!(Key192::try_from(&any), Ok(key));
+        assert_eq!(Key256::try_from(&any), Err(AesError::InvalidKeyLength));
+        assert_eq!(Key256::from([7u8; 32]).as_ref(), &[7u8; 32]);
+        assert_eq!(format!("{:?}", Key256::from([7u8; 32])), "Key256(..)");
+    }
+
+    #[test]
+    fn round_keys_match_the_schedule() {
+        for size in [KeySize::Aes128, KeySize::Aes192, KeySize::Aes256] {
+            let key: Vec<u8> = (0..size.bytes() as u8).collect();
+            let mut encryption = vec![0u32; size.bytes() + 28];
+            let mut decryption = encryption.clone();
+            aes_core::key_schedule_encrypt_decrypt_auto(&key, &mut encryption, &mut decryption);
+            let round_keys = RoundKeys::for_encryption(&key).unwrap();
+            assert_eq!(round_keys.as_slice(), encryption);
+            assert_eq!(round_keys.size(), size);
+            assert_eq!(
+                RoundKeys::for_decryption(&key).unwrap().as_slice(),
+                decryption
+            );
+        }
+        assert_eq!(
+            RoundKeys::for_decryption(&[0u8; 8]).err(),
+            Some(AesError::InvalidKeyLength)
+        );
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn os_keys_differ() {
+        let a = generate_key(KeySize::Aes128);
+        let b = generate_key(KeySize::Aes128);
+        assert_ne!(a.as_bytes(), b.as_bytes());
+    }
+}
@@ -0,0 +1,439 @@
+//! # key_wrap
+//! `key_wrap` provides the AES Key Wrap algorithms of NIST SP 800-38F, which protect key material
+//! with a key-encryption key (KEK):
+//! * [`wrap`] and [`unwrap`]: KW (RFC 3394), for keys which are a multiple of 8 bytes, at least
+//!   16 bytes long.
+//! * [`wrap_with_padding`] and [`unwrap_with_padding`]: KWP (RFC 5649), for key material of any
+//!   length from 1 byte.
+//!
+//! The [`WrappedKey`] is 8 bytes longer than the (padded) key. Unwrapping checks the integrity
+//! of the wrapped key and returns `Err(AesError::TagMismatch)` on failure, never the unchecked
+//! key. The key comes back as [`KeyMaterial`], which is wiped when it is dropped.
+//!
+//! [`WrappedKey`]: ../key_wrap/struct.WrappedKey.html
+//! [`KeyMaterial`]: ../key/struct.KeyMaterial.html
+//! [`wrap`]: ../key_wrap/fn.wrap.html
+//! [`unwrap`]: ../key_wrap/fn.unwrap.html
+//! [`wrap_with_padding`]: ../key_wrap/fn.wrap_with_padding.html
+//! [`unwrap_with_padding`]: ../key_wrap/fn.unwrap_with_padding.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::key::KeyMaterial;
+use super::tag::verify_tag;
+use std::fmt;
+use std::str::FromStr;
+
+const SEMIBLOCK: usize = 8;
+
+/// The default initial value of KW.
+pub const DEFAULT_IV: [u8; SEMIBLOCK] = [0xA6; SEMIBLOCK];
+/// The first half of the alternative initial value of KWP, which is followed by the 32-bit
+/// big-endian length of the key.
+pub const KWP_IV_PREFIX: [u8; 4] = [0xA6, 0x59, 0x59, 0xA6];
+
+/// A key wrapped with KW or KWP, which is wiped when it is dropped.
+///
+/// Its length is checked when it is created: a multiple of 8 bytes, at least 16 bytes. It can be
+/// parsed from (and displayed as) hexadecimal.
+/// # Examples
+/// ```
+/// use aes_frast::key_wrap::WrappedKey;
+/// use aes_frast::AesError;
+///
+/// let wrapped: WrappedKey = "1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5".parse().unwrap();
+/// assert_eq!(wrapped.as_bytes().len(), 24);
+/// assert_eq!(
+///     wrapped.to_string(),
+///     "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5"
+/// );
+///
+/// assert_eq!(WrappedKey::new(&[0u8; 20]), Err(AesError::InvalidDataLength));
+/// assert_eq!("1FA68B0A".parse::<WrappedKey>(), Err(AesError::InvalidDataLength));
+/// assert_eq!("not hex".parse::<WrappedKey>(), Err(AesError::InvalidParameter));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct WrappedKey(Vec<u8>);
+
+impl WrappedKey {
+    /// Copy a wrapped key from bytes.
+    ///
+    /// * *parameter* `bytes`: the wrapped key, a multiple of 8 bytes, at least 16 bytes.
+    /// * *return* : the wrapped key, or `Err(AesError::InvalidDataLength)`.
+    pub fn new(bytes: &[u8]) -> Result<Self, AesError> {
+        Self::from_vec(bytes.to_vec())
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> Result<Self, AesError> {
+        if bytes.len() < 2 * SEMIBLOCK || bytes.len() & 0b111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        Ok(Self(bytes))
+    }
+
+    /// The bytes of the wrapped key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for WrappedKey {
+    type Error = AesError;
+
+    /// Copy a wrapped key from bytes, see [`new`].
+    ///
+    /// [`new`]: ../key_wrap/struct.WrappedKey.html#method.new
+    fn try_from(bytes: &[u8]) -> Result<Self, AesError> {
+        Self::new(bytes)
+    }
+}
+
+impl AsRef<[u8]> for WrappedKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for WrappedKey {
+    type Err = AesError;
+
+    /// Parse a wrapped key from hexadecimal, case-insensitive, or return
+    /// `Err(AesError::InvalidParameter)` for a malformed string and
+    /// `Err(AesError::InvalidDataLength)` for a wrong length.
+    fn from_str(s: &str) -> Result<Self, AesError> {
+        if s.len() & 1 != 0 || !s.is_ascii() {
+            return Err(AesError::InvalidParameter);
+        }
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| AesError::InvalidParameter)?;
+        Self::from_vec(bytes)
+    }
+}
+
+impl fmt::Display for WrappedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl fmt::Debug for WrappedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("WrappedKey")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl Drop for WrappedKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+        std::hint::black_box(&self.0);
+    }
+}
+
+/// Wrap `key` with KW.
+///
+/// * *parameter* `kek`: the cipher of the key-encryption key.
+/// * *parameter* `key`: the key to wrap, a multiple of 8 bytes, at least 16 bytes.
+/// * *return* : the wrapped key, 8 bytes longer than `key`, or
+///   `Err(AesError::InvalidDataLength)`.
+/// # Examples
+/// ```
+/// use aes_frast::key_wrap::{unwrap, wrap};
+/// use aes_frast::Aes128;
+///
+/// // This example came from RFC 3394, section 4.1.
+/// let kek = Aes128::new(&[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+///                         0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F]);
+/// let key = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+///            0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF];
+/// let wrapped = wrap(&kek, &key).unwrap();
+/// assert_eq!(wrapped.as_bytes(), [0x1F, 0xA6, 0x8B, 0x0A, 0x81, 0x12, 0xB4, 0x47,
+///                                 0xAE, 0xF3, 0x4B, 0xD8, 0xFB, 0x5A, 0x7B, 0x82,
+///                                 0x9D, 0x3E, 0x86, 0x23, 0x71, 0xD2, 0xCF, 0xE5]);
+/// assert_eq!(unwrap(&kek, &wrapped).unwrap().as_bytes(), key);
+/// ```
+pub fn wrap<C: BlockCipher128 + ?Sized>(kek: &C, key: &[u8]) -> Result<WrappedKey, AesError> {
+    wrap_with_iv(kek, DEFAULT_IV, key)
+}
+
+/// [`wrap`] with another initial value than [`DEFAULT_IV`].
+///
+/// [`wrap`]: ../key_wrap/fn.wrap.html
+/// [`DEFAULT_IV`]: ../key_wrap/constant.DEFAULT_IV.html
+pub(crate) fn wrap_with_iv<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    iv: [u8; SEMIBLOCK],
+    key: &[u8],
+) -> Result<WrappedKey, AesError> {
+    if key.len() < 2 * SEMIBLOCK || key.len() & 0b111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(WrappedKey(wrapping_function(kek, iv, key)))
+}
+
+/// Unwrap a key wrapped with KW.
+///
+/// * *parameter* `kek`: the cipher of the key-encryption key.
+/// * *parameter* `wrapped`: the wrapped key, at least 24 bytes.
+/// * *return* : the key, or `Err(AesError::InvalidDataLength)` for a wrong length, or
+///   `Err(AesError::TagMismatch)` if the integrity check fails.
+pub fn unwrap<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    wrapped: &WrappedKey,
+) -> Result<KeyMaterial, AesError> {
+    unwrap_with_iv(kek, DEFAULT_IV, wrapped)
+}
+
+/// [`unwrap`] with another initial value than [`DEFAULT_IV`].
+///
+/// [`unwrap`]: ../key_wrap/fn.unwrap.html
+/// [`DEFAULT_IV`]: ../key_wrap/constant.DEFAULT_IV.html
+pub(crate) fn unwrap_with_iv<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    expected_iv: [u8; SEMIBLOCK],
+    wrapped: &WrappedKey,
+) -> Result<KeyMaterial, AesError> {
+    if wrapped.0.len() < 3 * SEMIBLOCK {
+        return Err(AesError::InvalidDataLength);
+    }
+    let (iv, key) = unwrapping_function(kek, &wrapped.0);
+    // The key is wiped when it is dropped on failure.
+    let key = KeyMaterial::from_vec(key);
+    verify_tag(&expected_iv, &iv)?;
+    Ok(key)
+}
+
+/// Wrap `key` with KWP, padding it with zeros to a multiple of 8 bytes.
+///
+/// * *parameter* `kek`: the cipher of the key-encryption key.
+/// * *parameter* `key`: the key material to wrap, from 1 byte to `2^32 - 1` bytes.
+/// * *return* : the wrapped key, or `Err(AesError::InvalidDataLength)`.
+/// # Examples
+/// ```
+/// use aes_frast::key_wrap::{unwrap_with_padding, wrap_with_padding, WrappedKey};
+/// use aes_frast::Aes256;
+///
+/// let kek = Aes256::new(&[0x42u8; 32]);
+/// // A 20-byte HMAC key, which plain KW can't wrap.
+/// let key = [0x5Au8; 20];
+/// let wrapped = wrap_with_padding(&kek, &key).unwrap();
+/// assert_eq!(wrapped.as_bytes().len(), 32);
+/// assert_eq!(unwrap_with_padding(&kek, &wrapped).unwrap().as_bytes(), key);
+///
+/// let mut forged = wrapped.as_bytes().to_vec();
+/// forged[5] ^= 1;
+/// let forged = WrappedKey::new(&forged).unwrap();
+/// assert!(unwrap_with_padding(&kek, &forged).is_err());
+/// ```
+pub fn wrap_with_padding<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    key: &[u8],
+) -> Result<WrappedKey, AesError> {
+    if key.is_empty() || key.len() > u32::MAX as usize {
+        return Err(AesError::InvalidDataLength);
+    }
+    let mut iv = [0u8; SEMIBLOCK];
+    iv[..4].copy_from_slice(&KWP_IV_PREFIX);
+    iv[4..].copy_from_slice(&(key.len() as u32).to_be_bytes());
+    // The padded copy of the key is wiped when it is dropped.
+    let mut padded = KeyMaterial::from_vec(key.to_vec());
+    padded
+        .as_mut_vec()
+        .resize(key.len().div_ceil(SEMIBLOCK) * SEMIBLOCK, 0);
+    let padded = padded.as_bytes();
+    if padded.len() == SEMIBLOCK {
+        // A single semiblock is encrypted as one block with the IV.
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[..SEMIBLOCK].copy_from_slice(&iv);
+        block[SEMIBLOCK..].copy_from_slice(padded);
+        kek.encrypt_block(&mut block);
+        return Ok(WrappedKey(block.to_vec()));
+    }
+    Ok(WrappedKey(wrapping_function(kek, iv, padded)))
+}
+
+/// Unwrap a key wrapped with KWP.
+///
+/// * *parameter* `kek`: the cipher of the key-encryption key.
+/// * *parameter* `wrapped`: the wrapped key.
+/// * *return* : the key material, or `Err(AesError::TagMismatch)` if the integrity check (of
+///   the IV, the length or the padding) fails.
+pub fn unwrap_with_padding<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    wrapped: &WrappedKey,
+) -> Result<KeyMaterial, AesError> {
+    let wrapped = &wrapped.0;
+    let (iv, padded) = if wrapped.len() == 2 * SEMIBLOCK {
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block.copy_from_slice(wrapped);
+        kek.decrypt_block(&mut block);
+        let mut iv = [0u8; SEMIBLOCK];
+        iv.copy_from_slice(&block[..SEMIBLOCK]);
+        (iv, block[SEMIBLOCK..].to_vec())
+    } else {
+        unwrapping_function(kek, wrapped)
+    };
+    // The length must fit in the last semiblock, and the padding must be zeros.
+    // The key is wiped when it is dropped on failure.
+    let mut key = KeyMaterial::from_vec(padded);
+    let padded = key.as_bytes();
+    let length = u32::from_be_bytes(iv[4..].try_into().unwrap()) as usize;
+    let length_fits = length <= padded.len() && length + SEMIBLOCK > padded.len();
+    let padding_is_zero = length_fits && padded[length..].iter().all(|&b| b == 0);
+    if verify_tag(&KWP_IV_PREFIX, &iv[..4]).is_err() || !padding_is_zero {
+        return Err(AesError::TagMismatch);
+    }
+    key.as_mut_vec().truncate(length);
+    Ok(key)
+}
+
+/// W of SP 800-38F, the index-based form of RFC 3394: 6 rounds over the semiblocks of `data`.
+fn wrapping_function<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    iv: [u8; SEMIBLOCK],
+    data: &[u8],
+) -> Vec<u8> {
+    let n = data.len() / SEMIBLOCK;
+    let mut a = iv;
+    let mut r = data.to_vec();
+    let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+    for j in 0..6 {
+        for (i, semiblock) in r.chunks_exact_mut(SEMIBLOCK).enumerate() {
+            block[..SEMIBLOCK].copy_from_slice(&a);
+            block[SEMIBLOCK..].copy_from_slice(semiblock);
+            kek.encrypt_block(&mut block);
+            let t = (n * j + i + 1) as u64;
+            for (a_byte, (b_byte, t_byte)) in
+                a.iter_mut().zip(block.iter().zip(t.to_be_bytes().iter()))
+            {
+                *a_byte = b_byte ^ t_byte;
+            }
+            semiblock.copy_from_slice(&block[SEMIBLOCK..]);
+        }
+    }
+    let mut wrapped = a.to_vec();
+    wrapped.extend_from_slice(&r);
+    wrapped
+}
+
+/// W^-1 of SP 800-38F. It returns the recovered IV and the semiblocks, which are unchecked.
+fn unwrapping_function<C: BlockCipher128 + ?Sized>(
+    kek: &C,
+    wrapped: &[u8],
+) -> ([u8; SEMIBLOCK], Vec<u8>) {
+    let n = wrapped.len() / SEMIBLOCK - 1;
+    let mut a = [0u8; SEMIBLOCK];
+    a.copy_from_slice(&wrapped[..SEMIBLOCK]);
+    let mut r = wrapped[SEMIBLOCK..].to_vec();
+    let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+    for j in (0..6).rev() {
+        for (i, semiblock) in r.chunks_exact_mut(SEMIBLOCK).enumerate().rev() {
+            let t = (n * j + i + 1) as u64;
+            for (b_byte, (a_byte, t_byte)) in
+                block.iter_mut().zip(a.iter().zip(t.to_be_bytes().iter()))
+            {
+                *b_byte = a_byte ^ t_byte;
+            }
+            block[SEMIBLOCK..].copy_from_slice(semiblock);
+            kek.decrypt_block(&mut block);
+            a.copy_from_slice(&block[..SEMIBLOCK]);
+            semiblock.copy_from_slice(&block[SEMIBLOCK..]);
+        }
+    }
+    (a, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::unhex;
+    use crate::{Aes192, Aes256};
+
+    #[test]
+    fn rfc3394_256bit_kek() {
+        // RFC 3394, section 4.6.
+        let kek = Aes256::new(&core::array::from_fn(|i| i as u8));
+        let key = unhex("00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f");
+        let wrapped = wrap(&kek, &key).unwrap();
+        assert_eq!(
+            wrapped.as_bytes(),
+            unhex(
+                "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326\
+                 cbc7f0e71a99f43bfb988b9b7a02dd21"
+            )
+        );
+        assert_eq!(unwrap(&kek, &wrapped).unwrap().as_bytes(), key);
+        assert_eq!(wrapped.to_string().parse(), Ok(wrapped.clone()));
+
+        let mut forged = wrapped.as_bytes().to_vec();
+        forged[30] ^= 0x80;
+        let forged = WrappedKey::new(&forged).unwrap();
+        assert_eq!(unwrap(&kek, &forged), Err(AesError::TagMismatch));
+        assert_eq!(wrap(&kek, &[0u8; 8]), Err(AesError::InvalidDataLength));
+        assert_eq!(wrap(&kek, &[0u8; 20]), Err(AesError::InvalidDataLength));
+        let short = WrappedKey::new(&[0u8; 16]).unwrap();
+        assert_eq!(unwrap(&kek, &short), Err(AesError::InvalidDataLength));
+        assert_eq!(
+            WrappedKey::try_from(&[0u8; 8][..]),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+
+    #[test]
+    fn rfc5649() {
+        // RFC 5649, section 6.
+        let kek = Aes192::new(
+            unhex("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8")[..]
+                .try_into()
+                .unwrap(),
+        );
+        let cases = [
+            (
+                "c37b7e6492584340bed12207808941155068f738",
+                "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+            ),
+            ("466f7250617369", "afbeb0f07dfbf5419200f2ccb50bb24f"),
+        ];
+        for (key, expected) in cases {
+            let key = unhex(key);
+            let wrapped = wrap_with_padding(&kek, &key).unwrap();
+            assert_eq!(wrapped.as_bytes(), unhex(expected));
+            assert_eq!(unwrap_with_padding(&kek, &wrapped).unwrap().as_bytes(), key);
+        }
+        assert_eq!(
+            wrap_with_padding(&kek, &[]),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+
+    #[test]
+    fn kwp_integrity() {
+        let kek = Aes256::new(&[0x24u8; 32]);
+        for length in 1..40 {
+            let key = vec![0xC3u8; length];
+            let wrapped = wrap_with_padding(&kek, &key).unwrap();
+            let bytes = wrapped.as_bytes();
+            assert_eq!(bytes.len(), length.div_ceil(8) * 8 + 8);
+            assert_eq!(unwrap_with_padding(&kek, &wrapped).unwrap().as_bytes(), key);
+            for i in 0..bytes.len() {
+                let mut forged = bytes.to_vec();
+                forged[i] ^= 1;
+                let forged = WrappedKey::new(&forged).unwrap();
+                assert_eq!(
+                    unwrap_with_padding(&kek, &forged),
+                    Err(AesError::TagMismatch)
+                );
+            }
+        }
+        // A KW wrapping is not a valid KWP wrapping.
+        let wrapped = wrap(&kek, &[0x11u8; 16]).unwrap();
+        assert_eq!(
+            unwrap_with_padding(&kek, &wrapped),
+            Err(AesError::TagMismatch)
+        );
+    }
+}
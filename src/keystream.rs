@@ -0,0 +1,312 @@
+//! # keystream
+//! `keystream` provides [`BackgroundKeystream`], a CTR or OFB keystream which is computed ahead
+//! of use on a worker thread.
+//!
+//! The keystream of CTR and OFB modes doesn't depend on the data, so it can be prepared before
+//! the data arrives. Then encrypting (or decrypting) a packet only costs copying and XORing.
+//!
+//! The worker fills a bounded buffer up to the *high watermark*, then sleeps until the buffer
+//! drains down to the *low watermark*. See [`PrefetchConfig`].
+//!
+//! [`BackgroundKeystream`]: ../keystream/struct.BackgroundKeystream.html
+//! [`PrefetchConfig`]: ../keystream/struct.PrefetchConfig.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::aes_with_operation_mode as mode;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// The buffering settings of a [`BackgroundKeystream`], in bytes.
+///
+/// [`BackgroundKeystream`]: ../keystream/struct.BackgroundKeystream.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefetchConfig {
+    /// The size of each piece of keystream the worker computes. It must be a positive multiple
+    /// of 16.
+    pub chunk_size: usize,
+    /// The worker resumes when the buffered keystream drops to this size.
+    pub low_watermark: usize,
+    /// The worker pauses when the buffered keystream reaches this size. It must be greater than
+    /// `low_watermark`.
+    pub high_watermark: usize,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 4096,
+            low_watermark: 16 * 1024,
+            high_watermark: 64 * 1024,
+        }
+    }
+}
+
+type ModeFunction = fn(&[u8], &mut [u8], &[u32], &[u8]) -> Vec<u8>;
+
+#[derive(Default)]
+struct State {
+    chunks: VecDeque<Vec<u8>>,
+    buffered: usize,
+    stopped: bool,
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        for chunk in self.chunks.iter_mut() {
+            chunk.fill(0);
+        }
+        std::hint::black_box(&self.chunks);
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Signaled by the worker when a chunk is added.
+    filled: Condvar,
+    // Signaled by the consumer when the buffer reaches the low watermark, or on drop.
+    drained: Condvar,
+}
+
+/// A CTR or OFB keystream computed ahead of use on a worker thread.
+///
+/// The worker thread stops when this object is dropped, and the copy of the key schedule and
+/// the buffered keystream are wiped.
+/// # Examples
+/// ```
+/// use aes_frast::aes_core::key_schedule_encrypt128;
+/// use aes_frast::aes_with_operation_mode::ctr_enc_dec;
+/// use aes_frast::keystream::{BackgroundKeystream, PrefetchConfig};
+/// use aes_frast::N_SUBKEYS_128BIT;
+///
+/// let mut w_keys = [0u32; N_SUBKEYS_128BIT];
+/// key_schedule_encrypt128(&[0x42u8; 16], &mut w_keys);
+/// let counter = [0u8; 16];
+///
+/// let config = PrefetchConfig {
+///     chunk_size: 1024,
+///     low_watermark: 2048,
+///     high_watermark: 8192,
+/// };
+/// let mut keystream = BackgroundKeystream::ctr(&w_keys, &counter, config);
+///
+/// // Packets of any length, one after another.
+/// let mut packet1 = vec![0x11u8; 100];
+/// let mut packet2 = vec![0x22u8; 1500];
+/// keystream.apply_keystream(&mut packet1);
+/// keystream.apply_keystream(&mut packet2);
+///
+/// // The same as CTR mode over the whole data.
+/// let mut plain = vec![0x11u8; 100];
+/// plain.extend_from_slice(&[0x22u8; 1500]);
+/// let mut expected = vec![0u8; plain.len()];
+/// ctr_enc_dec(&plain, &mut expected, &w_keys, &counter);
+/// assert_eq!(packet1, &expected[..100]);
+/// assert_eq!(packet2, &expected[100..]);
+/// ```
+pub struct BackgroundKeystream {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+    current: Vec<u8>,
+    position: usize,
+}
+
+impl BackgroundKeystream {
+    /// Start computing the keystream of **CTR** mode, see [`ctr_enc_dec`].
+    ///
+    /// * *parameter* `keys`: the scheduled sub-keys for **encryption**.
+    /// * *parameter* `counter`: the initial counter block (16 bytes).
+    /// * *parameter* `config`: the buffering settings.
+    ///
+    /// [`ctr_enc_dec`]: ../aes_with_operation_mode/fn.ctr_enc_dec.html
+    pub fn ctr(keys: &[u32], counter: &[u8], config: PrefetchConfig) -> Self {
+        Self::start(keys, counter, config, mode::ctr_enc_dec)
+    }
+
+    /// Start computing the keystream of **OFB** mode, see [`ofb_enc_dec`].
+    ///
+    /// * *parameter* `keys`: the scheduled sub-keys for **encryption**.
+    /// * *parameter* `iv`: the initialization vector (16 bytes).
+    /// * *parameter* `config`: the buffering settings.
+    ///
+    /// [`ofb_enc_dec`]: ../aes_with_operation_mode/fn.ofb_enc_dec.html
+    pub fn ofb(keys: &[u32], iv: &[u8], config: PrefetchConfig) -> Self {
+        Self::start(keys, iv, config, mode::ofb_enc_dec)
+    }
+
+    fn start(keys: &[u32], iv: &[u8], config: PrefetchConfig, function: ModeFunction) -> Self {
+        assert!(config.chunk_size > 0 && config.chunk_size & 0b1111 == 0);
+        assert!(config.low_watermark < config.high_watermark);
+        assert_eq!(iv.len(), BLOCKSIZE_IN_BYTES);
+        // Check the keys here, so the worker never panics and leaves the consumer waiting.
+        assert!(
+            keys.len() == N_SUBKEYS_128BIT
+                || keys.len() == N_SUBKEYS_192BIT
+                || keys.len() == N_SUBKEYS_256BIT,
+            "Invalid key length."
+        );
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            filled: Condvar::new(),
+            drained: Condvar::new(),
+        });
+        let worker_shared = Arc::clone(&shared);
+        let mut keys = keys.to_vec();
+        let mut iv = iv.to_vec();
+        let zeros = vec![0u8; config.chunk_size];
+        let worker = thread::spawn(move || {
+            loop {
+                let mut chunk = vec![0u8; config.chunk_size];
+                // The keystream is the output of the mode on zeros.
+                let next_iv = function(&zeros, &mut chunk, &keys, &iv);
+                iv.fill(0);
+                iv = next_iv;
+                let mut state = worker_shared.state.lock().unwrap();
+                if state.stopped {
+                    chunk.fill(0);
+                    std::hint::black_box(&chunk);
+                    break;
+                }
+                state.buffered += chunk.len();
+                state.chunks.push_back(chunk);
+                worker_shared.filled.notify_one();
+                if state.buffered >= config.high_watermark {
+                    state = worker_shared
+                        .drained
+                        .wait_while(state, |s| !s.stopped && s.buffered > config.low_watermark)
+                        .unwrap();
+                    if state.stopped {
+                        break;
+                    }
+                }
+            }
+            // The buffered chunks are wiped with `State`.
+            keys.fill(0);
+            iv.fill(0);
+            std::hint::black_box((&keys, &iv));
+        });
+        Self {
+            shared,
+            worker: Some(worker),
+            current: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// XOR the next bytes of the keystream into `data`, i.e. encrypt or decrypt it.
+    ///
+    /// This function waits for the worker if the buffered keystream is not enough.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        let mut done = 0;
+        while done < data.len() {
+            if self.position == self.current.len() {
+                self.current.fill(0);
+                self.current = self.next_chunk();
+                self.position = 0;
+            }
+            let n = (data.len() - done).min(self.current.len() - self.position);
+            for (x, k) in data[done..(done + n)]
+                .iter_mut()
+                .zip(&self.current[self.position..(self.position + n)])
+            {
+                *x ^= k;
+            }
+            done += n;
+            self.position += n;
+        }
+    }
+
+    fn next_chunk(&mut self) -> Vec<u8> {
+        let state = self.shared.state.lock().unwrap();
+        let mut state = self
+            .shared
+            .filled
+            .wait_while(state, |s| s.chunks.is_empty())
+            .unwrap();
+        let chunk = state.chunks.pop_front().unwrap();
+        state.buffered -= chunk.len();
+        // The worker checks the low watermark itself.
+        self.shared.drained.notify_one();
+        chunk
+    }
+}
+
+impl Drop for BackgroundKeystream {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.drained.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        self.current.fill(0);
+        std::hint::black_box(&self.current);
+    }
+}
+
+impl_redacted_debug!(BackgroundKeystream);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core;
+
+    fn setup() -> (Vec<u32>, [u8; BLOCKSIZE_IN_BYTES], Vec<u8>) {
+        let mut keys = vec![0u32; N_SUBKEYS_256BIT];
+        aes_core::key_schedule_encrypt256(&[0xA5u8; 32], &mut keys);
+        let iv: [u8; BLOCKSIZE_IN_BYTES] = core::array::from_fn(|i| i as u8);
+        let plain: Vec<u8> = (0..5000u32).map(|i| (i * 7) as u8).collect();
+        (keys, iv, plain)
+    }
+
+    #[test]
+    fn ctr_and_ofb_work() {
+        let (keys, iv, plain) = setup();
+        // Small watermarks make the worker pause and resume many times.
+        let config = PrefetchConfig {
+            chunk_size: 64,
+            low_watermark: 64,
+            high_watermark: 256,
+        };
+        type StartFunction = fn(&[u32], &[u8], PrefetchConfig) -> BackgroundKeystream;
+        let cases: [(ModeFunction, StartFunction); 2] = [
+            (mode::ctr_enc_dec, BackgroundKeystream::ctr),
+            (mode::ofb_enc_dec, BackgroundKeystream::ofb),
+        ];
+        for (function, start) in cases {
+            let mut expected = vec![0u8; plain.len()];
+            function(&plain, &mut expected, &keys, &iv);
+            let mut keystream = start(&keys, &iv, config);
+            let mut data = plain.clone();
+            for piece in data.chunks_mut(37) {
+                keystream.apply_keystream(piece);
+            }
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    fn drop_stops_worker() {
+        let (keys, iv, _) = setup();
+        // The worker is waiting at the high watermark when dropped.
+        let keystream = BackgroundKeystream::ctr(&keys, &iv, PrefetchConfig::default());
+        thread::sleep(std::time::Duration::from_millis(10));
+        drop(keystream);
+        // The worker has never been waited for.
+        drop(BackgroundKeystream::ofb(
+            &keys,
+            &iv,
+            PrefetchConfig::default(),
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_config_panic() {
+        let (keys, iv, _) = setup();
+        let config = PrefetchConfig {
+            chunk_size: 100,
+            ..PrefetchConfig::default()
+        };
+        BackgroundKeystream::ctr(&keys, &iv, config);
+    }
+}
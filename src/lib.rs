@@ -1,16 +1,148 @@
 //! # aes_frast
 //! `aes_frast` is an easy-to-use lib for AES encryption and decryption, coded in pure safe
 //! Rust-lang.
+//!
+//! The only `unsafe` code is the AES-NI backend behind the `aesni` feature, and the unchecked
+//! table lookups behind the `unsafe-speed` feature.
+#![deny(unsafe_code)]
+
+/// Implement `Debug` for types which hold keys, sub-keys or IVs, showing the type name only, so
+/// that logging them with `{:?}` never leaks the secrets.
+macro_rules! impl_redacted_debug {
+    ($($name:ident $(<$generic:ident>)?),* $(,)?) => {
+        $(
+            impl$(<$generic>)? std::fmt::Debug for $name$(<$generic>)? {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.debug_struct(stringify!($name)).finish_non_exhaustive()
+                }
+            }
+        )*
+    };
+}
+
+/// The `acvp` mod answers ACVP test vector sets with this library.
+#[cfg(feature = "acvp")]
+pub mod acvp;
+/// The `aes_cipher` mod provides the cipher objects `Aes128`, `Aes192`, `Aes256`, `AesAny` and
+/// `Aes`, which keep the scheduled sub-keys of a key.
+pub mod aes_cipher;
+/// The `aes_cmac` mod provides CMAC (NIST SP 800-38B), the MAC of a block cipher.
+pub mod aes_cmac;
 /// The `aes_core` mod provides the essential functions of AES, including key scheduling and
 /// single-block crypto.
 pub mod aes_core;
+/// The `aes_hash` mod provides hash functions built on AES, such as the Zigbee MMO hash.
+pub mod aes_hash;
 /// The `aes_with_operation_mode` mod provides operation modes such as CBC and OFB, and so on.
 pub mod aes_with_operation_mode;
+/// The `aes_with_operation_mode_inplace` mod provides the CBC, CFB, OFB and CTR modes which
+/// encrypt and decrypt a buffer in place, without allocation.
+pub mod aes_with_operation_mode_inplace;
+/// The `aes_with_operation_mode_io` mod provides the operation modes which write their output
+/// into a `std::io::Write` sink.
+pub mod aes_with_operation_mode_io;
+#[cfg(all(feature = "aesni", any(target_arch = "x86", target_arch = "x86_64")))]
+mod aesni;
+/// The `alphabet` mod maps strings to the numeral strings of format-preserving encryption.
+pub mod alphabet;
+/// The `block` mod provides the `Block` type, a 128-bit block with XOR and doubling operations.
+pub mod block;
+/// The `block_cipher` mod provides the `BlockCipher128` trait, which lets the operation modes
+/// work with any 128-bit block cipher.
+pub mod block_cipher;
+/// The `cbc_mac` mod provides CBC-MAC, raw and with the length prepended.
+pub mod cbc_mac;
+/// The `ccm` mod provides CCM (Counter with CBC-MAC) authenticated encryption.
+pub mod ccm;
+/// The `conformance` mod runs the example vectors of the NIST SP 800-38 series.
+pub mod conformance;
+/// The `context` mod provides heap-free AES contexts with `const` constructors, which can be
+/// placed in a `static`.
+pub mod context;
+/// The `ctr_drbg` mod provides CTR_DRBG (NIST SP 800-90A), a deterministic random bit generator.
+pub mod ctr_drbg;
+mod digest;
+/// The `dma` mod provides operation modes on caller-provided buffers which return the exact
+/// processed length, for output handed to DMA engines.
+pub mod dma;
+/// The `error` mod contains the error type of the fallible functions.
+pub mod error;
+/// The `evp` mod provides one-shot functions named after the ciphers of OpenSSL.
+pub mod evp;
+#[cfg(feature = "fixslice")]
+mod fixslice;
+/// The `ghash` mod provides GHASH, the universal hash of GCM, and multiplication in GF(2^128).
+pub mod ghash;
+/// The `incremental` mod encrypts and decrypts a message given in chunks of any size, as
+/// described by a `Recipe`.
+pub mod incremental;
+/// The `iv_generator` mod provides the `IvGenerator` trait and the common IV policies, such as
+/// random, counter-based and ESSIV IVs.
+pub mod iv_generator;
+/// The `kat` mod generates CAVP-style `.rsp` files of known answer and Monte Carlo tests.
+#[cfg(feature = "kat")]
+pub mod kat;
+/// The `key` mod provides the `Key` type, which is wiped on drop, and key generation.
+pub mod key;
+/// The `key_wrap` mod provides AES Key Wrap, with (KWP) and without (KW) padding.
+pub mod key_wrap;
+/// The `keystream` mod provides CTR and OFB keystreams computed ahead of use on a worker thread.
+pub mod keystream;
+/// The `mapped` mod encrypts and decrypts large regions in place, such as memory-mapped files.
+#[cfg(feature = "mapped")]
+pub mod mapped;
+#[cfg(any(feature = "acvp", feature = "kat"))]
+mod mct;
+/// The `metrics` mod counts the bytes, blocks, errors and tag failures of the high-level APIs.
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(not(feature = "metrics"))]
+mod metrics;
+/// The `nonce` mod derives per-record nonces from a static IV and a sequence number.
+pub mod nonce;
+/// The `offload` mod lets an AES peripheral do the block operations (or whole modes) for the
+/// modes and padding of this library.
+pub mod offload;
+/// The `openssl_enc` mod reads and writes the password-based files of `openssl enc`.
+pub mod openssl_enc;
 /// The `padding_128bit` mod provides padding and depadding functions for 128bit-block crypto.
 pub mod padding_128bit;
+#[cfg(feature = "parallel")]
+mod parallel;
+/// The `pbe` mod encrypts with a password, deriving the key and the IV by PBKDF2-HMAC-SHA256.
+#[cfg(feature = "pbe")]
+pub mod pbe;
+/// The `pkcs11` mod maps the AES mechanisms of PKCS #11 onto this library.
+pub mod pkcs11;
+/// The `pmac` mod provides PMAC, a MAC whose blocks can be processed in parallel.
+pub mod pmac;
+/// The `recipe` mod parses cipher recipes like `"AES-128/CBC/PKCS7"`.
+pub mod recipe;
+/// The `reference` mod provides a deliberately simple, textbook AES for cross-verification.
+#[cfg(feature = "reference")]
+pub mod reference;
+/// The `rng` mod provides the `Rng` trait, the source of randomness for IVs, keys and padding.
+pub mod rng;
+/// The `small_domain` mod provides keyed permutations of integer ranges, by a Feistel network
+/// and cycle walking.
+pub mod small_domain;
+/// The `stream` mod provides STREAM, chunked authenticated encryption of long messages over CCM.
+pub mod stream;
+/// The `tag` mod provides constant-time verification of authentication tags and MACs.
+pub mod tag;
+#[cfg(test)]
+mod test_util;
+/// The `tweakable` mod provides the `TweakableBlockCipher` trait and its XEX implementation.
+pub mod tweakable;
+/// The `wide_block` mod provides wide-block, length-preserving modes such as CMC.
+pub mod wide_block;
+/// The `xts` mod provides XTS-AES storage encryption with ciphertext stealing.
+pub mod xts;
 
 /// Miscellaneous functions
 pub mod misc {
+    use super::AesError;
+
     /// Convert hexadecimal string to 32-bit words (u32).
     /// # Examples
     /// ```
@@ -27,8 +159,116 @@ pub mod misc {
             .unwrap()
             .to_be()
     }
+
+    const BASE64_STANDARD: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    const BASE64_URL_SAFE: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    fn base64_encode_with(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+        let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+        for chunk in data.chunks(3) {
+            let mut group = [0u8; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+            for i in 0..=chunk.len() {
+                out.push(alphabet[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            }
+            if pad {
+                for _ in chunk.len()..3 {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    fn base64_decode_with(s: &str, alphabet: &[u8; 64]) -> Result<Vec<u8>, AesError> {
+        let bytes = s.as_bytes();
+        // The padding is optional, but it must be complete if present.
+        let digits = bytes.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+        if (bytes.len() != digits && !bytes.len().is_multiple_of(4))
+            || bytes.len() - digits > 2
+            || digits % 4 == 1
+        {
+            return Err(AesError::InvalidParameter);
+        }
+        let mut out = Vec::with_capacity(digits / 4 * 3 + 2);
+        for chunk in bytes[..digits].chunks(4) {
+            let mut n = 0u32;
+            for (i, &c) in chunk.iter().enumerate() {
+                let value = alphabet
+                    .iter()
+                    .position(|&a| a == c)
+                    .ok_or(AesError::InvalidParameter)?;
+                n |= (value as u32) << (18 - 6 * i);
+            }
+            let group = n.to_be_bytes();
+            let len = chunk.len() - 1;
+            // The unused bits of the last digit must be zero, so every data has one encoding.
+            if group[(1 + len)..].iter().any(|&b| b != 0) {
+                return Err(AesError::InvalidParameter);
+            }
+            out.extend_from_slice(&group[1..(1 + len)]);
+        }
+        Ok(out)
+    }
+
+    /// Encode bytes to standard base64 (RFC 4648), with `=` padding.
+    /// # Examples
+    /// ```
+    /// use aes_frast::misc::{base64_decode, base64_encode};
+    /// assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    /// assert_eq!(base64_encode(b"fooba"), "Zm9vYmE=");
+    /// assert_eq!(base64_encode(&[0xFB, 0xFF]), "+/8=");
+    /// assert_eq!(base64_decode("Zm9vYmE=").unwrap(), b"fooba");
+    /// ```
+    pub fn base64_encode(data: &[u8]) -> String {
+        base64_encode_with(data, BASE64_STANDARD, true)
+    }
+
+    /// Decode standard base64 (RFC 4648). The `=` padding may be omitted.
+    ///
+    /// * *return* : the decoded bytes, or `Err(AesError::InvalidParameter)` if `s` has characters
+    ///   outside the alphabet (including whitespace), a wrong length or wrong padding, or
+    ///   non-zero unused bits.
+    /// # Examples
+    /// ```
+    /// use aes_frast::misc::base64_decode;
+    /// use aes_frast::AesError;
+    /// assert_eq!(base64_decode("Zm8=").unwrap(), b"fo");
+    /// assert_eq!(base64_decode("Zm8").unwrap(), b"fo");
+    /// assert_eq!(base64_decode("Zm9=").unwrap_err(), AesError::InvalidParameter);
+    /// assert_eq!(base64_decode("Zm8-").unwrap_err(), AesError::InvalidParameter);
+    /// ```
+    pub fn base64_decode(s: &str) -> Result<Vec<u8>, AesError> {
+        base64_decode_with(s, BASE64_STANDARD)
+    }
+
+    /// Encode bytes to URL-safe base64 (RFC 4648 section 5), without padding, as used in URLs,
+    /// file names and JWTs.
+    /// # Examples
+    /// ```
+    /// use aes_frast::misc::{base64url_decode, base64url_encode};
+    /// assert_eq!(base64url_encode(&[0xFB, 0xFF]), "-_8");
+    /// assert_eq!(base64url_decode("-_8").unwrap(), [0xFB, 0xFF]);
+    /// ```
+    pub fn base64url_encode(data: &[u8]) -> String {
+        base64_encode_with(data, BASE64_URL_SAFE, false)
+    }
+
+    /// Decode URL-safe base64 (RFC 4648 section 5). The `=` padding may be present or omitted.
+    ///
+    /// * *return* : the decoded bytes, or `Err(AesError::InvalidParameter)` like
+    ///   [`base64_decode`].
+    ///
+    /// [`base64_decode`]: ../misc/fn.base64_decode.html
+    pub fn base64url_decode(s: &str) -> Result<Vec<u8>, AesError> {
+        base64_decode_with(s, BASE64_URL_SAFE)
+    }
 }
 
+pub use crate::aes_cipher::{Aes, Aes128, Aes192, Aes256, AesAny};
 pub use crate::aes_core::BLOCKSIZE_IN_BYTES;
 pub use crate::aes_core::KEY_BYTES_128BIT;
 pub use crate::aes_core::KEY_BYTES_192BIT;
@@ -36,3 +276,4 @@ pub use crate::aes_core::KEY_BYTES_256BIT;
 pub use crate::aes_core::N_SUBKEYS_128BIT;
 pub use crate::aes_core::N_SUBKEYS_192BIT;
 pub use crate::aes_core::N_SUBKEYS_256BIT;
+pub use crate::error::AesError;
@@ -0,0 +1,161 @@
+//! # mapped
+//! `mapped` encrypts and decrypts a large region of memory in place, such as a memory-mapped
+//! file, so a multi-GB image never has to be copied through another buffer.
+//!
+//! The functions take the region as `&mut [u8]`. This library maps no files itself: map the
+//! file with a crate such as `memmap2`, whose `MmapMut` dereferences to `[u8]`, and pass it on.
+//! They are built on the in-place modes of [`aes_with_operation_mode_inplace`], and CTR splits
+//! the region between up to `threads` threads like the other `_parallel` functions. For disk
+//! images made of sectors, [`Xts::encrypt_sectors_parallel`] does the same for XTS.
+//!
+//! A region can't grow, so there is no padding: CBC needs a region whose length is a multiple
+//! of 16 bytes.
+//!
+//! [`aes_with_operation_mode_inplace`]: ../aes_with_operation_mode_inplace/index.html
+//! [`Xts::encrypt_sectors_parallel`]: ../xts/struct.Xts.html#method.encrypt_sectors_parallel
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode_inplace as inplace;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::parallel;
+
+/// **Encrypt** or **decrypt** a region in place with CTR mode, on up to `threads` threads.
+///
+/// Data of less than 256 blocks per thread is not worth the threads, and is processed on the
+/// calling thread only.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `counter`: the initial counter block.
+/// * *parameter* `region`: the plain (or cipher), which becomes the cipher (or plain).
+/// * *parameter* `threads`: the maximum number of threads, 1 for the calling thread only.
+/// * *return* : the counter block after the region, for a following region.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode_inplace::ctr_enc_dec_with;
+/// use aes_frast::mapped::ctr_region;
+/// use aes_frast::Aes256;
+///
+/// let aes = Aes256::new(&[0x42u8; 32]);
+/// let counter = [0x24u8; 16];
+/// // A mapped file in practice, e.g. `memmap2::MmapMut`.
+/// let mut region = vec![0x5Au8; 1 << 20];
+///
+/// ctr_region(&aes, &counter, &mut region, 4);
+/// let mut expected = vec![0x5Au8; 1 << 20];
+/// ctr_enc_dec_with(&aes, &mut expected, &counter);
+/// assert_eq!(region, expected);
+///
+/// ctr_region(&aes, &counter, &mut region, 4);
+/// assert_eq!(region, vec![0x5Au8; 1 << 20]);
+/// ```
+pub fn ctr_region<C: BlockCipher128 + Sync>(
+    cipher: &C,
+    counter: &[u8; BLOCKSIZE_IN_BYTES],
+    region: &mut [u8],
+    threads: usize,
+) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let counter = u128::from_be_bytes(*counter);
+    parallel::for_each_unit_chunk(region, BLOCKSIZE_IN_BYTES, threads, |first_block, chunk| {
+        let start = counter.wrapping_add(first_block as u128).to_be_bytes();
+        inplace::ctr_enc_dec_with(cipher, chunk, &start);
+    });
+    let blocks = region.len().div_ceil(BLOCKSIZE_IN_BYTES);
+    counter.wrapping_add(blocks as u128).to_be_bytes()
+}
+
+/// **Encrypt** a region in place with CBC mode, without padding.
+///
+/// CBC encryption is sequential, so it runs on the calling thread.
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `iv`: the initialization vector.
+/// * *parameter* `region`: the plain, which becomes the cipher.
+/// * *return* : the last cipher block (the IV of a following region), or
+///   `Err(AesError::InvalidDataLength)` if the length of `region` is not a multiple of 16, in
+///   which case it is left untouched.
+/// # Examples
+/// ```
+/// use aes_frast::mapped::{cbc_decrypt_region, cbc_encrypt_region};
+/// use aes_frast::{Aes128, AesError};
+///
+/// let aes = Aes128::new(&[0x42u8; 16]);
+/// let iv = [0x24u8; 16];
+/// let mut region = vec![0x5Au8; 4096];
+/// cbc_encrypt_region(&aes, &iv, &mut region).unwrap();
+/// cbc_decrypt_region(&aes, &iv, &mut region).unwrap();
+/// assert_eq!(region, vec![0x5Au8; 4096]);
+///
+/// assert_eq!(cbc_encrypt_region(&aes, &iv, &mut [0u8; 20]), Err(AesError::InvalidDataLength));
+/// ```
+pub fn cbc_encrypt_region<C: BlockCipher128>(
+    cipher: &C,
+    iv: &[u8; BLOCKSIZE_IN_BYTES],
+    region: &mut [u8],
+) -> Result<[u8; BLOCKSIZE_IN_BYTES], AesError> {
+    if region.len() & 0b1111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(inplace::cbc_enc_with(cipher, region, iv))
+}
+
+/// **Decrypt** a region in place with CBC mode, the reverse of [`cbc_encrypt_region`].
+///
+/// [`cbc_encrypt_region`]: ../mapped/fn.cbc_encrypt_region.html
+pub fn cbc_decrypt_region<C: BlockCipher128>(
+    cipher: &C,
+    iv: &[u8; BLOCKSIZE_IN_BYTES],
+    region: &mut [u8],
+) -> Result<[u8; BLOCKSIZE_IN_BYTES], AesError> {
+    if region.len() & 0b1111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(inplace::cbc_dec_with(cipher, region, iv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_with_operation_mode::{cbc_enc_with, ctr_enc_dec_with};
+    use crate::Aes128;
+
+    #[test]
+    fn ctr_region_matches_ctr() {
+        let aes = Aes128::new(&[0x13u8; 16]);
+        // The counter wraps around inside the region.
+        let counter = (u128::MAX - 1000).to_be_bytes();
+        let plain: Vec<u8> = (0..100_003).map(|i| (i % 251) as u8).collect();
+        let mut expected = vec![0u8; plain.len()];
+        let next = ctr_enc_dec_with(&aes, &plain, &mut expected, &counter);
+        for threads in [1, 2, 5] {
+            let mut region = plain.clone();
+            assert_eq!(
+                ctr_region(&aes, &counter, &mut region, threads)[..],
+                next[..]
+            );
+            assert_eq!(region, expected);
+        }
+    }
+
+    #[test]
+    fn cbc_region_matches_cbc() {
+        let aes = Aes128::new(&[0x31u8; 16]);
+        let iv = [0x07u8; 16];
+        let plain: Vec<u8> = (0..4096).map(|i| (i % 249) as u8).collect();
+        let mut expected = vec![0u8; plain.len()];
+        let last = cbc_enc_with(&aes, &plain, &mut expected, &iv);
+        let mut region = plain.clone();
+        assert_eq!(
+            cbc_encrypt_region(&aes, &iv, &mut region).unwrap()[..],
+            last[..]
+        );
+        assert_eq!(region, expected);
+        cbc_decrypt_region(&aes, &iv, &mut region).unwrap();
+        assert_eq!(region, plain);
+        let mut region = [0x01u8; 17];
+        assert_eq!(
+            cbc_decrypt_region(&aes, &iv, &mut region),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(region, [0x01u8; 17]);
+    }
+}
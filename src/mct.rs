@@ -0,0 +1,96 @@
+//! # mct
+//! `mct` runs the Monte Carlo tests of ECB and CBC as described in the AESAVS, for the `acvp`
+//! and `kat` mods.
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+use super::aes_core::{KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::aes_with_operation_mode as mode;
+use super::error::AesError;
+
+/// The values at the start and at the end of one of the 100 outer iterations.
+pub(crate) struct MctRound {
+    pub(crate) key: Vec<u8>,
+    /// Only used by CBC.
+    pub(crate) iv: [u8; BLOCKSIZE_IN_BYTES],
+    pub(crate) input: [u8; BLOCKSIZE_IN_BYTES],
+    pub(crate) output: [u8; BLOCKSIZE_IN_BYTES],
+}
+
+/// Run a Monte Carlo test of ECB (`cbc` is false) or CBC, returning the 100 outer iterations.
+pub(crate) fn monte_carlo(
+    cbc: bool,
+    encrypt: bool,
+    key: &[u8],
+    iv: [u8; BLOCKSIZE_IN_BYTES],
+    input: [u8; BLOCKSIZE_IN_BYTES],
+) -> Result<Vec<MctRound>, AesError> {
+    let mut key = key.to_vec();
+    let mut iv = iv;
+    let mut input = input;
+    let mut rounds = Vec::with_capacity(100);
+    for _ in 0..100 {
+        let keys = schedule(&key, encrypt)?;
+        let mut chain = iv;
+        let mut previous = [0u8; BLOCKSIZE_IN_BYTES];
+        let mut output = [0u8; BLOCKSIZE_IN_BYTES];
+        let first_input = input;
+        for j in 0..1000 {
+            previous = output;
+            match (cbc, encrypt) {
+                (true, true) => {
+                    mode::cbc_enc(&input, &mut output, &keys, &chain);
+                    chain = output;
+                }
+                (true, false) => {
+                    mode::cbc_dec(&input, &mut output, &keys, &chain);
+                    chain = input;
+                }
+                (false, true) => {
+                    mode::ecb_enc(&input, &mut output, &keys);
+                }
+                (false, false) => {
+                    mode::ecb_dec(&input, &mut output, &keys);
+                }
+            }
+            input = match cbc {
+                true if j == 0 => iv,
+                true => previous,
+                false => output,
+            };
+        }
+        rounds.push(MctRound {
+            key: key.clone(),
+            iv,
+            input: first_input,
+            output,
+        });
+        // The new key is the old one XORed with the tail of `output[998] || output[999]`.
+        let mut tail = previous.to_vec();
+        tail.extend_from_slice(&output);
+        let tail = &tail[(tail.len() - key.len())..];
+        for (k, t) in key.iter_mut().zip(tail) {
+            *k ^= t;
+        }
+        if cbc {
+            iv = output;
+            input = previous;
+        }
+    }
+    Ok(rounds)
+}
+
+/// Schedule the sub-keys of a key of any size.
+pub(crate) fn schedule(key: &[u8], encrypt: bool) -> Result<Vec<u32>, AesError> {
+    let mut keys = match key.len() {
+        KEY_BYTES_128BIT => vec![0u32; N_SUBKEYS_128BIT],
+        KEY_BYTES_192BIT => vec![0u32; N_SUBKEYS_192BIT],
+        KEY_BYTES_256BIT => vec![0u32; N_SUBKEYS_256BIT],
+        _ => return Err(AesError::InvalidKeyLength),
+    };
+    if encrypt {
+        aes_core::key_schedule_encrypt_auto(key, &mut keys);
+    } else {
+        aes_core::key_schedule_decrypt_auto(key, &mut keys);
+    }
+    Ok(keys)
+}
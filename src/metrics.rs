@@ -0,0 +1,163 @@
+//! # metrics
+//! `metrics` counts what the high-level APIs do, so that a service can monitor its crypto
+//! throughput and error rates without wrapping every call. It is only available with the
+//! `metrics` feature; without it, the counting compiles to nothing.
+//!
+//! The counters are global and updated with relaxed atomics, so a [`snapshot`] taken while other
+//! threads are encrypting is not exact across counters, but each counter only ever grows (until
+//! [`reset`]). They are fed by:
+//! * [`Recipe`] and [`KeyedRecipe`]: the bytes and blocks of every encryption and decryption,
+//!   and every error they return.
+//! * [`encrypt_reader_to_writer`] and [`decrypt_reader_to_writer`]: the same, when they finish.
+//! * [`verify_tag`] (and so every MAC or tag check of this library): the tag failures.
+//!
+//! [`snapshot`]: ../metrics/fn.snapshot.html
+//! [`reset`]: ../metrics/fn.reset.html
+//! [`Recipe`]: ../recipe/struct.Recipe.html
+//! [`KeyedRecipe`]: ../recipe/struct.KeyedRecipe.html
+//! [`encrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.encrypt_reader_to_writer.html
+//! [`decrypt_reader_to_writer`]: ../aes_with_operation_mode_io/fn.decrypt_reader_to_writer.html
+//! [`verify_tag`]: ../tag/fn.verify_tag.html
+#![cfg_attr(not(feature = "metrics"), allow(dead_code))]
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::error::AesError;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The name of the AES implementation, which is the same for every call in this version.
+pub const BACKEND: &str = "t-table";
+
+static BYTES_ENCRYPTED: AtomicU64 = AtomicU64::new(0);
+static BYTES_DECRYPTED: AtomicU64 = AtomicU64::new(0);
+static BLOCKS_PROCESSED: AtomicU64 = AtomicU64::new(0);
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+static TAG_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// The values of the counters at one moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Snapshot {
+    /// The bytes of plain which have been encrypted, without padding.
+    pub bytes_encrypted: u64,
+    /// The bytes of cipher which have been decrypted, with padding.
+    pub bytes_decrypted: u64,
+    /// The 16-byte blocks which have been encrypted or decrypted, a partial block counting as
+    /// one.
+    pub blocks_processed: u64,
+    /// The errors returned by the instrumented functions, like invalid lengths or paddings.
+    pub errors: u64,
+    /// The tags (or MACs) which failed verification.
+    pub tag_failures: u64,
+}
+
+/// Read all the counters.
+/// # Examples
+/// ```
+/// # #[cfg(feature = "metrics")]
+/// # {
+/// use aes_frast::metrics;
+/// use aes_frast::recipe::Recipe;
+///
+/// let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+/// let before = metrics::snapshot();
+/// recipe.encrypt(&[0x42u8; 16], &[0x24u8; 16], &[0u8; 20]).unwrap();
+/// assert!(recipe.decrypt(&[0x42u8; 16], &[0x24u8; 16], &[0u8; 20]).is_err());
+///
+/// // Other threads may be counted too, so compare with at least.
+/// let after = metrics::snapshot();
+/// assert!(after.bytes_encrypted - before.bytes_encrypted >= 20);
+/// assert!(after.errors - before.errors >= 1);
+/// assert_eq!(metrics::BACKEND, "t-table");
+/// # }
+/// ```
+pub fn snapshot() -> Snapshot {
+    Snapshot {
+        bytes_encrypted: BYTES_ENCRYPTED.load(Ordering::Relaxed),
+        bytes_decrypted: BYTES_DECRYPTED.load(Ordering::Relaxed),
+        blocks_processed: BLOCKS_PROCESSED.load(Ordering::Relaxed),
+        errors: ERRORS.load(Ordering::Relaxed),
+        tag_failures: TAG_FAILURES.load(Ordering::Relaxed),
+    }
+}
+
+/// Set all the counters to zero, e.g. at the start of a reporting interval.
+pub fn reset() {
+    for counter in [
+        &BYTES_ENCRYPTED,
+        &BYTES_DECRYPTED,
+        &BLOCKS_PROCESSED,
+        &ERRORS,
+        &TAG_FAILURES,
+    ] {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// The direction of an instrumented operation.
+#[derive(Clone, Copy)]
+pub(crate) enum Operation {
+    Encrypt,
+    Decrypt,
+}
+
+fn add(counter: &AtomicU64, value: u64) {
+    if cfg!(feature = "metrics") {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+/// Count an operation over `bytes` bytes of input.
+pub(crate) fn record(operation: Operation, bytes: u64) {
+    match operation {
+        Operation::Encrypt => add(&BYTES_ENCRYPTED, bytes),
+        Operation::Decrypt => add(&BYTES_DECRYPTED, bytes),
+    }
+    add(&BLOCKS_PROCESSED, bytes.div_ceil(BLOCKSIZE_IN_BYTES as u64));
+}
+
+/// Count an error returned to the caller.
+pub(crate) fn record_error() {
+    add(&ERRORS, 1);
+}
+
+/// Count a failed tag verification.
+pub(crate) fn record_tag_failure() {
+    add(&TAG_FAILURES, 1);
+}
+
+/// Run `f`, an operation over `bytes` bytes of input, and count its result.
+pub(crate) fn observe<T, F>(operation: Operation, bytes: usize, f: F) -> Result<T, AesError>
+where
+    F: FnOnce() -> Result<T, AesError>,
+{
+    let result = f();
+    match result {
+        Ok(_) => record(operation, bytes as u64),
+        Err(_) => record_error(),
+    }
+    result
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::tag::verify_tag;
+
+    // The counters are shared with the tests running on other threads, so only lower bounds can
+    // be checked.
+    #[test]
+    fn counts() {
+        let before = snapshot();
+        record(Operation::Encrypt, 33);
+        record(Operation::Decrypt, 16);
+        assert!(verify_tag(&[0u8; 16], &[1u8; 16]).is_err());
+        let failed: Result<(), AesError> =
+            observe(Operation::Decrypt, 5, || Err(AesError::InvalidPadding));
+        assert!(failed.is_err());
+
+        let after = snapshot();
+        assert!(after.bytes_encrypted - before.bytes_encrypted >= 33);
+        assert!(after.bytes_decrypted - before.bytes_decrypted >= 16);
+        assert!(after.blocks_processed - before.blocks_processed >= 4);
+        assert!(after.tag_failures > before.tag_failures);
+        assert!(after.errors > before.errors);
+    }
+}
@@ -0,0 +1,436 @@
+//! # nonce
+//! `nonce` derives per-record nonces from a static IV (or salt) and a 64-bit sequence number,
+//! the way record-layer protocols do:
+//! * [`xor_sequence`]: the big-endian sequence number, left-padded with zeros, XORed into the
+//!   static IV, as TLS 1.3 (RFC 8446, section 5.3) and DTLS 1.3 do.
+//! * [`concat_sequence`]: the salt followed by the big-endian sequence number, as the explicit
+//!   nonces of TLS 1.2 AES-GCM (RFC 5288) and AES-CCM (RFC 6655).
+//! * [`cmac_sequence`]: the leading bytes of the CMAC of the salt and the big-endian sequence
+//!   number, under a key kept for deriving nonces. Writers which share the key but not a counter
+//!   use distinct salts, and their nonces look random and don't reveal the sequence numbers.
+//!
+//! [`SequenceNonce`] counts the records of a sender and refuses to wrap the sequence number.
+//! [`NonceSequence`] (also available as [`CounterNonce`]) hands out 96-bit counter nonces without a
+//! static part, and its state can be saved and resumed across restarts. Unlike [`SequenceNonce`],
+//! it has no static IV and no record sequence number.
+//!
+//! [`xor_sequence`]: ../nonce/fn.xor_sequence.html
+//! [`concat_sequence`]: ../nonce/fn.concat_sequence.html
+//! [`cmac_sequence`]: ../nonce/fn.cmac_sequence.html
+//! [`SequenceNonce`]: ../nonce/struct.SequenceNonce.html
+//! [`NonceSequence`]: ../nonce/struct.NonceSequence.html
+//! [`CounterNonce`]: ../nonce/type.CounterNonce.html
+use super::aes_cmac::Cmac;
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use std::fmt;
+
+const SEQUENCE_BYTES: usize = 8;
+
+/// Derive a nonce by XORing the sequence number into the static IV.
+///
+/// * *parameter* `static_iv`: the static (write) IV, at least 8 bytes.
+/// * *parameter* `sequence`: the sequence number, which is XORed into the last 8 bytes in
+///   big-endian.
+/// * *return* : the nonce.
+/// # Examples
+/// ```
+/// use aes_frast::nonce::xor_sequence;
+///
+/// let static_iv = [0xA0u8; 12];
+/// let nonce = xor_sequence(&static_iv, 0x0102);
+/// assert_eq!(nonce[..10], [0xA0u8; 10]);
+/// assert_eq!(nonce[10..], [0xA1, 0xA2]);
+/// ```
+/// # Panics
+/// This function panics if `N` is less than 8.
+pub fn xor_sequence<const N: usize>(static_iv: &[u8; N], sequence: u64) -> [u8; N] {
+    assert!(
+        N >= SEQUENCE_BYTES,
+        "The static IV is shorter than 8 bytes."
+    );
+    let mut nonce = *static_iv;
+    for (byte, sequence_byte) in nonce[(N - SEQUENCE_BYTES)..]
+        .iter_mut()
+        .zip(sequence.to_be_bytes().iter())
+    {
+        *byte ^= sequence_byte;
+    }
+    nonce
+}
+
+/// Derive a nonce by appending the sequence number to the salt.
+///
+/// * *parameter* `salt`: the salt (implicit nonce part), 8 bytes shorter than the nonce.
+/// * *parameter* `sequence`: the sequence number, appended in big-endian.
+/// * *return* : the nonce.
+/// # Examples
+/// ```
+/// use aes_frast::nonce::concat_sequence;
+///
+/// let nonce: [u8; 12] = concat_sequence(&[0xA0u8; 4], 0x0102);
+/// assert_eq!(nonce, [0xA0, 0xA0, 0xA0, 0xA0, 0, 0, 0, 0, 0, 0, 0x01, 0x02]);
+/// ```
+/// # Panics
+/// This function panics if the length of `salt` is not `N - 8`.
+pub fn concat_sequence<const N: usize>(salt: &[u8], sequence: u64) -> [u8; N] {
+    assert_eq!(
+        salt.len() + SEQUENCE_BYTES,
+        N,
+        "The salt must be 8 bytes shorter than the nonce."
+    );
+    let mut nonce = [0u8; N];
+    nonce[..salt.len()].copy_from_slice(salt);
+    nonce[salt.len()..].copy_from_slice(&sequence.to_be_bytes());
+    nonce
+}
+
+/// Derive a nonce from the CMAC of the salt followed by the sequence number.
+///
+/// * *parameter* `cmac`: the CMAC under the key for deriving nonces, which must not be the key
+///   of the messages.
+/// * *parameter* `salt`: the salt, e.g. the identifier of the writer.
+/// * *parameter* `sequence`: the sequence number, appended to the salt in big-endian.
+/// * *return* : the leading `N` bytes of the CMAC.
+///
+/// The nonces of different `(salt, sequence)` pairs collide only by chance, like random nonces,
+/// so keep the number of messages under one key well below 2^(4 * `N`).
+/// # Examples
+/// ```
+/// use aes_frast::aes_cmac::{aes_cmac, Cmac};
+/// use aes_frast::nonce::cmac_sequence;
+/// use aes_frast::Aes128;
+///
+/// let nonce_key = [0x2Bu8; 16];
+/// let cmac = Cmac::new(Aes128::new(&nonce_key));
+/// let nonce: [u8; 12] = cmac_sequence(&cmac, b"writer-7", 42);
+///
+/// let mut message = b"writer-7".to_vec();
+/// message.extend_from_slice(&42u64.to_be_bytes());
+/// assert_eq!(nonce, aes_cmac(&nonce_key, &message).unwrap().as_bytes()[..12]);
+/// ```
+/// # Panics
+/// This function panics if `N` is greater than 16.
+pub fn cmac_sequence<C: BlockCipher128, const N: usize>(
+    cmac: &Cmac<C>,
+    salt: &[u8],
+    sequence: u64,
+) -> [u8; N] {
+    assert!(
+        N <= BLOCKSIZE_IN_BYTES,
+        "The nonce is longer than 16 bytes."
+    );
+    let mut message = Vec::with_capacity(salt.len() + SEQUENCE_BYTES);
+    message.extend_from_slice(salt);
+    message.extend_from_slice(&sequence.to_be_bytes());
+    let mut nonce = [0u8; N];
+    nonce.copy_from_slice(&cmac.mac(&message).as_bytes()[..N]);
+    nonce
+}
+
+/// The two ways of combining the static part with the sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NonceConstruction {
+    /// See [`xor_sequence`].
+    ///
+    /// [`xor_sequence`]: ../nonce/fn.xor_sequence.html
+    Xor,
+    /// See [`concat_sequence`].
+    ///
+    /// [`concat_sequence`]: ../nonce/fn.concat_sequence.html
+    Concat,
+}
+
+/// The nonces of the records of one key, numbered from 0.
+///
+/// A sender calls [`next_nonce`] for each record, which fails with
+/// `Err(AesError::SequenceOverflow)` instead of reusing a nonce after 2^64 records (a protocol
+/// must rekey long before that). A receiver, which knows the sequence number of each record, e.g.
+/// in DTLS, calls [`nonce`].
+/// # Examples
+/// ```
+/// use aes_frast::nonce::{xor_sequence, SequenceNonce};
+///
+/// let static_iv = [0x5Au8; 12];
+/// let mut sender = SequenceNonce::xor(static_iv);
+/// assert_eq!(sender.next_nonce().unwrap(), static_iv);
+/// assert_eq!(sender.next_nonce().unwrap(), xor_sequence(&static_iv, 1));
+/// assert_eq!(sender.sequence(), Some(2));
+///
+/// let receiver = SequenceNonce::xor(static_iv);
+/// assert_eq!(receiver.nonce(1), xor_sequence(&static_iv, 1));
+/// ```
+///
+/// [`next_nonce`]: ../nonce/struct.SequenceNonce.html#method.next_nonce
+/// [`nonce`]: ../nonce/struct.SequenceNonce.html#method.nonce
+#[derive(Clone)]
+pub struct SequenceNonce<const N: usize> {
+    construction: NonceConstruction,
+    static_part: [u8; N],
+    next: Option<u64>,
+}
+
+impl<const N: usize> SequenceNonce<N> {
+    /// Create the nonces of [`xor_sequence`], starting at sequence number 0.
+    /// # Panics
+    /// This function panics if `N` is less than 8.
+    ///
+    /// [`xor_sequence`]: ../nonce/fn.xor_sequence.html
+    pub fn xor(static_iv: [u8; N]) -> Self {
+        assert!(
+            N >= SEQUENCE_BYTES,
+            "The static IV is shorter than 8 bytes."
+        );
+        Self {
+            construction: NonceConstruction::Xor,
+            static_part: static_iv,
+            next: Some(0),
+        }
+    }
+
+    /// Create the nonces of [`concat_sequence`], starting at sequence number 0.
+    /// # Panics
+    /// This function panics if the length of `salt` is not `N - 8`.
+    ///
+    /// [`concat_sequence`]: ../nonce/fn.concat_sequence.html
+    pub fn concat(salt: &[u8]) -> Self {
+        Self {
+            construction: NonceConstruction::Concat,
+            static_part: concat_sequence(salt, 0),
+            next: Some(0),
+        }
+    }
+
+    /// The construction of the nonces.
+    pub fn construction(&self) -> NonceConstruction {
+        self.construction
+    }
+
+    /// The sequence number of the next record, or `None` if all of them have been used.
+    pub fn sequence(&self) -> Option<u64> {
+        self.next
+    }
+
+    /// The nonce of the record with the sequence number `sequence`.
+    pub fn nonce(&self, sequence: u64) -> [u8; N] {
+        match self.construction {
+            NonceConstruction::Xor => xor_sequence(&self.static_part, sequence),
+            NonceConstruction::Concat => {
+                concat_sequence(&self.static_part[..(N - SEQUENCE_BYTES)], sequence)
+            }
+        }
+    }
+
+    /// The nonce of the next record, advancing the sequence number.
+    ///
+    /// * *return* : the nonce, or `Err(AesError::SequenceOverflow)` if all the sequence numbers
+    ///   have been used.
+    pub fn next_nonce(&mut self) -> Result<[u8; N], AesError> {
+        let sequence = self.next.ok_or(AesError::SequenceOverflow)?;
+        self.next = sequence.checked_add(1);
+        Ok(self.nonce(sequence))
+    }
+}
+
+impl<const N: usize> fmt::Debug for SequenceNonce<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SequenceNonce")
+            .field("construction", &self.construction)
+            .field("next", &self.next)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The length in bytes of the nonces of [`NonceSequence`], which is 12 (96 bits).
+///
+/// [`NonceSequence`]: ../nonce/struct.NonceSequence.html
+pub const NONCE_SEQUENCE_BYTES: usize = 12;
+
+/// The number of nonces of [`NonceSequence`], 2^96.
+const NONCE_SEQUENCE_END: u128 = 1 << (8 * NONCE_SEQUENCE_BYTES);
+
+/// Strictly increasing 96-bit nonces, for the AEAD modes and CTR under one key.
+///
+/// The nonces are the big-endian numbers from 0 (or a resumed state) up to 2^96 - 1, and
+/// [`next_nonce`] fails with `Err(AesError::SequenceOverflow)` instead of wrapping around.
+///
+/// To survive restarts without reusing a nonce, save [`state_after`] a batch before using the
+/// batch, and [`resume`] from the saved state after a restart. The nonces skipped by a crash are
+/// lost, which is harmless.
+/// # Examples
+/// ```
+/// use aes_frast::nonce::NonceSequence;
+///
+/// let mut nonces = NonceSequence::new();
+/// // Saved to disk before any of the next 1000 nonces is used.
+/// let saved = nonces.state_after(1000).unwrap();
+/// assert_eq!(nonces.next_nonce().unwrap(), [0u8; 12]);
+/// assert_eq!(nonces.next_nonce().unwrap()[11], 1);
+///
+/// // After a restart, the sequence goes on after the reserved nonces.
+/// let mut resumed = NonceSequence::resume(saved);
+/// assert_eq!(resumed.next_nonce().unwrap()[10..], [0x03, 0xE8]);
+/// ```
+///
+/// [`next_nonce`]: ../nonce/struct.NonceSequence.html#method.next_nonce
+/// [`state_after`]: ../nonce/struct.NonceSequence.html#method.state_after
+/// [`resume`]: ../nonce/struct.NonceSequence.html#method.resume
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NonceSequence {
+    next: u128,
+}
+
+/// Another name of [`NonceSequence`], which tells it apart from [`SequenceNonce`] by what it
+/// hands out: plain counter nonces.
+///
+/// [`NonceSequence`]: ../nonce/struct.NonceSequence.html
+/// [`SequenceNonce`]: ../nonce/struct.SequenceNonce.html
+pub type CounterNonce = NonceSequence;
+
+impl NonceSequence {
+    /// Create the sequence, starting at the nonce 0.
+    pub fn new() -> Self {
+        Self { next: 0 }
+    }
+
+    /// Resume the sequence from a saved state, i.e. the next nonce to hand out.
+    pub fn resume(state: [u8; NONCE_SEQUENCE_BYTES]) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes[(16 - NONCE_SEQUENCE_BYTES)..].copy_from_slice(&state);
+        Self {
+            next: u128::from_be_bytes(bytes),
+        }
+    }
+
+    /// The state to save, i.e. the next nonce, or `None` if all the nonces have been used.
+    pub fn state(&self) -> Option<[u8; NONCE_SEQUENCE_BYTES]> {
+        self.state_after(0).ok()
+    }
+
+    /// The state after `count` more nonces, to be saved before using them.
+    ///
+    /// * *return* : the state, or `Err(AesError::SequenceOverflow)` if no nonce would be left
+    ///   after them. An exhausted sequence has no state, since it must never be resumed.
+    pub fn state_after(&self, count: u64) -> Result<[u8; NONCE_SEQUENCE_BYTES], AesError> {
+        let next = self.next + count as u128;
+        if next >= NONCE_SEQUENCE_END {
+            return Err(AesError::SequenceOverflow);
+        }
+        Ok(Self::encode(next))
+    }
+
+    /// The number of nonces left.
+    pub fn remaining(&self) -> u128 {
+        NONCE_SEQUENCE_END - self.next
+    }
+
+    /// The next nonce, advancing the sequence.
+    ///
+    /// * *return* : the nonce, or `Err(AesError::SequenceOverflow)` if all the nonces have been
+    ///   used.
+    pub fn next_nonce(&mut self) -> Result<[u8; NONCE_SEQUENCE_BYTES], AesError> {
+        if self.next == NONCE_SEQUENCE_END {
+            return Err(AesError::SequenceOverflow);
+        }
+        let nonce = Self::encode(self.next);
+        self.next += 1;
+        Ok(nonce)
+    }
+
+    /// The initial counter block of the next message in CTR mode: the next nonce followed by a
+    /// 32-bit block counter of 0, so each message may be up to 2^32 blocks long.
+    ///
+    /// * *return* : the counter block, or `Err(AesError::SequenceOverflow)` if all the nonces
+    ///   have been used.
+    pub fn next_counter_block(&mut self) -> Result<[u8; BLOCKSIZE_IN_BYTES], AesError> {
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[..NONCE_SEQUENCE_BYTES].copy_from_slice(&self.next_nonce()?);
+        Ok(block)
+    }
+
+    fn encode(value: u128) -> [u8; NONCE_SEQUENCE_BYTES] {
+        let mut nonce = [0u8; NONCE_SEQUENCE_BYTES];
+        nonce.copy_from_slice(&value.to_be_bytes()[(16 - NONCE_SEQUENCE_BYTES)..]);
+        nonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xor_nonce() {
+        let iv = [
+            0x5d, 0x31, 0x3e, 0xb2, 0x67, 0x12, 0x76, 0xee, 0x13, 0x00, 0x0b, 0x30,
+        ];
+        let mut nonces = SequenceNonce::xor(iv);
+        assert_eq!(nonces.next_nonce().unwrap(), iv);
+        let second = nonces.next_nonce().unwrap();
+        assert_eq!(second[..11], iv[..11]);
+        assert_eq!(second[11], 0x31);
+    }
+
+    #[test]
+    fn concat_nonce() {
+        let mut nonces = SequenceNonce::<12>::concat(&[1, 2, 3, 4]);
+        assert_eq!(nonces.construction(), NonceConstruction::Concat);
+        nonces.next_nonce().unwrap();
+        assert_eq!(
+            nonces.next_nonce().unwrap(),
+            [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let mut nonces = SequenceNonce::xor([0u8; 8]);
+        nonces.next = Some(u64::MAX);
+        assert_eq!(nonces.next_nonce().unwrap(), [0xFFu8; 8]);
+        assert_eq!(nonces.sequence(), None);
+        assert_eq!(nonces.next_nonce(), Err(AesError::SequenceOverflow));
+    }
+
+    #[test]
+    fn cmac_nonce() {
+        let cmac = Cmac::new(crate::Aes256::new(&[7u8; 32]));
+        let first: [u8; 12] = cmac_sequence(&cmac, b"a", 0);
+        let second: [u8; 12] = cmac_sequence(&cmac, b"a", 1);
+        let other_writer: [u8; 12] = cmac_sequence(&cmac, b"b", 0);
+        assert_ne!(first, second);
+        assert_ne!(first, other_writer);
+        let full: [u8; 16] = cmac_sequence(&cmac, b"a", 0);
+        assert_eq!(full[..12], first);
+    }
+
+    #[test]
+    fn nonce_sequence_works() {
+        let mut nonces = NonceSequence::resume([0x12; NONCE_SEQUENCE_BYTES]);
+        assert_eq!(nonces.state(), Some([0x12; NONCE_SEQUENCE_BYTES]));
+        assert_eq!(nonces.next_nonce().unwrap(), [0x12; NONCE_SEQUENCE_BYTES]);
+        let block = nonces.next_counter_block().unwrap();
+        assert_eq!(block[11], 0x13);
+        assert_eq!(block[12..], [0u8; 4]);
+        assert_eq!(CounterNonce::new().remaining(), 1u128 << 96);
+    }
+
+    #[test]
+    fn nonce_sequence_overflow() {
+        let last = [0xFFu8; NONCE_SEQUENCE_BYTES];
+        let mut nonces = NonceSequence::resume(last);
+        assert_eq!(nonces.remaining(), 1);
+        assert_eq!(nonces.state_after(1), Err(AesError::SequenceOverflow));
+        assert_eq!(nonces.state_after(0), Ok(last));
+        assert_eq!(nonces.next_nonce().unwrap(), last);
+        assert_eq!(nonces.state(), None);
+        assert_eq!(nonces.next_nonce(), Err(AesError::SequenceOverflow));
+        assert_eq!(nonces.next_counter_block(), Err(AesError::SequenceOverflow));
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_salt() {
+        let _: [u8; 12] = concat_sequence(&[0u8; 3], 0);
+    }
+}
@@ -0,0 +1,319 @@
+//! # offload
+//! `offload` lets an on-chip AES peripheral (like STM32 CRYP or ESP32 AES) do the block
+//! operations, while this library does the modes and the padding.
+//!
+//! A board-support crate implements [`AesOffload`] for its peripheral. Wrapped in an
+//! [`Offloaded`], the peripheral is a [`BlockCipher128`], so it works with all the functions
+//! ending with `_with` in `aes_with_operation_mode`, the generic mode structs and the `dma` mod.
+//! A peripheral which can run a whole mode by itself (often with its own DMA) can also take over
+//! CBC and CTR through [`Offloaded::cbc_encrypt`] and friends.
+//!
+//! [`SoftwareAes`] implements [`AesOffload`] with this library, so an application written
+//! against `Offloaded<impl AesOffload>` switches between the software and the hardware backend
+//! without code changes.
+//!
+//! [`AesOffload`]: ../offload/trait.AesOffload.html
+//! [`Offloaded`]: ../offload/struct.Offloaded.html
+//! [`Offloaded::cbc_encrypt`]: ../offload/struct.Offloaded.html#method.cbc_encrypt
+//! [`SoftwareAes`]: ../offload/struct.SoftwareAes.html
+//! [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+use super::aes_cipher::{Aes128, Aes192, Aes256};
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode_inplace as inplace;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use std::cell::RefCell;
+
+/// An AES implementation which a block operation (and optionally a whole mode) can be offloaded
+/// to, usually a hardware peripheral.
+///
+/// Only [`load_key`], [`encrypt_block`] and [`decrypt_block`] are required. The mode methods
+/// return `false` by default, which means "not supported", and then the mode is run by this
+/// library on the block operations.
+///
+/// [`load_key`]: ../offload/trait.AesOffload.html#tymethod.load_key
+/// [`encrypt_block`]: ../offload/trait.AesOffload.html#tymethod.encrypt_block
+/// [`decrypt_block`]: ../offload/trait.AesOffload.html#tymethod.decrypt_block
+pub trait AesOffload {
+    /// Load a key of 16, 24 or 32 bytes. Return `Err(AesError::InvalidKeyLength)` for the key
+    /// sizes the implementation does not support.
+    fn load_key(&mut self, key: &[u8]) -> Result<(), AesError>;
+
+    /// **Encrypt** a block in place with the loaded key.
+    fn encrypt_block(&mut self, block: &mut [u8; BLOCKSIZE_IN_BYTES]);
+
+    /// **Decrypt** a block in place with the loaded key.
+    fn decrypt_block(&mut self, block: &mut [u8; BLOCKSIZE_IN_BYTES]);
+
+    /// **Encrypt** complete blocks in place with CBC mode, updating `iv` to the last cipher
+    /// block. Return `false` if not supported.
+    fn cbc_encrypt(&mut self, _blocks: &mut [u8], _iv: &mut [u8; BLOCKSIZE_IN_BYTES]) -> bool {
+        false
+    }
+
+    /// **Decrypt** complete blocks in place with CBC mode, updating `iv` to the last cipher
+    /// block. Return `false` if not supported.
+    fn cbc_decrypt(&mut self, _blocks: &mut [u8], _iv: &mut [u8; BLOCKSIZE_IN_BYTES]) -> bool {
+        false
+    }
+
+    /// **Encrypt** or **decrypt** complete blocks in place with CTR mode, updating `counter` to
+    /// the next unused counter block. Return `false` if not supported.
+    fn ctr_apply(&mut self, _blocks: &mut [u8], _counter: &mut [u8; BLOCKSIZE_IN_BYTES]) -> bool {
+        false
+    }
+}
+
+/// The software backend: an [`AesOffload`] implemented with this library.
+///
+/// [`AesOffload`]: ../offload/trait.AesOffload.html
+#[derive(Clone, Default)]
+pub struct SoftwareAes {
+    cipher: Option<SoftwareCipher>,
+}
+
+#[derive(Clone)]
+enum SoftwareCipher {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+impl SoftwareAes {
+    /// Create the backend without a key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn cipher(&self) -> &dyn BlockCipher128 {
+        match &self.cipher {
+            Some(SoftwareCipher::Aes128(cipher)) => cipher,
+            Some(SoftwareCipher::Aes192(cipher)) => cipher,
+            Some(SoftwareCipher::Aes256(cipher)) => cipher,
+            None => panic!("No key has been loaded."),
+        }
+    }
+}
+
+impl AesOffload for SoftwareAes {
+    fn load_key(&mut self, key: &[u8]) -> Result<(), AesError> {
+        self.cipher = Some(match key.len() {
+            16 => SoftwareCipher::Aes128(Aes128::new(key.try_into().unwrap())),
+            24 => SoftwareCipher::Aes192(Aes192::new(key.try_into().unwrap())),
+            32 => SoftwareCipher::Aes256(Aes256::new(key.try_into().unwrap())),
+            _ => return Err(AesError::InvalidKeyLength),
+        });
+        Ok(())
+    }
+
+    fn encrypt_block(&mut self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        self.cipher().encrypt_block(block);
+    }
+
+    fn decrypt_block(&mut self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        self.cipher().decrypt_block(block);
+    }
+}
+
+/// A keyed [`AesOffload`], which is a [`BlockCipher128`].
+///
+/// The peripheral is borrowed mutably for each block, so an `Offloaded` is not `Sync`. Share it
+/// between threads or interrupt handlers behind a lock, see the `context` mod.
+/// # Examples
+/// ```
+/// use aes_frast::aes_with_operation_mode::cbc_enc;
+/// use aes_frast::offload::{AesOffload, Offloaded, SoftwareAes};
+/// use aes_frast::{Aes128, AesError};
+///
+/// // The code is the same for any backend `B`.
+/// fn encrypt<B: AesOffload>(backend: B, data: &mut [u8], iv: &[u8; 16]) -> Result<(), AesError> {
+///     let aes = Offloaded::new(backend, &[0x2Bu8; 16])?;
+///     let mut iv = *iv;
+///     aes.cbc_encrypt(data, &mut iv)
+/// }
+///
+/// let mut data = [0x5Au8; 32];
+/// let iv = [0u8; 16];
+/// encrypt(SoftwareAes::new(), &mut data, &iv).unwrap();
+///
+/// let mut expected = [0u8; 32];
+/// let aes = Aes128::new(&[0x2Bu8; 16]);
+/// cbc_enc(&[0x5Au8; 32], &mut expected, aes.encryption_subkeys(), &iv);
+/// assert_eq!(data, expected);
+/// ```
+///
+/// [`AesOffload`]: ../offload/trait.AesOffload.html
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+pub struct Offloaded<P> {
+    peripheral: RefCell<P>,
+}
+
+impl<P: AesOffload> Offloaded<P> {
+    /// Load `key` into `peripheral` and wrap it.
+    pub fn new(mut peripheral: P, key: &[u8]) -> Result<Self, AesError> {
+        peripheral.load_key(key)?;
+        Ok(Self {
+            peripheral: RefCell::new(peripheral),
+        })
+    }
+
+    /// Unwrap the peripheral.
+    pub fn into_inner(self) -> P {
+        self.peripheral.into_inner()
+    }
+
+    /// **Encrypt** complete blocks in place with CBC mode, updating `iv` to the last cipher
+    /// block. The peripheral runs the whole mode if it can.
+    ///
+    /// Return `Err(AesError::InvalidDataLength)` if the length of `blocks` is not a multiple
+    /// of 16.
+    pub fn cbc_encrypt(
+        &self,
+        blocks: &mut [u8],
+        iv: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) -> Result<(), AesError> {
+        check_complete_blocks(blocks)?;
+        // The borrow ends with the condition, since the fallback borrows the peripheral again
+        // for each block.
+        if !self.peripheral.borrow_mut().cbc_encrypt(blocks, iv) {
+            *iv = inplace::cbc_enc_with(self, blocks, iv);
+        }
+        Ok(())
+    }
+
+    /// **Decrypt** complete blocks in place with CBC mode, updating `iv` to the last cipher
+    /// block. The peripheral runs the whole mode if it can.
+    ///
+    /// Return `Err(AesError::InvalidDataLength)` if the length of `blocks` is not a multiple
+    /// of 16.
+    pub fn cbc_decrypt(
+        &self,
+        blocks: &mut [u8],
+        iv: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) -> Result<(), AesError> {
+        check_complete_blocks(blocks)?;
+        if !self.peripheral.borrow_mut().cbc_decrypt(blocks, iv) {
+            *iv = inplace::cbc_dec_with(self, blocks, iv);
+        }
+        Ok(())
+    }
+
+    /// **Encrypt** or **decrypt** complete blocks in place with CTR mode, updating `counter` to
+    /// the next unused counter block. The peripheral runs the whole mode if it can.
+    ///
+    /// Return `Err(AesError::InvalidDataLength)` if the length of `blocks` is not a multiple
+    /// of 16.
+    pub fn ctr_apply(
+        &self,
+        blocks: &mut [u8],
+        counter: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) -> Result<(), AesError> {
+        check_complete_blocks(blocks)?;
+        if !self.peripheral.borrow_mut().ctr_apply(blocks, counter) {
+            *counter = inplace::ctr_enc_dec_with(self, blocks, counter);
+        }
+        Ok(())
+    }
+}
+
+impl<P: AesOffload> BlockCipher128 for Offloaded<P> {
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        self.peripheral.borrow_mut().encrypt_block(block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        self.peripheral.borrow_mut().decrypt_block(block);
+    }
+}
+
+fn check_complete_blocks(blocks: &[u8]) -> Result<(), AesError> {
+    if blocks.len() & 0b1111 != 0 {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(())
+}
+
+impl_redacted_debug!(SoftwareAes, Offloaded<P>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_with_operation_mode::{cbc_dec_with, cbc_enc_with, ctr_enc_dec_with};
+    use crate::dma;
+
+    /// A "peripheral" which runs whole modes, and counts what it was asked to do.
+    #[derive(Default)]
+    struct ModePeripheral {
+        software: SoftwareAes,
+        blocks: usize,
+        modes: usize,
+    }
+
+    impl AesOffload for ModePeripheral {
+        fn load_key(&mut self, key: &[u8]) -> Result<(), AesError> {
+            self.software.load_key(key)
+        }
+
+        fn encrypt_block(&mut self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+            self.blocks += 1;
+            self.software.encrypt_block(block);
+        }
+
+        fn decrypt_block(&mut self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+            self.blocks += 1;
+            self.software.decrypt_block(block);
+        }
+
+        fn ctr_apply(&mut self, blocks: &mut [u8], counter: &mut [u8; BLOCKSIZE_IN_BYTES]) -> bool {
+            self.modes += 1;
+            let input = blocks.to_vec();
+            dma::ctr_apply(self.software.cipher(), &input, blocks, counter).unwrap();
+            true
+        }
+    }
+
+    #[test]
+    fn offloaded_modes_work() {
+        let key: [u8; 24] = core::array::from_fn(|i| i as u8);
+        let software = Offloaded::new(SoftwareAes::new(), &key).unwrap();
+        let hardware = Offloaded::new(ModePeripheral::default(), &key).unwrap();
+        let plain: Vec<u8> = (0..64).collect();
+        let iv = [0x0Fu8; BLOCKSIZE_IN_BYTES];
+
+        let mut expected = vec![0u8; 64];
+        cbc_enc_with(&software, &plain, &mut expected, &iv);
+        let mut data = plain.clone();
+        let mut chained_iv = iv;
+        hardware.cbc_encrypt(&mut data, &mut chained_iv).unwrap();
+        assert_eq!(data, expected);
+        assert_eq!(chained_iv, expected[48..]);
+        chained_iv = iv;
+        hardware.cbc_decrypt(&mut data, &mut chained_iv).unwrap();
+        assert_eq!(data, plain);
+        cbc_dec_with(&hardware, &expected, &mut data, &iv);
+        assert_eq!(data, plain);
+
+        ctr_enc_dec_with(&software, &plain, &mut expected, &iv);
+        let mut counter = iv;
+        data.copy_from_slice(&plain);
+        software.ctr_apply(&mut data, &mut counter).unwrap();
+        assert_eq!(data, expected);
+        counter = iv;
+        data.copy_from_slice(&plain);
+        hardware.ctr_apply(&mut data, &mut counter).unwrap();
+        assert_eq!(data, expected);
+
+        let peripheral = hardware.into_inner();
+        assert_eq!(peripheral.modes, 1);
+        // CBC twice (4 blocks each) and the `_with` decryption (4 blocks) ran on blocks.
+        assert_eq!(peripheral.blocks, 12);
+        assert_eq!(
+            software.cbc_encrypt(&mut [0u8; 15], &mut counter),
+            Err(AesError::InvalidDataLength)
+        );
+        assert!(matches!(
+            Offloaded::new(SoftwareAes::new(), &[0u8; 20]),
+            Err(AesError::InvalidKeyLength)
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+//! # openssl_enc
+//! `openssl_enc` reads and writes the files of `openssl enc -aes-*-cbc` with a password: the
+//! magic `Salted__`, an 8-byte salt, and the CBC cipher with PKCS #7 padding.
+//!
+//! The key and the IV are derived from the password and the salt by `EVP_BytesToKey` with one
+//! iteration. The digest must match the one used by OpenSSL: SHA-256 is the default of OpenSSL
+//! 1.1.0 and later, MD5 of the earlier versions (`-md md5`).
+//!
+//! This key derivation is fast, so weak passwords can be guessed easily, and the format has no
+//! authentication. Use it to exchange files with OpenSSL, not to design new formats.
+//! # Examples
+//! ```
+//! use aes_frast::key::KeySize;
+//! use aes_frast::openssl_enc::{decrypt, encrypt, KdfDigest};
+//!
+//! // $ printf 'The quick brown fox jumps over the lazy dog' | openssl enc -aes-256-cbc \
+//! //     -md sha256 -S 0102030405060708 -pass pass:secret
+//! let salt = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+//! let plain = b"The quick brown fox jumps over the lazy dog";
+//!
+//! let file = encrypt(b"secret", &salt, KeySize::Aes256, KdfDigest::Sha256, plain);
+//! assert_eq!(&file[..8], b"Salted__");
+//! assert_eq!(&file[8..16], &salt);
+//! assert_eq!(&file[16..], &[0xF1, 0x1C, 0x07, 0x4A, 0xC4, 0x62, 0x65, 0x49,
+//!                           0xFC, 0xA9, 0xD7, 0xE6, 0xEB, 0x20, 0xF8, 0xCC,
+//!                           0x31, 0x9C, 0x0E, 0x67, 0x78, 0x72, 0x09, 0x5D,
+//!                           0x8F, 0x9A, 0x9B, 0xF6, 0xA3, 0xD4, 0x04, 0x13,
+//!                           0x56, 0x3A, 0x49, 0x65, 0x18, 0xF2, 0x55, 0xAB,
+//!                           0x2D, 0x1C, 0x5E, 0x2E, 0x48, 0x59, 0x62, 0x2F]);
+//!
+//! let decrypted = decrypt(b"secret", KeySize::Aes256, KdfDigest::Sha256, &file).unwrap();
+//! assert_eq!(decrypted, plain);
+//! ```
+use super::aes_core::{BLOCKSIZE_IN_BYTES, KEY_BYTES_256BIT};
+use super::digest;
+use super::error::AesError;
+use super::evp;
+use super::key::KeySize;
+use super::rng::Rng;
+
+/// The magic at the start of the files of `openssl enc` with a salt.
+pub const MAGIC: &[u8; 8] = b"Salted__";
+/// The length in bytes of the salt.
+pub const SALT_BYTES: usize = 8;
+/// The length in bytes of the header, the magic and the salt.
+pub const HEADER_BYTES: usize = MAGIC.len() + SALT_BYTES;
+
+/// The digest of `EVP_BytesToKey`, which is the `-md` option of `openssl enc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdfDigest {
+    /// MD5, the default of OpenSSL 1.0.2 and earlier.
+    Md5,
+    /// SHA-256, the default of OpenSSL 1.1.0 and later.
+    Sha256,
+}
+
+/// Derive a key and an IV from a password and a salt, as `EVP_BytesToKey` with one iteration.
+///
+/// The digest is chained over `previous digest ‖ password ‖ salt` until `key` and then `iv` are
+/// filled.
+///
+/// * *parameter* `digest`: the digest.
+/// * *parameter* `password`: the password.
+/// * *parameter* `salt`: the salt, which may be empty (`openssl enc -nosalt`).
+/// * *parameter* `key`: the output key.
+/// * *parameter* `iv`: the output IV, which may be empty.
+/// # Examples
+/// ```
+/// use aes_frast::openssl_enc::{evp_bytes_to_key, KdfDigest};
+///
+/// // $ openssl enc -aes-128-cbc -md md5 -S 0102030405060708 -pass pass:secret -P
+/// let salt = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+/// let mut key = [0u8; 16];
+/// let mut iv = [0u8; 16];
+/// evp_bytes_to_key(KdfDigest::Md5, b"secret", &salt, &mut key, &mut iv);
+/// assert_eq!(key, [0xC9, 0xE5, 0xA1, 0xBD, 0x21, 0x6D, 0xBE, 0x13,
+///                  0x17, 0xE2, 0x30, 0xCE, 0xF4, 0x8F, 0x38, 0xEE]);
+/// assert_eq!(iv, [0x7F, 0x0E, 0x17, 0xAD, 0x64, 0x02, 0x21, 0x44,
+///                 0xBC, 0xCE, 0xC4, 0xA1, 0xAA, 0x28, 0x79, 0xAB]);
+/// ```
+pub fn evp_bytes_to_key(
+    digest: KdfDigest,
+    password: &[u8],
+    salt: &[u8],
+    key: &mut [u8],
+    iv: &mut [u8],
+) {
+    let mut previous = [0u8; digest::SHA256_BYTES];
+    let mut previous_len = 0;
+    let mut written = 0;
+    let total = key.len() + iv.len();
+    while written < total {
+        let parts: [&[u8]; 3] = [&previous[..previous_len], password, salt];
+        let mut next = [0u8; digest::SHA256_BYTES];
+        previous_len = match digest {
+            KdfDigest::Md5 => {
+                next[..digest::MD5_BYTES].copy_from_slice(&digest::md5(&parts));
+                digest::MD5_BYTES
+            }
+            KdfDigest::Sha256 => {
+                next = digest::sha256(&parts);
+                digest::SHA256_BYTES
+            }
+        };
+        previous = next;
+        for &byte in previous[..previous_len].iter().take(total - written) {
+            if written < key.len() {
+                key[written] = byte;
+            } else {
+                iv[written - key.len()] = byte;
+            }
+            written += 1;
+        }
+    }
+    previous.fill(0);
+    std::hint::black_box(&previous);
+}
+
+fn derive(
+    password: &[u8],
+    salt: &[u8; SALT_BYTES],
+    size: KeySize,
+    digest: KdfDigest,
+) -> ([u8; KEY_BYTES_256BIT], [u8; BLOCKSIZE_IN_BYTES]) {
+    let mut key = [0u8; KEY_BYTES_256BIT];
+    let mut iv = [0u8; BLOCKSIZE_IN_BYTES];
+    evp_bytes_to_key(digest, password, salt, &mut key[..size.bytes()], &mut iv);
+    (key, iv)
+}
+
+fn wipe(key: &mut [u8; KEY_BYTES_256BIT]) {
+    key.fill(0);
+    std::hint::black_box(key);
+}
+
+/// Encrypt as `openssl enc -aes-*-cbc -pass ... -S <salt>`.
+///
+/// * *parameter* `password`: the password.
+/// * *parameter* `salt`: the salt, which must be random and different for every file.
+/// * *parameter* `size`: the key size, which selects `-aes-128-cbc`, `-aes-192-cbc` or
+///   `-aes-256-cbc`.
+/// * *parameter* `digest`: the digest of the key derivation, the `-md` option.
+/// * *parameter* `plain`: the plain data.
+/// * *return* : the file: the magic, the salt and the cipher.
+pub fn encrypt(
+    password: &[u8],
+    salt: &[u8; SALT_BYTES],
+    size: KeySize,
+    digest: KdfDigest,
+    plain: &[u8],
+) -> Vec<u8> {
+    let (mut key, iv) = derive(password, salt, size, digest);
+    let cipher = match size {
+        KeySize::Aes128 => evp::aes_128_cbc_encrypt(key[..16].try_into().unwrap(), &iv, plain),
+        KeySize::Aes192 => evp::aes_192_cbc_encrypt(key[..24].try_into().unwrap(), &iv, plain),
+        KeySize::Aes256 => evp::aes_256_cbc_encrypt(&key, &iv, plain),
+    };
+    wipe(&mut key);
+    let mut file = Vec::with_capacity(HEADER_BYTES + cipher.len());
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(salt);
+    file.extend_from_slice(&cipher);
+    file
+}
+
+/// Encrypt as [`encrypt`], with a salt from `rng`.
+///
+/// [`encrypt`]: ../openssl_enc/fn.encrypt.html
+pub fn encrypt_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    password: &[u8],
+    size: KeySize,
+    digest: KdfDigest,
+    plain: &[u8],
+) -> Vec<u8> {
+    let mut salt = [0u8; SALT_BYTES];
+    rng.fill_bytes(&mut salt);
+    encrypt(password, &salt, size, digest, plain)
+}
+
+/// Split a file into the salt and the cipher.
+///
+/// * *parameter* `file`: the file.
+/// * *return* : the salt and the cipher, or `Err(AesError::InvalidParameter)` if the file is
+///   shorter than the header or doesn't start with the magic.
+pub fn split_header(file: &[u8]) -> Result<([u8; SALT_BYTES], &[u8]), AesError> {
+    if file.len() < HEADER_BYTES || &file[..MAGIC.len()] != MAGIC {
+        return Err(AesError::InvalidParameter);
+    }
+    let salt = file[MAGIC.len()..HEADER_BYTES].try_into().unwrap();
+    Ok((salt, &file[HEADER_BYTES..]))
+}
+
+/// Decrypt as `openssl enc -d -aes-*-cbc -pass ...`.
+///
+/// * *parameter* `password`: the password.
+/// * *parameter* `size`: the key size.
+/// * *parameter* `digest`: the digest of the key derivation, the `-md` option.
+/// * *parameter* `file`: the file: the magic, the salt and the cipher.
+/// * *return* : the plain data, or
+///   * `Err(AesError::InvalidParameter)` if the header is missing.
+///   * `Err(AesError::InvalidDataLength)` if the cipher is not made of complete blocks.
+///   * `Err(AesError::InvalidPadding)` if the padding is wrong, which is also what a wrong
+///     password, key size or digest usually gives.
+pub fn decrypt(
+    password: &[u8],
+    size: KeySize,
+    digest: KdfDigest,
+    file: &[u8],
+) -> Result<Vec<u8>, AesError> {
+    let (salt, cipher) = split_header(file)?;
+    let (mut key, iv) = derive(password, &salt, size, digest);
+    let plain = match size {
+        KeySize::Aes128 => evp::aes_128_cbc_decrypt(key[..16].try_into().unwrap(), &iv, cipher),
+        KeySize::Aes192 => evp::aes_192_cbc_decrypt(key[..24].try_into().unwrap(), &iv, cipher),
+        KeySize::Aes256 => evp::aes_256_cbc_decrypt(&key, &iv, cipher),
+    };
+    wipe(&mut key);
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openssl_enc_works() {
+        // $ printf 'hello' | openssl enc -aes-128-cbc -md md5 -S 0102030405060708 -pass pass:secret
+        let salt = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let file = encrypt(b"secret", &salt, KeySize::Aes128, KdfDigest::Md5, b"hello");
+        assert_eq!(
+            &file[HEADER_BYTES..],
+            &[
+                0xCE, 0xAA, 0xA0, 0xDA, 0x24, 0x7A, 0x31, 0xB1, 0xC7, 0x4F, 0x19, 0x80, 0x69, 0x96,
+                0xEA, 0x5E
+            ]
+        );
+        assert_eq!(
+            decrypt(b"secret", KeySize::Aes128, KdfDigest::Md5, &file).unwrap(),
+            b"hello"
+        );
+
+        // The key and the IV of AES-192 with MD5 need a third round of the digest.
+        let file = encrypt(b"secret", &salt, KeySize::Aes192, KdfDigest::Md5, b"");
+        assert_eq!(file.len(), HEADER_BYTES + BLOCKSIZE_IN_BYTES);
+        assert_eq!(
+            decrypt(b"secret", KeySize::Aes192, KdfDigest::Md5, &file).unwrap(),
+            b""
+        );
+
+        assert_eq!(
+            decrypt(b"secret", KeySize::Aes128, KdfDigest::Md5, &file[1..]),
+            Err(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            decrypt(b"secret", KeySize::Aes128, KdfDigest::Md5, &file[..20]),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+
+    #[test]
+    fn evp_bytes_to_key_works() {
+        // $ openssl enc -aes-256-cbc -md sha256 -S 0102030405060708 -pass pass:secret -P
+        let salt = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let mut key = [0u8; 32];
+        let mut iv = [0u8; 16];
+        evp_bytes_to_key(KdfDigest::Sha256, b"secret", &salt, &mut key, &mut iv);
+        assert_eq!(
+            key,
+            [
+                0x03, 0xB3, 0x75, 0x94, 0x0C, 0xB9, 0x6C, 0x16, 0xF8, 0x4F, 0xAA, 0x87, 0xF5, 0xEF,
+                0x39, 0xCC, 0x0B, 0xC7, 0x06, 0x6C, 0xCD, 0x3E, 0x14, 0x45, 0x6D, 0x9D, 0x74, 0xE4,
+                0x38, 0xE3, 0x58, 0x32
+            ]
+        );
+        assert_eq!(
+            iv,
+            [
+                0x90, 0x4A, 0xEB, 0xC6, 0xE5, 0x88, 0xFD, 0xB4, 0x9F, 0xD1, 0x58, 0x06, 0xBB, 0x4F,
+                0xEE, 0x6F
+            ]
+        );
+    }
+}
@@ -3,6 +3,10 @@
 //! `aes_core` module.
 
 use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::error::AesError;
+use super::rng::Rng;
+use std::fmt;
+use std::str::FromStr;
 
 /// Add PKCS #7 padding
 ///
@@ -22,10 +26,48 @@ use super::aes_core::BLOCKSIZE_IN_BYTES;
 /// ```
 pub fn pa_pkcs7(input_vec: &mut Vec<u8>) -> usize {
     let r = BLOCKSIZE_IN_BYTES - (input_vec.len() & 0b1111);
-    input_vec.append(&mut vec![r as u8; r]);
+    input_vec.resize(input_vec.len() + r, r as u8);
     r
 }
 
+/// Check that the data ends with a valid PKCS #7 padding string.
+pub(crate) fn check_pkcs7(data: &[u8]) -> Result<(), AesError> {
+    let r = match data.last() {
+        Some(&r) => r as usize,
+        None => return Err(AesError::InvalidPadding),
+    };
+    if r == 0 || r > BLOCKSIZE_IN_BYTES || r > data.len() {
+        return Err(AesError::InvalidPadding);
+    }
+    // Every padding byte is checked, without stopping at the first wrong one.
+    let diff = data[(data.len() - r)..]
+        .iter()
+        .fold(0u8, |diff, &x| diff | (x ^ r as u8));
+    if diff != 0 {
+        return Err(AesError::InvalidPadding);
+    }
+    Ok(())
+}
+
+/// Check that the data ends with a valid ANSIX923 padding string.
+pub(crate) fn check_ansix923(data: &[u8]) -> Result<(), AesError> {
+    let r = match data.last() {
+        Some(&r) => r as usize,
+        None => return Err(AesError::InvalidPadding),
+    };
+    if r == 0 || r > BLOCKSIZE_IN_BYTES || r > data.len() {
+        return Err(AesError::InvalidPadding);
+    }
+    // Every padding byte is checked, without stopping at the first wrong one.
+    let diff = data[(data.len() - r)..(data.len() - 1)]
+        .iter()
+        .fold(0u8, |diff, &x| diff | x);
+    if diff != 0 {
+        return Err(AesError::InvalidPadding);
+    }
+    Ok(())
+}
+
 /// Add ANSIX923 padding
 ///
 /// * *parameter* `input_vec`: the vec that contains original data.
@@ -43,20 +85,63 @@ pub fn pa_pkcs7(input_vec: &mut Vec<u8>) -> usize {
 ///                         0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x09u8]);
 /// ```
 pub fn pa_ansix923(input_vec: &mut Vec<u8>) -> usize {
+    let r = BLOCKSIZE_IN_BYTES - (input_vec.len() & 0b1111);
+    input_vec.resize(input_vec.len() + r, 0);
+    *input_vec.last_mut().unwrap() = r as u8;
+    r
+}
+
+/// Add ISO 10126 padding
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *parameter* `rng`: the source of the random padding bytes.
+/// * *return* : the added bytes' length.
+///
+/// The padding bytes are random, except the last one which is the length of the padding. So it
+/// can be removed with [`de_ansix923_pkcs7`].
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_ansix923_pkcs7, pa_iso10126};
+/// use aes_frast::rng::Rng;
+///
+/// struct Fixed;
+///
+/// impl Rng for Fixed {
+///     fn fill_bytes(&mut self, dest: &mut [u8]) {
+///         dest.fill(0xA5);
+///     }
+/// }
+///
+/// let mut origin = vec![0xFFu8; 7];
+///
+/// let padding_length = pa_iso10126(&mut origin, &mut Fixed);
+///
+/// assert_eq!(padding_length, 9);
+/// assert_eq!(origin, vec![0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xA5u8,
+///                         0xA5u8, 0xA5u8, 0xA5u8, 0xA5u8, 0xA5u8, 0xA5u8, 0xA5u8, 0x09u8]);
+/// assert_eq!(de_ansix923_pkcs7(&mut origin), 9);
+/// assert_eq!(origin, vec![0xFFu8; 7]);
+/// ```
+///
+/// [`de_ansix923_pkcs7`]: ../padding_128bit/fn.de_ansix923_pkcs7.html
+pub fn pa_iso10126<R: Rng + ?Sized>(input_vec: &mut Vec<u8>, rng: &mut R) -> usize {
     let r = BLOCKSIZE_IN_BYTES - (input_vec.len() & 0b1111);
     let mut tail = vec![0u8; r];
-    *tail.last_mut().unwrap() = r as u8;
+    rng.fill_bytes(&mut tail[..(r - 1)]);
+    tail[r - 1] = r as u8;
     input_vec.append(&mut tail);
     r
 }
 
-/// Remove ANSIX923 or PKCS #7 padding
+/// Remove ANSIX923 or PKCS #7 padding (or ISO 10126 padding)
 ///
 /// * *parameter* `input_vec`: the vec that contains original data.
 /// * *return* : the removed bytes' length.
 ///
 /// **Attention!** Please be sure the parameter ends with exactly the ANSIX923 or PKCS #7 padding
-/// string. This function does NOT perform checks.
+/// string. This function does NOT perform checks: it trusts the last byte, and panics if it is
+/// 0 or greater than the length of the data. Use [`try_de_pkcs7`] or [`try_de_ansix923`] for the
+/// data which may be corrupted or forged.
 /// # Examples
 /// ```
 /// use aes_frast::padding_128bit::de_ansix923_pkcs7;
@@ -77,12 +162,74 @@ pub fn pa_ansix923(input_vec: &mut Vec<u8>) -> usize {
 /// assert_eq!(ansix923, expected);
 /// assert_eq!(pkcs7, expected);
 /// ```
+///
+/// [`try_de_pkcs7`]: ../padding_128bit/fn.try_de_pkcs7.html
+/// [`try_de_ansix923`]: ../padding_128bit/fn.try_de_ansix923.html
 pub fn de_ansix923_pkcs7(input_vec: &mut Vec<u8>) -> usize {
     let r = input_vec.pop().unwrap() as usize;
     input_vec.truncate(input_vec.len() + 1 - r);
     r
 }
 
+/// Remove PKCS #7 padding, checking it.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data doesn't
+///   end with a PKCS #7 padding string, in which case `input_vec` is left as it is.
+///
+/// The padding length must be 1 to 16 and fit in the data, and every padding byte is checked.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{pa_pkcs7, try_de_pkcs7};
+/// use aes_frast::AesError;
+///
+/// let mut data = vec![0xFFu8; 7];
+/// pa_pkcs7(&mut data);
+/// assert_eq!(try_de_pkcs7(&mut data), Ok(9));
+/// assert_eq!(data, vec![0xFFu8; 7]);
+/// assert_eq!(try_de_pkcs7(&mut data), Err(AesError::InvalidPadding));
+/// assert_eq!(try_de_pkcs7(&mut Vec::new()), Err(AesError::InvalidPadding));
+///
+/// // A padding length greater than 16, or a wrong padding byte, is rejected.
+/// let mut forged = vec![0x11u8; 32];
+/// assert_eq!(try_de_pkcs7(&mut forged), Err(AesError::InvalidPadding));
+/// let mut forged = vec![0x03u8; 16];
+/// forged[13] = 0x04;
+/// assert_eq!(try_de_pkcs7(&mut forged), Err(AesError::InvalidPadding));
+/// assert_eq!(forged.len(), 16);
+/// ```
+pub fn try_de_pkcs7(input_vec: &mut Vec<u8>) -> Result<usize, AesError> {
+    check_pkcs7(input_vec)?;
+    Ok(de_ansix923_pkcs7(input_vec))
+}
+
+/// Remove ANSIX923 padding, checking it.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data doesn't
+///   end with an ANSIX923 padding string, in which case `input_vec` is left as it is.
+///
+/// The padding length must be 1 to 16 and fit in the data, and every padding byte before it
+/// must be zero.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{pa_ansix923, try_de_ansix923};
+/// use aes_frast::AesError;
+///
+/// let mut data = vec![0xFFu8; 7];
+/// pa_ansix923(&mut data);
+/// assert_eq!(try_de_ansix923(&mut data), Ok(9));
+/// assert_eq!(data, vec![0xFFu8; 7]);
+///
+/// let mut forged = vec![0x00u8; 16];
+/// forged[15] = 0x20;
+/// assert_eq!(try_de_ansix923(&mut forged), Err(AesError::InvalidPadding));
+/// ```
+pub fn try_de_ansix923(input_vec: &mut Vec<u8>) -> Result<usize, AesError> {
+    check_ansix923(input_vec)?;
+    Ok(de_ansix923_pkcs7(input_vec))
+}
+
 /// Add Zeros padding
 ///
 /// * *parameter* `input_vec`: the vec that contains original data.
@@ -172,8 +319,12 @@ pub fn pa_zeros_ifnotcomplete(input_vec: &mut Vec<u8>) -> usize {
 /// * *parameter* `input_vec`: the vec that contains original data.
 /// * *return* : the removed bytes' length.
 ///
-/// **Attention!** If the origin data ends with zero(s) (one or more 0xFF), depadding will remove
-/// all these zeros.
+/// Only the zeros in the last block (at most 16 bytes) are removed, since Zeros padding is never
+/// longer than a block. It never panics: an empty vec is left as it is, and a vec of zeros loses
+/// its last block of them only.
+///
+/// **Attention!** If the origin data ends with zero(s) (one or more 0x00), depadding will remove
+/// these zeros too.
 /// # Examples
 /// ```
 /// use aes_frast::padding_128bit::de_zeros;
@@ -185,18 +336,41 @@ pub fn pa_zeros_ifnotcomplete(input_vec: &mut Vec<u8>) -> usize {
 ///
 /// assert_eq!(padding_length, 9);
 /// assert_eq!(zeros, vec![0xFFu8; 7]);
+///
+/// let mut only_zeros = vec![0x00u8; 40];
+/// assert_eq!(de_zeros(&mut only_zeros), 16);
+/// assert_eq!(only_zeros.len(), 24);
+/// assert_eq!(de_zeros(&mut Vec::new()), 0);
 /// ```
 pub fn de_zeros(input_vec: &mut Vec<u8>) -> usize {
-    let mut tmp: u8;
-    let mut count = 0;
-    loop {
-        tmp = input_vec.pop().unwrap();
-        if tmp != 0 {
-            input_vec.push(tmp);
-            break count;
-        }
-        count += 1;
-    }
+    let r = trailing_zeros_in_block(input_vec);
+    input_vec.truncate(input_vec.len() - r);
+    r
+}
+
+/// The number of zeros at the end of the data, counted in the last 16 bytes only.
+fn trailing_zeros_in_block(data: &[u8]) -> usize {
+    data.iter()
+        .rev()
+        .take(BLOCKSIZE_IN_BYTES)
+        .take_while(|&&x| x == 0)
+        .count()
+}
+
+/// Remove Zeros padding, checking that the data is not only zeros.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data is empty
+///   or only zeros, in which case `input_vec` is left as it is.
+///
+/// Like [`de_zeros`], only the zeros in the last block are removed.
+///
+/// [`de_zeros`]: ../padding_128bit/fn.de_zeros.html
+pub fn try_de_zeros(input_vec: &mut Vec<u8>) -> Result<usize, AesError> {
+    let original = de_zeros_slice(input_vec)?;
+    let r = input_vec.len() - original;
+    input_vec.truncate(original);
+    Ok(r)
 }
 
 /// Drop the last incomplete or complete block.
@@ -229,3 +403,883 @@ pub fn drop_last_block(input_vec: &mut Vec<u8>) -> usize {
     input_vec.truncate(input_vec.len() - r);
     r
 }
+
+/// Add PADMÉ padding, which hides the length of the original data.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the added bytes' length.
+///
+/// The data (with at least one byte of padding) is extended to the length chosen by PADMÉ,
+/// which keeps only the leading bits of the length, and then to a multiple of 16. The padded
+/// length leaks only `O(log log L)` bits about the original length `L`, while the overhead is at
+/// most about `1 / (2 * log2 L)` (e.g. 12% at 1 KiB, 3% at 1 GiB). The padding string is a byte
+/// `0x80` followed by zeros, like in ISO/IEC 7816-4, so it can be removed by [`de_padme`].
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::pa_padme;
+///
+/// let mut origin = vec![0xFFu8; 7];
+/// let padding_length = pa_padme(&mut origin);
+/// assert_eq!(padding_length, 9);
+/// assert_eq!(origin, vec![0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0x80u8,
+///                         0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8]);
+///
+/// // All the lengths from 993 to 1023 are padded to 1024.
+/// let mut origin = vec![0xFFu8; 1000];
+/// let padding_length = pa_padme(&mut origin);
+/// assert_eq!(padding_length, 24);
+/// assert_eq!(origin.len(), 1024);
+/// ```
+///
+/// [`de_padme`]: ../padding_128bit/fn.de_padme.html
+pub fn pa_padme(input_vec: &mut Vec<u8>) -> usize {
+    let padded = padme_length(input_vec.len());
+    let r = padded - input_vec.len();
+    input_vec.push(0x80);
+    input_vec.resize(padded, 0);
+    r
+}
+
+/// The length which PADMÉ pads `len` bytes of data to.
+fn padme_length(len: usize) -> usize {
+    // At least the marker byte is added.
+    let length = len + 1;
+    let padded = if length < 2 {
+        length
+    } else {
+        let e = usize::BITS - 1 - length.leading_zeros();
+        let s = u32::BITS - e.leading_zeros();
+        let mask = (1usize << (e - s)) - 1;
+        (length + mask) & !mask
+    };
+    (padded + BLOCKSIZE_IN_BYTES - 1) & !(BLOCKSIZE_IN_BYTES - 1)
+}
+
+/// Remove PADMÉ padding
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length.
+///
+/// **Attention!** Please be sure the parameter ends with the padding string added by
+/// [`pa_padme`]. This function only removes the trailing zeros and the `0x80` before them, it
+/// does NOT check the padded length.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_padme, pa_padme};
+///
+/// let mut data = vec![0x00u8; 1000];
+/// pa_padme(&mut data);
+///
+/// let padding_length = de_padme(&mut data);
+/// assert_eq!(padding_length, 24);
+/// assert_eq!(data, vec![0x00u8; 1000]);
+/// ```
+///
+/// [`pa_padme`]: ../padding_128bit/fn.pa_padme.html
+pub fn de_padme(input_vec: &mut Vec<u8>) -> usize {
+    let last = input_vec.iter().rposition(|&x| x != 0).unwrap_or(0);
+    let r = input_vec.len() - last;
+    input_vec.truncate(last);
+    r
+}
+
+/// Remove PADMÉ padding, checking the `0x80` before the trailing zeros.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data doesn't
+///   end with `0x80` and zeros, in which case `input_vec` is left as it is. Like [`de_padme`], it
+///   does NOT check the padded length.
+///
+/// [`de_padme`]: ../padding_128bit/fn.de_padme.html
+pub fn try_de_padme(input_vec: &mut Vec<u8>) -> Result<usize, AesError> {
+    match input_vec.iter().rposition(|&x| x != 0) {
+        Some(last) if input_vec[last] == 0x80 => {
+            let r = input_vec.len() - last;
+            input_vec.truncate(last);
+            Ok(r)
+        }
+        _ => Err(AesError::InvalidPadding),
+    }
+}
+
+/// The length of the ISO/IEC 7816-4 padding string at the end of the data, checking it.
+fn check_iso7816(data: &[u8]) -> Result<usize, AesError> {
+    match data.iter().rposition(|&x| x != 0) {
+        Some(last) if data[last] == 0x80 && data.len() - last <= BLOCKSIZE_IN_BYTES => {
+            Ok(data.len() - last)
+        }
+        _ => Err(AesError::InvalidPadding),
+    }
+}
+
+/// Add ISO/IEC 7816-4 padding
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the added bytes' length.
+///
+/// The padding string is a byte `0x80` followed by zeros, which is also the padding method 2 of
+/// ISO/IEC 9797-1.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::pa_iso7816;
+///
+/// let mut origin = vec![0xFFu8; 7];
+/// let padding_length = pa_iso7816(&mut origin);
+///
+/// assert_eq!(padding_length, 9);
+/// assert_eq!(origin, vec![0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0x80u8,
+///                         0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8]);
+/// ```
+pub fn pa_iso7816(input_vec: &mut Vec<u8>) -> usize {
+    let r = BLOCKSIZE_IN_BYTES - (input_vec.len() & 0b1111);
+    input_vec.push(0x80);
+    input_vec.resize(input_vec.len() + r - 1, 0);
+    r
+}
+
+/// Remove ISO/IEC 7816-4 padding, checking it.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data doesn't
+///   end with `0x80` and at most 15 zeros, in which case `input_vec` is left as it is.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{pa_iso7816, try_de_iso7816};
+/// use aes_frast::AesError;
+///
+/// let mut data = vec![0x00u8; 7];
+/// pa_iso7816(&mut data);
+/// assert_eq!(try_de_iso7816(&mut data), Ok(9));
+/// assert_eq!(data, vec![0x00u8; 7]);
+/// assert_eq!(try_de_iso7816(&mut data), Err(AesError::InvalidPadding));
+/// ```
+pub fn try_de_iso7816(input_vec: &mut Vec<u8>) -> Result<usize, AesError> {
+    let r = check_iso7816(input_vec)?;
+    input_vec.truncate(input_vec.len() - r);
+    Ok(r)
+}
+
+/// The byte of TBC padding after `data`, which complements the last bit of the data.
+fn tbc_byte(data: &[u8]) -> u8 {
+    match data.last() {
+        Some(&last) if last & 1 == 1 => 0x00,
+        // The empty data is padded as if its last bit were 0, like Bouncy Castle does.
+        _ => 0xFF,
+    }
+}
+
+/// The length of the TBC padding string at the end of the data, checking it.
+fn check_tbc(data: &[u8]) -> Result<usize, AesError> {
+    let pad = match data.last() {
+        Some(&pad) if pad == 0x00 || pad == 0xFF => pad,
+        _ => return Err(AesError::InvalidPadding),
+    };
+    let original = data
+        .iter()
+        .rposition(|&x| x != pad)
+        .map_or(0, |last| last + 1);
+    let r = data.len() - original;
+    if r > BLOCKSIZE_IN_BYTES || tbc_byte(&data[..original]) != pad {
+        return Err(AesError::InvalidPadding);
+    }
+    Ok(r)
+}
+
+/// Add TBC (Trailing Bit Complement) padding
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the added bytes' length.
+///
+/// The padding bytes are all `0xFF` if the last bit of the data is 0, or all `0x00` if it is 1,
+/// so the padding always differs from the last byte of the data and can be removed without a
+/// length byte. Like the other paddings here, a complete block is added after a complete
+/// end-block.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::pa_tbc;
+///
+/// let mut origin = vec![0xFFu8; 7];
+/// let padding_length = pa_tbc(&mut origin);
+///
+/// assert_eq!(padding_length, 9);
+/// assert_eq!(origin, vec![0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0x00u8,
+///                         0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8]);
+///
+/// let mut origin = vec![0x00u8; 7];
+/// pa_tbc(&mut origin);
+/// assert_eq!(origin, vec![0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8, 0xFFu8,
+///                         0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8]);
+/// ```
+pub fn pa_tbc(input_vec: &mut Vec<u8>) -> usize {
+    let r = BLOCKSIZE_IN_BYTES - (input_vec.len() & 0b1111);
+    let pad = tbc_byte(input_vec);
+    input_vec.resize(input_vec.len() + r, pad);
+    r
+}
+
+/// Remove TBC (Trailing Bit Complement) padding
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length.
+///
+/// **Attention!** Please be sure the parameter ends with the padding string added by
+/// [`pa_tbc`]. This function removes all the trailing bytes which equal the last byte, and does
+/// NOT perform checks.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_tbc, pa_tbc};
+///
+/// let mut data = vec![0x01u8, 0x02u8, 0x03u8];
+/// pa_tbc(&mut data);
+///
+/// let padding_length = de_tbc(&mut data);
+/// assert_eq!(padding_length, 13);
+/// assert_eq!(data, vec![0x01u8, 0x02u8, 0x03u8]);
+/// ```
+///
+/// [`pa_tbc`]: ../padding_128bit/fn.pa_tbc.html
+pub fn de_tbc(input_vec: &mut Vec<u8>) -> usize {
+    let pad = *input_vec.last().unwrap();
+    let original = input_vec
+        .iter()
+        .rposition(|&x| x != pad)
+        .map_or(0, |last| last + 1);
+    let r = input_vec.len() - original;
+    input_vec.truncate(original);
+    r
+}
+
+/// Remove TBC (Trailing Bit Complement) padding, checking it.
+///
+/// * *parameter* `input_vec`: the vec that contains original data.
+/// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data doesn't
+///   end with 1 to 16 bytes of `0x00` or `0xFF` which complement the last bit of the data before
+///   them, in which case `input_vec` is left as it is.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::try_de_tbc;
+/// use aes_frast::AesError;
+///
+/// let mut data = vec![0x02u8, 0xFFu8, 0xFFu8];
+/// assert_eq!(try_de_tbc(&mut data), Ok(2));
+/// assert_eq!(data, vec![0x02u8]);
+///
+/// // 0x03 ends with the bit 1, so it must be followed by 0x00 bytes.
+/// let mut data = vec![0x03u8, 0xFFu8, 0xFFu8];
+/// assert_eq!(try_de_tbc(&mut data), Err(AesError::InvalidPadding));
+/// ```
+pub fn try_de_tbc(input_vec: &mut Vec<u8>) -> Result<usize, AesError> {
+    let r = check_tbc(input_vec)?;
+    input_vec.truncate(input_vec.len() - r);
+    Ok(r)
+}
+
+/// The length of `len` bytes of data after PKCS #7, ANSIX923, ISO 10126, ISO/IEC 7816-4, TBC or
+/// Zeros padding, i.e. the capacity a caller-provided buffer needs for the `_slice` functions.
+///
+/// * *parameter* `len`: the length of the original data.
+/// * *return* : the length of the padded data, which is 1 to 16 bytes more than `len`.
+///
+/// It is a `const fn`, so it can size a buffer at compile time, e.g. a fixed DMA buffer on an
+/// embedded target. [`pa_padme`] pads to other lengths.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{pa_pkcs7_slice, padded_length};
+///
+/// const RECORD: usize = 40;
+/// let mut buf = [0u8; padded_length(RECORD)];
+/// assert_eq!(buf.len(), 48);
+/// assert_eq!(pa_pkcs7_slice(&mut buf, RECORD), Ok(48));
+/// assert_eq!(padded_length(48), 64);
+/// ```
+///
+/// [`pa_padme`]: ../padding_128bit/fn.pa_padme.html
+pub const fn padded_length(len: usize) -> usize {
+    len + BLOCKSIZE_IN_BYTES - (len & 0b1111)
+}
+
+/// Check the filled length of a buffer and the room for `r` bytes of padding after it.
+fn check_room(buf: &[u8], filled: usize, r: usize) -> Result<usize, AesError> {
+    if filled > buf.len() || buf.len() - filled < r {
+        return Err(AesError::InvalidDataLength);
+    }
+    Ok(filled + r)
+}
+
+/// Add PKCS #7 padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_pkcs7_slice, pa_pkcs7_slice};
+/// use aes_frast::AesError;
+///
+/// let mut buf = [0xFFu8; 32];
+/// let padded_length = pa_pkcs7_slice(&mut buf, 7).unwrap();
+/// assert_eq!(padded_length, 16);
+/// assert_eq!(&buf[..16], &[0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0xFFu8, 0x09u8,
+///                          0x09u8, 0x09u8, 0x09u8, 0x09u8, 0x09u8, 0x09u8, 0x09u8, 0x09u8]);
+/// assert_eq!(de_pkcs7_slice(&buf[..padded_length]), Ok(7));
+///
+/// // A complete block needs a whole block of padding.
+/// assert_eq!(pa_pkcs7_slice(&mut [0u8; 31], 16), Err(AesError::InvalidDataLength));
+/// ```
+pub fn pa_pkcs7_slice(buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+    let r = BLOCKSIZE_IN_BYTES - (filled & 0b1111);
+    let padded = check_room(buf, filled, r)?;
+    buf[filled..padded].fill(r as u8);
+    Ok(padded)
+}
+
+/// Add PKCS #7 padding in a buffer which reserves room for it, e.g. a fixed buffer of
+/// [`padded_length`] bytes.
+///
+/// * *parameter* `buf`: the buffer whose leading `used` bytes are the original data.
+/// * *parameter* `used`: the length of the original data.
+/// * *return* : the length of the padded data.
+///
+/// It is [`pa_pkcs7_slice`] for a buffer which is known to be large enough.
+/// # Panics
+/// This function panics if `used` is greater than the length of `buf` or there is no room for the
+/// padding after it.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_pkcs7_slice, pad_in_place, padded_length};
+///
+/// const RECORD: usize = 40;
+/// let mut buf = [0xFFu8; padded_length(RECORD)];
+/// let padded = pad_in_place(&mut buf, RECORD);
+/// assert_eq!(padded, 48);
+/// assert_eq!(&buf[RECORD..], &[0x08u8; 8]);
+/// assert_eq!(de_pkcs7_slice(&buf[..padded]), Ok(RECORD));
+/// ```
+///
+/// [`padded_length`]: ../padding_128bit/fn.padded_length.html
+/// [`pa_pkcs7_slice`]: ../padding_128bit/fn.pa_pkcs7_slice.html
+pub fn pad_in_place(buf: &mut [u8], used: usize) -> usize {
+    pa_pkcs7_slice(buf, used).expect("No room for the padding in the buffer.")
+}
+
+/// Add ANSIX923 padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+pub fn pa_ansix923_slice(buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+    let r = BLOCKSIZE_IN_BYTES - (filled & 0b1111);
+    let padded = check_room(buf, filled, r)?;
+    buf[filled..(padded - 1)].fill(0);
+    buf[padded - 1] = r as u8;
+    Ok(padded)
+}
+
+/// Add ISO 10126 padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *parameter* `rng`: the source of the random padding bytes.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+///
+/// Like the padding of [`pa_iso10126`], it can be removed by [`de_ansix923_pkcs7_slice`].
+///
+/// [`pa_iso10126`]: ../padding_128bit/fn.pa_iso10126.html
+/// [`de_ansix923_pkcs7_slice`]: ../padding_128bit/fn.de_ansix923_pkcs7_slice.html
+pub fn pa_iso10126_slice<R: Rng + ?Sized>(
+    buf: &mut [u8],
+    filled: usize,
+    rng: &mut R,
+) -> Result<usize, AesError> {
+    let r = BLOCKSIZE_IN_BYTES - (filled & 0b1111);
+    let padded = check_room(buf, filled, r)?;
+    rng.fill_bytes(&mut buf[filled..(padded - 1)]);
+    buf[padded - 1] = r as u8;
+    Ok(padded)
+}
+
+/// Add Zeros padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+///
+/// Like [`pa_zeros`], it adds a complete block after a complete end-block, and it is **NOT**
+/// recommended.
+///
+/// [`pa_zeros`]: ../padding_128bit/fn.pa_zeros.html
+pub fn pa_zeros_slice(buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+    let r = BLOCKSIZE_IN_BYTES - (filled & 0b1111);
+    let padded = check_room(buf, filled, r)?;
+    buf[filled..padded].fill(0);
+    Ok(padded)
+}
+
+/// Add PADMÉ padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+///
+/// See [`pa_padme`] for the padded length.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_padme_slice, pa_padme_slice};
+///
+/// let mut buf = [0xFFu8; 1024];
+/// let padded_length = pa_padme_slice(&mut buf, 1000).unwrap();
+/// assert_eq!(padded_length, 1024);
+/// assert_eq!(de_padme_slice(&buf[..padded_length]), Ok(1000));
+/// ```
+///
+/// [`pa_padme`]: ../padding_128bit/fn.pa_padme.html
+pub fn pa_padme_slice(buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+    let padded = check_room(buf, filled, padme_length(filled) - filled)?;
+    buf[filled] = 0x80;
+    buf[(filled + 1)..padded].fill(0);
+    Ok(padded)
+}
+
+/// Add ISO/IEC 7816-4 padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+pub fn pa_iso7816_slice(buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+    let r = BLOCKSIZE_IN_BYTES - (filled & 0b1111);
+    let padded = check_room(buf, filled, r)?;
+    buf[filled] = 0x80;
+    buf[(filled + 1)..padded].fill(0);
+    Ok(padded)
+}
+
+/// Add TBC (Trailing Bit Complement) padding in a caller-provided buffer, without allocation.
+///
+/// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+/// * *parameter* `filled`: the length of the original data.
+/// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if `filled`
+///   is greater than the length of `buf` or there is no room for the padding after it, in which
+///   case `buf` is left as it is.
+///
+/// See [`pa_tbc`] for the padding bytes.
+///
+/// [`pa_tbc`]: ../padding_128bit/fn.pa_tbc.html
+pub fn pa_tbc_slice(buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+    let r = BLOCKSIZE_IN_BYTES - (filled & 0b1111);
+    let padded = check_room(buf, filled, r)?;
+    let pad = tbc_byte(&buf[..filled]);
+    buf[filled..padded].fill(pad);
+    Ok(padded)
+}
+
+/// Remove ANSIX923 or PKCS #7 padding (or ISO 10126 padding) from a slice, without checking it.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the last
+///   byte is not a padding length which fits in `data`.
+///
+/// Like [`de_ansix923_pkcs7`], only the last byte is read. Use [`de_pkcs7_slice`] or
+/// [`de_ansix923_slice`] to check the whole padding string.
+///
+/// [`de_ansix923_pkcs7`]: ../padding_128bit/fn.de_ansix923_pkcs7.html
+/// [`de_pkcs7_slice`]: ../padding_128bit/fn.de_pkcs7_slice.html
+/// [`de_ansix923_slice`]: ../padding_128bit/fn.de_ansix923_slice.html
+pub fn de_ansix923_pkcs7_slice(data: &[u8]) -> Result<usize, AesError> {
+    match data.last() {
+        Some(&r) if r != 0 && r as usize <= BLOCKSIZE_IN_BYTES && r as usize <= data.len() => {
+            Ok(data.len() - r as usize)
+        }
+        _ => Err(AesError::InvalidPadding),
+    }
+}
+
+/// Remove PKCS #7 padding from a slice, checking it.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the data
+///   doesn't end with a PKCS #7 padding string.
+pub fn de_pkcs7_slice(data: &[u8]) -> Result<usize, AesError> {
+    check_pkcs7(data)?;
+    de_ansix923_pkcs7_slice(data)
+}
+
+/// Remove ANSIX923 padding from a slice, checking it.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the data
+///   doesn't end with an ANSIX923 padding string.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{de_ansix923_slice, pa_ansix923_slice};
+/// use aes_frast::AesError;
+///
+/// let mut buf = [0xFFu8; 16];
+/// assert_eq!(pa_ansix923_slice(&mut buf, 7), Ok(16));
+/// assert_eq!(de_ansix923_slice(&buf), Ok(7));
+///
+/// buf[10] = 0x01;
+/// assert_eq!(de_ansix923_slice(&buf), Err(AesError::InvalidPadding));
+/// ```
+pub fn de_ansix923_slice(data: &[u8]) -> Result<usize, AesError> {
+    check_ansix923(data)?;
+    de_ansix923_pkcs7_slice(data)
+}
+
+/// Remove Zeros padding from a slice.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the data is
+///   empty or only zeros.
+///
+/// Like [`de_zeros`], only the zeros in the last block are removed.
+///
+/// **Attention!** If the origin data ends with zero(s), they are removed too.
+///
+/// [`de_zeros`]: ../padding_128bit/fn.de_zeros.html
+pub fn de_zeros_slice(data: &[u8]) -> Result<usize, AesError> {
+    if data.iter().all(|&x| x == 0) {
+        return Err(AesError::InvalidPadding);
+    }
+    Ok(data.len() - trailing_zeros_in_block(data))
+}
+
+/// Remove PADMÉ padding from a slice, checking the `0x80` before the trailing zeros.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the data
+///   doesn't end with `0x80` and zeros. Like [`de_padme`], it does NOT check the padded length.
+///
+/// [`de_padme`]: ../padding_128bit/fn.de_padme.html
+pub fn de_padme_slice(data: &[u8]) -> Result<usize, AesError> {
+    match data.iter().rposition(|&x| x != 0) {
+        Some(last) if data[last] == 0x80 => Ok(last),
+        _ => Err(AesError::InvalidPadding),
+    }
+}
+
+/// Remove TBC (Trailing Bit Complement) padding from a slice, checking it.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the data
+///   doesn't end with a TBC padding string, like [`try_de_tbc`].
+///
+/// [`try_de_tbc`]: ../padding_128bit/fn.try_de_tbc.html
+pub fn de_tbc_slice(data: &[u8]) -> Result<usize, AesError> {
+    Ok(data.len() - check_tbc(data)?)
+}
+
+/// Remove ISO/IEC 7816-4 padding from a slice, checking it.
+///
+/// * *parameter* `data`: the padded data.
+/// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)` if the data
+///   doesn't end with `0x80` and at most 15 zeros.
+pub fn de_iso7816_slice(data: &[u8]) -> Result<usize, AesError> {
+    Ok(data.len() - check_iso7816(data)?)
+}
+
+/// A padding scheme for 128bit-block crypto, which adds and removes its padding on a `Vec` or on
+/// a caller-provided buffer.
+///
+/// It is implemented by a unit struct for each scheme, such as [`Pkcs7`], and by
+/// [`PaddingScheme`], which picks the scheme at runtime.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{Padding, PaddingScheme, Pkcs7};
+///
+/// fn roundtrip<P: Padding + ?Sized>(padding: &P, data: &[u8]) -> Vec<u8> {
+///     let mut padded = data.to_vec();
+///     padding.pad(&mut padded).unwrap();
+///     assert_eq!(padded.len() % 16, 0);
+///     padding.unpad(&mut padded).unwrap();
+///     padded
+/// }
+///
+/// assert_eq!(roundtrip(&Pkcs7, b"message"), b"message");
+/// let scheme: PaddingScheme = "ISO7816".parse().unwrap();
+/// assert_eq!(roundtrip(&scheme, b"message"), b"message");
+/// ```
+///
+/// [`Pkcs7`]: ../padding_128bit/struct.Pkcs7.html
+/// [`PaddingScheme`]: ../padding_128bit/enum.PaddingScheme.html
+pub trait Padding {
+    /// Add the padding to the end of the data.
+    ///
+    /// * *parameter* `data`: the vec that contains original data.
+    /// * *return* : the added bytes' length, or `Err(AesError::InvalidDataLength)` if the scheme
+    ///   adds no padding and the data is not made of complete blocks.
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError>;
+
+    /// Check and remove the padding at the end of the data.
+    ///
+    /// * *parameter* `data`: the vec that contains padded data.
+    /// * *return* : the removed bytes' length, or `Err(AesError::InvalidPadding)` if the data
+    ///   doesn't end with the padding, in which case `data` is left as it is.
+    fn unpad(&self, data: &mut Vec<u8>) -> Result<usize, AesError>;
+
+    /// Add the padding in a caller-provided buffer, without allocation.
+    ///
+    /// * *parameter* `buf`: the buffer whose leading `filled` bytes are the original data.
+    /// * *parameter* `filled`: the length of the original data.
+    /// * *return* : the length of the padded data, or `Err(AesError::InvalidDataLength)` if there
+    ///   is no room for the padding, or the scheme adds no padding and the data is not made of
+    ///   complete blocks.
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError>;
+
+    /// Check the padding at the end of a slice.
+    ///
+    /// * *parameter* `data`: the padded data.
+    /// * *return* : the length of the original data, or `Err(AesError::InvalidPadding)`.
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError>;
+
+    /// The length of `len` bytes of data after the padding, i.e. the capacity a buffer needs for
+    /// [`pad_slice`].
+    ///
+    /// The default is [`padded_length`], the length of the schemes which add 1 to 16 bytes.
+    ///
+    /// [`pad_slice`]: ../padding_128bit/trait.Padding.html#tymethod.pad_slice
+    /// [`padded_length`]: ../padding_128bit/fn.padded_length.html
+    fn padded_length(&self, len: usize) -> usize {
+        padded_length(len)
+    }
+}
+
+/// PKCS #7 padding, see [`pa_pkcs7`] and [`try_de_pkcs7`].
+///
+/// [`pa_pkcs7`]: ../padding_128bit/fn.pa_pkcs7.html
+/// [`try_de_pkcs7`]: ../padding_128bit/fn.try_de_pkcs7.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Pkcs7;
+
+impl Padding for Pkcs7 {
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        Ok(pa_pkcs7(data))
+    }
+
+    fn unpad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        try_de_pkcs7(data)
+    }
+
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+        pa_pkcs7_slice(buf, filled)
+    }
+
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError> {
+        de_pkcs7_slice(data)
+    }
+}
+
+/// ANSIX923 padding, see [`pa_ansix923`] and [`try_de_ansix923`].
+///
+/// [`pa_ansix923`]: ../padding_128bit/fn.pa_ansix923.html
+/// [`try_de_ansix923`]: ../padding_128bit/fn.try_de_ansix923.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct AnsiX923;
+
+impl Padding for AnsiX923 {
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        Ok(pa_ansix923(data))
+    }
+
+    fn unpad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        try_de_ansix923(data)
+    }
+
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+        pa_ansix923_slice(buf, filled)
+    }
+
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError> {
+        de_ansix923_slice(data)
+    }
+}
+
+/// ISO/IEC 7816-4 padding, see [`pa_iso7816`] and [`try_de_iso7816`].
+///
+/// [`pa_iso7816`]: ../padding_128bit/fn.pa_iso7816.html
+/// [`try_de_iso7816`]: ../padding_128bit/fn.try_de_iso7816.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Iso7816;
+
+impl Padding for Iso7816 {
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        Ok(pa_iso7816(data))
+    }
+
+    fn unpad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        try_de_iso7816(data)
+    }
+
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+        pa_iso7816_slice(buf, filled)
+    }
+
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError> {
+        de_iso7816_slice(data)
+    }
+}
+
+/// Zeros padding, see [`pa_zeros`] and [`try_de_zeros`]. It is **NOT** recommended.
+///
+/// [`pa_zeros`]: ../padding_128bit/fn.pa_zeros.html
+/// [`try_de_zeros`]: ../padding_128bit/fn.try_de_zeros.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Zeros;
+
+impl Padding for Zeros {
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        Ok(pa_zeros(data))
+    }
+
+    fn unpad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        try_de_zeros(data)
+    }
+
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+        pa_zeros_slice(buf, filled)
+    }
+
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError> {
+        de_zeros_slice(data)
+    }
+}
+
+/// No padding. The data must be made of complete blocks, which is checked by `pad` and
+/// `pad_slice` but not by `unpad` and `unpad_slice`, since they remove nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NoPadding;
+
+impl Padding for NoPadding {
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        let filled = data.len();
+        self.pad_slice(data, filled)?;
+        Ok(0)
+    }
+
+    fn unpad(&self, _data: &mut Vec<u8>) -> Result<usize, AesError> {
+        Ok(0)
+    }
+
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+        if filled > buf.len() || filled & 0b1111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        Ok(filled)
+    }
+
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError> {
+        Ok(data.len())
+    }
+
+    fn padded_length(&self, len: usize) -> usize {
+        len
+    }
+}
+
+/// A padding scheme chosen at runtime, e.g. from a configuration or a command line.
+///
+/// It can be parsed from (and displayed as) its name: `PKCS7` (or `PKCS5`), `ANSIX923`,
+/// `ISO7816`, `ZEROS` or `NOPADDING` (or `NONE`), case-insensitively.
+/// # Examples
+/// ```
+/// use aes_frast::padding_128bit::{Padding, PaddingScheme};
+/// use aes_frast::AesError;
+///
+/// let scheme: PaddingScheme = "ansix923".parse().unwrap();
+/// assert_eq!(scheme, PaddingScheme::AnsiX923);
+/// assert_eq!(scheme.to_string(), "ANSIX923");
+///
+/// let mut buf = vec![0xFFu8; scheme.padded_length(20)];
+/// assert_eq!(scheme.pad_slice(&mut buf, 20), Ok(32));
+/// assert_eq!(scheme.unpad_slice(&buf), Ok(20));
+///
+/// let no_padding = PaddingScheme::NoPadding;
+/// assert_eq!(no_padding.pad(&mut vec![0u8; 20]), Err(AesError::InvalidDataLength));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaddingScheme {
+    /// PKCS #7 padding
+    Pkcs7,
+    /// ANSIX923 padding
+    AnsiX923,
+    /// ISO/IEC 7816-4 padding
+    Iso7816,
+    /// Zeros padding (**NOT** recommended)
+    Zeros,
+    /// No padding
+    NoPadding,
+}
+
+impl PaddingScheme {
+    /// The implementation of the scheme.
+    fn padding(&self) -> &'static dyn Padding {
+        match self {
+            Self::Pkcs7 => &Pkcs7,
+            Self::AnsiX923 => &AnsiX923,
+            Self::Iso7816 => &Iso7816,
+            Self::Zeros => &Zeros,
+            Self::NoPadding => &NoPadding,
+        }
+    }
+}
+
+impl Padding for PaddingScheme {
+    fn pad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        self.padding().pad(data)
+    }
+
+    fn unpad(&self, data: &mut Vec<u8>) -> Result<usize, AesError> {
+        self.padding().unpad(data)
+    }
+
+    fn pad_slice(&self, buf: &mut [u8], filled: usize) -> Result<usize, AesError> {
+        self.padding().pad_slice(buf, filled)
+    }
+
+    fn unpad_slice(&self, data: &[u8]) -> Result<usize, AesError> {
+        self.padding().unpad_slice(data)
+    }
+
+    fn padded_length(&self, len: usize) -> usize {
+        self.padding().padded_length(len)
+    }
+}
+
+impl FromStr for PaddingScheme {
+    type Err = AesError;
+
+    /// Parse the name of a scheme, or return `Err(AesError::InvalidParameter)`.
+    fn from_str(s: &str) -> Result<Self, AesError> {
+        match s.to_ascii_uppercase().as_str() {
+            "PKCS7" | "PKCS5" => Ok(Self::Pkcs7),
+            "ANSIX923" => Ok(Self::AnsiX923),
+            "ISO7816" => Ok(Self::Iso7816),
+            "ZEROS" => Ok(Self::Zeros),
+            "NOPADDING" | "NONE" => Ok(Self::NoPadding),
+            _ => Err(AesError::InvalidParameter),
+        }
+    }
+}
+
+impl fmt::Display for PaddingScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pkcs7 => "PKCS7",
+            Self::AnsiX923 => "ANSIX923",
+            Self::Iso7816 => "ISO7816",
+            Self::Zeros => "ZEROS",
+            Self::NoPadding => "NOPADDING",
+        })
+    }
+}
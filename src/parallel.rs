@@ -0,0 +1,229 @@
+//! # parallel
+//! `parallel` splits the data of the parallelizable operation modes between scoped threads.
+//!
+//! The chunks are made of whole blocks (only the last one may end with a partial block), so each
+//! thread runs the ordinary mode on its chunk, knowing the index of its first block. ECB needs
+//! nothing else; CTR starts its chunk from the counter advanced by that index.
+//!
+//! The data of XTS is split between the threads the same way, but in whole sectors, which are
+//! independent of each other.
+//!
+//! [`for_each_chunk_with_hooks`] also splits each chunk into slices of 4096 blocks, checking the
+//! cancellation token and reporting the progress between slices.
+//!
+//! [`for_each_chunk_with_hooks`]: ../parallel/fn.for_each_chunk_with_hooks.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::aes_with_operation_mode_io::ParallelHooks;
+use super::error::AesError;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+
+/// Below this number of blocks per thread, the threads cost more than they save.
+const MIN_BLOCKS_PER_THREAD: usize = 256;
+
+/// The number of blocks between two checks of the cancellation token.
+const BLOCKS_PER_CHECK: usize = 4096;
+
+/// Run `process` on the chunks of `input` and of the same length of `output`, on up to `threads`
+/// scoped threads. `process` gets the index of the first block of its chunk.
+///
+/// Data of less than 256 blocks per thread is processed on the calling thread only, in a single
+/// chunk.
+/// # Panics
+/// This function panics if `output` is shorter than `input`.
+pub(crate) fn for_each_chunk<F>(input: &[u8], output: &mut [u8], threads: usize, process: F)
+where
+    F: Fn(usize, &[u8], &mut [u8]) + Sync,
+{
+    let output = &mut output[..input.len()];
+    let blocks = input.len().div_ceil(BLOCKSIZE_IN_BYTES);
+    let threads = threads.clamp(1, (blocks / MIN_BLOCKS_PER_THREAD).max(1));
+    if threads == 1 {
+        process(0, input, output);
+        return;
+    }
+    let chunk_blocks = blocks.div_ceil(threads);
+    let chunk_len = chunk_blocks * BLOCKSIZE_IN_BYTES;
+    let process = &process;
+    thread::scope(|scope| {
+        for (i, (input, output)) in input
+            .chunks(chunk_len)
+            .zip(output.chunks_mut(chunk_len))
+            .enumerate()
+        {
+            scope.spawn(move || process(i * chunk_blocks, input, output));
+        }
+    });
+}
+
+/// The same as [`for_each_chunk`], checking `hooks.cancel` before each slice of 4096 blocks and
+/// calling `hooks.progress` after it with the number of bytes processed so far by all threads.
+///
+/// On cancellation, the processed part of `output` is zeroed, so no partial output is left.
+/// * *return* : `Ok(())`, or `Err(AesError::Cancelled)`.
+///
+/// [`for_each_chunk`]: ../parallel/fn.for_each_chunk.html
+pub(crate) fn for_each_chunk_with_hooks<F>(
+    input: &[u8],
+    output: &mut [u8],
+    threads: usize,
+    hooks: &ParallelHooks,
+    process: F,
+) -> Result<(), AesError>
+where
+    F: Fn(usize, &[u8], &mut [u8]) + Sync,
+{
+    let slice_len = BLOCKS_PER_CHECK * BLOCKSIZE_IN_BYTES;
+    let processed = AtomicU64::new(0);
+    let stopped = AtomicBool::new(false);
+    for_each_chunk(input, output, threads, |first_block, input, output| {
+        for (i, (input, output)) in input
+            .chunks(slice_len)
+            .zip(output.chunks_mut(slice_len))
+            .enumerate()
+        {
+            if stopped.load(Ordering::Relaxed) || hooks.cancel.is_some_and(|t| t.is_cancelled()) {
+                stopped.store(true, Ordering::Relaxed);
+                return;
+            }
+            process(first_block + i * BLOCKS_PER_CHECK, input, output);
+            let total = processed.fetch_add(input.len() as u64, Ordering::Relaxed);
+            if let Some(progress) = hooks.progress {
+                progress(total + input.len() as u64);
+            }
+        }
+    });
+    if stopped.into_inner() {
+        let output = &mut output[..input.len()];
+        output.fill(0);
+        std::hint::black_box(&output);
+        return Err(AesError::Cancelled);
+    }
+    Ok(())
+}
+
+/// Run `process` in place on the chunks of `data`, made of whole units of `unit_len` bytes (only
+/// the last one may end with a partial unit), on up to `threads` scoped threads. `process` gets
+/// the index of the first unit of its chunk.
+///
+/// Data of less than 256 blocks per thread is processed on the calling thread only, in a single
+/// chunk.
+/// # Panics
+/// This function panics if `unit_len` is 0.
+pub(crate) fn for_each_unit_chunk<F>(data: &mut [u8], unit_len: usize, threads: usize, process: F)
+where
+    F: Fn(usize, &mut [u8]) + Sync,
+{
+    let units = data.len().div_ceil(unit_len);
+    let blocks = data.len().div_ceil(BLOCKSIZE_IN_BYTES);
+    let threads = threads
+        .clamp(1, (blocks / MIN_BLOCKS_PER_THREAD).max(1))
+        .min(units.max(1));
+    if threads == 1 {
+        process(0, data);
+        return;
+    }
+    let chunk_units = units.div_ceil(threads);
+    let process = &process;
+    thread::scope(|scope| {
+        for (i, chunk) in data.chunks_mut(chunk_units * unit_len).enumerate() {
+            scope.spawn(move || process(i * chunk_units, chunk));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_with_operation_mode_io::CancelToken;
+    use std::sync::Mutex;
+
+    #[test]
+    fn chunks_cover_the_data() {
+        let input: Vec<u8> = (0..(1030 * BLOCKSIZE_IN_BYTES + 7))
+            .map(|i| i as u8)
+            .collect();
+        for threads in [0, 1, 3, 4, 16] {
+            let mut output = vec![0u8; input.len() + 16];
+            let starts = Mutex::new(Vec::new());
+            for_each_chunk(
+                &input,
+                &mut output,
+                threads,
+                |first_block, input, output| {
+                    starts.lock().unwrap().push(first_block);
+                    assert_eq!(input.len(), output.len());
+                    output.copy_from_slice(input);
+                },
+            );
+            assert_eq!(output[..input.len()], input[..]);
+            assert_eq!(output[input.len()..], [0u8; 16]);
+            let mut starts = starts.into_inner().unwrap();
+            starts.sort_unstable();
+            let expected: Vec<usize> = match threads {
+                0 | 1 => vec![0],
+                3 => vec![0, 344, 688],
+                _ => vec![0, 258, 516, 774],
+            };
+            assert_eq!(starts, expected);
+        }
+    }
+
+    #[test]
+    fn hooked_chunks() {
+        let input = vec![0x5Au8; 3 * BLOCKS_PER_CHECK * BLOCKSIZE_IN_BYTES + 5];
+        let mut output = vec![0u8; input.len()];
+        let last = AtomicU64::new(0);
+        let progress = |total: u64| {
+            last.fetch_max(total, Ordering::Relaxed);
+        };
+        let hooks = ParallelHooks {
+            progress: Some(&progress),
+            cancel: None,
+        };
+        let result = for_each_chunk_with_hooks(&input, &mut output, 2, &hooks, |_, i, o| {
+            o.copy_from_slice(i)
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(output, input);
+        assert_eq!(last.into_inner(), input.len() as u64);
+
+        let token = CancelToken::new();
+        let hooks = ParallelHooks {
+            progress: None,
+            cancel: Some(&token),
+        };
+        let result = for_each_chunk_with_hooks(&input, &mut output, 2, &hooks, |_, i, o| {
+            o.copy_from_slice(i);
+            token.cancel();
+        });
+        assert_eq!(result, Err(AesError::Cancelled));
+        assert!(output.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn unit_chunks_cover_the_data() {
+        // 600 sectors of 512 bytes, 19200 blocks.
+        let mut data = vec![0u8; 600 * 512];
+        for threads in [1, 7, 1000] {
+            let starts = Mutex::new(Vec::new());
+            for_each_unit_chunk(&mut data, 512, threads, |first_unit, chunk| {
+                starts.lock().unwrap().push((first_unit, chunk.len()));
+                assert_eq!(chunk.len() % 512, 0);
+                chunk.iter_mut().for_each(|b| *b += 1);
+            });
+            let mut starts = starts.into_inner().unwrap();
+            starts.sort_unstable();
+            let expected: Vec<(usize, usize)> = match threads {
+                1 => vec![(0, 600 * 512)],
+                7 => (0..7)
+                    .map(|i| (i * 86, if i < 6 { 86 } else { 84 } * 512))
+                    .collect(),
+                // Not worth more threads than 75 chunks of 256 blocks.
+                _ => (0..75).map(|i| (i * 8, 8 * 512)).collect(),
+            };
+            assert_eq!(starts, expected);
+        }
+        assert!(data.iter().all(|&b| b == 3));
+    }
+}
@@ -0,0 +1,430 @@
+//! # pbe
+//! `pbe` encrypts with a password instead of a key. PBKDF2-HMAC-SHA256 (RFC 8018) derives the
+//! key and the IV from the password, a random salt and an iteration count, and a [`Recipe`]
+//! encrypts. [`encrypt`] writes the parameters in a header before the cipher, so [`decrypt`]
+//! needs nothing but the password.
+//!
+//! The header is, in order:
+//! * the magic `AFPB` and the version `0x01`.
+//! * the key derivation function: `0x01` for PBKDF2-HMAC-SHA256.
+//! * the iteration count, 4 bytes in big-endian.
+//! * the length of the salt (1 byte), and the salt.
+//! * the length of the recipe (1 byte), and the recipe as text, such as `AES-256/CBC/PKCS7`.
+//!
+//! A single PBKDF2 output is split into the key and the IV which follows it, so the derivation
+//! of `AES-*/CBC/PKCS7` matches `openssl enc -aes-*-cbc -pbkdf2 -iter <count>`.
+//!
+//! Nothing is authenticated: decryption with a wrong password fails on the padding at best,
+//! and gives garbage for the modes without padding. More iterations make guessing passwords
+//! slower, and [`DEFAULT_ITERATIONS`] follows the current OWASP recommendation. Only available
+//! with the `pbe` feature.
+//! # Examples
+//! ```
+//! use aes_frast::pbe::{self, Params};
+//! use aes_frast::recipe::Recipe;
+//!
+//! let recipe: Recipe = "AES-256/CTR".parse().unwrap();
+//! // Use a random salt (`Params::generate`) and `DEFAULT_ITERATIONS` in practice.
+//! let params = Params::new(recipe, 1000, b"NaCl and pepper!").unwrap();
+//!
+//! let password = b"correct horse battery staple";
+//! let sealed = pbe::encrypt(password, &params, b"attack at dawn").unwrap();
+//! assert_eq!(&sealed[..4], b"AFPB");
+//! let (read, cipher) = pbe::parse_header(&sealed).unwrap();
+//! assert_eq!(read, params);
+//! assert_eq!(cipher.len(), 14);
+//!
+//! let plain = pbe::decrypt(password, &sealed).unwrap();
+//! assert_eq!(plain, b"attack at dawn");
+//! ```
+//!
+//! [`Recipe`]: ../recipe/struct.Recipe.html
+//! [`encrypt`]: ../pbe/fn.encrypt.html
+//! [`decrypt`]: ../pbe/fn.decrypt.html
+//! [`DEFAULT_ITERATIONS`]: ../pbe/constant.DEFAULT_ITERATIONS.html
+use super::aes_core::{BLOCKSIZE_IN_BYTES, KEY_BYTES_256BIT};
+use super::digest::{Sha256, DIGEST_BLOCK_BYTES, SHA256_BYTES};
+use super::error::AesError;
+use super::recipe::Recipe;
+use super::rng::Rng;
+
+/// The magic at the start of the header.
+pub const MAGIC: &[u8; 4] = b"AFPB";
+/// The version of the header.
+pub const VERSION: u8 = 1;
+/// The default iteration count of PBKDF2-HMAC-SHA256.
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+/// The length in bytes of the salts of [`Params::generate`].
+///
+/// [`Params::generate`]: ../pbe/struct.Params.html#method.generate
+pub const DEFAULT_SALT_BYTES: usize = 16;
+/// The largest iteration count [`decrypt`] accepts, so a forged header cannot keep it busy for
+/// hours.
+///
+/// [`decrypt`]: ../pbe/fn.decrypt.html
+pub const MAX_ITERATIONS: u32 = 100_000_000;
+
+const KDF_PBKDF2_HMAC_SHA256: u8 = 1;
+
+/// HMAC-SHA256 with the key absorbed once, so it can be applied to many messages.
+struct HmacSha256 {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl HmacSha256 {
+    fn new(key: &[u8]) -> Self {
+        let mut block = [0u8; DIGEST_BLOCK_BYTES];
+        if key.len() > DIGEST_BLOCK_BYTES {
+            let mut hasher = Sha256::new();
+            hasher.update(key);
+            block[..SHA256_BYTES].copy_from_slice(&hasher.finalize());
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let mut inner = Sha256::new();
+        let mut outer = Sha256::new();
+        for byte in block.iter_mut() {
+            *byte ^= 0x36;
+        }
+        inner.update(&block);
+        for byte in block.iter_mut() {
+            *byte ^= 0x36 ^ 0x5C;
+        }
+        outer.update(&block);
+        block.fill(0);
+        std::hint::black_box(&block);
+        Self { inner, outer }
+    }
+
+    fn mac(&self, parts: &[&[u8]]) -> [u8; SHA256_BYTES] {
+        let mut inner = self.inner.clone();
+        for part in parts {
+            inner.update(part);
+        }
+        let mut outer = self.outer.clone();
+        outer.update(&inner.finalize());
+        outer.finalize()
+    }
+}
+
+/// Derive `output.len()` bytes from a password with PBKDF2-HMAC-SHA256 (RFC 8018).
+///
+/// * *parameter* `password`: the password.
+/// * *parameter* `salt`: the salt.
+/// * *parameter* `iterations`: the iteration count.
+/// * *parameter* `output`: the output key material.
+/// # Examples
+/// ```
+/// use aes_frast::pbe::pbkdf2_hmac_sha256;
+///
+/// // This example came from RFC 7914 section 11
+/// let mut output = [0u8; 16];
+/// pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut output);
+/// assert_eq!(output, [0x55, 0xAC, 0x04, 0x6E, 0x56, 0xE3, 0x08, 0x9F,
+///                     0xEC, 0x16, 0x91, 0xC2, 0x25, 0x44, 0xB6, 0x05]);
+/// ```
+/// # Panics
+/// This function panics if `iterations` is zero.
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, output: &mut [u8]) {
+    assert!(iterations > 0, "The iteration count must be positive.");
+    let hmac = HmacSha256::new(password);
+    for (index, chunk) in output.chunks_mut(SHA256_BYTES).enumerate() {
+        let block_index = (index as u32 + 1).to_be_bytes();
+        let mut u = hmac.mac(&[salt, &block_index]);
+        let mut t = u;
+        for _ in 1..iterations {
+            u = hmac.mac(&[&u]);
+            for (t, u) in t.iter_mut().zip(u.iter()) {
+                *t ^= u;
+            }
+        }
+        chunk.copy_from_slice(&t[..chunk.len()]);
+        u.fill(0);
+        t.fill(0);
+        std::hint::black_box((&u, &t));
+    }
+}
+
+/// The parameters of password-based encryption: the recipe, the iteration count and the salt.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Params {
+    recipe: Recipe,
+    iterations: u32,
+    salt: Vec<u8>,
+}
+
+impl Params {
+    /// Make the parameters.
+    ///
+    /// * *parameter* `recipe`: the recipe which encrypts.
+    /// * *parameter* `iterations`: the iteration count of PBKDF2, from 1 to [`MAX_ITERATIONS`].
+    /// * *parameter* `salt`: the salt, from 1 to 255 bytes. It must be random and different
+    ///   for every message, or equal passwords give equal keys and IVs.
+    /// * *return* : the parameters, or `Err(AesError::InvalidParameter)` if the iteration count
+    ///   or the length of the salt is out of range.
+    ///
+    /// [`MAX_ITERATIONS`]: ../pbe/constant.MAX_ITERATIONS.html
+    pub fn new(recipe: Recipe, iterations: u32, salt: &[u8]) -> Result<Self, AesError> {
+        if iterations == 0 || iterations > MAX_ITERATIONS || salt.is_empty() || salt.len() > 255 {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(Self {
+            recipe,
+            iterations,
+            salt: salt.to_vec(),
+        })
+    }
+
+    /// Make the parameters with a salt of [`DEFAULT_SALT_BYTES`] bytes from `rng`.
+    ///
+    /// [`DEFAULT_SALT_BYTES`]: ../pbe/constant.DEFAULT_SALT_BYTES.html
+    pub fn generate<R: Rng + ?Sized>(
+        rng: &mut R,
+        recipe: Recipe,
+        iterations: u32,
+    ) -> Result<Self, AesError> {
+        let mut salt = [0u8; DEFAULT_SALT_BYTES];
+        rng.fill_bytes(&mut salt);
+        Self::new(recipe, iterations, &salt)
+    }
+
+    /// The recipe.
+    pub fn recipe(&self) -> Recipe {
+        self.recipe
+    }
+
+    /// The iteration count of PBKDF2.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// The salt.
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// The header which [`encrypt`] writes before the cipher.
+    ///
+    /// [`encrypt`]: ../pbe/fn.encrypt.html
+    pub fn header(&self) -> Vec<u8> {
+        let recipe = self.recipe.to_string();
+        let mut header = Vec::with_capacity(12 + self.salt.len() + recipe.len());
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        header.push(KDF_PBKDF2_HMAC_SHA256);
+        header.extend_from_slice(&self.iterations.to_be_bytes());
+        header.push(self.salt.len() as u8);
+        header.extend_from_slice(&self.salt);
+        header.push(recipe.len() as u8);
+        header.extend_from_slice(recipe.as_bytes());
+        header
+    }
+
+    /// Derive the key and the IV from `password`, and call `function` with them.
+    fn with_key<T>(&self, password: &[u8], function: impl FnOnce(&[u8], &[u8]) -> T) -> T {
+        let key_bytes = self.recipe.key_bits() / 8;
+        let mut material = [0u8; KEY_BYTES_256BIT + BLOCKSIZE_IN_BYTES];
+        let material_bytes = key_bytes + BLOCKSIZE_IN_BYTES;
+        pbkdf2_hmac_sha256(
+            password,
+            &self.salt,
+            self.iterations,
+            &mut material[..material_bytes],
+        );
+        let result = function(&material[..key_bytes], &material[key_bytes..material_bytes]);
+        material.fill(0);
+        std::hint::black_box(&material);
+        result
+    }
+}
+
+/// Encrypt `plain` with a password.
+///
+/// * *parameter* `password`: the password.
+/// * *parameter* `params`: the recipe, the iteration count and the salt.
+/// * *parameter* `plain`: the plain data.
+/// * *return* : the header of `params` followed by the cipher, or the error of the recipe (for
+///   example `Err(AesError::InvalidDataLength)` for incomplete blocks without padding).
+pub fn encrypt(password: &[u8], params: &Params, plain: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = params.with_key(password, |key, iv| params.recipe.encrypt(key, iv, plain))?;
+    let mut sealed = params.header();
+    sealed.extend_from_slice(&cipher);
+    Ok(sealed)
+}
+
+/// Encrypt `plain` with a password, with a random salt from `rng`.
+///
+/// * *parameter* `rng`: the source of the salt.
+/// * *parameter* `password`: the password.
+/// * *parameter* `recipe`: the recipe which encrypts.
+/// * *parameter* `iterations`: the iteration count of PBKDF2, usually [`DEFAULT_ITERATIONS`].
+/// * *parameter* `plain`: the plain data.
+/// * *return* : the header followed by the cipher, or the error.
+///
+/// [`DEFAULT_ITERATIONS`]: ../pbe/constant.DEFAULT_ITERATIONS.html
+pub fn encrypt_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    password: &[u8],
+    recipe: Recipe,
+    iterations: u32,
+    plain: &[u8],
+) -> Result<Vec<u8>, AesError> {
+    let params = Params::generate(rng, recipe, iterations)?;
+    encrypt(password, &params, plain)
+}
+
+/// Read the header of [`encrypt`].
+///
+/// * *parameter* `sealed`: the header followed by the cipher.
+/// * *return* : the parameters and the cipher, or
+///   * `Err(AesError::Unsupported)` for a newer version or an unknown key derivation function.
+///   * `Err(AesError::InvalidParameter)` if the header is malformed or out of range.
+///
+/// [`encrypt`]: ../pbe/fn.encrypt.html
+pub fn parse_header(sealed: &[u8]) -> Result<(Params, &[u8]), AesError> {
+    let mut rest = sealed;
+    let mut take = |n: usize| -> Result<&[u8], AesError> {
+        if rest.len() < n {
+            return Err(AesError::InvalidParameter);
+        }
+        let (head, tail) = rest.split_at(n);
+        rest = tail;
+        Ok(head)
+    };
+    if take(MAGIC.len())? != MAGIC {
+        return Err(AesError::InvalidParameter);
+    }
+    if take(1)?[0] != VERSION || take(1)?[0] != KDF_PBKDF2_HMAC_SHA256 {
+        return Err(AesError::Unsupported);
+    }
+    let iterations = u32::from_be_bytes(take(4)?.try_into().unwrap());
+    let salt_bytes = take(1)?[0] as usize;
+    let salt = take(salt_bytes)?;
+    let recipe_bytes = take(1)?[0] as usize;
+    let recipe = std::str::from_utf8(take(recipe_bytes)?)
+        .map_err(|_| AesError::InvalidParameter)?
+        .parse()?;
+    let params = Params::new(recipe, iterations, salt)?;
+    Ok((params, rest))
+}
+
+/// Decrypt the output of [`encrypt`] with a password.
+///
+/// * *parameter* `password`: the password.
+/// * *parameter* `sealed`: the header followed by the cipher.
+/// * *return* : the plain data, or the error of [`parse_header`] or of the recipe. A wrong
+///   password gives `Err(AesError::InvalidPadding)` at best, see the module documentation.
+///
+/// [`encrypt`]: ../pbe/fn.encrypt.html
+/// [`parse_header`]: ../pbe/fn.parse_header.html
+pub fn decrypt(password: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AesError> {
+    let (params, cipher) = parse_header(sealed)?;
+    params.with_key(password, |key, iv| params.recipe.decrypt(key, iv, cipher))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pbkdf2_works() {
+        // This example came from RFC 7914 section 11
+        let mut output = [0u8; 64];
+        pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut output);
+        assert_eq!(
+            output,
+            [
+                0x55, 0xAC, 0x04, 0x6E, 0x56, 0xE3, 0x08, 0x9F, 0xEC, 0x16, 0x91, 0xC2, 0x25, 0x44,
+                0xB6, 0x05, 0xF9, 0x41, 0x85, 0x21, 0x6D, 0xDE, 0x04, 0x65, 0xE6, 0x8B, 0x9D, 0x57,
+                0xC2, 0x0D, 0xAC, 0xBC, 0x49, 0xCA, 0x9C, 0xCC, 0xF1, 0x79, 0xB6, 0x45, 0x99, 0x16,
+                0x64, 0xB3, 0x9D, 0x77, 0xEF, 0x31, 0x7C, 0x71, 0xB8, 0x45, 0xB1, 0xE3, 0x0B, 0xD5,
+                0x09, 0x11, 0x20, 0x41, 0xD3, 0xA1, 0x97, 0x83
+            ]
+        );
+
+        // A password longer than the block of SHA-256 is hashed first.
+        let mut output = [0u8; 32];
+        pbkdf2_hmac_sha256(
+            b"passwordPASSWORDpasswordPASSWORDpasswordPASSWORDpasswordPASSWORD",
+            b"salt",
+            2,
+            &mut output,
+        );
+        assert_eq!(
+            output,
+            [
+                0x9A, 0x70, 0xF9, 0xCA, 0xA3, 0x59, 0x12, 0x23, 0xFD, 0x77, 0x00, 0x8E, 0xAC, 0x01,
+                0x7D, 0xA5, 0x35, 0xB5, 0x5C, 0x67, 0x76, 0xB9, 0xE4, 0x00, 0x05, 0xB0, 0x81, 0x15,
+                0x8B, 0xBD, 0x59, 0x9F
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_openssl_enc_pbkdf2() {
+        // $ printf 'x' | openssl enc -aes-256-cbc -pbkdf2 -iter 4096 -S 0102030405060708 \
+        //     -pass pass:secret -P
+        let recipe: Recipe = "AES-256/CBC/PKCS7".parse().unwrap();
+        let params = Params::new(recipe, 4096, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        params.with_key(b"secret", |key, iv| {
+            assert_eq!(
+                key,
+                [
+                    0x43, 0x94, 0x70, 0xF6, 0x4A, 0x78, 0x5C, 0xF7, 0xE5, 0x57, 0x3C, 0xCC, 0xC7,
+                    0xDF, 0x9F, 0x87, 0xE3, 0xC3, 0x9C, 0x53, 0xAE, 0x77, 0x4E, 0xB6, 0x55, 0xCE,
+                    0x78, 0xE0, 0x1A, 0x82, 0xDF, 0x11
+                ]
+            );
+            assert_eq!(
+                iv,
+                [
+                    0x8B, 0xFD, 0x27, 0x9D, 0x89, 0xE1, 0x65, 0xA0, 0x88, 0xDA, 0xF9, 0x46, 0x40,
+                    0x0A, 0x10, 0xCC
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn header_is_checked() {
+        let recipe: Recipe = "AES-128/CBC".parse().unwrap();
+        let params = Params::new(recipe, 10, b"salt").unwrap();
+        let sealed = encrypt(b"password", &params, b"plain").unwrap();
+        assert_eq!(sealed.len(), params.header().len() + 16);
+        assert_eq!(decrypt(b"password", &sealed).unwrap(), b"plain");
+        assert!(decrypt(b"passw0rd", &sealed) != Ok(b"plain".to_vec()));
+
+        let header = params.header();
+        for length in 0..header.len() {
+            assert_eq!(
+                parse_header(&header[..length]),
+                Err(AesError::InvalidParameter)
+            );
+        }
+        let mut forged = sealed.clone();
+        forged[4] = 2;
+        assert_eq!(decrypt(b"password", &forged), Err(AesError::Unsupported));
+        let mut forged = sealed.clone();
+        forged[6..10].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(
+            decrypt(b"password", &forged),
+            Err(AesError::InvalidParameter)
+        );
+        let mut forged = sealed;
+        forged[6..10].copy_from_slice(&0u32.to_be_bytes());
+        assert_eq!(
+            decrypt(b"password", &forged),
+            Err(AesError::InvalidParameter)
+        );
+
+        assert_eq!(
+            Params::new(recipe, 10, b""),
+            Err(AesError::InvalidParameter)
+        );
+        assert_eq!(
+            Params::new(recipe, 10, &[0u8; 256]),
+            Err(AesError::InvalidParameter)
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! # pkcs11
+//! `pkcs11` maps the AES mechanisms of PKCS #11 onto the functions of this library, for software
+//! tokens built on it.
+//!
+//! A [`Mechanism`] is a mechanism type (`CKM_AES_*`) together with its parameter, like
+//! `CK_MECHANISM` in PKCS #11. Its [`encrypt`] and [`decrypt`] functions do a single-part
+//! operation (`C_Encrypt` or `C_Decrypt`), and an error can be turned into the `CKR_*` return
+//! value by [`return_value`].
+//!
+//! mechanism | supported
+//! - | -
+//! `CKM_AES_ECB` | yes
+//! `CKM_AES_CBC` | yes
+//! `CKM_AES_CBC_PAD` | yes
+//! `CKM_AES_CTR` | yes
+//! `CKM_AES_GCM` | not yet, returns `CKR_MECHANISM_INVALID`
+//! `CKM_AES_KEY_WRAP` | yes, KW of [`key_wrap`]
+//! `CKM_AES_KEY_WRAP_PAD` | yes, KWP (RFC 5649) of [`key_wrap`]
+//!
+//! [`Mechanism`]: ../pkcs11/enum.Mechanism.html
+//! [`encrypt`]: ../pkcs11/enum.Mechanism.html#method.encrypt
+//! [`decrypt`]: ../pkcs11/enum.Mechanism.html#method.decrypt
+//! [`return_value`]: ../pkcs11/fn.return_value.html
+//! [`key_wrap`]: ../key_wrap/index.html
+use super::aes_cipher::AesAny;
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+use super::aes_core::{KEY_BYTES_128BIT, KEY_BYTES_192BIT, KEY_BYTES_256BIT};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::aes_with_operation_mode as mode;
+use super::error::AesError;
+use super::key_wrap::{self, WrappedKey};
+use super::padding_128bit;
+use std::fmt;
+use std::os::raw::c_ulong;
+
+/// `CK_MECHANISM_TYPE`
+pub type MechanismType = c_ulong;
+/// `CK_RV`
+pub type ReturnValue = c_ulong;
+
+/// `CKM_AES_ECB`
+pub const CKM_AES_ECB: MechanismType = 0x0000_1081;
+/// `CKM_AES_CBC`
+pub const CKM_AES_CBC: MechanismType = 0x0000_1082;
+/// `CKM_AES_CBC_PAD`
+pub const CKM_AES_CBC_PAD: MechanismType = 0x0000_1085;
+/// `CKM_AES_CTR`
+pub const CKM_AES_CTR: MechanismType = 0x0000_1086;
+/// `CKM_AES_GCM`
+pub const CKM_AES_GCM: MechanismType = 0x0000_1087;
+/// `CKM_AES_KEY_WRAP`
+pub const CKM_AES_KEY_WRAP: MechanismType = 0x0000_2109;
+/// `CKM_AES_KEY_WRAP_PAD`
+pub const CKM_AES_KEY_WRAP_PAD: MechanismType = 0x0000_210A;
+
+/// `CKR_GENERAL_ERROR`
+pub const CKR_GENERAL_ERROR: ReturnValue = 0x0000_0005;
+/// `CKR_DATA_LEN_RANGE`
+pub const CKR_DATA_LEN_RANGE: ReturnValue = 0x0000_0021;
+/// `CKR_ENCRYPTED_DATA_INVALID`
+pub const CKR_ENCRYPTED_DATA_INVALID: ReturnValue = 0x0000_0040;
+/// `CKR_ENCRYPTED_DATA_LEN_RANGE`
+pub const CKR_ENCRYPTED_DATA_LEN_RANGE: ReturnValue = 0x0000_0041;
+/// `CKR_FUNCTION_CANCELED`
+pub const CKR_FUNCTION_CANCELED: ReturnValue = 0x0000_0050;
+/// `CKR_KEY_SIZE_RANGE`
+pub const CKR_KEY_SIZE_RANGE: ReturnValue = 0x0000_0062;
+/// `CKR_MECHANISM_INVALID`
+pub const CKR_MECHANISM_INVALID: ReturnValue = 0x0000_0070;
+/// `CKR_MECHANISM_PARAM_INVALID`
+pub const CKR_MECHANISM_PARAM_INVALID: ReturnValue = 0x0000_0071;
+
+/// `CK_AES_CTR_PARAMS`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AesCtrParams {
+    /// `ulCounterBits`: the number of the low bits of the counter block used as the counter.
+    pub counter_bits: c_ulong,
+    /// `cb`: the initial counter block.
+    pub cb: [u8; BLOCKSIZE_IN_BYTES],
+}
+
+/// `CK_GCM_PARAMS`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct GcmParams<'a> {
+    /// `pIv` and `ulIvLen`
+    pub iv: &'a [u8],
+    /// `pAAD` and `ulAADLen`
+    pub aad: &'a [u8],
+    /// `ulTagBits`
+    pub tag_bits: c_ulong,
+}
+
+/// An AES mechanism of PKCS #11 with its parameter (`CK_MECHANISM`).
+///
+/// Its `Debug` output never shows the IVs and the counter blocks.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mechanism<'a> {
+    /// `CKM_AES_ECB`, without parameter.
+    AesEcb,
+    /// `CKM_AES_CBC`, whose parameter is the IV.
+    AesCbc([u8; BLOCKSIZE_IN_BYTES]),
+    /// `CKM_AES_CBC_PAD` (CBC with PKCS #7 padding), whose parameter is the IV.
+    AesCbcPad([u8; BLOCKSIZE_IN_BYTES]),
+    /// `CKM_AES_CTR`
+    AesCtr(AesCtrParams),
+    /// `CKM_AES_GCM`
+    AesGcm(GcmParams<'a>),
+    /// `CKM_AES_KEY_WRAP`, whose optional parameter is the initial value, [`DEFAULT_IV`] if
+    /// `None`.
+    ///
+    /// [`DEFAULT_IV`]: ../key_wrap/constant.DEFAULT_IV.html
+    AesKeyWrap(Option<[u8; 8]>),
+    /// `CKM_AES_KEY_WRAP_PAD` (KWP of RFC 5649), without parameter.
+    AesKeyWrapPad,
+}
+
+impl fmt::Debug for AesCtrParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AesCtrParams")
+            .field("counter_bits", &self.counter_bits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for GcmParams<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GcmParams")
+            .field("tag_bits", &self.tag_bits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for Mechanism<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mechanism::AesEcb => f.write_str("AesEcb"),
+            Mechanism::AesCbc(_) => f.write_str("AesCbc(..)"),
+            Mechanism::AesCbcPad(_) => f.write_str("AesCbcPad(..)"),
+            Mechanism::AesCtr(params) => f.debug_tuple("AesCtr").field(params).finish(),
+            Mechanism::AesGcm(params) => f.debug_tuple("AesGcm").field(params).finish(),
+            Mechanism::AesKeyWrap(_) => f.write_str("AesKeyWrap(..)"),
+            Mechanism::AesKeyWrapPad => f.write_str("AesKeyWrapPad"),
+        }
+    }
+}
+
+/// The operation that failed, which decides some of the return values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// `C_Encrypt`
+    Encrypt,
+    /// `C_Decrypt`
+    Decrypt,
+}
+
+impl Mechanism<'_> {
+    /// The mechanism type (`CKM_AES_*`).
+    pub fn mechanism_type(&self) -> MechanismType {
+        match self {
+            Mechanism::AesEcb => CKM_AES_ECB,
+            Mechanism::AesCbc(_) => CKM_AES_CBC,
+            Mechanism::AesCbcPad(_) => CKM_AES_CBC_PAD,
+            Mechanism::AesCtr(_) => CKM_AES_CTR,
+            Mechanism::AesGcm(_) => CKM_AES_GCM,
+            Mechanism::AesKeyWrap(_) => CKM_AES_KEY_WRAP,
+            Mechanism::AesKeyWrapPad => CKM_AES_KEY_WRAP_PAD,
+        }
+    }
+
+    /// Encrypt `data` with `key` in a single part, like `C_Encrypt`.
+    ///
+    /// * *parameter* `key`: the key (`CKA_VALUE` of the key object), 16, 24 or 32 bytes.
+    /// * *parameter* `data`: the data to encrypt.
+    /// * *return* : the encrypted data, or the error.
+    /// # Examples
+    /// ```
+    /// use aes_frast::pkcs11::{return_value, Mechanism, Operation, CKR_ENCRYPTED_DATA_INVALID};
+    ///
+    /// let key = [0x42u8; 16];
+    /// let mechanism = Mechanism::AesCbcPad([0x24u8; 16]);
+    ///
+    /// let encrypted = mechanism.encrypt(&key, b"attack at dawn").unwrap();
+    /// assert_eq!(encrypted.len(), 16);
+    /// let decrypted = mechanism.decrypt(&key, &encrypted).unwrap();
+    /// assert_eq!(decrypted, b"attack at dawn");
+    ///
+    /// // The padding is checked.
+    /// let error = mechanism.decrypt(&[0x43u8; 16], &encrypted).unwrap_err();
+    /// assert_eq!(return_value(error, Operation::Decrypt), CKR_ENCRYPTED_DATA_INVALID);
+    /// ```
+    pub fn encrypt(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+        match self {
+            Mechanism::AesEcb => {
+                let keys = schedule_encrypt(key)?;
+                check_complete_blocks(data)?;
+                let mut output = vec![0u8; data.len()];
+                if !data.is_empty() {
+                    mode::ecb_enc(data, &mut output, &keys);
+                }
+                Ok(output)
+            }
+            Mechanism::AesCbc(iv) => {
+                let keys = schedule_encrypt(key)?;
+                check_complete_blocks(data)?;
+                let mut output = vec![0u8; data.len()];
+                if !data.is_empty() {
+                    mode::cbc_enc(data, &mut output, &keys, iv);
+                }
+                Ok(output)
+            }
+            Mechanism::AesCbcPad(iv) => {
+                let keys = schedule_encrypt(key)?;
+                let mut padded = data.to_vec();
+                padding_128bit::pa_pkcs7(&mut padded);
+                let mut output = vec![0u8; padded.len()];
+                mode::cbc_enc(&padded, &mut output, &keys, iv);
+                Ok(output)
+            }
+            Mechanism::AesCtr(params) => ctr(params, key, data),
+            Mechanism::AesGcm(_) => Err(AesError::Unsupported),
+            Mechanism::AesKeyWrap(iv) => {
+                let kek = AesAny::new(key)?;
+                let wrapped =
+                    key_wrap::wrap_with_iv(&kek, iv.unwrap_or(key_wrap::DEFAULT_IV), data)?;
+                Ok(wrapped.as_bytes().to_vec())
+            }
+            Mechanism::AesKeyWrapPad => {
+                let wrapped = key_wrap::wrap_with_padding(&AesAny::new(key)?, data)?;
+                Ok(wrapped.as_bytes().to_vec())
+            }
+        }
+    }
+
+    /// Decrypt `data` with `key` in a single part, like `C_Decrypt`.
+    ///
+    /// * *parameter* `key`: the key (`CKA_VALUE` of the key object), 16, 24 or 32 bytes.
+    /// * *parameter* `data`: the data to decrypt.
+    /// * *return* : the decrypted data, or the error.
+    /// # Examples
+    /// Please refer to the [`encrypt`] function.
+    ///
+    /// [`encrypt`]: ../pkcs11/enum.Mechanism.html#method.encrypt
+    pub fn decrypt(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+        match self {
+            Mechanism::AesEcb => {
+                let keys = schedule_decrypt(key)?;
+                check_complete_blocks(data)?;
+                let mut output = vec![0u8; data.len()];
+                if !data.is_empty() {
+                    mode::ecb_dec(data, &mut output, &keys);
+                }
+                Ok(output)
+            }
+            Mechanism::AesCbc(iv) => {
+                let keys = schedule_decrypt(key)?;
+                check_complete_blocks(data)?;
+                let mut output = vec![0u8; data.len()];
+                if !data.is_empty() {
+                    mode::cbc_dec(data, &mut output, &keys, iv);
+                }
+                Ok(output)
+            }
+            Mechanism::AesCbcPad(iv) => {
+                let keys = schedule_decrypt(key)?;
+                check_complete_blocks(data)?;
+                if data.is_empty() {
+                    return Err(AesError::InvalidDataLength);
+                }
+                let mut output = vec![0u8; data.len()];
+                mode::cbc_dec(data, &mut output, &keys, iv);
+                padding_128bit::check_pkcs7(&output)?;
+                padding_128bit::de_ansix923_pkcs7(&mut output);
+                Ok(output)
+            }
+            Mechanism::AesCtr(params) => ctr(params, key, data),
+            Mechanism::AesGcm(_) => Err(AesError::Unsupported),
+            Mechanism::AesKeyWrap(iv) => {
+                let kek = AesAny::new(key)?;
+                let wrapped = WrappedKey::new(data)?;
+                let key =
+                    key_wrap::unwrap_with_iv(&kek, iv.unwrap_or(key_wrap::DEFAULT_IV), &wrapped)?;
+                Ok(key.as_bytes().to_vec())
+            }
+            Mechanism::AesKeyWrapPad => {
+                let wrapped = WrappedKey::new(data)?;
+                let key = key_wrap::unwrap_with_padding(&AesAny::new(key)?, &wrapped)?;
+                Ok(key.as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+/// Map an error from [`Mechanism`] to the return value (`CKR_*`) of PKCS #11.
+///
+/// * *parameter* `error`: the error.
+/// * *parameter* `operation`: whether the error came from encryption or decryption.
+/// * *return* : the return value.
+/// # Examples
+/// ```
+/// use aes_frast::pkcs11::{return_value, Mechanism, Operation, CKR_DATA_LEN_RANGE};
+///
+/// let error = Mechanism::AesEcb.encrypt(&[0u8; 16], &[0u8; 15]).unwrap_err();
+/// assert_eq!(return_value(error, Operation::Encrypt), CKR_DATA_LEN_RANGE);
+/// ```
+///
+/// [`Mechanism`]: ../pkcs11/enum.Mechanism.html
+pub fn return_value(error: AesError, operation: Operation) -> ReturnValue {
+    match (error, operation) {
+        (AesError::InvalidKeyLength, _) => CKR_KEY_SIZE_RANGE,
+        (AesError::InvalidDataLength, Operation::Encrypt) => CKR_DATA_LEN_RANGE,
+        (AesError::InvalidDataLength, Operation::Decrypt) => CKR_ENCRYPTED_DATA_LEN_RANGE,
+        (AesError::InvalidPadding, _) | (AesError::TagMismatch, _) => CKR_ENCRYPTED_DATA_INVALID,
+        (AesError::InvalidParameter, _) => CKR_MECHANISM_PARAM_INVALID,
+        (AesError::Unsupported, _) => CKR_MECHANISM_INVALID,
+        (AesError::SequenceOverflow, _) => CKR_GENERAL_ERROR,
+        (AesError::Cancelled, _) => CKR_FUNCTION_CANCELED,
+    }
+}
+
+fn subkeys_buffer(key: &[u8]) -> Result<Vec<u32>, AesError> {
+    match key.len() {
+        KEY_BYTES_128BIT => Ok(vec![0u32; N_SUBKEYS_128BIT]),
+        KEY_BYTES_192BIT => Ok(vec![0u32; N_SUBKEYS_192BIT]),
+        KEY_BYTES_256BIT => Ok(vec![0u32; N_SUBKEYS_256BIT]),
+        _ => Err(AesError::InvalidKeyLength),
+    }
+}
+
+fn schedule_encrypt(key: &[u8]) -> Result<Vec<u32>, AesError> {
+    let mut keys = subkeys_buffer(key)?;
+    aes_core::key_schedule_encrypt_auto(key, &mut keys);
+    Ok(keys)
+}
+
+fn schedule_decrypt(key: &[u8]) -> Result<Vec<u32>, AesError> {
+    let mut keys = subkeys_buffer(key)?;
+    aes_core::key_schedule_decrypt_auto(key, &mut keys);
+    Ok(keys)
+}
+
+fn check_complete_blocks(data: &[u8]) -> Result<(), AesError> {
+    if data.len() & 0b1111 == 0 {
+        Ok(())
+    } else {
+        Err(AesError::InvalidDataLength)
+    }
+}
+
+fn ctr(params: &AesCtrParams, key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    if params.counter_bits == 0 || params.counter_bits > 128 {
+        return Err(AesError::InvalidParameter);
+    }
+    let keys = schedule_encrypt(key)?;
+    // The counter must not wrap around within its `counter_bits` bits.
+    let mask = u128::MAX >> (128 - params.counter_bits);
+    let remaining = mask - (u128::from_be_bytes(params.cb) & mask);
+    let blocks = (data.len() as u128 + BLOCKSIZE_IN_BYTES as u128 - 1) >> 4;
+    if blocks > 0 && blocks - 1 > remaining {
+        return Err(AesError::InvalidDataLength);
+    }
+    let mut output = vec![0u8; data.len()];
+    mode::ctr_enc_dec(data, &mut output, &keys, &params.cb);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These examples came from NIST SP 800-38A F.1.1, F.2.1 and F.5.1
+    const KEY: [u8; 16] = [
+        0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F,
+        0x3C,
+    ];
+    const PLAIN: [u8; 16] = [
+        0x6B, 0xC1, 0xBE, 0xE2, 0x2E, 0x40, 0x9F, 0x96, 0xE9, 0x3D, 0x7E, 0x11, 0x73, 0x93, 0x17,
+        0x2A,
+    ];
+
+    #[test]
+    fn block_mechanisms_work() {
+        let iv: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let cases: [(Mechanism, [u8; 16]); 3] = [
+            (
+                Mechanism::AesEcb,
+                [
+                    0x3A, 0xD7, 0x7B, 0xB4, 0x0D, 0x7A, 0x36, 0x60, 0xA8, 0x9E, 0xCA, 0xF3, 0x24,
+                    0x66, 0xEF, 0x97,
+                ],
+            ),
+            (
+                Mechanism::AesCbc(iv),
+                [
+                    0x76, 0x49, 0xAB, 0xAC, 0x81, 0x19, 0xB2, 0x46, 0xCE, 0xE9, 0x8E, 0x9B, 0x12,
+                    0xE9, 0x19, 0x7D,
+                ],
+            ),
+            (
+                Mechanism::AesCtr(AesCtrParams {
+                    counter_bits: 128,
+                    cb: core::array::from_fn(|i| 0xF0 + i as u8),
+                }),
+                [
+                    0x87, 0x4D, 0x61, 0x91, 0xB6, 0x20, 0xE3, 0x26, 0x1B, 0xEF, 0x68, 0x64, 0x99,
+                    0x0D, 0xB6, 0xCE,
+                ],
+            ),
+        ];
+        for (mechanism, expected) in cases {
+            let encrypted = mechanism.encrypt(&KEY, &PLAIN).unwrap();
+            assert_eq!(encrypted, expected);
+            assert_eq!(mechanism.decrypt(&KEY, &encrypted).unwrap(), PLAIN);
+        }
+        assert_eq!(
+            Mechanism::AesEcb.encrypt(&KEY, &[]).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn cbc_pad_works() {
+        let mechanism = Mechanism::AesCbcPad([0u8; 16]);
+        for length in [0, 1, 15, 16, 17] {
+            let data = vec![0xA5u8; length];
+            let encrypted = mechanism.encrypt(&KEY, &data).unwrap();
+            assert_eq!(encrypted.len(), (length / 16 + 1) * 16);
+            assert_eq!(mechanism.decrypt(&KEY, &encrypted).unwrap(), data);
+        }
+        assert_eq!(
+            mechanism.decrypt(&KEY, &[]),
+            Err(AesError::InvalidDataLength)
+        );
+        // A block of zeros decrypts to the zero padding byte with the CBC mechanism.
+        let mut zeros = [0u8; 16];
+        aes_core::block_encrypt128_inplace(&mut zeros, &schedule_encrypt(&KEY).unwrap());
+        assert_eq!(
+            mechanism.decrypt(&KEY, &zeros),
+            Err(AesError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn ctr_counter_bits_works() {
+        let mut cb = [0u8; 16];
+        cb[15] = 0xFE;
+        let mechanism = Mechanism::AesCtr(AesCtrParams {
+            counter_bits: 8,
+            cb,
+        });
+        assert!(mechanism.encrypt(&KEY, &[0u8; 32]).is_ok());
+        assert!(mechanism.encrypt(&KEY, &[0u8; 17]).is_ok());
+        assert_eq!(
+            mechanism.encrypt(&KEY, &[0u8; 33]),
+            Err(AesError::InvalidDataLength)
+        );
+        let mechanism = Mechanism::AesCtr(AesCtrParams {
+            counter_bits: 0,
+            cb,
+        });
+        assert_eq!(
+            mechanism.encrypt(&KEY, &[]),
+            Err(AesError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn key_wrap_works() {
+        // RFC 3394, section 4.1.
+        let kek: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let key: [u8; 16] = core::array::from_fn(|i| (i as u8) * 0x11);
+        let mechanism = Mechanism::AesKeyWrap(None);
+        let wrapped = mechanism.encrypt(&kek, &key).unwrap();
+        assert_eq!(
+            wrapped,
+            [
+                0x1F, 0xA6, 0x8B, 0x0A, 0x81, 0x12, 0xB4, 0x47, 0xAE, 0xF3, 0x4B, 0xD8, 0xFB, 0x5A,
+                0x7B, 0x82, 0x9D, 0x3E, 0x86, 0x23, 0x71, 0xD2, 0xCF, 0xE5
+            ]
+        );
+        assert_eq!(mechanism.decrypt(&kek, &wrapped).unwrap(), key);
+        // Another initial value gives another wrapped key, which the default doesn't unwrap.
+        let other = Mechanism::AesKeyWrap(Some([0x5A; 8]));
+        let other_wrapped = other.encrypt(&kek, &key).unwrap();
+        assert_ne!(other_wrapped, wrapped);
+        assert_eq!(other.decrypt(&kek, &other_wrapped).unwrap(), key);
+        assert_eq!(
+            mechanism.decrypt(&kek, &other_wrapped),
+            Err(AesError::TagMismatch)
+        );
+        assert_eq!(
+            mechanism.encrypt(&kek, &key[..12]),
+            Err(AesError::InvalidDataLength)
+        );
+
+        let mechanism = Mechanism::AesKeyWrapPad;
+        assert_eq!(mechanism.mechanism_type(), CKM_AES_KEY_WRAP_PAD);
+        let wrapped = mechanism.encrypt(&kek, &key[..12]).unwrap();
+        assert_eq!(wrapped.len(), 24);
+        assert_eq!(mechanism.decrypt(&kek, &wrapped).unwrap(), key[..12]);
+    }
+
+    #[test]
+    fn errors_work() {
+        assert_eq!(
+            Mechanism::AesEcb.encrypt(&[0u8; 17], &PLAIN),
+            Err(AesError::InvalidKeyLength)
+        );
+        let gcm = Mechanism::AesGcm(GcmParams {
+            iv: &[0u8; 12],
+            aad: &[],
+            tag_bits: 128,
+        });
+        assert_eq!(gcm.mechanism_type(), CKM_AES_GCM);
+        let error = gcm.encrypt(&KEY, &PLAIN).unwrap_err();
+        assert_eq!(
+            return_value(error, Operation::Encrypt),
+            CKR_MECHANISM_INVALID
+        );
+        let error = Mechanism::AesKeyWrap(None)
+            .decrypt(&KEY, &[0u8; 24])
+            .unwrap_err();
+        assert_eq!(
+            return_value(error, Operation::Decrypt),
+            CKR_ENCRYPTED_DATA_INVALID
+        );
+        assert_eq!(
+            return_value(AesError::InvalidDataLength, Operation::Decrypt),
+            CKR_ENCRYPTED_DATA_LEN_RANGE
+        );
+    }
+}
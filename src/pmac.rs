@@ -0,0 +1,227 @@
+//! # pmac
+//! `pmac` provides PMAC, the parallelizable MAC of Black and Rogaway (PMAC1, the variant which
+//! pairs with OCB).
+//!
+//! Each block but the last is encrypted on its own, masked with an offset which only depends on
+//! its index (the Gray code of the index, over the doublings of `L = E(0)`), and the results are
+//! XORed together. So the blocks can be processed in any order or on several threads:
+//! [`Pmac::mac_parallel`] splits a long message between scoped threads and gives the same MAC as
+//! [`Pmac::mac`].
+//!
+//! [`Pmac::mac_parallel`]: ../pmac/struct.Pmac.html#method.mac_parallel
+//! [`Pmac::mac`]: ../pmac/struct.Pmac.html#method.mac
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::tag::{verify_tag, Tag};
+use std::thread;
+
+/// The number of doublings of `L` kept, enough for the index of any block.
+const L_TABLE_LEN: usize = usize::BITS as usize;
+
+/// PMAC over a [`BlockCipher128`].
+/// # Examples
+/// ```
+/// use aes_frast::pmac::Pmac;
+/// use aes_frast::Aes128;
+///
+/// let key: [u8; 16] = std::array::from_fn(|i| i as u8);
+/// let pmac = Pmac::new(Aes128::new(&key));
+///
+/// // This example came from the PMAC-AES-128 test vectors, the 3-byte message.
+/// let mac = pmac.mac(&[0x00, 0x01, 0x02]);
+/// assert_eq!(mac.as_bytes(), &[0x25, 0x6B, 0xA5, 0x19, 0x3C, 0x1B, 0x99, 0x1B,
+///                              0x4D, 0xF0, 0xC5, 0x1F, 0x38, 0x8A, 0x9E, 0x27]);
+///
+/// // Several threads give the same MAC.
+/// let message = vec![0x5Au8; 100_000];
+/// assert_eq!(pmac.mac_parallel(&message, 4), pmac.mac(&message));
+/// assert_eq!(pmac.verify(&message, pmac.mac(&message).as_bytes()), Ok(()));
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Pmac<C> {
+    cipher: C,
+    /// `L(i) = L * x^i`.
+    l: [Block; L_TABLE_LEN],
+    /// `L(-1) = L * x^(-1)`, which masks a complete last block.
+    l_inv: Block,
+}
+
+impl<C: BlockCipher128> Pmac<C> {
+    /// Create the MAC, deriving `L` and its doublings from `cipher`.
+    pub fn new(cipher: C) -> Self {
+        let mut first = Block::default();
+        cipher.encrypt_block(&mut first.0);
+        let mut l = [first; L_TABLE_LEN];
+        for i in 1..L_TABLE_LEN {
+            l[i] = l[i - 1].dbl();
+        }
+        // Halving is the reverse of `dbl`: undo the reduction, then shift right.
+        let value = u128::from_be_bytes(first.0);
+        let l_inv = if value & 1 != 0 {
+            ((value ^ 0x87) >> 1) | (1 << 127)
+        } else {
+            value >> 1
+        };
+        Self {
+            cipher,
+            l,
+            l_inv: Block::from(l_inv.to_be_bytes()),
+        }
+    }
+
+    /// The block cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Compute the 16-byte MAC of `message`.
+    pub fn mac(&self, message: &[u8]) -> Tag {
+        let (head, last) = split_last(message);
+        self.finish(self.sum(head, 0), last)
+    }
+
+    /// Compute the MAC of `message` on up to `threads` threads.
+    ///
+    /// The MAC is the same as the one of [`mac`]. A message of less than 256 blocks per thread is
+    /// not worth the threads, and is processed on the calling thread only.
+    ///
+    /// [`mac`]: ../pmac/struct.Pmac.html#method.mac
+    pub fn mac_parallel(&self, message: &[u8], threads: usize) -> Tag
+    where
+        C: Sync,
+    {
+        let (head, last) = split_last(message);
+        let blocks = head.len() / BLOCKSIZE_IN_BYTES;
+        let threads = threads.clamp(1, (blocks / 256).max(1));
+        if threads == 1 {
+            return self.finish(self.sum(head, 0), last);
+        }
+        let chunk_blocks = blocks.div_ceil(threads);
+        let sum = thread::scope(|scope| {
+            let workers: Vec<_> = head
+                .chunks(chunk_blocks * BLOCKSIZE_IN_BYTES)
+                .enumerate()
+                .map(|(i, chunk)| scope.spawn(move || self.sum(chunk, i * chunk_blocks)))
+                .collect();
+            workers
+                .into_iter()
+                .fold(Block::default(), |sum, worker| sum ^ worker.join().unwrap())
+        });
+        self.finish(sum, last)
+    }
+
+    /// Verify the received 16-byte MAC of `message` in constant time.
+    ///
+    /// * *return* : `Ok(())` if it is right, otherwise `Err(AesError::TagMismatch)`.
+    pub fn verify(&self, message: &[u8], tag: &[u8]) -> Result<(), AesError> {
+        verify_tag(tag, self.mac(message).as_bytes())
+    }
+
+    /// The offset of the block of index `index` (from 1): the XOR of the `L(j)` of the bits of
+    /// the Gray code of `index`.
+    fn offset(&self, index: usize) -> Block {
+        let gray = index ^ (index >> 1);
+        (0..L_TABLE_LEN)
+            .filter(|j| (gray >> j) & 1 != 0)
+            .fold(Block::default(), |offset, j| offset ^ self.l[j])
+    }
+
+    /// The XOR of the masked encryptions of the complete blocks of `blocks`, the first of which
+    /// is the block of index `skipped + 1` of the message.
+    fn sum(&self, blocks: &[u8], skipped: usize) -> Block {
+        let mut offset = self.offset(skipped);
+        let mut sum = Block::default();
+        for (i, chunk) in blocks.chunks_exact(BLOCKSIZE_IN_BYTES).enumerate() {
+            offset ^= self.l[(skipped + i + 1).trailing_zeros() as usize];
+            let mut block = Block::from_slice(chunk) ^ offset;
+            self.cipher.encrypt_block(&mut block.0);
+            sum ^= block;
+        }
+        sum
+    }
+
+    fn finish(&self, mut sum: Block, last: &[u8]) -> Tag {
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[..last.len()].copy_from_slice(last);
+        if last.len() == BLOCKSIZE_IN_BYTES {
+            sum ^= self.l_inv;
+        } else {
+            block[last.len()] = 0x80;
+        }
+        sum ^= Block::from(block);
+        self.cipher.encrypt_block(&mut sum.0);
+        Tag::from(sum.0)
+    }
+}
+
+/// Split `message` into its complete blocks but the last one, and the last (maybe partial or
+/// empty) block.
+fn split_last(message: &[u8]) -> (&[u8], &[u8]) {
+    let blocks = message.len().div_ceil(BLOCKSIZE_IN_BYTES).max(1);
+    message.split_at(BLOCKSIZE_IN_BYTES * (blocks - 1))
+}
+
+impl_redacted_debug!(Pmac<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+    use crate::Aes128;
+
+    #[test]
+    fn pmac_aes_128_vectors() {
+        // The PMAC-AES-128 test vectors, with the key 000102...0F and the message 000102...
+        let key: [u8; 16] = std::array::from_fn(|i| i as u8);
+        let pmac = Pmac::new(Aes128::new(&key));
+        let cases = [
+            (0, "4399572cd6ea5341b8d35876a7098af7"),
+            (3, "256ba5193c1b991b4df0c51f388a9e27"),
+            (16, "ebbd822fa458daf6dfdad7c27da76338"),
+            (20, "0412ca150bbf79058d8c75a58c993f55"),
+            (32, "e97ac04e9e5e3399ce5355cd7407bc75"),
+            (34, "5cba7d5eb24f7c86ccc54604e53d5512"),
+        ];
+        for (len, expected) in cases {
+            let message: Vec<u8> = (0..len as u8).collect();
+            assert_eq!(hex(pmac.mac(&message).as_bytes()), expected, "{}", len);
+        }
+        assert_eq!(
+            hex(pmac.mac(&[0u8; 1000]).as_bytes()),
+            "c2c9fa1d9985f6f0d2aff915a0e8d910"
+        );
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let pmac = Pmac::new(Aes128::new(&[0x42u8; 16]));
+        let message: Vec<u8> = (0..70_000).map(|i| (i * 7) as u8).collect();
+        for len in [0, 15, 16, 4096, 4111, 65536, 70_000] {
+            let serial = pmac.mac(&message[..len]);
+            for threads in [0, 1, 2, 3, 8] {
+                assert_eq!(pmac.mac_parallel(&message[..len], threads), serial);
+            }
+        }
+    }
+
+    #[test]
+    fn offsets_and_halving() {
+        let pmac = Pmac::new(Aes128::new(&[0x13u8; 16]));
+        assert_eq!(pmac.l_inv.dbl(), pmac.l[0]);
+        let mut offset = Block::default();
+        for index in 1usize..100 {
+            offset ^= pmac.l[index.trailing_zeros() as usize];
+            assert_eq!(pmac.offset(index), offset);
+        }
+        let mac = pmac.mac(b"message");
+        assert_eq!(pmac.verify(b"message", mac.as_bytes()), Ok(()));
+        assert_eq!(
+            pmac.verify(b"massage", mac.as_bytes()),
+            Err(AesError::TagMismatch)
+        );
+    }
+}
@@ -0,0 +1,813 @@
+//! # recipe
+//! `recipe` parses cipher "recipes" like `"AES-128/CBC/PKCS7"` into a [`Recipe`], which can
+//! encrypt and decrypt data.
+//!
+//! A recipe is `AES-<key bits>/<mode>[/<padding>]`, case-insensitive:
+//! * key bits: `128`, `192` or `256` (`AES128` and so on are accepted too).
+//! * mode: `ECB`, `CBC`, `PCBC`, `CFB`, `CFB8`, `OFB` or `CTR`.
+//! * padding: `PKCS7` (also `PKCS5`, `PKCS5Padding`, `PKCS7Padding`), `ANSIX923`, `ISO7816`,
+//!   `ZEROS` or `NoPadding` (also `None`), see [`PaddingScheme`]. `ECB`, `CBC` and `PCBC` use `PKCS7` by default, the other modes
+//!   don't accept padding.
+//!
+//! Parsing gives `Err(AesError::Unsupported)` for well-formed recipes naming algorithms this
+//! library doesn't provide (such as `AES-256/GCM`), and `Err(AesError::InvalidParameter)` for
+//! everything else.
+//!
+//! [`Recipe::encrypt`] schedules the key on every call. For many messages under one key,
+//! [`Recipe::with_key`] schedules it once, and the [`KeyedRecipe`] it returns can work in a
+//! reusable [`Scratch`] buffer without allocating.
+//! # Examples
+//! ```
+//! use aes_frast::padding_128bit::PaddingScheme;
+//! use aes_frast::recipe::{Mode, Recipe};
+//! use aes_frast::AesError;
+//!
+//! let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+//! assert_eq!(recipe.key_bits(), 128);
+//! assert_eq!(recipe.mode(), Mode::Cbc);
+//! assert_eq!(recipe.padding(), PaddingScheme::Pkcs7);
+//! assert_eq!(recipe.to_string(), "AES-128/CBC/PKCS7");
+//!
+//! let key = [0x42u8; 16];
+//! let iv = [0x24u8; 16];
+//! let cipher = recipe.encrypt(&key, &iv, b"attack at dawn").unwrap();
+//! assert_eq!(recipe.decrypt(&key, &iv, &cipher).unwrap(), b"attack at dawn");
+//!
+//! assert_eq!("AES-256/GCM".parse::<Recipe>(), Err(AesError::Unsupported));
+//! assert_eq!("AES-100/CBC".parse::<Recipe>(), Err(AesError::InvalidParameter));
+//! assert_eq!("AES-128/CTR/PKCS7".parse::<Recipe>(), Err(AesError::InvalidParameter));
+//! ```
+//!
+//! [`Recipe`]: ../recipe/struct.Recipe.html
+//! [`Recipe::encrypt`]: ../recipe/struct.Recipe.html#method.encrypt
+//! [`Recipe::with_key`]: ../recipe/struct.Recipe.html#method.with_key
+//! [`KeyedRecipe`]: ../recipe/struct.KeyedRecipe.html
+//! [`Scratch`]: ../recipe/struct.Scratch.html
+//! [`PaddingScheme`]: ../padding_128bit/enum.PaddingScheme.html
+use super::aes_core::{self, BLOCKSIZE_IN_BYTES};
+use super::aes_core::{N_SUBKEYS_128BIT, N_SUBKEYS_192BIT, N_SUBKEYS_256BIT};
+use super::aes_with_operation_mode::{self as mode, ChainedFunction, Subkeys};
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::iv_generator::IvGenerator;
+use super::metrics::{self, Operation};
+use super::padding_128bit::{Padding, PaddingScheme};
+use std::fmt;
+use std::str::FromStr;
+
+/// The operation mode of a [`Recipe`].
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// ECB (Electronic Codebook)
+    Ecb,
+    /// CBC (Cipher Block Chaining)
+    Cbc,
+    /// PCBC (Propagating Cipher Block Chaining)
+    Pcbc,
+    /// CFB (Cipher Feedback) with 128-bit feedback
+    Cfb,
+    /// CFB (Cipher Feedback) with 8-bit feedback
+    Cfb8,
+    /// OFB (Output Feedback)
+    Ofb,
+    /// CTR (Counter)
+    Ctr,
+}
+
+/// A parsed cipher recipe, such as `AES-128/CBC/PKCS7`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Recipe {
+    key_bits: usize,
+    mode: Mode,
+    padding: PaddingScheme,
+}
+
+impl Recipe {
+    /// The key size in bits.
+    pub fn key_bits(&self) -> usize {
+        self.key_bits
+    }
+
+    /// The operation mode.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The padding.
+    pub fn padding(&self) -> PaddingScheme {
+        self.padding
+    }
+
+    /// Encrypt `data`.
+    ///
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *parameter* `data`: the data to encrypt.
+    /// * *return* : the encrypted data, or the error.
+    pub fn encrypt(&self, key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+        metrics::observe(Operation::Encrypt, data.len(), || {
+            let keys = self.schedule(key, iv, true)?;
+            let mut input = data.to_vec();
+            self.pad(&mut input)?;
+            let mut output = vec![0u8; input.len()];
+            if input.is_empty() {
+                return Ok(output);
+            }
+            self.mode_function(true)(&input, &mut output, &keys, iv);
+            Ok(output)
+        })
+    }
+
+    /// Decrypt `data`, checking and removing the padding.
+    ///
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *parameter* `data`: the data to decrypt.
+    /// * *return* : the decrypted data, or the error.
+    pub fn decrypt(&self, key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+        metrics::observe(Operation::Decrypt, data.len(), || {
+            let keys = self.schedule(key, iv, false)?;
+            self.check_length(data)?;
+            if self.padding != PaddingScheme::NoPadding && data.is_empty() {
+                return Err(AesError::InvalidDataLength);
+            }
+            let mut output = vec![0u8; data.len()];
+            if data.is_empty() {
+                return Ok(output);
+            }
+            self.mode_function(false)(data, &mut output, &keys, iv);
+            self.unpad(&mut output)?;
+            Ok(output)
+        })
+    }
+
+    /// Encrypt `data` with the IV given by `iv_generator`.
+    ///
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv_generator`: the IV policy, which is asked for one IV (even in ECB mode).
+    /// * *parameter* `index`: the position of the message, such as a sector number, passed to
+    ///   `iv_generator`.
+    /// * *parameter* `data`: the data to encrypt.
+    /// * *return* : the IV and the encrypted data, or the error. The IV is needed for decryption
+    ///   unless `iv_generator` gives it again from `index`.
+    /// # Examples
+    /// ```
+    /// use aes_frast::iv_generator::Plain64;
+    /// use aes_frast::recipe::Recipe;
+    ///
+    /// let recipe: Recipe = "AES-128/CTR".parse().unwrap();
+    /// let key = [0x42u8; 16];
+    /// let (iv, cipher) = recipe
+    ///     .encrypt_with_iv_generator(&key, &mut Plain64, 3, b"sector three")
+    ///     .unwrap();
+    /// assert_eq!(iv[0], 3);
+    /// let plain = recipe
+    ///     .decrypt_with_iv_generator(&key, &mut Plain64, 3, &cipher)
+    ///     .unwrap();
+    /// assert_eq!(plain, b"sector three");
+    /// ```
+    pub fn encrypt_with_iv_generator<G: IvGenerator + ?Sized>(
+        &self,
+        key: &[u8],
+        iv_generator: &mut G,
+        index: u64,
+        data: &[u8],
+    ) -> Result<([u8; BLOCKSIZE_IN_BYTES], Vec<u8>), AesError> {
+        let iv = iv_generator.generate_iv(index);
+        Ok((iv, self.encrypt(key, &iv, data)?))
+    }
+
+    /// Encrypt `data` with a random IV from the random number generator of the OS.
+    ///
+    /// Only available with the `getrandom` feature.
+    ///
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `data`: the data to encrypt.
+    /// * *return* : the IV and the encrypted data, or the error. The IV must be kept with the
+    ///   encrypted data for decryption.
+    /// # Examples
+    /// ```
+    /// use aes_frast::recipe::Recipe;
+    ///
+    /// let recipe: Recipe = "AES-256/CBC/PKCS7".parse().unwrap();
+    /// let key = [0x42u8; 32];
+    /// let (iv, cipher) = recipe.encrypt_with_random_iv(&key, b"message").unwrap();
+    /// assert_eq!(recipe.decrypt(&key, &iv, &cipher).unwrap(), b"message");
+    /// ```
+    /// # Panics
+    /// This function panics if the OS fails to provide random bytes.
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_random_iv(
+        &self,
+        key: &[u8],
+        data: &[u8],
+    ) -> Result<([u8; BLOCKSIZE_IN_BYTES], Vec<u8>), AesError> {
+        let iv = super::rng::os_iv();
+        Ok((iv, self.encrypt(key, &iv, data)?))
+    }
+
+    /// Decrypt `data` with the IV given by `iv_generator`, checking and removing the padding.
+    ///
+    /// This only makes sense with generators which give the same IV for the same `index`, such
+    /// as [`Plain64`] and [`Essiv`]. Otherwise, decrypt with the IV returned by
+    /// [`encrypt_with_iv_generator`].
+    ///
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *parameter* `iv_generator`: the IV policy of the encryption.
+    /// * *parameter* `index`: the position of the message, such as a sector number.
+    /// * *parameter* `data`: the data to decrypt.
+    /// * *return* : the decrypted data, or the error.
+    ///
+    /// [`Plain64`]: ../iv_generator/struct.Plain64.html
+    /// [`Essiv`]: ../iv_generator/struct.Essiv.html
+    /// [`encrypt_with_iv_generator`]: ../recipe/struct.Recipe.html#method.encrypt_with_iv_generator
+    pub fn decrypt_with_iv_generator<G: IvGenerator + ?Sized>(
+        &self,
+        key: &[u8],
+        iv_generator: &mut G,
+        index: u64,
+        data: &[u8],
+    ) -> Result<Vec<u8>, AesError> {
+        let iv = iv_generator.generate_iv(index);
+        self.decrypt(key, &iv, data)
+    }
+
+    /// The mode function of `aes_with_operation_mode`, with the signature of the ones that take an
+    /// IV. It returns the chaining value for the next call.
+    pub(crate) fn mode_function(&self, encrypt: bool) -> ChainedFunction {
+        match (self.mode, encrypt) {
+            (Mode::Ecb, true) => |input, output, keys, _| mode::ecb_enc(input, output, keys),
+            (Mode::Ecb, false) => |input, output, keys, _| mode::ecb_dec(input, output, keys),
+            (Mode::Cbc, true) => mode::cbc_enc,
+            (Mode::Cbc, false) => mode::cbc_dec,
+            (Mode::Pcbc, true) => mode::pcbc_enc,
+            (Mode::Pcbc, false) => mode::pcbc_dec,
+            (Mode::Cfb, true) => mode::cfb_enc,
+            (Mode::Cfb, false) => mode::cfb_dec,
+            (Mode::Cfb8, true) => mode::cfb_8_enc,
+            (Mode::Cfb8, false) => mode::cfb_8_dec,
+            (Mode::Ofb, _) => mode::ofb_enc_dec,
+            (Mode::Ctr, _) => mode::ctr_enc_dec,
+        }
+    }
+
+    /// Add the padding to the end of a message, or check its length without padding.
+    pub(crate) fn pad(&self, data: &mut Vec<u8>) -> Result<(), AesError> {
+        match self.padding {
+            PaddingScheme::NoPadding => self.check_length(data)?,
+            padding => {
+                padding.pad(data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check and remove the padding at the end of a decrypted message.
+    pub(crate) fn unpad(&self, data: &mut Vec<u8>) -> Result<(), AesError> {
+        self.padding.unpad(data)?;
+        Ok(())
+    }
+
+    /// Schedule `key` once, for encrypting and decrypting many messages.
+    ///
+    /// * *parameter* `key`: the key, whose length must match the recipe.
+    /// * *return* : the keyed recipe, or `Err(AesError::InvalidKeyLength)`.
+    pub fn with_key(&self, key: &[u8]) -> Result<KeyedRecipe, AesError> {
+        let mut encryption_subkeys = self.subkeys_buffer(key)?;
+        let mut decryption_subkeys = encryption_subkeys.clone();
+        aes_core::key_schedule_encrypt_decrypt_auto(
+            key,
+            &mut encryption_subkeys,
+            &mut decryption_subkeys,
+        );
+        Ok(KeyedRecipe {
+            recipe: *self,
+            encryption_subkeys,
+            decryption_subkeys,
+        })
+    }
+
+    fn subkeys_buffer(&self, key: &[u8]) -> Result<Vec<u32>, AesError> {
+        match (self.key_bits, key.len() * 8) {
+            (128, 128) => Ok(vec![0u32; N_SUBKEYS_128BIT]),
+            (192, 192) => Ok(vec![0u32; N_SUBKEYS_192BIT]),
+            (256, 256) => Ok(vec![0u32; N_SUBKEYS_256BIT]),
+            _ => Err(AesError::InvalidKeyLength),
+        }
+    }
+
+    fn check_iv(&self, iv: &[u8]) -> Result<(), AesError> {
+        if self.mode != Mode::Ecb && iv.len() != BLOCKSIZE_IN_BYTES {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(())
+    }
+
+    // Only ECB, CBC and PCBC use block-decryption.
+    fn block_decryption(&self) -> bool {
+        matches!(self.mode, Mode::Ecb | Mode::Cbc | Mode::Pcbc)
+    }
+
+    pub(crate) fn schedule(
+        &self,
+        key: &[u8],
+        iv: &[u8],
+        encrypt: bool,
+    ) -> Result<Vec<u32>, AesError> {
+        let mut keys = self.subkeys_buffer(key)?;
+        self.check_iv(iv)?;
+        if encrypt || !self.block_decryption() {
+            aes_core::key_schedule_encrypt_auto(key, &mut keys);
+        } else {
+            aes_core::key_schedule_decrypt_auto(key, &mut keys);
+        }
+        Ok(keys)
+    }
+
+    pub(crate) fn check_length(&self, data: &[u8]) -> Result<(), AesError> {
+        let block_mode = matches!(self.mode, Mode::Ecb | Mode::Cbc | Mode::Pcbc);
+        if block_mode && data.len() & 0b1111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        Ok(())
+    }
+}
+
+/// A buffer owned by the caller, which [`KeyedRecipe`] reuses for every message.
+///
+/// It grows to the longest message (plus padding) seen, and is never shrunk, so a loop over
+/// messages of bounded length allocates only in its first iterations, or never with
+/// [`with_capacity`].
+///
+/// [`KeyedRecipe`]: ../recipe/struct.KeyedRecipe.html
+/// [`with_capacity`]: ../recipe/struct.Scratch.html#method.with_capacity
+#[derive(Clone, Default)]
+pub struct Scratch {
+    buffer: Vec<u8>,
+}
+
+impl Scratch {
+    /// Create an empty scratch buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a scratch buffer for messages of up to `bytes` bytes, padding included.
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(bytes),
+        }
+    }
+
+    /// The number of bytes the buffer holds without allocating.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+/// A [`Recipe`] with a scheduled key, for encrypting many messages with the same key.
+///
+/// The key is scheduled once by [`Recipe::with_key`]. With a [`Scratch`], the messages are
+/// processed in place in the scratch buffer, so a call neither schedules the key nor allocates.
+/// # Examples
+/// ```
+/// use aes_frast::recipe::{Recipe, Scratch};
+///
+/// let recipe: Recipe = "AES-128/CBC/PKCS7".parse().unwrap();
+/// let key = [0x42u8; 16];
+/// let keyed = recipe.with_key(&key).unwrap();
+/// let mut scratch = Scratch::with_capacity(1024);
+///
+/// for i in 0..100u8 {
+///     let iv = [i; 16];
+///     let message = vec![i; i as usize];
+///     let cipher = keyed.encrypt_with_scratch(&iv, &message, &mut scratch).unwrap();
+///     assert_eq!(cipher, recipe.encrypt(&key, &iv, &message).unwrap());
+/// }
+/// assert_eq!(scratch.capacity(), 1024);
+/// ```
+///
+/// [`Recipe`]: ../recipe/struct.Recipe.html
+/// [`Recipe::with_key`]: ../recipe/struct.Recipe.html#method.with_key
+/// [`Scratch`]: ../recipe/struct.Scratch.html
+#[derive(Clone)]
+pub struct KeyedRecipe {
+    recipe: Recipe,
+    encryption_subkeys: Vec<u32>,
+    decryption_subkeys: Vec<u32>,
+}
+
+impl KeyedRecipe {
+    /// The recipe.
+    pub fn recipe(&self) -> Recipe {
+        self.recipe
+    }
+
+    /// Encrypt `data` in the scratch buffer.
+    ///
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *parameter* `data`: the data to encrypt.
+    /// * *parameter* `scratch`: the buffer which receives the encrypted data.
+    /// * *return* : the encrypted data in the scratch buffer, or the error.
+    pub fn encrypt_with_scratch<'a>(
+        &self,
+        iv: &[u8],
+        data: &[u8],
+        scratch: &'a mut Scratch,
+    ) -> Result<&'a [u8], AesError> {
+        metrics::observe(Operation::Encrypt, data.len(), || {
+            self.recipe.check_iv(iv)?;
+            let buffer = &mut scratch.buffer;
+            buffer.clear();
+            buffer.extend_from_slice(data);
+            self.recipe.pad(buffer)?;
+            let cipher = Subkeys::new(&self.encryption_subkeys);
+            apply_in_place(self.recipe.mode, true, &cipher, buffer, iv);
+            Ok(&buffer[..])
+        })
+    }
+
+    /// Decrypt `data` in the scratch buffer, checking and removing the padding.
+    ///
+    /// * *parameter* `iv`: the initialization vector (or initial counter block), which is
+    ///   ignored by ECB mode.
+    /// * *parameter* `data`: the data to decrypt.
+    /// * *parameter* `scratch`: the buffer which receives the decrypted data.
+    /// * *return* : the decrypted data in the scratch buffer, or the error.
+    pub fn decrypt_with_scratch<'a>(
+        &self,
+        iv: &[u8],
+        data: &[u8],
+        scratch: &'a mut Scratch,
+    ) -> Result<&'a [u8], AesError> {
+        metrics::observe(Operation::Decrypt, data.len(), || {
+            self.recipe.check_iv(iv)?;
+            self.recipe.check_length(data)?;
+            if self.recipe.padding != PaddingScheme::NoPadding && data.is_empty() {
+                return Err(AesError::InvalidDataLength);
+            }
+            let buffer = &mut scratch.buffer;
+            buffer.clear();
+            buffer.extend_from_slice(data);
+            let cipher = if self.recipe.block_decryption() {
+                Subkeys::new(&self.decryption_subkeys)
+            } else {
+                Subkeys::new(&self.encryption_subkeys)
+            };
+            apply_in_place(self.recipe.mode, false, &cipher, buffer, iv);
+            self.recipe.unpad(buffer)?;
+            Ok(&buffer[..])
+        })
+    }
+
+    /// Encrypt `data` into a new `Vec`, like [`Recipe::encrypt`] without scheduling the key.
+    ///
+    /// [`Recipe::encrypt`]: ../recipe/struct.Recipe.html#method.encrypt
+    pub fn encrypt(&self, iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+        let mut scratch = Scratch::new();
+        self.encrypt_with_scratch(iv, data, &mut scratch)?;
+        Ok(scratch.buffer)
+    }
+
+    /// Decrypt `data` into a new `Vec`, like [`Recipe::decrypt`] without scheduling the key.
+    ///
+    /// [`Recipe::decrypt`]: ../recipe/struct.Recipe.html#method.decrypt
+    pub fn decrypt(&self, iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+        let mut scratch = Scratch::new();
+        self.decrypt_with_scratch(iv, data, &mut scratch)?;
+        Ok(scratch.buffer)
+    }
+}
+
+/// Run a mode over `data` in place, keeping the chaining value on the stack.
+fn apply_in_place<C: BlockCipher128>(
+    mode: Mode,
+    encrypt: bool,
+    cipher: &C,
+    data: &mut [u8],
+    iv: &[u8],
+) {
+    let mut chain = [0u8; BLOCKSIZE_IN_BYTES];
+    if mode != Mode::Ecb {
+        chain.copy_from_slice(iv);
+    }
+    let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+    match (mode, encrypt) {
+        (Mode::Ecb, _) => {
+            for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                block.copy_from_slice(chunk);
+                if encrypt {
+                    cipher.encrypt_block(&mut block);
+                } else {
+                    cipher.decrypt_block(&mut block);
+                }
+                chunk.copy_from_slice(&block);
+            }
+        }
+        (Mode::Cbc, true) => {
+            for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    chain[j] ^= chunk[j];
+                }
+                cipher.encrypt_block(&mut chain);
+                chunk.copy_from_slice(&chain);
+            }
+        }
+        (Mode::Cbc, false) => {
+            for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                block.copy_from_slice(chunk);
+                cipher.decrypt_block(&mut block);
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    block[j] ^= chain[j];
+                }
+                chain.copy_from_slice(chunk);
+                chunk.copy_from_slice(&block);
+            }
+        }
+        (Mode::Pcbc, true) => {
+            for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    block[j] = chunk[j] ^ chain[j];
+                }
+                cipher.encrypt_block(&mut block);
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    chain[j] = chunk[j] ^ block[j];
+                }
+                chunk.copy_from_slice(&block);
+            }
+        }
+        (Mode::Pcbc, false) => {
+            for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+                block.copy_from_slice(chunk);
+                cipher.decrypt_block(&mut block);
+                for j in 0..BLOCKSIZE_IN_BYTES {
+                    block[j] ^= chain[j];
+                    chain[j] = chunk[j] ^ block[j];
+                }
+                chunk.copy_from_slice(&block);
+            }
+        }
+        (Mode::Cfb, _) => {
+            for chunk in data.chunks_mut(BLOCKSIZE_IN_BYTES) {
+                block = chain;
+                cipher.encrypt_block(&mut block);
+                if !encrypt {
+                    chain[..chunk.len()].copy_from_slice(chunk);
+                }
+                for (byte, key_byte) in chunk.iter_mut().zip(block.iter()) {
+                    *byte ^= key_byte;
+                }
+                if encrypt {
+                    chain[..chunk.len()].copy_from_slice(chunk);
+                }
+            }
+        }
+        (Mode::Cfb8, _) => {
+            for byte in data.iter_mut() {
+                block = chain;
+                cipher.encrypt_block(&mut block);
+                let cipher_byte = if encrypt { *byte ^ block[0] } else { *byte };
+                *byte ^= block[0];
+                chain.copy_within(1.., 0);
+                chain[BLOCKSIZE_IN_BYTES - 1] = cipher_byte;
+            }
+        }
+        (Mode::Ofb, _) => {
+            for chunk in data.chunks_mut(BLOCKSIZE_IN_BYTES) {
+                cipher.encrypt_block(&mut chain);
+                for (byte, key_byte) in chunk.iter_mut().zip(chain.iter()) {
+                    *byte ^= key_byte;
+                }
+            }
+        }
+        (Mode::Ctr, _) => {
+            for chunk in data.chunks_mut(BLOCKSIZE_IN_BYTES) {
+                block = chain;
+                cipher.encrypt_block(&mut block);
+                for (byte, key_byte) in chunk.iter_mut().zip(block.iter()) {
+                    *byte ^= key_byte;
+                }
+                chain = u128::from_be_bytes(chain).wrapping_add(1).to_be_bytes();
+            }
+        }
+    }
+}
+
+impl FromStr for Recipe {
+    type Err = AesError;
+
+    fn from_str(s: &str) -> Result<Self, AesError> {
+        let upper = s.trim().to_ascii_uppercase();
+        let parts: Vec<&str> = upper.split('/').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(AesError::InvalidParameter);
+        }
+        let key_bits = match parts[0] {
+            "AES-128" | "AES128" => 128,
+            "AES-192" | "AES192" => 192,
+            "AES-256" | "AES256" => 256,
+            _ => return Err(AesError::InvalidParameter),
+        };
+        let mode = match parts[1] {
+            "ECB" => Mode::Ecb,
+            "CBC" => Mode::Cbc,
+            "PCBC" => Mode::Pcbc,
+            "CFB" | "CFB128" => Mode::Cfb,
+            "CFB8" => Mode::Cfb8,
+            "OFB" => Mode::Ofb,
+            "CTR" => Mode::Ctr,
+            "GCM" | "CCM" | "OCB" | "SIV" | "GCM-SIV" | "XTS" | "EAX" => {
+                return Err(AesError::Unsupported)
+            }
+            _ => return Err(AesError::InvalidParameter),
+        };
+        let block_mode = matches!(mode, Mode::Ecb | Mode::Cbc | Mode::Pcbc);
+        let padding = match parts.get(2) {
+            None if block_mode => PaddingScheme::Pkcs7,
+            None => PaddingScheme::NoPadding,
+            Some(&"PKCS5PADDING") | Some(&"PKCS7PADDING") => PaddingScheme::Pkcs7,
+            Some(padding) => padding.parse()?,
+        };
+        if !block_mode && padding != PaddingScheme::NoPadding {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(Self {
+            key_bits,
+            mode,
+            padding,
+        })
+    }
+}
+
+impl fmt::Display for Recipe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match self.mode {
+            Mode::Ecb => "ECB",
+            Mode::Cbc => "CBC",
+            Mode::Pcbc => "PCBC",
+            Mode::Cfb => "CFB",
+            Mode::Cfb8 => "CFB8",
+            Mode::Ofb => "OFB",
+            Mode::Ctr => "CTR",
+        };
+        match self.padding {
+            PaddingScheme::NoPadding => write!(f, "AES-{}/{}/NoPadding", self.key_bits, mode),
+            padding => write!(f, "AES-{}/{}/{}", self.key_bits, mode, padding),
+        }
+    }
+}
+
+impl_redacted_debug!(Scratch, KeyedRecipe);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        let recipe: Recipe = "aes256/ctr".parse().unwrap();
+        assert_eq!(recipe.key_bits(), 256);
+        assert_eq!(recipe.mode(), Mode::Ctr);
+        assert_eq!(recipe.padding(), PaddingScheme::NoPadding);
+        assert_eq!(recipe.to_string(), "AES-256/CTR/NoPadding");
+        assert_eq!(recipe.to_string().parse::<Recipe>(), Ok(recipe));
+        let recipe: Recipe = " AES-192/ECB/PKCS5Padding ".parse().unwrap();
+        assert_eq!(recipe.padding(), PaddingScheme::Pkcs7);
+        assert_eq!(
+            "AES-128/CBC".parse::<Recipe>().unwrap().padding(),
+            PaddingScheme::Pkcs7
+        );
+        for (text, padding) in [
+            ("AES-128/CBC/ISO7816", PaddingScheme::Iso7816),
+            ("AES-128/PCBC/zeros", PaddingScheme::Zeros),
+        ] {
+            let recipe: Recipe = text.parse().unwrap();
+            assert_eq!(recipe.padding(), padding);
+            assert_eq!(recipe.to_string().parse::<Recipe>(), Ok(recipe));
+            let cipher = recipe
+                .encrypt(&[7u8; 16], &[9u8; 16], b"odd length")
+                .unwrap();
+            assert_eq!(cipher.len(), 16);
+            assert_eq!(
+                recipe.decrypt(&[7u8; 16], &[9u8; 16], &cipher).unwrap(),
+                b"odd length"
+            );
+        }
+        assert_eq!(
+            "AES-128/CTR/ISO7816".parse::<Recipe>(),
+            Err(AesError::InvalidParameter)
+        );
+        for wrong in [
+            "",
+            "AES",
+            "AES-128",
+            "DES/CBC",
+            "AES-128/XYZ",
+            "AES-128/CBC/XYZ/1",
+        ] {
+            assert_eq!(wrong.parse::<Recipe>(), Err(AesError::InvalidParameter));
+        }
+        assert_eq!("aes-128/xts".parse::<Recipe>(), Err(AesError::Unsupported));
+    }
+
+    #[test]
+    fn round_trip_works() {
+        let key = [0x33u8; 24];
+        let iv = [0x44u8; 16];
+        let data: Vec<u8> = (0..45).collect();
+        for mode in ["ECB", "CBC", "PCBC", "CFB", "CFB8", "OFB", "CTR"] {
+            let recipe: Recipe = format!("AES-192/{}", mode).parse().unwrap();
+            let cipher = recipe.encrypt(&key, &iv, &data).unwrap();
+            assert_eq!(
+                recipe.decrypt(&key, &iv, &cipher).unwrap(),
+                data,
+                "{}",
+                mode
+            );
+        }
+        let recipe: Recipe = "AES-192/CBC/ANSIX923".parse().unwrap();
+        let cipher = recipe.encrypt(&key, &iv, &data).unwrap();
+        assert_eq!(recipe.decrypt(&key, &iv, &cipher).unwrap(), data);
+    }
+
+    #[test]
+    fn errors_work() {
+        let recipe: Recipe = "AES-128/CBC/NoPadding".parse().unwrap();
+        assert_eq!(
+            recipe.encrypt(&[0u8; 24], &[0u8; 16], &[0u8; 16]),
+            Err(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            recipe.encrypt(&[0u8; 16], &[0u8; 16], &[0u8; 15]),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            recipe.encrypt(&[0u8; 16], &[0u8; 8], &[0u8; 16]),
+            Err(AesError::InvalidParameter)
+        );
+        // Decrypting with the wrong key breaks the padding.
+        let recipe: Recipe = "AES-128/CBC/ANSIX923".parse().unwrap();
+        let cipher = recipe.encrypt(&[1u8; 16], &[0u8; 16], b"data").unwrap();
+        assert_eq!(
+            recipe.decrypt(&[2u8; 16], &[0u8; 16], &cipher),
+            Err(AesError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn keyed_matches_recipe() {
+        let key: Vec<u8> = (0..32).collect();
+        let iv: Vec<u8> = (0x40..0x50).collect();
+        let recipes = [
+            "AES-256/ECB/PKCS7",
+            "AES-256/CBC/ANSIX923",
+            "AES-256/CBC/NoPadding",
+            "AES-256/PCBC/PKCS7",
+            "AES-256/CFB",
+            "AES-256/CFB8",
+            "AES-256/OFB",
+            "AES-256/CTR",
+        ];
+        let mut scratch = Scratch::new();
+        for recipe in recipes {
+            let recipe: Recipe = recipe.parse().unwrap();
+            let keyed = recipe.with_key(&key).unwrap();
+            assert_eq!(keyed.recipe(), recipe);
+            for length in [0, 1, 15, 16, 17, 48, 100] {
+                let data: Vec<u8> = (0..length).map(|i| (i * 7 + 3) as u8).collect();
+                let expected = recipe.encrypt(&key, &iv, &data);
+                let cipher = keyed.encrypt_with_scratch(&iv, &data, &mut scratch);
+                assert_eq!(
+                    cipher.map(|c| c.to_vec()),
+                    expected,
+                    "{} {}",
+                    recipe,
+                    length
+                );
+                let cipher = match expected {
+                    Ok(cipher) => cipher,
+                    Err(_) => continue,
+                };
+                let plain = keyed.decrypt_with_scratch(&iv, &cipher, &mut scratch);
+                assert_eq!(plain, Ok(&data[..]), "{} {}", recipe, length);
+                assert_eq!(
+                    keyed.decrypt(&iv, &cipher),
+                    recipe.decrypt(&key, &iv, &cipher)
+                );
+            }
+        }
+        assert_eq!(
+            "AES-128/CBC"
+                .parse::<Recipe>()
+                .unwrap()
+                .with_key(&key)
+                .err(),
+            Some(AesError::InvalidKeyLength)
+        );
+    }
+}
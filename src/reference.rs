@@ -0,0 +1,262 @@
+//! # reference
+//! `reference` is a deliberately simple, textbook implementation of AES (NIST.FIPS.197), only
+//! available with the `reference` feature.
+//!
+//! It follows the specification step by step on a byte state: `SubBytes`, `ShiftRows`,
+//! `MixColumns` and `AddRoundKey`, with the S-box computed from the inverse in GF(2^8) and the
+//! affine transformation instead of being copied from a table. It is slow and not constant-time,
+//! so it must not be used for real data. Its purpose is to be an oracle inside the crate: the
+//! tests check that the T-table implementation (and any future backend) agrees with it
+//! bit-exactly over random keys and blocks.
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+
+/// Multiply in GF(2^8) modulo `x^8 + x^4 + x^3 + x + 1`.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1B;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// The S-box entry of `x`: the inverse in GF(2^8) (`x^254`, with 0 mapped to 0), then the
+/// affine transformation.
+fn s_box_entry(x: u8) -> u8 {
+    let mut inverse = 1u8;
+    for _ in 0..254 {
+        inverse = gf_mul(inverse, x);
+    }
+    inverse
+        ^ inverse.rotate_left(1)
+        ^ inverse.rotate_left(2)
+        ^ inverse.rotate_left(3)
+        ^ inverse.rotate_left(4)
+        ^ 0x63
+}
+
+/// AES by the book, with any of the three key sizes.
+/// # Examples
+/// ```
+/// use aes_frast::reference::ReferenceAes;
+///
+/// // This example came from NIST.FIPS.197 Appendix C.1
+/// let key: Vec<u8> = (0..16).collect();
+/// let aes = ReferenceAes::new(&key).unwrap();
+/// let mut block = [
+///     0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+///     0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF,
+/// ];
+/// aes.encrypt_block(&mut block);
+/// assert_eq!(block, [0x69, 0xC4, 0xE0, 0xD8, 0x6A, 0x7B, 0x04, 0x30,
+///                    0xD8, 0xCD, 0xB7, 0x80, 0x70, 0xB4, 0xC5, 0x5A]);
+/// ```
+#[derive(Clone)]
+pub struct ReferenceAes {
+    round_keys: Vec<[u8; BLOCKSIZE_IN_BYTES]>,
+    s_box: [u8; 256],
+    inverse_s_box: [u8; 256],
+}
+
+impl ReferenceAes {
+    /// Expand a key of 16, 24 or 32 bytes, or return `Err(AesError::InvalidKeyLength)`.
+    pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        let nk = match key.len() {
+            16 | 24 | 32 => key.len() / 4,
+            _ => return Err(AesError::InvalidKeyLength),
+        };
+        let nr = nk + 6;
+        let mut s_box = [0u8; 256];
+        let mut inverse_s_box = [0u8; 256];
+        for x in 0..=255u8 {
+            s_box[x as usize] = s_box_entry(x);
+            inverse_s_box[s_box[x as usize] as usize] = x;
+        }
+
+        // KeyExpansion, FIPS.197 section 5.2
+        let mut words: Vec<[u8; 4]> = key
+            .chunks_exact(4)
+            .map(|w| [w[0], w[1], w[2], w[3]])
+            .collect();
+        let mut rcon = 1u8;
+        for i in nk..(4 * (nr + 1)) {
+            let mut temp = words[i - 1];
+            if i % nk == 0 {
+                temp.rotate_left(1);
+                temp = temp.map(|b| s_box[b as usize]);
+                temp[0] ^= rcon;
+                rcon = gf_mul(rcon, 2);
+            } else if nk > 6 && i % nk == 4 {
+                temp = temp.map(|b| s_box[b as usize]);
+            }
+            let previous = words[i - nk];
+            words.push(core::array::from_fn(|j| previous[j] ^ temp[j]));
+        }
+        let round_keys = words
+            .chunks_exact(4)
+            .map(|w| core::array::from_fn(|i| w[i / 4][i % 4]))
+            .collect();
+        Ok(Self {
+            round_keys,
+            s_box,
+            inverse_s_box,
+        })
+    }
+
+    /// **Encrypt** a block in place, FIPS.197 section 5.1.
+    pub fn encrypt_block(&self, state: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        let nr = self.round_keys.len() - 1;
+        add_round_key(state, &self.round_keys[0]);
+        for round in 1..=nr {
+            for byte in state.iter_mut() {
+                *byte = self.s_box[*byte as usize];
+            }
+            shift_rows(state);
+            if round != nr {
+                mix_columns(state, [2, 3, 1, 1]);
+            }
+            add_round_key(state, &self.round_keys[round]);
+        }
+    }
+
+    /// **Decrypt** a block in place, FIPS.197 section 5.3.
+    pub fn decrypt_block(&self, state: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        let nr = self.round_keys.len() - 1;
+        add_round_key(state, &self.round_keys[nr]);
+        for round in (0..nr).rev() {
+            inverse_shift_rows(state);
+            for byte in state.iter_mut() {
+                *byte = self.inverse_s_box[*byte as usize];
+            }
+            add_round_key(state, &self.round_keys[round]);
+            if round != 0 {
+                mix_columns(state, [14, 11, 13, 9]);
+            }
+        }
+    }
+}
+
+impl BlockCipher128 for ReferenceAes {
+    fn encrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        ReferenceAes::encrypt_block(self, block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+        ReferenceAes::decrypt_block(self, block);
+    }
+}
+
+// The state is in the order of the input bytes, so the byte in row `r` and column `c` is
+// `state[r + 4 * c]`.
+
+fn add_round_key(state: &mut [u8; BLOCKSIZE_IN_BYTES], round_key: &[u8; BLOCKSIZE_IN_BYTES]) {
+    for (byte, key_byte) in state.iter_mut().zip(round_key.iter()) {
+        *byte ^= key_byte;
+    }
+}
+
+fn shift_rows(state: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+    let old = *state;
+    for r in 0..4 {
+        for c in 0..4 {
+            state[r + 4 * c] = old[r + 4 * ((c + r) % 4)];
+        }
+    }
+}
+
+fn inverse_shift_rows(state: &mut [u8; BLOCKSIZE_IN_BYTES]) {
+    let old = *state;
+    for r in 0..4 {
+        for c in 0..4 {
+            state[r + 4 * ((c + r) % 4)] = old[r + 4 * c];
+        }
+    }
+}
+
+/// Multiply each column by the circulant matrix whose first row is `row`: `[2, 3, 1, 1]` for
+/// `MixColumns` and `[14, 11, 13, 9]` for `InvMixColumns`.
+fn mix_columns(state: &mut [u8; BLOCKSIZE_IN_BYTES], row: [u8; 4]) {
+    for column in state.chunks_exact_mut(4) {
+        let old = [column[0], column[1], column[2], column[3]];
+        for r in 0..4 {
+            column[r] = (0..4).fold(0, |sum, j| sum ^ gf_mul(row[(j + 4 - r) % 4], old[j]));
+        }
+    }
+}
+
+impl_redacted_debug!(ReferenceAes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_with_operation_mode::{cbc_dec_with, cbc_enc_with};
+    use crate::AesAny;
+
+    /// xorshift64*, enough for test inputs.
+    fn next(state: &mut u64) -> u8 {
+        *state ^= *state >> 12;
+        *state ^= *state << 25;
+        *state ^= *state >> 27;
+        (state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    #[test]
+    fn s_box_matches_fips197() {
+        let aes = ReferenceAes::new(&[0u8; 16]).unwrap();
+        assert_eq!(aes.s_box[0x00], 0x63);
+        assert_eq!(aes.s_box[0x53], 0xED);
+        assert_eq!(aes.inverse_s_box[0xED], 0x53);
+    }
+
+    #[test]
+    fn t_tables_match_reference() {
+        let mut state = 0x0123_4567_89AB_CDEFu64;
+        for key_bytes in [16, 24, 32] {
+            for _ in 0..200 {
+                let key: Vec<u8> = (0..key_bytes).map(|_| next(&mut state)).collect();
+                let block: [u8; 16] = core::array::from_fn(|_| next(&mut state));
+                let reference = ReferenceAes::new(&key).unwrap();
+                let fast = AesAny::new(&key).unwrap();
+
+                let mut expected = block;
+                reference.encrypt_block(&mut expected);
+                let mut actual = block;
+                fast.encrypt_block(&mut actual);
+                assert_eq!(actual, expected);
+
+                reference.decrypt_block(&mut expected);
+                fast.decrypt_block(&mut actual);
+                assert_eq!(expected, block);
+                assert_eq!(actual, block);
+
+                let expected_round_keys: Vec<[u8; 16]> = reference.round_keys.clone();
+                assert_eq!(fast.round_keys(), expected_round_keys);
+            }
+        }
+    }
+
+    #[test]
+    fn modes_match_reference() {
+        let key: Vec<u8> = (0..32).collect();
+        let iv = [0x24u8; 16];
+        let plain: Vec<u8> = (0..160).map(|i| (i * 7) as u8).collect();
+        let reference = ReferenceAes::new(&key).unwrap();
+        let fast = AesAny::new(&key).unwrap();
+        let mut expected = vec![0u8; 160];
+        let mut actual = vec![0u8; 160];
+        cbc_enc_with(&reference, &plain, &mut expected, &iv);
+        cbc_enc_with(&fast, &plain, &mut actual, &iv);
+        assert_eq!(actual, expected);
+        cbc_dec_with(&reference, &expected, &mut actual, &iv);
+        assert_eq!(actual, plain);
+    }
+}
@@ -0,0 +1,156 @@
+//! # rng
+//! `rng` provides the [`Rng`] trait, the only source of randomness of this library.
+//!
+//! Everything that needs random bytes, like generating IVs and keys or the ISO 10126 padding,
+//! takes an [`Rng`] as a parameter. So a deterministic generator can be injected in tests, and a
+//! hardware TRNG on embedded boards. With the `getrandom` feature, [`OsRng`] reads the random
+//! number generator of the OS, and [`os_iv`] and [`os_nonce`] take an IV or a nonce from it.
+//!
+//! [`Rng`]: ../rng/trait.Rng.html
+//! [`OsRng`]: ../rng/struct.OsRng.html
+//! [`os_iv`]: ../rng/fn.os_iv.html
+//! [`os_nonce`]: ../rng/fn.os_nonce.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+
+/// A source of random bytes.
+///
+/// The bytes used for keys and IVs must be unpredictable, so implement this trait with a
+/// cryptographically secure generator unless it is only for testing.
+/// # Examples
+/// ```
+/// use aes_frast::rng::{generate_iv, Rng};
+///
+/// // A deterministic generator for testing. It is NOT random at all.
+/// struct Counter(u8);
+///
+/// impl Rng for Counter {
+///     fn fill_bytes(&mut self, dest: &mut [u8]) {
+///         for byte in dest.iter_mut() {
+///             *byte = self.0;
+///             self.0 = self.0.wrapping_add(1);
+///         }
+///     }
+/// }
+///
+/// let mut rng = Counter(0);
+/// let iv = generate_iv(&mut rng);
+/// assert_eq!(iv[0], 0x00);
+/// assert_eq!(iv[15], 0x0F);
+/// ```
+pub trait Rng {
+    /// Fill `dest` with random bytes.
+    fn fill_bytes(&mut self, dest: &mut [u8]);
+}
+
+/// The random number generator of the OS, through the `getrandom` crate.
+///
+/// Only available with the `getrandom` feature.
+/// # Panics
+/// [`fill_bytes`] panics if the OS fails to provide random bytes.
+///
+/// [`fill_bytes`]: ../rng/trait.Rng.html#tymethod.fill_bytes
+#[cfg(feature = "getrandom")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRng;
+
+#[cfg(feature = "getrandom")]
+impl Rng for OsRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        getrandom::getrandom(dest).expect("The random number generator of the OS failed.");
+    }
+}
+
+/// Generate a random IV (or initial counter block).
+///
+/// * *parameter* `rng`: the source of random bytes.
+/// * *return* : the IV.
+pub fn generate_iv<R: Rng + ?Sized>(rng: &mut R) -> [u8; BLOCKSIZE_IN_BYTES] {
+    let mut iv = [0u8; BLOCKSIZE_IN_BYTES];
+    rng.fill_bytes(&mut iv);
+    iv
+}
+
+/// Generate a random nonce of any length, e.g. 12 bytes for GCM.
+///
+/// * *parameter* `rng`: the source of random bytes.
+/// * *parameter* `len`: the length of the nonce.
+/// * *return* : the nonce.
+pub fn generate_nonce<R: Rng + ?Sized>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut nonce = vec![0u8; len];
+    rng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Generate a random IV (or initial counter block) with [`OsRng`].
+///
+/// Only available with the `getrandom` feature. Prefer it to an ad-hoc generator: a fresh
+/// random IV for each message is what CBC and CFB need.
+/// # Examples
+/// ```
+/// use aes_frast::rng::os_iv;
+///
+/// assert_ne!(os_iv(), os_iv());
+/// ```
+/// # Panics
+/// This function panics if the OS fails to provide random bytes.
+///
+/// [`OsRng`]: ../rng/struct.OsRng.html
+#[cfg(feature = "getrandom")]
+pub fn os_iv() -> [u8; BLOCKSIZE_IN_BYTES] {
+    generate_iv(&mut OsRng)
+}
+
+/// Generate a random nonce of `len` bytes with [`OsRng`], e.g. 12 bytes for GCM.
+///
+/// Only available with the `getrandom` feature. Random 96-bit nonces may collide after about
+/// 2<sup>32</sup> messages under one key, so count the messages for long-lived keys.
+/// # Examples
+/// ```
+/// use aes_frast::rng::os_nonce;
+///
+/// let nonce = os_nonce(12);
+/// assert_eq!(nonce.len(), 12);
+/// ```
+/// # Panics
+/// This function panics if the OS fails to provide random bytes.
+///
+/// [`OsRng`]: ../rng/struct.OsRng.html
+#[cfg(feature = "getrandom")]
+pub fn os_nonce(len: usize) -> Vec<u8> {
+    generate_nonce(&mut OsRng, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(u8);
+
+    impl Rng for Counter {
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest.iter_mut() {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_works() {
+        let mut rng = Counter(0);
+        let iv = generate_iv(&mut rng);
+        assert_eq!(iv, core::array::from_fn(|i| i as u8));
+        assert_eq!(generate_nonce(&mut rng, 12), (16..28).collect::<Vec<u8>>());
+        // Works through a trait object too.
+        let dyn_rng: &mut dyn Rng = &mut rng;
+        assert_eq!(generate_iv(dyn_rng)[0], 28);
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn os_rng_works() {
+        let a = generate_iv(&mut OsRng);
+        let b = generate_iv(&mut OsRng);
+        assert_ne!(a, b);
+    }
+}
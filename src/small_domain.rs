@@ -0,0 +1,200 @@
+//! # small_domain
+//! `small_domain` encrypts integers in an arbitrary range `[0, N)` to integers in the same range,
+//! e.g. to shuffle or hide database IDs and ticket numbers without changing their size.
+//!
+//! [`IntegerPermutation`] is a balanced Feistel network whose round function is AES, over the
+//! smallest domain of an even number of bits which holds `N`. A value which leaves `[0, N)` is
+//! encrypted again (cycle walking) until it comes back. Both steps are permutations, so every
+//! value of `[0, N)` has exactly one image: there is no modulo reduction and so no bias. As the
+//! Feistel domain is less than `4 * N`, the expected number of walks is less than 4.
+//!
+//! Unlike FF1 and FF3-1 (see the [`alphabet`] mod), the input is a plain integer rather than a
+//! numeral string, and `N` does not have to be a power of a radix.
+//!
+//! **Attention!** Like any deterministic cipher, equal inputs give equal outputs, and a small `N`
+//! can be enumerated by anyone who can query the permutation. The running time depends on the
+//! number of walks, which leaks a little about the value.
+//!
+//! [`IntegerPermutation`]: ../small_domain/struct.IntegerPermutation.html
+//! [`alphabet`]: ../alphabet/index.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+
+/// The number of Feistel rounds.
+pub const ROUNDS: u8 = 10;
+
+/// A keyed pseudorandom permutation of the integers `[0, N)`.
+/// # Examples
+/// ```
+/// use aes_frast::small_domain::IntegerPermutation;
+/// use aes_frast::Aes128;
+///
+/// // Ticket numbers from 0 to 999_999.
+/// let tickets = IntegerPermutation::new(Aes128::new(&[0x2Bu8; 16]), 1_000_000).unwrap();
+///
+/// let hidden = tickets.encrypt(42).unwrap();
+/// assert!(hidden < 1_000_000);
+/// assert_eq!(tickets.decrypt(hidden).unwrap(), 42);
+/// assert!(tickets.encrypt(1_000_000).is_err());
+/// ```
+#[derive(Clone)]
+pub struct IntegerPermutation<C> {
+    cipher: C,
+    domain: u64,
+    half_bits: u32,
+}
+
+impl<C: BlockCipher128> IntegerPermutation<C> {
+    /// Create the permutation of `[0, domain)`.
+    ///
+    /// * *parameter* `cipher`: the block cipher of the round function.
+    /// * *parameter* `domain`: `N`, the number of integers, which must not be 0.
+    /// * *return* : the permutation, or `Err(AesError::InvalidParameter)` if `domain` is 0.
+    pub fn new(cipher: C, domain: u64) -> Result<Self, AesError> {
+        if domain == 0 {
+            return Err(AesError::InvalidParameter);
+        }
+        // The bits of the largest value, at least 2, and a half of them rounded up.
+        let bits = (u64::BITS - (domain - 1).leading_zeros()).max(2);
+        Ok(Self {
+            cipher,
+            domain,
+            half_bits: bits.div_ceil(2),
+        })
+    }
+
+    /// The block cipher of the round function.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// `N`, the number of integers.
+    pub fn domain(&self) -> u64 {
+        self.domain
+    }
+
+    /// **Encrypt** `value`.
+    ///
+    /// * *parameter* `value`: an integer less than `N`.
+    /// * *return* : the image, which is less than `N` too, or `Err(AesError::InvalidParameter)`
+    ///   if `value` is not less than `N`.
+    pub fn encrypt(&self, value: u64) -> Result<u64, AesError> {
+        self.walk(value, |x| self.feistel(x, true))
+    }
+
+    /// **Decrypt** `value`, the reverse of [`encrypt`].
+    ///
+    /// [`encrypt`]: ../small_domain/struct.IntegerPermutation.html#method.encrypt
+    pub fn decrypt(&self, value: u64) -> Result<u64, AesError> {
+        self.walk(value, |x| self.feistel(x, false))
+    }
+
+    fn walk<F: Fn(u64) -> u64>(&self, value: u64, step: F) -> Result<u64, AesError> {
+        if value >= self.domain {
+            return Err(AesError::InvalidParameter);
+        }
+        let mut x = step(value);
+        while x >= self.domain {
+            x = step(x);
+        }
+        Ok(x)
+    }
+
+    fn half_mask(&self) -> u64 {
+        (1u64 << self.half_bits) - 1
+    }
+
+    /// The round function: AES of the round number, the domain and the right half, truncated to
+    /// the bits of a half.
+    fn round_function(&self, round: u8, right: u64) -> u64 {
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        block[0] = round;
+        block[1] = self.half_bits as u8;
+        block[2..10].copy_from_slice(&self.domain.to_be_bytes());
+        block[12..].copy_from_slice(&(right as u32).to_be_bytes());
+        self.cipher.encrypt_block(&mut block);
+        u64::from(u32::from_be_bytes(block[..4].try_into().unwrap())) & self.half_mask()
+    }
+
+    fn feistel(&self, x: u64, encrypt: bool) -> u64 {
+        let mut left = x >> self.half_bits;
+        let mut right = x & self.half_mask();
+        if encrypt {
+            for round in 0..ROUNDS {
+                (left, right) = (right, left ^ self.round_function(round, right));
+            }
+        } else {
+            for round in (0..ROUNDS).rev() {
+                (left, right) = (right ^ self.round_function(round, left), left);
+            }
+        }
+        (left << self.half_bits) | right
+    }
+}
+
+impl_redacted_debug!(IntegerPermutation<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Aes128;
+
+    fn check_bijection(domain: u64) {
+        let permutation = IntegerPermutation::new(Aes128::new(&[0x5Au8; 16]), domain).unwrap();
+        let mut seen = vec![false; domain as usize];
+        for value in 0..domain {
+            let image = permutation.encrypt(value).unwrap();
+            assert!(!seen[image as usize], "{} is hit twice", image);
+            seen[image as usize] = true;
+            assert_eq!(permutation.decrypt(image).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn bijections() {
+        for domain in [1, 2, 3, 4, 5, 17, 1000, 65536, 65537] {
+            check_bijection(domain);
+        }
+    }
+
+    #[test]
+    fn large_domains() {
+        for domain in [1u64 << 32, (1u64 << 63) + 1, u64::MAX] {
+            let permutation = IntegerPermutation::new(Aes128::new(&[0x11u8; 16]), domain).unwrap();
+            for value in [0, 1, domain / 2, domain - 1] {
+                let image = permutation.encrypt(value).unwrap();
+                assert!(image < domain);
+                assert_eq!(permutation.decrypt(image).unwrap(), value);
+            }
+        }
+    }
+
+    #[test]
+    fn keys_and_domains_differ() {
+        let a = IntegerPermutation::new(Aes128::new(&[0x01u8; 16]), 1000).unwrap();
+        let b = IntegerPermutation::new(Aes128::new(&[0x02u8; 16]), 1000).unwrap();
+        let c = IntegerPermutation::new(Aes128::new(&[0x01u8; 16]), 999).unwrap();
+        let images = |p: &IntegerPermutation<Aes128>| -> Vec<u64> {
+            (0..999).map(|v| p.encrypt(v).unwrap()).collect()
+        };
+        assert_ne!(images(&a), images(&b));
+        assert_ne!(images(&a), images(&c));
+        // Not the identity, nor a rotation.
+        assert!(images(&a).windows(2).any(|w| w[1] != w[0] + 1));
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert_eq!(
+            IntegerPermutation::new(Aes128::new(&[0u8; 16]), 0).err(),
+            Some(AesError::InvalidParameter)
+        );
+        let permutation = IntegerPermutation::new(Aes128::new(&[0u8; 16]), 10).unwrap();
+        assert_eq!(permutation.encrypt(10), Err(AesError::InvalidParameter));
+        assert_eq!(
+            permutation.decrypt(u64::MAX),
+            Err(AesError::InvalidParameter)
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! # stream
+//! `stream` provides STREAM, the chunked authenticated encryption of Hoang, Reyhanitabar, Rogaway
+//! and Vizár ("Online Authenticated-Encryption and its Nonce-Reuse Misuse-Resistance"), over
+//! AES-CCM.
+//!
+//! A long message is split into chunks, each sealed on its own with CCM (16-byte tag). The nonce
+//! of a chunk is a random 7-byte prefix, the 32-bit big-endian index of the chunk and a last-chunk
+//! flag (1 for the last chunk, 0 otherwise). So a chunk can't be dropped, reordered or moved into
+//! another stream, and a stream cut after any chunk doesn't decrypt as a complete one: only the
+//! flagged chunk is accepted by [`StreamDecryptor::decrypt_last`].
+//!
+//! [`StreamEncryptor`] and [`StreamDecryptor`] process one chunk at a time, e.g. while reading a
+//! file, and [`seal_chunks`] and [`open_chunks`] a whole message with a fixed chunk length.
+//!
+//! **Attention!** A prefix must never be used twice with the same key. A chunk is at most
+//! `2^24 - 1` bytes long, and a stream at most `2^32` chunks long.
+//!
+//! [`StreamEncryptor`]: ../stream/struct.StreamEncryptor.html
+//! [`StreamDecryptor`]: ../stream/struct.StreamDecryptor.html
+//! [`StreamDecryptor::decrypt_last`]: ../stream/struct.StreamDecryptor.html#method.decrypt_last
+//! [`seal_chunks`]: ../stream/fn.seal_chunks.html
+//! [`open_chunks`]: ../stream/fn.open_chunks.html
+use super::block_cipher::BlockCipher128;
+use super::ccm::Ccm;
+use super::error::AesError;
+use super::tag::TagPlacement;
+
+/// The length in bytes of the nonce prefix.
+pub const PREFIX_LEN: usize = 7;
+/// The length in bytes of the tag of each chunk.
+pub const TAG_LEN: usize = 16;
+/// The maximum length in bytes of a chunk, before sealing.
+pub const MAX_CHUNK_LEN: usize = (1 << 24) - 1;
+
+const NONCE_LEN: usize = PREFIX_LEN + 5;
+
+/// The prefix and the index of the next chunk, which is `None` once every index has been used.
+#[derive(Clone)]
+struct Position {
+    prefix: [u8; PREFIX_LEN],
+    index: Option<u32>,
+}
+
+impl Position {
+    /// The nonce of the next chunk, moving on to the following one.
+    fn next_nonce(&mut self, last: bool) -> Result<[u8; NONCE_LEN], AesError> {
+        let index = self.index.ok_or(AesError::SequenceOverflow)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..PREFIX_LEN].copy_from_slice(&self.prefix);
+        nonce[PREFIX_LEN..(NONCE_LEN - 1)].copy_from_slice(&index.to_be_bytes());
+        nonce[NONCE_LEN - 1] = u8::from(last);
+        self.index = index.checked_add(1);
+        Ok(nonce)
+    }
+}
+
+fn new_ccm<C: BlockCipher128>(cipher: C) -> Ccm<C> {
+    Ccm::new(cipher, NONCE_LEN, TAG_LEN).unwrap()
+}
+
+/// The sealing side of a STREAM.
+/// # Examples
+/// ```
+/// use aes_frast::stream::{StreamDecryptor, StreamEncryptor};
+/// use aes_frast::Aes256;
+///
+/// let key = [0x42u8; 32];
+/// let prefix = [0x07u8; 7]; // random, and never reused with this key
+///
+/// let mut encryptor = StreamEncryptor::new(Aes256::new(&key), &prefix);
+/// let first = encryptor.encrypt_next(b"", b"the first chunk").unwrap();
+/// let last = encryptor.encrypt_last(b"", b"the last chunk").unwrap();
+///
+/// let mut decryptor = StreamDecryptor::new(Aes256::new(&key), &prefix);
+/// assert_eq!(decryptor.decrypt_next(b"", &first).unwrap(), b"the first chunk");
+/// // A truncated stream is detected: the first chunk is not the last one.
+/// assert!(decryptor.clone().decrypt_last(b"", &first).is_err());
+/// assert_eq!(decryptor.decrypt_last(b"", &last).unwrap(), b"the last chunk");
+/// ```
+#[derive(Clone)]
+pub struct StreamEncryptor<C> {
+    ccm: Ccm<C>,
+    position: Position,
+    placement: TagPlacement,
+}
+
+impl<C: BlockCipher128> StreamEncryptor<C> {
+    /// Start a stream.
+    ///
+    /// * *parameter* `cipher`: the block cipher.
+    /// * *parameter* `prefix`: the nonce prefix of the stream, which must be unique per key.
+    pub fn new(cipher: C, prefix: &[u8; PREFIX_LEN]) -> Self {
+        Self {
+            ccm: new_ccm(cipher),
+            position: Position {
+                prefix: *prefix,
+                index: Some(0),
+            },
+            placement: TagPlacement::Appended,
+        }
+    }
+
+    /// Put the tag of each chunk where `placement` says, after the cipher by default. The other
+    /// side of the stream must use the same placement.
+    pub fn with_tag_placement(mut self, placement: TagPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// **Encrypt** a chunk which is not the last one.
+    ///
+    /// * *parameter* `aad`: the associated data of the chunk, which may be empty.
+    /// * *parameter* `chunk`: the plain of the chunk.
+    /// * *return* : the cipher followed by the tag, or `Err(AesError::InvalidDataLength)` for a
+    ///   too long chunk, or `Err(AesError::SequenceOverflow)` after `2^32` chunks.
+    pub fn encrypt_next(&mut self, aad: &[u8], chunk: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.seal(aad, chunk, false)
+    }
+
+    /// **Encrypt** the last chunk, which ends the stream.
+    ///
+    /// The errors are those of [`encrypt_next`].
+    ///
+    /// [`encrypt_next`]: ../stream/struct.StreamEncryptor.html#method.encrypt_next
+    pub fn encrypt_last(mut self, aad: &[u8], chunk: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.seal(aad, chunk, true)
+    }
+
+    fn seal(&mut self, aad: &[u8], chunk: &[u8], last: bool) -> Result<Vec<u8>, AesError> {
+        if chunk.len() > MAX_CHUNK_LEN {
+            return Err(AesError::InvalidDataLength);
+        }
+        let nonce = self.position.next_nonce(last)?;
+        self.ccm
+            .seal_with_layout(&nonce, aad, chunk, self.placement)
+    }
+}
+
+/// The opening side of a STREAM.
+///
+/// Please refer to [`StreamEncryptor`] for an example. The stream is complete only when
+/// [`decrypt_last`] has succeeded; a stream which ends before is truncated.
+///
+/// [`StreamEncryptor`]: ../stream/struct.StreamEncryptor.html
+/// [`decrypt_last`]: ../stream/struct.StreamDecryptor.html#method.decrypt_last
+#[derive(Clone)]
+pub struct StreamDecryptor<C> {
+    ccm: Ccm<C>,
+    position: Position,
+    placement: TagPlacement,
+}
+
+impl<C: BlockCipher128> StreamDecryptor<C> {
+    /// Start a stream, with the cipher and the prefix of the encryption.
+    pub fn new(cipher: C, prefix: &[u8; PREFIX_LEN]) -> Self {
+        Self {
+            ccm: new_ccm(cipher),
+            position: Position {
+                prefix: *prefix,
+                index: Some(0),
+            },
+            placement: TagPlacement::Appended,
+        }
+    }
+
+    /// Put the tag of each chunk where `placement` says, after the cipher by default. The other
+    /// side of the stream must use the same placement.
+    pub fn with_tag_placement(mut self, placement: TagPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// **Decrypt** a chunk which is not the last one.
+    ///
+    /// * *parameter* `aad`: the associated data of the chunk.
+    /// * *parameter* `sealed`: the cipher followed by the tag.
+    /// * *return* : the plain, or `Err(AesError::TagMismatch)` if the chunk is not authentic or
+    ///   not the next one, or `Err(AesError::InvalidDataLength)` for a wrong length. The stream
+    ///   only moves on to the next chunk on success.
+    pub fn decrypt_next(&mut self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.open(aad, sealed, false)
+    }
+
+    /// **Decrypt** the last chunk, which ends the stream.
+    ///
+    /// The errors are those of [`decrypt_next`].
+    ///
+    /// [`decrypt_next`]: ../stream/struct.StreamDecryptor.html#method.decrypt_next
+    pub fn decrypt_last(mut self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AesError> {
+        self.open(aad, sealed, true)
+    }
+
+    fn open(&mut self, aad: &[u8], sealed: &[u8], last: bool) -> Result<Vec<u8>, AesError> {
+        if sealed.len() > MAX_CHUNK_LEN + TAG_LEN {
+            return Err(AesError::InvalidDataLength);
+        }
+        let mut next = self.position.clone();
+        let nonce = next.next_nonce(last)?;
+        let plain = self
+            .ccm
+            .open_with_layout(&nonce, aad, sealed, self.placement)?;
+        self.position = next;
+        Ok(plain)
+    }
+}
+
+/// **Encrypt** `plain` as a STREAM of chunks of `chunk_len` bytes, the last one being shorter or
+/// as long (or empty, for an empty `plain`).
+///
+/// * *parameter* `cipher`: the block cipher.
+/// * *parameter* `prefix`: the nonce prefix, which must be unique per key.
+/// * *parameter* `aad`: the associated data of every chunk.
+/// * *parameter* `plain`: the plain.
+/// * *parameter* `chunk_len`: the length of a chunk, from 1 to `2^24 - 1`.
+/// * *return* : the sealed chunks, one after the other, or `Err(AesError::InvalidParameter)` for
+///   a wrong `chunk_len`, or `Err(AesError::SequenceOverflow)` for more than `2^32` chunks.
+/// # Examples
+/// ```
+/// use aes_frast::stream::{open_chunks, seal_chunks, TAG_LEN};
+/// use aes_frast::Aes128;
+///
+/// let aes = Aes128::new(&[0x11u8; 16]);
+/// let prefix = [0x22u8; 7];
+/// let file = vec![0x33u8; 10_000];
+///
+/// let sealed = seal_chunks(&aes, &prefix, b"file.bin", &file, 4096).unwrap();
+/// assert_eq!(sealed.len(), file.len() + 3 * TAG_LEN);
+/// assert_eq!(open_chunks(&aes, &prefix, b"file.bin", &sealed, 4096).unwrap(), file);
+///
+/// // Dropping the last chunk is detected.
+/// let truncated = &sealed[..(2 * (4096 + TAG_LEN))];
+/// assert!(open_chunks(&aes, &prefix, b"file.bin", truncated, 4096).is_err());
+/// ```
+pub fn seal_chunks<C: BlockCipher128 + Clone>(
+    cipher: &C,
+    prefix: &[u8; PREFIX_LEN],
+    aad: &[u8],
+    plain: &[u8],
+    chunk_len: usize,
+) -> Result<Vec<u8>, AesError> {
+    if chunk_len == 0 || chunk_len > MAX_CHUNK_LEN {
+        return Err(AesError::InvalidParameter);
+    }
+    let mut encryptor = StreamEncryptor::new(cipher.clone(), prefix);
+    let last_start = plain.len().saturating_sub(1) / chunk_len * chunk_len;
+    let mut sealed = Vec::with_capacity(plain.len() + (last_start / chunk_len + 1) * TAG_LEN);
+    for chunk in plain[..last_start].chunks(chunk_len) {
+        sealed.extend_from_slice(&encryptor.encrypt_next(aad, chunk)?);
+    }
+    sealed.extend_from_slice(&encryptor.encrypt_last(aad, &plain[last_start..])?);
+    Ok(sealed)
+}
+
+/// **Decrypt** a STREAM sealed by [`seal_chunks`] with the same `chunk_len`.
+///
+/// * *return* : the plain, or `Err(AesError::TagMismatch)` if any chunk is not authentic, or if
+///   chunks are missing, reordered or appended. Wrong lengths give
+///   `Err(AesError::InvalidParameter)` (for `chunk_len`) or `Err(AesError::InvalidDataLength)`.
+///
+/// [`seal_chunks`]: ../stream/fn.seal_chunks.html
+pub fn open_chunks<C: BlockCipher128 + Clone>(
+    cipher: &C,
+    prefix: &[u8; PREFIX_LEN],
+    aad: &[u8],
+    sealed: &[u8],
+    chunk_len: usize,
+) -> Result<Vec<u8>, AesError> {
+    if chunk_len == 0 || chunk_len > MAX_CHUNK_LEN {
+        return Err(AesError::InvalidParameter);
+    }
+    if sealed.len() < TAG_LEN {
+        return Err(AesError::InvalidDataLength);
+    }
+    let sealed_len = chunk_len + TAG_LEN;
+    let mut decryptor = StreamDecryptor::new(cipher.clone(), prefix);
+    let last_start = (sealed.len() - 1) / sealed_len * sealed_len;
+    let mut plain = Vec::with_capacity(sealed.len());
+    for chunk in sealed[..last_start].chunks(sealed_len) {
+        plain.extend_from_slice(&decryptor.decrypt_next(aad, chunk)?);
+    }
+    plain.extend_from_slice(&decryptor.decrypt_last(aad, &sealed[last_start..])?);
+    Ok(plain)
+}
+
+impl_redacted_debug!(StreamEncryptor<C>, StreamDecryptor<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+    use crate::{Aes128, AesAny};
+
+    #[test]
+    fn chunks_are_ccm_with_stream_nonces() {
+        // The nonce of chunk `i` is `prefix || i || flag`; check the construction with CCM
+        // directly.
+        let aes = Aes128::new(&[0x5Cu8; 16]);
+        let prefix = [0xA5u8; PREFIX_LEN];
+        let sealed = seal_chunks(&aes, &prefix, b"ad", &[0x01u8; 40], 16).unwrap();
+        let ccm = Ccm::new(aes, 12, 16).unwrap();
+        let nonce = |index: u8, last: u8| {
+            let mut nonce = [0xA5u8; 12];
+            nonce[7..11].copy_from_slice(&[0, 0, 0, index]);
+            nonce[11] = last;
+            nonce
+        };
+        let mut expected = ccm.seal(&nonce(0, 0), b"ad", &[0x01u8; 16]).unwrap();
+        expected.extend(ccm.seal(&nonce(1, 0), b"ad", &[0x01u8; 16]).unwrap());
+        expected.extend(ccm.seal(&nonce(2, 1), b"ad", &[0x01u8; 8]).unwrap());
+        assert_eq!(hex(&sealed), hex(&expected));
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let aes = AesAny::new(&[0x77u8; 24]).unwrap();
+        let prefix = [0x01u8; PREFIX_LEN];
+        let plain: Vec<u8> = (0..100).collect();
+        for len in [0, 1, 31, 32, 33, 64, 100] {
+            let sealed = seal_chunks(&aes, &prefix, b"", &plain[..len], 32).unwrap();
+            let chunks = len.saturating_sub(1) / 32 + 1;
+            assert_eq!(sealed.len(), len + chunks * TAG_LEN, "{}", len);
+            let opened = open_chunks(&aes, &prefix, b"", &sealed, 32).unwrap();
+            assert_eq!(opened, &plain[..len]);
+        }
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let aes = Aes128::new(&[0x0Au8; 16]);
+        let prefix = [0x0Bu8; PREFIX_LEN];
+        let plain = [0x0Cu8; 90];
+        let sealed = seal_chunks(&aes, &prefix, b"", &plain, 30).unwrap();
+        let sealed_len = 30 + TAG_LEN;
+        // Swapped chunks.
+        let mut swapped = sealed.clone();
+        swapped[..sealed_len].copy_from_slice(&sealed[sealed_len..(2 * sealed_len)]);
+        swapped[sealed_len..(2 * sealed_len)].copy_from_slice(&sealed[..sealed_len]);
+        // Truncated after a chunk, and extended with a copy of the last chunk.
+        let truncated = &sealed[..(2 * sealed_len)];
+        let mut extended = sealed.clone();
+        extended.extend_from_slice(&sealed[(2 * sealed_len)..]);
+        let mut flipped = sealed.clone();
+        flipped[5] ^= 1;
+        for forged in [&swapped[..], truncated, &extended, &flipped] {
+            assert_eq!(
+                open_chunks(&aes, &prefix, b"", forged, 30),
+                Err(AesError::TagMismatch)
+            );
+        }
+        assert!(open_chunks(&aes, &[0u8; PREFIX_LEN], b"", &sealed, 30).is_err());
+        assert!(open_chunks(&aes, &prefix, b"x", &sealed, 30).is_err());
+        assert_eq!(
+            open_chunks(&aes, &prefix, b"", &sealed[..15], 30),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            seal_chunks(&aes, &prefix, b"", &plain, 0),
+            Err(AesError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn failed_chunk_can_be_retried() {
+        let aes = Aes128::new(&[0x3Du8; 16]);
+        let prefix = [0x4Eu8; PREFIX_LEN];
+        let mut encryptor = StreamEncryptor::new(aes.clone(), &prefix);
+        let first = encryptor.encrypt_next(b"", b"one").unwrap();
+        let mut decryptor = StreamDecryptor::new(aes, &prefix);
+        let mut forged = first.clone();
+        forged[0] ^= 0x80;
+        assert!(decryptor.decrypt_next(b"", &forged).is_err());
+        assert_eq!(decryptor.decrypt_next(b"", &first).unwrap(), b"one");
+    }
+
+    #[test]
+    fn prepended_tags() {
+        let aes = Aes128::new(&[0x5Fu8; 16]);
+        let prefix = [0x6Au8; PREFIX_LEN];
+        let mut encryptor =
+            StreamEncryptor::new(aes.clone(), &prefix).with_tag_placement(TagPlacement::Prepended);
+        let first = encryptor.encrypt_next(b"", b"first").unwrap();
+        let last = encryptor.encrypt_last(b"", b"last").unwrap();
+        let appended = StreamEncryptor::new(aes.clone(), &prefix)
+            .encrypt_next(b"", b"first")
+            .unwrap();
+        assert_eq!(first[..TAG_LEN], appended[5..]);
+
+        assert!(StreamDecryptor::new(aes.clone(), &prefix)
+            .decrypt_next(b"", &first)
+            .is_err());
+        let mut decryptor =
+            StreamDecryptor::new(aes, &prefix).with_tag_placement(TagPlacement::Prepended);
+        assert_eq!(decryptor.decrypt_next(b"", &first).unwrap(), b"first");
+        assert_eq!(decryptor.decrypt_last(b"", &last).unwrap(), b"last");
+    }
+
+    #[test]
+    fn index_overflow() {
+        let mut encryptor = StreamEncryptor::new(Aes128::new(&[0u8; 16]), &[0u8; PREFIX_LEN]);
+        encryptor.position.index = Some(u32::MAX);
+        assert!(encryptor.encrypt_next(b"", b"").is_ok());
+        assert_eq!(
+            encryptor.encrypt_next(b"", b""),
+            Err(AesError::SequenceOverflow)
+        );
+    }
+}
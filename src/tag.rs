@@ -0,0 +1,267 @@
+//! # tag
+//! `tag` provides the functions to deal with authentication tags and MACs.
+//!
+//! **Attention!** Never compare a tag with `==` on slices or arrays. That comparison returns at
+//! the first differing byte, so its running time tells an attacker how many leading bytes of a
+//! forged tag are correct. Use [`verify_tag`] (or [`ct_eq`]) instead.
+//!
+//! The MACs in this library return a [`Tag`], whose `==` is constant-time, so comparing it with
+//! a received tag is safe.
+//!
+//! [`verify_tag`]: ../tag/fn.verify_tag.html
+//! [`ct_eq`]: ../tag/fn.ct_eq.html
+//! [`Tag`]: ../tag/struct.Tag.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::error::AesError;
+use super::metrics;
+use std::fmt;
+use std::hint::black_box;
+
+/// An authentication tag (or MAC) of at most 16 bytes.
+///
+/// `==` on `Tag`s is constant-time, and `Debug` prints no tag bytes, so a tag never leaks
+/// through comparisons or logs by accident. Read the bytes with [`as_bytes`] when the tag has to
+/// be sent along with the message.
+/// # Examples
+/// ```
+/// use aes_frast::tag::Tag;
+/// use aes_frast::AesError;
+///
+/// let computed = Tag::new(&[0x5Au8; 16]);
+/// let received = [0x5Au8; 16];
+/// assert!(computed.ct_eq(&received));
+/// assert_eq!(computed.verify(&received), Ok(()));
+/// assert_eq!(computed, Tag::from([0x5Au8; 16]));
+///
+/// // Truncated tags are supported too.
+/// let short = computed.truncate(8);
+/// assert_eq!(short.as_bytes(), &[0x5Au8; 8]);
+/// assert_eq!(short.verify(&received), Err(AesError::TagMismatch));
+///
+/// assert_eq!(format!("{:?}", short), "Tag(<8 bytes redacted>)");
+/// ```
+///
+/// [`as_bytes`]: ../tag/struct.Tag.html#method.as_bytes
+#[derive(Clone, Copy)]
+pub struct Tag {
+    bytes: [u8; BLOCKSIZE_IN_BYTES],
+    len: usize,
+}
+
+impl Tag {
+    /// Create a tag from its bytes.
+    /// # Panics
+    /// This function panics if `bytes` is longer than 16 bytes.
+    pub fn new(bytes: &[u8]) -> Self {
+        assert!(bytes.len() <= BLOCKSIZE_IN_BYTES, "Tag is too long.");
+        let mut tag = Self {
+            bytes: [0u8; BLOCKSIZE_IN_BYTES],
+            len: bytes.len(),
+        };
+        tag.bytes[..bytes.len()].copy_from_slice(bytes);
+        tag
+    }
+
+    /// The bytes of the tag.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// The length of the tag in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the tag is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Keep only the leading `len` bytes of the tag.
+    /// # Panics
+    /// This function panics if `len` is greater than the length of the tag.
+    pub fn truncate(&self, len: usize) -> Self {
+        assert!(len <= self.len, "Cannot extend a tag by truncating it.");
+        Self::new(&self.bytes[..len])
+    }
+
+    /// Compare the tag with a byte string in constant time, see [`ct_eq`].
+    ///
+    /// [`ct_eq`]: ../tag/fn.ct_eq.html
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        ct_eq(self.as_bytes(), other)
+    }
+
+    /// Verify a received tag against this one in constant time, see [`verify_tag`].
+    ///
+    /// [`verify_tag`]: ../tag/fn.verify_tag.html
+    pub fn verify(&self, expected: &[u8]) -> Result<(), AesError> {
+        verify_tag(expected, self.as_bytes())
+    }
+}
+
+impl PartialEq for Tag {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(self.as_bytes(), other.as_bytes())
+    }
+}
+
+impl Eq for Tag {}
+
+impl fmt::Debug for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Tag(<{} bytes redacted>)", self.len)
+    }
+}
+
+impl From<[u8; BLOCKSIZE_IN_BYTES]> for Tag {
+    fn from(bytes: [u8; BLOCKSIZE_IN_BYTES]) -> Self {
+        Self {
+            bytes,
+            len: BLOCKSIZE_IN_BYTES,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Tag {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Where the combined (non-detached) authenticated encryption functions put the tag, such as
+/// [`Ccm::seal_with_layout`].
+///
+/// [`Ccm::seal_with_layout`]: ../ccm/struct.Ccm.html#method.seal_with_layout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagPlacement {
+    /// The cipher followed by the tag, the common layout.
+    #[default]
+    Appended,
+    /// The tag followed by the cipher, as in some legacy formats.
+    Prepended,
+}
+
+impl TagPlacement {
+    /// Split `sealed` into the cipher and the tag of `tag_len` bytes.
+    ///
+    /// * *return* : `(cipher, tag)`, or `Err(AesError::InvalidDataLength)` if `sealed` is shorter
+    ///   than the tag.
+    pub(crate) fn split(self, sealed: &[u8], tag_len: usize) -> Result<(&[u8], &[u8]), AesError> {
+        if sealed.len() < tag_len {
+            return Err(AesError::InvalidDataLength);
+        }
+        Ok(match self {
+            Self::Appended => sealed.split_at(sealed.len() - tag_len),
+            Self::Prepended => {
+                let (tag, cipher) = sealed.split_at(tag_len);
+                (cipher, tag)
+            }
+        })
+    }
+}
+
+/// Compare two byte strings in constant time.
+///
+/// The running time only depends on the lengths of the parameters, never on their contents.
+/// The lengths themselves are not considered secret: byte strings of different lengths are
+/// unequal immediately.
+///
+/// * *parameter* `a`: the first byte string.
+/// * *parameter* `b`: the second byte string.
+/// * *return* : `true` if `a` and `b` are equal.
+/// # Examples
+/// ```
+/// use aes_frast::tag::ct_eq;
+///
+/// assert!(ct_eq(&[0x01, 0x02, 0x03], &[0x01, 0x02, 0x03]));
+/// assert!(!ct_eq(&[0x01, 0x02, 0x03], &[0x01, 0x02, 0x04]));
+/// assert!(!ct_eq(&[0x01, 0x02, 0x03], &[0x01, 0x02]));
+/// ```
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut difference = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        // `black_box` keeps the compiler from turning the loop into an early-exit comparison.
+        difference = black_box(difference | (x ^ y));
+    }
+    difference == 0
+}
+
+/// Verify an authentication tag (or MAC) in constant time.
+///
+/// * *parameter* `expected`: the tag which came with the message.
+/// * *parameter* `computed`: the tag computed from the message.
+/// * *return* : `Ok(())` if the tags are equal, otherwise `Err(AesError::TagMismatch)`.
+///
+/// All the authenticated functions in this library check their tags with this function. It is
+/// public so that tags and MACs from elsewhere can be checked in the same way.
+/// # Examples
+/// ```
+/// use aes_frast::tag::verify_tag;
+/// use aes_frast::AesError;
+///
+/// let received = [0x5Au8; 16];
+/// let computed = [0x5Au8; 16];
+/// assert_eq!(verify_tag(&received, &computed), Ok(()));
+///
+/// let forged = [0x5Bu8; 16];
+/// assert_eq!(verify_tag(&forged, &computed), Err(AesError::TagMismatch));
+/// ```
+pub fn verify_tag(expected: &[u8], computed: &[u8]) -> Result<(), AesError> {
+    if ct_eq(expected, computed) {
+        Ok(())
+    } else {
+        metrics::record_tag_failure();
+        Err(AesError::TagMismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ct_eq_works() {
+        let a: Vec<u8> = (0..16).collect();
+        for i in 0..16 {
+            let mut b = a.clone();
+            b[i] ^= 0x80;
+            assert!(!ct_eq(&a, &b));
+        }
+        assert!(ct_eq(&a, &a.clone()));
+        assert!(ct_eq(&[], &[]));
+        assert!(!ct_eq(&a[..15], &a));
+    }
+
+    #[test]
+    fn verify_tag_works() {
+        let tag = [0xA5u8; 16];
+        assert!(verify_tag(&tag, &tag).is_ok());
+        assert_eq!(verify_tag(&tag[..8], &tag), Err(AesError::TagMismatch));
+        let mut other = tag;
+        other[15] = 0;
+        assert_eq!(verify_tag(&other, &tag), Err(AesError::TagMismatch));
+    }
+
+    #[test]
+    fn tag_works() {
+        let bytes: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let tag = Tag::from(bytes);
+        assert_eq!(tag.len(), 16);
+        assert_eq!(tag.as_bytes(), &bytes);
+        assert_eq!(tag, Tag::new(&bytes));
+        assert_ne!(tag, tag.truncate(15));
+        assert_ne!(tag, Tag::new(&[0u8; 16]));
+        assert!(tag.truncate(0).is_empty());
+        assert_eq!(format!("{:?}", tag), "Tag(<16 bytes redacted>)");
+    }
+
+    #[test]
+    #[should_panic]
+    fn tag_too_long_panic() {
+        Tag::new(&[0u8; 17]);
+    }
+}
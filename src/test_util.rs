@@ -0,0 +1,14 @@
+//! Helpers shared by the unit tests.
+
+/// Format `bytes` as lowercase hexadecimal.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parse a hexadecimal string, panicking if it is not one.
+pub(crate) fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16).unwrap())
+        .collect()
+}
@@ -0,0 +1,251 @@
+//! # tweakable
+//! `tweakable` provides the [`TweakableBlockCipher`] trait, a block cipher which also takes a
+//! 128-bit tweak, and [`Xex`], its implementation with the XEX construction of IEEE 1619 (XTS).
+//!
+//! Modes built on a tweakable block cipher, like sector encryption or deterministic per-record
+//! encryption, take a [`TweakableBlockCipher`] instead of doing the tweak encryption and the
+//! doubling themselves. [`Xex::encrypt_unit`] applies the tweak sequence of a whole data unit
+//! (sector), `α^j` times the encrypted tweak for the `j`-th block, which is XTS without
+//! ciphertext stealing. The [`xts`] mod has the full XTS mode.
+//!
+//! [`TweakableBlockCipher`]: ../tweakable/trait.TweakableBlockCipher.html
+//! [`Xex`]: ../tweakable/struct.Xex.html
+//! [`Xex::encrypt_unit`]: ../tweakable/struct.Xex.html#method.encrypt_unit
+//! [`xts`]: ../xts/index.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+
+/// A block cipher with 128-bit blocks and 128-bit tweaks.
+///
+/// Encrypting the same block with different tweaks gives unrelated results, as if each tweak
+/// selected its own key.
+pub trait TweakableBlockCipher {
+    /// **Encrypt** a block in place under `tweak`.
+    fn encrypt_block_tweaked(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+    );
+
+    /// **Decrypt** a block in place under `tweak`.
+    fn decrypt_block_tweaked(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+    );
+}
+
+impl<T: TweakableBlockCipher + ?Sized> TweakableBlockCipher for &T {
+    fn encrypt_block_tweaked(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) {
+        (**self).encrypt_block_tweaked(tweak, block);
+    }
+
+    fn decrypt_block_tweaked(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) {
+        (**self).decrypt_block_tweaked(tweak, block);
+    }
+}
+
+/// The XEX tweakable block cipher over a [`BlockCipher128`], with two keys as in XTS.
+///
+/// For a block `P` and a tweak `T`, the mask is `Δ = E_K2(T)` and the cipher is
+/// `E_K1(P ⊕ Δ) ⊕ Δ`. The tweak of a sector is its number in little-endian, as in IEEE 1619.
+/// # Examples
+/// ```
+/// use aes_frast::tweakable::{TweakableBlockCipher, Xex};
+/// use aes_frast::Aes128;
+///
+/// let xex = Xex::new(Aes128::new(&[0x11u8; 16]), Aes128::new(&[0x22u8; 16]));
+/// let tweak = 7u128.to_le_bytes();
+///
+/// let mut a = [0x5Au8; 16];
+/// let mut b = [0x5Au8; 16];
+/// xex.encrypt_block_tweaked(&tweak, &mut a);
+/// xex.encrypt_block_tweaked(&8u128.to_le_bytes(), &mut b);
+/// assert_ne!(a, b);
+/// xex.decrypt_block_tweaked(&tweak, &mut a);
+/// assert_eq!(a, [0x5Au8; 16]);
+///
+/// // A whole sector, with a different mask for every block.
+/// let mut sector = [0x5Au8; 64];
+/// xex.encrypt_unit(&tweak, &mut sector).unwrap();
+/// assert_ne!(sector[..16], sector[16..32]);
+/// xex.decrypt_unit(&tweak, &mut sector).unwrap();
+/// assert_eq!(sector, [0x5Au8; 64]);
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Xex<C> {
+    cipher: C,
+    tweak_cipher: C,
+}
+
+impl<C: BlockCipher128> Xex<C> {
+    /// Create the cipher from the data cipher (key 1) and the tweak cipher (key 2).
+    pub fn new(cipher: C, tweak_cipher: C) -> Self {
+        Self {
+            cipher,
+            tweak_cipher,
+        }
+    }
+
+    /// The data cipher (key 1).
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// **Encrypt** a data unit in place, the `j`-th block with the mask `E_K2(tweak) ⊗ α^j`.
+    ///
+    /// * *parameter* `tweak`: the tweak of the data unit, e.g. the little-endian sector number.
+    /// * *parameter* `data`: the plain, which becomes the cipher. Its length must be a multiple
+    ///   of 16.
+    /// * *return* : `Err(AesError::InvalidDataLength)` for a wrong length of `data`, which is
+    ///   left untouched then.
+    pub fn encrypt_unit(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+    ) -> Result<(), AesError> {
+        if data.len() & 0b1111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        self.masked_blocks(data, self.first_mask(tweak), C::encrypt_blocks);
+        Ok(())
+    }
+
+    /// **Decrypt** a data unit in place, the reverse of [`encrypt_unit`].
+    ///
+    /// [`encrypt_unit`]: ../tweakable/struct.Xex.html#method.encrypt_unit
+    pub fn decrypt_unit(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+    ) -> Result<(), AesError> {
+        if data.len() & 0b1111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        self.masked_blocks(data, self.first_mask(tweak), C::decrypt_blocks);
+        Ok(())
+    }
+
+    /// The mask of the first block of a data unit, `E_K2(tweak)`.
+    pub(crate) fn first_mask(&self, tweak: &[u8; BLOCKSIZE_IN_BYTES]) -> Block {
+        let mut mask = *tweak;
+        self.tweak_cipher.encrypt_block(&mut mask);
+        Block::from(mask)
+    }
+
+    // Mask, process and mask again the consecutive blocks of `data`, handing them over to the
+    // data cipher 8 at a time, and return the mask after the last block.
+    pub(crate) fn masked_blocks(
+        &self,
+        data: &mut [u8],
+        mut mask: Block,
+        process: fn(&C, &mut [u8]),
+    ) -> Block {
+        let mut masks = [Block::default(); 8];
+        for chunk in data.chunks_mut(8 * BLOCKSIZE_IN_BYTES) {
+            for (block, block_mask) in chunk.chunks_exact_mut(16).zip(masks.iter_mut()) {
+                *block_mask = mask;
+                block.copy_from_slice(&(Block::from_slice(block) ^ mask).0);
+                mask = mask.dbl_le();
+            }
+            process(&self.cipher, chunk);
+            for (block, block_mask) in chunk.chunks_exact_mut(16).zip(masks.iter()) {
+                block.copy_from_slice(&(Block::from_slice(block) ^ *block_mask).0);
+            }
+        }
+        mask
+    }
+
+    // Mask, process and mask again a single block.
+    pub(crate) fn masked_block(
+        &self,
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+        mask: &Block,
+        process: fn(&C, &mut [u8; BLOCKSIZE_IN_BYTES]),
+    ) {
+        let mut masked = Block::from(*block) ^ *mask;
+        process(&self.cipher, &mut masked.0);
+        *block = (masked ^ *mask).0;
+    }
+}
+
+impl<C: BlockCipher128> TweakableBlockCipher for Xex<C> {
+    fn encrypt_block_tweaked(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) {
+        self.masked_block(block, &self.first_mask(tweak), C::encrypt_block);
+    }
+
+    fn decrypt_block_tweaked(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        block: &mut [u8; BLOCKSIZE_IN_BYTES],
+    ) {
+        self.masked_block(block, &self.first_mask(tweak), C::decrypt_block);
+    }
+}
+
+impl_redacted_debug!(Xex<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+    use crate::Aes128;
+
+    #[test]
+    fn ieee1619_vector_1() {
+        // IEEE 1619-2007, Annex B, XTS-AES-128 vector 1.
+        let xex = Xex::new(Aes128::new(&[0u8; 16]), Aes128::new(&[0u8; 16]));
+        let mut data = [0u8; 32];
+        xex.encrypt_unit(&[0u8; 16], &mut data).unwrap();
+        assert_eq!(
+            hex(&data),
+            "917cf69ebd68b2ec9b9fe9a3eadda692cd43d2f59598ed858c02c2652fbf922e"
+        );
+    }
+
+    #[test]
+    fn unit_matches_xts() {
+        // The expected value came from the XTS mode of OpenSSL (through Python's `cryptography`).
+        let key: Vec<u8> = (0..32).collect();
+        let xex = Xex::new(
+            Aes128::new(key[..16].try_into().unwrap()),
+            Aes128::new(key[16..].try_into().unwrap()),
+        );
+        let tweak = 5u128.to_le_bytes();
+        let mut data: Vec<u8> = (0..64).collect();
+        xex.encrypt_unit(&tweak, &mut data).unwrap();
+        assert_eq!(
+            hex(&data),
+            "2dbdc260709c00db30639a42ffb50a6780a3b540429e484f806e2198d6a90ecf\
+             0bfc60bc5d580d3efe60032b5eb1049682fe5c904ec23a87c59d52fe3ecd0ae6"
+        );
+
+        // The first block of a unit is the single tweaked block.
+        let mut first: [u8; 16] = core::array::from_fn(|i| i as u8);
+        xex.encrypt_block_tweaked(&tweak, &mut first);
+        assert_eq!(first, data[..16]);
+
+        xex.decrypt_unit(&tweak, &mut data).unwrap();
+        assert_eq!(data, (0..64).collect::<Vec<u8>>());
+        assert_eq!(
+            xex.encrypt_unit(&tweak, &mut [0u8; 17]),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+}
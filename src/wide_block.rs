@@ -0,0 +1,233 @@
+//! # wide_block
+//! `wide_block` provides wide-block, length-preserving modes: the whole message is one big block,
+//! so a change of any bit of the plain changes every bit of the cipher. They suit full-record
+//! (e.g. disk sector) encryption, where there is no room for an IV or a tag. The tweak, such as
+//! the sector number, makes equal plains at different positions encrypt differently.
+//!
+//! * [`Cmc`]: CMC (CBC-Mask-CBC) by Halevi and Rogaway, two CBC passes with a mask between them.
+//!
+//! Like every length-preserving mode, these modes are deterministic and do not authenticate the
+//! data: an altered cipher decrypts to random-looking garbage, which is not detected.
+//!
+//! [`Cmc`]: ../wide_block/struct.Cmc.html
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block::Block;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+
+/// CMC (CBC-Mask-CBC) mode over a [`BlockCipher128`], as defined by Halevi and Rogaway in
+/// "A Tweakable Enciphering Mode" (CRYPTO 2003).
+///
+/// CMC needs two independent keys: one for the data passes, and one which encrypts the tweak.
+/// The message is at least one block, and a multiple of 16 bytes.
+/// # Examples
+/// ```
+/// use aes_frast::wide_block::Cmc;
+/// use aes_frast::Aes128;
+///
+/// let cmc = Cmc::new(Aes128::new(&[0x2Bu8; 16]), Aes128::new(&[0x7Eu8; 16]));
+/// let sector_number = 5u128.to_be_bytes();
+/// let plain = [0x5Au8; 64];
+///
+/// let mut data = plain;
+/// cmc.encrypt(&sector_number, &mut data).unwrap();
+/// // Equal plain blocks give unrelated cipher blocks.
+/// assert_ne!(data[..16], data[16..32]);
+///
+/// cmc.decrypt(&sector_number, &mut data).unwrap();
+/// assert_eq!(data, plain);
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Cmc<C> {
+    cipher: C,
+    tweak_cipher: C,
+}
+
+impl<C: BlockCipher128> Cmc<C> {
+    /// Create the mode from the cipher of the data passes and the cipher of the tweak, which must
+    /// have independent keys.
+    pub fn new(cipher: C, tweak_cipher: C) -> Self {
+        Self {
+            cipher,
+            tweak_cipher,
+        }
+    }
+
+    /// The cipher of the data passes.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// **Encrypt** `data` in place.
+    ///
+    /// * *parameter* `tweak`: the tweak, e.g. the big-endian sector number.
+    /// * *parameter* `data`: the plain, which becomes the cipher. Its length must be a non-zero
+    ///   multiple of 16.
+    /// * *return* : `Err(AesError::InvalidDataLength)` for a wrong length of `data`, which is
+    ///   left untouched then.
+    pub fn encrypt(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+    ) -> Result<(), AesError> {
+        self.apply(tweak, data, |block| self.cipher.encrypt_block(block))
+    }
+
+    /// **Decrypt** `data` in place.
+    ///
+    /// * *parameter* `tweak`: the tweak of the encryption.
+    /// * *parameter* `data`: the cipher, which becomes the plain. Its length must be a non-zero
+    ///   multiple of 16.
+    /// * *return* : `Err(AesError::InvalidDataLength)` for a wrong length of `data`, which is
+    ///   left untouched then.
+    pub fn decrypt(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+    ) -> Result<(), AesError> {
+        self.apply(tweak, data, |block| self.cipher.decrypt_block(block))
+    }
+
+    // Decryption is encryption with the inverse cipher in the data passes.
+    fn apply<F>(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+        block_function: F,
+    ) -> Result<(), AesError>
+    where
+        F: Fn(&mut [u8; BLOCKSIZE_IN_BYTES]),
+    {
+        if data.is_empty() || data.len() & 0b1111 != 0 {
+            return Err(AesError::InvalidDataLength);
+        }
+        let mut encrypted_tweak = *tweak;
+        self.tweak_cipher.encrypt_block(&mut encrypted_tweak);
+        let encrypted_tweak = Block::from(encrypted_tweak);
+
+        // The first CBC pass, with the encrypted tweak as the IV.
+        let mut chain = encrypted_tweak;
+        for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+            let mut block = Block::from_slice(chunk) ^ chain;
+            block_function(&mut block.0);
+            chunk.copy_from_slice(&block.0);
+            chain = block;
+        }
+
+        // The mask, then the blocks in reverse order.
+        let first = Block::from_slice(&data[..BLOCKSIZE_IN_BYTES]);
+        let mask = (first ^ chain).dbl();
+        let n_blocks = data.len() / BLOCKSIZE_IN_BYTES;
+        for i in 0..(n_blocks / 2) {
+            let j = n_blocks - 1 - i;
+            let (head, tail) = data.split_at_mut(j * BLOCKSIZE_IN_BYTES);
+            head[(i * BLOCKSIZE_IN_BYTES)..((i + 1) * BLOCKSIZE_IN_BYTES)]
+                .swap_with_slice(&mut tail[..BLOCKSIZE_IN_BYTES]);
+        }
+
+        // The second CBC pass, with a zero IV and the chaining XOR after the cipher.
+        let mut chain = Block::default();
+        for chunk in data.chunks_exact_mut(BLOCKSIZE_IN_BYTES) {
+            let masked = Block::from_slice(chunk) ^ mask;
+            let mut block = masked;
+            block_function(&mut block.0);
+            chunk.copy_from_slice(&(block ^ chain).0);
+            chain = masked;
+        }
+        for (byte, tweak_byte) in data[..BLOCKSIZE_IN_BYTES]
+            .iter_mut()
+            .zip(encrypted_tweak.0.iter())
+        {
+            *byte ^= tweak_byte;
+        }
+        Ok(())
+    }
+}
+
+impl_redacted_debug!(Cmc<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+    use crate::Aes128;
+
+    /// Check the properties every wide-block mode must have, with a 4-block message.
+    fn check_wide_block<E, D>(encrypt: E, decrypt: D)
+    where
+        E: Fn(&[u8; BLOCKSIZE_IN_BYTES], &mut [u8]) -> Result<(), AesError>,
+        D: Fn(&[u8; BLOCKSIZE_IN_BYTES], &mut [u8]) -> Result<(), AesError>,
+    {
+        let tweak = [0x11u8; BLOCKSIZE_IN_BYTES];
+        let plain: Vec<u8> = (0..64).collect();
+        let mut cipher = plain.clone();
+        encrypt(&tweak, &mut cipher).unwrap();
+        assert_eq!(cipher.len(), plain.len());
+        let mut decrypted = cipher.clone();
+        decrypt(&tweak, &mut decrypted).unwrap();
+        assert_eq!(decrypted, plain);
+
+        // A change in the last byte changes every block of the cipher.
+        let mut changed = plain.clone();
+        changed[63] ^= 1;
+        encrypt(&tweak, &mut changed).unwrap();
+        for (a, b) in cipher.chunks(16).zip(changed.chunks(16)) {
+            assert_ne!(a, b);
+        }
+
+        // So does a change of the tweak.
+        let mut changed = plain.clone();
+        encrypt(&[0x12u8; BLOCKSIZE_IN_BYTES], &mut changed).unwrap();
+        for (a, b) in cipher.chunks(16).zip(changed.chunks(16)) {
+            assert_ne!(a, b);
+        }
+    }
+
+    fn cmc() -> Cmc<Aes128> {
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let tweak_key: [u8; 16] = core::array::from_fn(|i| (i + 16) as u8);
+        Cmc::new(Aes128::new(&key), Aes128::new(&tweak_key))
+    }
+
+    #[test]
+    fn cmc_properties() {
+        let cmc = cmc();
+        check_wide_block(|t, d| cmc.encrypt(t, d), |t, d| cmc.decrypt(t, d));
+    }
+
+    #[test]
+    fn cmc_known_answer() {
+        // The expected values came from an independent implementation of the CMC paper.
+        let cmc = cmc();
+        let tweak: [u8; 16] = core::array::from_fn(|i| (i + 0xA0) as u8);
+        let mut data: Vec<u8> = (0..64).collect();
+        cmc.encrypt(&tweak, &mut data).unwrap();
+        let expected = "80be73500400e76a6abdcf204418fe4511713049c3bcbe27dc441b76aab668b5\
+                        cadf9d7a6fb949f017077bab869b07c8e14cbe0e3d83261a8cd6ed62417e5df0";
+        assert_eq!(hex(&data), expected);
+
+        let mut data: Vec<u8> = (0..16).collect();
+        cmc.encrypt(&tweak, &mut data).unwrap();
+        assert_eq!(hex(&data), "b0b978082e9246a92bd307f2530bbdc4");
+        cmc.decrypt(&tweak, &mut data).unwrap();
+        assert_eq!(data, (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn cmc_invalid_length() {
+        let cmc = cmc();
+        let mut empty = [0u8; 0];
+        let mut odd = [0x5Au8; 17];
+        assert_eq!(
+            cmc.encrypt(&[0u8; 16], &mut empty),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            cmc.decrypt(&[0u8; 16], &mut odd),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(odd, [0x5Au8; 17]);
+    }
+}
@@ -0,0 +1,498 @@
+//! # xts
+//! `xts` provides XTS-AES, the storage encryption mode of IEEE 1619 and NIST SP 800-38E.
+//!
+//! XTS encrypts each data unit (sector) with the XEX tweakable block cipher (see the
+//! [`tweakable`] mod), the tweak being the sector number. A data unit whose length is not a
+//! multiple of 16 bytes is encrypted with ciphertext stealing, so the cipher is exactly as long as
+//! the plain. A data unit must be at least 16 bytes long.
+//!
+//! Like every length-preserving mode, XTS is deterministic and does not authenticate the data.
+//!
+//! The sectors are independent of each other, so with the `parallel` feature
+//! [`Xts::encrypt_sectors_parallel`] spreads a run of sectors, such as a disk image, over
+//! several threads.
+//!
+//! [`tweakable`]: ../tweakable/index.html
+//! [`Xts::encrypt_sectors_parallel`]: ../xts/struct.Xts.html#method.encrypt_sectors_parallel
+use super::aes_cipher::AesAny;
+use super::aes_core::BLOCKSIZE_IN_BYTES;
+use super::block_cipher::BlockCipher128;
+use super::error::AesError;
+use super::key::{Key, KeySize};
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::tag::ct_eq;
+use super::tweakable::Xex;
+
+/// The key pair of XTS-AES: the data key (key 1) and the tweak key (key 2).
+///
+/// Both keys are 128-bit (XTS-AES-128) or 256-bit (XTS-AES-256), and they must differ: with
+/// equal keys the tweak mask is the encryption of the sector number under the data key, which
+/// SP 800-38E forbids. The combined form of other libraries, key 1 followed by key 2 in one
+/// 256-bit or 512-bit string, is accepted by [`from_combined`] and `TryFrom<&[u8]>`.
+/// # Examples
+/// ```
+/// use aes_frast::key::Key;
+/// use aes_frast::xts::{Xts, XtsKeys};
+/// use aes_frast::AesError;
+///
+/// let keys = XtsKeys::new(Key::new(&[0x11u8; 16]).unwrap(), Key::new(&[0x22u8; 16]).unwrap())
+///     .unwrap();
+/// let mut combined = [0x11u8; 32];
+/// combined[16..].fill(0x22);
+/// assert_eq!(XtsKeys::from_combined(&combined).unwrap().data().as_bytes(), &[0x11u8; 16]);
+/// let xts = Xts::from_keys(&keys);
+///
+/// // A key used twice is a common integration bug, and is rejected.
+/// let same = Key::new(&[0x11u8; 16]).unwrap();
+/// assert_eq!(XtsKeys::new(same.clone(), same).err(), Some(AesError::InvalidParameter));
+/// assert_eq!(XtsKeys::from_combined(&[0x11u8; 32]).err(), Some(AesError::InvalidParameter));
+/// ```
+///
+/// [`from_combined`]: ../xts/struct.XtsKeys.html#method.from_combined
+#[derive(Clone)]
+pub struct XtsKeys {
+    data: Key,
+    tweak: Key,
+}
+
+impl XtsKeys {
+    /// Pair a data key and a tweak key.
+    ///
+    /// * *parameter* `data`: the data key (key 1).
+    /// * *parameter* `tweak`: the tweak key (key 2).
+    /// * *return* : the key pair, or `Err(AesError::InvalidKeyLength)` unless both keys are
+    ///   128-bit or both are 256-bit, or `Err(AesError::InvalidParameter)` if they are equal.
+    pub fn new(data: Key, tweak: Key) -> Result<Self, AesError> {
+        if data.size() != tweak.size() || data.size() == KeySize::Aes192 {
+            return Err(AesError::InvalidKeyLength);
+        }
+        if ct_eq(data.as_bytes(), tweak.as_bytes()) {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(Self { data, tweak })
+    }
+
+    /// Split a combined XTS key: key 1 followed by key 2.
+    ///
+    /// * *parameter* `key`: 32 bytes for XTS-AES-128 or 64 bytes for XTS-AES-256.
+    /// * *return* : the key pair, or `Err(AesError::InvalidKeyLength)` for other lengths, or
+    ///   `Err(AesError::InvalidParameter)` if the two halves are equal.
+    pub fn from_combined(key: &[u8]) -> Result<Self, AesError> {
+        if key.len() != 32 && key.len() != 64 {
+            return Err(AesError::InvalidKeyLength);
+        }
+        let (data, tweak) = key.split_at(key.len() / 2);
+        Self::new(Key::new(data)?, Key::new(tweak)?)
+    }
+
+    /// The data key (key 1).
+    pub fn data(&self) -> &Key {
+        &self.data
+    }
+
+    /// The tweak key (key 2).
+    pub fn tweak(&self) -> &Key {
+        &self.tweak
+    }
+}
+
+impl TryFrom<&[u8]> for XtsKeys {
+    type Error = AesError;
+
+    fn try_from(key: &[u8]) -> Result<Self, AesError> {
+        Self::from_combined(key)
+    }
+}
+
+/// XTS mode over a [`BlockCipher128`], with the data cipher (key 1) and the tweak cipher
+/// (key 2).
+/// # Examples
+/// ```
+/// use aes_frast::xts::Xts;
+///
+/// // XTS-AES-128 takes a 256-bit key: key 1 and then key 2.
+/// let key: Vec<u8> = (0..32).collect();
+/// let xts = Xts::from_key(&key).unwrap();
+///
+/// // A sector which is not a multiple of 16 bytes long is fine too.
+/// let plain = [0x5Au8; 520];
+/// let mut sector = plain;
+/// xts.encrypt_sector(7, &mut sector).unwrap();
+/// assert_ne!(sector, plain);
+/// xts.decrypt_sector(7, &mut sector).unwrap();
+/// assert_eq!(sector, plain);
+/// ```
+///
+/// [`BlockCipher128`]: ../block_cipher/trait.BlockCipher128.html
+#[derive(Clone)]
+pub struct Xts<C> {
+    xex: Xex<C>,
+}
+
+impl Xts<AesAny> {
+    /// Create the mode from a double-length key: key 1 followed by key 2, see
+    /// [`XtsKeys::from_combined`].
+    ///
+    /// * *parameter* `key`: 32 bytes for XTS-AES-128 or 64 bytes for XTS-AES-256.
+    /// * *return* : the mode, or `Err(AesError::InvalidKeyLength)` for other lengths, or
+    ///   `Err(AesError::InvalidParameter)` if the two halves are equal, which SP 800-38E forbids.
+    ///
+    /// [`XtsKeys::from_combined`]: ../xts/struct.XtsKeys.html#method.from_combined
+    pub fn from_key(key: &[u8]) -> Result<Self, AesError> {
+        Ok(Self::from_keys(&XtsKeys::from_combined(key)?))
+    }
+
+    /// Create the mode from a checked key pair.
+    pub fn from_keys(keys: &XtsKeys) -> Self {
+        Self {
+            xex: Xex::new(AesAny::from(keys.data()), AesAny::from(keys.tweak())),
+        }
+    }
+}
+
+impl<C: BlockCipher128> Xts<C> {
+    /// Create the mode from the data cipher (key 1) and the tweak cipher (key 2).
+    ///
+    /// * *return* : the mode, or `Err(AesError::InvalidParameter)` if both ciphers have the same
+    ///   key, which SP 800-38E forbids. It is detected by encrypting a block of zeros with both.
+    pub fn new(cipher: C, tweak_cipher: C) -> Result<Self, AesError> {
+        let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+        let mut tweak_block = [0u8; BLOCKSIZE_IN_BYTES];
+        cipher.encrypt_block(&mut block);
+        tweak_cipher.encrypt_block(&mut tweak_block);
+        if ct_eq(&block, &tweak_block) {
+            return Err(AesError::InvalidParameter);
+        }
+        Ok(Self {
+            xex: Xex::new(cipher, tweak_cipher),
+        })
+    }
+
+    /// The data cipher (key 1).
+    pub fn cipher(&self) -> &C {
+        self.xex.cipher()
+    }
+
+    /// **Encrypt** a data unit in place.
+    ///
+    /// * *parameter* `tweak`: the tweak of the data unit.
+    /// * *parameter* `data`: the plain, which becomes the cipher. It must be at least 16 bytes.
+    /// * *return* : `Err(AesError::InvalidDataLength)` for a data unit shorter than 16 bytes,
+    ///   which is left untouched then.
+    pub fn encrypt(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+    ) -> Result<(), AesError> {
+        if data.len() < BLOCKSIZE_IN_BYTES {
+            return Err(AesError::InvalidDataLength);
+        }
+        let (full_blocks, partial) = (data.len() / BLOCKSIZE_IN_BYTES, data.len() & 0b1111);
+        let mask = self.xex.masked_blocks(
+            &mut data[..(16 * full_blocks)],
+            self.xex.first_mask(tweak),
+            C::encrypt_blocks,
+        );
+        if partial != 0 {
+            // Ciphertext stealing: the partial block takes the head of the last full cipher
+            // block, whose tail pads the partial plain block into a full one.
+            let last = 16 * full_blocks;
+            let mut stolen = [0u8; BLOCKSIZE_IN_BYTES];
+            stolen[..partial].copy_from_slice(&data[last..]);
+            stolen[partial..].copy_from_slice(&data[(last - 16 + partial)..last]);
+            data.copy_within((last - 16)..(last - 16 + partial), last);
+            self.xex.masked_block(&mut stolen, &mask, C::encrypt_block);
+            data[(last - 16)..last].copy_from_slice(&stolen);
+        }
+        Ok(())
+    }
+
+    /// **Decrypt** a data unit in place, the reverse of [`encrypt`].
+    ///
+    /// [`encrypt`]: ../xts/struct.Xts.html#method.encrypt
+    pub fn decrypt(
+        &self,
+        tweak: &[u8; BLOCKSIZE_IN_BYTES],
+        data: &mut [u8],
+    ) -> Result<(), AesError> {
+        if data.len() < BLOCKSIZE_IN_BYTES {
+            return Err(AesError::InvalidDataLength);
+        }
+        let (full_blocks, partial) = (data.len() / BLOCKSIZE_IN_BYTES, data.len() & 0b1111);
+        // With stealing, the last full block is decrypted with the mask after its own.
+        let normal_blocks = if partial == 0 {
+            full_blocks
+        } else {
+            full_blocks - 1
+        };
+        let mask = self.xex.masked_blocks(
+            &mut data[..(16 * normal_blocks)],
+            self.xex.first_mask(tweak),
+            C::decrypt_blocks,
+        );
+        if partial != 0 {
+            let last = 16 * full_blocks;
+            let mut stolen = [0u8; BLOCKSIZE_IN_BYTES];
+            stolen.copy_from_slice(&data[(last - 16)..last]);
+            self.xex
+                .masked_block(&mut stolen, &mask.dbl_le(), C::decrypt_block);
+            let mut block = [0u8; BLOCKSIZE_IN_BYTES];
+            block[..partial].copy_from_slice(&data[last..]);
+            block[partial..].copy_from_slice(&stolen[partial..]);
+            data[last..].copy_from_slice(&stolen[..partial]);
+            self.xex.masked_block(&mut block, &mask, C::decrypt_block);
+            data[(last - 16)..last].copy_from_slice(&block);
+        }
+        Ok(())
+    }
+
+    /// **Encrypt** the sector numbered `sector`, whose tweak is the little-endian number.
+    pub fn encrypt_sector(&self, sector: u128, data: &mut [u8]) -> Result<(), AesError> {
+        self.encrypt(&sector.to_le_bytes(), data)
+    }
+
+    /// **Decrypt** the sector numbered `sector`, whose tweak is the little-endian number.
+    pub fn decrypt_sector(&self, sector: u128, data: &mut [u8]) -> Result<(), AesError> {
+        self.decrypt(&sector.to_le_bytes(), data)
+    }
+
+    /// **Encrypt** consecutive sectors in place, on up to `threads` threads.
+    ///
+    /// * *parameter* `start_sector`: the number of the first sector; the next ones follow it.
+    /// * *parameter* `sector_size`: the length of each sector, at least 16 bytes.
+    /// * *parameter* `data`: the plain sectors, which become the cipher.
+    /// * *parameter* `threads`: the maximum number of threads. Data of less than 256 blocks per
+    ///   thread is not worth the threads, and is processed on the calling thread only.
+    /// * *return* : `Err(AesError::InvalidDataLength)` for a sector shorter than 16 bytes, or data
+    ///   which is not made of whole sectors, which is left untouched then.
+    /// # Examples
+    /// ```
+    /// use aes_frast::xts::Xts;
+    ///
+    /// let key: Vec<u8> = (0..64).collect();
+    /// let xts = Xts::from_key(&key).unwrap();
+    ///
+    /// let plain = vec![0x5Au8; 4096 * 512];
+    /// let mut image = plain.clone();
+    /// xts.encrypt_sectors_parallel(1000, 512, &mut image, 4).unwrap();
+    ///
+    /// // The same as encrypting the sectors one by one.
+    /// let mut sector = plain[..512].to_vec();
+    /// xts.encrypt_sector(1003, &mut sector).unwrap();
+    /// assert_eq!(image[(3 * 512)..(4 * 512)], sector[..]);
+    ///
+    /// xts.decrypt_sectors_parallel(1000, 512, &mut image, 4).unwrap();
+    /// assert_eq!(image, plain);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn encrypt_sectors_parallel(
+        &self,
+        start_sector: u128,
+        sector_size: usize,
+        data: &mut [u8],
+        threads: usize,
+    ) -> Result<(), AesError>
+    where
+        C: Sync,
+    {
+        self.sectors_parallel(
+            start_sector,
+            sector_size,
+            data,
+            threads,
+            Self::encrypt_sector,
+        )
+    }
+
+    /// **Decrypt** consecutive sectors in place, on up to `threads` threads, the reverse of
+    /// [`encrypt_sectors_parallel`].
+    ///
+    /// [`encrypt_sectors_parallel`]: ../xts/struct.Xts.html#method.encrypt_sectors_parallel
+    #[cfg(feature = "parallel")]
+    pub fn decrypt_sectors_parallel(
+        &self,
+        start_sector: u128,
+        sector_size: usize,
+        data: &mut [u8],
+        threads: usize,
+    ) -> Result<(), AesError>
+    where
+        C: Sync,
+    {
+        self.sectors_parallel(
+            start_sector,
+            sector_size,
+            data,
+            threads,
+            Self::decrypt_sector,
+        )
+    }
+
+    #[cfg(feature = "parallel")]
+    fn sectors_parallel(
+        &self,
+        start_sector: u128,
+        sector_size: usize,
+        data: &mut [u8],
+        threads: usize,
+        process: fn(&Self, u128, &mut [u8]) -> Result<(), AesError>,
+    ) -> Result<(), AesError>
+    where
+        C: Sync,
+    {
+        if sector_size < BLOCKSIZE_IN_BYTES || !data.len().is_multiple_of(sector_size) {
+            return Err(AesError::InvalidDataLength);
+        }
+        parallel::for_each_unit_chunk(data, sector_size, threads, |first_sector, chunk| {
+            for (i, sector) in chunk.chunks_exact_mut(sector_size).enumerate() {
+                let number = start_sector.wrapping_add((first_sector + i) as u128);
+                // The sector size was checked above.
+                process(self, number, sector).unwrap();
+            }
+        });
+        Ok(())
+    }
+}
+
+impl_redacted_debug!(XtsKeys, Xts<C>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    fn check(key: &[u8], sector: u128, plain: &[u8], expected: &str) {
+        let xts = Xts::from_key(key).unwrap();
+        let mut data = plain.to_vec();
+        xts.encrypt_sector(sector, &mut data).unwrap();
+        assert_eq!(hex(&data), expected);
+        xts.decrypt_sector(sector, &mut data).unwrap();
+        assert_eq!(data, plain);
+    }
+
+    #[test]
+    fn ciphertext_stealing() {
+        // The expected values came from the XTS mode of OpenSSL (through Python's `cryptography`).
+        let key: Vec<u8> = (0..32).collect();
+        check(
+            &key,
+            9,
+            &(0..17).collect::<Vec<u8>>(),
+            "4c247a71be7efb5d1dd8a6a21705fd9961",
+        );
+        check(
+            &key,
+            9,
+            &(0..47).collect::<Vec<u8>>(),
+            "6177403c0b160d18a8f17b7fa8db7efb2de0d65d1211332995dd29ade1e52c5b\
+             9318a81e287fb74931f66c1d352dba",
+        );
+        let key: Vec<u8> = (0..64).collect();
+        check(
+            &key,
+            0x12_3456_789A,
+            &(0..40).map(|i| (i * 3) as u8).collect::<Vec<u8>>(),
+            "c6a44d00e35e0caa0279f623f17ece8c3b90a9569bfceaa432df43cb7610d83e69c162eb3ca9ff76",
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert_eq!(
+            Xts::from_key(&[0u8; 48]).err(),
+            Some(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            Xts::from_key(&[0u8; 32]).err(),
+            Some(AesError::InvalidParameter)
+        );
+        let key: Vec<u8> = (0..32).collect();
+        let xts = Xts::from_key(&key).unwrap();
+        assert_eq!(
+            xts.encrypt_sector(0, &mut [0u8; 15]),
+            Err(AesError::InvalidDataLength)
+        );
+    }
+
+    #[test]
+    fn key_pairs() {
+        use crate::Aes256;
+
+        let key: Vec<u8> = (0..64).collect();
+        let keys = XtsKeys::try_from(&key[..]).unwrap();
+        assert_eq!(keys.data().as_bytes(), &key[..32]);
+        assert_eq!(keys.tweak().as_bytes(), &key[32..]);
+        let mut expected = [0x5Au8; 40];
+        Xts::from_key(&key)
+            .unwrap()
+            .encrypt_sector(3, &mut expected)
+            .unwrap();
+        let mut data = [0x5Au8; 40];
+        Xts::from_keys(&keys).encrypt_sector(3, &mut data).unwrap();
+        assert_eq!(data, expected);
+        let mut data = [0x5Au8; 40];
+        let data_cipher = Aes256::new(key[..32].try_into().unwrap());
+        let tweak_cipher = Aes256::new(key[32..].try_into().unwrap());
+        Xts::new(data_cipher, tweak_cipher)
+            .unwrap()
+            .encrypt_sector(3, &mut data)
+            .unwrap();
+        assert_eq!(data, expected);
+
+        let key_128 = Key::new(&[0x01u8; 16]).unwrap();
+        let key_256 = Key::new(&[0x02u8; 32]).unwrap();
+        let key_192 = Key::new(&[0x03u8; 24]).unwrap();
+        assert_eq!(
+            XtsKeys::new(key_128.clone(), key_256).err(),
+            Some(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            XtsKeys::new(key_192.clone(), Key::new(&[0x04u8; 24]).unwrap()).err(),
+            Some(AesError::InvalidKeyLength)
+        );
+        assert_eq!(
+            XtsKeys::new(key_128.clone(), key_128).err(),
+            Some(AesError::InvalidParameter)
+        );
+        let same = Aes256::new(&[0x05u8; 32]);
+        assert_eq!(
+            Xts::new(same.clone(), same).err(),
+            Some(AesError::InvalidParameter)
+        );
+        assert_eq!(format!("{:?}", keys), "XtsKeys { .. }");
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_sectors() {
+        let key: Vec<u8> = (0..32).collect();
+        let xts = Xts::from_key(&key).unwrap();
+        // Sectors of 520 bytes, so the chunks don't fall on the blocks of the threads.
+        let plain: Vec<u8> = (0..(1000 * 520)).map(|i| (i % 251) as u8).collect();
+        let mut expected = plain.clone();
+        for (i, sector) in expected.chunks_exact_mut(520).enumerate() {
+            xts.encrypt_sector((u128::MAX - 5).wrapping_add(i as u128), sector)
+                .unwrap();
+        }
+        for threads in [1, 3, 8] {
+            let mut data = plain.clone();
+            xts.encrypt_sectors_parallel(u128::MAX - 5, 520, &mut data, threads)
+                .unwrap();
+            assert_eq!(data, expected);
+            xts.decrypt_sectors_parallel(u128::MAX - 5, 520, &mut data, threads)
+                .unwrap();
+            assert_eq!(data, plain);
+        }
+        let mut data = plain[..1000].to_vec();
+        assert_eq!(
+            xts.encrypt_sectors_parallel(0, 520, &mut data, 2),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(
+            xts.encrypt_sectors_parallel(0, 8, &mut data, 2),
+            Err(AesError::InvalidDataLength)
+        );
+        assert_eq!(data, plain[..1000]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Differential tests against the `openssl` command line tool, with random keys, IVs and plains
+//! for every mode and padding which OpenSSL also supports (all but PCBC).
+//!
+//! They only run with the `openssl-interop` feature, and need `openssl` in `PATH`:
+//! ```text
+//! cargo test --features openssl-interop --test openssl_interop
+//! ```
+//! `AES_FRAST_INTEROP_SEED` replays a failed run with the seed printed by it, and
+//! `AES_FRAST_INTEROP_ROUNDS` sets the number of random messages per cipher (8 by default).
+#![cfg(feature = "openssl-interop")]
+
+use aes_frast::key::KeySize;
+use aes_frast::openssl_enc::{self, KdfDigest};
+use aes_frast::padding_128bit::PaddingScheme;
+use aes_frast::recipe::{Mode, Recipe};
+use aes_frast::rng::Rng;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// xorshift64*, enough for test inputs.
+struct TestRng(u64);
+
+impl Rng for TestRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            *byte = (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8;
+        }
+    }
+}
+
+impl TestRng {
+    fn below(&mut self, n: usize) -> usize {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        (u64::from_le_bytes(bytes) % n as u64) as usize
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Run `openssl enc` on `input`.
+fn openssl(
+    cipher: &str,
+    key: &[u8],
+    iv: &[u8],
+    no_padding: bool,
+    encrypt: bool,
+    input: &[u8],
+) -> Vec<u8> {
+    let mut command = Command::new("openssl");
+    command.args(["enc", &format!("-{}", cipher), "-K", &hex(key)]);
+    if !cipher.ends_with("ecb") {
+        command.args(["-iv", &hex(iv)]);
+    }
+    if no_padding {
+        command.arg("-nopad");
+    }
+    command.arg(if encrypt { "-e" } else { "-d" });
+    run(command, cipher, input)
+}
+
+/// Run `command` with `input` as its standard input, and return its standard output.
+fn run(mut command: Command, name: &str, input: &[u8]) -> Vec<u8> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Cannot run `openssl`, is it in PATH?");
+    // Write in another thread, so a large output cannot block the child.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input).unwrap());
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(
+        output.status.success(),
+        "openssl {} failed: {}",
+        name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+fn seeded_rng() -> TestRng {
+    let seed = match std::env::var("AES_FRAST_INTEROP_SEED") {
+        Ok(seed) => seed.parse().expect("Invalid AES_FRAST_INTEROP_SEED."),
+        Err(_) => {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos() as u64
+                | 1
+        }
+    };
+    eprintln!("AES_FRAST_INTEROP_SEED={}", seed);
+    TestRng(seed)
+}
+
+fn rounds() -> usize {
+    match std::env::var("AES_FRAST_INTEROP_ROUNDS") {
+        Ok(rounds) => rounds.parse().expect("Invalid AES_FRAST_INTEROP_ROUNDS."),
+        Err(_) => 8,
+    }
+}
+
+#[test]
+fn matches_openssl() {
+    let rounds = rounds();
+    let mut rng = seeded_rng();
+
+    let modes = [
+        (Mode::Ecb, "ecb", true),
+        (Mode::Cbc, "cbc", true),
+        (Mode::Cfb, "cfb", false),
+        (Mode::Cfb8, "cfb8", false),
+        (Mode::Ofb, "ofb", false),
+        (Mode::Ctr, "ctr", false),
+    ];
+    for key_bits in [128, 192, 256] {
+        for (mode, mode_name, block_mode) in modes {
+            let paddings: &[PaddingScheme] = if block_mode {
+                &[
+                    PaddingScheme::Pkcs7,
+                    PaddingScheme::AnsiX923,
+                    PaddingScheme::NoPadding,
+                ]
+            } else {
+                &[PaddingScheme::NoPadding]
+            };
+            let cipher = format!("aes-{}-{}", key_bits, mode_name);
+            for &padding in paddings {
+                let recipe: Recipe = format!("AES-{}/{}/{}", key_bits, mode_name, padding)
+                    .parse()
+                    .unwrap();
+                assert_eq!(recipe.mode(), mode);
+                for _ in 0..rounds {
+                    let mut key = vec![0u8; key_bits / 8];
+                    let mut iv = [0u8; 16];
+                    rng.fill_bytes(&mut key);
+                    rng.fill_bytes(&mut iv);
+                    let mut length = rng.below(200);
+                    if block_mode && padding == PaddingScheme::NoPadding {
+                        length &= !0b1111;
+                    }
+                    let mut plain = vec![0u8; length];
+                    rng.fill_bytes(&mut plain);
+                    let context = format!(
+                        "{} {:?} key={} iv={} plain={}",
+                        cipher,
+                        padding,
+                        hex(&key),
+                        hex(&iv),
+                        hex(&plain)
+                    );
+
+                    let ours = recipe.encrypt(&key, &iv, &plain).unwrap();
+                    match padding {
+                        // OpenSSL has no ANSIX923: check the padding of our output by hand.
+                        PaddingScheme::AnsiX923 => {
+                            let padded = openssl(&cipher, &key, &iv, true, false, &ours);
+                            let r = 16 - (length & 0b1111);
+                            assert_eq!(padded[..length], plain[..], "{}", context);
+                            assert!(
+                                padded[length..(length + r - 1)].iter().all(|&b| b == 0),
+                                "{}",
+                                context
+                            );
+                            assert_eq!(padded[length + r - 1] as usize, r, "{}", context);
+                            let theirs = openssl(&cipher, &key, &iv, true, true, &padded);
+                            assert_eq!(
+                                recipe.decrypt(&key, &iv, &theirs).unwrap(),
+                                plain,
+                                "{}",
+                                context
+                            );
+                        }
+                        _ => {
+                            let no_padding = padding == PaddingScheme::NoPadding;
+                            let theirs = openssl(&cipher, &key, &iv, no_padding, true, &plain);
+                            assert_eq!(ours, theirs, "{}", context);
+                            let decrypted = openssl(&cipher, &key, &iv, no_padding, false, &ours);
+                            assert_eq!(decrypted, plain, "{}", context);
+                            assert_eq!(
+                                recipe.decrypt(&key, &iv, &theirs).unwrap(),
+                                plain,
+                                "{}",
+                                context
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn matches_openssl_enc_password() {
+    let rounds = rounds();
+    let mut rng = seeded_rng();
+    for (size, key_bits) in [
+        (KeySize::Aes128, 128),
+        (KeySize::Aes192, 192),
+        (KeySize::Aes256, 256),
+    ] {
+        for (digest, md) in [(KdfDigest::Md5, "md5"), (KdfDigest::Sha256, "sha256")] {
+            let cipher = format!("aes-{}-cbc", key_bits);
+            for _ in 0..rounds {
+                let mut password = [0u8; 12];
+                rng.fill_bytes(&mut password);
+                // Printable, to pass it on the command line.
+                let password = hex(&password);
+                let mut plain = vec![0u8; rng.below(200)];
+                rng.fill_bytes(&mut plain);
+                let context = format!(
+                    "{} -md {} pass={} plain={}",
+                    cipher,
+                    md,
+                    password,
+                    hex(&plain)
+                );
+                let pass = format!("pass:{}", password);
+
+                let ours = openssl_enc::encrypt_with_rng(
+                    &mut rng,
+                    password.as_bytes(),
+                    size,
+                    digest,
+                    &plain,
+                );
+                let mut command = Command::new("openssl");
+                command.args(["enc", &format!("-{}", cipher), "-md", md, "-pass", &pass]);
+                let theirs = run(command, &cipher, &plain);
+                assert_eq!(
+                    openssl_enc::decrypt(password.as_bytes(), size, digest, &theirs).unwrap(),
+                    plain,
+                    "{}",
+                    context
+                );
+
+                let mut command = Command::new("openssl");
+                command.args([
+                    "enc",
+                    "-d",
+                    &format!("-{}", cipher),
+                    "-md",
+                    md,
+                    "-pass",
+                    &pass,
+                ]);
+                assert_eq!(run(command, &cipher, &ours), plain, "{}", context);
+            }
+        }
+    }
+}